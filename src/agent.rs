@@ -0,0 +1,744 @@
+use crate::backend::Trace;
+use crate::TraceInfo;
+use anyhow::{anyhow, Context};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+use trippy::tracing::{
+    ChecksumStats, CompletionReason, IcmpPacketType, IcmpTimestamps, Port, Probe, ProbeStatus,
+    RecordedRoute, Round, Sequence, TcpHandshake, TimeToLive, TraceId, TracerProtocol, TracerRound,
+    TypeOfService,
+};
+
+/// The maximum time to wait for a connection to be established and authenticated.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The maximum number of rounds which may be queued for a client before it is disconnected.
+const MAX_QUEUED_ROUNDS: usize = 1024;
+
+/// The maximum length of the authentication message sent by a client.
+const MAX_AUTH_LEN: u64 = 4096;
+
+/// The maximum length of any other message, such as a round, received from an agent.
+const MAX_MESSAGE_LEN: u64 = 4 * 1024 * 1024;
+
+/// Run as an agent, streaming the rounds of all `traces` to the clients which connect to `server`.
+///
+/// Each client is served from its own thread and is disconnected if it cannot keep up with the rounds.
+pub fn run_agent(server: &AgentServer, traces: &[TraceInfo]) -> anyhow::Result<()> {
+    let hello = Message::Hello {
+        traces: traces.iter().map(WireTrace::from).collect(),
+    };
+    let hello = serde_json::to_string(&hello)?;
+    info!(addr = %server.addr, "serving agent");
+    for stream in server.listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                debug!(%err, "failed to accept agent connection");
+                continue;
+            }
+        };
+        let token = server.token.clone();
+        let hello = hello.clone();
+        let hub = server.hub.clone();
+        thread::Builder::new()
+            .name(String::from("agent-client"))
+            .spawn(move || {
+                let peer = stream.peer_addr().ok();
+                match handle_client(&stream, &token, &hello, &hub) {
+                    Ok(()) => info!(?peer, "agent client disconnected"),
+                    Err(err) => info!(?peer, %err, "agent client disconnected"),
+                }
+            })?;
+    }
+    Ok(())
+}
+
+/// Authenticate a client and then stream rounds to it until it disconnects or all traces have finished.
+fn handle_client(
+    stream: &TcpStream,
+    token: &str,
+    hello: &str,
+    hub: &AgentHub,
+) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut writer = stream;
+    let mut line = String::new();
+    read_line(&mut BufReader::new(stream), &mut line, MAX_AUTH_LEN)?;
+    match serde_json::from_str(&line) {
+        Ok(Message::Auth {
+            token: client_token,
+        }) if is_token_match(&client_token, token) => {}
+        _ => {
+            let error = Message::Error {
+                message: String::from("authentication failed"),
+            };
+            writeln!(writer, "{}", serde_json::to_string(&error)?)?;
+            return Err(anyhow!("authentication failed"));
+        }
+    }
+    info!(peer = ?stream.peer_addr().ok(), "agent client connected");
+    writeln!(writer, "{hello}")?;
+    writer.flush()?;
+    for round in hub.subscribe() {
+        writeln!(writer, "{round}")?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Compare tokens in a time which does not depend on the position of the first difference.
+fn is_token_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// The listener of an agent.
+///
+/// The listener is bound before tracing begins, so that privileged ports may be used.
+pub struct AgentServer {
+    addr: SocketAddr,
+    token: String,
+    listener: TcpListener,
+    hub: AgentHub,
+}
+
+impl AgentServer {
+    /// Bind the listener of an agent to `addr`.
+    ///
+    /// The connection to the agent is not encrypted, and so a warning is logged if `addr` is not a loopback address,
+    /// which must have been allowed with `agent-insecure`, such that it may be reached from other hosts without a
+    /// tunnel.
+    pub fn bind(addr: SocketAddr, token: String) -> anyhow::Result<Self> {
+        let listener =
+            TcpListener::bind(addr).with_context(|| format!("failed to bind agent: {addr}"))?;
+        if !addr.ip().is_loopback() {
+            warn!(%addr, "agent connections are not encrypted, use a loopback address and an SSH tunnel or a trusted network");
+        }
+        Ok(Self {
+            addr,
+            token,
+            listener,
+            hub: AgentHub::default(),
+        })
+    }
+
+    /// A publisher for the rounds of the trace with a given `index`.
+    pub fn publisher(&self, index: usize) -> RoundPublisher {
+        self.hub.publisher(index)
+    }
+}
+
+/// The clients which are subscribed to the rounds of all traces.
+#[derive(Debug, Clone, Default)]
+struct AgentHub {
+    state: Arc<Mutex<HubState>>,
+}
+
+/// The subscribers and the number of publishers of an `AgentHub`.
+///
+/// Once every publisher has been dropped the hub is closed, which drops all subscribers such that their clients are
+/// disconnected.
+#[derive(Debug, Default)]
+struct HubState {
+    subscribers: Vec<SyncSender<Arc<str>>>,
+    publishers: usize,
+    closed: bool,
+}
+
+impl AgentHub {
+    /// A publisher for the rounds of the trace with a given `index`.
+    fn publisher(&self, index: usize) -> RoundPublisher {
+        self.state.lock().publishers += 1;
+        RoundPublisher {
+            index,
+            hub: self.clone(),
+        }
+    }
+
+    /// Subscribe to the rounds of all traces.
+    ///
+    /// The subscription ends once the hub is closed, or immediately if it is already closed.
+    fn subscribe(&self) -> Receiver<Arc<str>> {
+        let (tx, rx) = mpsc::sync_channel(MAX_QUEUED_ROUNDS);
+        let mut state = self.state.lock();
+        if !state.closed {
+            state.subscribers.push(tx);
+        }
+        rx
+    }
+}
+
+/// Publishes the rounds of a trace to all clients of an agent.
+#[derive(Debug)]
+pub struct RoundPublisher {
+    index: usize,
+    hub: AgentHub,
+}
+
+impl Drop for RoundPublisher {
+    fn drop(&mut self) {
+        let mut state = self.hub.state.lock();
+        state.publishers -= 1;
+        if state.publishers == 0 {
+            state.closed = true;
+            state.subscribers.clear();
+        }
+    }
+}
+
+impl RoundPublisher {
+    /// Publish a round, dropping any client which has disconnected or has too many rounds queued.
+    pub fn publish(&self, round: &TracerRound<'_>) {
+        let mut state = self.hub.state.lock();
+        let subscribers = &mut state.subscribers;
+        if subscribers.is_empty() {
+            return;
+        }
+        let message = Message::Round {
+            trace: self.index,
+            round: WireRound::from(round),
+        };
+        match serde_json::to_string(&message) {
+            Ok(line) => {
+                let line: Arc<str> = Arc::from(line);
+                subscribers.retain(|tx| match tx.try_send(line.clone()) {
+                    Ok(()) => true,
+                    Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => false,
+                });
+            }
+            Err(err) => debug!(%err, "failed to serialize round"),
+        }
+    }
+}
+
+/// A connection to a remote agent.
+pub struct RemoteAgent {
+    addr: String,
+    reader: BufReader<TcpStream>,
+    traces: Vec<RemoteTrace>,
+}
+
+impl RemoteAgent {
+    /// Connect and authenticate to the remote agent at `addr`.
+    pub fn connect(addr: &str, token: &str) -> anyhow::Result<Self> {
+        let socket_addr = addr
+            .to_socket_addrs()
+            .with_context(|| format!("failed to resolve remote agent: {addr}"))?
+            .next()
+            .ok_or_else(|| anyhow!("failed to resolve remote agent: {addr}"))?;
+        let stream = TcpStream::connect_timeout(&socket_addr, CONNECT_TIMEOUT)
+            .with_context(|| format!("failed to connect to remote agent: {addr}"))?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        let auth = Message::Auth {
+            token: token.to_string(),
+        };
+        let mut writer = &stream;
+        writeln!(writer, "{}", serde_json::to_string(&auth)?)?;
+        writer.flush()?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        read_line(&mut reader, &mut line, MAX_MESSAGE_LEN)?;
+        let traces = match serde_json::from_str(&line) {
+            Ok(Message::Hello { traces }) => traces,
+            Ok(Message::Error { message }) => {
+                return Err(anyhow!("remote agent {addr} refused connection: {message}"))
+            }
+            _ => return Err(anyhow!("unexpected response from remote agent: {addr}")),
+        };
+        stream.set_read_timeout(None)?;
+        Ok(Self {
+            addr: addr.to_string(),
+            reader,
+            traces: traces.into_iter().map(RemoteTrace::from).collect(),
+        })
+    }
+
+    /// The traces of the remote agent.
+    pub fn traces(&self) -> &[RemoteTrace] {
+        &self.traces
+    }
+
+    /// Update `trace_data`, one per trace of the remote agent, from the rounds streamed by the agent in a background
+    /// thread.
+    pub fn start(self, trace_data: Vec<Arc<RwLock<Trace>>>) -> anyhow::Result<()> {
+        let Self { addr, reader, .. } = self;
+        thread::Builder::new()
+            .name(String::from("remote"))
+            .spawn(move || {
+                let err = match receive_rounds(reader, &trace_data) {
+                    Ok(()) => format!("remote agent {addr} disconnected"),
+                    Err(err) => format!("remote agent {addr} disconnected: {err}"),
+                };
+                for trace in &trace_data {
+                    trace.write().set_error(err.clone());
+                }
+            })?;
+        Ok(())
+    }
+}
+
+/// Update the trace data from each round received until the connection is closed.
+fn receive_rounds(
+    mut reader: BufReader<TcpStream>,
+    trace_data: &[Arc<RwLock<Trace>>],
+) -> anyhow::Result<()> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if read_line(&mut reader, &mut line, MAX_MESSAGE_LEN)? == 0 {
+            return Ok(());
+        }
+        let Message::Round { trace, round } = serde_json::from_str(&line)? else {
+            continue;
+        };
+        let trace = trace_data
+            .get(trace)
+            .ok_or_else(|| anyhow!("unknown remote trace: {trace}"))?;
+        let probes: Vec<_> = round.probes.iter().map(Probe::from).collect();
+        let late_probes: Vec<_> = round.late_probes.iter().map(Probe::from).collect();
+        trace.write().update_from_round(&TracerRound::new(
+            &probes,
+            &late_probes,
+            TimeToLive(round.largest_ttl),
+            CompletionReason::from(round.reason),
+            ChecksumStats::from(round.checksum_stats),
+        ));
+    }
+}
+
+/// Read a line of at most `max_len` bytes into `line`, returning the number of bytes read, which is zero at the end of
+/// the stream.
+///
+/// A longer line is an error, such that a peer cannot make the line grow without bound.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String, max_len: u64) -> anyhow::Result<usize> {
+    let len = Read::take(reader, max_len).read_line(line)?;
+    if len as u64 == max_len && !line.ends_with('\n') {
+        return Err(anyhow!("message exceeds {max_len} bytes"));
+    }
+    Ok(len)
+}
+
+/// A trace of a remote agent.
+#[derive(Debug, Clone)]
+pub struct RemoteTrace {
+    pub hostname: String,
+    pub target_addr: IpAddr,
+    pub source_addr: IpAddr,
+    pub protocol: TracerProtocol,
+    pub destination_only: bool,
+}
+
+impl From<WireTrace> for RemoteTrace {
+    fn from(value: WireTrace) -> Self {
+        Self {
+            hostname: value.hostname,
+            target_addr: value.target_addr,
+            source_addr: value.source_addr,
+            protocol: match value.protocol {
+                WireProtocol::Icmp => TracerProtocol::Icmp,
+                WireProtocol::Udp => TracerProtocol::Udp,
+                WireProtocol::Tcp => TracerProtocol::Tcp,
+            },
+            destination_only: value.destination_only,
+        }
+    }
+}
+
+/// A message of the agent protocol.
+///
+/// Each message is sent as a single line of JSON.  A client sends an `Auth` message, to which the agent responds with
+/// a `Hello` message, or an `Error` message if authentication failed, and then a `Round` message for each round of
+/// each trace.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Message {
+    Auth { token: String },
+    Hello { traces: Vec<WireTrace> },
+    Round { trace: usize, round: WireRound },
+    Error { message: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WireTrace {
+    hostname: String,
+    target_addr: IpAddr,
+    source_addr: IpAddr,
+    protocol: WireProtocol,
+    destination_only: bool,
+}
+
+impl From<&TraceInfo> for WireTrace {
+    fn from(value: &TraceInfo) -> Self {
+        Self {
+            hostname: value.target_hostname.clone(),
+            target_addr: value.target_addr,
+            source_addr: value.source_addr,
+            protocol: match value.protocol {
+                TracerProtocol::Icmp => WireProtocol::Icmp,
+                TracerProtocol::Udp => WireProtocol::Udp,
+                TracerProtocol::Tcp => WireProtocol::Tcp,
+            },
+            destination_only: value.destination_only,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum WireProtocol {
+    Icmp,
+    Udp,
+    Tcp,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WireRound {
+    probes: Vec<WireProbe>,
+    late_probes: Vec<WireProbe>,
+    largest_ttl: u8,
+    reason: WireCompletionReason,
+    checksum_stats: WireChecksumStats,
+}
+
+impl From<&TracerRound<'_>> for WireRound {
+    fn from(value: &TracerRound<'_>) -> Self {
+        Self {
+            probes: value.probes.iter().map(WireProbe::from).collect(),
+            late_probes: value.late_probes.iter().map(WireProbe::from).collect(),
+            largest_ttl: value.largest_ttl.0,
+            reason: WireCompletionReason::from(value.reason),
+            checksum_stats: WireChecksumStats::from(value.checksum_stats),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum WireCompletionReason {
+    TargetFound,
+    RoundTimeLimitExceeded,
+}
+
+impl From<CompletionReason> for WireCompletionReason {
+    fn from(value: CompletionReason) -> Self {
+        match value {
+            CompletionReason::TargetFound => Self::TargetFound,
+            CompletionReason::RoundTimeLimitExceeded => Self::RoundTimeLimitExceeded,
+        }
+    }
+}
+
+impl From<WireCompletionReason> for CompletionReason {
+    fn from(value: WireCompletionReason) -> Self {
+        match value {
+            WireCompletionReason::TargetFound => Self::TargetFound,
+            WireCompletionReason::RoundTimeLimitExceeded => Self::RoundTimeLimitExceeded,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+struct WireChecksumStats {
+    validated: usize,
+    invalid_icmp: usize,
+    invalid_quoted_icmp: usize,
+    invalid_quoted_udp: usize,
+}
+
+impl From<ChecksumStats> for WireChecksumStats {
+    fn from(value: ChecksumStats) -> Self {
+        Self {
+            validated: value.validated,
+            invalid_icmp: value.invalid_icmp,
+            invalid_quoted_icmp: value.invalid_quoted_icmp,
+            invalid_quoted_udp: value.invalid_quoted_udp,
+        }
+    }
+}
+
+impl From<WireChecksumStats> for ChecksumStats {
+    fn from(value: WireChecksumStats) -> Self {
+        Self {
+            validated: value.validated,
+            invalid_icmp: value.invalid_icmp,
+            invalid_quoted_icmp: value.invalid_quoted_icmp,
+            invalid_quoted_udp: value.invalid_quoted_udp,
+        }
+    }
+}
+
+/// A `Probe` as sent by an agent, with timestamps given in microseconds since the Unix epoch.
+#[derive(Debug, Serialize, Deserialize)]
+struct WireProbe {
+    sequence: u16,
+    identifier: u16,
+    src_port: u16,
+    dest_port: u16,
+    ttl: u8,
+    round: usize,
+    sent: Option<u64>,
+    status: WireProbeStatus,
+    host: Option<IpAddr>,
+    received: Option<u64>,
+    icmp_packet_type: Option<WireIcmpPacketType>,
+    received_tos: Option<u8>,
+    reply_ttl: Option<u8>,
+    icmp_code: Option<u8>,
+    tcp_handshake: Option<WireTcpHandshake>,
+    quote_corrupted: bool,
+    #[serde(default)]
+    recorded_route: Vec<Ipv4Addr>,
+    #[serde(default)]
+    icmp_timestamps: Option<WireIcmpTimestamps>,
+    #[serde(default)]
+    next_hop_mtu: Option<u16>,
+    duplicates: u16,
+    retry: u8,
+}
+
+impl From<&Probe> for WireProbe {
+    fn from(value: &Probe) -> Self {
+        Self {
+            sequence: value.sequence.0,
+            identifier: value.identifier.0,
+            src_port: value.src_port.0,
+            dest_port: value.dest_port.0,
+            ttl: value.ttl.0,
+            round: value.round.0,
+            sent: value.sent.map(to_micros),
+            status: match value.status {
+                ProbeStatus::NotSent => WireProbeStatus::NotSent,
+                ProbeStatus::Awaited => WireProbeStatus::Awaited,
+                ProbeStatus::Complete => WireProbeStatus::Complete,
+            },
+            host: value.host,
+            received: value.received.map(to_micros),
+            icmp_packet_type: value.icmp_packet_type.map(|packet_type| match packet_type {
+                IcmpPacketType::TimeExceeded => WireIcmpPacketType::TimeExceeded,
+                IcmpPacketType::EchoReply => WireIcmpPacketType::EchoReply,
+                IcmpPacketType::Unreachable => WireIcmpPacketType::Unreachable,
+                IcmpPacketType::NotApplicable => WireIcmpPacketType::NotApplicable,
+            }),
+            received_tos: value.received_tos.map(|tos| tos.0),
+            reply_ttl: value.reply_ttl.map(|ttl| ttl.0),
+            icmp_code: value.icmp_code,
+            tcp_handshake: value.tcp_handshake.map(|handshake| match handshake {
+                TcpHandshake::Established => WireTcpHandshake::Established,
+                TcpHandshake::Refused => WireTcpHandshake::Refused,
+            }),
+            quote_corrupted: value.quote_corrupted,
+            recorded_route: value.recorded_route.addrs().to_vec(),
+            icmp_timestamps: value.icmp_timestamps.map(|ts| WireIcmpTimestamps {
+                originate: ts.originate,
+                receive: ts.receive,
+                transmit: ts.transmit,
+            }),
+            next_hop_mtu: value.next_hop_mtu,
+            duplicates: value.duplicates,
+            retry: value.retry,
+        }
+    }
+}
+
+impl From<&WireProbe> for Probe {
+    fn from(value: &WireProbe) -> Self {
+        Self {
+            sequence: Sequence(value.sequence),
+            identifier: TraceId(value.identifier),
+            src_port: Port(value.src_port),
+            dest_port: Port(value.dest_port),
+            ttl: TimeToLive(value.ttl),
+            round: Round(value.round),
+            sent: value.sent.map(from_micros),
+            status: match value.status {
+                WireProbeStatus::NotSent => ProbeStatus::NotSent,
+                WireProbeStatus::Awaited => ProbeStatus::Awaited,
+                WireProbeStatus::Complete => ProbeStatus::Complete,
+            },
+            host: value.host,
+            received: value.received.map(from_micros),
+            icmp_packet_type: value.icmp_packet_type.map(|packet_type| match packet_type {
+                WireIcmpPacketType::TimeExceeded => IcmpPacketType::TimeExceeded,
+                WireIcmpPacketType::EchoReply => IcmpPacketType::EchoReply,
+                WireIcmpPacketType::Unreachable => IcmpPacketType::Unreachable,
+                WireIcmpPacketType::NotApplicable => IcmpPacketType::NotApplicable,
+            }),
+            received_tos: value.received_tos.map(TypeOfService),
+            reply_ttl: value.reply_ttl.map(TimeToLive),
+            icmp_code: value.icmp_code,
+            tcp_handshake: value.tcp_handshake.map(|handshake| match handshake {
+                WireTcpHandshake::Established => TcpHandshake::Established,
+                WireTcpHandshake::Refused => TcpHandshake::Refused,
+            }),
+            quote_corrupted: value.quote_corrupted,
+            recorded_route: RecordedRoute::new(value.recorded_route.iter().copied()),
+            icmp_timestamps: value.icmp_timestamps.map(|ts| IcmpTimestamps {
+                originate: ts.originate,
+                receive: ts.receive,
+                transmit: ts.transmit,
+            }),
+            next_hop_mtu: value.next_hop_mtu,
+            duplicates: value.duplicates,
+            retry: value.retry,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum WireProbeStatus {
+    NotSent,
+    Awaited,
+    Complete,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum WireIcmpPacketType {
+    TimeExceeded,
+    EchoReply,
+    Unreachable,
+    NotApplicable,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum WireTcpHandshake {
+    Established,
+    Refused,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+struct WireIcmpTimestamps {
+    originate: u32,
+    receive: u32,
+    transmit: u32,
+}
+
+fn to_micros(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_micros() as u64)
+}
+
+fn from_micros(micros: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_micros(micros)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn test_probe_round_trip() {
+        let sent = from_micros(1_700_000_000_123_456);
+        let probe = Probe {
+            host: Some(IpAddr::from([192, 0, 2, 1])),
+            received: Some(sent + Duration::from_micros(12_345)),
+            status: ProbeStatus::Complete,
+            icmp_packet_type: Some(IcmpPacketType::TimeExceeded),
+            received_tos: Some(TypeOfService(0x02)),
+            reply_ttl: Some(TimeToLive(62)),
+            icmp_code: Some(0),
+            duplicates: 2,
+            retry: 1,
+            ..Probe::new(
+                Sequence(33000),
+                TraceId(1234),
+                Port(5000),
+                Port(33434),
+                TimeToLive(3),
+                Round(7),
+                sent,
+            )
+        };
+        let line = serde_json::to_string(&WireProbe::from(&probe)).unwrap();
+        let wire: WireProbe = serde_json::from_str(&line).unwrap();
+        assert_eq!(probe, Probe::from(&wire));
+    }
+
+    #[test]
+    fn test_round_message() {
+        let probes = [Probe::default()];
+        let round = TracerRound::new(
+            &probes,
+            &probes,
+            TimeToLive(1),
+            CompletionReason::RoundTimeLimitExceeded,
+            ChecksumStats::default(),
+        );
+        let message = Message::Round {
+            trace: 2,
+            round: WireRound::from(&round),
+        };
+        let line = serde_json::to_string(&message).unwrap();
+        let Ok(Message::Round { trace, round }) = serde_json::from_str(&line) else {
+            panic!("expected a round message: {line}");
+        };
+        assert_eq!(2, trace);
+        assert_eq!(1, round.largest_ttl);
+        assert_eq!(1, round.probes.len());
+        assert_eq!(1, round.late_probes.len());
+        assert!(matches!(
+            round.reason,
+            WireCompletionReason::RoundTimeLimitExceeded
+        ));
+    }
+
+    #[test]
+    fn test_hub_closed_when_publishers_dropped() {
+        let probes = [Probe::default()];
+        let round = TracerRound::new(
+            &probes,
+            &probes,
+            TimeToLive(1),
+            CompletionReason::TargetFound,
+            ChecksumStats::default(),
+        );
+        let hub = AgentHub::default();
+        let publisher0 = hub.publisher(0);
+        let publisher1 = hub.publisher(1);
+        let rounds = hub.subscribe();
+        publisher0.publish(&round);
+        assert!(rounds.try_recv().is_ok());
+        drop(publisher0);
+        assert!(matches!(rounds.try_recv(), Err(mpsc::TryRecvError::Empty)));
+        publisher1.publish(&round);
+        drop(publisher1);
+        assert!(rounds.recv().is_ok());
+        assert!(rounds.recv().is_err());
+        assert!(hub.subscribe().recv().is_err());
+    }
+
+    #[test_case("secret", "secret", true; "matching")]
+    #[test_case("secreT", "secret", false; "different")]
+    #[test_case("secret1", "secret", false; "longer")]
+    #[test_case("", "secret", false; "empty")]
+    fn test_is_token_match(given: &str, expected: &str, matched: bool) {
+        assert_eq!(matched, is_token_match(given, expected));
+    }
+
+    #[test_case("abc\ndef\n", 8, Some("abc\n"); "within limit")]
+    #[test_case("abc\n", 4, Some("abc\n"); "at limit")]
+    #[test_case("abc", 8, Some("abc"); "without newline")]
+    #[test_case("", 8, Some(""); "end of stream")]
+    #[test_case("abcdef\n", 4, None; "exceeds limit")]
+    fn test_read_line(input: &str, max_len: u64, expected: Option<&str>) {
+        let mut reader = input.as_bytes();
+        let mut line = String::new();
+        let result = read_line(&mut reader, &mut line, max_len);
+        assert_eq!(expected, result.ok().map(|_| line.as_str()));
+    }
+}
@@ -0,0 +1,269 @@
+use anyhow::{anyhow, Context};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use std::str::FromStr;
+
+/// User supplied labels for IP addresses and prefixes.
+///
+/// Annotations are read from a file in which each line maps an IP address or prefix to a label, such as
+/// `10.1.2.0/24 = office-core-sw`.  Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Default)]
+pub struct Annotations {
+    entries: Vec<(Prefix, String)>,
+}
+
+impl Annotations {
+    /// Create new `Annotations` from an annotations file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let contents =
+            fs::read_to_string(path.as_ref()).context(format!("{}", path.as_ref().display()))?;
+        Self::from_str(&contents).context(format!("{}", path.as_ref().display()))
+    }
+
+    /// Create `Annotations` that returns `None` for all `IpAddr` lookups.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Lookup the label of an `IpAddr`.
+    ///
+    /// If the `IpAddr` is contained within several annotated prefixes then the label of the most specific is returned.
+    pub fn lookup(&self, addr: IpAddr) -> Option<&str> {
+        self.entries
+            .iter()
+            .filter(|(prefix, _)| prefix.contains(addr))
+            .max_by_key(|(prefix, _)| prefix.len)
+            .map(|(_, label)| label.as_str())
+    }
+}
+
+impl FromStr for Annotations {
+    type Err = anyhow::Error;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let entries = contents
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(num, line)| {
+                let (prefix, label) = line
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("line {}: expected `prefix = label`", num))?;
+                let prefix = Prefix::from_str(prefix.trim())
+                    .map_err(|err| anyhow!("line {}: {}", num, err))?;
+                let label = label.trim();
+                if label.is_empty() {
+                    return Err(anyhow!("line {}: empty label", num));
+                }
+                Ok((prefix, label.to_string()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self { entries })
+    }
+}
+
+/// A special purpose IP address range which is not routable on the public internet.
+///
+/// Addresses in these ranges typically have no public DNS or AS information.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SpecialPurpose {
+    /// A private address (RFC 1918 or an IPv6 unique local address).
+    Private,
+    /// A carrier grade NAT shared address (RFC 6598).
+    Cgnat,
+    /// A link local address.
+    LinkLocal,
+    /// A loopback address.
+    Loopback,
+    /// Any other address which should not appear on the public internet.
+    Bogon,
+}
+
+impl SpecialPurpose {
+    /// The special purpose range of an `IpAddr`, if any.
+    pub fn lookup(addr: IpAddr) -> Option<Self> {
+        match addr {
+            IpAddr::V4(addr) => Self::lookup_ipv4(addr),
+            IpAddr::V6(addr) => Self::lookup_ipv6(addr),
+        }
+    }
+
+    fn lookup_ipv4(addr: Ipv4Addr) -> Option<Self> {
+        let [a, b, c, _] = addr.octets();
+        if addr.is_private() {
+            Some(Self::Private)
+        } else if a == 100 && b & 0xc0 == 64 {
+            Some(Self::Cgnat)
+        } else if addr.is_link_local() {
+            Some(Self::LinkLocal)
+        } else if addr.is_loopback() {
+            Some(Self::Loopback)
+        } else if a == 0
+            || a >= 224
+            || addr.is_documentation()
+            || (a, b, c) == (192, 0, 0)
+            || (a == 198 && b & 0xfe == 18)
+        {
+            Some(Self::Bogon)
+        } else {
+            None
+        }
+    }
+
+    fn lookup_ipv6(addr: Ipv6Addr) -> Option<Self> {
+        if let Some(addr) = addr.to_ipv4_mapped() {
+            return Self::lookup_ipv4(addr);
+        }
+        let segments = addr.segments();
+        if segments[0] & 0xfe00 == 0xfc00 {
+            Some(Self::Private)
+        } else if segments[0] & 0xffc0 == 0xfe80 {
+            Some(Self::LinkLocal)
+        } else if addr.is_loopback() {
+            Some(Self::Loopback)
+        } else if addr.is_unspecified()
+            || addr.is_multicast()
+            || segments[..2] == [0x2001, 0x0db8]
+            || segments[..4] == [0x0100, 0, 0, 0]
+        {
+            Some(Self::Bogon)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for SpecialPurpose {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Private => write!(f, "private"),
+            Self::Cgnat => write!(f, "cgnat"),
+            Self::LinkLocal => write!(f, "link-local"),
+            Self::Loopback => write!(f, "loopback"),
+            Self::Bogon => write!(f, "bogon"),
+        }
+    }
+}
+
+/// An IP address prefix, such as `10.1.2.0/24`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Prefix {
+    addr: IpAddr,
+    len: u8,
+}
+
+impl Prefix {
+    /// Is an `IpAddr` contained within this prefix?
+    fn contains(self, addr: IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.len)).unwrap_or(0);
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Prefix {
+    type Err = anyhow::Error;
+
+    /// Parse a prefix in CIDR notation, or a single IP address.
+    fn from_str(prefix: &str) -> Result<Self, Self::Err> {
+        let (addr, len) = match prefix.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (prefix, None),
+        };
+        let addr = IpAddr::from_str(addr.trim())
+            .map_err(|_| anyhow!("invalid IP address: {}", addr.trim()))?;
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+        let len = match len {
+            Some(len) => u8::from_str(len.trim())
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| anyhow!("invalid prefix length: {}", len.trim()))?,
+            None => max_len,
+        };
+        Ok(Self { addr, len })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const ANNOTATIONS: &str = r"
+# office network
+10.1.0.0/16 = office
+10.1.2.0/24 = office-core-sw
+10.1.2.1 = office-gw
+2001:db8::/32 = documentation
+";
+
+    #[test_case("10.1.2.1", Some("office-gw"); "exact address")]
+    #[test_case("10.1.2.2", Some("office-core-sw"); "most specific prefix")]
+    #[test_case("10.1.3.1", Some("office"); "least specific prefix")]
+    #[test_case("10.2.0.1", None; "no matching prefix")]
+    #[test_case("2001:db8::1", Some("documentation"); "ipv6 prefix")]
+    #[test_case("2001:db9::1", None; "no matching ipv6 prefix")]
+    fn test_lookup(addr: &str, expected: Option<&str>) {
+        let annotations = Annotations::from_str(ANNOTATIONS).unwrap();
+        assert_eq!(
+            expected,
+            annotations.lookup(IpAddr::from_str(addr).unwrap())
+        );
+    }
+
+    #[test_case("10.1.2.0/24 office"; "missing separator")]
+    #[test_case("10.1.2/24 = office"; "invalid address")]
+    #[test_case("10.1.2.0/33 = office"; "invalid prefix length")]
+    #[test_case("10.1.2.0/24 = "; "empty label")]
+    fn test_invalid(annotations: &str) {
+        assert!(Annotations::from_str(annotations).is_err());
+    }
+
+    #[test_case("10.1.2.3", Some(SpecialPurpose::Private); "rfc1918 10/8")]
+    #[test_case("172.31.0.1", Some(SpecialPurpose::Private); "rfc1918 172.16/12")]
+    #[test_case("192.168.1.1", Some(SpecialPurpose::Private); "rfc1918 192.168/16")]
+    #[test_case("100.64.0.1", Some(SpecialPurpose::Cgnat); "cgnat lower")]
+    #[test_case("100.127.255.254", Some(SpecialPurpose::Cgnat); "cgnat upper")]
+    #[test_case("100.128.0.1", None; "above cgnat")]
+    #[test_case("169.254.1.1", Some(SpecialPurpose::LinkLocal); "ipv4 link local")]
+    #[test_case("127.0.0.1", Some(SpecialPurpose::Loopback); "ipv4 loopback")]
+    #[test_case("0.1.2.3", Some(SpecialPurpose::Bogon); "this network")]
+    #[test_case("192.0.2.1", Some(SpecialPurpose::Bogon); "ipv4 documentation")]
+    #[test_case("198.19.0.1", Some(SpecialPurpose::Bogon); "benchmarking")]
+    #[test_case("240.0.0.1", Some(SpecialPurpose::Bogon); "reserved")]
+    #[test_case("8.8.8.8", None; "ipv4 public")]
+    #[test_case("fd00::1", Some(SpecialPurpose::Private); "unique local")]
+    #[test_case("fe80::1", Some(SpecialPurpose::LinkLocal); "ipv6 link local")]
+    #[test_case("::1", Some(SpecialPurpose::Loopback); "ipv6 loopback")]
+    #[test_case("2001:db8::1", Some(SpecialPurpose::Bogon); "ipv6 documentation")]
+    #[test_case("::ffff:10.0.0.1", Some(SpecialPurpose::Private); "ipv4 mapped")]
+    #[test_case("2606:4700::1111", None; "ipv6 public")]
+    fn test_special_purpose(addr: &str, expected: Option<SpecialPurpose>) {
+        assert_eq!(
+            expected,
+            SpecialPurpose::lookup(IpAddr::from_str(addr).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_default_route() {
+        let annotations = Annotations::from_str("0.0.0.0/0 = anywhere").unwrap();
+        assert_eq!(
+            Some("anywhere"),
+            annotations.lookup(IpAddr::from_str("192.0.2.1").unwrap())
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use crate::dns::{DnsHosts, DnsResolverConfig};
+use crate::report::make_report;
+use crate::server;
+use crate::{DnsResolver, TraceInfo};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender, SyncSender};
+use std::thread;
+use std::time::Duration;
+use tracing::info;
+use trippy::tracing::TracerProtocol;
+
+/// The maximum time a client connection may be idle before it is closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The number of threads serving client connections.
+const WORKERS: usize = 4;
+
+/// The maximum number of client connections waiting for a free worker, beyond which connections are closed.
+const MAX_QUEUED: usize = 4;
+
+/// Start the tracer of a trace added via the API, with the given trace id, and return its information.
+pub type TraceStarter =
+    Box<dyn FnMut(&NewTrace, usize, &DnsResolver) -> anyhow::Result<TraceInfo> + Send>;
+
+/// Start the control and query API server on `addr` in a background thread.
+///
+/// The server accepts JSON-RPC 2.0 requests, one per line, and writes one response per line.  Connections are served
+/// on a small pool of threads, such that an idle client does not delay any other client, and the requests of all
+/// connections are performed in turn by a single dispatch thread.
+///
+/// The traces are those started when Trippy starts, which are given the ids `0` to `n - 1`, and those added via the
+/// API, which are started with the `starter`.  Each trace keeps its id when other traces are removed.
+///
+/// The API is not authenticated and so `addr` must be a loopback address, which is enforced when the configuration is
+/// validated.
+///
+/// The dispatch thread performs reverse DNS lookups with its own `DnsResolver`, which is started from `dns_config` and
+/// `dns_hosts`.
+pub fn start_api(
+    addr: SocketAddr,
+    traces: Vec<TraceInfo>,
+    starter: TraceStarter,
+    dns_config: DnsResolverConfig,
+    dns_hosts: DnsHosts,
+) -> anyhow::Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("failed to bind api server: {addr}"))?;
+    info!(%addr, "serving api");
+    let (requests_tx, requests_rx) = mpsc::channel::<ApiRequest>();
+    thread::Builder::new()
+        .name(String::from("api-dispatch"))
+        .spawn(move || -> anyhow::Result<()> {
+            let resolver = DnsResolver::start(dns_config, dns_hosts)?;
+            let mut traces = ApiTraces::new(traces, starter);
+            for (line, response_tx) in requests_rx {
+                // The client may have disconnected while the request was queued.
+                drop(response_tx.send(handle_request(&line, &mut traces, &resolver)));
+            }
+            Ok(())
+        })?;
+    server::serve(listener, "api", WORKERS, MAX_QUEUED, move |stream| {
+        handle_connection(stream, &requests_tx)
+    })
+}
+
+/// A request line and the sender on which its response is returned to the connection.
+type ApiRequest = (String, SyncSender<Response>);
+
+/// Handle each request of a client connection until it is closed or idle.
+fn handle_connection(stream: &TcpStream, requests: &Sender<ApiRequest>) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    let mut writer = stream;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response_tx, response_rx) = mpsc::sync_channel(1);
+        requests.send((line, response_tx))?;
+        let response = response_rx.recv()?;
+        writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// The traces of the API, each with its id, and the `TraceStarter` for those added.
+struct ApiTraces {
+    traces: Vec<(usize, TraceInfo)>,
+    next_id: usize,
+    starter: TraceStarter,
+}
+
+impl ApiTraces {
+    fn new(traces: Vec<TraceInfo>, starter: TraceStarter) -> Self {
+        let next_id = traces.len();
+        Self {
+            traces: traces.into_iter().enumerate().collect(),
+            next_id,
+            starter,
+        }
+    }
+
+    /// Find the trace with the `id`.
+    fn find(&self, id: usize) -> Result<&TraceInfo, RpcError> {
+        self.traces
+            .iter()
+            .find(|(trace_id, _)| *trace_id == id)
+            .map(|(_, info)| info)
+            .ok_or_else(|| RpcError::invalid_params(&format!("no trace with id {id}")))
+    }
+
+    /// Start a new trace and return its id.
+    fn add(&mut self, new_trace: &NewTrace, resolver: &DnsResolver) -> Result<usize, RpcError> {
+        let id = self.next_id;
+        let info = (self.starter)(new_trace, id, resolver)
+            .map_err(|err| RpcError::trace_failed(&format!("{err:#}")))?;
+        self.next_id += 1;
+        self.traces.push((id, info));
+        Ok(id)
+    }
+
+    /// Stop and remove the trace with the `id`.
+    fn remove(&mut self, id: usize) -> Result<(), RpcError> {
+        self.find(id)?.data.read().stop();
+        self.traces.retain(|(trace_id, _)| *trace_id != id);
+        Ok(())
+    }
+}
+
+/// The target, and optionally the protocol, of a trace to be added.
+#[derive(Debug, Eq, PartialEq)]
+pub struct NewTrace {
+    /// The hostname or address of the target.
+    pub target: String,
+    /// The protocol to trace with, or that of the first trace if `None`.
+    pub protocol: Option<TracerProtocol>,
+}
+
+/// Handle a single JSON-RPC request.
+fn handle_request(line: &str, traces: &mut ApiTraces, resolver: &DnsResolver) -> Response {
+    match parse_request(line) {
+        Ok(request) => {
+            let id = request.id.clone();
+            match dispatch(&request, traces, resolver) {
+                Ok(result) => Response::result(id, result),
+                Err(err) => Response::error(id, err),
+            }
+        }
+        Err(err) => Response::error(Value::Null, err),
+    }
+}
+
+/// Perform the method of a request.
+fn dispatch(
+    request: &Request,
+    traces: &mut ApiTraces,
+    resolver: &DnsResolver,
+) -> Result<Value, RpcError> {
+    match request.method.as_str() {
+        "list_traces" => Ok(Value::Array(
+            traces
+                .traces
+                .iter()
+                .map(|(id, info)| {
+                    let trace = info.data.read();
+                    json!({
+                        "id": id,
+                        "target": info.target_hostname,
+                        "addr": info.target_addr.to_string(),
+                        "protocol": format!("{:?}", info.protocol).to_lowercase(),
+                        "round": trace.round().map(|round| round + 1),
+                        "paused": trace.is_paused(),
+                    })
+                })
+                .collect(),
+        )),
+        "get_trace" => {
+            let info = traces.find(trace_id(&request.params)?)?;
+            // The trace is copied such that the tracer is not blocked while the report is made.
+            let trace = info.data.read().clone();
+            let report = make_report(info, &trace, resolver, None);
+            serde_json::to_value(report).map_err(|err| RpcError::internal(&err.to_string()))
+        }
+        "pause_trace" => {
+            traces
+                .find(trace_id(&request.params)?)?
+                .data
+                .write()
+                .set_paused(true);
+            Ok(Value::Bool(true))
+        }
+        "resume_trace" => {
+            traces
+                .find(trace_id(&request.params)?)?
+                .data
+                .write()
+                .set_paused(false);
+            Ok(Value::Bool(true))
+        }
+        "clear_trace" => {
+            traces
+                .find(trace_id(&request.params)?)?
+                .data
+                .write()
+                .clear();
+            Ok(Value::Bool(true))
+        }
+        "add_trace" => {
+            let id = traces.add(&new_trace(&request.params)?, resolver)?;
+            Ok(json!({ "id": id }))
+        }
+        "remove_trace" => {
+            traces.remove(trace_id(&request.params)?)?;
+            Ok(Value::Bool(true))
+        }
+        method => Err(RpcError::method_not_found(method)),
+    }
+}
+
+/// The `id` parameter of a request, given either by name or by position.
+fn trace_id(params: &Value) -> Result<usize, RpcError> {
+    let id = match params {
+        Value::Object(params) => params.get("id"),
+        Value::Array(params) => params.first(),
+        _ => None,
+    };
+    id.and_then(Value::as_u64)
+        .and_then(|id| usize::try_from(id).ok())
+        .ok_or_else(|| RpcError::invalid_params("expected a trace id"))
+}
+
+/// The `target` and optional `protocol` parameters of an `add_trace` request, given either by name or by position.
+fn new_trace(params: &Value) -> Result<NewTrace, RpcError> {
+    let (target, protocol) = match params {
+        Value::Object(params) => (params.get("target"), params.get("protocol")),
+        Value::Array(params) => (params.first(), params.get(1)),
+        _ => (None, None),
+    };
+    let target = target
+        .and_then(Value::as_str)
+        .filter(|target| !target.is_empty())
+        .ok_or_else(|| RpcError::invalid_params("expected a target"))?;
+    let protocol = protocol
+        .filter(|protocol| !protocol.is_null())
+        .map(|protocol| match protocol.as_str() {
+            Some("icmp") => Ok(TracerProtocol::Icmp),
+            Some("udp") => Ok(TracerProtocol::Udp),
+            Some("tcp") => Ok(TracerProtocol::Tcp),
+            _ => Err(RpcError::invalid_params(
+                "protocol must be icmp, udp or tcp",
+            )),
+        })
+        .transpose()?;
+    Ok(NewTrace {
+        target: target.to_string(),
+        protocol,
+    })
+}
+
+/// Parse a JSON-RPC request.
+fn parse_request(line: &str) -> Result<Request, RpcError> {
+    let value: Value = serde_json::from_str(line).map_err(|_| RpcError::parse_error())?;
+    let request: Request =
+        serde_json::from_value(value).map_err(|err| RpcError::invalid_request(&err.to_string()))?;
+    if request.jsonrpc == "2.0" {
+        Ok(request)
+    } else {
+        Err(RpcError::invalid_request("jsonrpc must be 2.0"))
+    }
+}
+
+/// A JSON-RPC request.
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A JSON-RPC response.
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl Response {
+    fn result(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn error(id: Value, error: RpcError) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// A JSON-RPC error.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+struct RpcError {
+    code: i32,
+    message: String,
+}
+
+impl RpcError {
+    fn parse_error() -> Self {
+        Self {
+            code: -32700,
+            message: String::from("parse error"),
+        }
+    }
+
+    fn invalid_request(reason: &str) -> Self {
+        Self {
+            code: -32600,
+            message: format!("invalid request: {reason}"),
+        }
+    }
+
+    fn method_not_found(method: &str) -> Self {
+        Self {
+            code: -32601,
+            message: format!("method not found: {method}"),
+        }
+    }
+
+    fn trace_failed(reason: &str) -> Self {
+        Self {
+            code: -32000,
+            message: format!("failed to start trace: {reason}"),
+        }
+    }
+
+    fn invalid_params(reason: &str) -> Self {
+        Self {
+            code: -32602,
+            message: format!("invalid params: {reason}"),
+        }
+    }
+
+    fn internal(reason: &str) -> Self {
+        Self {
+            code: -32603,
+            message: format!("internal error: {reason}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn test_parse_request() {
+        let request = parse_request(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "get_trace", "params": {"id": 0}}"#,
+        )
+        .unwrap();
+        assert_eq!(request.id, json!(1));
+        assert_eq!(request.method, "get_trace");
+        assert_eq!(trace_id(&request.params), Ok(0));
+    }
+
+    #[test_case("not json", -32700; "parse error")]
+    #[test_case(r#"{"jsonrpc": "2.0", "id": 1}"#, -32600; "missing method")]
+    #[test_case(r#"{"jsonrpc": "1.0", "id": 1, "method": "list_traces"}"#, -32600; "wrong version")]
+    fn test_parse_request_invalid(line: &str, code: i32) {
+        assert_eq!(parse_request(line).unwrap_err().code, code);
+    }
+
+    #[test_case(r#"{"id": 2}"#, Ok(2); "named")]
+    #[test_case("[3]", Ok(3); "positional")]
+    #[test_case(r#"{"id": -1}"#, Err(-32602); "negative")]
+    #[test_case(r#"{"index": 2}"#, Err(-32602); "index")]
+    #[test_case("{}", Err(-32602); "missing")]
+    #[test_case("null", Err(-32602); "no params")]
+    fn test_trace_id(params: &str, expected: Result<usize, i32>) {
+        let params: Value = serde_json::from_str(params).unwrap();
+        assert_eq!(trace_id(&params).map_err(|err| err.code), expected);
+    }
+
+    #[test_case(r#"{"target": "example.com"}"#, Ok(("example.com", None)); "named")]
+    #[test_case(r#"{"target": "example.com", "protocol": "udp"}"#, Ok(("example.com", Some(TracerProtocol::Udp))); "named with protocol")]
+    #[test_case(r#"["example.com", "tcp"]"#, Ok(("example.com", Some(TracerProtocol::Tcp))); "positional")]
+    #[test_case(r#"{"target": "example.com", "protocol": null}"#, Ok(("example.com", None)); "null protocol")]
+    #[test_case(r#"{"target": "example.com", "protocol": "sctp"}"#, Err(-32602); "unknown protocol")]
+    #[test_case(r#"{"target": ""}"#, Err(-32602); "empty target")]
+    #[test_case("{}", Err(-32602); "missing target")]
+    fn test_new_trace(params: &str, expected: Result<(&str, Option<TracerProtocol>), i32>) {
+        let params: Value = serde_json::from_str(params).unwrap();
+        let expected = expected.map(|(target, protocol)| NewTrace {
+            target: target.to_string(),
+            protocol,
+        });
+        assert_eq!(new_trace(&params).map_err(|err| err.code), expected);
+    }
+}
@@ -1,36 +1,113 @@
+use crate::agent::RoundPublisher;
 use crate::caps::drop_caps;
-use crate::config::MAX_HOPS;
+use crate::config::{Dscp, EcnCodepoint, ReResolveStrategy, MAX_HOPS};
+use crate::dns::{DnsEntry, DnsHosts, DnsResolver, DnsResolverConfig};
+use crate::hooks::Hooks;
 use indexmap::IndexMap;
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fmt::{Display, Formatter};
+use std::iter;
 use std::net::{IpAddr, Ipv4Addr};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::SyncSender;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{error, info, warn};
 use trippy::tracing::{
-    Probe, ProbeStatus, Tracer, TracerChannel, TracerChannelConfig, TracerConfig, TracerRound,
+    ChecksumStats, CompletionReason, IcmpPacketType, IcmpTimestamps, MaxRounds, Probe, ProbeStatus,
+    ProhibitedStrategy, Round, TcpHandshake, Tracer, TracerChannel, TracerChannelConfig,
+    TracerConfig, TracerRound,
 };
 
+/// The maximum number of route changes retained for a trace.
+const MAX_ROUTE_CHANGES: usize = 256;
+
+/// The maximum number of AS path changes retained for a trace.
+const MAX_AS_PATH_CHANGES: usize = 256;
+
+/// The maximum number of target address changes retained for a trace.
+const MAX_TARGET_CHANGES: usize = 256;
+
+/// The interval at which a paused trace checks whether it has been resumed.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// The state of all hops in a trace.
 #[derive(Debug, Clone)]
 pub struct Trace {
     max_samples: usize,
+    destination_only: bool,
+    backfill_late: bool,
+    record_probes: bool,
     lowest_ttl: u8,
     highest_ttl: u8,
     highest_ttl_for_round: u8,
     round: Option<usize>,
     hops: Vec<Hop>,
     error: Option<String>,
+    generation: usize,
+    unreachable_rounds: usize,
+    route_changes: Vec<RouteChange>,
+    total_route_changes: usize,
+    as_path: Vec<String>,
+    as_path_changes: Vec<AsPathChange>,
+    total_as_path_changes: usize,
+    target_addr: Option<IpAddr>,
+    target_changes: Vec<TargetChange>,
+    total_target_changes: usize,
+    checksum_stats: ChecksumStats,
+    paused: bool,
+    stop: Arc<AtomicBool>,
+    total_rounds: usize,
+    round_duration: Option<Duration>,
+    round_start: Option<SystemTime>,
+    round_probes: usize,
+    probe_rate: Option<f64>,
+    backoff: Option<Duration>,
+    reset_time: SystemTime,
 }
 
 impl Trace {
-    pub fn new(max_samples: usize) -> Self {
+    /// Create a `Trace`, which records the result of every probe of each hop if `record_probes` is set.
+    pub fn new(
+        max_samples: usize,
+        destination_only: bool,
+        backfill_late: bool,
+        record_probes: bool,
+    ) -> Self {
         Self {
             max_samples,
+            destination_only,
+            backfill_late,
+            record_probes,
             lowest_ttl: 0,
             highest_ttl: 0,
             highest_ttl_for_round: 0,
             round: None,
             hops: (0..MAX_HOPS).map(|_| Hop::default()).collect(),
             error: None,
+            generation: 0,
+            unreachable_rounds: 0,
+            route_changes: Vec::new(),
+            total_route_changes: 0,
+            as_path: Vec::new(),
+            as_path_changes: Vec::new(),
+            total_as_path_changes: 0,
+            target_addr: None,
+            target_changes: Vec::new(),
+            total_target_changes: 0,
+            checksum_stats: ChecksumStats::default(),
+            paused: false,
+            stop: Arc::new(AtomicBool::new(false)),
+            total_rounds: 0,
+            round_duration: None,
+            round_start: None,
+            round_probes: 0,
+            probe_rate: None,
+            backoff: None,
+            reset_time: SystemTime::now(),
         }
     }
 
@@ -39,6 +116,11 @@ impl Trace {
         self.round
     }
 
+    /// The time at which the trace was created or its statistics were last cleared.
+    pub fn reset_time(&self) -> SystemTime {
+        self.reset_time
+    }
+
     /// Information about each hop in the trace.
     pub fn hops(&self) -> &[Hop] {
         if self.lowest_ttl == 0 || self.highest_ttl == 0 {
@@ -65,6 +147,105 @@ impl Trace {
         hop.ttl <= self.highest_ttl_for_round
     }
 
+    /// Is the loss observed at a given `Hop` likely caused by ICMP rate limiting rather than forwarding loss?
+    ///
+    /// Routers frequently rate limit the ICMP responses they generate, which appears as loss at that hop only,
+    /// whereas loss which occurs when forwarding probes at a hop is also observed at all subsequent hops.  The loss
+    /// at a `Hop` is therefore attributed to rate limiting if it is not the target, some but not all probes were
+    /// answered and a subsequent `Hop` which responded has a lower loss.
+    pub fn is_rate_limited(&self, hop: &Hop) -> bool {
+        if self.is_target(hop) || hop.total_recv() == 0 || hop.total_recv() == hop.total_sent() {
+            return false;
+        }
+        let loss_pct = hop.loss_pct();
+        self.hops()
+            .iter()
+            .filter(|later| later.ttl > hop.ttl && later.total_recv() > 0)
+            .any(|later| later.loss_pct() < loss_pct)
+    }
+
+    /// The status of a given `Hop`.
+    pub fn hop_status(&self, hop: &Hop) -> HopStatus {
+        let lost = hop.total_sent() - hop.total_recv();
+        match (lost, self.is_target(hop)) {
+            _ if self.is_rate_limited(hop) => HopStatus::RateLimited,
+            (lost, true) if lost == hop.total_sent() => HopStatus::TargetDown,
+            (lost, true) if lost > 0 => HopStatus::TargetLoss,
+            (lost, false) if lost == hop.total_sent() => HopStatus::NoResponse,
+            (lost, false) if lost > 0 => HopStatus::Loss,
+            _ => HopStatus::Ok,
+        }
+    }
+
+    /// How far the probes of this trace reached towards the target at `target_addr`.
+    ///
+    /// If a hop at `target_addr` responded then the target is `Open` or `Closed` if a TCP handshake with it was
+    /// established or refused, and is otherwise `Reached`.  If not, the probes were `Rejected` by the first hop which
+    /// responded that the target is unreachable or, failing that, were `Filtered` beyond the last hop which responded.
+    pub fn reachability(&self, target_addr: IpAddr) -> Reachability {
+        let responded = || self.hops().iter().filter(|hop| hop.total_recv() > 0);
+        if let Some(target) = responded().find(|hop| hop.addrs().any(|addr| *addr == target_addr)) {
+            if target.tcp_established() > 0 {
+                Reachability::Open(target.ttl())
+            } else if target.tcp_refused() > 0 {
+                Reachability::Closed(target.ttl())
+            } else {
+                Reachability::Reached(target.ttl())
+            }
+        } else if let Some(hop) = responded().find(|hop| {
+            hop.icmp_response()
+                .is_some_and(IcmpResponse::is_unreachable)
+        }) {
+            Reachability::Rejected(hop.ttl())
+        } else {
+            Reachability::Filtered(responded().next_back().map(Hop::ttl))
+        }
+    }
+
+    /// The first hop which responded that communication with the target is administratively prohibited, if any.
+    ///
+    /// This is the hop which blocks the probes, regardless of whether probing continued beyond it.
+    pub fn blocking_hop(&self) -> Option<&Hop> {
+        self.hops().iter().find(|hop| {
+            hop.total_recv() > 0 && hop.icmp_response().is_some_and(IcmpResponse::is_prohibited)
+        })
+    }
+
+    /// The first hop which responded that a probe must be fragmented to be forwarded, which its `Don't Fragment` bit
+    /// prohibits, if any.
+    pub fn fragmentation_hop(&self) -> Option<&Hop> {
+        self.hops().iter().find(|hop| {
+            hop.total_recv() > 0
+                && hop
+                    .icmp_response()
+                    .is_some_and(IcmpResponse::is_fragmentation_needed)
+        })
+    }
+
+    /// Flag `addr`, which sent a `TimeExceeded` error for the hop at `index`, if it is not an in-path interface of the
+    /// hop.
+    ///
+    /// An address which also responded for a non-adjacent hop, such as the loopback address of a router or the target
+    /// itself, cannot be the interface on which the probe for both hops arrived.
+    fn update_off_path(&mut self, index: usize, addr: IpAddr) {
+        if self.hops[index].off_path.contains_key(&addr) {
+            return;
+        }
+        let off_path = if addr.is_loopback() {
+            Some(OffPath::Loopback)
+        } else {
+            self.hops
+                .iter()
+                .enumerate()
+                .find(|(other, hop)| other.abs_diff(index) > 1 && hop.addrs.contains_key(&addr))
+                .map(|(_, hop)| OffPath::OtherHop(hop.ttl))
+        };
+        if let Some(off_path) = off_path {
+            info!(ttl = index + 1, %addr, %off_path, "off-path response");
+            self.hops[index].off_path.insert(addr, off_path);
+        }
+    }
+
     /// Return the target `Hop`.
     ///
     /// TODO Do we guarantee there is always a target hop?
@@ -80,53 +261,448 @@ impl Trace {
         self.error.as_deref()
     }
 
+    /// The number of times this trace has been updated.
+    ///
+    /// Used by the frontend to determine if the trace has changed since it was last rendered.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// The number of consecutive rounds, up to and including the current round, in which the target did not respond.
+    pub fn unreachable_rounds(&self) -> usize {
+        self.unreachable_rounds
+    }
+
+    /// The route changes observed during the trace, oldest first.
+    ///
+    /// Only the most recent `MAX_ROUTE_CHANGES` route changes are retained.
+    pub fn route_changes(&self) -> &[RouteChange] {
+        &self.route_changes
+    }
+
+    /// The total number of route changes observed during the trace, including those no longer retained.
+    pub fn total_route_changes(&self) -> usize {
+        self.total_route_changes
+    }
+
+    /// The AS path of the trace as of the latest round, if AS information is being looked up.
+    pub fn as_path(&self) -> &[String] {
+        &self.as_path
+    }
+
+    /// The AS path changes observed during the trace, oldest first.
+    ///
+    /// Only the most recent `MAX_AS_PATH_CHANGES` AS path changes are retained.
+    pub fn as_path_changes(&self) -> &[AsPathChange] {
+        &self.as_path_changes
+    }
+
+    /// The total number of AS path changes observed during the trace, including those no longer retained.
+    pub fn total_as_path_changes(&self) -> usize {
+        self.total_as_path_changes
+    }
+
+    /// Update the AS path of the trace from the latest round, recording an `AsPathChange` if it differs.
+    ///
+    /// A change is not recorded when the AS path is only extended, such as when further hops are discovered, and an AS
+    /// path which is a prefix of the current AS path, such as after the trace is cleared, is ignored.
+    pub fn update_as_path(&mut self, as_path: Vec<String>) {
+        if self.as_path.starts_with(&as_path) {
+            return;
+        }
+        if !as_path.starts_with(&self.as_path) {
+            let change = AsPathChange {
+                timestamp: SystemTime::now(),
+                round: self.round.unwrap_or_default(),
+                from: std::mem::take(&mut self.as_path),
+                to: as_path.clone(),
+            };
+            info!(from = ?change.from, to = ?change.to, "as path changed");
+            if self.as_path_changes.len() == MAX_AS_PATH_CHANGES {
+                self.as_path_changes.remove(0);
+            }
+            self.as_path_changes.push(change);
+            self.total_as_path_changes += 1;
+        }
+        self.as_path = as_path;
+        self.generation += 1;
+    }
+
+    /// The address of the target being traced, if it has changed since tracing started.
+    pub fn target_addr(&self) -> Option<IpAddr> {
+        self.target_addr
+    }
+
+    /// The target address changes observed when re-resolving the hostname of the target, oldest first.
+    ///
+    /// Only the most recent `MAX_TARGET_CHANGES` target address changes are retained.
+    pub fn target_changes(&self) -> &[TargetChange] {
+        &self.target_changes
+    }
+
+    /// The total number of target address changes observed during the trace, including those no longer retained.
+    pub fn total_target_changes(&self) -> usize {
+        self.total_target_changes
+    }
+
+    /// Record that the hostname of the target was re-resolved to a different address.
+    ///
+    /// If the new address is `followed` then the statistics of all hops, which relate to the previous address, are
+    /// cleared.
+    pub fn update_target_addr(&mut self, from: IpAddr, to: IpAddr, followed: bool) {
+        let change = TargetChange {
+            timestamp: SystemTime::now(),
+            round: self.round.unwrap_or_default(),
+            from,
+            to,
+            followed,
+        };
+        if self.target_changes.len() == MAX_TARGET_CHANGES {
+            self.target_changes.remove(0);
+        }
+        self.target_changes.push(change);
+        self.total_target_changes += 1;
+        if followed {
+            self.target_addr = Some(to);
+            self.clear();
+        } else {
+            self.generation += 1;
+        }
+    }
+
+    /// The rate at which probes are sent, in probes per second, which is not known until the second round has started.
+    pub fn probe_rate(&self) -> Option<f64> {
+        self.probe_rate
+    }
+
+    /// A summary of the trace as a whole, rather than of each hop.
+    pub fn summary(&self) -> TraceSummary {
+        let hops = self.hops();
+        TraceSummary {
+            total_rounds: self.total_rounds,
+            total_sent: hops.iter().map(Hop::total_sent).sum(),
+            total_recv: hops.iter().map(Hop::total_recv).sum(),
+            loss_pct: hops.last().map_or(0_f64, Hop::loss_pct),
+            round_duration: self.round_duration,
+            path_len: self.highest_ttl_for_round,
+        }
+    }
+
+    /// The checksum validation statistics of the responses received for the trace.
+    pub fn checksum_stats(&self) -> ChecksumStats {
+        self.checksum_stats
+    }
+
+    /// The delay added after the current round because the target is unreachable, if tracing is backing off.
+    pub fn backoff(&self) -> Option<Duration> {
+        self.backoff
+    }
+
+    /// Set the delay added after the current round because the target is unreachable.
+    pub fn set_backoff(&mut self, backoff: Option<Duration>) {
+        self.backoff = backoff;
+        self.generation += 1;
+    }
+
+    /// Is tracing paused?
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Pause or resume tracing.
+    ///
+    /// Tracing is paused at the end of the current round.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.generation += 1;
+    }
+
+    /// Has tracing been stopped?
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Stop tracing, at the end of the current round, such as when the trace is removed.
+    ///
+    /// Unlike pausing, tracing cannot be resumed once stopped.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// The flag which is set once tracing is stopped.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Clear the statistics of all hops, retaining the history of route and AS path changes and whether tracing is
+    /// paused or stopped.
+    pub fn clear(&mut self) {
+        *self = Self {
+            generation: self.generation + 1,
+            route_changes: std::mem::take(&mut self.route_changes),
+            total_route_changes: self.total_route_changes,
+            as_path: std::mem::take(&mut self.as_path),
+            as_path_changes: std::mem::take(&mut self.as_path_changes),
+            total_as_path_changes: self.total_as_path_changes,
+            target_addr: self.target_addr,
+            target_changes: std::mem::take(&mut self.target_changes),
+            total_target_changes: self.total_target_changes,
+            paused: self.paused,
+            stop: self.stop.clone(),
+            ..Self::new(
+                self.max_samples,
+                self.destination_only,
+                self.backfill_late,
+                self.record_probes,
+            )
+        };
+    }
+
+    /// The statistics of the hops and the route changes of the trace, such as to save them with a session.
+    pub fn state(&self) -> TraceState {
+        TraceState {
+            hops: self.hops().to_vec(),
+            route_changes: self.route_changes.clone(),
+            total_route_changes: self.total_route_changes,
+            as_path_changes: self.as_path_changes.clone(),
+            total_as_path_changes: self.total_as_path_changes,
+            total_rounds: self.total_rounds,
+        }
+    }
+
+    /// Restore the statistics of the hops and the route changes of a trace from a previous session.
+    ///
+    /// Tracing then continues to accumulate statistics on top of those restored.
+    pub fn restore(&mut self, state: TraceState) {
+        for mut hop in state.hops {
+            if hop.ttl == 0 || usize::from(hop.ttl) > MAX_HOPS {
+                continue;
+            }
+            hop.samples.truncate(self.max_samples);
+            self.lowest_ttl = match self.lowest_ttl {
+                0 => hop.ttl,
+                lowest => lowest.min(hop.ttl),
+            };
+            self.highest_ttl = self.highest_ttl.max(hop.ttl);
+            let index = usize::from(hop.ttl) - 1;
+            self.hops[index] = hop;
+        }
+        let skip = state.route_changes.len().saturating_sub(MAX_ROUTE_CHANGES);
+        self.route_changes = state.route_changes.into_iter().skip(skip).collect();
+        self.total_route_changes = state.total_route_changes;
+        let skip = state
+            .as_path_changes
+            .len()
+            .saturating_sub(MAX_AS_PATH_CHANGES);
+        self.as_path_changes = state.as_path_changes.into_iter().skip(skip).collect();
+        self.total_as_path_changes = state.total_as_path_changes;
+        self.total_rounds = state.total_rounds;
+        self.generation += 1;
+    }
+
+    /// Record a tracing error.
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+        self.generation += 1;
+    }
+
     /// Update the tracing state from a `TracerRound`.
+    ///
+    /// When tracing the destination only, the probes sent to the hops before the target (whilst discovering the `ttl`
+    /// of the target) are ignored.
+    ///
+    /// When a probe has been retried only the latest retry for each `ttl` is recorded, such that retries are counted
+    /// separately rather than as additional probes sent.
+    ///
+    /// The late responses to the probes of the previous round are counted and, if back-filling, are also recorded as
+    /// received such that a hop which is slow to respond is not reported as lossy.
     pub fn update_from_round(&mut self, round: &TracerRound<'_>) {
         self.highest_ttl = std::cmp::max(self.highest_ttl, round.largest_ttl.0);
         self.highest_ttl_for_round = round.largest_ttl.0;
+        self.checksum_stats = round.checksum_stats;
+        let mut latest_retry = [0_u8; 256];
+        for probe in round.probes {
+            let retry = &mut latest_retry[usize::from(probe.ttl.0)];
+            *retry = (*retry).max(probe.retry);
+        }
         for probe in round.probes {
-            self.update_from_probe(probe);
+            let superseded = probe.retry < latest_retry[usize::from(probe.ttl.0)];
+            if !superseded && (!self.destination_only || probe.ttl >= round.largest_ttl) {
+                self.update_from_probe(probe);
+            }
+        }
+        for probe in round.late_probes {
+            if !self.destination_only || probe.ttl >= round.largest_ttl {
+                self.update_from_late_probe(probe);
+            }
+        }
+        self.total_rounds += 1;
+        self.round_duration = round_duration(round.probes);
+        self.update_probe_rate(round.probes);
+        self.unreachable_rounds = match round.reason {
+            CompletionReason::TargetFound => 0,
+            CompletionReason::RoundTimeLimitExceeded => self.unreachable_rounds + 1,
+        };
+        self.generation += 1;
+    }
+
+    /// Update the rate at which probes are sent from the probes sent in the latest round.
+    ///
+    /// The rate is the number of probes sent in the previous round divided by the interval from the start of the
+    /// previous round to the start of the latest round, and so includes any delay between rounds.
+    fn update_probe_rate(&mut self, probes: &[Probe]) {
+        let Some(round_start) = probes.iter().filter_map(|probe| probe.sent).min() else {
+            return;
+        };
+        if let Some(period) = self
+            .round_start
+            .and_then(|previous| round_start.duration_since(previous).ok())
+            .filter(|period| !period.is_zero())
+        {
+            self.probe_rate = Some(self.round_probes as f64 / period.as_secs_f64());
         }
+        self.round_start = Some(round_start);
+        self.round_probes = probes.iter().filter(|probe| probe.sent.is_some()).count();
     }
 
     fn update_from_probe(&mut self, probe: &Probe) {
         self.update_lowest_ttl(probe);
         self.update_round(probe);
+        if probe.retry > 0 {
+            self.hops[usize::from(probe.ttl.0) - 1].total_retries += usize::from(probe.retry);
+        }
         match probe.status {
             ProbeStatus::Complete => {
                 let index = usize::from(probe.ttl.0) - 1;
                 let hop = &mut self.hops[index];
                 hop.ttl = probe.ttl.0;
                 hop.total_sent += 1;
-                hop.total_recv += 1;
+                hop.total_dup += usize::from(probe.duplicates);
+                if probe.quote_corrupted {
+                    hop.total_corrupt += 1;
+                }
                 let dur = probe.duration();
-                let dur_ms = dur.as_secs_f64() * 1000_f64;
-                hop.total_time += dur;
+                hop.add_rtt(dur);
+                hop.add_jitter(dur);
                 hop.last = Some(dur);
                 hop.samples.insert(0, dur);
-                hop.best = hop.best.map_or(Some(dur), |d| Some(d.min(dur)));
-                hop.worst = hop.worst.map_or(Some(dur), |d| Some(d.max(dur)));
-                hop.mean += (dur_ms - hop.mean) / hop.total_recv as f64;
-                hop.m2 += (dur_ms - hop.mean) * (dur_ms - hop.mean);
                 if hop.samples.len() > self.max_samples {
                     hop.samples.pop();
                 }
+                if self.record_probes {
+                    hop.probes.push(ProbeSample::new(probe, Some(dur)));
+                }
                 let host = probe.host.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
-                *hop.addrs.entry(host).or_default() += 1;
+                if let Some(from) = hop.last_addr.filter(|_| !hop.addrs.contains_key(&host)) {
+                    let change = RouteChange {
+                        timestamp: probe.received.unwrap_or_else(SystemTime::now),
+                        round: probe.round.0,
+                        ttl: probe.ttl.0,
+                        from,
+                        to: host,
+                    };
+                    info!(ttl = change.ttl, %from, to = %host, "route changed");
+                    if self.route_changes.len() == MAX_ROUTE_CHANGES {
+                        self.route_changes.remove(0);
+                    }
+                    self.route_changes.push(change);
+                    self.total_route_changes += 1;
+                }
+                hop.last_addr = Some(host);
+                hop.add_addr_response(probe, host, dur);
+                if let (Some(packet_type), Some(code)) = (probe.icmp_packet_type, probe.icmp_code) {
+                    hop.last_icmp = Some(IcmpResponse::new(
+                        packet_type,
+                        code,
+                        host.is_ipv6(),
+                        probe.next_hop_mtu,
+                    ));
+                }
+                if let Some(ttl) = probe.reply_ttl {
+                    hop.last_reply_ttl = Some(ttl.0);
+                }
+                if let Some(tos) = probe.received_tos {
+                    hop.last_ecn = Some(EcnCodepoint::from_tos(tos.0));
+                    hop.last_dscp = Some(Dscp::from_tos(tos.0));
+                }
+                if let Some(handshake) = probe.tcp_handshake {
+                    match handshake {
+                        TcpHandshake::Established => hop.tcp_established += 1,
+                        TcpHandshake::Refused => hop.tcp_refused += 1,
+                    }
+                    hop.tcp_connect_time += dur;
+                    hop.last_tcp_connect = Some(dur);
+                }
+                if let Some(timestamps) = probe.icmp_timestamps {
+                    hop.last_timestamp = Some(Timestamp {
+                        originate: timestamps.originate,
+                        receive: timestamps.receive,
+                        transmit: timestamps.transmit,
+                        received: IcmpTimestamps::timestamp(
+                            probe.received.unwrap_or_else(SystemTime::now),
+                        ),
+                    });
+                }
+                if probe.icmp_packet_type == Some(IcmpPacketType::TimeExceeded) {
+                    self.update_off_path(index, host);
+                }
+                self.update_recorded_route(probe);
             }
             ProbeStatus::Awaited => {
                 let index = usize::from(probe.ttl.0) - 1;
                 self.hops[index].total_sent += 1;
                 self.hops[index].ttl = probe.ttl.0;
+                self.hops[index].add_lost(probe);
                 self.hops[index].samples.insert(0, Duration::default());
                 if self.hops[index].samples.len() > self.max_samples {
                     self.hops[index].samples.pop();
                 }
+                if self.record_probes {
+                    self.hops[index].probes.push(ProbeSample::new(probe, None));
+                }
             }
             ProbeStatus::NotSent => {}
         }
     }
 
+    /// Record the addresses of the IPv4 Record Route option quoted in the response to `probe` against their hops.
+    fn update_recorded_route(&mut self, probe: &Probe) {
+        let recorded = probe.recorded_route.addrs();
+        for (index, addr) in recorded.iter().enumerate().take(usize::from(probe.ttl.0)) {
+            let addr = IpAddr::V4(*addr);
+            let hop = &mut self.hops[index];
+            if !hop.recorded.contains(&addr) {
+                hop.recorded.push(addr);
+            }
+        }
+    }
+
+    fn update_from_late_probe(&mut self, probe: &Probe) {
+        let hop = &mut self.hops[usize::from(probe.ttl.0) - 1];
+        hop.total_late += 1;
+        if let Some(sample) = hop
+            .probes
+            .iter_mut()
+            .rev()
+            .find(|sample| sample.sequence == probe.sequence.0 && sample.rtt.is_none())
+        {
+            *sample = ProbeSample {
+                late: true,
+                ..ProbeSample::new(probe, Some(probe.duration()))
+            };
+        }
+        if self.backfill_late && hop.total_recv < hop.total_sent {
+            hop.add_rtt(probe.duration());
+            let host = probe.host.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+            *hop.addrs.entry(host).or_default() += 1;
+            hop.update_seen(host, probe.received.unwrap_or_else(SystemTime::now));
+            let stats = hop.addr_stats.entry(host).or_default();
+            if stats.total_recv < stats.total_sent {
+                stats.add_rtt(probe.duration());
+            }
+        }
+    }
+
     /// Update `lowest_ttl` for valid probes.
     fn update_lowest_ttl(&mut self, probe: &Probe) {
         if matches!(probe.status, ProbeStatus::Awaited | ProbeStatus::Complete) {
@@ -149,20 +725,193 @@ impl Trace {
     }
 }
 
+/// The result of a single probe sent for a `Hop`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ProbeSample {
+    /// The sequence number of the probe.
+    pub sequence: u16,
+    /// The round in which the probe was sent.
+    pub round: usize,
+    /// The time the probe was sent.
+    pub sent: Option<SystemTime>,
+    /// The round trip time of the probe, `None` if no response was received.
+    pub rtt: Option<Duration>,
+    /// The address which responded to the probe, if any.
+    pub host: Option<IpAddr>,
+    /// Whether the response was received after the round in which the probe was sent.
+    pub late: bool,
+}
+
+impl ProbeSample {
+    fn new(probe: &Probe, rtt: Option<Duration>) -> Self {
+        Self {
+            sequence: probe.sequence.0,
+            round: probe.round.0,
+            sent: probe.sent,
+            rtt,
+            host: probe.host,
+            late: false,
+        }
+    }
+}
+
+/// A change of the route observed at a `Hop`.
+///
+/// A route change is recorded whenever a `Hop` which has previously responded does so from an address which has not
+/// been seen for that `Hop` before.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RouteChange {
+    /// The time the response from the new address was received.
+    pub timestamp: SystemTime,
+    /// The round in which the route change was observed.
+    pub round: usize,
+    /// The time-to-live of the `Hop` at which the route changed.
+    pub ttl: u8,
+    /// The address which previously responded for the `Hop`.
+    pub from: IpAddr,
+    /// The new address which responded for the `Hop`.
+    pub to: IpAddr,
+}
+
+/// A change of the AS path of a `Trace`.
+///
+/// The AS path is the sequence of distinct `AS` numbers of the last address to respond for each `Hop`, excluding
+/// addresses without `AS` information.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AsPathChange {
+    /// The time the new AS path was observed.
+    pub timestamp: SystemTime,
+    /// The round in which the AS path change was observed.
+    pub round: usize,
+    /// The previous AS path.
+    pub from: Vec<String>,
+    /// The new AS path.
+    pub to: Vec<String>,
+}
+
+/// A change of the address of the target of a `Trace`, observed when re-resolving the hostname of the target.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TargetChange {
+    /// The time the new address was observed.
+    pub timestamp: SystemTime,
+    /// The round after which the new address was observed.
+    pub round: usize,
+    /// The previous address of the target.
+    pub from: IpAddr,
+    /// The new address of the target.
+    pub to: IpAddr,
+    /// Whether tracing switched to the new address.
+    pub followed: bool,
+}
+
+/// Format an AS path, such as `AS64496 AS64511`.
+pub fn format_as_path(as_path: &[String]) -> String {
+    as_path
+        .iter()
+        .map(|asn| format!("AS{asn}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A summary of a `Trace` as a whole.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceSummary {
+    /// The number of rounds completed.
+    pub total_rounds: usize,
+    /// The total number of probes sent, for all hops.
+    pub total_sent: usize,
+    /// The total number of responses received, for all hops.
+    pub total_recv: usize,
+    /// The % of probes sent to the target `Hop` which were lost.
+    pub loss_pct: f64,
+    /// The duration of the last round completed, from the first probe sent to the last response received.
+    pub round_duration: Option<Duration>,
+    /// The number of hops of the path in the last round completed.
+    pub path_len: u8,
+}
+
+/// The duration of a round, from the first probe sent to the last response received, if any response was received.
+fn round_duration(probes: &[Probe]) -> Option<Duration> {
+    let first_sent = probes.iter().filter_map(|probe| probe.sent).min()?;
+    let last_received = probes.iter().filter_map(|probe| probe.received).max()?;
+    last_received.duration_since(first_sent).ok()
+}
+
+/// The statistics of the hops and the route changes of a `Trace`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TraceState {
+    hops: Vec<Hop>,
+    route_changes: Vec<RouteChange>,
+    total_route_changes: usize,
+    #[serde(default)]
+    as_path_changes: Vec<AsPathChange>,
+    #[serde(default)]
+    total_as_path_changes: usize,
+    #[serde(default)]
+    total_rounds: usize,
+}
+
 /// Information about a single `Hop` within a `Trace`.
-#[derive(Debug, Clone)]
+///
+/// The ECN codepoint and ICMP response of the last response received, and the result of every probe, are not
+/// serialized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct Hop {
     ttl: u8,
+    #[serde(with = "serde_addrs")]
     addrs: IndexMap<IpAddr, usize>,
+    #[serde(default, with = "serde_addrs")]
+    seen: IndexMap<IpAddr, Seen>,
+    #[serde(default, with = "serde_addrs")]
+    addr_stats: IndexMap<IpAddr, AddrStats>,
+    #[serde(skip)]
+    flows: IndexMap<(u16, u16), IpAddr>,
     total_sent: usize,
     total_recv: usize,
+    total_dup: usize,
+    total_corrupt: usize,
+    total_retries: usize,
+    total_late: usize,
     total_time: Duration,
     last: Option<Duration>,
     best: Option<Duration>,
     worst: Option<Duration>,
     mean: f64,
     m2: f64,
+    #[serde(default)]
+    jitter: Option<Duration>,
+    #[serde(default)]
+    worst_jitter: Option<Duration>,
+    #[serde(default)]
+    total_jitter: Duration,
+    #[serde(default)]
+    total_jitter_count: usize,
     samples: Vec<Duration>,
+    #[serde(skip)]
+    last_ecn: Option<EcnCodepoint>,
+    #[serde(skip)]
+    last_dscp: Option<Dscp>,
+    last_reply_ttl: Option<u8>,
+    #[serde(skip)]
+    last_icmp: Option<IcmpResponse>,
+    last_addr: Option<IpAddr>,
+    tcp_established: usize,
+    tcp_refused: usize,
+    tcp_connect_time: Duration,
+    last_tcp_connect: Option<Duration>,
+    #[serde(default)]
+    recorded: Vec<IpAddr>,
+    #[serde(default, with = "serde_addrs")]
+    off_path: IndexMap<IpAddr, OffPath>,
+    #[serde(default)]
+    last_timestamp: Option<Timestamp>,
+    #[serde(skip)]
+    probes: Vec<ProbeSample>,
 }
 
 impl Hop {
@@ -185,6 +934,41 @@ impl Hop {
         self.addrs.len()
     }
 
+    /// When an address was first and last observed responding for this time-to-live.
+    pub fn addr_seen(&self, addr: &IpAddr) -> Option<Seen> {
+        self.seen.get(addr).copied()
+    }
+
+    /// The statistics of the responses from an address for this time-to-live.
+    pub fn addr_stats(&self, addr: &IpAddr) -> Option<&AddrStats> {
+        self.addr_stats.get(addr)
+    }
+
+    /// When any address was first and last observed responding for this time-to-live.
+    pub fn seen(&self) -> Option<Seen> {
+        self.seen.values().copied().reduce(|acc, seen| Seen {
+            first: acc.first.min(seen.first),
+            last: acc.last.max(seen.last),
+        })
+    }
+
+    /// The addresses recorded for this time-to-live by the IPv4 Record Route option.
+    pub fn recorded_addrs(&self) -> impl Iterator<Item = &IpAddr> {
+        self.recorded.iter()
+    }
+
+    /// The recorded addresses which were not also observed responding for this time-to-live.
+    pub fn unseen_recorded_addrs(&self) -> impl Iterator<Item = &IpAddr> {
+        self.recorded
+            .iter()
+            .filter(|addr| !self.addrs.contains_key(*addr))
+    }
+
+    /// Why an address which responded for this time-to-live is not an in-path interface of the hop, if it is not.
+    pub fn off_path(&self, addr: &IpAddr) -> Option<OffPath> {
+        self.off_path.get(addr).copied()
+    }
+
     /// The total number of probes sent.
     pub fn total_sent(&self) -> usize {
         self.total_sent
@@ -195,6 +979,26 @@ impl Hop {
         self.total_recv
     }
 
+    /// The total number of duplicate responses received.
+    pub fn total_dup(&self) -> usize {
+        self.total_dup
+    }
+
+    /// The total number of responses which quoted a corrupted copy of the probe.
+    pub fn total_corrupt(&self) -> usize {
+        self.total_corrupt
+    }
+
+    /// The total number of probes which were resent as no response was received in time.
+    pub fn total_retries(&self) -> usize {
+        self.total_retries
+    }
+
+    /// The total number of responses received after the round of the probe had completed.
+    pub fn total_late(&self) -> usize {
+        self.total_late
+    }
+
     /// The % of packets that are lost.
     pub fn loss_pct(&self) -> f64 {
         if self.total_sent > 0 {
@@ -238,10 +1042,632 @@ impl Hop {
         }
     }
 
+    /// The jitter of the last probe, which is the difference between its duration and that of the previous probe.
+    pub fn jitter_ms(&self) -> Option<f64> {
+        self.jitter.map(|jitter| jitter.as_secs_f64() * 1000_f64)
+    }
+
+    /// The average jitter of all probes.
+    pub fn avg_jitter_ms(&self) -> Option<f64> {
+        match self.total_jitter_count {
+            0 => None,
+            count => Some(self.total_jitter.as_secs_f64() * 1000_f64 / count as f64),
+        }
+    }
+
+    /// The worst jitter of all probes.
+    pub fn worst_jitter_ms(&self) -> Option<f64> {
+        self.worst_jitter
+            .map(|jitter| jitter.as_secs_f64() * 1000_f64)
+    }
+
+    /// The duration below which `pct` % of the last N samples which received a response fall, using the nearest rank.
+    pub fn percentile_ms(&self, pct: f64) -> Option<f64> {
+        let mut samples = self
+            .samples
+            .iter()
+            .filter(|sample| !sample.is_zero())
+            .collect::<Vec<_>>();
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let rank = (pct / 100_f64 * samples.len() as f64).ceil() as usize;
+        let sample = samples[rank.clamp(1, samples.len()) - 1];
+        Some(sample.as_secs_f64() * 1000_f64)
+    }
+
     /// The last N samples.
     pub fn samples(&self) -> &[Duration] {
         &self.samples
     }
+
+    /// The result of every probe sent for this time-to-live, oldest first, if probes are being recorded.
+    pub fn probes(&self) -> &[ProbeSample] {
+        &self.probes
+    }
+
+    /// The type and code of the last ICMP response received from this hop.
+    pub fn icmp_response(&self) -> Option<IcmpResponse> {
+        self.last_icmp
+    }
+
+    /// The time-to-live of the last response received from this hop.
+    ///
+    /// This is only available for IPv4.
+    pub fn reply_ttl(&self) -> Option<u8> {
+        self.last_reply_ttl
+    }
+
+    /// The inferred number of hops on the return path from this hop.
+    ///
+    /// This is derived from the time-to-live of the last response received, assuming it was sent with the smallest of
+    /// the commonly used initial time-to-live values (32, 64, 128 and 255) which is not less than the observed value.
+    pub fn return_path_len(&self) -> Option<u8> {
+        self.last_reply_ttl.map(|reply_ttl| {
+            let initial_ttl = [32, 64, 128, 255]
+                .into_iter()
+                .find(|&ttl| ttl >= reply_ttl)
+                .unwrap_or(u8::MAX);
+            (initial_ttl - reply_ttl).saturating_add(1)
+        })
+    }
+
+    /// The number of TCP handshakes with this hop which were established (`SYN+ACK`).
+    pub fn tcp_established(&self) -> usize {
+        self.tcp_established
+    }
+
+    /// The number of TCP handshakes with this hop which were refused (`RST`).
+    pub fn tcp_refused(&self) -> usize {
+        self.tcp_refused
+    }
+
+    /// The duration of the last TCP handshake with this hop, in milliseconds.
+    ///
+    /// Only probes which completed (or were refused by) a TCP handshake are included, such that the connection level
+    /// round-trip-time can be distinguished from the network path latency measured by other responses, such as those
+    /// generated by middleboxes which filter `SYN`.
+    pub fn last_tcp_connect_ms(&self) -> Option<f64> {
+        self.last_tcp_connect
+            .map(|last| last.as_secs_f64() * 1000_f64)
+    }
+
+    /// The last ICMP timestamp reply from this hop, if `TimestampRequest` probes are sent.
+    pub fn last_timestamp(&self) -> Option<Timestamp> {
+        self.last_timestamp
+    }
+
+    /// The average duration of the TCP handshakes with this hop, in milliseconds.
+    pub fn avg_tcp_connect_ms(&self) -> Option<f64> {
+        match self.tcp_established + self.tcp_refused {
+            0 => None,
+            count => Some(self.tcp_connect_time.as_secs_f64() * 1000_f64 / count as f64),
+        }
+    }
+
+    /// The status of the ECN codepoint, as sent in probes, when last received by this hop.
+    ///
+    /// Returns `None` if no response which quoted the original probe has been received for this hop.
+    pub fn ecn_status(&self, sent: EcnCodepoint) -> Option<EcnStatus> {
+        self.last_ecn.map(|received| match received {
+            ecn if ecn == sent => EcnStatus::Preserved,
+            EcnCodepoint::NotEct => EcnStatus::Bleached,
+            ecn => EcnStatus::Remarked(ecn),
+        })
+    }
+
+    /// The status of the DSCP, as sent in probes, when last received by this hop.
+    ///
+    /// Returns `None` if no response which quoted the original probe has been received for this hop.
+    pub fn dscp_status(&self, sent: Dscp) -> Option<DscpStatus> {
+        self.last_dscp.map(|received| match received {
+            dscp if dscp == sent => DscpStatus::Preserved,
+            Dscp(0) => DscpStatus::Bleached,
+            dscp => DscpStatus::Remarked(dscp),
+        })
+    }
+
+    /// Record that `addr` responded at `timestamp`.
+    fn update_seen(&mut self, addr: IpAddr, timestamp: SystemTime) {
+        self.seen
+            .entry(addr)
+            .and_modify(|seen| {
+                seen.first = seen.first.min(timestamp);
+                seen.last = seen.last.max(timestamp);
+            })
+            .or_insert(Seen {
+                first: timestamp,
+                last: timestamp,
+            });
+    }
+
+    /// Record a response from `host` to a `probe` after `dur`, for the statistics of each address.
+    fn add_addr_response(&mut self, probe: &Probe, host: IpAddr, dur: Duration) {
+        *self.addrs.entry(host).or_default() += 1;
+        self.update_seen(host, probe.received.unwrap_or_else(SystemTime::now));
+        self.flows.insert(flow(probe), host);
+        let stats = self.addr_stats.entry(host).or_default();
+        stats.total_sent += 1;
+        stats.add_rtt(dur);
+    }
+
+    /// Attribute a `probe` for which no response was received to the address which last responded to a probe of the
+    /// same flow for this time-to-live, if any.
+    ///
+    /// The flow of a probe is identified by its source and destination ports, such that, when tracing with the `paris`
+    /// or `dublin` multipath strategies, the probes of a flow are hashed to the same member of an ECMP group.  The
+    /// probes of a flow which has never responded are not attributed to any address.
+    fn add_lost(&mut self, probe: &Probe) {
+        if let Some(addr) = self.flows.get(&flow(probe)) {
+            self.addr_stats.entry(*addr).or_default().total_sent += 1;
+        }
+    }
+
+    /// Record the jitter of a response received after `dur`, relative to the last response received.
+    fn add_jitter(&mut self, dur: Duration) {
+        if let Some(last) = self.last {
+            let jitter = dur.abs_diff(last);
+            self.jitter = Some(jitter);
+            self.worst_jitter = self
+                .worst_jitter
+                .map_or(Some(jitter), |j| Some(j.max(jitter)));
+            self.total_jitter += jitter;
+            self.total_jitter_count += 1;
+        }
+    }
+
+    /// Record a response received after `dur`.
+    fn add_rtt(&mut self, dur: Duration) {
+        let dur_ms = dur.as_secs_f64() * 1000_f64;
+        self.total_recv += 1;
+        self.total_time += dur;
+        self.best = self.best.map_or(Some(dur), |d| Some(d.min(dur)));
+        self.worst = self.worst.map_or(Some(dur), |d| Some(d.max(dur)));
+        self.mean += (dur_ms - self.mean) / self.total_recv as f64;
+        self.m2 += (dur_ms - self.mean) * (dur_ms - self.mean);
+    }
+}
+
+/// The flow of a `Probe`, which is identified by its source and destination ports.
+fn flow(probe: &Probe) -> (u16, u16) {
+    (probe.src_port.0, probe.dest_port.0)
+}
+
+/// The statistics of the responses from a single address of a `Hop`.
+///
+/// A probe for which no response was received is attributed to the address which last responded to a probe of the
+/// same flow, such that the loss of a single member of an ECMP group can be identified.  Note that the sum of the
+/// probes sent to each address may therefore be less than the total number of probes sent for the `Hop`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AddrStats {
+    total_sent: usize,
+    total_recv: usize,
+    total_time: Duration,
+    last: Option<Duration>,
+    best: Option<Duration>,
+    worst: Option<Duration>,
+    mean: f64,
+    m2: f64,
+}
+
+impl AddrStats {
+    /// The total number of probes attributed to this address.
+    pub fn total_sent(&self) -> usize {
+        self.total_sent
+    }
+
+    /// The total number of responses received from this address.
+    pub fn total_recv(&self) -> usize {
+        self.total_recv
+    }
+
+    /// The % of packets attributed to this address that are lost.
+    pub fn loss_pct(&self) -> f64 {
+        if self.total_sent > 0 {
+            let lost = self.total_sent - self.total_recv;
+            lost as f64 / self.total_sent as f64 * 100f64
+        } else {
+            0_f64
+        }
+    }
+
+    /// The duration of the last response from this address.
+    pub fn last_ms(&self) -> Option<f64> {
+        self.last.map(|last| last.as_secs_f64() * 1000_f64)
+    }
+
+    /// The duration of the best response from this address.
+    pub fn best_ms(&self) -> Option<f64> {
+        self.best.map(|best| best.as_secs_f64() * 1000_f64)
+    }
+
+    /// The duration of the worst response from this address.
+    pub fn worst_ms(&self) -> Option<f64> {
+        self.worst.map(|worst| worst.as_secs_f64() * 1000_f64)
+    }
+
+    /// The average duration of all responses from this address.
+    pub fn avg_ms(&self) -> f64 {
+        if self.total_recv > 0 {
+            (self.total_time.as_secs_f64() * 1000_f64) / self.total_recv as f64
+        } else {
+            0_f64
+        }
+    }
+
+    /// The standard deviation of all responses from this address.
+    pub fn stddev_ms(&self) -> f64 {
+        if self.total_recv > 1 {
+            (self.m2 / (self.total_recv - 1) as f64).sqrt()
+        } else {
+            0_f64
+        }
+    }
+
+    /// Record a response received after `dur`, calculated in the same way as for the `Hop` as a whole.
+    fn add_rtt(&mut self, dur: Duration) {
+        let dur_ms = dur.as_secs_f64() * 1000_f64;
+        self.total_recv += 1;
+        self.total_time += dur;
+        self.last = Some(dur);
+        self.best = self.best.map_or(Some(dur), |d| Some(d.min(dur)));
+        self.worst = self.worst.map_or(Some(dur), |d| Some(d.max(dur)));
+        self.mean += (dur_ms - self.mean) / self.total_recv as f64;
+        self.m2 += (dur_ms - self.mean) * (dur_ms - self.mean);
+    }
+}
+
+/// The type and code of an ICMP response received for a `Hop`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct IcmpResponse {
+    packet_type: IcmpPacketType,
+    code: u8,
+    ipv6: bool,
+    next_hop_mtu: Option<u16>,
+}
+
+impl IcmpResponse {
+    pub fn new(
+        packet_type: IcmpPacketType,
+        code: u8,
+        ipv6: bool,
+        next_hop_mtu: Option<u16>,
+    ) -> Self {
+        Self {
+            packet_type,
+            code,
+            ipv6,
+            next_hop_mtu,
+        }
+    }
+
+    /// The `ICMP` (or `ICMPv6`) type number.
+    pub fn icmp_type(self) -> Option<u8> {
+        if self.ipv6 && self.is_fragmentation_needed() {
+            return Some(2);
+        }
+        match (self.packet_type, self.ipv6) {
+            (IcmpPacketType::TimeExceeded, false) => Some(11),
+            (IcmpPacketType::TimeExceeded, true) | (IcmpPacketType::Unreachable, false) => Some(3),
+            (IcmpPacketType::Unreachable, true) => Some(1),
+            (IcmpPacketType::EchoReply, false) => Some(0),
+            (IcmpPacketType::EchoReply, true) => Some(129),
+            (IcmpPacketType::NotApplicable, _) => None,
+        }
+    }
+
+    /// Is this an ICMP destination unreachable response?
+    pub fn is_unreachable(self) -> bool {
+        self.packet_type == IcmpPacketType::Unreachable
+    }
+
+    /// Is this an ICMP destination unreachable response which is administratively prohibited?
+    pub fn is_prohibited(self) -> bool {
+        self.is_unreachable()
+            && !self.is_fragmentation_needed()
+            && ProhibitedStrategy::is_prohibited(self.code, self.ipv6)
+    }
+
+    /// Is this an ICMP `Fragmentation Needed` (`IPv4`) or `Packet Too Big` (`IPv6`) response?
+    pub fn is_fragmentation_needed(self) -> bool {
+        self.next_hop_mtu.is_some()
+    }
+
+    /// The next hop MTU of a `Fragmentation Needed` (`IPv4`) or `Packet Too Big` (`IPv6`) response.
+    pub fn next_hop_mtu(self) -> Option<u16> {
+        self.next_hop_mtu
+    }
+
+    /// The flag shown alongside the addresses of a `Hop` for a response which stopped the probes, if any.
+    pub fn flag(self) -> Option<String> {
+        if let Some(mtu) = self.next_hop_mtu {
+            Some(format!("frag needed, mtu {mtu}"))
+        } else if self.is_prohibited() {
+            Some(String::from("prohibited"))
+        } else {
+            None
+        }
+    }
+
+    /// A description of the ICMP type and code.
+    pub fn description(self) -> &'static str {
+        if self.ipv6 && self.is_fragmentation_needed() {
+            return "packet too big";
+        }
+        match (self.packet_type, self.ipv6, self.code) {
+            (IcmpPacketType::TimeExceeded, false, 0) => "ttl exceeded in transit",
+            (IcmpPacketType::TimeExceeded, true, 0) => "hop limit exceeded in transit",
+            (IcmpPacketType::TimeExceeded, _, 1) => "fragment reassembly time exceeded",
+            (IcmpPacketType::TimeExceeded, _, _) => "time exceeded",
+            (IcmpPacketType::Unreachable, false, 0) => "network unreachable",
+            (IcmpPacketType::Unreachable, false, 1) => "host unreachable",
+            (IcmpPacketType::Unreachable, false, 2) => "protocol unreachable",
+            (IcmpPacketType::Unreachable, false, 3) | (IcmpPacketType::Unreachable, true, 4) => {
+                "port unreachable"
+            }
+            (IcmpPacketType::Unreachable, false, 4) => "fragmentation needed",
+            (IcmpPacketType::Unreachable, false, 5) => "source route failed",
+            (IcmpPacketType::Unreachable, false, 6) => "destination network unknown",
+            (IcmpPacketType::Unreachable, false, 7) => "destination host unknown",
+            (IcmpPacketType::Unreachable, false, 9) => "network administratively prohibited",
+            (IcmpPacketType::Unreachable, false, 10) => "host administratively prohibited",
+            (IcmpPacketType::Unreachable, false, 13) | (IcmpPacketType::Unreachable, true, 1) => {
+                "communication administratively prohibited"
+            }
+            (IcmpPacketType::Unreachable, true, 0) => "no route to destination",
+            (IcmpPacketType::Unreachable, true, 2) => "beyond scope of source address",
+            (IcmpPacketType::Unreachable, true, 3) => "address unreachable",
+            (IcmpPacketType::Unreachable, true, 5) => "source address failed ingress/egress policy",
+            (IcmpPacketType::Unreachable, true, 6) => "reject route to destination",
+            (IcmpPacketType::Unreachable, _, _) => "destination unreachable",
+            (IcmpPacketType::EchoReply, _, _) => "echo reply",
+            (IcmpPacketType::NotApplicable, _, _) => "n/a",
+        }
+    }
+}
+
+impl Display for IcmpResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.icmp_type(), self.next_hop_mtu) {
+            (Some(icmp_type), Some(mtu)) => write!(
+                f,
+                "{} (type {}, code {}, mtu {})",
+                self.description(),
+                icmp_type,
+                self.code,
+                mtu
+            ),
+            (Some(icmp_type), None) => write!(
+                f,
+                "{} (type {}, code {})",
+                self.description(),
+                icmp_type,
+                self.code
+            ),
+            (None, _) => write!(f, "{}", self.description()),
+        }
+    }
+}
+
+/// Why the address which sent a `TimeExceeded` error for a `Hop` is not an in-path interface of the hop.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OffPath {
+    /// The address is a loopback address.
+    Loopback,
+    /// The address also responded for the non-adjacent hop with this time-to-live.
+    OtherHop(u8),
+}
+
+impl Display for OffPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loopback => write!(f, "loopback"),
+            Self::OtherHop(ttl) => write!(f, "also hop {ttl}"),
+        }
+    }
+}
+
+/// The status of the ECN codepoint of probes as received by a `Hop`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EcnStatus {
+    /// The ECN codepoint was received unchanged.
+    Preserved,
+    /// The ECN codepoint was cleared (i.e. set to `Not-ECT`).
+    Bleached,
+    /// The ECN codepoint was changed to a different value.
+    Remarked(EcnCodepoint),
+}
+
+impl Display for EcnStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Preserved => write!(f, "preserved"),
+            Self::Bleached => write!(f, "bleached"),
+            Self::Remarked(ecn) => write!(f, "remarked to {ecn}"),
+        }
+    }
+}
+
+/// The status of the DSCP of probes as received by a `Hop`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DscpStatus {
+    /// The DSCP was received unchanged.
+    Preserved,
+    /// The DSCP was cleared (i.e. set to the default `CS0`).
+    Bleached,
+    /// The DSCP was changed to a different value.
+    Remarked(Dscp),
+}
+
+impl Display for DscpStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Preserved => write!(f, "preserved"),
+            Self::Bleached => write!(f, "bleached"),
+            Self::Remarked(dscp) => write!(f, "remarked to {dscp}"),
+        }
+    }
+}
+
+/// The status of a `Hop`, as derived from the loss observed at it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HopStatus {
+    /// All probes were responded to.
+    Ok,
+    /// Some probes were not responded to.
+    Loss,
+    /// No probes were responded to.
+    NoResponse,
+    /// Some probes were not responded to, likely due to ICMP rate limiting.
+    RateLimited,
+    /// Some probes to the target were not responded to.
+    TargetLoss,
+    /// No probes to the target were responded to.
+    TargetDown,
+}
+
+impl Display for HopStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "ok"),
+            Self::Loss => write!(f, "loss"),
+            Self::NoResponse => write!(f, "none"),
+            Self::RateLimited => write!(f, "rl"),
+            Self::TargetLoss => write!(f, "LOSS"),
+            Self::TargetDown => write!(f, "DOWN"),
+        }
+    }
+}
+
+/// How far the probes of a trace reached towards the target, with the `ttl` of the hop concerned.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Reachability {
+    /// The target established a TCP handshake (`SYN+ACK`).
+    Open(u8),
+    /// The target refused a TCP handshake (`RST`).
+    Closed(u8),
+    /// The target responded.
+    Reached(u8),
+    /// A hop responded that the target is unreachable.
+    Rejected(u8),
+    /// No hop responded beyond this hop, if any responded.
+    Filtered(Option<u8>),
+}
+
+impl Reachability {
+    /// The `ttl` of the hop concerned, if any.
+    pub fn ttl(self) -> Option<u8> {
+        match self {
+            Self::Open(ttl) | Self::Closed(ttl) | Self::Reached(ttl) | Self::Rejected(ttl) => {
+                Some(ttl)
+            }
+            Self::Filtered(ttl) => ttl,
+        }
+    }
+}
+
+impl Display for Reachability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Open(_) => write!(f, "open"),
+            Self::Closed(_) => write!(f, "closed"),
+            Self::Reached(_) => write!(f, "reached"),
+            Self::Rejected(_) => write!(f, "rejected"),
+            Self::Filtered(_) => write!(f, "filtered"),
+        }
+    }
+}
+
+/// (De)serialize a map keyed by the addresses of a `Hop`, such as the number of responses from each, as a sequence in the
+/// order the addresses first responded.
+mod serde_addrs {
+    use indexmap::IndexMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::net::IpAddr;
+
+    pub fn serialize<S: Serializer, V: Serialize>(
+        addrs: &IndexMap<IpAddr, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(addrs)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<IndexMap<IpAddr, V>, D::Error> {
+        Ok(Vec::<(IpAddr, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// The timestamps of an ICMP timestamp reply from a `Hop`, each the number of milliseconds since midnight UTC.
+///
+/// The one-way delays are the differences between timestamps set by different hosts and so include the offset between
+/// their clocks, which cancels out in the round trip but not in the asymmetry of the forward and return delays.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Timestamp {
+    /// When the request was sent by this host.
+    pub originate: u32,
+    /// When the request was received by the hop.
+    pub receive: u32,
+    /// When the reply was sent by the hop.
+    pub transmit: u32,
+    /// When the reply was received by this host.
+    pub received: u32,
+}
+
+impl Timestamp {
+    /// The estimated delay from this host to the hop, in milliseconds, if the hop set standard timestamps.
+    pub fn forward_ms(&self) -> Option<i64> {
+        self.is_standard()
+            .then(|| timestamp_diff(self.receive, self.originate))
+    }
+
+    /// The estimated delay from the hop to this host, in milliseconds, if the hop set standard timestamps.
+    pub fn return_ms(&self) -> Option<i64> {
+        self.is_standard()
+            .then(|| timestamp_diff(self.received, self.transmit))
+    }
+
+    /// The difference between the forward and return delays, in milliseconds.
+    pub fn asymmetry_ms(&self) -> Option<i64> {
+        Some(self.forward_ms()? - self.return_ms()?)
+    }
+
+    fn is_standard(&self) -> bool {
+        IcmpTimestamps {
+            originate: self.originate,
+            receive: self.receive,
+            transmit: self.transmit,
+        }
+        .is_standard()
+    }
+}
+
+/// The difference between two timestamps in milliseconds, allowing for `later` to have wrapped at midnight UTC.
+fn timestamp_diff(later: u32, earlier: u32) -> i64 {
+    let day = i64::from(IcmpTimestamps::MILLIS_PER_DAY);
+    let diff = (i64::from(later) - i64::from(earlier)).rem_euclid(day);
+    if diff > day / 2 {
+        diff - day
+    } else {
+        diff
+    }
+}
+
+/// When an address was first and last observed responding for a `Hop`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Seen {
+    /// The time the first response from the address was received.
+    pub first: SystemTime,
+    /// The time the last response from the address was received.
+    pub last: SystemTime,
 }
 
 impl Default for Hop {
@@ -249,39 +1675,463 @@ impl Default for Hop {
         Self {
             ttl: 0,
             addrs: IndexMap::default(),
+            seen: IndexMap::default(),
+            addr_stats: IndexMap::default(),
+            flows: IndexMap::default(),
             total_sent: 0,
             total_recv: 0,
+            total_dup: 0,
+            total_corrupt: 0,
+            total_retries: 0,
+            total_late: 0,
             total_time: Duration::default(),
             last: None,
             best: None,
             worst: None,
             mean: 0f64,
             m2: 0f64,
+            jitter: None,
+            worst_jitter: None,
+            total_jitter: Duration::default(),
+            total_jitter_count: 0,
             samples: Vec::default(),
+            last_ecn: None,
+            last_dscp: None,
+            last_reply_ttl: None,
+            last_icmp: None,
+            last_addr: None,
+            tcp_established: 0,
+            tcp_refused: 0,
+            tcp_connect_time: Duration::default(),
+            last_tcp_connect: None,
+            recorded: Vec::new(),
+            off_path: IndexMap::new(),
+            last_timestamp: None,
+            probes: Vec::new(),
         }
     }
 }
 
 /// Run the tracing backend.
 ///
+/// The outcome of creating the tracer channel is sent to `connected` and tracing begins once all tracers and the
+/// caller have reached the `start` barrier, which allows the caller to drop privileges in between.
+///
+/// The `hooks` are run for the events of each round, and so hook commands are never run with elevated privileges.
+///
+/// If a `reset_interval` is given then the statistics of all hops are cleared whenever it has elapsed.
+///
+/// If a `publisher` is given then each round is published to the clients of the agent.
+///
+/// If an `as_lookup` configuration is given then the AS path of the trace is looked up after each round, so that AS
+/// path changes are recorded before the hooks are run.  The lookups are performed with a `DnsResolver` owned by the
+/// backend, which is started from the `DnsResolverConfig` and `DnsHosts`.
+///
+/// If a `re_resolve` configuration is given then the hostname of the target is re-resolved periodically and, if the
+/// address of the target has changed, the change is recorded and the new address is traced or not according to the
+/// `ReResolveStrategy`.
+///
 /// Note that this implementation blocks the tracer on the `RwLock` and so any delays in the the TUI will delay the
 /// next round of the started.
+#[allow(clippy::too_many_arguments)]
 pub fn run_backend(
     tracer_config: &TracerConfig,
     channel_config: &TracerChannelConfig,
     trace_data: Arc<RwLock<Trace>>,
+    connected: &SyncSender<anyhow::Result<()>>,
+    start: &Barrier,
+    hooks: Hooks,
+    reset_interval: Option<Duration>,
+    publisher: Option<RoundPublisher>,
+    as_lookup: Option<(DnsResolverConfig, DnsHosts)>,
+    unreachable_backoff: Option<Duration>,
+    re_resolve: Option<ReResolveConfig>,
 ) -> anyhow::Result<()> {
     let td = trace_data.clone();
-    let channel = TracerChannel::connect(channel_config)?;
+    let channel = match TracerChannel::connect(channel_config) {
+        Ok(channel) => channel,
+        Err(err) => {
+            connected.send(Err(err.into()))?;
+            return Ok(());
+        }
+    };
+    connected.send(Ok(()))?;
+    start.wait();
     drop_caps()?;
-    let tracer = Tracer::new(tracer_config, move |round| {
-        trace_data.write().update_from_round(round);
-    });
-    match tracer.trace(channel) {
-        Ok(_) => {}
+    let as_resolver = as_lookup
+        .map(|(config, hosts)| DnsResolver::start(config, hosts))
+        .transpose()?;
+    let re_resolver = re_resolve
+        .map(|re_resolve| {
+            DnsResolver::start(re_resolve.resolver_config, re_resolve.hosts.clone())
+                .map(|resolver| (re_resolve, resolver))
+        })
+        .transpose()?;
+    let stop = trace_data.read().stop_flag();
+    let last_reset = Cell::new(Instant::now());
+    let min_round_duration = tracer_config.min_round_duration;
+    let on_round = move |round: &TracerRound<'_>| {
+        let mut trace = trace_data.write();
+        if let Some(interval) = reset_interval {
+            if last_reset.get().elapsed() >= interval {
+                trace.clear();
+                last_reset.set(Instant::now());
+            }
+        }
+        trace.update_from_round(round);
+        if let Some(as_path) = as_resolver
+            .as_ref()
+            .and_then(|resolver| lookup_as_path(&trace, resolver))
+        {
+            trace.update_as_path(as_path);
+        }
+        hooks.on_round(&trace);
+        let backoff = unreachable_backoff.and_then(|max_backoff| {
+            backoff_delay(trace.unreachable_rounds(), min_round_duration, max_backoff)
+        });
+        trace.set_backoff(backoff);
+        drop(trace);
+        if let Some(publisher) = &publisher {
+            publisher.publish(round);
+        }
+        if let Some(backoff) = backoff {
+            let backoff_start = Instant::now();
+            while backoff_start.elapsed() < backoff
+                && trace_data.read().unreachable_rounds() > 0
+                && !trace_data.read().is_stopped()
+            {
+                thread::sleep(PAUSE_POLL_INTERVAL.min(backoff));
+            }
+        }
+        while trace_data.read().is_paused() && !trace_data.read().is_stopped() {
+            thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+    };
+    let result = match re_resolver {
+        None => Tracer::new(tracer_config, on_round)
+            .with_stop(stop)
+            .trace(channel)
+            .map_err(anyhow::Error::from),
+        Some((re_resolve, resolver)) => trace_re_resolving(
+            tracer_config,
+            on_round,
+            channel,
+            &re_resolve,
+            &resolver,
+            &td,
+            &stop,
+        ),
+    };
+    match result {
+        Ok(()) => {}
         Err(err) => {
-            td.write().error = Some(err.to_string());
+            error!(%err, "tracer failed");
+            td.write().set_error(err.to_string());
         }
     };
     Ok(())
 }
+
+/// The configuration for periodically re-resolving the hostname of the target of a trace.
+#[derive(Debug, Clone)]
+pub struct ReResolveConfig {
+    /// The hostname of the target.
+    pub hostname: String,
+    /// The number of rounds between each re-resolution.
+    pub every: usize,
+    /// What to do when the address of the target changes.
+    pub strategy: ReResolveStrategy,
+    /// The configuration of the `DnsResolver` used to re-resolve the hostname.
+    pub resolver_config: DnsResolverConfig,
+    /// The hosts used by the `DnsResolver` used to re-resolve the hostname.
+    pub hosts: DnsHosts,
+}
+
+/// Trace in batches of `ReResolveConfig::every` rounds, re-resolving the hostname of the target after each batch.
+///
+/// The tracer channel, and so the raw sockets opened before privileges were dropped, is reused for every batch such
+/// that the address of the target can be changed without requiring privileges.
+///
+/// Each batch starts again from the first round and so the rounds of each batch are offset by the number of rounds
+/// of all previous batches before they are published, such that the rounds of the trace as a whole are contiguous.
+///
+/// Tracing ends, without re-resolving, once the `stop` flag is set.
+fn trace_re_resolving<F: Fn(&TracerRound<'_>)>(
+    tracer_config: &TracerConfig,
+    on_round: F,
+    mut channel: TracerChannel,
+    re_resolve: &ReResolveConfig,
+    resolver: &DnsResolver,
+    trace_data: &RwLock<Trace>,
+    stop: &Arc<AtomicBool>,
+) -> anyhow::Result<()> {
+    let mut target_addr = tracer_config.target_addr;
+    let mut resolved_addr = target_addr;
+    let mut batches = re_resolve_batches(tracer_config.max_rounds, re_resolve.every).peekable();
+    while let Some((round_offset, rounds)) = batches.next() {
+        let config = TracerConfig {
+            target_addr,
+            max_rounds: Some(MaxRounds(rounds - 1)),
+            ..*tracer_config
+        };
+        Tracer::new(&config, |round: &TracerRound<'_>| {
+            let probes = offset_rounds(round.probes, round_offset);
+            let late_probes = offset_rounds(round.late_probes, round_offset);
+            on_round(&TracerRound::new(
+                &probes,
+                &late_probes,
+                round.largest_ttl,
+                round.reason,
+                round.checksum_stats,
+            ));
+        })
+        .with_stop(stop.clone())
+        .trace(&mut channel)?;
+        if batches.peek().is_none() || stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let addrs = match resolver.lookup(&re_resolve.hostname) {
+            Ok(addrs) => addrs,
+            Err(err) => {
+                warn!(hostname = re_resolve.hostname, %err, "failed to re-resolve target");
+                continue;
+            }
+        };
+        let Some(addr) = select_target_addr(&addrs, resolved_addr) else {
+            warn!(
+                hostname = re_resolve.hostname,
+                "re-resolved target has no address of the traced family"
+            );
+            continue;
+        };
+        if addr != resolved_addr {
+            let followed = re_resolve.strategy == ReResolveStrategy::Follow;
+            warn!(hostname = re_resolve.hostname, from = %resolved_addr, to = %addr, followed, "target address changed");
+            trace_data
+                .write()
+                .update_target_addr(resolved_addr, addr, followed);
+            if followed {
+                channel.set_target_addr(addr)?;
+                target_addr = addr;
+            }
+            resolved_addr = addr;
+        }
+    }
+    Ok(())
+}
+
+/// The round offset and number of rounds of each batch of a trace of `max_rounds`, re-resolved `every` rounds.
+///
+/// Every batch is of `every` rounds other than the last batch of a trace with `max_rounds`, which is of the rounds
+/// remaining.  A trace without `max_rounds` has an unlimited number of batches.
+fn re_resolve_batches(
+    max_rounds: Option<MaxRounds>,
+    every: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    let total_rounds = max_rounds.map(|max_rounds| max_rounds.0 + 1);
+    let mut round_offset = 0;
+    iter::from_fn(move || {
+        let rounds = match total_rounds {
+            Some(total) if round_offset >= total => return None,
+            Some(total) => (total - round_offset).min(every),
+            None => every,
+        };
+        let batch = (round_offset, rounds);
+        round_offset += rounds;
+        Some(batch)
+    })
+}
+
+/// Offset the round of each of the `probes` by `round_offset` rounds.
+fn offset_rounds(probes: &[Probe], round_offset: usize) -> Vec<Probe> {
+    probes
+        .iter()
+        .map(|probe| Probe {
+            round: Round(probe.round.0 + round_offset),
+            ..*probe
+        })
+        .collect()
+}
+
+/// Select the address of the target from the re-resolved `addrs`, given the `current` address of the target.
+///
+/// The `current` address is retained if it is still amongst the addresses, such that a hostname which resolves to
+/// several addresses in a rotating order is not considered to have changed.  Otherwise, the first address of the
+/// same family as the `current` address is selected, if any.
+fn select_target_addr(addrs: &[IpAddr], current: IpAddr) -> Option<IpAddr> {
+    if addrs.contains(&current) {
+        Some(current)
+    } else {
+        addrs
+            .iter()
+            .find(|addr| addr.is_ipv4() == current.is_ipv4())
+            .copied()
+    }
+}
+
+/// The delay to add after a round for a target which has been unreachable for `unreachable_rounds` consecutive rounds.
+///
+/// The interval between rounds doubles for each consecutive round in which the target did not respond, starting from
+/// the second, until it reaches `max_backoff`.  Returns `None` if the target responded in the latest round or in the
+/// round before it.
+fn backoff_delay(
+    unreachable_rounds: usize,
+    min_round_duration: Duration,
+    max_backoff: Duration,
+) -> Option<Duration> {
+    if unreachable_rounds < 2 {
+        return None;
+    }
+    let max_delay = max_backoff.saturating_sub(min_round_duration);
+    let exponent = u32::try_from(unreachable_rounds - 1)
+        .unwrap_or(u32::MAX)
+        .min(16);
+    let factor = 2_u32.pow(exponent) - 1;
+    Some(
+        min_round_duration
+            .checked_mul(factor)
+            .map_or(max_delay, |delay| delay.min(max_delay)),
+    )
+}
+
+/// Lookup the AS path of the trace from the `AS` information of the last address to respond for each hop.
+///
+/// Consecutive hops in the same `AS` are collapsed and hops without `AS` information are skipped.  Returns `None` if
+/// the lookup of any hop is still pending, in which case the AS path is looked up again after the next round.
+fn lookup_as_path(trace: &Trace, resolver: &DnsResolver) -> Option<Vec<String>> {
+    let mut as_path: Vec<String> = vec![];
+    for addr in trace.hops().iter().filter_map(|hop| hop.last_addr) {
+        if addr.is_unspecified() {
+            continue;
+        }
+        let entry = resolver.reverse_lookup_with_asinfo(addr);
+        if matches!(entry, DnsEntry::Pending(_) | DnsEntry::Timeout(_)) {
+            return None;
+        }
+        if let Some(asn) = entry.asn() {
+            if as_path.last().map(String::as_str) != Some(asn) {
+                as_path.push(asn.to_string());
+            }
+        }
+    }
+    Some(as_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+    use test_case::test_case;
+
+    #[test]
+    fn test_stop_retained_by_clear() {
+        let mut trace = Trace::new(10, false, false, false);
+        let stop = trace.stop_flag();
+        trace.stop();
+        trace.clear();
+        assert!(trace.is_stopped());
+        assert!(stop.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_reset_time_updated_by_clear() {
+        let mut trace = Trace::new(10, false, false, false);
+        trace.reset_time = UNIX_EPOCH;
+        trace.clear();
+        assert!(trace.reset_time() > UNIX_EPOCH);
+    }
+
+    #[test_case(0, 1000, 60000, None; "reachable")]
+    #[test_case(1, 1000, 60000, None; "first unreachable round")]
+    #[test_case(2, 1000, 60000, Some(1000); "second unreachable round")]
+    #[test_case(3, 1000, 60000, Some(3000); "third unreachable round")]
+    #[test_case(6, 1000, 60000, Some(31000); "sixth unreachable round")]
+    #[test_case(7, 1000, 60000, Some(59000); "capped at max backoff")]
+    #[test_case(17, 1, 1_000_000, Some(65535); "exponent at 16")]
+    #[test_case(100, 1, 1_000_000, Some(65535); "exponent saturated at 16")]
+    #[test_case(usize::MAX, 1, 1_000_000, Some(65535); "exponent saturated at max rounds")]
+    #[test_case(5, 10000, 5000, Some(0); "max backoff below min round duration")]
+    #[test_case(5, 10000, 10000, Some(0); "max backoff equal to min round duration")]
+    fn test_backoff_delay(
+        unreachable_rounds: usize,
+        min_round_duration_ms: u64,
+        max_backoff_ms: u64,
+        expected_ms: Option<u64>,
+    ) {
+        assert_eq!(
+            expected_ms.map(Duration::from_millis),
+            backoff_delay(
+                unreachable_rounds,
+                Duration::from_millis(min_round_duration_ms),
+                Duration::from_millis(max_backoff_ms)
+            )
+        );
+    }
+
+    #[test]
+    fn test_backoff_delay_overflow() {
+        let min_round_duration = Duration::from_secs(u64::MAX / 2);
+        assert_eq!(
+            Duration::MAX.checked_sub(min_round_duration),
+            backoff_delay(3, min_round_duration, Duration::MAX)
+        );
+    }
+
+    #[test_case(&["192.0.2.1"], "192.0.2.1", Some("192.0.2.1"); "unchanged")]
+    #[test_case(&["192.0.2.2", "192.0.2.1"], "192.0.2.1", Some("192.0.2.1"); "rotated")]
+    #[test_case(&["192.0.2.2", "192.0.2.3"], "192.0.2.1", Some("192.0.2.2"); "changed")]
+    #[test_case(&["2001:db8::2", "192.0.2.2"], "192.0.2.1", Some("192.0.2.2"); "changed ipv4 skips ipv6")]
+    #[test_case(&["192.0.2.2", "2001:db8::2"], "2001:db8::1", Some("2001:db8::2"); "changed ipv6 skips ipv4")]
+    #[test_case(&["2001:db8::2"], "192.0.2.1", None; "no address of family")]
+    #[test_case(&[], "192.0.2.1", None; "no addresses")]
+    fn test_select_target_addr(addrs: &[&str], current: &str, expected: Option<&str>) {
+        let addrs = addrs
+            .iter()
+            .map(|addr| addr.parse().unwrap())
+            .collect::<Vec<IpAddr>>();
+        assert_eq!(
+            expected.map(|addr| addr.parse::<IpAddr>().unwrap()),
+            select_target_addr(&addrs, current.parse().unwrap())
+        );
+    }
+
+    #[test_case(Some(0), 5, &[(0, 1)]; "single round")]
+    #[test_case(Some(3), 5, &[(0, 4)]; "fewer rounds than a batch")]
+    #[test_case(Some(7), 4, &[(0, 4), (4, 4)]; "whole batches")]
+    #[test_case(Some(9), 4, &[(0, 4), (4, 4), (8, 2)]; "partial last batch")]
+    #[test_case(Some(4), 1, &[(0, 1), (1, 1), (2, 1), (3, 1), (4, 1)]; "batches of one round")]
+    fn test_re_resolve_batches(
+        max_rounds: Option<usize>,
+        every: usize,
+        expected: &[(usize, usize)],
+    ) {
+        let batches = re_resolve_batches(max_rounds.map(MaxRounds), every).collect::<Vec<_>>();
+        assert_eq!(expected, batches);
+    }
+
+    #[test]
+    fn test_re_resolve_batches_unlimited() {
+        let batches = re_resolve_batches(None, 3).take(4).collect::<Vec<_>>();
+        assert_eq!(vec![(0, 3), (3, 3), (6, 3), (9, 3)], batches);
+    }
+
+    #[test]
+    fn test_offset_rounds() {
+        let probes = [
+            Probe {
+                round: Round(0),
+                ..Probe::default()
+            },
+            Probe {
+                round: Round(2),
+                ..Probe::default()
+            },
+        ];
+        let rounds = offset_rounds(&probes, 10)
+            .iter()
+            .map(|probe| probe.round.0)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![10, 12], rounds);
+        assert_eq!(probes.to_vec(), offset_rounds(&probes, 0));
+    }
+}
@@ -0,0 +1,244 @@
+use crate::dns::{AsInfo, DnsEntry, DnsResolver, Resolved, Unresolved};
+use crate::geoip::{GeoIpCity, GeoIpLookup};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// A cache of reverse DNS, `AS` and `GeoIp` lookups which is persisted to a file between runs.
+///
+/// Entries older than the time-to-live are discarded when the cache is loaded.  Entries which were loaded keep the time
+/// they were looked up when the cache is saved, such that they expire rather than being renewed by every run.
+#[derive(Debug)]
+pub struct LookupCache {
+    path: PathBuf,
+    dns: HashMap<IpAddr, u64>,
+    geoip: HashMap<IpAddr, u64>,
+}
+
+impl LookupCache {
+    /// Load the cache file at `path` and add the entries which have not expired to the `resolver` and `geoip_lookup`.
+    ///
+    /// A missing cache file is treated as empty, as is a cache file which cannot be read, after logging a warning.
+    /// Reverse DNS entries without `AS` information are not loaded if `with_asinfo` is set.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        ttl: Duration,
+        with_asinfo: bool,
+        resolver: &DnsResolver,
+        geoip_lookup: &GeoIpLookup,
+    ) -> Self {
+        let path = path.as_ref();
+        let file = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str::<CacheFile>(&contents)
+                .map_err(|err| warn!(path = %path.display(), %err, "ignoring invalid cache file"))
+                .unwrap_or_default(),
+            Err(err) if err.kind() == ErrorKind::NotFound => CacheFile::default(),
+            Err(err) => {
+                warn!(path = %path.display(), %err, "ignoring unreadable cache file");
+                CacheFile::default()
+            }
+        };
+        let file = file.retain_fresh(now(), ttl, with_asinfo);
+        debug!(path = %path.display(), dns = file.dns.len(), geoip = file.geoip.len(), "loaded cache");
+        let dns = file.dns.iter().map(|r| (r.addr, r.created)).collect();
+        let geoip = file.geoip.iter().map(|r| (r.addr, r.created)).collect();
+        resolver.seed(file.dns.into_iter().map(DnsRecord::into_entry));
+        geoip_lookup.seed(file.geoip.into_iter().map(|r| (r.addr, r.geoip)));
+        Self {
+            path: path.to_path_buf(),
+            dns,
+            geoip,
+        }
+    }
+
+    /// Save the completed lookups of the `resolver` and `geoip_lookup` to the cache file.
+    ///
+    /// The cache file is replaced atomically, such that a concurrent run never reads a partially written file.
+    pub fn save(&self, resolver: &DnsResolver, geoip_lookup: &GeoIpLookup) -> anyhow::Result<()> {
+        let now = now();
+        let file = CacheFile {
+            dns: resolver
+                .entries()
+                .iter()
+                .filter_map(|entry| {
+                    let addr = entry.completed_addr()?;
+                    DnsRecord::from_entry(entry, self.dns.get(&addr).copied().unwrap_or(now))
+                })
+                .collect(),
+            geoip: geoip_lookup
+                .entries()
+                .into_iter()
+                .map(|(addr, geoip)| GeoIpRecord {
+                    addr,
+                    geoip: (*geoip).clone(),
+                    created: self.geoip.get(&addr).copied().unwrap_or(now),
+                })
+                .collect(),
+        };
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_string(&file)?)
+            .and_then(|()| fs::rename(&tmp_path, &self.path))
+            .with_context(|| format!("failed to save cache file: {}", self.path.display()))?;
+        debug!(path = %self.path.display(), dns = file.dns.len(), geoip = file.geoip.len(), "saved cache");
+        Ok(())
+    }
+}
+
+/// The contents of a cache file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CacheFile {
+    #[serde(default)]
+    dns: Vec<DnsRecord>,
+    #[serde(default)]
+    geoip: Vec<GeoIpRecord>,
+}
+
+impl CacheFile {
+    /// Discard the entries which are older than `ttl` at `now`, and those without `AS` information if `with_asinfo`.
+    fn retain_fresh(mut self, now: u64, ttl: Duration, with_asinfo: bool) -> Self {
+        let is_fresh = |created: u64| now.saturating_sub(created) < ttl.as_secs();
+        self.dns
+            .retain(|r| is_fresh(r.created) && (r.as_info.is_some() || !with_asinfo));
+        self.geoip.retain(|r| is_fresh(r.created));
+        self
+    }
+}
+
+/// A completed reverse DNS lookup.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DnsRecord {
+    addr: IpAddr,
+    /// The hostnames of the address, empty if none were found.
+    #[serde(default)]
+    hostnames: Vec<String>,
+    as_info: Option<AsInfo>,
+    /// The time of the lookup, in seconds since the Unix epoch.
+    created: u64,
+}
+
+impl DnsRecord {
+    /// Create a record from a `DnsEntry`, if it is resolved or unresolved.
+    fn from_entry(entry: &DnsEntry, created: u64) -> Option<Self> {
+        let (addr, hostnames, as_info) = match entry {
+            DnsEntry::Resolved(Resolved::Normal(addr, hostnames)) => (*addr, hostnames, None),
+            DnsEntry::Resolved(Resolved::WithAsInfo(addr, hostnames, as_info)) => {
+                (*addr, hostnames, Some(as_info))
+            }
+            DnsEntry::NotFound(Unresolved::Normal(addr)) => (*addr, &vec![], None),
+            DnsEntry::NotFound(Unresolved::WithAsInfo(addr, as_info)) => {
+                (*addr, &vec![], Some(as_info))
+            }
+            DnsEntry::Pending(_) | DnsEntry::Failed(_) | DnsEntry::Timeout(_) => return None,
+        };
+        Some(Self {
+            addr,
+            hostnames: hostnames.clone(),
+            as_info: as_info.cloned(),
+            created,
+        })
+    }
+
+    fn into_entry(self) -> DnsEntry {
+        match (self.hostnames.is_empty(), self.as_info) {
+            (false, None) => DnsEntry::Resolved(Resolved::Normal(self.addr, self.hostnames)),
+            (false, Some(as_info)) => {
+                DnsEntry::Resolved(Resolved::WithAsInfo(self.addr, self.hostnames, as_info))
+            }
+            (true, None) => DnsEntry::NotFound(Unresolved::Normal(self.addr)),
+            (true, Some(as_info)) => DnsEntry::NotFound(Unresolved::WithAsInfo(self.addr, as_info)),
+        }
+    }
+}
+
+/// A completed `GeoIp` lookup.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct GeoIpRecord {
+    addr: IpAddr,
+    geoip: GeoIpCity,
+    /// The time of the lookup, in seconds since the Unix epoch.
+    created: u64,
+}
+
+/// The current time, in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn as_info() -> AsInfo {
+        AsInfo {
+            asn: String::from("64496"),
+            name: String::from("EXAMPLE"),
+            ..AsInfo::default()
+        }
+    }
+
+    #[test_case(&DnsEntry::Resolved(Resolved::Normal(IpAddr::from_str("192.0.2.1").unwrap(), vec![String::from("host.example")])); "resolved")]
+    #[test_case(&DnsEntry::Resolved(Resolved::WithAsInfo(IpAddr::from_str("192.0.2.1").unwrap(), vec![String::from("host.example")], as_info())); "resolved with as info")]
+    #[test_case(&DnsEntry::NotFound(Unresolved::Normal(IpAddr::from_str("2001:db8::1").unwrap())); "not found")]
+    #[test_case(&DnsEntry::NotFound(Unresolved::WithAsInfo(IpAddr::from_str("2001:db8::1").unwrap(), as_info())); "not found with as info")]
+    fn test_dns_record_round_trip(entry: &DnsEntry) -> anyhow::Result<()> {
+        let record = DnsRecord::from_entry(entry, 1).unwrap();
+        let record = serde_json::from_str::<DnsRecord>(&serde_json::to_string(&record)?)?;
+        assert_eq!(entry.to_string(), record.into_entry().to_string());
+        Ok(())
+    }
+
+    #[test_case(&DnsEntry::Pending(IpAddr::from_str("192.0.2.1").unwrap()); "pending")]
+    #[test_case(&DnsEntry::Failed(IpAddr::from_str("192.0.2.1").unwrap()); "failed")]
+    #[test_case(&DnsEntry::Timeout(IpAddr::from_str("192.0.2.1").unwrap()); "timeout")]
+    fn test_dns_record_incomplete(entry: &DnsEntry) {
+        assert!(DnsRecord::from_entry(entry, 1).is_none());
+    }
+
+    #[test_case(false, &["192.0.2.1", "192.0.2.2"], &["192.0.2.1"]; "without as info")]
+    #[test_case(true, &["192.0.2.2"], &["192.0.2.1"]; "with as info")]
+    fn test_retain_fresh(with_asinfo: bool, dns: &[&str], geoip: &[&str]) -> anyhow::Result<()> {
+        let file = serde_json::from_str::<CacheFile>(
+            r#"{
+                "dns": [
+                    {"addr": "192.0.2.1", "hostnames": ["a.example"], "created": 950},
+                    {"addr": "192.0.2.2", "as-info": {"asn": "64496", "prefix": "", "cc": "", "registry": "", "allocated": "", "name": ""}, "created": 901},
+                    {"addr": "192.0.2.3", "hostnames": ["c.example"], "created": 900}
+                ],
+                "geoip": [
+                    {"addr": "192.0.2.1", "geoip": {"city": "Paris"}, "created": 999},
+                    {"addr": "192.0.2.2", "geoip": {"city": "London"}, "created": 100}
+                ]
+            }"#,
+        )?;
+        let file = file.retain_fresh(1000, Duration::from_secs(100), with_asinfo);
+        assert_eq!(
+            dns,
+            file.dns
+                .iter()
+                .map(|r| r.addr.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            geoip,
+            file.geoip
+                .iter()
+                .map(|r| r.addr.to_string())
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
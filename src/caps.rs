@@ -1,15 +1,72 @@
+use trippy::tracing::{PrivilegeMode, TracerAddrFamily, TracerChannel, TracerProtocol};
+
+/// The documentation of the privileges required by Trippy.
+const PRIVILEGES_URL: &str = "https://github.com/fujiapple852/trippy#privileges";
+
+/// Fall back to unprivileged mode if it is permitted for the protocol and address family.
+///
+/// Otherwise the `reason` raw sockets cannot be used and the `remedies` are printed and the process exits.  The
+/// `unprivileged_remedy`, if any, describes how to permit unprivileged mode and is only printed if it is not permitted.
+fn fallback(
+    protocol: TracerProtocol,
+    addr_family: TracerAddrFamily,
+    reason: &str,
+    remedies: &[String],
+    unprivileged_remedy: Option<&str>,
+) -> PrivilegeMode {
+    let unprivileged = TracerChannel::is_unprivileged_permitted(addr_family);
+    if unprivileged && matches!(protocol, TracerProtocol::Icmp) {
+        tracing::warn!("{reason}, falling back to unprivileged mode");
+        return PrivilegeMode::Unprivileged;
+    }
+    eprintln!("{reason}, either:");
+    for remedy in remedies {
+        eprintln!("  - {remedy}");
+    }
+    if unprivileged {
+        eprintln!("  - use the icmp protocol, which is supported in unprivileged mode:\n      trip --protocol icmp ...");
+    } else if let Some(remedy) = unprivileged_remedy {
+        eprintln!("  - {remedy}");
+    }
+    eprintln!("see {PRIVILEGES_URL}");
+    std::process::exit(-1);
+}
+
+/// The path of the running executable, for use in remediation instructions.
+#[cfg(all(unix, not(target_os = "android")))]
+fn current_exe() -> String {
+    std::env::current_exe().map_or_else(
+        |_| String::from("$(which trip)"),
+        |path| path.display().to_string(),
+    )
+}
+
 // Linux
 
 #[cfg(target_os = "linux")]
 /// Check if `CAP_NET_RAW` is in the permitted set and if so raise it to the effective set.
-pub fn ensure_caps() -> anyhow::Result<()> {
+///
+/// If `CAP_NET_RAW` is not permitted then fall back to unprivileged mode if possible.
+pub fn ensure_caps(
+    protocol: TracerProtocol,
+    addr_family: TracerAddrFamily,
+) -> anyhow::Result<PrivilegeMode> {
     if caps::has_cap(None, caps::CapSet::Permitted, caps::Capability::CAP_NET_RAW)? {
         caps::raise(None, caps::CapSet::Effective, caps::Capability::CAP_NET_RAW)?;
+        Ok(PrivilegeMode::Privileged)
     } else {
-        eprintln!("capability CAP_NET_RAW is required, see https://github.com/fujiapple852/trippy#privileges");
-        std::process::exit(-1);
+        let exe = current_exe();
+        Ok(fallback(
+            protocol,
+            addr_family,
+            "capability CAP_NET_RAW is required to use raw sockets",
+            &[
+                "run as root:\n      sudo trip ...".to_string(),
+                format!("set the CAP_NET_RAW capability:\n      sudo setcap CAP_NET_RAW+p {exe}"),
+            ],
+            Some("permit unprivileged ICMP sockets and use the icmp protocol:\n      sudo sysctl -w net.ipv4.ping_group_range=\"0 2147483647\""),
+        ))
     }
-    Ok(())
 }
 
 #[cfg(target_os = "linux")]
@@ -19,17 +76,52 @@ pub fn drop_caps() -> anyhow::Result<()> {
     Ok(())
 }
 
+// Android
+
+#[cfg(target_os = "android")]
+#[allow(clippy::unnecessary_wraps)]
+/// Ensure the effective user is `root`, otherwise fall back to unprivileged mode, which Android permits by default.
+pub fn ensure_caps(
+    protocol: TracerProtocol,
+    addr_family: TracerAddrFamily,
+) -> anyhow::Result<PrivilegeMode> {
+    if nix::unistd::Uid::effective().is_root() {
+        Ok(PrivilegeMode::Privileged)
+    } else {
+        Ok(fallback(
+            protocol,
+            addr_family,
+            "root user required to use raw sockets",
+            &["run as root on a rooted device:\n      su -c trip ...".to_string()],
+            Some("permit unprivileged ICMP sockets and use the icmp protocol:\n      su -c sysctl -w net.ipv4.ping_group_range=\"0 2147483647\""),
+        ))
+    }
+}
+
 // macOS, BSD etc
 
-#[cfg(all(unix, not(target_os = "linux")))]
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
 #[allow(clippy::unnecessary_wraps)]
-/// Ensure the effective user is `root`.
-pub fn ensure_caps() -> anyhow::Result<()> {
-    if !nix::unistd::Uid::effective().is_root() {
-        eprintln!("root user required to use raw sockets, see https://github.com/fujiapple852/trippy#privileges");
-        std::process::exit(-1);
+/// Ensure the effective user is `root`, otherwise fall back to unprivileged mode if possible.
+pub fn ensure_caps(
+    protocol: TracerProtocol,
+    addr_family: TracerAddrFamily,
+) -> anyhow::Result<PrivilegeMode> {
+    if nix::unistd::Uid::effective().is_root() {
+        Ok(PrivilegeMode::Privileged)
+    } else {
+        let exe = current_exe();
+        Ok(fallback(
+            protocol,
+            addr_family,
+            "root user required to use raw sockets",
+            &[
+                "run as root:\n      sudo trip ...".to_string(),
+                format!("set the setuid bit:\n      sudo chown root {exe} && sudo chmod +s {exe}"),
+            ],
+            None,
+        ))
     }
-    Ok(())
 }
 
 #[cfg(all(unix, not(target_os = "linux")))]
@@ -41,12 +133,55 @@ pub fn drop_caps() -> anyhow::Result<()> {
     Ok(())
 }
 
+// Unix
+
+#[cfg(unix)]
+/// Switch from `root` to an unprivileged user.
+///
+/// The user is `uid` if given, otherwise the user who invoked `sudo` as given by `SUDO_UID`, otherwise the real user
+/// if the executable is `setuid`.  The group is the primary group of the user, or `SUDO_GID` if the user is unknown.
+///
+/// This is a no-op if the effective user is not `root` or if there is no unprivileged user to switch to.  It must
+/// only be called after all raw sockets have been created.
+pub fn drop_privileges(uid: Option<u32>) -> anyhow::Result<()> {
+    use nix::unistd::{setgid, setuid, Gid, Uid, User};
+    use tracing::debug;
+    if !Uid::effective().is_root() {
+        return Ok(());
+    }
+    if let Some(uid) = uid
+        .or_else(|| env_id("SUDO_UID"))
+        .map(Uid::from_raw)
+        .or_else(|| Some(Uid::current()).filter(|uid| !uid.is_root()))
+    {
+        let gid = User::from_uid(uid)?
+            .map(|user| user.gid)
+            .or_else(|| env_id("SUDO_GID").map(Gid::from_raw))
+            .ok_or_else(|| anyhow::anyhow!("unknown user id {uid}"))?;
+        #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+        nix::unistd::setgroups(&[gid])?;
+        setgid(gid)?;
+        setuid(uid)?;
+        debug!(%uid, %gid, "dropped privileges");
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+/// Read a numeric user or group id from an environment variable.
+fn env_id(name: &str) -> Option<u32> {
+    std::env::var(name).ok().and_then(|id| id.parse().ok())
+}
+
 // Windows
 
 #[cfg(windows)]
 #[allow(clippy::unnecessary_wraps)]
-/// Ensure the effective user is `root`.
-pub fn ensure_caps() -> anyhow::Result<()> {
+/// Ensure the process is elevated, otherwise fall back to unprivileged mode if possible.
+pub fn ensure_caps(
+    protocol: TracerProtocol,
+    addr_family: TracerAddrFamily,
+) -> anyhow::Result<PrivilegeMode> {
     macro_rules! syscall {
         ($p: path, $fn: ident ( $($arg: expr),* $(,)* ) ) => {{
             #[allow(unsafe_code)]
@@ -109,11 +244,17 @@ pub fn ensure_caps() -> anyhow::Result<()> {
         }
     }
 
-    if !Privileged::current_process()?.is_elevated()? {
-        eprintln!("administrator capability is required, see https://github.com/fujiapple852/trippy#privileges");
-        std::process::exit(-1);
+    if Privileged::current_process()?.is_elevated()? {
+        Ok(PrivilegeMode::Privileged)
+    } else {
+        Ok(fallback(
+            protocol,
+            addr_family,
+            "administrator capability is required to use raw sockets",
+            &["run trip from an elevated terminal (\"Run as administrator\")".to_string()],
+            None,
+        ))
     }
-    Ok(())
 }
 
 #[cfg(windows)]
@@ -124,3 +265,12 @@ pub fn ensure_caps() -> anyhow::Result<()> {
 pub fn drop_caps() -> anyhow::Result<()> {
     Ok(())
 }
+
+#[cfg(windows)]
+#[allow(clippy::unnecessary_wraps)]
+/// Switch from an elevated to an unprivileged user.
+///
+/// This is a no-op on Windows.
+pub fn drop_privileges(_uid: Option<u32>) -> anyhow::Result<()> {
+    Ok(())
+}
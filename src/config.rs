@@ -1,25 +1,35 @@
-use crate::config::config_file::{ConfigBindings, ConfigFile, ConfigThemeColors};
+use crate::config::config_file::{
+    ConfigBindings, ConfigDns, ConfigExport, ConfigFile, ConfigHooks, ConfigReport, ConfigStrategy,
+    ConfigThemeColors, ConfigTrippy, ConfigTui,
+};
 use crate::config::TuiCommandItem::{
-    AddressModeBoth, AddressModeHost, AddressModeIp, ChartZoomIn, ChartZoomOut, ClearDnsCache,
-    ClearSelection, ClearTraceData, ContractHosts, ContractHostsMin, ExpandHosts, ExpandHostsMax,
-    NextHop, NextHopAddress, NextTrace, PreviousHop, PreviousHopAddress, PreviousTrace, Quit,
-    ToggleASInfo, ToggleChart, ToggleFreeze, ToggleHelp, ToggleHopDetails, ToggleMap,
-    ToggleSettings,
+    AddressModeBoth, AddressModeHost, AddressModeIp, ChartZoomIn, ChartZoomOut, ClearAllTraceData,
+    ClearDnsCache, ClearSelection, ClearTraceData, ContractHosts, ContractHostsMin, ExpandHosts,
+    ExpandHostsMax, ExportReport, NextHop, NextHopAddress, NextTrace, PreviousHop,
+    PreviousHopAddress, PreviousTrace, Quit, ToggleASInfo, ToggleChart, ToggleCompare,
+    ToggleDiagnostics, ToggleDns, ToggleFreeze, ToggleHelp, ToggleHideHops, ToggleHopDetails,
+    ToggleMap, ToggleOverview, TogglePanes, ToggleRouteChanges, ToggleSettings,
 };
+use crate::session::Session;
+use crate::template::Template;
 use anyhow::anyhow;
+use chrono::format::{Item, StrftimeItems};
 use clap::{Command, CommandFactory, Parser, ValueEnum};
 use clap_complete::{generate, Generator, Shell};
 use crossterm::event::{KeyCode, KeyModifiers};
 use itertools::Itertools;
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Display, Formatter};
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::process;
 use std::str::FromStr;
 use std::time::Duration;
 use strum::{AsRefStr, EnumString, EnumVariantNames, VariantNames};
-use trippy::tracing::{MultipathStrategy, PortDirection, TracerAddrFamily, TracerProtocol};
+use trippy::tracing::{
+    Interface, Ipv6ExtHeader, Ipv6SourcePreference, MultipathStrategy, PortDirection,
+    ProhibitedStrategy, SequenceStrategy, TracerAddrFamily, TracerProtocol,
+};
 
 /// The maximum number of hops we allow.
 ///
@@ -47,6 +57,24 @@ const MAX_GRACE_DURATION_MS: Duration = Duration::from_millis(1000);
 /// The default value for `mode`.
 const DEFAULT_MODE: Mode = Mode::Tui;
 
+/// The default value for `rtt-unit`.
+const DEFAULT_RTT_UNIT: RttUnit = RttUnit::Ms;
+
+/// The default value for `rtt-precision`.
+const DEFAULT_RTT_PRECISION: u8 = 1;
+
+/// The default value for `locale`.
+const DEFAULT_LOCALE: Locale = Locale::En;
+
+/// The default value for `exit-summary`.
+const DEFAULT_EXIT_SUMMARY: bool = true;
+
+/// The default value for `agent-insecure`.
+const DEFAULT_AGENT_INSECURE: bool = false;
+
+/// The default value for `re-resolve-strategy`.
+const DEFAULT_RE_RESOLVE_STRATEGY: ReResolveStrategy = ReResolveStrategy::Follow;
+
 /// The default value for `protocol`.
 const DEFAULT_STRATEGY_PROTOCOL: Protocol = Protocol::Icmp;
 
@@ -56,9 +84,16 @@ const DEFAULT_STRATEGY_MIN_ROUND_DURATION: &str = "1s";
 /// The default value for `max-round-duration`.
 const DEFAULT_STRATEGY_MAX_ROUND_DURATION: &str = "1s";
 
+/// The default value for `round-jitter`.
+const DEFAULT_STRATEGY_ROUND_JITTER: &str = "0s";
+
 /// The default value for `initial-sequence`.
 const DEFAULT_STRATEGY_INITIAL_SEQUENCE: u16 = 33000;
 
+/// The default value for `sequence-strategy`.
+const DEFAULT_STRATEGY_SEQUENCE_STRATEGY: SequenceStrategyConfig =
+    SequenceStrategyConfig::Sequential;
+
 /// The default value for `multipath-strategy`.
 const DEFAULT_STRATEGY_MULTIPATH: MultipathStrategyConfig = MultipathStrategyConfig::Classic;
 
@@ -68,15 +103,51 @@ const DEFAULT_STRATEGY_GRACE_DURATION: &str = "100ms";
 /// The default value for `max-inflight`.
 const DEFAULT_STRATEGY_MAX_INFLIGHT: u8 = 24;
 
+/// The default value for `burst`.
+const DEFAULT_STRATEGY_BURST: bool = false;
+
+/// The default value for `record-route`.
+const DEFAULT_STRATEGY_RECORD_ROUTE: bool = false;
+
+/// The default value for `icmp-timestamp`.
+const DEFAULT_STRATEGY_ICMP_TIMESTAMP: bool = false;
+
+/// The default value for `adaptive-inflight`.
+const DEFAULT_STRATEGY_ADAPTIVE_INFLIGHT: bool = false;
+
 /// The default value for `first-ttl`.
 const DEFAULT_STRATEGY_FIRST_TTL: u8 = 1;
 
 /// The default value for `max-ttl`.
 const DEFAULT_STRATEGY_MAX_TTL: u8 = 64;
 
+/// The default value for `destination-only`.
+const DEFAULT_STRATEGY_DESTINATION_ONLY: bool = false;
+
+/// The default value for `adaptive-ttl`.
+const DEFAULT_STRATEGY_ADAPTIVE_TTL: bool = false;
+
+/// The default value for `prohibited-strategy`.
+const DEFAULT_STRATEGY_PROHIBITED: ProhibitedStrategyConfig = ProhibitedStrategyConfig::Stop;
+
+/// The default value for `retries`.
+const DEFAULT_STRATEGY_RETRIES: u8 = 0;
+
+/// The default value for `backfill-late`.
+const DEFAULT_STRATEGY_BACKFILL_LATE: bool = false;
+
 /// The default value for `packet-size`.
 const DEFAULT_STRATEGY_PACKET_SIZE: u16 = 84;
 
+/// The default value for `sweep-range`.
+const DEFAULT_STRATEGY_SWEEP_RANGE: &str = "84-1024";
+
+/// The default value for `sweep-steps`.
+const DEFAULT_STRATEGY_SWEEP_STEPS: u8 = 5;
+
+/// The maximum value for `sweep-steps`.
+const MAX_SWEEP_STEPS: u8 = 16;
+
 /// The default value for `payload-pattern`.
 const DEFAULT_STRATEGY_PAYLOAD_PATTERN: u8 = 0;
 
@@ -89,9 +160,27 @@ const DEFAULT_STRATEGY_READ_TIMEOUT: &str = "10ms";
 /// The default value for `tui-max-samples`.
 const DEFAULT_TUI_MAX_SAMPLES: usize = 256;
 
+/// The default value for `tui-export-format`.
+const DEFAULT_TUI_EXPORT_FORMAT: TuiExportFormat = TuiExportFormat::Json;
+
+/// The default value for `tui-export-dir`.
+const DEFAULT_TUI_EXPORT_DIR: &str = ".";
+
 /// The default value for `tui-preserve-screen`.
 const DEFAULT_TUI_PRESERVE_SCREEN: bool = false;
 
+/// The default value for `tui-title`.
+const DEFAULT_TUI_TITLE: bool = false;
+
+/// The default value for `tui-seen-columns`.
+const DEFAULT_TUI_SEEN_COLUMNS: bool = false;
+
+/// The default value for `tui-ascii`.
+const DEFAULT_TUI_ASCII: bool = false;
+
+/// The default value for `tui-palette`.
+const DEFAULT_TUI_PALETTE: TuiPalette = TuiPalette::Default;
+
 /// The default value for `tui-as-mode`.
 const DEFAULT_TUI_AS_MODE: AsMode = AsMode::Asn;
 
@@ -101,6 +190,12 @@ const DEFAULT_TUI_GEOIP_MODE: GeoIpMode = GeoIpMode::Off;
 /// The default value for `tui-address-mode`.
 const DEFAULT_TUI_ADDRESS_MODE: AddressMode = AddressMode::Host;
 
+/// The default value for `tui-panes`.
+const DEFAULT_TUI_PANES: [TuiPane; 2] = [TuiPane::Chart, TuiPane::RouteChanges];
+
+/// The default value for `tui-pane-split`.
+const DEFAULT_TUI_PANE_SPLIT: u16 = 60;
+
 /// The default value for `tui-refresh-rate`.
 const DEFAULT_TUI_REFRESH_RATE: &str = "100ms";
 
@@ -113,17 +208,111 @@ const DEFAULT_DNS_LOOKUP_AS_INFO: bool = false;
 /// The default value for `dns-timeout`.
 const DEFAULT_DNS_TIMEOUT: &str = "5s";
 
+/// The default value for `dns-max-in-flight`.
+const DEFAULT_DNS_MAX_IN_FLIGHT: usize = 4;
+
+/// The maximum value for `dns-max-in-flight`.
+const MAX_DNS_MAX_IN_FLIGHT: usize = 64;
+
+/// The default value for `cache-ttl`.
+const DEFAULT_CACHE_TTL: &str = "24h";
+
 /// The default value for `report-cycles`.
 const DEFAULT_REPORT_CYCLES: usize = 10;
 
+/// The default value for `verbose`.
+const DEFAULT_VERBOSE: u8 = 0;
+
+/// The default value for `packet-dump`.
+const DEFAULT_PACKET_DUMP: bool = false;
+
+/// The default value for `report-timestamp`.
+const DEFAULT_REPORT_TIMESTAMP: TimestampMode = TimestampMode::Off;
+
+/// The default value for `report-timestamp-format`.
+const DEFAULT_REPORT_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
+
+/// The default value for `report-overflow`.
+const DEFAULT_REPORT_OVERFLOW: ReportOverflow = ReportOverflow::Wrap;
+
+/// The default value for `report-include-samples`.
+const DEFAULT_REPORT_INCLUDE_SAMPLES: bool = false;
+
+/// The default value for `report-per-address`.
+const DEFAULT_REPORT_PER_ADDRESS: bool = false;
+
+/// The minimum value for `report-width`.
+const MIN_REPORT_WIDTH: u16 = 40;
+
+/// The default value for `report-columns`.
+const DEFAULT_REPORT_COLUMNS: [ReportColumn; 8] = [
+    ReportColumn::LossPct,
+    ReportColumn::Sent,
+    ReportColumn::Recv,
+    ReportColumn::Last,
+    ReportColumn::Avg,
+    ReportColumn::Best,
+    ReportColumn::Worst,
+    ReportColumn::Stddev,
+];
+
+/// The default value for `otlp-interval`.
+const DEFAULT_OTLP_INTERVAL: &str = "10s";
+
+/// The default port of an `otlp-endpoint`.
+const DEFAULT_OTLP_PORT: u16 = 4318;
+
+/// The default path of an `otlp-endpoint`.
+const DEFAULT_OTLP_PATH: &str = "/v1/metrics";
+
+/// The default value for `statsd-flavor`.
+const DEFAULT_STATSD_FLAVOR: StatsdFlavor = StatsdFlavor::Statsd;
+
+/// The default value for `statsd-prefix`.
+const DEFAULT_STATSD_PREFIX: &str = "trippy";
+
+/// The default value for `mqtt-topic`.
+const DEFAULT_MQTT_TOPIC: &str = "trippy";
+
+/// The default port of a remote `syslog` server.
+const DEFAULT_SYSLOG_PORT: u16 = 514;
+
+/// The default value for `syslog-facility`.
+const DEFAULT_SYSLOG_FACILITY: SyslogFacility = SyslogFacility::User;
+
 /// The minimum packet size we allow.
 const MIN_PACKET_SIZE: u16 = 28;
 
 /// The maximum packet size we allow.
 const MAX_PACKET_SIZE: u16 = 1024;
 
+/// The maximum number of host bits of a CIDR target we allow.
+///
+/// Every host of a CIDR target is traced concurrently and so this limits a CIDR target to at most 256 traces.
+const MAX_CIDR_HOST_BITS: u8 = 8;
+
+/// The maximum number of ports of `target-ports` we allow.
+///
+/// Every port of each target is traced concurrently and so this limits the number of traces per target.
+const MAX_TARGET_PORTS: usize = 64;
+
+/// The port to firewalk if neither `target-ports` nor `target-port` are given.
+const DEFAULT_FIREWALK_PORT: u16 = 80;
+
+/// The maximum number of decimal places of round-trip times we allow.
+const MAX_RTT_PRECISION: u8 = 3;
+
+/// The minimum TCP maximum segment size we allow.
+const MIN_TCP_MSS: u16 = 88;
+
+/// The minimum percentage of the multi-pane layout given to the hop table we allow.
+const MIN_TUI_PANE_SPLIT: u16 = 10;
+
+/// The maximum percentage of the multi-pane layout given to the hop table we allow.
+const MAX_TUI_PANE_SPLIT: u16 = 90;
+
 /// The tool mode.
-#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum Mode {
     /// Display interactive TUI.
@@ -136,12 +325,26 @@ pub enum Mode {
     Markdown,
     /// Generate a SCV report for N cycles.
     Csv,
+    /// Display a continuous stream of CSV tracing data.
+    CsvStream,
     /// Generate a JSON report for N cycles.
     Json,
+    /// Generate a report for N cycles rendered from a template of the JSON report data.
+    Template,
+    /// Generate a table report of how far the probes of each target (and port) reached for N cycles.
+    Ports,
+    /// Generate a matrix of the protocols and ports forwarded by a gateway hop for N cycles.
+    Firewalk,
+    /// Generate a table of the hops which forward probes with an IPv6 extension header for N cycles.
+    ExtHeader,
+    /// Generate a table of the DSCP of the probes as received by each hop for N cycles.
+    Qos,
+    /// Generate a table of the loss and latency of each hop for a range of probe sizes for N cycles.
+    SizeSweep,
 }
 
 /// The tracing protocol.
-#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum Protocol {
     /// Internet Control Message Protocol
@@ -153,17 +356,19 @@ pub enum Protocol {
 }
 
 /// The address family.
-#[derive(Debug, Copy, Clone, Deserialize)]
+#[derive(Debug, Copy, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum AddressFamily {
     /// Internet Protocol V4
     Ipv4,
     /// Internet Protocol V6
     Ipv6,
+    /// Both Internet Protocol V4 and V6, tracing each target with each
+    DualStack,
 }
 
 /// The strategy Equal-cost Multi-Path routing strategy.
-#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum MultipathStrategyConfig {
     /// The src or dest port is used to store the sequence number.
@@ -174,8 +379,48 @@ pub enum MultipathStrategyConfig {
     Dublin,
 }
 
+/// The preferred kind of `IPv6` source address.
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ipv6SourcePreferenceConfig {
+    /// Prefer temporary (privacy) addresses.
+    Temporary,
+    /// Prefer stable (public) addresses.
+    Stable,
+}
+
+/// How to handle a hop which responds that communication with the target is administratively prohibited.
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProhibitedStrategyConfig {
+    /// Stop probing beyond the hop, as for any other unreachable response.
+    Stop,
+    /// Continue probing beyond the hop.
+    Continue,
+}
+
+/// How the sequence number of the first probe of every round is chosen.
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SequenceStrategyConfig {
+    /// Continue from the sequence number following the previous round.
+    Sequential,
+    /// Start from a random sequence number.
+    Random,
+}
+
+/// The `IPv6` extension header to include in probes.
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ipv6ExtHeaderConfig {
+    /// A destination options header.
+    DstOpts,
+    /// A hop-by-hop options header.
+    HopByHop,
+}
+
 /// How to render the addresses.
-#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum AddressMode {
     /// Show IP address only.
@@ -187,7 +432,7 @@ pub enum AddressMode {
 }
 
 /// How to render AS information.
-#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum AsMode {
     /// Show the ASN.
@@ -207,7 +452,7 @@ pub enum AsMode {
 /// How to render `GeoIp` information in the hop table.
 ///
 /// Note that the hop details view is always shown using the `Long` representation.
-#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum GeoIpMode {
     /// Do not display GeoIp data.
@@ -232,8 +477,299 @@ pub enum GeoIpMode {
     Location,
 }
 
+/// The palette of colors used to signal the status of hops and the `AS` groups in the Tui.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TuiPalette {
+    /// The default palette, where the status of hops is shown as colored emoji.
+    Default,
+    /// A palette safe for deuteranopia (red-green color blindness).
+    Deuteranopia,
+    /// A palette safe for protanopia (red-green color blindness).
+    Protanopia,
+    /// A palette safe for tritanopia (blue-yellow color blindness).
+    Tritanopia,
+}
+
+/// The unit in which round-trip times are displayed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RttUnit {
+    /// Milliseconds.
+    Ms,
+    /// Microseconds.
+    Us,
+}
+
+/// The language of the labels of the Tui and the headers of reports.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Locale {
+    /// English.
+    En,
+    /// German.
+    De,
+    /// Spanish.
+    Es,
+    /// French.
+    Fr,
+}
+
+impl Display for RttUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ms => write!(f, "ms"),
+            Self::Us => write!(f, "µs"),
+        }
+    }
+}
+
+/// The unit and number of decimal places with which round-trip times are displayed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RttFormat {
+    pub unit: RttUnit,
+    pub precision: u8,
+}
+
+impl RttFormat {
+    pub fn new(unit: RttUnit, precision: u8) -> Self {
+        Self { unit, precision }
+    }
+
+    /// Convert a round-trip time in milliseconds to the unit.
+    pub fn scale(self, rtt_ms: f64) -> f64 {
+        match self.unit {
+            RttUnit::Ms => rtt_ms,
+            RttUnit::Us => rtt_ms * 1000_f64,
+        }
+    }
+
+    /// Format a round-trip time in milliseconds in the unit, without the unit.
+    pub fn format(self, rtt_ms: f64) -> String {
+        format!("{:.*}", usize::from(self.precision), self.scale(rtt_ms))
+    }
+
+    /// Format a round-trip time in milliseconds in the unit, followed by the unit.
+    pub fn format_with_unit(self, rtt_ms: f64) -> String {
+        format!("{}{}", self.format(rtt_ms), self.unit)
+    }
+}
+
+/// The format to print the effective configuration in.
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum ConfigFormat {
+    /// Print as TOML.
+    Toml,
+    /// Print as JSON.
+    Json,
+}
+
+/// A pane which is shown alongside the hop table in the multi-pane layout.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TuiPane {
+    /// The chart of the samples of each hop.
+    Chart,
+    /// The route changes observed during the trace.
+    RouteChanges,
+    /// The world map.
+    Map,
+    /// The DNS lookups.
+    Dns,
+    /// The diagnostics.
+    Diagnostics,
+}
+
+/// The format of the reports exported from the Tui.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TuiExportFormat {
+    /// A JSON report.
+    Json,
+    /// A markdown table report.
+    Markdown,
+    /// A CSV report.
+    Csv,
+}
+
+/// The ECN codepoint to set on probes for ECN path validation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EcnCodepoint {
+    /// Not ECN-Capable Transport (`Not-ECT`).
+    NotEct = 0b00,
+    /// ECN-Capable Transport (`ECT(1)`).
+    Ect1 = 0b01,
+    /// ECN-Capable Transport (`ECT(0)`).
+    Ect0 = 0b10,
+    /// Congestion Experienced (`CE`).
+    Ce = 0b11,
+}
+
+impl EcnCodepoint {
+    /// The ECN codepoint for the two least significant bits of a TOS (i.e. DSCP+ECN) value.
+    pub const fn from_tos(tos: u8) -> Self {
+        match tos & 0x3 {
+            0 => Self::NotEct,
+            1 => Self::Ect1,
+            2 => Self::Ect0,
+            _ => Self::Ce,
+        }
+    }
+
+    /// Replace the ECN bits of a TOS (i.e. DSCP+ECN) value with this codepoint.
+    pub const fn apply_to(self, tos: u8) -> u8 {
+        (tos & 0xfc) | self as u8
+    }
+}
+
+impl Display for EcnCodepoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotEct => write!(f, "not-ect"),
+            Self::Ect1 => write!(f, "ect1"),
+            Self::Ect0 => write!(f, "ect0"),
+            Self::Ce => write!(f, "ce"),
+        }
+    }
+}
+
+/// A Differentiated Services Code Point, the six most significant bits of a TOS (i.e. DSCP+ECN) value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Dscp(pub u8);
+
+impl Dscp {
+    /// The DSCP of a TOS (i.e. DSCP+ECN) value.
+    pub const fn from_tos(tos: u8) -> Self {
+        Self(tos >> 2)
+    }
+
+    /// The name of the DSCP per the IANA registry, if it is a standard codepoint.
+    const fn name(self) -> Option<&'static str> {
+        Some(match self.0 {
+            0 => "cs0",
+            1 => "le",
+            8 => "cs1",
+            10 => "af11",
+            12 => "af12",
+            14 => "af13",
+            16 => "cs2",
+            18 => "af21",
+            20 => "af22",
+            22 => "af23",
+            24 => "cs3",
+            26 => "af31",
+            28 => "af32",
+            30 => "af33",
+            32 => "cs4",
+            34 => "af41",
+            36 => "af42",
+            38 => "af43",
+            40 => "cs5",
+            44 => "voice-admit",
+            46 => "ef",
+            48 => "cs6",
+            56 => "cs7",
+            _ => return None,
+        })
+    }
+}
+
+impl Display for Dscp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{name}"),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// How to timestamp streamed output.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampMode {
+    /// Do not timestamp output.
+    Off,
+    /// Timestamp output using the local timezone.
+    Local,
+    /// Timestamp output using UTC.
+    Utc,
+}
+
+/// What to do when the address of a target changes after it is re-resolved.
+///
+/// A target is only ever traced by a single tracer and so the original and new addresses are never traced together.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReResolveStrategy {
+    /// Follow the new address, clearing the statistics of all hops.
+    Follow,
+    /// Only record the change and keep tracing the original address, the new address is not traced.
+    RecordOnly,
+}
+
+/// A column of statistics of each hop in the csv and table reports.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportColumn {
+    /// The % of packets that are lost.
+    LossPct,
+    /// The number of probes sent.
+    Sent,
+    /// The number of responses received.
+    Recv,
+    /// The round-trip time of the last probe.
+    Last,
+    /// The average round-trip time of all probes.
+    Avg,
+    /// The best round-trip time of all probes.
+    Best,
+    /// The worst round-trip time of all probes.
+    Worst,
+    /// The standard deviation of the round-trip time of all probes.
+    Stddev,
+    /// The jitter of the last probe.
+    Jitter,
+    /// The average jitter of all probes.
+    JitterAvg,
+    /// The worst jitter of all probes.
+    JitterMax,
+    /// The 50th percentile (median) round-trip time of the recent samples.
+    P50,
+    /// The 95th percentile round-trip time of the recent samples.
+    P95,
+    /// The 99th percentile round-trip time of the recent samples.
+    P99,
+    /// The one-way delay to the hop of the last ICMP timestamp reply.
+    Fwd,
+    /// The one-way delay from the hop of the last ICMP timestamp reply.
+    Rtn,
+}
+
+/// The style of the borders of the pretty report.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportBorder {
+    /// Draw borders with unicode box drawing characters.
+    Unicode,
+    /// Draw borders with ASCII characters.
+    Ascii,
+    /// Do not draw borders.
+    None,
+}
+
+/// How the cells of the pretty report which are wider than their column are shown.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportOverflow {
+    /// Wrap the cell onto multiple lines.
+    Wrap,
+    /// Truncate the cell, such that each hop has one line per address.
+    Truncate,
+}
+
 /// How DNS queries will be resolved.
-#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum DnsResolveMethod {
     /// Resolve using the OS resolver.
@@ -246,12 +782,223 @@ pub enum DnsResolveMethod {
     Cloudflare,
 }
 
+/// A filter of hops to hide in the Tui.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct HopFilter {
+    /// Hide hops which have not responded.
+    pub unresponsive: bool,
+    /// Hide hops which only have private or otherwise special purpose addresses.
+    pub private: bool,
+    /// Hide hops with an address or hostname containing any of these patterns.
+    pub patterns: Vec<String>,
+}
+
+impl HopFilter {
+    /// The filter used when hops are hidden from the Tui without a configured filter.
+    pub fn unresponsive() -> Self {
+        Self {
+            unresponsive: true,
+            ..Self::default()
+        }
+    }
+}
+
+impl Display for HopFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let terms = [
+            self.unresponsive.then(|| String::from("unresponsive")),
+            self.private.then(|| String::from("private")),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(
+            self.patterns
+                .iter()
+                .map(|pattern| format!("pattern={pattern}")),
+        );
+        write!(f, "{}", terms.format(","))
+    }
+}
+
+impl TryFrom<&str> for HopFilter {
+    type Error = anyhow::Error;
+
+    /// Parse a comma separated filter of `unresponsive`, `private` and `pattern=<text>` terms.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut filter = Self::default();
+        for term in value.split(',').map(str::trim) {
+            match term {
+                "unresponsive" => filter.unresponsive = true,
+                "private" => filter.private = true,
+                _ => match term.strip_prefix("pattern=") {
+                    Some(pattern) if !pattern.is_empty() => {
+                        filter.patterns.push(pattern.to_string());
+                    }
+                    _ => return Err(anyhow!("invalid hop filter term: {}", term)),
+                },
+            }
+        }
+        Ok(filter)
+    }
+}
+
+/// The flavor of statsd metrics to emit.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StatsdFlavor {
+    /// Plain statsd, with the target and hop in the metric name.
+    Statsd,
+    /// Datadog statsd, with the target and hop as tags.
+    Dogstatsd,
+}
+
+/// An OTLP/HTTP endpoint of an OpenTelemetry collector.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OtlpEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl Display for OtlpEndpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "http://[{}]:{}{}", self.host, self.port, self.path)
+        } else {
+            write!(f, "http://{}:{}{}", self.host, self.port, self.path)
+        }
+    }
+}
+
+impl TryFrom<&str> for OtlpEndpoint {
+    type Error = anyhow::Error;
+
+    /// Parse an `http://host[:port][/path]` URL, where the port defaults to 4318 and the path to `/v1/metrics`.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let rest = value
+            .strip_prefix("http://")
+            .ok_or_else(|| anyhow!("otlp-endpoint must be an http:// URL: {}", value))?;
+        let (authority, path) = rest
+            .find('/')
+            .map_or((rest, ""), |index| rest.split_at(index));
+        let (host, port) = parse_authority(authority, DEFAULT_OTLP_PORT, "otlp-endpoint")?;
+        let path = match path {
+            "" | "/" => DEFAULT_OTLP_PATH,
+            path => path,
+        };
+        Ok(Self {
+            host,
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Where to send syslog messages.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SyslogTarget {
+    /// The local syslog daemon.
+    Local,
+    /// A remote syslog server over UDP.
+    Udp { host: String, port: u16 },
+    /// A remote syslog server over TCP.
+    Tcp { host: String, port: u16 },
+}
+
+impl Display for SyslogTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (scheme, host, port) = match self {
+            Self::Local => return write!(f, "local"),
+            Self::Udp { host, port } => ("udp", host, port),
+            Self::Tcp { host, port } => ("tcp", host, port),
+        };
+        if host.contains(':') {
+            write!(f, "{scheme}://[{host}]:{port}")
+        } else {
+            write!(f, "{scheme}://{host}:{port}")
+        }
+    }
+}
+
+impl TryFrom<&str> for SyslogTarget {
+    type Error = anyhow::Error;
+
+    /// Parse `local`, `udp://host[:port]` or `tcp://host[:port]`, where the port defaults to 514.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value == "local" {
+            Ok(Self::Local)
+        } else if let Some(authority) = value.strip_prefix("udp://") {
+            let (host, port) = parse_authority(authority, DEFAULT_SYSLOG_PORT, "syslog")?;
+            Ok(Self::Udp { host, port })
+        } else if let Some(authority) = value.strip_prefix("tcp://") {
+            let (host, port) = parse_authority(authority, DEFAULT_SYSLOG_PORT, "syslog")?;
+            Ok(Self::Tcp { host, port })
+        } else {
+            Err(anyhow!(
+                "syslog must be local, udp://host[:port] or tcp://host[:port]: {}",
+                value
+            ))
+        }
+    }
+}
+
+/// The syslog facility of messages.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyslogFacility {
+    /// Generic user-level messages.
+    User,
+    /// System daemons.
+    Daemon,
+    /// Reserved for local use.
+    Local0,
+    /// Reserved for local use.
+    Local1,
+    /// Reserved for local use.
+    Local2,
+    /// Reserved for local use.
+    Local3,
+    /// Reserved for local use.
+    Local4,
+    /// Reserved for local use.
+    Local5,
+    /// Reserved for local use.
+    Local6,
+    /// Reserved for local use.
+    Local7,
+}
+
+/// Parse a `host[:port]` or `[host][:port]` authority, where the port defaults to `default_port`.
+fn parse_authority(
+    authority: &str,
+    default_port: u16,
+    name: &str,
+) -> anyhow::Result<(String, u16)> {
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => bracketed
+            .split_once(']')
+            .map(|(host, port)| (host, port.strip_prefix(':')))
+            .ok_or_else(|| anyhow!("invalid {} host: {}", name, authority))?,
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = port
+        .map(|port| u16::from_str(port).map_err(|_| anyhow!("invalid {} port: {}", name, port)))
+        .transpose()?
+        .unwrap_or(default_port);
+    if host.is_empty() {
+        return Err(anyhow!("{} must have a host: {}", name, authority));
+    }
+    Ok((host.to_string(), port))
+}
+
 /// Trace a route to a host and record statistics
 #[derive(Parser, Debug)]
 #[command(name = "trip", author, version, about, long_about = None)]
 pub struct Args {
-    /// A space delimited list of hostnames and IPs to trace
-    #[arg(required_unless_present_any(["print_tui_theme_items", "print_tui_binding_commands", "generate"]))]
+    /// A space delimited list of hostnames, IPs and URLs to trace, each optionally with a protocol scheme and port
     pub targets: Vec<String>,
 
     /// Config file
@@ -262,14 +1009,85 @@ pub struct Args {
     #[arg(value_enum, short = 'm', long, display_order = 1)]
     pub mode: Option<Mode>,
 
+    /// Output without colors, with textual status indicators and a linear layout [default: false, or true if the
+    /// `NO_COLOR` environment variable is set]
+    #[arg(long, display_order = 2)]
+    pub plain: Option<bool>,
+
+    /// The unit in which round-trip times are shown in the Tui, stream and reports (other than json) [default: ms]
+    #[arg(value_enum, long, display_order = 3)]
+    pub rtt_unit: Option<RttUnit>,
+
+    /// The number of decimal places with which round-trip times are shown [default: 1]
+    #[arg(long, display_order = 4)]
+    pub rtt_precision: Option<u8>,
+
+    /// The language of the labels of the Tui and the headers of table reports [default: en]
+    #[arg(value_enum, long, display_order = 5)]
+    pub locale: Option<Locale>,
+
+    /// Print a summary of each trace to stdout on exiting the Tui or interrupting the stream mode [default: true]
+    #[arg(long, display_order = 6)]
+    pub exit_summary: Option<bool>,
+
+    /// Serve a web dashboard on an address, such as `127.0.0.1:8080`, in place of the Tui
+    #[arg(long, display_order = 7)]
+    pub web: Option<String>,
+
+    /// Serve a JSON-RPC API to query and control the traces on a loopback address, such as `127.0.0.1:8081`
+    #[arg(long, display_order = 8)]
+    pub api: Option<String>,
+
+    /// Run as a remote agent, streaming the traces to clients on an address, such as `127.0.0.1:8082`, in place of the Tui
+    /// (the connection is not encrypted)
+    #[arg(long, display_order = 9)]
+    pub agent: Option<String>,
+
+    /// Allow the agent to be served on an address other than a loopback address, such that the connection and token
+    /// are sent unencrypted over the network [default: false]
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true", display_order = 9)]
+    pub agent_insecure: Option<bool>,
+
+    /// Show the traces of a remote agent, such as `agent.example.com:8082`, alongside the local traces
+    #[arg(long, display_order = 10)]
+    pub remote: Option<String>,
+
+    /// The token which authenticates clients to a remote agent
+    #[arg(long, display_order = 11)]
+    pub agent_token: Option<String>,
+
+    /// The maximum duration to trace for before stopping, in any mode
+    #[arg(long, display_order = 12)]
+    pub max_duration: Option<String>,
+
+    /// Stop after N consecutive rounds in which the target did not respond (non-Tui modes only)
+    #[arg(long, display_order = 13)]
+    pub max_unreachable_rounds: Option<usize>,
+
+    /// Reset the statistics of all hops whenever this duration has elapsed [default: never]
+    #[arg(long, display_order = 14)]
+    pub reset_interval: Option<String>,
+
+    /// Back off probing of an unreachable target exponentially, up to this interval between rounds [default: never]
+    #[arg(long, display_order = 15)]
+    pub unreachable_backoff: Option<String>,
+
+    /// Re-resolve the target hostname every N rounds [default: never]
+    #[arg(long, display_order = 16)]
+    pub re_resolve_every: Option<usize>,
+
+    /// What to do when the address of a target changes after it is re-resolved [default: follow]
+    #[arg(value_enum, long, display_order = 17)]
+    pub re_resolve_strategy: Option<ReResolveStrategy>,
+
     /// Tracing protocol [default: icmp]
-    #[arg(value_enum, short = 'p', long, display_order = 2)]
+    #[arg(value_enum, short = 'p', long, display_order = 18)]
     pub protocol: Option<Protocol>,
 
     /// Trace using the UDP protocol
     #[arg(
         long,
-        display_order = 3,
+        display_order = 19,
         conflicts_with = "protocol",
         conflicts_with = "tcp"
     )]
@@ -278,151 +1096,510 @@ pub struct Args {
     /// Trace using the TCP protocol
     #[arg(
         long,
-        display_order = 4,
+        display_order = 20,
         conflicts_with = "protocol",
         conflicts_with = "udp"
     )]
     pub tcp: bool,
 
+    /// Trace each target using the ICMP, UDP and TCP protocols simultaneously
+    #[arg(
+        long,
+        display_order = 21,
+        conflicts_with = "protocol",
+        conflicts_with = "udp",
+        conflicts_with = "tcp"
+    )]
+    pub all_protocols: bool,
+
     /// use IPv4 only
-    #[arg(short = '4', long, display_order = 5, conflicts_with = "ipv6")]
+    #[arg(short = '4', long, display_order = 22, conflicts_with = "ipv6")]
     pub ipv4: bool,
 
     /// Use IPv6 only
-    #[arg(short = '6', long, display_order = 6, conflicts_with = "ipv4")]
+    #[arg(short = '6', long, display_order = 23, conflicts_with = "ipv4")]
     pub ipv6: bool,
 
+    /// Trace each target using both IPv4 and IPv6 simultaneously
+    #[arg(
+        long,
+        display_order = 24,
+        conflicts_with = "ipv4",
+        conflicts_with = "ipv6"
+    )]
+    pub dual_stack: bool,
+
     /// The target port (TCP & UDP only) [default: 80]
-    #[arg(long, short = 'P', display_order = 7)]
+    #[arg(long, short = 'P', display_order = 25)]
     pub target_port: Option<u16>,
 
-    /// The source port (TCP & UDP only) [default: auto]
-    #[arg(long, short = 'S', display_order = 8)]
-    pub source_port: Option<u16>,
+    /// Trace each target to each of a list of TCP ports (and UDP ports for firewalk mode), such as 22,80,443,8000-8010
+    #[arg(
+        long,
+        display_order = 26,
+        conflicts_with = "target_port",
+        conflicts_with = "all_protocols",
+        conflicts_with = "udp"
+    )]
+    pub target_ports: Option<String>,
 
-    /// The source IP address [default: auto]
-    #[arg(short = 'A', long, display_order = 9, conflicts_with = "interface")]
-    pub source_address: Option<String>,
+    /// The TTL of the gateway hop to firewalk, probing the hop just past it (firewalk mode only)
+    #[arg(
+        long,
+        display_order = 27,
+        conflicts_with = "all_protocols",
+        conflicts_with = "protocol",
+        conflicts_with = "udp",
+        conflicts_with = "tcp",
+        conflicts_with = "first_ttl",
+        conflicts_with = "max_ttl"
+    )]
+    pub firewalk_gateway: Option<u8>,
+
+    /// The source port (TCP & UDP only) [default: auto]
+    #[arg(long, short = 'S', display_order = 28)]
+    pub source_port: Option<u16>,
+
+    /// The source IP address [default: auto]
+    #[arg(short = 'A', long, display_order = 29, conflicts_with = "interface")]
+    pub source_address: Option<String>,
 
-    /// The network interface [default: auto]
-    #[arg(short = 'I', long, display_order = 10)]
+    /// The network interface name or index [default: auto]
+    #[arg(short = 'I', long, display_order = 30)]
     pub interface: Option<String>,
 
+    /// Prefer temporary or stable IPv6 source addresses (IPv6 only) [default: auto]
+    #[arg(
+        value_enum,
+        long,
+        display_order = 31,
+        conflicts_with = "source_address"
+    )]
+    pub ipv6_source_preference: Option<Ipv6SourcePreferenceConfig>,
+
+    /// Include an IPv6 extension header in probes, and also trace without it for the ext-header mode (IPv6 only)
+    #[arg(value_enum, long, display_order = 32)]
+    pub ipv6_ext_header: Option<Ipv6ExtHeaderConfig>,
+
+    /// Set the IPv4 Record Route option in probes to discover more addresses of the first 9 hops (IPv4 icmp and udp
+    /// only) [default: false]
+    #[arg(long, display_order = 33)]
+    pub record_route: Option<bool>,
+
+    /// Set the Don't Fragment bit of IPv4 probes and prohibit fragmentation of IPv6 probes, or permit fragmentation if
+    /// false [default: set for privileged IPv4 icmp and udp probes, otherwise chosen by the OS]
+    #[arg(long, display_order = 34)]
+    pub do_not_fragment: Option<bool>,
+
+    /// Send ICMP Timestamp requests, rather than Echo requests, to report the one-way delays to and from the target
+    /// (IPv4 icmp only) [default: false]
+    #[arg(long, display_order = 35)]
+    pub icmp_timestamp: Option<bool>,
+
     /// The minimum duration of every round [default: 1s]
-    #[arg(short = 'i', long, display_order = 11)]
+    #[arg(short = 'i', long, display_order = 36)]
     pub min_round_duration: Option<String>,
 
     /// The maximum duration of every round [default: 1s]
-    #[arg(short = 'T', long, display_order = 12)]
+    #[arg(short = 'T', long, display_order = 37)]
     pub max_round_duration: Option<String>,
 
+    /// Lengthen or shorten every round by a random duration of up to +/- this jitter, such that rounds do not align
+    /// with those of other instances or monitors [default: 0s]
+    #[arg(long, display_order = 38)]
+    pub round_jitter: Option<String>,
+
+    /// The maximum time to wait for the response to a probe before it is considered lost, regardless of the round
+    /// duration
+    #[arg(long, display_order = 39)]
+    pub probe_timeout: Option<String>,
+
+    /// The number of times to resend a probe in the same round if not responded to within the probe timeout
+    /// [default: 0]
+    #[arg(long, display_order = 40)]
+    pub retries: Option<u8>,
+
+    /// Record responses received after the round of the probe as received, rather than only counting them as late
+    /// [default: false]
+    #[arg(long, display_order = 41)]
+    pub backfill_late: Option<bool>,
+
     /// The period of time to wait for additional ICMP responses after the target has responded [default: 100ms]
-    #[arg(short = 'g', long, display_order = 13)]
+    #[arg(short = 'g', long, display_order = 42)]
     pub grace_duration: Option<String>,
 
     /// The initial sequence number [default: 33000]
-    #[arg(long, display_order = 14)]
+    #[arg(long, display_order = 43)]
     pub initial_sequence: Option<u16>,
 
+    /// Whether every round continues the sequence numbers of the previous round or starts from a random sequence
+    /// number [default: sequential]
+    #[arg(value_enum, long, display_order = 44)]
+    pub sequence_strategy: Option<SequenceStrategyConfig>,
+
+    /// The identifier of ICMP probes, incremented for each additional target [default: the process id]
+    #[arg(long, display_order = 45)]
+    pub icmp_identifier: Option<u16>,
+
     /// The Equal-cost Multi-Path routing strategy (IPv4/UDP only) [default: classic]
-    #[arg(value_enum, short = 'R', long, display_order = 15)]
+    #[arg(value_enum, short = 'R', long, display_order = 46)]
     pub multipath_strategy: Option<MultipathStrategyConfig>,
 
     /// The maximum number of in-flight ICMP echo requests [default: 24]
-    #[arg(short = 'U', long, display_order = 16)]
+    #[arg(short = 'U', long, display_order = 47)]
     pub max_inflight: Option<u8>,
 
+    /// Grow and shrink the in-flight window, up to max-inflight, based on the loss and latency of each round
+    /// [default: false]
+    #[arg(long, display_order = 48)]
+    pub adaptive_inflight: Option<bool>,
+
+    /// Send the probes for every TTL in a round back-to-back [default: false]
+    #[arg(long, display_order = 49)]
+    pub burst: Option<bool>,
+
     /// The TTL to start from [default: 1]
-    #[arg(short = 'f', long, display_order = 17)]
+    #[arg(short = 'f', long, display_order = 50)]
     pub first_ttl: Option<u8>,
 
     /// The maximum number of TTL hops [default: 64]
-    #[arg(short = 't', long, display_order = 18)]
+    #[arg(short = 't', long, display_order = 51)]
     pub max_ttl: Option<u8>,
 
+    /// Probe only the target, at the TTL of the target once discovered, like ping [default: false]
+    #[arg(long, display_order = 52)]
+    pub destination_only: Option<bool>,
+
+    /// Stop probing beyond the TTL of the target once discovered, until the path lengthens [default: false]
+    #[arg(long, display_order = 53)]
+    pub adaptive_ttl: Option<bool>,
+
+    /// Whether to stop or continue probing beyond a hop which responds that the target is administratively
+    /// prohibited [default: stop]
+    #[arg(value_enum, long, display_order = 54)]
+    pub prohibited_strategy: Option<ProhibitedStrategyConfig>,
+
     /// The size of IP packet to send (IP header + ICMP header + payload) [default: 84]
-    #[arg(long, display_order = 19)]
+    #[arg(long, display_order = 55)]
     pub packet_size: Option<u16>,
 
+    /// The range of IP packet sizes to trace with for the size-sweep mode [default: 84-1024]
+    #[arg(long, display_order = 56)]
+    pub sweep_range: Option<String>,
+
+    /// The number of packet sizes, evenly spaced across the sweep-range, to trace with [default: 5]
+    #[arg(long, display_order = 57)]
+    pub sweep_steps: Option<u8>,
+
     /// The repeating pattern in the payload of the ICMP packet [default: 0]
-    #[arg(long, display_order = 20)]
+    #[arg(long, display_order = 58)]
     pub payload_pattern: Option<u8>,
 
-    /// The TOS (i.e. DSCP+ECN) IP header value (TCP and UDP only) [default: 0]
-    #[arg(short = 'Q', long, display_order = 21)]
+    /// The TOS (i.e. DSCP+ECN) IP header value (traffic class for IPv6) [default: 0]
+    #[arg(short = 'Q', long, display_order = 59)]
     pub tos: Option<u8>,
 
+    /// Set the ECN codepoint of probes and report whether it is preserved at each hop [default: off]
+    #[arg(value_enum, long, display_order = 60)]
+    pub ecn: Option<EcnCodepoint>,
+
+    /// The TCP maximum segment size (MSS) option (TCP only) [default: auto]
+    #[arg(long, display_order = 61)]
+    pub tcp_mss: Option<u16>,
+
+    /// A hint for the size of the TCP receive buffer in bytes, from which the OS derives the advertised window (TCP
+    /// only) [default: auto]
+    #[arg(long, display_order = 62)]
+    pub tcp_window_size: Option<u32>,
+
     /// The socket read timeout [default: 10ms]
-    #[arg(long, display_order = 22)]
+    #[arg(long, display_order = 63)]
     pub read_timeout: Option<String>,
 
     /// How to perform DNS queries [default: system]
-    #[arg(value_enum, short = 'r', long, display_order = 23)]
+    #[arg(value_enum, short = 'r', long, display_order = 64)]
     pub dns_resolve_method: Option<DnsResolveMethod>,
 
     /// The maximum time to wait to perform DNS queries [default: 5s]
-    #[arg(long, display_order = 24)]
+    #[arg(long, display_order = 65)]
     pub dns_timeout: Option<String>,
 
     /// Lookup autonomous system (AS) information during DNS queries [default: false]
-    #[arg(long, short = 'z', display_order = 25)]
+    #[arg(long, short = 'z', display_order = 66)]
     pub dns_lookup_as_info: Option<bool>,
 
+    /// A hosts file of static hostnames to use in place of reverse DNS lookups
+    #[arg(long, display_order = 67, value_hint = clap::ValueHint::FilePath)]
+    pub dns_hosts_file: Option<String>,
+
+    /// The maximum number of reverse DNS lookups to perform concurrently [default: 4]
+    #[arg(long, display_order = 68)]
+    pub dns_max_in_flight: Option<usize>,
+
+    /// The source IP address to send DNS queries from, independent of the source address of the probes [default: auto]
+    #[arg(long, display_order = 69, conflicts_with = "dns_interface")]
+    pub dns_source_address: Option<String>,
+
+    /// The network interface, by name or index, to send DNS queries from, independent of the interface of the probes
+    /// [default: auto]
+    #[arg(long, display_order = 70)]
+    pub dns_interface: Option<String>,
+
     /// How to render addresses [default: host]
-    #[arg(value_enum, short = 'a', long, display_order = 26)]
+    #[arg(value_enum, short = 'a', long, display_order = 71)]
     pub tui_address_mode: Option<AddressMode>,
 
     /// How to render AS information [default: asn]
-    #[arg(value_enum, long, display_order = 27)]
+    #[arg(value_enum, long, display_order = 72)]
     pub tui_as_mode: Option<AsMode>,
 
     /// How to render GeoIp information [default: short]
-    #[arg(value_enum, long, display_order = 28)]
+    #[arg(value_enum, long, display_order = 73)]
     pub tui_geoip_mode: Option<GeoIpMode>,
 
     /// The maximum number of addresses to show per hop [default: auto]
-    #[arg(short = 'M', long, display_order = 29)]
+    #[arg(short = 'M', long, display_order = 74)]
     pub tui_max_addrs: Option<u8>,
 
     /// The maximum number of samples to record per hop [default: 256]
-    #[arg(long, short = 's', display_order = 30)]
+    #[arg(long, short = 's', display_order = 75)]
     pub tui_max_samples: Option<usize>,
 
+    /// Hide hops matching a comma separated filter of `unresponsive`, `private` and `pattern=<text>` terms [default:
+    /// none]
+    #[arg(long, display_order = 76)]
+    pub tui_hide_hops: Option<String>,
+
+    /// Show when each hop address was first and last seen responding [default: false]
+    #[arg(long, display_order = 77)]
+    pub tui_seen_columns: Option<bool>,
+
     /// Preserve the screen on exit [default: false]
-    #[arg(long, display_order = 31)]
+    #[arg(long, display_order = 78)]
     pub tui_preserve_screen: Option<bool>,
 
+    /// Set the terminal title to the target, loss and average round trip time of the selected trace [default: false]
+    #[arg(long, display_order = 79)]
+    pub tui_title: Option<bool>,
+
+    /// The format of the report of the selected trace exported with the export-report key [default: json]
+    #[arg(value_enum, long, display_order = 80)]
+    pub tui_export_format: Option<TuiExportFormat>,
+
+    /// The directory to write reports exported with the export-report key to [default: .]
+    #[arg(long, display_order = 81)]
+    pub tui_export_dir: Option<String>,
+
+    /// The palette of colors used to signal the status of hops [default: default]
+    #[arg(value_enum, long, display_order = 82)]
+    pub tui_palette: Option<TuiPalette>,
+
+    /// Render the Tui and pretty reports using ASCII characters only [default: false]
+    #[arg(long, display_order = 83)]
+    pub tui_ascii: Option<bool>,
+
+    /// The panes to show alongside the hop table in the multi-pane layout [default: chart,route-changes]
+    #[arg(value_enum, long, value_delimiter(','), display_order = 84)]
+    pub tui_panes: Vec<TuiPane>,
+
+    /// The percentage of the width, or height, of the multi-pane layout given to the hop table [default: 60]
+    #[arg(long, display_order = 85)]
+    pub tui_pane_split: Option<u16>,
+
     /// The Tui refresh rate [default: 100ms]
-    #[arg(long, display_order = 32)]
+    #[arg(long, display_order = 86)]
     pub tui_refresh_rate: Option<String>,
 
     /// The TUI theme colors [item=color,item=color,..]
-    #[arg(long, value_delimiter(','), value_parser = parse_tui_theme_color_value, display_order = 33)]
+    #[arg(long, value_delimiter(','), value_parser = parse_tui_theme_color_value, display_order = 87)]
     pub tui_theme_colors: Vec<(TuiThemeItem, TuiColor)>,
 
     /// Print all TUI theme items and exit
-    #[arg(long, display_order = 34)]
+    #[arg(long, display_order = 88)]
     pub print_tui_theme_items: bool,
 
     /// The TUI key bindings [command=key,command=key,..]
-    #[arg(long, value_delimiter(','), value_parser = parse_tui_binding_value, display_order = 35)]
+    #[arg(long, value_delimiter(','), value_parser = parse_tui_binding_value, display_order = 89)]
     pub tui_key_bindings: Vec<(TuiCommandItem, TuiKeyBinding)>,
 
     /// Print all TUI commands that can be bound and exit
-    #[arg(long, display_order = 36)]
+    #[arg(long, display_order = 90)]
     pub print_tui_binding_commands: bool,
 
     /// The number of report cycles to run [default: 10]
-    #[arg(short = 'C', long, display_order = 37)]
+    #[arg(short = 'C', long, display_order = 91)]
     pub report_cycles: Option<usize>,
 
+    /// How to timestamp each line of stream output [default: off]
+    #[arg(value_enum, long, display_order = 92)]
+    pub report_timestamp: Option<TimestampMode>,
+
+    /// The strftime format of stream output timestamps [default: %Y-%m-%dT%H:%M:%S%.3f%:z]
+    #[arg(long, display_order = 93)]
+    pub report_timestamp_format: Option<String>,
+
+    /// The columns of statistics of each hop in the csv and table reports [default:
+    /// loss-pct,sent,recv,last,avg,best,worst,stddev]
+    #[arg(value_enum, long, value_delimiter(','), display_order = 94)]
+    pub report_columns: Vec<ReportColumn>,
+
+    /// The maximum width of the pretty report [default: the width of the terminal]
+    #[arg(long, display_order = 95)]
+    pub report_width: Option<u16>,
+
+    /// How cells of the pretty report which are wider than their column are shown [default: wrap]
+    #[arg(value_enum, long, display_order = 96)]
+    pub report_overflow: Option<ReportOverflow>,
+
+    /// The style of the borders of the pretty report [default: unicode, or ascii if tui-ascii is set]
+    #[arg(value_enum, long, display_order = 97)]
+    pub report_border: Option<ReportBorder>,
+
+    /// Include the result of every probe of each hop in the json report [default: false]
+    #[arg(long, display_order = 98)]
+    pub report_include_samples: Option<bool>,
+
+    /// Show the statistics of each address of a hop with several addresses in the csv, pretty and markdown reports
+    /// [default: false]
+    #[arg(long, display_order = 99)]
+    pub report_per_address: Option<bool>,
+
+    /// The file of the template from which the report of the template mode is rendered
+    #[arg(long, display_order = 100, value_hint = clap::ValueHint::FilePath)]
+    pub template: Option<String>,
+
+    /// Write the report to a file, refreshing it after every round
+    #[arg(long, display_order = 101, value_hint = clap::ValueHint::FilePath)]
+    pub report_file: Option<String>,
+
+    /// Repeat the report every duration, such as `10m`, pausing tracing in between [default: never]
+    #[arg(long, display_order = 102)]
+    pub repeat_every: Option<String>,
+
+    /// The number of times to run the report when repeating it [default: unlimited]
+    #[arg(long, display_order = 103)]
+    pub repeat_count: Option<usize>,
+
     /// The MaxMind City GeoLite2 mmdb file
-    #[arg(short = 'G', long, display_order = 38, value_hint = clap::ValueHint::FilePath)]
+    #[arg(short = 'G', long, display_order = 104, value_hint = clap::ValueHint::FilePath)]
     pub geoip_mmdb_file: Option<String>,
 
+    /// A file of IP addresses and prefixes with labels to annotate hops with
+    #[arg(long, display_order = 105, value_hint = clap::ValueHint::FilePath)]
+    pub annotations_file: Option<String>,
+
+    /// A file to persist reverse DNS, AS and `GeoIp` lookups to between runs
+    #[arg(long, display_order = 106, value_hint = clap::ValueHint::FilePath)]
+    pub cache_file: Option<String>,
+
+    /// The time after which persisted lookups expire [default: 24h]
+    #[arg(long, display_order = 107)]
+    pub cache_ttl: Option<String>,
+
+    /// A file to save the Tui session (the traces, their statistics and the view) to, periodically and on exit
+    #[arg(long, display_order = 108, value_hint = clap::ValueHint::FilePath)]
+    pub state_file: Option<String>,
+
+    /// Resume the Tui session saved to the state file, tracing its targets if none are given
+    #[arg(long, display_order = 109)]
+    pub resume: bool,
+
+    /// Increase the logging verbosity (-v info, -vv debug, -vvv trace) [default: warn]
+    #[arg(short = 'v', long, action = clap::ArgAction::Count, display_order = 110)]
+    pub verbose: u8,
+
+    /// The file to write log output to [default: stderr, disabled for the Tui]
+    #[arg(long, display_order = 111, value_hint = clap::ValueHint::FilePath)]
+    pub log_file: Option<String>,
+
+    /// Log an annotated hex dump of every probe sent and response received [default: false]
+    #[arg(long, display_order = 112)]
+    pub packet_dump: Option<bool>,
+
+    /// The user id to switch to after creating sockets when started as root [default: sudo user]
+    #[arg(long, display_order = 113)]
+    pub uid: Option<u32>,
+
+    /// Run a command when a route change is detected
+    #[arg(long, display_order = 114)]
+    pub on_route_change: Option<String>,
+
+    /// Run a command when an AS path change is detected, requires dns-lookup-as-info
+    #[arg(long, display_order = 115)]
+    pub on_as_path_change: Option<String>,
+
+    /// Run a command when the target breaches the loss or latency threshold
+    #[arg(long, display_order = 116)]
+    pub on_threshold_breach: Option<String>,
+
+    /// Run a command when the target does not respond for max-unreachable-rounds (or 1) consecutive rounds
+    #[arg(long, display_order = 117)]
+    pub on_target_unreachable: Option<String>,
+
+    /// The loss percentage of the target above which the threshold is breached
+    #[arg(long, display_order = 118)]
+    pub threshold_loss_pct: Option<f64>,
+
+    /// The average latency of the target above which the threshold is breached
+    #[arg(long, display_order = 119)]
+    pub threshold_latency: Option<String>,
+
+    /// Send significant events and round summaries to syslog, either `local` or `udp://host[:port]` or
+    /// `tcp://host[:port]`
+    #[arg(long, display_order = 120)]
+    pub syslog: Option<String>,
+
+    /// The syslog facility of events [default: user]
+    #[arg(value_enum, long, display_order = 121)]
+    pub syslog_facility: Option<SyslogFacility>,
+
+    /// Export hop metrics to an OpenTelemetry collector at an OTLP/HTTP endpoint, such as `http://localhost:4318`
+    #[arg(long, display_order = 122)]
+    pub otlp_endpoint: Option<String>,
+
+    /// The interval at which hop metrics are exported to the OpenTelemetry collector [default: 10s]
+    #[arg(long, display_order = 123)]
+    pub otlp_interval: Option<String>,
+
+    /// Send hop metrics for every round to a statsd server over UDP, such as `127.0.0.1:8125`
+    #[arg(long, display_order = 124)]
+    pub statsd_addr: Option<String>,
+
+    /// The flavor of statsd metrics to send [default: statsd]
+    #[arg(value_enum, long, display_order = 125)]
+    pub statsd_flavor: Option<StatsdFlavor>,
+
+    /// The prefix of the name of statsd metrics [default: trippy]
+    #[arg(long, display_order = 126)]
+    pub statsd_prefix: Option<String>,
+
+    /// Publish a JSON summary of every round to an MQTT broker, such as `127.0.0.1:1883`
+    #[arg(long, display_order = 127)]
+    pub mqtt_broker: Option<String>,
+
+    /// The topic under which the summaries are published, as `<topic>/<target>/<protocol>` [default: trippy]
+    #[arg(long, display_order = 128)]
+    pub mqtt_topic: Option<String>,
+
+    /// The username to authenticate to the MQTT broker with
+    #[arg(long, display_order = 129)]
+    pub mqtt_username: Option<String>,
+
+    /// The password to authenticate to the MQTT broker with
+    #[arg(long, display_order = 130)]
+    pub mqtt_password: Option<String>,
+
     /// Generate shell completion
-    #[arg(long, display_order = 39)]
+    #[arg(long, display_order = 131)]
     pub generate: Option<Shell>,
+
+    /// Print all network interfaces with their index and addresses and exit
+    #[arg(long, display_order = 132)]
+    pub list_interfaces: bool,
+
+    /// Print the effective configuration, after merging the command line and config file, and exit [default: toml]
+    #[arg(value_enum, long, num_args = 0..=1, require_equals = true, default_missing_value = "toml", display_order = 133)]
+    pub print_config: Option<ConfigFormat>,
 }
 
 fn parse_tui_theme_color_value(value: &str) -> anyhow::Result<(TuiThemeItem, TuiColor)> {
@@ -443,41 +1620,137 @@ fn parse_tui_binding_value(value: &str) -> anyhow::Result<(TuiCommandItem, TuiKe
     Ok((item, binding))
 }
 
+/// A target to trace with the protocol, address family and ports to use.
+#[derive(Debug, Clone)]
+pub struct TraceTarget {
+    pub hostname: String,
+    pub protocol: TracerProtocol,
+    pub addr_family: TracerAddrFamily,
+    pub port_direction: PortDirection,
+    pub ipv6_ext_header: Option<Ipv6ExtHeader>,
+    pub packet_size: u16,
+    pub group: Option<String>,
+}
+
 /// Fully parsed and validate configuration.
 pub struct TrippyConfig {
-    pub targets: Vec<String>,
-    pub protocol: TracerProtocol,
+    pub targets: Vec<TraceTarget>,
     pub addr_family: TracerAddrFamily,
+    pub dual_stack: bool,
     pub first_ttl: u8,
     pub max_ttl: u8,
+    pub destination_only: bool,
+    pub adaptive_ttl: bool,
+    pub prohibited_strategy: ProhibitedStrategy,
     pub min_round_duration: Duration,
     pub max_round_duration: Duration,
+    pub round_jitter: Duration,
+    pub probe_timeout: Option<Duration>,
+    pub retries: u8,
+    pub backfill_late: bool,
     pub grace_duration: Duration,
     pub max_inflight: u8,
+    pub adaptive_inflight: bool,
+    pub burst: bool,
     pub initial_sequence: u16,
+    pub sequence_strategy: SequenceStrategy,
+    pub icmp_identifier: u16,
     pub tos: u8,
+    pub ecn: Option<EcnCodepoint>,
+    pub tcp_mss: Option<u16>,
+    pub tcp_window_size: Option<u32>,
     pub read_timeout: Duration,
     pub packet_size: u16,
+    pub sweep_sizes: Vec<u16>,
     pub payload_pattern: u8,
     pub source_addr: Option<IpAddr>,
     pub interface: Option<String>,
+    pub ipv6_source_preference: Option<Ipv6SourcePreference>,
+    pub record_route: bool,
+    pub do_not_fragment: Option<bool>,
+    pub icmp_timestamp: bool,
     pub multipath_strategy: MultipathStrategy,
-    pub port_direction: PortDirection,
     pub dns_timeout: Duration,
     pub dns_resolve_method: DnsResolveMethod,
     pub dns_lookup_as_info: bool,
+    pub dns_hosts_file: Option<String>,
+    pub dns_max_in_flight: usize,
+    pub dns_source_addrs: Vec<IpAddr>,
     pub tui_max_samples: usize,
     pub tui_preserve_screen: bool,
+    pub tui_title: bool,
+    pub tui_export_format: TuiExportFormat,
+    pub tui_export_dir: String,
+    pub tui_seen_columns: bool,
+    pub tui_ascii: bool,
+    pub tui_palette: TuiPalette,
     pub tui_refresh_rate: Duration,
     pub tui_address_mode: AddressMode,
     pub tui_as_mode: AsMode,
     pub tui_geoip_mode: GeoIpMode,
     pub tui_max_addrs: Option<u8>,
+    pub tui_hide_hops: Option<HopFilter>,
+    pub tui_panes: Vec<TuiPane>,
+    pub tui_pane_split: u16,
     pub tui_theme: TuiTheme,
     pub tui_bindings: TuiBindings,
     pub mode: Mode,
+    pub plain: bool,
+    pub rtt_unit: RttUnit,
+    pub rtt_precision: u8,
+    pub locale: Locale,
+    pub exit_summary: bool,
+    pub web: Option<SocketAddr>,
+    pub api: Option<SocketAddr>,
+    pub agent: Option<SocketAddr>,
+    pub remote: Option<String>,
+    pub agent_token: Option<String>,
     pub report_cycles: usize,
+    pub report_timestamp: TimestampMode,
+    pub report_timestamp_format: String,
+    pub report_columns: Vec<ReportColumn>,
+    pub report_width: Option<u16>,
+    pub report_overflow: ReportOverflow,
+    pub report_border: ReportBorder,
+    pub report_include_samples: bool,
+    pub report_per_address: bool,
+    pub template: Option<String>,
+    pub report_file: Option<String>,
+    pub repeat_every: Option<Duration>,
+    pub repeat_count: Option<usize>,
+    pub max_duration: Option<Duration>,
+    pub max_unreachable_rounds: Option<usize>,
+    pub reset_interval: Option<Duration>,
+    pub unreachable_backoff: Option<Duration>,
+    pub re_resolve_every: Option<usize>,
+    pub re_resolve_strategy: ReResolveStrategy,
+    pub verbose: u8,
+    pub log_file: Option<String>,
+    pub packet_dump: bool,
+    pub uid: Option<u32>,
+    pub on_route_change: Option<String>,
+    pub on_as_path_change: Option<String>,
+    pub on_threshold_breach: Option<String>,
+    pub on_target_unreachable: Option<String>,
+    pub threshold_loss_pct: Option<f64>,
+    pub threshold_latency: Option<Duration>,
+    pub syslog: Option<SyslogTarget>,
+    pub syslog_facility: SyslogFacility,
+    pub otlp_endpoint: Option<OtlpEndpoint>,
+    pub otlp_interval: Duration,
+    pub statsd_addr: Option<String>,
+    pub statsd_flavor: StatsdFlavor,
+    pub statsd_prefix: String,
+    pub mqtt_broker: Option<String>,
+    pub mqtt_topic: String,
+    pub mqtt_username: Option<String>,
+    pub mqtt_password: Option<String>,
     pub geoip_mmdb_file: Option<String>,
+    pub annotations_file: Option<String>,
+    pub cache_file: Option<String>,
+    pub cache_ttl: Duration,
+    pub state_file: Option<String>,
+    pub resume: bool,
     pub max_rounds: Option<usize>,
 }
 
@@ -663,6 +1936,40 @@ impl From<(HashMap<TuiThemeItem, TuiColor>, ConfigThemeColors)> for TuiTheme {
     }
 }
 
+impl From<&TuiTheme> for ConfigThemeColors {
+    fn from(theme: &TuiTheme) -> Self {
+        Self {
+            bg_color: Some(theme.bg_color),
+            border_color: Some(theme.border_color),
+            text_color: Some(theme.text_color),
+            tab_text_color: Some(theme.tab_text_color),
+            hops_table_header_bg_color: Some(theme.hops_table_header_bg_color),
+            hops_table_header_text_color: Some(theme.hops_table_header_text_color),
+            hops_table_row_active_text_color: Some(theme.hops_table_row_active_text_color),
+            hops_table_row_inactive_text_color: Some(theme.hops_table_row_inactive_text_color),
+            hops_chart_selected_color: Some(theme.hops_chart_selected_color),
+            hops_chart_unselected_color: Some(theme.hops_chart_unselected_color),
+            hops_chart_axis_color: Some(theme.hops_chart_axis_color),
+            frequency_chart_bar_color: Some(theme.frequency_chart_bar_color),
+            frequency_chart_text_color: Some(theme.frequency_chart_text_color),
+            samples_chart_color: Some(theme.samples_chart_color),
+            help_dialog_bg_color: Some(theme.help_dialog_bg_color),
+            help_dialog_text_color: Some(theme.help_dialog_text_color),
+            settings_dialog_bg_color: Some(theme.settings_dialog_bg_color),
+            settings_tab_text_color: Some(theme.settings_tab_text_color),
+            settings_table_header_text_color: Some(theme.settings_table_header_text_color),
+            settings_table_header_bg_color: Some(theme.settings_table_header_bg_color),
+            settings_table_row_text_color: Some(theme.settings_table_row_text_color),
+            map_world_color: Some(theme.map_world_color),
+            map_radius_color: Some(theme.map_radius_color),
+            map_selected_color: Some(theme.map_selected_color),
+            map_info_panel_border_color: Some(theme.map_info_panel_border_color),
+            map_info_panel_bg_color: Some(theme.map_info_panel_bg_color),
+            map_info_panel_text_color: Some(theme.map_info_panel_text_color),
+        }
+    }
+}
+
 /// A TUI theme item.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, EnumString, EnumVariantNames)]
 #[strum(serialize_all = "kebab-case")]
@@ -787,6 +2094,36 @@ impl TryFrom<&str> for TuiColor {
     }
 }
 
+impl Display for TuiColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Black => write!(f, "black"),
+            Self::Red => write!(f, "red"),
+            Self::Green => write!(f, "green"),
+            Self::Yellow => write!(f, "yellow"),
+            Self::Blue => write!(f, "blue"),
+            Self::Magenta => write!(f, "magenta"),
+            Self::Cyan => write!(f, "cyan"),
+            Self::Gray => write!(f, "gray"),
+            Self::DarkGray => write!(f, "darkgray"),
+            Self::LightRed => write!(f, "lightred"),
+            Self::LightGreen => write!(f, "lightgreen"),
+            Self::LightYellow => write!(f, "lightyellow"),
+            Self::LightBlue => write!(f, "lightblue"),
+            Self::LightMagenta => write!(f, "lightmagenta"),
+            Self::LightCyan => write!(f, "lightcyan"),
+            Self::White => write!(f, "white"),
+            Self::Rgb(r, g, b) => write!(f, "{r:02x}{g:02x}{b:02x}"),
+        }
+    }
+}
+
+impl Serialize for TuiColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 /// Tui keyboard bindings.
 #[derive(Debug, Clone, Copy)]
 pub struct TuiBindings {
@@ -804,6 +2141,13 @@ pub struct TuiBindings {
     pub toggle_freeze: TuiKeyBinding,
     pub toggle_chart: TuiKeyBinding,
     pub toggle_map: TuiKeyBinding,
+    pub toggle_route_changes: TuiKeyBinding,
+    pub toggle_dns: TuiKeyBinding,
+    pub toggle_diagnostics: TuiKeyBinding,
+    pub toggle_panes: TuiKeyBinding,
+    pub toggle_compare: TuiKeyBinding,
+    pub toggle_overview: TuiKeyBinding,
+    pub toggle_hide_hops: TuiKeyBinding,
     pub expand_hosts: TuiKeyBinding,
     pub contract_hosts: TuiKeyBinding,
     pub expand_hosts_max: TuiKeyBinding,
@@ -811,10 +2155,12 @@ pub struct TuiBindings {
     pub chart_zoom_in: TuiKeyBinding,
     pub chart_zoom_out: TuiKeyBinding,
     pub clear_trace_data: TuiKeyBinding,
+    pub clear_all_trace_data: TuiKeyBinding,
     pub clear_dns_cache: TuiKeyBinding,
     pub clear_selection: TuiKeyBinding,
     pub toggle_as_info: TuiKeyBinding,
     pub toggle_hop_details: TuiKeyBinding,
+    pub export_report: TuiKeyBinding,
     pub quit: TuiKeyBinding,
 }
 
@@ -838,6 +2184,13 @@ impl TuiBindings {
             (self.toggle_freeze, ToggleFreeze),
             (self.toggle_chart, ToggleChart),
             (self.toggle_map, ToggleMap),
+            (self.toggle_route_changes, ToggleRouteChanges),
+            (self.toggle_dns, ToggleDns),
+            (self.toggle_diagnostics, ToggleDiagnostics),
+            (self.toggle_panes, TogglePanes),
+            (self.toggle_compare, ToggleCompare),
+            (self.toggle_overview, ToggleOverview),
+            (self.toggle_hide_hops, ToggleHideHops),
             (self.expand_hosts, ExpandHosts),
             (self.expand_hosts_max, ExpandHostsMax),
             (self.contract_hosts, ContractHosts),
@@ -845,10 +2198,12 @@ impl TuiBindings {
             (self.chart_zoom_in, ChartZoomIn),
             (self.chart_zoom_out, ChartZoomOut),
             (self.clear_trace_data, ClearTraceData),
+            (self.clear_all_trace_data, ClearAllTraceData),
             (self.clear_dns_cache, ClearDnsCache),
             (self.clear_selection, ClearSelection),
             (self.toggle_as_info, ToggleASInfo),
             (self.toggle_hop_details, ToggleHopDetails),
+            (self.export_report, ExportReport),
             (self.quit, Quit),
         ]
         .iter()
@@ -933,6 +2288,34 @@ impl From<(HashMap<TuiCommandItem, TuiKeyBinding>, ConfigBindings)> for TuiBindi
                 .get(&ToggleMap)
                 .or(cfg.toggle_map.as_ref())
                 .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('m'))),
+            toggle_route_changes: *cmd_items
+                .get(&ToggleRouteChanges)
+                .or(cfg.toggle_route_changes.as_ref())
+                .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('l'))),
+            toggle_dns: *cmd_items
+                .get(&ToggleDns)
+                .or(cfg.toggle_dns.as_ref())
+                .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('r'))),
+            toggle_diagnostics: *cmd_items
+                .get(&ToggleDiagnostics)
+                .or(cfg.toggle_diagnostics.as_ref())
+                .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('x'))),
+            toggle_panes: *cmd_items
+                .get(&TogglePanes)
+                .or(cfg.toggle_panes.as_ref())
+                .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('p'))),
+            toggle_compare: *cmd_items
+                .get(&ToggleCompare)
+                .or(cfg.toggle_compare.as_ref())
+                .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('v'))),
+            toggle_overview: *cmd_items
+                .get(&ToggleOverview)
+                .or(cfg.toggle_overview.as_ref())
+                .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('o'))),
+            toggle_hide_hops: *cmd_items
+                .get(&ToggleHideHops)
+                .or(cfg.toggle_hide_hops.as_ref())
+                .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('u'))),
             expand_hosts: *cmd_items
                 .get(&ExpandHosts)
                 .or(cfg.expand_hosts.as_ref())
@@ -964,6 +2347,13 @@ impl From<(HashMap<TuiCommandItem, TuiKeyBinding>, ConfigBindings)> for TuiBindi
                     KeyCode::Char('r'),
                     KeyModifiers::CONTROL,
                 )),
+            clear_all_trace_data: *cmd_items
+                .get(&ClearAllTraceData)
+                .or(cfg.clear_all_trace_data.as_ref())
+                .unwrap_or(&TuiKeyBinding::new_with_modifier(
+                    KeyCode::Char('r'),
+                    KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+                )),
             clear_dns_cache: *cmd_items
                 .get(&ClearDnsCache)
                 .or(cfg.clear_dns_cache.as_ref())
@@ -983,6 +2373,10 @@ impl From<(HashMap<TuiCommandItem, TuiKeyBinding>, ConfigBindings)> for TuiBindi
                 .get(&ToggleHopDetails)
                 .or(cfg.toggle_hop_details.as_ref())
                 .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('d'))),
+            export_report: *cmd_items
+                .get(&ExportReport)
+                .or(cfg.export_report.as_ref())
+                .unwrap_or(&TuiKeyBinding::new(KeyCode::Char('e'))),
             quit: *cmd_items
                 .get(&Quit)
                 .or(cfg.quit.as_ref())
@@ -991,6 +2385,48 @@ impl From<(HashMap<TuiCommandItem, TuiKeyBinding>, ConfigBindings)> for TuiBindi
     }
 }
 
+impl From<&TuiBindings> for ConfigBindings {
+    fn from(bindings: &TuiBindings) -> Self {
+        Self {
+            toggle_help: Some(bindings.toggle_help),
+            toggle_settings: Some(bindings.toggle_settings),
+            previous_hop: Some(bindings.previous_hop),
+            next_hop: Some(bindings.next_hop),
+            previous_trace: Some(bindings.previous_trace),
+            next_trace: Some(bindings.next_trace),
+            previous_hop_address: Some(bindings.previous_hop_address),
+            next_hop_address: Some(bindings.next_hop_address),
+            address_mode_ip: Some(bindings.address_mode_ip),
+            address_mode_host: Some(bindings.address_mode_host),
+            address_mode_both: Some(bindings.address_mode_both),
+            toggle_freeze: Some(bindings.toggle_freeze),
+            toggle_chart: Some(bindings.toggle_chart),
+            toggle_map: Some(bindings.toggle_map),
+            toggle_route_changes: Some(bindings.toggle_route_changes),
+            toggle_dns: Some(bindings.toggle_dns),
+            toggle_diagnostics: Some(bindings.toggle_diagnostics),
+            toggle_panes: Some(bindings.toggle_panes),
+            toggle_compare: Some(bindings.toggle_compare),
+            toggle_overview: Some(bindings.toggle_overview),
+            toggle_hide_hops: Some(bindings.toggle_hide_hops),
+            expand_hosts: Some(bindings.expand_hosts),
+            contract_hosts: Some(bindings.contract_hosts),
+            expand_hosts_max: Some(bindings.expand_hosts_max),
+            contract_hosts_min: Some(bindings.contract_hosts_min),
+            chart_zoom_in: Some(bindings.chart_zoom_in),
+            chart_zoom_out: Some(bindings.chart_zoom_out),
+            clear_trace_data: Some(bindings.clear_trace_data),
+            clear_all_trace_data: Some(bindings.clear_all_trace_data),
+            clear_dns_cache: Some(bindings.clear_dns_cache),
+            clear_selection: Some(bindings.clear_selection),
+            toggle_as_info: Some(bindings.toggle_as_info),
+            toggle_hop_details: Some(bindings.toggle_hop_details),
+            export_report: Some(bindings.export_report),
+            quit: Some(bindings.quit),
+        }
+    }
+}
+
 /// Tui key binding.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Deserialize)]
 #[serde(try_from = "String")]
@@ -1138,6 +2574,271 @@ mod tests {
         Ok(())
     }
 
+    #[test_case(0x00, EcnCodepoint::Ect0, 0x02; "set ect0")]
+    #[test_case(0x00, EcnCodepoint::Ect1, 0x01; "set ect1")]
+    #[test_case(0x00, EcnCodepoint::Ce, 0x03; "set ce")]
+    #[test_case(0xb9, EcnCodepoint::Ect0, 0xba; "replace ecn and keep dscp")]
+    #[test_case(0xbb, EcnCodepoint::NotEct, 0xb8; "clear ecn and keep dscp")]
+    fn test_ecn_apply_to_tos(tos: u8, ecn: EcnCodepoint, expected: u8) {
+        let tos = ecn.apply_to(tos);
+        assert_eq!(tos, expected);
+        assert_eq!(EcnCodepoint::from_tos(tos), ecn);
+    }
+
+    #[test_case(0x00, "cs0"; "default")]
+    #[test_case(0xb8, "ef"; "expedited forwarding")]
+    #[test_case(0xba, "ef"; "expedited forwarding with ect0")]
+    #[test_case(0x88, "af41"; "assured forwarding")]
+    #[test_case(0x04, "le"; "lower effort")]
+    #[test_case(0x34, "13"; "non standard")]
+    fn test_dscp_from_tos(tos: u8, expected: &str) {
+        assert_eq!(Dscp::from_tos(tos).to_string(), expected);
+    }
+
+    #[test_case("unresponsive", true, false, &[]; "unresponsive")]
+    #[test_case("private", false, true, &[]; "private")]
+    #[test_case("pattern=example.net", false, false, &["example.net"]; "pattern")]
+    #[test_case("unresponsive, private,pattern=10.0.,pattern=lab", true, true, &["10.0.", "lab"]; "all terms")]
+    fn test_hop_filter(
+        input: &str,
+        unresponsive: bool,
+        private: bool,
+        patterns: &[&str],
+    ) -> anyhow::Result<()> {
+        let filter = HopFilter::try_from(input)?;
+        assert_eq!(filter.unresponsive, unresponsive);
+        assert_eq!(filter.private, private);
+        assert_eq!(filter.patterns, patterns);
+        Ok(())
+    }
+
+    #[test_case(""; "empty")]
+    #[test_case("pattern="; "empty pattern")]
+    #[test_case("unresponsive,silent"; "unknown term")]
+    fn test_hop_filter_invalid(input: &str) {
+        assert!(HopFilter::try_from(input).is_err());
+    }
+
+    #[test]
+    fn test_hop_filter_display() -> anyhow::Result<()> {
+        let filter = HopFilter::try_from("pattern=lab,private")?;
+        assert_eq!(filter.to_string(), "private,pattern=lab");
+        Ok(())
+    }
+
+    #[test_case("darkgray", "darkgray"; "named")]
+    #[test_case("Light-Blue", "lightblue"; "named with separator")]
+    #[test_case("1E90ff", "1e90ff"; "rgb")]
+    fn test_tui_color_display(input: &str, expected: &str) -> anyhow::Result<()> {
+        assert_eq!(TuiColor::try_from(input)?.to_string(), expected);
+        Ok(())
+    }
+
+    #[test_case("http://localhost", "localhost", 4318, "/v1/metrics"; "host")]
+    #[test_case("http://localhost:4000/", "localhost", 4000, "/v1/metrics"; "host and port")]
+    #[test_case("http://10.0.0.1:4318/otlp/v1/metrics", "10.0.0.1", 4318, "/otlp/v1/metrics"; "path")]
+    #[test_case("http://[2001:db8::1]:4318", "2001:db8::1", 4318, "/v1/metrics"; "ipv6 and port")]
+    #[test_case("http://[2001:db8::1]", "2001:db8::1", 4318, "/v1/metrics"; "ipv6")]
+    fn test_otlp_endpoint(input: &str, host: &str, port: u16, path: &str) -> anyhow::Result<()> {
+        let endpoint = OtlpEndpoint::try_from(input)?;
+        assert_eq!(endpoint.host, host);
+        assert_eq!(endpoint.port, port);
+        assert_eq!(endpoint.path, path);
+        Ok(())
+    }
+
+    #[test_case("localhost:4318"; "no scheme")]
+    #[test_case("https://localhost:4318"; "https")]
+    #[test_case("http://:4318"; "no host")]
+    #[test_case("http://localhost:otlp"; "invalid port")]
+    fn test_otlp_endpoint_invalid(input: &str) {
+        assert!(OtlpEndpoint::try_from(input).is_err());
+    }
+
+    #[test_case("local", "local"; "local")]
+    #[test_case("udp://syslog.example.com", "udp://syslog.example.com:514"; "udp")]
+    #[test_case("tcp://10.0.0.1:601", "tcp://10.0.0.1:601"; "tcp and port")]
+    #[test_case("udp://[2001:db8::1]", "udp://[2001:db8::1]:514"; "ipv6")]
+    fn test_syslog_target(input: &str, expected: &str) -> anyhow::Result<()> {
+        assert_eq!(SyslogTarget::try_from(input)?.to_string(), expected);
+        Ok(())
+    }
+
+    #[test_case("syslog.example.com:514"; "no scheme")]
+    #[test_case("udp://:514"; "no host")]
+    #[test_case("tcp://localhost:syslog"; "invalid port")]
+    fn test_syslog_target_invalid(input: &str) {
+        assert!(SyslogTarget::try_from(input).is_err());
+    }
+
+    #[test_case(RttUnit::Ms, 1, 12.345, "12.3", "12.3ms"; "milliseconds")]
+    #[test_case(RttUnit::Ms, 0, 12.4, "12", "12ms"; "milliseconds without decimals")]
+    #[test_case(RttUnit::Us, 1, 0.012_34, "12.3", "12.3µs"; "microseconds")]
+    #[test_case(RttUnit::Us, 3, 0.5, "500.000", "500.000µs"; "microseconds with decimals")]
+    fn test_rtt_format(unit: RttUnit, precision: u8, rtt_ms: f64, expected: &str, with_unit: &str) {
+        let format = RttFormat::new(unit, precision);
+        assert_eq!(format.format(rtt_ms), expected);
+        assert_eq!(format.format_with_unit(rtt_ms), with_unit);
+    }
+
+    #[test_case("example.com", "example.com", None, None; "host")]
+    #[test_case("example.com:443", "example.com", Some(443), None; "host and port")]
+    #[test_case("1.2.3.4:53", "1.2.3.4", Some(53), None; "ipv4 and port")]
+    #[test_case("2001:db8::1", "2001:db8::1", None, None; "ipv6")]
+    #[test_case("[2001:db8::1]", "2001:db8::1", None, None; "bracketed ipv6")]
+    #[test_case("[2001:db8::1]:443", "2001:db8::1", Some(443), None; "bracketed ipv6 and port")]
+    #[test_case("https://example.com", "example.com", None, Some(443); "https url")]
+    #[test_case("http://example.com/path?query#fragment", "example.com", None, Some(80); "http url with path")]
+    #[test_case("HTTPS://example.com:8443/path", "example.com", Some(8443), Some(443); "url with port")]
+    #[test_case("ssh://user@example.com", "example.com", None, Some(22); "url with userinfo")]
+    #[test_case("https://[2001:db8::1]:8443/", "2001:db8::1", Some(8443), Some(443); "url with ipv6 and port")]
+    #[test_case("https://example.com/24", "example.com", None, Some(443); "url with numeric path")]
+    #[test_case("192.0.2.0/28", "192.0.2.0/28", None, None; "cidr")]
+    fn test_parse_target(
+        target: &str,
+        host: &str,
+        port: Option<u16>,
+        scheme_port: Option<u16>,
+    ) -> anyhow::Result<()> {
+        let expected = TargetSpec {
+            host: host.to_string(),
+            protocol: None,
+            port,
+            scheme_port,
+            group: None,
+        };
+        assert_eq!(expected, parse_target(target)?);
+        Ok(())
+    }
+
+    #[test_case("icmp://example.com", "example.com", Protocol::Icmp, None; "icmp")]
+    #[test_case("tcp://example.com:443", "example.com", Protocol::Tcp, Some(443); "tcp and port")]
+    #[test_case("UDP://[2001:db8::1]:53", "2001:db8::1", Protocol::Udp, Some(53); "udp ipv6 and port")]
+    #[test_case("icmp://192.0.2.0/28", "192.0.2.0/28", Protocol::Icmp, None; "icmp cidr")]
+    fn test_parse_target_protocol(
+        target: &str,
+        host: &str,
+        protocol: Protocol,
+        port: Option<u16>,
+    ) -> anyhow::Result<()> {
+        let expected = TargetSpec {
+            host: host.to_string(),
+            protocol: Some(protocol),
+            port,
+            scheme_port: None,
+            group: None,
+        };
+        assert_eq!(expected, parse_target(target)?);
+        Ok(())
+    }
+
+    #[test_case("192.0.2.0/30", &["192.0.2.1", "192.0.2.2"]; "ipv4")]
+    #[test_case("192.0.2.6/30", &["192.0.2.5", "192.0.2.6"]; "ipv4 host address")]
+    #[test_case("192.0.2.4/31", &["192.0.2.4", "192.0.2.5"]; "ipv4 point to point")]
+    #[test_case("192.0.2.7/32", &["192.0.2.7"]; "ipv4 single host")]
+    #[test_case("2001:db8::/127", &["2001:db8::", "2001:db8::1"]; "ipv6")]
+    #[test_case("example.com", &["example.com"]; "not a block")]
+    fn test_expand_cidr_target(host: &str, expected: &[&str]) -> anyhow::Result<()> {
+        let target = TargetSpec {
+            host: host.to_string(),
+            protocol: None,
+            port: None,
+            scheme_port: None,
+            group: None,
+        };
+        let hosts = expand_cidr_target(target)?
+            .into_iter()
+            .map(|target| target.host)
+            .collect::<Vec<_>>();
+        assert_eq!(expected, hosts);
+        Ok(())
+    }
+
+    #[test_case("192.0.2.0/33"; "prefix too long")]
+    #[test_case("192.0.2.0/23"; "too many hosts")]
+    #[test_case("2001:db8::/64"; "too many ipv6 hosts")]
+    #[test_case("example.com/24"; "hostname")]
+    fn test_expand_invalid_cidr_target(host: &str) {
+        let target = TargetSpec {
+            host: host.to_string(),
+            protocol: None,
+            port: None,
+            scheme_port: None,
+            group: None,
+        };
+        assert!(expand_cidr_target(target).is_err());
+    }
+
+    #[test_case("443", &[443]; "single port")]
+    #[test_case("22, 80,443", &[22, 80, 443]; "list")]
+    #[test_case("8000-8002,22", &[8000, 8001, 8002, 22]; "range")]
+    #[test_case("80,79-81", &[80, 79, 81]; "duplicates")]
+    fn test_parse_port_list(ports: &str, expected: &[u16]) -> anyhow::Result<()> {
+        assert_eq!(expected, parse_port_list(ports)?);
+        Ok(())
+    }
+
+    #[test_case(""; "empty")]
+    #[test_case("0"; "zero")]
+    #[test_case("80-22"; "reversed range")]
+    #[test_case("http"; "not a number")]
+    #[test_case("1-65"; "too many ports")]
+    fn test_parse_invalid_port_list(ports: &str) {
+        assert!(parse_port_list(ports).is_err());
+    }
+
+    #[test_case("84-1024", 5, &[84, 319, 554, 789, 1024]; "default")]
+    #[test_case("100 - 200", 2, &[100, 200]; "endpoints")]
+    #[test_case("100-103", 8, &[100, 101, 102, 103]; "more steps than sizes")]
+    fn test_parse_sweep_sizes(range: &str, steps: u8, expected: &[u16]) -> anyhow::Result<()> {
+        assert_eq!(expected, parse_sweep_sizes(range, steps)?);
+        Ok(())
+    }
+
+    #[test_case("84", 5; "not a range")]
+    #[test_case("1024-84", 5; "reversed range")]
+    #[test_case("20-1024", 5; "too small")]
+    #[test_case("84-1500", 5; "too large")]
+    #[test_case("84-1024", 1; "too few steps")]
+    #[test_case("84-1024", 17; "too many steps")]
+    fn test_parse_invalid_sweep_sizes(range: &str, steps: u8) {
+        assert!(parse_sweep_sizes(range, steps).is_err());
+    }
+
+    #[test_case("example.com:"; "empty port")]
+    #[test_case("example.com:https"; "named port")]
+    #[test_case("example.com:65536"; "port out of range")]
+    #[test_case("[2001:db8::1"; "unclosed bracket")]
+    #[test_case("[2001:db8::1]443"; "missing colon")]
+    #[test_case("gopher://example.com"; "unknown scheme")]
+    #[test_case("https:///path"; "url without host")]
+    fn test_parse_invalid_target(target: &str) {
+        assert!(parse_target(target).is_err());
+    }
+
+    #[test_case(&["trip", "example.com"], ""; "defaults")]
+    #[test_case(&["trip", "example.com", "--udp", "--target-port", "53", "--max-duration", "1m30s"], ""; "options")]
+    #[test_case(&["trip", "-m", "size-sweep", "example.com"], ""; "size sweep")]
+    #[test_case(&["trip"], "[trippy]\ntargets = [\"example.com\"]\n[groups]\nlab = [\"192.0.2.1\"]\n"; "groups")]
+    fn test_print_config_round_trip(args: &[&str], cfg_file: &str) -> anyhow::Result<()> {
+        let (_, effective) =
+            TrippyConfig::merge(Args::try_parse_from(args)?, toml::from_str(cfg_file)?, 1234)?;
+        let printed = toml::to_string(&effective)?;
+        assert!(printed.starts_with("[trippy]\n"));
+        assert!(!printed.contains("icmp-identifier"));
+        assert_eq!(
+            printed.contains("sweep-range"),
+            args.contains(&"size-sweep")
+        );
+        let (_, reread) = TrippyConfig::merge(
+            Args::try_parse_from(["trip"])?,
+            toml::from_str(&printed)?,
+            1234,
+        )?;
+        assert_eq!(printed, toml::to_string(&reread)?);
+        Ok(())
+    }
+
     #[test]
     fn test_unknown_modifier() {
         let binding = TuiKeyBinding::try_from("foo+c");
@@ -1206,6 +2907,12 @@ impl Display for TuiKeyBinding {
     }
 }
 
+impl Serialize for TuiKeyBinding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 /// A Tui command that can be bound to a key.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, EnumString, EnumVariantNames)]
 #[strum(serialize_all = "kebab-case")]
@@ -1240,6 +2947,20 @@ pub enum TuiCommandItem {
     ToggleChart,
     /// Toggle the map.
     ToggleMap,
+    /// Toggle the route changes.
+    ToggleRouteChanges,
+    /// Toggle the DNS lookups.
+    ToggleDns,
+    /// Toggle the diagnostics.
+    ToggleDiagnostics,
+    /// Toggle the multi-pane layout.
+    TogglePanes,
+    /// Toggle comparing the selected trace with another trace side by side.
+    ToggleCompare,
+    /// Toggle the overview of all traces by group.
+    ToggleOverview,
+    /// Toggle hiding hops which match the hop filter.
+    ToggleHideHops,
     /// Expand hosts.
     ExpandHosts,
     /// Expand hosts to max.
@@ -1254,6 +2975,8 @@ pub enum TuiCommandItem {
     ChartZoomOut,
     /// Clear all tracing data.
     ClearTraceData,
+    /// Clear all tracing data for all targets.
+    ClearAllTraceData,
     /// Clear DNS cache.
     ClearDnsCache,
     /// Clear hop selection.
@@ -1262,18 +2985,24 @@ pub enum TuiCommandItem {
     ToggleASInfo,
     /// Toggle hop details.
     ToggleHopDetails,
+    /// Export a report of the selected trace to a file.
+    ExportReport,
     /// Quit the application.
     Quit,
 }
 
 pub mod config_file {
     use crate::config::{
-        AddressFamily, AddressMode, AsMode, DnsResolveMethod, GeoIpMode, Mode,
-        MultipathStrategyConfig, Protocol, TuiColor, TuiKeyBinding,
+        AddressFamily, AddressMode, AsMode, DnsResolveMethod, EcnCodepoint, GeoIpMode,
+        Ipv6ExtHeaderConfig, Ipv6SourcePreferenceConfig, Locale, Mode, MultipathStrategyConfig,
+        ProhibitedStrategyConfig, Protocol, ReResolveStrategy, ReportBorder, ReportColumn,
+        ReportOverflow, RttUnit, SequenceStrategyConfig, StatsdFlavor, SyslogFacility,
+        TimestampMode, TuiColor, TuiExportFormat, TuiKeyBinding, TuiPalette, TuiPane,
     };
     use anyhow::Context;
     use etcetera::BaseStrategy;
-    use serde::Deserialize;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
     use std::fs::File;
     use std::io::read_to_string;
     use std::path::Path;
@@ -1336,7 +3065,7 @@ pub mod config_file {
         }
     }
 
-    #[derive(Debug, Default, Deserialize)]
+    #[derive(Debug, Default, Deserialize, Serialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct ConfigFile {
         pub trippy: Option<ConfigTrippy>,
@@ -1346,15 +3075,44 @@ pub mod config_file {
         pub tui: Option<ConfigTui>,
         pub dns: Option<ConfigDns>,
         pub report: Option<ConfigReport>,
+        pub hooks: Option<ConfigHooks>,
+        pub export: Option<ConfigExport>,
+        pub groups: Option<BTreeMap<String, Vec<String>>>,
     }
 
-    #[derive(Debug, Default, Deserialize)]
+    #[derive(Debug, Default, Deserialize, Serialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct ConfigTrippy {
+        pub targets: Option<Vec<String>>,
         pub mode: Option<Mode>,
+        pub plain: Option<bool>,
+        pub rtt_unit: Option<RttUnit>,
+        pub rtt_precision: Option<u8>,
+        pub locale: Option<Locale>,
+        pub exit_summary: Option<bool>,
+        pub web: Option<String>,
+        pub api: Option<String>,
+        pub agent: Option<String>,
+        pub agent_insecure: Option<bool>,
+        pub remote: Option<String>,
+        pub agent_token: Option<String>,
+        pub max_duration: Option<String>,
+        pub max_unreachable_rounds: Option<usize>,
+        pub reset_interval: Option<String>,
+        pub unreachable_backoff: Option<String>,
+        pub re_resolve_every: Option<usize>,
+        pub re_resolve_strategy: Option<ReResolveStrategy>,
+        pub verbose: Option<u8>,
+        pub log_file: Option<String>,
+        pub packet_dump: Option<bool>,
+        pub uid: Option<u32>,
+        pub annotations_file: Option<String>,
+        pub cache_file: Option<String>,
+        pub cache_ttl: Option<String>,
+        pub state_file: Option<String>,
     }
 
-    #[derive(Debug, Default, Deserialize)]
+    #[derive(Debug, Default, Deserialize, Serialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct ConfigStrategy {
         pub protocol: Option<Protocol>,
@@ -1363,48 +3121,121 @@ pub mod config_file {
         pub source_port: Option<u16>,
         pub source_address: Option<String>,
         pub interface: Option<String>,
+        pub ipv6_source_preference: Option<Ipv6SourcePreferenceConfig>,
+        pub ipv6_ext_header: Option<Ipv6ExtHeaderConfig>,
+        pub record_route: Option<bool>,
+        pub do_not_fragment: Option<bool>,
+        pub icmp_timestamp: Option<bool>,
         pub min_round_duration: Option<String>,
         pub max_round_duration: Option<String>,
+        pub round_jitter: Option<String>,
+        pub probe_timeout: Option<String>,
+        pub retries: Option<u8>,
+        pub backfill_late: Option<bool>,
         pub initial_sequence: Option<u16>,
+        pub sequence_strategy: Option<SequenceStrategyConfig>,
+        pub icmp_identifier: Option<u16>,
         pub multipath_strategy: Option<MultipathStrategyConfig>,
         pub grace_duration: Option<String>,
         pub max_inflight: Option<u8>,
+        pub adaptive_inflight: Option<bool>,
+        pub burst: Option<bool>,
         pub first_ttl: Option<u8>,
         pub max_ttl: Option<u8>,
+        pub destination_only: Option<bool>,
+        pub adaptive_ttl: Option<bool>,
+        pub prohibited_strategy: Option<ProhibitedStrategyConfig>,
         pub packet_size: Option<u16>,
+        pub sweep_range: Option<String>,
+        pub sweep_steps: Option<u8>,
         pub payload_pattern: Option<u8>,
         pub tos: Option<u8>,
+        pub ecn: Option<EcnCodepoint>,
+        pub tcp_mss: Option<u16>,
+        pub tcp_window_size: Option<u32>,
         pub read_timeout: Option<String>,
     }
 
-    #[derive(Debug, Default, Deserialize)]
+    #[derive(Debug, Default, Deserialize, Serialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct ConfigDns {
         pub dns_resolve_method: Option<DnsResolveMethod>,
         pub dns_lookup_as_info: Option<bool>,
         pub dns_timeout: Option<String>,
+        pub dns_hosts_file: Option<String>,
+        pub dns_max_in_flight: Option<usize>,
+        pub dns_source_address: Option<String>,
+        pub dns_interface: Option<String>,
     }
 
-    #[derive(Debug, Default, Deserialize)]
+    #[derive(Debug, Default, Deserialize, Serialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct ConfigReport {
         pub report_cycles: Option<usize>,
+        pub report_timestamp: Option<TimestampMode>,
+        pub report_timestamp_format: Option<String>,
+        pub report_columns: Option<Vec<ReportColumn>>,
+        pub report_width: Option<u16>,
+        pub report_overflow: Option<ReportOverflow>,
+        pub report_border: Option<ReportBorder>,
+        pub report_include_samples: Option<bool>,
+        pub report_per_address: Option<bool>,
+        pub template: Option<String>,
+        pub report_file: Option<String>,
+        pub repeat_every: Option<String>,
+        pub repeat_count: Option<usize>,
+    }
+
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct ConfigHooks {
+        pub on_route_change: Option<String>,
+        pub on_as_path_change: Option<String>,
+        pub on_threshold_breach: Option<String>,
+        pub on_target_unreachable: Option<String>,
+        pub threshold_loss_pct: Option<f64>,
+        pub threshold_latency: Option<String>,
+        pub syslog: Option<String>,
+        pub syslog_facility: Option<SyslogFacility>,
+    }
+
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct ConfigExport {
+        pub otlp_endpoint: Option<String>,
+        pub otlp_interval: Option<String>,
+        pub statsd_addr: Option<String>,
+        pub statsd_flavor: Option<StatsdFlavor>,
+        pub statsd_prefix: Option<String>,
+        pub mqtt_broker: Option<String>,
+        pub mqtt_topic: Option<String>,
+        pub mqtt_username: Option<String>,
+        pub mqtt_password: Option<String>,
     }
 
-    #[derive(Debug, Default, Deserialize)]
+    #[derive(Debug, Default, Deserialize, Serialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct ConfigTui {
         pub tui_max_samples: Option<usize>,
         pub tui_preserve_screen: Option<bool>,
+        pub tui_title: Option<bool>,
+        pub tui_export_format: Option<TuiExportFormat>,
+        pub tui_export_dir: Option<String>,
+        pub tui_seen_columns: Option<bool>,
+        pub tui_ascii: Option<bool>,
+        pub tui_palette: Option<TuiPalette>,
         pub tui_refresh_rate: Option<String>,
         pub tui_address_mode: Option<AddressMode>,
         pub tui_as_mode: Option<AsMode>,
         pub tui_geoip_mode: Option<GeoIpMode>,
         pub tui_max_addrs: Option<u8>,
+        pub tui_hide_hops: Option<String>,
+        pub tui_panes: Option<Vec<TuiPane>>,
+        pub tui_pane_split: Option<u16>,
         pub geoip_mmdb_file: Option<String>,
     }
 
-    #[derive(Debug, Default, Deserialize)]
+    #[derive(Debug, Default, Deserialize, Serialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct ConfigThemeColors {
         pub bg_color: Option<TuiColor>,
@@ -1436,7 +3267,7 @@ pub mod config_file {
         pub map_info_panel_text_color: Option<TuiColor>,
     }
 
-    #[derive(Debug, Default, Deserialize)]
+    #[derive(Debug, Default, Deserialize, Serialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct ConfigBindings {
         pub toggle_help: Option<TuiKeyBinding>,
@@ -1453,6 +3284,13 @@ pub mod config_file {
         pub toggle_freeze: Option<TuiKeyBinding>,
         pub toggle_chart: Option<TuiKeyBinding>,
         pub toggle_map: Option<TuiKeyBinding>,
+        pub toggle_route_changes: Option<TuiKeyBinding>,
+        pub toggle_dns: Option<TuiKeyBinding>,
+        pub toggle_diagnostics: Option<TuiKeyBinding>,
+        pub toggle_panes: Option<TuiKeyBinding>,
+        pub toggle_compare: Option<TuiKeyBinding>,
+        pub toggle_overview: Option<TuiKeyBinding>,
+        pub toggle_hide_hops: Option<TuiKeyBinding>,
         pub expand_hosts: Option<TuiKeyBinding>,
         pub contract_hosts: Option<TuiKeyBinding>,
         pub expand_hosts_max: Option<TuiKeyBinding>,
@@ -1460,10 +3298,12 @@ pub mod config_file {
         pub chart_zoom_in: Option<TuiKeyBinding>,
         pub chart_zoom_out: Option<TuiKeyBinding>,
         pub clear_trace_data: Option<TuiKeyBinding>,
+        pub clear_all_trace_data: Option<TuiKeyBinding>,
         pub clear_dns_cache: Option<TuiKeyBinding>,
         pub clear_selection: Option<TuiKeyBinding>,
         pub toggle_as_info: Option<TuiKeyBinding>,
         pub toggle_hop_details: Option<TuiKeyBinding>,
+        pub export_report: Option<TuiKeyBinding>,
         pub quit: Option<TuiKeyBinding>,
     }
 }
@@ -1471,9 +3311,9 @@ pub mod config_file {
 impl TryFrom<(Args, u16)> for TrippyConfig {
     type Error = anyhow::Error;
 
-    #[allow(clippy::too_many_lines)]
     fn try_from(data: (Args, u16)) -> Result<Self, Self::Error> {
         let (args, pid) = data;
+        let print_config = args.print_config;
         if args.print_tui_theme_items {
             println!(
                 "TUI theme color items: {}",
@@ -1493,13 +3333,33 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
             print_completions(generator, &mut cmd);
             process::exit(0);
         }
-        let cfg_file = if let Some(cfg) = args.config_file {
+        if args.list_interfaces {
+            print_interfaces()?;
+            process::exit(0);
+        }
+        let cfg_file = if let Some(cfg) = &args.config_file {
             config_file::read_config_file(cfg)?
         } else if let Some(cfg) = config_file::read_default_config_file()? {
             cfg
         } else {
             ConfigFile::default()
         };
+        let (cfg, effective) = Self::merge(args, cfg_file, pid)?;
+        if let Some(format) = print_config {
+            print_config_as(&effective, format)?;
+            process::exit(0);
+        }
+        Ok(cfg)
+    }
+}
+
+impl TrippyConfig {
+    /// Merge the command line `args` over the config file `cfg_file` and the defaults.
+    ///
+    /// The effective configuration is also returned in the layout of a config file, such that it may be printed and
+    /// then read back as a config file.
+    #[allow(clippy::too_many_lines)]
+    fn merge(args: Args, cfg_file: ConfigFile, pid: u16) -> anyhow::Result<(Self, ConfigFile)> {
         let cfg_file_trace = cfg_file.trippy.unwrap_or_default();
         let cfg_file_strategy = cfg_file.strategy.unwrap_or_default();
         let cfg_file_tui_bindings = cfg_file.bindings.unwrap_or_default();
@@ -1507,16 +3367,178 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
         let cfg_file_tui = cfg_file.tui.unwrap_or_default();
         let cfg_file_dns = cfg_file.dns.unwrap_or_default();
         let cfg_file_report = cfg_file.report.unwrap_or_default();
+        let cfg_file_hooks = cfg_file.hooks.unwrap_or_default();
+        let cfg_file_export = cfg_file.export.unwrap_or_default();
         let mode = cfg_layer(args.mode, cfg_file_trace.mode, DEFAULT_MODE);
-        let protocol = cfg_layer(
-            args.protocol,
-            cfg_file_strategy.protocol,
-            DEFAULT_STRATEGY_PROTOCOL,
+        let plain = cfg_layer_opt(args.plain, cfg_file_trace.plain).unwrap_or_else(no_color);
+        let rtt_unit = cfg_layer(args.rtt_unit, cfg_file_trace.rtt_unit, DEFAULT_RTT_UNIT);
+        let rtt_precision = cfg_layer(
+            args.rtt_precision,
+            cfg_file_trace.rtt_precision,
+            DEFAULT_RTT_PRECISION,
+        );
+        let locale = cfg_layer(args.locale, cfg_file_trace.locale, DEFAULT_LOCALE);
+        let exit_summary = cfg_layer(
+            args.exit_summary,
+            cfg_file_trace.exit_summary,
+            DEFAULT_EXIT_SUMMARY,
+        );
+        let web = cfg_layer_opt(args.web, cfg_file_trace.web)
+            .map(|addr| {
+                SocketAddr::from_str(&addr).map_err(|_| anyhow!("invalid web address: {}", addr))
+            })
+            .transpose()?;
+        let api = cfg_layer_opt(args.api, cfg_file_trace.api)
+            .map(|addr| {
+                SocketAddr::from_str(&addr).map_err(|_| anyhow!("invalid api address: {}", addr))
+            })
+            .transpose()?;
+        let agent = cfg_layer_opt(args.agent, cfg_file_trace.agent)
+            .map(|addr| {
+                SocketAddr::from_str(&addr).map_err(|_| anyhow!("invalid agent address: {}", addr))
+            })
+            .transpose()?;
+        let agent_insecure = cfg_layer(
+            args.agent_insecure,
+            cfg_file_trace.agent_insecure,
+            DEFAULT_AGENT_INSECURE,
+        );
+        let remote = cfg_layer_opt(args.remote, cfg_file_trace.remote);
+        let agent_token = cfg_layer_opt(args.agent_token, cfg_file_trace.agent_token);
+        let max_duration = cfg_layer_opt(args.max_duration, cfg_file_trace.max_duration);
+        let max_unreachable_rounds = cfg_layer_opt(
+            args.max_unreachable_rounds,
+            cfg_file_trace.max_unreachable_rounds,
+        );
+        let reset_interval = cfg_layer_opt(args.reset_interval, cfg_file_trace.reset_interval);
+        let unreachable_backoff =
+            cfg_layer_opt(args.unreachable_backoff, cfg_file_trace.unreachable_backoff);
+        let re_resolve_every =
+            cfg_layer_opt(args.re_resolve_every, cfg_file_trace.re_resolve_every);
+        let re_resolve_strategy = cfg_layer(
+            args.re_resolve_strategy,
+            cfg_file_trace.re_resolve_strategy,
+            DEFAULT_RE_RESOLVE_STRATEGY,
+        );
+        let verbose = cfg_layer(
+            (args.verbose > 0).then_some(args.verbose),
+            cfg_file_trace.verbose,
+            DEFAULT_VERBOSE,
+        );
+        let log_file = cfg_layer_opt(args.log_file, cfg_file_trace.log_file);
+        let packet_dump = cfg_layer(
+            args.packet_dump,
+            cfg_file_trace.packet_dump,
+            DEFAULT_PACKET_DUMP,
+        );
+        let uid = cfg_layer_opt(args.uid, cfg_file_trace.uid);
+        let annotations_file =
+            cfg_layer_opt(args.annotations_file, cfg_file_trace.annotations_file);
+        let cache_file = cfg_layer_opt(args.cache_file, cfg_file_trace.cache_file);
+        let cache_ttl = cfg_layer(
+            args.cache_ttl,
+            cfg_file_trace.cache_ttl,
+            String::from(DEFAULT_CACHE_TTL),
+        );
+        let state_file = cfg_layer_opt(args.state_file, cfg_file_trace.state_file);
+        let resume = args.resume;
+        let on_route_change = cfg_layer_opt(args.on_route_change, cfg_file_hooks.on_route_change);
+        let on_as_path_change =
+            cfg_layer_opt(args.on_as_path_change, cfg_file_hooks.on_as_path_change);
+        let on_threshold_breach =
+            cfg_layer_opt(args.on_threshold_breach, cfg_file_hooks.on_threshold_breach);
+        let on_target_unreachable = cfg_layer_opt(
+            args.on_target_unreachable,
+            cfg_file_hooks.on_target_unreachable,
+        );
+        let threshold_loss_pct =
+            cfg_layer_opt(args.threshold_loss_pct, cfg_file_hooks.threshold_loss_pct);
+        let threshold_latency =
+            cfg_layer_opt(args.threshold_latency, cfg_file_hooks.threshold_latency);
+        let syslog = cfg_layer_opt(args.syslog, cfg_file_hooks.syslog)
+            .as_deref()
+            .map(SyslogTarget::try_from)
+            .transpose()?;
+        let syslog_facility = cfg_layer(
+            args.syslog_facility,
+            cfg_file_hooks.syslog_facility,
+            DEFAULT_SYSLOG_FACILITY,
+        );
+        let otlp_endpoint = cfg_layer_opt(args.otlp_endpoint, cfg_file_export.otlp_endpoint)
+            .as_deref()
+            .map(OtlpEndpoint::try_from)
+            .transpose()?;
+        let otlp_interval = cfg_layer(
+            args.otlp_interval,
+            cfg_file_export.otlp_interval,
+            String::from(DEFAULT_OTLP_INTERVAL),
+        );
+        let statsd_addr = cfg_layer_opt(args.statsd_addr, cfg_file_export.statsd_addr);
+        let statsd_flavor = cfg_layer(
+            args.statsd_flavor,
+            cfg_file_export.statsd_flavor,
+            DEFAULT_STATSD_FLAVOR,
         );
+        let statsd_prefix = cfg_layer(
+            args.statsd_prefix,
+            cfg_file_export.statsd_prefix,
+            String::from(DEFAULT_STATSD_PREFIX),
+        );
+        let mqtt_broker = cfg_layer_opt(args.mqtt_broker, cfg_file_export.mqtt_broker);
+        let mqtt_topic = cfg_layer(
+            args.mqtt_topic,
+            cfg_file_export.mqtt_topic,
+            String::from(DEFAULT_MQTT_TOPIC),
+        );
+        let mqtt_username = cfg_layer_opt(args.mqtt_username, cfg_file_export.mqtt_username);
+        let mqtt_password = cfg_layer_opt(args.mqtt_password, cfg_file_export.mqtt_password);
+        // the groups of targets in the config file are only traced along with the targets of the config file
+        let (targets, groups) = match (args.targets.is_empty(), &state_file) {
+            (false, _) => (args.targets, BTreeMap::new()),
+            (true, Some(path)) if resume => (Session::load(path)?.targets(), BTreeMap::new()),
+            (true, _) => (
+                cfg_file_trace.targets.unwrap_or_default(),
+                cfg_file.groups.unwrap_or_default(),
+            ),
+        };
+        validate_groups(&groups)?;
+        let config_targets = targets.clone();
+        let targets = targets
+            .iter()
+            .map(|target| parse_target(target).and_then(expand_cidr_target))
+            .chain(groups.iter().flat_map(|(group, targets)| {
+                targets.iter().map(|target| {
+                    let target = parse_target(target)?;
+                    expand_cidr_target(TargetSpec {
+                        group: Some(group.clone()),
+                        ..target
+                    })
+                })
+            }))
+            .flatten_ok()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let protocol = cfg_layer_opt(args.protocol, cfg_file_strategy.protocol);
         let target_port = cfg_layer_opt(args.target_port, cfg_file_strategy.target_port);
         let source_port = cfg_layer_opt(args.source_port, cfg_file_strategy.source_port);
         let source_address = cfg_layer_opt(args.source_address, cfg_file_strategy.source_address);
-        let interface = cfg_layer_opt(args.interface, cfg_file_strategy.interface);
+        let interface = cfg_layer_opt(args.interface, cfg_file_strategy.interface)
+            .map(|interface| Interface::resolve(&interface))
+            .transpose()?;
+        let ipv6_source_preference = cfg_layer_opt(
+            args.ipv6_source_preference,
+            cfg_file_strategy.ipv6_source_preference,
+        )
+        .map(|preference| match preference {
+            Ipv6SourcePreferenceConfig::Temporary => Ipv6SourcePreference::Temporary,
+            Ipv6SourcePreferenceConfig::Stable => Ipv6SourcePreference::Stable,
+        });
+        let ipv6_ext_header =
+            cfg_layer_opt(args.ipv6_ext_header, cfg_file_strategy.ipv6_ext_header).map(|header| {
+                match header {
+                    Ipv6ExtHeaderConfig::DstOpts => Ipv6ExtHeader::DestinationOptions,
+                    Ipv6ExtHeaderConfig::HopByHop => Ipv6ExtHeader::HopByHop,
+                }
+            });
         let min_round_duration = cfg_layer(
             args.min_round_duration,
             cfg_file_strategy.min_round_duration,
@@ -1527,11 +3549,37 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
             cfg_file_strategy.max_round_duration,
             String::from(DEFAULT_STRATEGY_MAX_ROUND_DURATION),
         );
+        let round_jitter = cfg_layer(
+            args.round_jitter,
+            cfg_file_strategy.round_jitter,
+            String::from(DEFAULT_STRATEGY_ROUND_JITTER),
+        );
+        let probe_timeout = cfg_layer_opt(args.probe_timeout, cfg_file_strategy.probe_timeout);
+        let retries = cfg_layer(
+            args.retries,
+            cfg_file_strategy.retries,
+            DEFAULT_STRATEGY_RETRIES,
+        );
+        let backfill_late = cfg_layer(
+            args.backfill_late,
+            cfg_file_strategy.backfill_late,
+            DEFAULT_STRATEGY_BACKFILL_LATE,
+        );
         let initial_sequence = cfg_layer(
             args.initial_sequence,
             cfg_file_strategy.initial_sequence,
             DEFAULT_STRATEGY_INITIAL_SEQUENCE,
         );
+        let sequence_strategy = match cfg_layer(
+            args.sequence_strategy,
+            cfg_file_strategy.sequence_strategy,
+            DEFAULT_STRATEGY_SEQUENCE_STRATEGY,
+        ) {
+            SequenceStrategyConfig::Sequential => SequenceStrategy::Sequential,
+            SequenceStrategyConfig::Random => SequenceStrategy::Random,
+        };
+        let icmp_identifier =
+            cfg_layer(args.icmp_identifier, cfg_file_strategy.icmp_identifier, pid);
         let multipath_strategy_cfg = cfg_layer(
             args.multipath_strategy,
             cfg_file_strategy.multipath_strategy,
@@ -1547,6 +3595,24 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
             cfg_file_strategy.max_inflight,
             DEFAULT_STRATEGY_MAX_INFLIGHT,
         );
+        let adaptive_inflight = cfg_layer(
+            args.adaptive_inflight,
+            cfg_file_strategy.adaptive_inflight,
+            DEFAULT_STRATEGY_ADAPTIVE_INFLIGHT,
+        );
+        let burst = cfg_layer(args.burst, cfg_file_strategy.burst, DEFAULT_STRATEGY_BURST);
+        let record_route = cfg_layer(
+            args.record_route,
+            cfg_file_strategy.record_route,
+            DEFAULT_STRATEGY_RECORD_ROUTE,
+        );
+        let do_not_fragment =
+            cfg_layer_opt(args.do_not_fragment, cfg_file_strategy.do_not_fragment);
+        let icmp_timestamp = cfg_layer(
+            args.icmp_timestamp,
+            cfg_file_strategy.icmp_timestamp,
+            DEFAULT_STRATEGY_ICMP_TIMESTAMP,
+        );
         let first_ttl = cfg_layer(
             args.first_ttl,
             cfg_file_strategy.first_ttl,
@@ -1557,17 +3623,58 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
             cfg_file_strategy.max_ttl,
             DEFAULT_STRATEGY_MAX_TTL,
         );
+        let firewalk_gateway = args.firewalk_gateway;
+        // firewalking probes only the hop just past the gateway
+        let (first_ttl, max_ttl) = match firewalk_gateway {
+            Some(gateway) => (gateway.saturating_add(1), gateway.saturating_add(1)),
+            None => (first_ttl, max_ttl),
+        };
+        let destination_only = cfg_layer(
+            args.destination_only,
+            cfg_file_strategy.destination_only,
+            DEFAULT_STRATEGY_DESTINATION_ONLY,
+        );
+        let adaptive_ttl = cfg_layer(
+            args.adaptive_ttl,
+            cfg_file_strategy.adaptive_ttl,
+            DEFAULT_STRATEGY_ADAPTIVE_TTL,
+        );
+        let prohibited_strategy = match cfg_layer(
+            args.prohibited_strategy,
+            cfg_file_strategy.prohibited_strategy,
+            DEFAULT_STRATEGY_PROHIBITED,
+        ) {
+            ProhibitedStrategyConfig::Stop => ProhibitedStrategy::Stop,
+            ProhibitedStrategyConfig::Continue => ProhibitedStrategy::Continue,
+        };
         let packet_size = cfg_layer(
             args.packet_size,
             cfg_file_strategy.packet_size,
             DEFAULT_STRATEGY_PACKET_SIZE,
         );
+        let sweep_range = cfg_layer(
+            args.sweep_range,
+            cfg_file_strategy.sweep_range,
+            String::from(DEFAULT_STRATEGY_SWEEP_RANGE),
+        );
+        let sweep_steps = cfg_layer(
+            args.sweep_steps,
+            cfg_file_strategy.sweep_steps,
+            DEFAULT_STRATEGY_SWEEP_STEPS,
+        );
+        let sweep_sizes = parse_sweep_sizes(&sweep_range, sweep_steps)?;
         let payload_pattern = cfg_layer(
             args.payload_pattern,
             cfg_file_strategy.payload_pattern,
             DEFAULT_STRATEGY_PAYLOAD_PATTERN,
         );
+        let ecn = cfg_layer_opt(args.ecn, cfg_file_strategy.ecn);
         let tos = cfg_layer(args.tos, cfg_file_strategy.tos, DEFAULT_STRATEGY_TOS);
+        // The ECN codepoint, if set, takes precedence over the ECN bits of the `tos` value.
+        let tos = ecn.map_or(tos, |ecn| ecn.apply_to(tos));
+        let tcp_mss = cfg_layer_opt(args.tcp_mss, cfg_file_strategy.tcp_mss);
+        let tcp_window_size =
+            cfg_layer_opt(args.tcp_window_size, cfg_file_strategy.tcp_window_size);
         let read_timeout = cfg_layer(
             args.read_timeout,
             cfg_file_strategy.read_timeout,
@@ -1583,6 +3690,28 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
             cfg_file_tui.tui_preserve_screen,
             DEFAULT_TUI_PRESERVE_SCREEN,
         );
+        let tui_title = cfg_layer(args.tui_title, cfg_file_tui.tui_title, DEFAULT_TUI_TITLE);
+        let tui_export_format = cfg_layer(
+            args.tui_export_format,
+            cfg_file_tui.tui_export_format,
+            DEFAULT_TUI_EXPORT_FORMAT,
+        );
+        let tui_export_dir = cfg_layer(
+            args.tui_export_dir,
+            cfg_file_tui.tui_export_dir,
+            String::from(DEFAULT_TUI_EXPORT_DIR),
+        );
+        let tui_seen_columns = cfg_layer(
+            args.tui_seen_columns,
+            cfg_file_tui.tui_seen_columns,
+            DEFAULT_TUI_SEEN_COLUMNS,
+        );
+        let tui_ascii = cfg_layer(args.tui_ascii, cfg_file_tui.tui_ascii, DEFAULT_TUI_ASCII);
+        let tui_palette = cfg_layer(
+            args.tui_palette,
+            cfg_file_tui.tui_palette,
+            DEFAULT_TUI_PALETTE,
+        );
         let tui_refresh_rate = cfg_layer(
             args.tui_refresh_rate,
             cfg_file_tui.tui_refresh_rate,
@@ -1604,6 +3733,21 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
             DEFAULT_TUI_GEOIP_MODE,
         );
         let tui_max_addrs = cfg_layer_opt(args.tui_max_addrs, cfg_file_tui.tui_max_addrs);
+        let tui_hide_hops = cfg_layer_opt(args.tui_hide_hops, cfg_file_tui.tui_hide_hops)
+            .map(|filter| HopFilter::try_from(filter.as_str()))
+            .transpose()?;
+        let tui_panes = if args.tui_panes.is_empty() {
+            cfg_file_tui
+                .tui_panes
+                .unwrap_or_else(|| DEFAULT_TUI_PANES.to_vec())
+        } else {
+            args.tui_panes
+        };
+        let tui_pane_split = cfg_layer(
+            args.tui_pane_split,
+            cfg_file_tui.tui_pane_split,
+            DEFAULT_TUI_PANE_SPLIT,
+        );
         let dns_resolve_method = cfg_layer(
             args.dns_resolve_method,
             cfg_file_dns.dns_resolve_method,
@@ -1619,20 +3763,88 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
             cfg_file_dns.dns_timeout,
             String::from(DEFAULT_DNS_TIMEOUT),
         );
+        let dns_hosts_file = cfg_layer_opt(args.dns_hosts_file, cfg_file_dns.dns_hosts_file);
+        let dns_max_in_flight = cfg_layer(
+            args.dns_max_in_flight,
+            cfg_file_dns.dns_max_in_flight,
+            DEFAULT_DNS_MAX_IN_FLIGHT,
+        );
+        let dns_source_address =
+            cfg_layer_opt(args.dns_source_address, cfg_file_dns.dns_source_address);
+        let dns_interface = cfg_layer_opt(args.dns_interface, cfg_file_dns.dns_interface);
+        let dns_source_addrs = match (&dns_source_address, &dns_interface) {
+            (Some(addr), _) => vec![IpAddr::from_str(addr)
+                .map_err(|_| anyhow!("invalid dns source IP address format: {}", addr))?],
+            (None, Some(interface)) => interface_addrs(&Interface::resolve(interface)?)?,
+            (None, None) => vec![],
+        };
         let report_cycles = cfg_layer(
             args.report_cycles,
             cfg_file_report.report_cycles,
             DEFAULT_REPORT_CYCLES,
         );
-        let geoip_mmdb_file = cfg_layer_opt(args.geoip_mmdb_file, cfg_file_tui.geoip_mmdb_file);
-        let protocol = match (args.udp, args.tcp, protocol) {
-            (false, false, Protocol::Icmp) => TracerProtocol::Icmp,
-            (false, false, Protocol::Udp) | (true, _, _) => TracerProtocol::Udp,
-            (false, false, Protocol::Tcp) | (_, true, _) => TracerProtocol::Tcp,
+        let report_timestamp = cfg_layer(
+            args.report_timestamp,
+            cfg_file_report.report_timestamp,
+            DEFAULT_REPORT_TIMESTAMP,
+        );
+        let report_timestamp_format = cfg_layer(
+            args.report_timestamp_format,
+            cfg_file_report.report_timestamp_format,
+            String::from(DEFAULT_REPORT_TIMESTAMP_FORMAT),
+        );
+        let report_columns = if args.report_columns.is_empty() {
+            cfg_file_report
+                .report_columns
+                .unwrap_or_else(|| DEFAULT_REPORT_COLUMNS.to_vec())
+        } else {
+            args.report_columns
+        };
+        let report_width = cfg_layer_opt(args.report_width, cfg_file_report.report_width);
+        let report_overflow = cfg_layer(
+            args.report_overflow,
+            cfg_file_report.report_overflow,
+            DEFAULT_REPORT_OVERFLOW,
+        );
+        let report_border = cfg_layer(
+            args.report_border,
+            cfg_file_report.report_border,
+            if tui_ascii {
+                ReportBorder::Ascii
+            } else {
+                ReportBorder::Unicode
+            },
+        );
+        let report_include_samples = cfg_layer(
+            args.report_include_samples,
+            cfg_file_report.report_include_samples,
+            DEFAULT_REPORT_INCLUDE_SAMPLES,
+        );
+        let report_per_address = cfg_layer(
+            args.report_per_address,
+            cfg_file_report.report_per_address,
+            DEFAULT_REPORT_PER_ADDRESS,
+        );
+        let template = cfg_layer_opt(args.template, cfg_file_report.template);
+        let report_file = cfg_layer_opt(args.report_file, cfg_file_report.report_file);
+        let repeat_every = cfg_layer_opt(args.repeat_every, cfg_file_report.repeat_every);
+        let repeat_count = cfg_layer_opt(args.repeat_count, cfg_file_report.repeat_count);
+        let geoip_mmdb_file = cfg_layer_opt(args.geoip_mmdb_file, cfg_file_tui.geoip_mmdb_file);
+        let protocol = match (args.udp, args.tcp, protocol) {
+            (true, _, _) => Some(Protocol::Udp),
+            (_, true, _) => Some(Protocol::Tcp),
+            (false, false, protocol) => protocol,
         };
+        let all_protocols = args.all_protocols;
+        let target_ports = args
+            .target_ports
+            .as_deref()
+            .map(parse_port_list)
+            .transpose()?;
         let read_timeout = humantime::parse_duration(&read_timeout)?;
         let min_round_duration = humantime::parse_duration(&min_round_duration)?;
         let max_round_duration = humantime::parse_duration(&max_round_duration)?;
+        let round_jitter = humantime::parse_duration(&round_jitter)?;
         let grace_duration = humantime::parse_duration(&grace_duration)?;
         let source_addr = source_address
             .as_ref()
@@ -1641,65 +3853,279 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
                     .map_err(|_| anyhow!("invalid source IP address format: {}", addr))
             })
             .transpose()?;
-        let addr_family = match (args.ipv4, args.ipv6, cfg_file_strategy.addr_family) {
-            (false, false, Some(AddressFamily::Ipv4) | None) | (true, _, _) => {
-                TracerAddrFamily::Ipv4
+        let (addr_family, dual_stack) = match (
+            args.ipv4,
+            args.ipv6,
+            args.dual_stack,
+            cfg_file_strategy.addr_family,
+        ) {
+            (true, _, _, _) | (false, false, false, Some(AddressFamily::Ipv4) | None) => {
+                (TracerAddrFamily::Ipv4, false)
+            }
+            (_, true, _, _) | (false, false, false, Some(AddressFamily::Ipv6)) => {
+                (TracerAddrFamily::Ipv6, false)
+            }
+            (false, false, true, _) | (false, false, false, Some(AddressFamily::DualStack)) => {
+                (TracerAddrFamily::Ipv4, true)
             }
-            (false, false, Some(AddressFamily::Ipv6)) | (_, true, _) => TracerAddrFamily::Ipv6,
         };
         let multipath_strategy = match (multipath_strategy_cfg, addr_family) {
             (MultipathStrategyConfig::Classic, _) => Ok(MultipathStrategy::Classic),
             (MultipathStrategyConfig::Paris, _) => {
                 Err(anyhow!("Paris multipath strategy not implemented yet!"))
             }
-            (MultipathStrategyConfig::Dublin, TracerAddrFamily::Ipv4) => {
+            (MultipathStrategyConfig::Dublin, TracerAddrFamily::Ipv4) if !dual_stack => {
                 Ok(MultipathStrategy::Dublin)
             }
-            (MultipathStrategyConfig::Dublin, TracerAddrFamily::Ipv6) => Err(anyhow!(
+            (MultipathStrategyConfig::Dublin, _) => Err(anyhow!(
                 "Dublin multipath strategy not implemented for IPv6 yet!"
             )),
         }?;
-        let port_direction = match (protocol, source_port, target_port, multipath_strategy_cfg) {
-            (TracerProtocol::Icmp, _, _, _) => PortDirection::None,
-            (TracerProtocol::Udp, None, None, _) => PortDirection::new_fixed_src(pid.max(1024)),
-            (TracerProtocol::Udp, Some(src), None, _) => {
-                validate_source_port(src)?;
-                PortDirection::new_fixed_src(src)
-            }
-            (TracerProtocol::Tcp, None, None, _) => PortDirection::new_fixed_dest(80),
-            (TracerProtocol::Tcp, Some(src), None, _) => PortDirection::new_fixed_src(src),
-            (_, None, Some(dest), _) => PortDirection::new_fixed_dest(dest),
-            (TracerProtocol::Udp, Some(src), Some(dest), MultipathStrategyConfig::Dublin) => {
-                validate_source_port(src)?;
-                PortDirection::new_fixed_both(src, dest)
-            }
-            (_, Some(_), Some(_), _) => {
-                return Err(anyhow!(
-                    "only one of source-port and target-port may be fixed (except IPv4/udp protocol with dublin strategy)"
-                ));
-            }
-        };
+        let targets = targets
+            .into_iter()
+            .flat_map(|target| {
+                let protocols = if firewalk_gateway.is_some() {
+                    let ports = target_ports
+                        .clone()
+                        .unwrap_or_else(|| vec![target_port.unwrap_or(DEFAULT_FIREWALK_PORT)]);
+                    std::iter::once((Protocol::Icmp, None))
+                        .chain(ports.into_iter().flat_map(|port| {
+                            [(Protocol::Tcp, Some(port)), (Protocol::Udp, Some(port))]
+                        }))
+                        .collect()
+                } else if all_protocols {
+                    vec![(Protocol::Icmp, None), (Protocol::Udp, None), (Protocol::Tcp, None)]
+                } else if let Some(target_ports) = &target_ports {
+                    // every port is traced with the TCP protocol, which is validated below
+                    let protocol = target.protocol.or(protocol).unwrap_or(Protocol::Tcp);
+                    target_ports
+                        .iter()
+                        .map(|&port| (protocol, Some(port)))
+                        .collect()
+                } else {
+                    match target.protocol.or(protocol) {
+                        Some(protocol) => vec![(protocol, None)],
+                        None if target.scheme_port.is_some() => vec![(Protocol::Tcp, None)],
+                        None => vec![(DEFAULT_STRATEGY_PROTOCOL, None)],
+                    }
+                };
+                // with dual stack, a hostname is traced with both address families and an address with its own
+                let addr_families = match IpAddr::from_str(&target.host) {
+                    _ if !dual_stack => vec![addr_family],
+                    Ok(IpAddr::V4(_)) => vec![TracerAddrFamily::Ipv4],
+                    Ok(IpAddr::V6(_)) => vec![TracerAddrFamily::Ipv6],
+                    Err(_) => vec![TracerAddrFamily::Ipv4, TracerAddrFamily::Ipv6],
+                };
+                // with the ext-header mode, every trace is repeated with the extension header to compare with
+                let ext_headers = match mode {
+                    Mode::ExtHeader if ipv6_ext_header.is_some() => vec![None, ipv6_ext_header],
+                    _ => vec![ipv6_ext_header],
+                };
+                // with the size-sweep mode, every trace is repeated with each packet size of the sweep
+                let packet_sizes = match mode {
+                    Mode::SizeSweep => sweep_sizes.clone(),
+                    _ => vec![packet_size],
+                };
+                protocols.into_iter().flat_map(move |(protocol, port)| {
+                    let target = target.clone();
+                    let ext_headers = ext_headers.clone();
+                    let packet_sizes = packet_sizes.clone();
+                    addr_families.clone().into_iter().flat_map(move |addr_family| {
+                        let target = target.clone();
+                        let packet_sizes = packet_sizes.clone();
+                        ext_headers.clone().into_iter().flat_map(move |ext_header| {
+                            let target = target.clone();
+                            packet_sizes.clone().into_iter().map(move |packet_size| {
+                                (target.clone(), protocol, port, addr_family, ext_header, packet_size)
+                            })
+                        })
+                    })
+                })
+            })
+            .zip(0_u16..)
+            .map(|((target, protocol, port, addr_family, ipv6_ext_header, packet_size), index)| {
+                let protocol = match protocol {
+                    Protocol::Icmp => TracerProtocol::Icmp,
+                    Protocol::Udp => TracerProtocol::Udp,
+                    Protocol::Tcp => TracerProtocol::Tcp,
+                };
+                let target_port = match (protocol, port) {
+                    // the port of the target only applies to the UDP and TCP traces of the target
+                    (TracerProtocol::Icmp, _) if all_protocols || firewalk_gateway.is_some() => None,
+                    (TracerProtocol::Tcp, Some(port)) => {
+                        validate_target_port(protocol, Some(port), &target)?
+                    }
+                    (TracerProtocol::Udp, Some(port)) if firewalk_gateway.is_some() => {
+                        validate_target_port(protocol, Some(port), &target)?
+                    }
+                    (_, Some(_)) => {
+                        return Err(anyhow!(
+                            "target-ports may only be used with the tcp protocol"
+                        ));
+                    }
+                    (_, None) => validate_target_port(protocol, target_port, &target)?,
+                };
+                let port_direction = match (protocol, source_port, target_port, multipath_strategy_cfg) {
+                    (TracerProtocol::Icmp, _, _, _) => PortDirection::None,
+                    (TracerProtocol::Udp, None, None, _) => {
+                        PortDirection::new_fixed_src(pid.max(1024).wrapping_add(index))
+                    }
+                    (TracerProtocol::Udp, Some(src), None, _) => {
+                        validate_source_port(src)?;
+                        PortDirection::new_fixed_src(src)
+                    }
+                    (TracerProtocol::Tcp, None, None, _) => PortDirection::new_fixed_dest(80),
+                    (TracerProtocol::Tcp, Some(src), None, _) => PortDirection::new_fixed_src(src),
+                    (_, None, Some(dest), _) => PortDirection::new_fixed_dest(dest),
+                    (TracerProtocol::Udp, Some(src), Some(dest), MultipathStrategyConfig::Dublin) => {
+                        validate_source_port(src)?;
+                        PortDirection::new_fixed_both(src, dest)
+                    }
+                    (_, Some(_), Some(_), _) => {
+                        return Err(anyhow!(
+                            "only one of source-port and target-port may be fixed (except IPv4/udp protocol with dublin strategy)"
+                        ));
+                    }
+                };
+                Ok(TraceTarget {
+                    hostname: target.host,
+                    protocol,
+                    addr_family,
+                    port_direction,
+                    ipv6_ext_header,
+                    packet_size,
+                    group: target.group,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
         let tui_refresh_rate = humantime::parse_duration(&tui_refresh_rate)?;
         let dns_timeout = humantime::parse_duration(&dns_timeout)?;
+        let max_duration = max_duration
+            .as_deref()
+            .map(humantime::parse_duration)
+            .transpose()?;
+        let probe_timeout = probe_timeout
+            .as_deref()
+            .map(humantime::parse_duration)
+            .transpose()?;
+        let repeat_every = repeat_every
+            .as_deref()
+            .map(humantime::parse_duration)
+            .transpose()?;
+        let reset_interval = reset_interval
+            .as_deref()
+            .map(humantime::parse_duration)
+            .transpose()?;
+        let unreachable_backoff = unreachable_backoff
+            .as_deref()
+            .map(humantime::parse_duration)
+            .transpose()?;
+        let cache_ttl = humantime::parse_duration(&cache_ttl)?;
+        let threshold_latency = threshold_latency
+            .as_deref()
+            .map(humantime::parse_duration)
+            .transpose()?;
+        let otlp_interval = humantime::parse_duration(&otlp_interval)?;
+        if otlp_interval.is_zero() {
+            return Err(anyhow!("otlp-interval must be greater than zero"));
+        }
         let max_rounds = match mode {
-            Mode::Stream | Mode::Tui => None,
-            Mode::Pretty | Mode::Markdown | Mode::Csv | Mode::Json => Some(report_cycles),
+            Mode::Stream | Mode::CsvStream | Mode::Tui => None,
+            Mode::Pretty | Mode::Markdown | Mode::Csv | Mode::Json | Mode::Template
+                if repeat_every.is_some() =>
+            {
+                None
+            }
+            Mode::Pretty
+            | Mode::Markdown
+            | Mode::Csv
+            | Mode::Json
+            | Mode::Template
+            | Mode::Ports
+            | Mode::Firewalk
+            | Mode::ExtHeader
+            | Mode::Qos
+            | Mode::SizeSweep => Some(report_cycles),
         };
         let tui_max_addrs = match tui_max_addrs {
             Some(n) if n > 0 => Some(n),
             _ => None,
         };
-        validate_multi(mode, protocol, &args.targets)?;
+        validate_all_protocols(mode, all_protocols)?;
+        validate_dual_stack(mode, dual_stack)?;
+        validate_firewalk(mode, firewalk_gateway)?;
+        validate_template(mode, template.as_deref())?;
+        validate_qos(mode, tos)?;
+        validate_ipv6_ext_header(mode, addr_family, ipv6_ext_header, &targets)?;
+        validate_multi(mode, &targets)?;
+        validate_web(mode, web)?;
+        validate_api(api)?;
+        validate_mqtt(
+            &mqtt_topic,
+            mqtt_username.as_deref(),
+            mqtt_password.as_deref(),
+        )?;
+        validate_agent(
+            mode,
+            web.is_some(),
+            agent,
+            agent_insecure,
+            remote.is_some(),
+            agent_token.as_deref(),
+        )?;
+        validate_logging(
+            mode,
+            web.is_some() || agent.is_some(),
+            verbose,
+            packet_dump,
+            log_file.as_deref(),
+        )?;
         validate_ttl(first_ttl, max_ttl)?;
         validate_max_inflight(max_inflight)?;
         validate_read_timeout(read_timeout)?;
         validate_round_duration(min_round_duration, max_round_duration)?;
+        validate_round_jitter(round_jitter, min_round_duration)?;
+        validate_probe_timeout(probe_timeout)?;
+        validate_retries(retries, probe_timeout)?;
         validate_grace_duration(grace_duration)?;
         validate_packet_size(packet_size)?;
+        validate_record_route(record_route, addr_family, dual_stack, packet_size, &targets)?;
+        validate_icmp_timestamp(icmp_timestamp, addr_family, dual_stack, &targets)?;
+        validate_tcp_options(tcp_mss, tcp_window_size)?;
         validate_tui_refresh_rate(tui_refresh_rate)?;
         validate_report_cycles(report_cycles)?;
+        validate_repeat(mode, repeat_every, repeat_count)?;
+        validate_max_duration(max_duration)?;
+        validate_max_unreachable_rounds(max_unreachable_rounds)?;
+        validate_reset_interval(reset_interval)?;
+        validate_re_resolve_every(re_resolve_every)?;
+        validate_unreachable_backoff(unreachable_backoff, min_round_duration)?;
+        validate_report_timestamp_format(&report_timestamp_format)?;
+        validate_report_columns(&report_columns)?;
+        validate_report_width(report_width)?;
+        validate_report_include_samples(mode, report_include_samples)?;
+        validate_report_per_address(mode, report_per_address)?;
         validate_dns(dns_resolve_method, dns_lookup_as_info)?;
+        validate_dns_max_in_flight(dns_max_in_flight)?;
+        validate_dns_source_addrs(&dns_source_addrs, dns_resolve_method)?;
+        validate_rtt_precision(rtt_precision)?;
+        validate_state_file(
+            mode,
+            web,
+            agent,
+            remote.as_deref(),
+            state_file.as_deref(),
+            resume,
+        )?;
+        validate_hooks(
+            on_as_path_change.as_deref(),
+            on_threshold_breach.as_deref(),
+            threshold_loss_pct,
+            threshold_latency,
+            dns_lookup_as_info,
+        )?;
         validate_geoip(tui_geoip_mode, &geoip_mmdb_file)?;
+        validate_tui_panes(&tui_panes, tui_pane_split)?;
         let tui_theme_items = args
             .tui_theme_colors
             .into_iter()
@@ -1711,43 +4137,313 @@ impl TryFrom<(Args, u16)> for TrippyConfig {
             .collect::<HashMap<TuiCommandItem, TuiKeyBinding>>();
         let tui_bindings = TuiBindings::from((tui_binding_items, cfg_file_tui_bindings));
         validate_bindings(&tui_bindings)?;
-        Ok(Self {
-            targets: args.targets,
-            protocol,
+        let duration = |duration: Duration| humantime::format_duration(duration).to_string();
+        let redacted =
+            |secret: &Option<String>| secret.as_ref().map(|_| String::from("<redacted>"));
+        // the icmp identifier is left out as it defaults to the process id
+        let effective = ConfigFile {
+            trippy: Some(ConfigTrippy {
+                targets: Some(config_targets),
+                mode: Some(mode),
+                plain: Some(plain),
+                rtt_unit: Some(rtt_unit),
+                rtt_precision: Some(rtt_precision),
+                locale: Some(locale),
+                exit_summary: Some(exit_summary),
+                web: web.map(|addr| addr.to_string()),
+                api: api.map(|addr| addr.to_string()),
+                agent: agent.map(|addr| addr.to_string()),
+                agent_insecure: Some(agent_insecure),
+                remote: remote.clone(),
+                agent_token: redacted(&agent_token),
+                max_duration: max_duration.map(duration),
+                max_unreachable_rounds,
+                reset_interval: reset_interval.map(duration),
+                unreachable_backoff: unreachable_backoff.map(duration),
+                re_resolve_every,
+                re_resolve_strategy: Some(re_resolve_strategy),
+                verbose: Some(verbose),
+                log_file: log_file.clone(),
+                packet_dump: Some(packet_dump),
+                uid,
+                annotations_file: annotations_file.clone(),
+                cache_file: cache_file.clone(),
+                cache_ttl: Some(duration(cache_ttl)),
+                state_file: state_file.clone(),
+            }),
+            strategy: Some(ConfigStrategy {
+                protocol,
+                addr_family: Some(match (addr_family, dual_stack) {
+                    (_, true) => AddressFamily::DualStack,
+                    (TracerAddrFamily::Ipv4, false) => AddressFamily::Ipv4,
+                    (TracerAddrFamily::Ipv6, false) => AddressFamily::Ipv6,
+                }),
+                target_port,
+                source_port,
+                source_address: source_addr.map(|addr| addr.to_string()),
+                interface: interface.clone(),
+                ipv6_source_preference: ipv6_source_preference.map(|preference| match preference {
+                    Ipv6SourcePreference::Temporary => Ipv6SourcePreferenceConfig::Temporary,
+                    Ipv6SourcePreference::Stable => Ipv6SourcePreferenceConfig::Stable,
+                }),
+                ipv6_ext_header: ipv6_ext_header.map(|header| match header {
+                    Ipv6ExtHeader::DestinationOptions => Ipv6ExtHeaderConfig::DstOpts,
+                    Ipv6ExtHeader::HopByHop => Ipv6ExtHeaderConfig::HopByHop,
+                }),
+                record_route: Some(record_route),
+                do_not_fragment,
+                icmp_timestamp: Some(icmp_timestamp),
+                min_round_duration: Some(duration(min_round_duration)),
+                max_round_duration: Some(duration(max_round_duration)),
+                round_jitter: Some(duration(round_jitter)),
+                probe_timeout: probe_timeout.map(duration),
+                retries: Some(retries),
+                backfill_late: Some(backfill_late),
+                initial_sequence: Some(initial_sequence),
+                sequence_strategy: Some(match sequence_strategy {
+                    SequenceStrategy::Sequential => SequenceStrategyConfig::Sequential,
+                    SequenceStrategy::Random => SequenceStrategyConfig::Random,
+                }),
+                icmp_identifier: None,
+                multipath_strategy: Some(multipath_strategy_cfg),
+                grace_duration: Some(duration(grace_duration)),
+                max_inflight: Some(max_inflight),
+                adaptive_inflight: Some(adaptive_inflight),
+                burst: Some(burst),
+                first_ttl: Some(first_ttl),
+                max_ttl: Some(max_ttl),
+                destination_only: Some(destination_only),
+                adaptive_ttl: Some(adaptive_ttl),
+                prohibited_strategy: Some(match prohibited_strategy {
+                    ProhibitedStrategy::Stop => ProhibitedStrategyConfig::Stop,
+                    ProhibitedStrategy::Continue => ProhibitedStrategyConfig::Continue,
+                }),
+                packet_size: Some(packet_size),
+                // the packet sizes of a sweep only apply to the size-sweep mode
+                sweep_range: matches!(mode, Mode::SizeSweep).then(|| sweep_range.clone()),
+                sweep_steps: matches!(mode, Mode::SizeSweep).then_some(sweep_steps),
+                payload_pattern: Some(payload_pattern),
+                tos: Some(tos),
+                ecn,
+                tcp_mss,
+                tcp_window_size,
+                read_timeout: Some(duration(read_timeout)),
+            }),
+            theme_colors: Some(ConfigThemeColors::from(&tui_theme)),
+            bindings: Some(ConfigBindings::from(&tui_bindings)),
+            tui: Some(ConfigTui {
+                tui_max_samples: Some(tui_max_samples),
+                tui_preserve_screen: Some(tui_preserve_screen),
+                tui_title: Some(tui_title),
+                tui_export_format: Some(tui_export_format),
+                tui_export_dir: Some(tui_export_dir.clone()),
+                tui_seen_columns: Some(tui_seen_columns),
+                tui_ascii: Some(tui_ascii),
+                tui_palette: Some(tui_palette),
+                tui_refresh_rate: Some(duration(tui_refresh_rate)),
+                tui_address_mode: Some(tui_address_mode),
+                tui_as_mode: Some(tui_as_mode),
+                tui_geoip_mode: Some(tui_geoip_mode),
+                tui_max_addrs,
+                tui_hide_hops: tui_hide_hops.as_ref().map(ToString::to_string),
+                tui_panes: Some(tui_panes.clone()),
+                tui_pane_split: Some(tui_pane_split),
+                geoip_mmdb_file: geoip_mmdb_file.clone(),
+            }),
+            dns: Some(ConfigDns {
+                dns_resolve_method: Some(dns_resolve_method),
+                dns_lookup_as_info: Some(dns_lookup_as_info),
+                dns_timeout: Some(duration(dns_timeout)),
+                dns_hosts_file: dns_hosts_file.clone(),
+                dns_max_in_flight: Some(dns_max_in_flight),
+                dns_source_address,
+                dns_interface,
+            }),
+            report: Some(ConfigReport {
+                report_cycles: Some(report_cycles),
+                report_timestamp: Some(report_timestamp),
+                report_timestamp_format: Some(report_timestamp_format.clone()),
+                report_columns: Some(report_columns.clone()),
+                report_width,
+                report_overflow: Some(report_overflow),
+                report_border: Some(report_border),
+                report_include_samples: Some(report_include_samples),
+                report_per_address: Some(report_per_address),
+                template: template.clone(),
+                report_file: report_file.clone(),
+                repeat_every: repeat_every.map(duration),
+                repeat_count,
+            }),
+            hooks: Some(ConfigHooks {
+                on_route_change: on_route_change.clone(),
+                on_as_path_change: on_as_path_change.clone(),
+                on_threshold_breach: on_threshold_breach.clone(),
+                on_target_unreachable: on_target_unreachable.clone(),
+                threshold_loss_pct,
+                threshold_latency: threshold_latency.map(duration),
+                syslog: syslog.as_ref().map(ToString::to_string),
+                syslog_facility: Some(syslog_facility),
+            }),
+            export: Some(ConfigExport {
+                otlp_endpoint: otlp_endpoint.as_ref().map(ToString::to_string),
+                otlp_interval: Some(duration(otlp_interval)),
+                statsd_addr: statsd_addr.clone(),
+                statsd_flavor: Some(statsd_flavor),
+                statsd_prefix: Some(statsd_prefix.clone()),
+                mqtt_broker: mqtt_broker.clone(),
+                mqtt_topic: Some(mqtt_topic.clone()),
+                mqtt_username: mqtt_username.clone(),
+                mqtt_password: redacted(&mqtt_password),
+            }),
+            groups: (!groups.is_empty()).then_some(groups),
+        };
+        let cfg = Self {
+            targets,
             addr_family,
+            dual_stack,
             first_ttl,
             max_ttl,
+            destination_only,
+            adaptive_ttl,
+            prohibited_strategy,
             min_round_duration,
             max_round_duration,
+            round_jitter,
+            probe_timeout,
+            retries,
+            backfill_late,
             grace_duration,
             max_inflight,
+            adaptive_inflight,
+            burst,
             initial_sequence,
-            multipath_strategy,
+            sequence_strategy,
+            icmp_identifier,
+            tos,
+            ecn,
+            tcp_mss,
+            tcp_window_size,
             read_timeout,
             packet_size,
+            sweep_sizes,
             payload_pattern,
-            tos,
             source_addr,
             interface,
-            port_direction,
+            ipv6_source_preference,
+            record_route,
+            do_not_fragment,
+            icmp_timestamp,
+            multipath_strategy,
             dns_timeout,
             dns_resolve_method,
             dns_lookup_as_info,
+            dns_hosts_file,
+            dns_max_in_flight,
+            dns_source_addrs,
             tui_max_samples,
             tui_preserve_screen,
+            tui_title,
+            tui_export_format,
+            tui_export_dir,
+            tui_seen_columns,
+            tui_ascii,
+            tui_palette,
             tui_refresh_rate,
             tui_address_mode,
             tui_as_mode,
             tui_geoip_mode,
             tui_max_addrs,
+            tui_hide_hops,
+            tui_panes,
+            tui_pane_split,
             tui_theme,
             tui_bindings,
             mode,
+            plain,
+            rtt_unit,
+            rtt_precision,
+            locale,
+            exit_summary,
+            web,
+            api,
+            agent,
+            remote,
+            agent_token,
             report_cycles,
+            report_timestamp,
+            report_timestamp_format,
+            report_columns,
+            report_width,
+            report_overflow,
+            report_border,
+            report_include_samples,
+            report_per_address,
+            template,
+            report_file,
+            repeat_every,
+            repeat_count,
+            max_duration,
+            max_unreachable_rounds,
+            reset_interval,
+            unreachable_backoff,
+            re_resolve_every,
+            re_resolve_strategy,
+            verbose,
+            log_file,
+            packet_dump,
+            uid,
+            on_route_change,
+            on_as_path_change,
+            on_threshold_breach,
+            on_target_unreachable,
+            threshold_loss_pct,
+            threshold_latency,
+            syslog,
+            syslog_facility,
+            otlp_endpoint,
+            otlp_interval,
+            statsd_addr,
+            statsd_flavor,
+            statsd_prefix,
+            mqtt_broker,
+            mqtt_topic,
+            mqtt_username,
+            mqtt_password,
             geoip_mmdb_file,
+            annotations_file,
+            cache_file,
+            cache_ttl,
+            state_file,
+            resume,
             max_rounds,
-        })
+        };
+        Ok((cfg, effective))
+    }
+}
+
+/// Print the effective configuration.
+fn print_config_as(cfg: &ConfigFile, format: ConfigFormat) -> anyhow::Result<()> {
+    match format {
+        ConfigFormat::Toml => print!("{}", toml::to_string(cfg)?),
+        ConfigFormat::Json => println!("{}", serde_json::to_string_pretty(cfg)?),
+    }
+    Ok(())
+}
+
+fn print_interfaces() -> anyhow::Result<()> {
+    for interface in Interface::list()? {
+        if interface.addrs.is_empty() {
+            println!("{}: {}", interface.index, interface.name);
+        } else {
+            println!(
+                "{}: {} ({})",
+                interface.index,
+                interface.name,
+                interface.addrs.iter().join(", ")
+            );
+        }
     }
+    Ok(())
 }
 
 fn print_completions<G: Generator>(gen: G, cmd: &mut Command) {
@@ -1768,23 +4464,452 @@ fn cfg_layer_opt<T>(fst: Option<T>, snd: Option<T>) -> Option<T> {
     }
 }
 
-/// We only allow multiple targets to be specified for the Tui and for `Icmp` tracing.
-fn validate_multi(mode: Mode, protocol: TracerProtocol, targets: &[String]) -> anyhow::Result<()> {
-    match (mode, protocol) {
-        (Mode::Stream | Mode::Pretty | Mode::Markdown | Mode::Csv | Mode::Json, _)
+/// Verbose logging and packet dumps in the Tui require a log file as the terminal is owned by the Tui.
+///
+/// The Tui is not shown when `headless`, such as when serving the web dashboard or running as an agent.
+fn validate_logging(
+    mode: Mode,
+    headless: bool,
+    verbose: u8,
+    packet_dump: bool,
+    log_file: Option<&str>,
+) -> anyhow::Result<()> {
+    match (mode, log_file) {
+        (Mode::Tui, None) if !headless && (verbose > 0 || packet_dump) => Err(anyhow!(
+            "log-file must be given for verbose logging or packet-dump in tui mode"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The web dashboard is served in place of the Tui and so cannot be used with any other mode.
+fn validate_web(mode: Mode, web: Option<SocketAddr>) -> anyhow::Result<()> {
+    match mode {
+        Mode::Tui => Ok(()),
+        _ if web.is_some() => Err(anyhow!("web may only be used with tui mode")),
+        _ => Ok(()),
+    }
+}
+
+/// The API is not authenticated and so may only be served on a loopback address.
+fn validate_api(api: Option<SocketAddr>) -> anyhow::Result<()> {
+    match api {
+        Some(addr) if !addr.ip().is_loopback() => Err(anyhow!(
+            "api ({}) must be a loopback address, such as 127.0.0.1:8081, as it is not authenticated",
+            addr
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// An agent is run in place of the Tui or web dashboard, which show the remote traces, and both require a token.
+///
+/// The connection to an agent is not encrypted and so it may only be served on a loopback address, such as to be
+/// reached via an SSH tunnel, unless `agent_insecure` is set.
+fn validate_agent(
+    mode: Mode,
+    web: bool,
+    agent_addr: Option<SocketAddr>,
+    agent_insecure: bool,
+    remote: bool,
+    agent_token: Option<&str>,
+) -> anyhow::Result<()> {
+    let agent = agent_addr.is_some();
+    match agent_addr {
+        Some(addr) if !addr.ip().is_loopback() && !agent_insecure => {
+            return Err(anyhow!(
+                "agent ({}) must be a loopback address, such as 127.0.0.1:8082, as it is not encrypted, unless agent-insecure is given",
+                addr
+            ));
+        }
+        _ => {}
+    }
+    match (mode, agent_token) {
+        (Mode::Tui, _) if agent && (web || remote) => {
+            Err(anyhow!("agent may not be used with web or remote"))
+        }
+        (Mode::Tui, None) if agent || remote => {
+            Err(anyhow!("agent-token must be given for agent or remote"))
+        }
+        (Mode::Tui, Some("")) if agent || remote => Err(anyhow!("agent-token must not be empty")),
+        (Mode::Tui, _) => Ok(()),
+        _ if agent || remote => Err(anyhow!("agent and remote may only be used with tui mode")),
+        _ => Ok(()),
+    }
+}
+
+/// The MQTT topic may not be empty or contain wildcards and a password requires a username.
+fn validate_mqtt(
+    topic: &str,
+    username: Option<&str>,
+    password: Option<&str>,
+) -> anyhow::Result<()> {
+    if topic.is_empty() || topic.contains(['+', '#']) {
+        Err(anyhow!(
+            "mqtt-topic must not be empty or contain wildcards: {}",
+            topic
+        ))
+    } else if password.is_some() && username.is_none() {
+        Err(anyhow!("mqtt-username must be given with mqtt-password"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate the groups of targets given in the config file.
+fn validate_groups(groups: &BTreeMap<String, Vec<String>>) -> anyhow::Result<()> {
+    match groups.iter().find(|(_, targets)| targets.is_empty()) {
+        Some((group, _)) => Err(anyhow!("group {group} has no targets")),
+        None => Ok(()),
+    }
+}
+
+/// A target host with an optional protocol and port.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct TargetSpec {
+    /// The hostname or IP address.
+    host: String,
+    /// The protocol given as the scheme of the target.
+    protocol: Option<Protocol>,
+    /// The port given explicitly as part of the target.
+    port: Option<u16>,
+    /// The default port of the scheme of a URL target.
+    scheme_port: Option<u16>,
+    /// The group of targets given in the config file which the target belongs to, if any.
+    group: Option<String>,
+}
+
+/// Parse a target of the form `host`, `host:port`, `ipv6`, `[ipv6]:port` or a URL such as `https://host/path`.
+///
+/// The protocol to use for a target may be given as a scheme, such as `udp://host:53`, in which case the host may be
+/// a CIDR block, such as `udp://192.0.2.0/28`.
+fn parse_target(target: &str) -> anyhow::Result<TargetSpec> {
+    if let Some((scheme, rest)) = target.split_once("://") {
+        let scheme = scheme.to_ascii_lowercase();
+        let (protocol, scheme_port) = match scheme.as_str() {
+            "icmp" => (Some(Protocol::Icmp), None),
+            "udp" => (Some(Protocol::Udp), None),
+            "tcp" => (Some(Protocol::Tcp), None),
+            scheme => (
+                None,
+                Some(
+                    scheme_port(scheme)
+                        .ok_or_else(|| anyhow!("unknown scheme in target: {target}"))?,
+                ),
+            ),
+        };
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let prefix_len = rest[authority.len()..].strip_prefix('/').filter(|len| {
+            protocol.is_some() && !len.is_empty() && len.bytes().all(|b| b.is_ascii_digit())
+        });
+        let authority = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, authority)| authority);
+        let (host, port) = parse_host_port(target, authority)?;
+        let host = match prefix_len {
+            Some(prefix_len) => format!("{host}/{prefix_len}"),
+            None => host,
+        };
+        if host.is_empty() {
+            return Err(anyhow!("missing host in target: {target}"));
+        }
+        Ok(TargetSpec {
+            host,
+            protocol,
+            port,
+            scheme_port,
+            group: None,
+        })
+    } else {
+        let (host, port) = parse_host_port(target, target)?;
+        Ok(TargetSpec {
+            host,
+            protocol: None,
+            port,
+            scheme_port: None,
+            group: None,
+        })
+    }
+}
+
+/// Expand a target whose host is a CIDR block, such as `192.0.2.0/28`, into a target for each host of the block.
+///
+/// The network and broadcast addresses of IPv4 blocks are excluded, other than for `/31` and `/32` blocks.
+fn expand_cidr_target(target: TargetSpec) -> anyhow::Result<Vec<TargetSpec>> {
+    let Some((addr, prefix_len)) = target.host.split_once('/') else {
+        return Ok(vec![target]);
+    };
+    let invalid = || anyhow!("invalid CIDR target: {}", target.host);
+    let addr = IpAddr::from_str(addr).map_err(|_| invalid())?;
+    let prefix_len = prefix_len.parse::<u8>().map_err(|_| invalid())?;
+    let (value, bits) = match addr {
+        IpAddr::V4(addr) => (u128::from(u32::from(addr)), 32),
+        IpAddr::V6(addr) => (u128::from(addr), 128),
+    };
+    if prefix_len > bits {
+        return Err(invalid());
+    }
+    let host_bits = bits - prefix_len;
+    if host_bits > MAX_CIDR_HOST_BITS {
+        return Err(anyhow!(
+            "CIDR target {} has more than {} hosts",
+            target.host,
+            1 << MAX_CIDR_HOST_BITS
+        ));
+    }
+    let size = 1_u128 << host_bits;
+    let network = value & !(size - 1);
+    let hosts = match addr {
+        IpAddr::V4(_) if host_bits > 1 => network + 1..network + size - 1,
+        _ => network..network + size,
+    };
+    Ok(hosts
+        .map(|host| {
+            let host = match addr {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(host as u32)),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(host)),
+            };
+            TargetSpec {
+                host: host.to_string(),
+                ..target.clone()
+            }
+        })
+        .collect())
+}
+
+/// Split `host`, `host:port`, `ipv6` or `[ipv6]:port` into the host and port.
+fn parse_host_port(target: &str, host_port: &str) -> anyhow::Result<(String, Option<u16>)> {
+    let parse_port = |port: &str| {
+        port.parse::<u16>()
+            .map_err(|_| anyhow!("invalid port in target: {target}"))
+    };
+    if let Some(bracketed) = host_port.strip_prefix('[') {
+        match bracketed.split_once(']') {
+            Some((host, "")) => Ok((host.to_string(), None)),
+            Some((host, port)) => match port.strip_prefix(':') {
+                Some(port) => Ok((host.to_string(), Some(parse_port(port)?))),
+                None => Err(anyhow!("invalid target: {target}")),
+            },
+            None => Err(anyhow!("invalid target: {target}")),
+        }
+    } else {
+        match host_port.split_once(':') {
+            Some((host, port)) if !port.contains(':') => {
+                Ok((host.to_string(), Some(parse_port(port)?)))
+            }
+            _ => Ok((host_port.to_string(), None)),
+        }
+    }
+}
+
+/// The default port of a URL scheme.
+fn scheme_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        "ssh" | "sftp" => Some(22),
+        "telnet" => Some(23),
+        "smtp" => Some(25),
+        "imap" => Some(143),
+        "imaps" => Some(993),
+        "ldap" => Some(389),
+        "ldaps" => Some(636),
+        _ => None,
+    }
+}
+
+/// Validate the port given as part of a target, and return the target port to use.
+///
+/// A port given as part of a target takes the place of `target-port`, which must agree with it if both are given.  The
+/// default port of the scheme of a URL target is used if neither is given.
+fn validate_target_port(
+    protocol: TracerProtocol,
+    target_port: Option<u16>,
+    target: &TargetSpec,
+) -> anyhow::Result<Option<u16>> {
+    match (protocol, target.port) {
+        (TracerProtocol::Icmp, Some(_)) => Err(anyhow!(
+            "a target port may only be specified for TCP and UDP tracing"
+        )),
+        (TracerProtocol::Icmp, None) => Ok(target_port),
+        (_, Some(port)) if matches!(target_port, Some(target_port) if target_port != port) => {
+            Err(anyhow!(
+                "target port ({port}) does not match target-port ({})",
+                target_port.unwrap_or_default()
+            ))
+        }
+        (_, Some(port)) => Ok(Some(port)),
+        (_, None) => Ok(target_port.or(target.scheme_port)),
+    }
+}
+
+/// Parse a comma separated list of ports and inclusive port ranges, such as `22,80,443,8000-8010`.
+///
+/// Ports which are listed more than once are only included once.
+fn parse_port_list(ports: &str) -> anyhow::Result<Vec<u16>> {
+    let invalid = || anyhow!("invalid target-ports: {ports}");
+    let mut parsed = vec![];
+    for item in ports.split(',').map(str::trim) {
+        let (first, last) = item.split_once('-').unwrap_or((item, item));
+        let first = first.trim().parse::<u16>().map_err(|_| invalid())?;
+        let last = last.trim().parse::<u16>().map_err(|_| invalid())?;
+        if first == 0 || first > last {
+            return Err(invalid());
+        }
+        parsed.extend(first..=last);
+    }
+    let parsed = parsed.into_iter().unique().collect::<Vec<_>>();
+    if parsed.len() > MAX_TARGET_PORTS {
+        Err(anyhow!(
+            "target-ports must contain at most {MAX_TARGET_PORTS} ports"
+        ))
+    } else {
+        Ok(parsed)
+    }
+}
+
+/// We only allow multiple targets to be specified for the Tui and the ports report.
+///
+/// Firewalking traces every protocol and port to a single target, as the gateway hop is specific to its path.
+fn validate_multi(mode: Mode, targets: &[TraceTarget]) -> anyhow::Result<()> {
+    match mode {
+        _ if targets.is_empty() => Err(anyhow!("no targets specified")),
+        Mode::Firewalk if targets.iter().map(|t| &t.hostname).unique().count() > 1 => Err(anyhow!(
+            "only a single target may be specified for firewalk mode"
+        )),
+        Mode::ExtHeader if targets.iter().map(|t| &t.hostname).unique().count() > 1 => Err(
+            anyhow!("only a single target may be specified for ext-header mode"),
+        ),
+        Mode::Stream
+        | Mode::Pretty
+        | Mode::Markdown
+        | Mode::Csv
+        | Mode::CsvStream
+        | Mode::Json
+        | Mode::Template
             if targets.len() > 1 =>
         {
             Err(anyhow!(
                 "only a single target may be specified for this mode"
             ))
         }
-        (_, TracerProtocol::Tcp | TracerProtocol::Udp) if targets.len() > 1 => Err(anyhow!(
-            "only a single target may be specified for TCP and UDP tracing"
+        _ => Ok(()),
+    }
+}
+
+/// We only allow tracing with both address families for the modes which show multiple traces.
+fn validate_dual_stack(mode: Mode, dual_stack: bool) -> anyhow::Result<()> {
+    match mode {
+        Mode::Stream
+        | Mode::Pretty
+        | Mode::Markdown
+        | Mode::Csv
+        | Mode::CsvStream
+        | Mode::Json
+        | Mode::Template
+            if dual_stack =>
+        {
+            Err(anyhow!(
+                "dual-stack may only be used in Tui, ports and firewalk modes"
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// We only allow tracing with all protocols for the Tui.
+fn validate_all_protocols(mode: Mode, all_protocols: bool) -> anyhow::Result<()> {
+    match mode {
+        Mode::Stream
+        | Mode::Pretty
+        | Mode::Markdown
+        | Mode::Csv
+        | Mode::CsvStream
+        | Mode::Json
+        | Mode::Template
+            if all_protocols =>
+        {
+            Err(anyhow!("all-protocols may only be used in Tui mode"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Validate that the `tos` sets a DSCP other than the default, as required by the qos mode.
+fn validate_qos(mode: Mode, tos: u8) -> anyhow::Result<()> {
+    match mode {
+        Mode::Qos if Dscp::from_tos(tos).0 == 0 => Err(anyhow!(
+            "qos mode requires a tos ({tos}) with a non-zero DSCP, such as 184 (ef)"
         )),
         _ => Ok(()),
     }
 }
 
+/// Validate `template`, which is required by, and may only be used with, the template mode.
+///
+/// The template is read and parsed such that an invalid template is reported before tracing starts.
+fn validate_template(mode: Mode, template: Option<&str>) -> anyhow::Result<()> {
+    match (mode, template) {
+        (Mode::Template, None) => Err(anyhow!("template mode requires template")),
+        (Mode::Template, Some(path)) => {
+            let source = std::fs::read_to_string(path)
+                .map_err(|err| anyhow!("failed to read template {path}: {err}"))?;
+            Template::parse(&source)
+                .map(|_| ())
+                .map_err(|err| anyhow!("invalid template {path}: {err}"))
+        }
+        (_, None) => Ok(()),
+        (_, Some(_)) => Err(anyhow!("template may only be used with the template mode")),
+    }
+}
+
+/// Validate `firewalk_gateway`, which is required by, and may only be used with, the firewalk mode.
+fn validate_firewalk(mode: Mode, firewalk_gateway: Option<u8>) -> anyhow::Result<()> {
+    match (mode, firewalk_gateway) {
+        (Mode::Firewalk, None) => Err(anyhow!("firewalk mode requires firewalk-gateway")),
+        (Mode::Firewalk, Some(gateway)) if gateway == 0 || usize::from(gateway) >= MAX_HOPS => {
+            Err(anyhow!(
+                "firewalk-gateway ({gateway}) must be in the range 1..{}",
+                MAX_HOPS - 1
+            ))
+        }
+        (Mode::Firewalk, Some(_)) | (_, None) => Ok(()),
+        (_, Some(_)) => Err(anyhow!(
+            "firewalk-gateway may only be used with the firewalk mode"
+        )),
+    }
+}
+
+/// Validate `ipv6_ext_header`, which may only be used with `IPv6` and is required by the ext-header mode.
+///
+/// The address family is `IPv4` with dual-stack and so dual-stack is not permitted either.  Setting the extension
+/// header requires privileges, which are dropped before the socket of each TCP probe is created, and so the tcp
+/// protocol is not permitted.
+fn validate_ipv6_ext_header(
+    mode: Mode,
+    addr_family: TracerAddrFamily,
+    ipv6_ext_header: Option<Ipv6ExtHeader>,
+    targets: &[TraceTarget],
+) -> anyhow::Result<()> {
+    match (mode, addr_family, ipv6_ext_header) {
+        (Mode::ExtHeader, _, None) => Err(anyhow!("ext-header mode requires ipv6-ext-header")),
+        (_, TracerAddrFamily::Ipv4, Some(_)) => {
+            Err(anyhow!("ipv6-ext-header may only be used with IPv6"))
+        }
+        (_, _, Some(_))
+            if targets
+                .iter()
+                .any(|target| matches!(target.protocol, TracerProtocol::Tcp)) =>
+        {
+            Err(anyhow!(
+                "ipv6-ext-header may only be used with the icmp and udp protocols"
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Validate `first_ttl` and `max_ttl`.
 fn validate_ttl(first_ttl: u8, max_ttl: u8) -> anyhow::Result<()> {
     if (first_ttl as usize) < 1 || (first_ttl as usize) > MAX_HOPS {
@@ -1860,6 +4985,43 @@ fn validate_grace_duration(grace_duration: Duration) -> anyhow::Result<()> {
     }
 }
 
+/// Parse the `sweep_range`, of the form `min-max`, into `sweep_steps` packet sizes evenly spaced across the range.
+fn parse_sweep_sizes(sweep_range: &str, sweep_steps: u8) -> anyhow::Result<Vec<u16>> {
+    let (min, max) = sweep_range
+        .split_once('-')
+        .and_then(|(min, max)| {
+            Some((
+                min.trim().parse::<u16>().ok()?,
+                max.trim().parse::<u16>().ok()?,
+            ))
+        })
+        .ok_or_else(|| {
+            anyhow!("sweep-range ({sweep_range}) must be of the form min-max, such as 84-1024")
+        })?;
+    if !(MIN_PACKET_SIZE..=MAX_PACKET_SIZE).contains(&min)
+        || !(MIN_PACKET_SIZE..=MAX_PACKET_SIZE).contains(&max)
+        || min >= max
+    {
+        return Err(anyhow!(
+            "sweep-range ({sweep_range}) must be an increasing range of packet sizes between {MIN_PACKET_SIZE} and {MAX_PACKET_SIZE} inclusive"
+        ));
+    }
+    if !(2..=MAX_SWEEP_STEPS).contains(&sweep_steps) {
+        return Err(anyhow!(
+            "sweep-steps ({sweep_steps}) must be between 2 and {MAX_SWEEP_STEPS} inclusive"
+        ));
+    }
+    let steps = u32::from(sweep_steps) - 1;
+    let sizes = (0..=steps)
+        .map(|step| {
+            let size = u32::from(min) + u32::from(max - min) * step / steps;
+            u16::try_from(size).unwrap_or(max)
+        })
+        .dedup()
+        .collect();
+    Ok(sizes)
+}
+
 /// Validate `packet_size`.
 fn validate_packet_size(packet_size: u16) -> anyhow::Result<()> {
     if (MIN_PACKET_SIZE..=MAX_PACKET_SIZE).contains(&packet_size) {
@@ -1874,6 +5036,71 @@ fn validate_packet_size(packet_size: u16) -> anyhow::Result<()> {
     }
 }
 
+/// Validate `record_route`, which may only be used with the `IPv4` icmp and udp protocols.
+///
+/// The probe must have room for the `IPv4` header with the option, of 60 bytes, and the `ICMP` or `UDP` header.
+fn validate_record_route(
+    record_route: bool,
+    addr_family: TracerAddrFamily,
+    dual_stack: bool,
+    packet_size: u16,
+    targets: &[TraceTarget],
+) -> anyhow::Result<()> {
+    const MIN_RECORD_ROUTE_PACKET_SIZE: u16 = 68;
+    if !record_route {
+        Ok(())
+    } else if matches!(addr_family, TracerAddrFamily::Ipv6) || dual_stack {
+        Err(anyhow!("record-route may only be used with IPv4"))
+    } else if targets
+        .iter()
+        .any(|target| matches!(target.protocol, TracerProtocol::Tcp))
+    {
+        Err(anyhow!(
+            "record-route may only be used with the icmp and udp protocols"
+        ))
+    } else if packet_size < MIN_RECORD_ROUTE_PACKET_SIZE {
+        Err(anyhow!(
+            "packet-size ({packet_size}) must be at least {MIN_RECORD_ROUTE_PACKET_SIZE} with record-route"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate `icmp_timestamp`, which may only be used with the `IPv4` icmp protocol.
+fn validate_icmp_timestamp(
+    icmp_timestamp: bool,
+    addr_family: TracerAddrFamily,
+    dual_stack: bool,
+    targets: &[TraceTarget],
+) -> anyhow::Result<()> {
+    if !icmp_timestamp {
+        Ok(())
+    } else if matches!(addr_family, TracerAddrFamily::Ipv6) || dual_stack {
+        Err(anyhow!("icmp-timestamp may only be used with IPv4"))
+    } else if targets
+        .iter()
+        .any(|target| !matches!(target.protocol, TracerProtocol::Icmp))
+    {
+        Err(anyhow!(
+            "icmp-timestamp may only be used with the icmp protocol"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate `tcp_mss` and `tcp_window_size`.
+fn validate_tcp_options(tcp_mss: Option<u16>, tcp_window_size: Option<u32>) -> anyhow::Result<()> {
+    match (tcp_mss, tcp_window_size) {
+        (Some(mss), _) if mss < MIN_TCP_MSS => {
+            Err(anyhow!("tcp-mss ({}) must be >= {}", mss, MIN_TCP_MSS))
+        }
+        (_, Some(0)) => Err(anyhow!("tcp-window-size must be greater than zero")),
+        _ => Ok(()),
+    }
+}
+
 /// Validate `source_port`.
 fn validate_source_port(source_port: u16) -> anyhow::Result<()> {
     if source_port < 1024 {
@@ -1909,6 +5136,235 @@ fn validate_report_cycles(report_cycles: usize) -> anyhow::Result<()> {
     }
 }
 
+/// Validate `max_duration`.
+fn validate_max_duration(max_duration: Option<Duration>) -> anyhow::Result<()> {
+    match max_duration {
+        Some(max_duration) if max_duration.is_zero() => Err(anyhow!(
+            "max-duration ({:?}) must be greater than zero",
+            max_duration
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `probe_timeout`.
+fn validate_probe_timeout(probe_timeout: Option<Duration>) -> anyhow::Result<()> {
+    match probe_timeout {
+        Some(probe_timeout) if probe_timeout.is_zero() => Err(anyhow!(
+            "probe-timeout ({:?}) must be greater than zero",
+            probe_timeout
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `retries`.
+fn validate_retries(retries: u8, probe_timeout: Option<Duration>) -> anyhow::Result<()> {
+    if retries > 0 && probe_timeout.is_none() {
+        Err(anyhow!(
+            "retries ({retries}) requires probe-timeout to be set"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Is the `NO_COLOR` environment variable set to a non-empty value?
+///
+/// See <https://no-color.org>.
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Validate `tui_panes` and `tui_pane_split`.
+fn validate_tui_panes(tui_panes: &[TuiPane], tui_pane_split: u16) -> anyhow::Result<()> {
+    if tui_panes.is_empty() {
+        Err(anyhow!("tui-panes must contain at least one pane"))
+    } else if !(MIN_TUI_PANE_SPLIT..=MAX_TUI_PANE_SPLIT).contains(&tui_pane_split) {
+        Err(anyhow!(
+            "tui-pane-split ({tui_pane_split}) must be between {MIN_TUI_PANE_SPLIT} and {MAX_TUI_PANE_SPLIT} inclusive"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate `report_columns`.
+fn validate_report_columns(report_columns: &[ReportColumn]) -> anyhow::Result<()> {
+    if report_columns.is_empty() {
+        Err(anyhow!("report-columns must contain at least one column"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate `report_include_samples`, which is only supported by the json and template reports.
+fn validate_report_include_samples(mode: Mode, report_include_samples: bool) -> anyhow::Result<()> {
+    match mode {
+        Mode::Json | Mode::Template => Ok(()),
+        _ if report_include_samples => Err(anyhow!(
+            "report-include-samples is only supported by the json and template modes"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `report_per_address`, which is only supported by the csv, pretty and markdown reports and by the reports
+/// exported from the Tui.
+fn validate_report_per_address(mode: Mode, report_per_address: bool) -> anyhow::Result<()> {
+    match mode {
+        Mode::Tui | Mode::Csv | Mode::Pretty | Mode::Markdown => Ok(()),
+        _ if report_per_address => Err(anyhow!(
+            "report-per-address is only supported by the tui, csv, pretty and markdown modes"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `report_width`.
+fn validate_report_width(report_width: Option<u16>) -> anyhow::Result<()> {
+    match report_width {
+        Some(width) if width < MIN_REPORT_WIDTH => Err(anyhow!(
+            "report-width ({width}) must be at least {MIN_REPORT_WIDTH}"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `max_unreachable_rounds`.
+fn validate_max_unreachable_rounds(max_unreachable_rounds: Option<usize>) -> anyhow::Result<()> {
+    match max_unreachable_rounds {
+        Some(0) => Err(anyhow!(
+            "max-unreachable-rounds (0) must be greater than zero"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `repeat_every` and `repeat_count`.
+///
+/// Only the modes which generate a report after a number of cycles can be repeated.
+fn validate_repeat(
+    mode: Mode,
+    repeat_every: Option<Duration>,
+    repeat_count: Option<usize>,
+) -> anyhow::Result<()> {
+    match (mode, repeat_every, repeat_count) {
+        (_, None, Some(_)) => Err(anyhow!("repeat-count may only be used with repeat-every")),
+        (_, Some(every), _) if every.is_zero() => Err(anyhow!(
+            "repeat-every ({:?}) must be greater than zero",
+            every
+        )),
+        (_, _, Some(0)) => Err(anyhow!("repeat-count must be greater than zero")),
+        (
+            Mode::Tui
+            | Mode::Stream
+            | Mode::CsvStream
+            | Mode::Ports
+            | Mode::Firewalk
+            | Mode::ExtHeader
+            | Mode::Qos
+            | Mode::SizeSweep,
+            Some(_),
+            _,
+        ) => Err(anyhow!(
+            "repeat-every may only be used with the pretty, markdown, csv, json and template modes"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `reset_interval`.
+fn validate_reset_interval(reset_interval: Option<Duration>) -> anyhow::Result<()> {
+    match reset_interval {
+        Some(reset_interval) if reset_interval.is_zero() => Err(anyhow!(
+            "reset-interval ({:?}) must be greater than zero",
+            reset_interval
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `re_resolve_every`.
+fn validate_re_resolve_every(re_resolve_every: Option<usize>) -> anyhow::Result<()> {
+    match re_resolve_every {
+        Some(0) => Err(anyhow!("re-resolve-every must be greater than zero")),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `round_jitter`.
+fn validate_round_jitter(
+    round_jitter: Duration,
+    min_round_duration: Duration,
+) -> anyhow::Result<()> {
+    if round_jitter > min_round_duration {
+        Err(anyhow!(
+            "round-jitter ({:?}) must not be greater than min-round-duration ({:?})",
+            round_jitter,
+            min_round_duration
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate `unreachable_backoff`.
+fn validate_unreachable_backoff(
+    unreachable_backoff: Option<Duration>,
+    min_round_duration: Duration,
+) -> anyhow::Result<()> {
+    match unreachable_backoff {
+        Some(unreachable_backoff) if unreachable_backoff <= min_round_duration => Err(anyhow!(
+            "unreachable-backoff ({:?}) must be greater than min-round-duration ({:?})",
+            unreachable_backoff,
+            min_round_duration
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate the hook thresholds.
+///
+/// The `on_threshold_breach` hook requires at least one threshold and the `on_as_path_change` hook requires AS lookups.
+fn validate_hooks(
+    on_as_path_change: Option<&str>,
+    on_threshold_breach: Option<&str>,
+    threshold_loss_pct: Option<f64>,
+    threshold_latency: Option<Duration>,
+    dns_lookup_as_info: bool,
+) -> anyhow::Result<()> {
+    if on_as_path_change.is_some() && !dns_lookup_as_info {
+        return Err(anyhow!("on-as-path-change requires dns-lookup-as-info"));
+    }
+    match (on_threshold_breach, threshold_loss_pct, threshold_latency) {
+        (_, Some(loss_pct), _) if !(0_f64..100_f64).contains(&loss_pct) => Err(anyhow!(
+            "threshold-loss-pct ({}) must be in the range 0..100",
+            loss_pct
+        )),
+        (_, _, Some(latency)) if latency.is_zero() => Err(anyhow!(
+            "threshold-latency ({:?}) must be greater than zero",
+            latency
+        )),
+        (Some(_), None, None) => Err(anyhow!(
+            "on-threshold-breach requires threshold-loss-pct or threshold-latency"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Validate `report_timestamp_format`.
+fn validate_report_timestamp_format(format: &str) -> anyhow::Result<()> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        Err(anyhow!(
+            "report-timestamp-format ({}) is not a valid strftime format",
+            format
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 /// Validate `dns_resolve_method` and `dns_lookup_as_info`.
 fn validate_dns(
     dns_resolve_method: DnsResolveMethod,
@@ -1922,6 +5378,85 @@ fn validate_dns(
     }
 }
 
+/// Validate `state_file` and `resume`.
+///
+/// We only allow the session to be saved, and resumed, for the Tui.
+fn validate_state_file(
+    mode: Mode,
+    web: Option<SocketAddr>,
+    agent: Option<SocketAddr>,
+    remote: Option<&str>,
+    state_file: Option<&str>,
+    resume: bool,
+) -> anyhow::Result<()> {
+    match (mode, state_file) {
+        (_, None) if resume => Err(anyhow!("resume requires state-file")),
+        (Mode::Tui, Some(_)) if web.is_some() || agent.is_some() || remote.is_some() => Err(
+            anyhow!("state-file may not be used with web, agent or remote"),
+        ),
+        (Mode::Tui, _) | (_, None) => Ok(()),
+        (_, Some(_)) => Err(anyhow!("state-file may only be used in Tui mode")),
+    }
+}
+
+fn validate_rtt_precision(rtt_precision: u8) -> anyhow::Result<()> {
+    if rtt_precision > MAX_RTT_PRECISION {
+        Err(anyhow!(
+            "rtt-precision ({rtt_precision}) must be at most {MAX_RTT_PRECISION}"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate that DNS queries can be sent from `dns_source_addrs`, if any, with the `dns_resolve_method`.
+///
+/// The `system` resolver performs lookups via the operating system, which cannot be bound to a source address.
+fn validate_dns_source_addrs(
+    dns_source_addrs: &[IpAddr],
+    dns_resolve_method: DnsResolveMethod,
+) -> anyhow::Result<()> {
+    if !dns_source_addrs.is_empty() && matches!(dns_resolve_method, DnsResolveMethod::System) {
+        Err(anyhow!(
+            "dns-source-address and dns-interface are not supported with the system dns-resolve-method, use resolv, google or cloudflare"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// The addresses of the network `interface` which DNS queries may be sent from.
+///
+/// Link-local `IPv6` addresses are excluded as they cannot be used to reach a name server without a scope.
+fn interface_addrs(interface: &str) -> anyhow::Result<Vec<IpAddr>> {
+    let addrs: Vec<_> = Interface::list()?
+        .into_iter()
+        .find(|candidate| candidate.name == interface)
+        .ok_or_else(|| anyhow!("dns-interface ({interface}) does not exist"))?
+        .addrs
+        .into_iter()
+        .filter(|addr| !matches!(addr, IpAddr::V6(addr) if addr.segments()[0] & 0xffc0 == 0xfe80))
+        .collect();
+    if addrs.is_empty() {
+        Err(anyhow!(
+            "dns-interface ({interface}) has no usable addresses"
+        ))
+    } else {
+        Ok(addrs)
+    }
+}
+
+/// Validate `dns_max_in_flight`.
+fn validate_dns_max_in_flight(dns_max_in_flight: usize) -> anyhow::Result<()> {
+    if (1..=MAX_DNS_MAX_IN_FLIGHT).contains(&dns_max_in_flight) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "dns-max-in-flight ({dns_max_in_flight}) must be between 1 and {MAX_DNS_MAX_IN_FLIGHT} inclusive"
+        ))
+    }
+}
+
 fn validate_geoip(
     tui_geoip_mode: GeoIpMode,
     geoip_mmdb_file: &Option<String>,
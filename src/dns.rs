@@ -1,8 +1,16 @@
 use crate::config::DnsResolveMethod;
 use crate::dns::inner::DnsResolverInner;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::fmt::{Display, Formatter};
-use std::net::IpAddr;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
 use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// The state of reverse DNS resolution.
@@ -38,6 +46,32 @@ pub enum Unresolved {
     WithAsInfo(IpAddr, AsInfo),
 }
 
+impl DnsEntry {
+    /// The `IpAddr` of an entry which was resolved or was found to have no hostnames.
+    ///
+    /// Returns `None` for entries which are pending, have failed or have timed out.
+    pub fn completed_addr(&self) -> Option<IpAddr> {
+        match self {
+            Self::Resolved(Resolved::Normal(addr, _) | Resolved::WithAsInfo(addr, _, _))
+            | Self::NotFound(Unresolved::Normal(addr) | Unresolved::WithAsInfo(addr, _)) => {
+                Some(*addr)
+            }
+            Self::Pending(_) | Self::Failed(_) | Self::Timeout(_) => None,
+        }
+    }
+
+    /// The `AS` number of the entry, if it was looked up with `AS` information and the `AS` was found.
+    pub fn asn(&self) -> Option<&str> {
+        match self {
+            Self::Resolved(Resolved::WithAsInfo(_, _, as_info))
+            | Self::NotFound(Unresolved::WithAsInfo(_, as_info)) => {
+                Some(as_info.asn.as_str()).filter(|asn| !asn.is_empty())
+            }
+            _ => None,
+        }
+    }
+}
+
 impl Display for DnsEntry {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         #[allow(clippy::match_same_arms)]
@@ -58,7 +92,8 @@ impl Display for DnsEntry {
 }
 
 /// Autonomous System (AS) information.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct AsInfo {
     pub asn: String,
     pub prefix: String,
@@ -73,6 +108,7 @@ pub struct AsInfo {
 pub enum IpAddrFamily {
     Ipv4,
     Ipv6,
+    Ipv4AndIpv6,
 }
 
 /// Configuration for the `DnsResolver`.
@@ -81,23 +117,136 @@ pub struct DnsResolverConfig {
     pub resolve_method: DnsResolveMethod,
     pub addr_family: IpAddrFamily,
     pub timeout: Duration,
+    /// The maximum number of reverse DNS lookups which may be in-flight at once.
+    pub max_in_flight: usize,
+    /// The local `IPv4` address to send queries to `IPv4` name servers from, if any.
+    pub bind_addr_ipv4: Option<Ipv4Addr>,
+    /// The local `IPv6` address to send queries to `IPv6` name servers from, if any.
+    pub bind_addr_ipv6: Option<Ipv6Addr>,
 }
 
 impl DnsResolverConfig {
-    pub fn new_ipv4(resolve_method: DnsResolveMethod, timeout: Duration) -> Self {
+    pub fn new_ipv4(
+        resolve_method: DnsResolveMethod,
+        timeout: Duration,
+        max_in_flight: usize,
+    ) -> Self {
         Self {
             resolve_method,
             addr_family: IpAddrFamily::Ipv4,
             timeout,
+            max_in_flight,
+            bind_addr_ipv4: None,
+            bind_addr_ipv6: None,
         }
     }
 
-    pub fn new_ipv6(resolve_method: DnsResolveMethod, timeout: Duration) -> Self {
+    pub fn new_ipv6(
+        resolve_method: DnsResolveMethod,
+        timeout: Duration,
+        max_in_flight: usize,
+    ) -> Self {
         Self {
             resolve_method,
             addr_family: IpAddrFamily::Ipv6,
             timeout,
+            max_in_flight,
+            bind_addr_ipv4: None,
+            bind_addr_ipv6: None,
+        }
+    }
+
+    pub fn new_dual_stack(
+        resolve_method: DnsResolveMethod,
+        timeout: Duration,
+        max_in_flight: usize,
+    ) -> Self {
+        Self {
+            resolve_method,
+            addr_family: IpAddrFamily::Ipv4AndIpv6,
+            timeout,
+            max_in_flight,
+            bind_addr_ipv4: None,
+            bind_addr_ipv6: None,
+        }
+    }
+
+    /// Send queries from the first `IPv4` and the first `IPv6` address of `bind_addrs`.
+    ///
+    /// Only name servers of an address family for which an address is given are queried.  If no addresses are
+    /// given then the operating system chooses the address to send queries from.
+    #[must_use]
+    pub fn with_bind_addrs(self, bind_addrs: &[IpAddr]) -> Self {
+        Self {
+            bind_addr_ipv4: bind_addrs.iter().find_map(|addr| match addr {
+                IpAddr::V4(addr) => Some(*addr),
+                IpAddr::V6(_) => None,
+            }),
+            bind_addr_ipv6: bind_addrs.iter().find_map(|addr| match addr {
+                IpAddr::V4(_) => None,
+                IpAddr::V6(addr) => Some(*addr),
+            }),
+            ..self
+        }
+    }
+
+    /// Is this resolver configured to send queries from a given address?
+    const fn is_bound(&self) -> bool {
+        self.bind_addr_ipv4.is_some() || self.bind_addr_ipv6.is_some()
+    }
+}
+
+/// Static hostnames for `IpAddr`, consulted before performing a reverse DNS lookup.
+///
+/// Hosts are read from a file in the `/etc/hosts` format, in which each line holds an IP address followed by one or
+/// more hostnames, such as `10.1.2.1 office-gw office-gw.lab`.  Text following a `#` is ignored, as are lines which do
+/// not start with a valid IP address.
+#[derive(Debug, Clone, Default)]
+pub struct DnsHosts {
+    hosts: Arc<HashMap<IpAddr, Vec<String>>>,
+}
+
+impl DnsHosts {
+    /// Create new `DnsHosts` from a hosts file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let contents =
+            fs::read_to_string(path.as_ref()).context(format!("{}", path.as_ref().display()))?;
+        Ok(Self::from_str(&contents)?)
+    }
+
+    /// Create `DnsHosts` that returns `None` for all `IpAddr` lookups.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Lookup the static hostnames of an `IpAddr`.
+    pub fn lookup(&self, addr: IpAddr) -> Option<&[String]> {
+        self.hosts.get(&addr).map(Vec::as_slice)
+    }
+}
+
+impl FromStr for DnsHosts {
+    type Err = Infallible;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut hosts: HashMap<IpAddr, Vec<String>> = HashMap::new();
+        for line in contents.lines() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line);
+            let mut fields = line.split_whitespace();
+            let Some(Ok(addr)) = fields.next().map(IpAddr::from_str) else {
+                continue;
+            };
+            let hostnames = hosts.entry(addr).or_default();
+            for hostname in fields {
+                if !hostnames.iter().any(|h| h == hostname) {
+                    hostnames.push(hostname.to_string());
+                }
+            }
         }
+        hosts.retain(|_, hostnames| !hostnames.is_empty());
+        Ok(Self {
+            hosts: Arc::new(hosts),
+        })
     }
 }
 
@@ -108,9 +257,9 @@ pub struct DnsResolver {
 }
 
 impl DnsResolver {
-    pub fn start(config: DnsResolverConfig) -> anyhow::Result<Self> {
+    pub fn start(config: DnsResolverConfig, hosts: DnsHosts) -> anyhow::Result<Self> {
         Ok(Self {
-            inner: Rc::new(DnsResolverInner::start(config)?),
+            inner: Rc::new(DnsResolverInner::start(config, hosts)?),
         })
     }
 
@@ -124,11 +273,13 @@ impl DnsResolver {
     /// If the `IpAddr` has already been resolved then `DnsEntry::Resolved` is returned immediately.
     ///
     /// Otherwise, the `IpAddr` is enqueued to be resolved in the background and a `DnsEntry::Pending` is
-    /// returned.
+    /// returned.  Lookups are performed most recently requested first and so addresses which are requested repeatedly,
+    /// such as those of the hops which are being displayed, are resolved ahead of those which are not.
     ///
     /// If the entry exists but is `DnsEntry::Timeout` then it is changed to be `DnsEntry::Pending` and enqueued.
     ///
-    /// If enqueuing times out then the entry is changed to be `DnsEntry::Timeout` and returned.
+    /// If the queue is full then the least recently requested entry is dropped from it and changed to be
+    /// `DnsEntry::Timeout`, such that it is enqueued again when it is next requested.
     pub fn reverse_lookup(&self, addr: IpAddr) -> DnsEntry {
         self.inner.reverse_lookup(addr, false)
     }
@@ -140,6 +291,13 @@ impl DnsResolver {
         self.inner.reverse_lookup(addr, true)
     }
 
+    /// The time taken to perform the reverse DNS lookup of `IpAddr`, if it has completed.
+    ///
+    /// This includes the time taken to lookup `AS` information, if requested.
+    pub fn reverse_lookup_duration(&self, addr: IpAddr) -> Option<Duration> {
+        self.inner.reverse_lookup_duration(addr)
+    }
+
     /// Get the `DnsResolverConfig`.
     pub fn config(&self) -> &DnsResolverConfig {
         self.inner.config()
@@ -148,61 +306,172 @@ impl DnsResolver {
     pub fn flush(&self) {
         self.inner.flush();
     }
+
+    /// Add previously resolved `DnsEntry` entries to the cache.
+    pub fn seed(&self, entries: impl IntoIterator<Item = DnsEntry>) {
+        self.inner.seed(entries);
+    }
+
+    /// The cached `DnsEntry` entries.
+    pub fn entries(&self) -> Vec<DnsEntry> {
+        self.inner.entries()
+    }
 }
 
 /// Private impl of resolver.
 mod inner {
     use crate::dns::{
-        AsInfo, DnsEntry, DnsResolveMethod, DnsResolverConfig, IpAddrFamily, Resolved, Unresolved,
+        AsInfo, DnsEntry, DnsHosts, DnsResolveMethod, DnsResolverConfig, IpAddrFamily, Resolved,
+        Unresolved,
     };
     use anyhow::anyhow;
-    use crossbeam::channel::{bounded, Receiver, Sender};
     use itertools::Itertools;
-    use parking_lot::RwLock;
-    use std::collections::HashMap;
-    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use parking_lot::{Condvar, Mutex, RwLock};
+    use std::collections::{HashMap, VecDeque};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
     use std::str::FromStr;
     use std::sync::Arc;
     use std::thread;
-    use std::time::Duration;
-    use trust_dns_resolver::config::{LookupIpStrategy, ResolverConfig, ResolverOpts};
+    use std::time::{Duration, Instant};
+    use tracing::{debug, trace, warn};
+    use trust_dns_resolver::config::{
+        LookupIpStrategy, NameServerConfig, ResolverConfig, ResolverOpts,
+    };
     use trust_dns_resolver::error::ResolveErrorKind;
     use trust_dns_resolver::proto::rr::RecordType;
+    use trust_dns_resolver::system_conf::read_system_conf;
     use trust_dns_resolver::{Name, Resolver};
 
-    /// The maximum number of in-flight reverse DNS resolutions that may be
-    const RESOLVER_MAX_QUEUE_SIZE: usize = 100;
+    /// Send queries to the name servers of `resolver_config` from the bind addresses of `config`, if any.
+    ///
+    /// Name servers of an address family which has no bind address are dropped, as queries to them would otherwise be
+    /// sent from an address chosen by the operating system.
+    fn bind_name_servers(
+        resolver_config: &ResolverConfig,
+        config: &DnsResolverConfig,
+    ) -> anyhow::Result<ResolverConfig> {
+        if !config.is_bound() {
+            return Ok(resolver_config.clone());
+        }
+        let name_servers: Vec<_> = resolver_config
+            .name_servers()
+            .iter()
+            .filter_map(|name_server| {
+                let bind_addr = match name_server.socket_addr.ip() {
+                    IpAddr::V4(_) => config.bind_addr_ipv4.map(IpAddr::V4),
+                    IpAddr::V6(_) => config.bind_addr_ipv6.map(IpAddr::V6),
+                }?;
+                Some(NameServerConfig {
+                    bind_addr: Some(SocketAddr::new(bind_addr, 0)),
+                    ..name_server.clone()
+                })
+            })
+            .collect();
+        if name_servers.is_empty() {
+            return Err(anyhow!(
+                "no name servers of the same address family as the dns source address"
+            ));
+        }
+        Ok(ResolverConfig::from_parts(
+            resolver_config.domain().cloned(),
+            resolver_config.search().to_vec(),
+            name_servers,
+        ))
+    }
 
-    /// The duration wait to enqueue a `DnsEntry::Pending` to the resolver before returning `DnsEntry::Timeout`.
-    const RESOLVER_QUEUE_TIMEOUT: Duration = Duration::from_millis(10);
+    /// The maximum number of reverse DNS resolutions that may be queued, excluding those which are in-flight.
+    pub const RESOLVER_MAX_QUEUE_SIZE: usize = 100;
 
     /// Alias for a cache of reverse DNS lookup entries.
     type Cache = Arc<RwLock<HashMap<IpAddr, DnsEntry>>>;
 
+    /// Alias for the durations of completed reverse DNS lookups.
+    type Durations = Arc<RwLock<HashMap<IpAddr, Duration>>>;
+
     #[derive(Clone)]
     enum DnsProvider {
         TrustDns(Arc<Resolver>),
         DnsLookup,
     }
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Copy, Clone)]
     pub struct DnsResolveRequest {
-        addr: IpAddr,
-        with_asinfo: bool,
+        pub addr: IpAddr,
+        pub with_asinfo: bool,
+    }
+
+    /// A bounded queue of reverse DNS lookup requests, ordered most recently requested first.
+    #[derive(Debug)]
+    pub struct RequestQueue {
+        requests: VecDeque<DnsResolveRequest>,
+        capacity: usize,
+        closed: bool,
+    }
+
+    impl RequestQueue {
+        pub fn new(capacity: usize) -> Self {
+            Self {
+                requests: VecDeque::with_capacity(capacity + 1),
+                capacity,
+                closed: false,
+            }
+        }
+
+        /// Add a request to the front of the queue, or move it to the front if it is already queued.
+        ///
+        /// Returns the address of the least recently requested entry if it was dropped as the queue was full.
+        pub fn push(&mut self, request: DnsResolveRequest) -> Option<IpAddr> {
+            let with_asinfo = match self.requests.iter().position(|r| r.addr == request.addr) {
+                Some(index) => self.requests.remove(index).is_some_and(|r| r.with_asinfo),
+                None => false,
+            };
+            self.requests.push_front(DnsResolveRequest {
+                with_asinfo: request.with_asinfo || with_asinfo,
+                ..request
+            });
+            if self.requests.len() > self.capacity {
+                self.requests.pop_back().map(|r| r.addr)
+            } else {
+                None
+            }
+        }
+
+        /// Move a request to the front of the queue if it is queued.
+        pub fn promote(&mut self, addr: IpAddr) {
+            if let Some(index) = self.requests.iter().position(|r| r.addr == addr) {
+                if let Some(request) = self.requests.remove(index) {
+                    self.requests.push_front(request);
+                }
+            }
+        }
+
+        /// Take the most recently requested request from the queue.
+        pub fn pop(&mut self) -> Option<DnsResolveRequest> {
+            self.requests.pop_front()
+        }
     }
 
+    /// Alias for a queue of reverse DNS lookup requests shared with the workers which process it.
+    type Queue = Arc<(Mutex<RequestQueue>, Condvar)>;
+
     /// Resolver implementation.
     pub struct DnsResolverInner {
         config: DnsResolverConfig,
         provider: DnsProvider,
-        tx: Sender<DnsResolveRequest>,
+        queue: Queue,
         addr_cache: Cache,
+        durations: Durations,
     }
 
     impl DnsResolverInner {
-        pub fn start(config: DnsResolverConfig) -> anyhow::Result<Self> {
-            let (tx, rx) = bounded(RESOLVER_MAX_QUEUE_SIZE);
+        pub fn start(config: DnsResolverConfig, hosts: DnsHosts) -> anyhow::Result<Self> {
+            let queue = Arc::new((
+                Mutex::new(RequestQueue::new(RESOLVER_MAX_QUEUE_SIZE)),
+                Condvar::new(),
+            ));
+            let hosts = Arc::new(hosts);
             let addr_cache = Arc::new(RwLock::new(HashMap::new()));
+            let durations = Arc::new(RwLock::new(HashMap::new()));
 
             let provider = if matches!(config.resolve_method, DnsResolveMethod::System) {
                 DnsProvider::DnsLookup
@@ -212,30 +481,46 @@ mod inner {
                 options.ip_strategy = match config.addr_family {
                     IpAddrFamily::Ipv4 => LookupIpStrategy::Ipv4Only,
                     IpAddrFamily::Ipv6 => LookupIpStrategy::Ipv6Only,
+                    IpAddrFamily::Ipv4AndIpv6 => LookupIpStrategy::Ipv4AndIpv6,
                 };
                 let res = match config.resolve_method {
-                    DnsResolveMethod::Resolv => Resolver::from_system_conf(),
-                    DnsResolveMethod::Google => Resolver::new(ResolverConfig::google(), options),
-                    DnsResolveMethod::Cloudflare => {
-                        Resolver::new(ResolverConfig::cloudflare(), options)
+                    DnsResolveMethod::Resolv if config.is_bound() => {
+                        let (resolver_config, options) = read_system_conf()?;
+                        Resolver::new(bind_name_servers(&resolver_config, &config)?, options)
                     }
+                    DnsResolveMethod::Resolv => Resolver::from_system_conf(),
+                    DnsResolveMethod::Google => Resolver::new(
+                        bind_name_servers(&ResolverConfig::google(), &config)?,
+                        options,
+                    ),
+                    DnsResolveMethod::Cloudflare => Resolver::new(
+                        bind_name_servers(&ResolverConfig::cloudflare(), &config)?,
+                        options,
+                    ),
                     DnsResolveMethod::System => unreachable!(),
                 }?;
                 let resolver = Arc::new(res);
                 DnsProvider::TrustDns(resolver)
             };
+            debug!(resolve_method = ?config.resolve_method, timeout = ?config.timeout, max_in_flight = config.max_in_flight, "started dns resolver");
 
-            // spawn a thread to process the resolve queue
-            {
+            // spawn a thread to process the resolve queue for each lookup which may be in-flight
+            for _ in 0..config.max_in_flight {
+                let queue = queue.clone();
+                let hosts = hosts.clone();
                 let cache = addr_cache.clone();
+                let durations = durations.clone();
                 let provider = provider.clone();
-                thread::spawn(move || resolver_queue_processor(rx, &provider, &cache));
+                thread::spawn(move || {
+                    resolver_queue_processor(&queue, &provider, &hosts, &cache, &durations);
+                });
             }
             Ok(Self {
                 config,
                 provider,
-                tx,
+                queue,
                 addr_cache,
+                durations,
             })
         }
 
@@ -278,57 +563,110 @@ mod inner {
                 enqueue = true;
             }
 
-            // If this is a newly added `DnsEntry` then send it to the channel to be resolved in the background.  We do
-            // this after the above to ensure we aren't holding the lock on the cache, which is usd by the resolver and so
-            // would deadlock.
+            // If this is a newly added `DnsEntry` then add it to the queue to be resolved in the background, otherwise
+            // if it is still pending then move it to the front of the queue.  We do this after the above to ensure we
+            // aren't holding the lock on the cache, which is used by the resolver and so would deadlock.
+            let (lock, condvar) = &*self.queue;
             if enqueue {
-                if self
-                    .tx
-                    .send_timeout(
-                        DnsResolveRequest { addr, with_asinfo },
-                        RESOLVER_QUEUE_TIMEOUT,
-                    )
-                    .is_ok()
-                {
-                    dns_entry
-                } else {
-                    *self
-                        .addr_cache
+                let dropped = lock.lock().push(DnsResolveRequest { addr, with_asinfo });
+                condvar.notify_one();
+                if let Some(dropped) = dropped {
+                    debug!(addr = %dropped, "dns resolver queue is full, dropped least recently requested lookup");
+                    self.addr_cache
                         .write()
-                        .get_mut(&addr)
-                        .expect("addr must be in cache") = DnsEntry::Timeout(addr);
-                    DnsEntry::Timeout(addr)
+                        .insert(dropped, DnsEntry::Timeout(dropped));
+                    if dropped == addr {
+                        return DnsEntry::Timeout(addr);
+                    }
                 }
-            } else {
-                dns_entry
+            } else if matches!(dns_entry, DnsEntry::Pending(_)) {
+                lock.lock().promote(addr);
             }
+            dns_entry
+        }
+
+        pub fn reverse_lookup_duration(&self, addr: IpAddr) -> Option<Duration> {
+            self.durations.read().get(&addr).copied()
         }
 
         pub fn flush(&self) {
             self.addr_cache.write().clear();
+            self.durations.write().clear();
+        }
+
+        pub fn seed(&self, entries: impl IntoIterator<Item = DnsEntry>) {
+            let mut cache = self.addr_cache.write();
+            for entry in entries {
+                if let Some(addr) = entry.completed_addr() {
+                    cache.entry(addr).or_insert(entry);
+                }
+            }
+        }
+
+        pub fn entries(&self) -> Vec<DnsEntry> {
+            self.addr_cache.read().values().cloned().collect()
+        }
+    }
+
+    impl Drop for DnsResolverInner {
+        fn drop(&mut self) {
+            let (lock, condvar) = &*self.queue;
+            lock.lock().closed = true;
+            condvar.notify_all();
         }
     }
 
     /// Process each `IpAddr` from the resolver queue and perform the reverse DNS lookup.
     ///
-    /// For each `IpAddr`, perform the reverse DNS lookup and update the cache with the result (`Resolved`, `NotFound`,
-    /// `Timeout` or `Failed`) for that addr.
+    /// Each of the resolver threads processes the queue until the resolver is dropped.  For each `IpAddr`, perform the reverse DNS lookup and update the cache with the result (`Resolved`, `NotFound`,
+    /// `Timeout` or `Failed`) and the time taken for that addr.
+    ///
+    /// If the `IpAddr` has static hostnames in `DnsHosts` then these are used and no reverse DNS lookup is performed.
     fn resolver_queue_processor(
-        rx: Receiver<DnsResolveRequest>,
+        queue: &Queue,
         provider: &DnsProvider,
+        hosts: &DnsHosts,
         cache: &Cache,
+        durations: &Durations,
     ) {
-        for DnsResolveRequest { addr, with_asinfo } in rx {
-            let entry = match &provider {
-                DnsProvider::DnsLookup => {
+        let (lock, condvar) = &**queue;
+        loop {
+            let DnsResolveRequest { addr, with_asinfo } = {
+                let mut queue = lock.lock();
+                loop {
+                    if queue.closed {
+                        return;
+                    }
+                    if let Some(request) = queue.pop() {
+                        break request;
+                    }
+                    condvar.wait(&mut queue);
+                }
+            };
+            trace!(%addr, with_asinfo, "reverse dns lookup");
+            let start = Instant::now();
+            let entry = match (hosts.lookup(addr), &provider) {
+                (Some(hostnames), DnsProvider::TrustDns(resolver)) if with_asinfo => {
+                    let as_info = lookup_asinfo(resolver, addr)
+                        .map_err(|err| debug!(%addr, %err, "AS info lookup failed"))
+                        .unwrap_or_default();
+                    DnsEntry::Resolved(Resolved::WithAsInfo(addr, hostnames.to_vec(), as_info))
+                }
+                (Some(hostnames), _) => {
+                    DnsEntry::Resolved(Resolved::Normal(addr, hostnames.to_vec()))
+                }
+                (None, DnsProvider::DnsLookup) => {
                     // we can't distinguish between a failed lookup or a genuine error and so we just assume all
                     // failures are `DnsEntry::NotFound`.
                     match dns_lookup::lookup_addr(&addr) {
                         Ok(dns) => DnsEntry::Resolved(Resolved::Normal(addr, vec![dns])),
-                        Err(_) => DnsEntry::NotFound(Unresolved::Normal(addr)),
+                        Err(err) => {
+                            debug!(%addr, %err, "reverse dns lookup failed");
+                            DnsEntry::NotFound(Unresolved::Normal(addr))
+                        }
                     }
                 }
-                DnsProvider::TrustDns(resolver) => match resolver.reverse_lookup(addr) {
+                (None, DnsProvider::TrustDns(resolver)) => match resolver.reverse_lookup(addr) {
                     Ok(name) => {
                         let hostnames = name
                             .into_iter()
@@ -339,7 +677,9 @@ mod inner {
                             .map(|s| s.to_string())
                             .collect();
                         if with_asinfo {
-                            let as_info = lookup_asinfo(resolver, addr).unwrap_or_default();
+                            let as_info = lookup_asinfo(resolver, addr)
+                                .map_err(|err| debug!(%addr, %err, "AS info lookup failed"))
+                                .unwrap_or_default();
                             DnsEntry::Resolved(Resolved::WithAsInfo(addr, hostnames, as_info))
                         } else {
                             DnsEntry::Resolved(Resolved::Normal(addr, hostnames))
@@ -348,17 +688,28 @@ mod inner {
                     Err(err) => match err.kind() {
                         ResolveErrorKind::NoRecordsFound { .. } => {
                             if with_asinfo {
-                                let as_info = lookup_asinfo(resolver, addr).unwrap_or_default();
+                                let as_info = lookup_asinfo(resolver, addr)
+                                    .map_err(|err| debug!(%addr, %err, "AS info lookup failed"))
+                                    .unwrap_or_default();
                                 DnsEntry::NotFound(Unresolved::WithAsInfo(addr, as_info))
                             } else {
                                 DnsEntry::NotFound(Unresolved::Normal(addr))
                             }
                         }
-                        ResolveErrorKind::Timeout => DnsEntry::Timeout(addr),
-                        _ => DnsEntry::Failed(addr),
+                        ResolveErrorKind::Timeout => {
+                            warn!(%addr, "reverse dns lookup timed out");
+                            DnsEntry::Timeout(addr)
+                        }
+                        _ => {
+                            warn!(%addr, %err, "reverse dns lookup failed");
+                            DnsEntry::Failed(addr)
+                        }
                     },
                 },
             };
+            let elapsed = start.elapsed();
+            trace!(%addr, ?elapsed, "reverse dns lookup complete");
+            durations.write().insert(addr, elapsed);
             cache.write().insert(addr, entry);
         }
     }
@@ -473,3 +824,96 @@ mod inner {
         Ok(split.nth(4).unwrap_or_default().trim().to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const HOSTS: &str = r"
+# lab network
+127.0.0.1   localhost
+10.1.2.1    office-gw office-gw.lab  # core gateway
+10.1.2.1    office-gw.lab office-gw-alt
+2001:db8::1 lab-v6
+fe80::1%lo0 localhost
+10.1.2.2
+";
+
+    #[test_case("127.0.0.1", Some(&["localhost"]); "single hostname")]
+    #[test_case("10.1.2.1", Some(&["office-gw", "office-gw.lab", "office-gw-alt"]); "merged aliases")]
+    #[test_case("2001:db8::1", Some(&["lab-v6"]); "ipv6 address")]
+    #[test_case("10.1.2.2", None; "no hostnames")]
+    #[test_case("10.1.2.3", None; "unknown address")]
+    fn test_lookup(addr: &str, expected: Option<&[&str]>) {
+        let hosts = DnsHosts::from_str(HOSTS).unwrap();
+        let actual = hosts.lookup(IpAddr::from_str(addr).unwrap());
+        assert_eq!(
+            expected.map(<[&str]>::to_vec),
+            actual.map(|h| h.iter().map(String::as_str).collect::<Vec<_>>())
+        );
+    }
+    fn request(addr: &str, with_asinfo: bool) -> inner::DnsResolveRequest {
+        inner::DnsResolveRequest {
+            addr: IpAddr::from_str(addr).unwrap(),
+            with_asinfo,
+        }
+    }
+
+    fn drain(queue: &mut inner::RequestQueue) -> Vec<(String, bool)> {
+        std::iter::from_fn(|| queue.pop())
+            .map(|r| (r.addr.to_string(), r.with_asinfo))
+            .collect()
+    }
+
+    #[test]
+    fn test_queue_most_recent_first() {
+        let mut queue = inner::RequestQueue::new(10);
+        assert_eq!(None, queue.push(request("10.0.0.1", false)));
+        assert_eq!(None, queue.push(request("10.0.0.2", false)));
+        assert_eq!(None, queue.push(request("10.0.0.3", false)));
+        queue.promote(IpAddr::from_str("10.0.0.1").unwrap());
+        queue.promote(IpAddr::from_str("10.0.0.4").unwrap());
+        assert_eq!(
+            vec![
+                (String::from("10.0.0.1"), false),
+                (String::from("10.0.0.3"), false),
+                (String::from("10.0.0.2"), false),
+            ],
+            drain(&mut queue)
+        );
+    }
+
+    #[test]
+    fn test_queue_requeue_merges_asinfo() {
+        let mut queue = inner::RequestQueue::new(10);
+        queue.push(request("10.0.0.1", true));
+        queue.push(request("10.0.0.2", false));
+        queue.push(request("10.0.0.1", false));
+        assert_eq!(
+            vec![
+                (String::from("10.0.0.1"), true),
+                (String::from("10.0.0.2"), false),
+            ],
+            drain(&mut queue)
+        );
+    }
+
+    #[test]
+    fn test_queue_full_drops_least_recent() {
+        let mut queue = inner::RequestQueue::new(2);
+        assert_eq!(None, queue.push(request("10.0.0.1", false)));
+        assert_eq!(None, queue.push(request("10.0.0.2", false)));
+        assert_eq!(
+            Some(IpAddr::from_str("10.0.0.1").unwrap()),
+            queue.push(request("10.0.0.3", false))
+        );
+        assert_eq!(
+            vec![
+                (String::from("10.0.0.3"), false),
+                (String::from("10.0.0.2"), false),
+            ],
+            drain(&mut queue)
+        );
+    }
+}
@@ -1,27 +1,42 @@
-use crate::backend::Hop;
+use crate::annotations::{Annotations, SpecialPurpose};
+use crate::backend::{
+    format_as_path, AddrStats, Hop, HopStatus, IcmpResponse, Seen, Timestamp, TraceSummary,
+};
 use crate::config::{
-    AddressMode, AsMode, DnsResolveMethod, GeoIpMode, TuiBindings, TuiColor, TuiKeyBinding,
-    TuiTheme,
+    AddressMode, AsMode, DnsResolveMethod, Dscp, EcnCodepoint, GeoIpMode, HopFilter, Locale,
+    ReportColumn, RttFormat, TuiBindings, TuiColor, TuiExportFormat, TuiKeyBinding, TuiPalette,
+    TuiPane, TuiTheme,
 };
-use crate::dns::{AsInfo, DnsEntry, Resolved, Unresolved};
-use crate::geoip::{GeoIpCity, GeoIpLookup};
+use crate::dns::{AsInfo, DnsEntry, DnsResolverConfig, Resolved, Unresolved};
+use crate::geoip::{geo_distance, GeoDistance, GeoIpCity, GeoIpLookup};
+use crate::locale::{translate, translate_with};
+use crate::problems::ProblemLog;
+use crate::report;
+use crate::session::Session;
 use crate::{DnsResolver, Trace, TraceInfo};
-use chrono::SecondsFormat;
+use chrono::{DateTime, Local, SecondsFormat};
 use crossterm::event::{KeyEvent, KeyEventKind, KeyModifiers};
 use crossterm::{
     event::{self, Event, KeyCode},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    style::Print,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+    },
 };
 use humantime::format_duration;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Display, Formatter};
 use std::io;
 use std::net::IpAddr;
+use std::ops::RangeInclusive;
+use std::path::Path;
 use std::rc::Rc;
-use std::time::{Duration, SystemTime};
-use trippy::tracing::{PortDirection, TracerProtocol};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, error, info, trace, warn};
+use trippy::tracing::{Egress, PortDirection, TracerProtocol};
 use tui::layout::{Alignment, Direction, Margin, Rect};
 use tui::symbols::Marker;
 use tui::text::{Span, Spans};
@@ -38,29 +53,100 @@ use tui::{
     Frame, Terminal,
 };
 
-const TABLE_HEADER: [&str; 11] = [
-    "#", "Host", "Loss%", "Snt", "Recv", "Last", "Avg", "Best", "Wrst", "StDev", "Sts",
+const TABLE_HEADER: [&str; 13] = [
+    "#", "Host", "Loss%", "Snt", "Recv", "Dup", "Last", "Avg", "Best", "Wrst", "StDev", "Rtn",
+    "Sts",
 ];
 
 /// The name and number of items for each tabs in the setting dialog.
 const SETTINGS_TABS: [(&str, usize); 6] = [
-    ("Tui", 7),
-    ("Trace", 14),
-    ("Dns", 3),
+    ("Tui", 19),
+    ("Trace", 28),
+    ("Dns", 5),
     ("GeoIp", 1),
-    ("Bindings", 25),
+    ("Bindings", 34),
     ("Theme", 27),
 ];
 
 /// The settings table header.
 const SETTINGS_TABLE_HEADER: [&str; 2] = ["Setting", "Value"];
 
-const TABLE_WIDTH: [Constraint; 11] = [
-    Constraint::Percentage(3),
-    Constraint::Percentage(42),
+/// The route changes table header.
+const ROUTE_CHANGES_TABLE_HEADER: [&str; 5] = ["Time", "Round", "Hop", "From", "To"];
+
+const ROUTE_CHANGES_TABLE_WIDTH: [Constraint; 5] = [
+    Constraint::Length(20),
+    Constraint::Length(8),
+    Constraint::Length(4),
+    Constraint::Percentage(50),
+    Constraint::Percentage(50),
+];
+
+/// The DNS lookups table header.
+const DNS_TABLE_HEADER: [&str; 4] = ["Hop", "Address", "Hostname", "Lookup"];
+
+const DNS_TABLE_WIDTH: [Constraint; 4] = [
+    Constraint::Length(4),
+    Constraint::Percentage(35),
+    Constraint::Percentage(50),
+    Constraint::Length(10),
+];
+
+/// The diagnostics table header.
+const DIAGNOSTICS_TABLE_HEADER: [&str; 2] = ["Diagnostic", "Count"];
+
+const DIAGNOSTICS_TABLE_WIDTH: [Constraint; 2] =
+    [Constraint::Percentage(80), Constraint::Percentage(20)];
+
+/// The runtime problems table header.
+const PROBLEMS_TABLE_HEADER: [&str; 3] = ["Time", "Level", "Problem"];
+
+const PROBLEMS_TABLE_WIDTH: [Constraint; 3] = [
+    Constraint::Length(10),
+    Constraint::Length(7),
+    Constraint::Percentage(100),
+];
+
+/// The height of the diagnostics table, which is shown above the table of runtime problems.
+const DIAGNOSTICS_TABLE_HEIGHT: u16 = 8;
+
+/// The maximum number of characters of the latest runtime problem shown in the header.
+const MAX_PROBLEM_MESSAGE_LEN: usize = 60;
+
+/// The overview table header.
+const OVERVIEW_TABLE_HEADER: [&str; 6] =
+    ["Target", "Status", "Loss%", "Path", "Rate", "Last Change"];
+
+const OVERVIEW_TABLE_WIDTH: [Constraint; 6] = [
+    Constraint::Percentage(40),
+    Constraint::Length(14),
+    Constraint::Length(8),
+    Constraint::Length(6),
+    Constraint::Length(20),
+    Constraint::Length(20),
+];
+
+/// The header of the hop tables of the comparison view.
+const COMPARE_TABLE_HEADER: [&str; 7] = ["#", "Host", "Loss%", "Snt", "Last", "Avg", "StDev"];
+
+const COMPARE_TABLE_WIDTH: [Constraint; 7] = [
+    Constraint::Percentage(7),
+    Constraint::Percentage(48),
+    Constraint::Percentage(9),
+    Constraint::Percentage(9),
+    Constraint::Percentage(9),
+    Constraint::Percentage(9),
+    Constraint::Percentage(9),
+];
+
+const TABLE_WIDTH: [Constraint; 13] = [
+    Constraint::Percentage(4),
+    Constraint::Percentage(37),
     Constraint::Percentage(5),
     Constraint::Percentage(5),
     Constraint::Percentage(5),
+    Constraint::Percentage(4),
+    Constraint::Percentage(5),
     Constraint::Percentage(5),
     Constraint::Percentage(5),
     Constraint::Percentage(5),
@@ -69,6 +155,28 @@ const TABLE_WIDTH: [Constraint; 11] = [
     Constraint::Percentage(5),
 ];
 
+/// The header of the optional columns of the hop table which show when each address was first and last seen.
+const SEEN_TABLE_HEADER: [&str; 2] = ["First", "Seen"];
+
+/// The widths of the hop table with the optional first and last seen columns.
+const SEEN_TABLE_WIDTH: [Constraint; 15] = [
+    Constraint::Percentage(4),
+    Constraint::Percentage(21),
+    Constraint::Percentage(5),
+    Constraint::Percentage(5),
+    Constraint::Percentage(5),
+    Constraint::Percentage(4),
+    Constraint::Percentage(5),
+    Constraint::Percentage(5),
+    Constraint::Percentage(5),
+    Constraint::Percentage(5),
+    Constraint::Percentage(5),
+    Constraint::Percentage(5),
+    Constraint::Percentage(5),
+    Constraint::Percentage(8),
+    Constraint::Percentage(8),
+];
+
 const SETTINGS_TABLE_WIDTH: [Constraint; 3] = [
     Constraint::Length(3),
     Constraint::Min(1),
@@ -76,18 +184,27 @@ const SETTINGS_TABLE_WIDTH: [Constraint; 3] = [
 ];
 
 const LAYOUT_WITHOUT_TABS: [Constraint; 3] = [
-    Constraint::Length(5),
+    Constraint::Length(6),
     Constraint::Min(10),
     Constraint::Length(6),
 ];
 
 const LAYOUT_WITH_TABS: [Constraint; 4] = [
-    Constraint::Length(5),
+    Constraint::Length(6),
     Constraint::Length(3),
     Constraint::Min(10),
     Constraint::Length(6),
 ];
 
+/// The minimum width of the body for which the panes are shown beside the hop table rather than below it.
+const PANES_MIN_WIDTH_BESIDE: u16 = 200;
+
+/// The minimum height of the body for which the panes are shown below the hop table.
+///
+/// The panes are not shown if the body is neither wide enough to show them beside the hop table nor tall enough to
+/// show them below it.
+const PANES_MIN_HEIGHT_BELOW: u16 = 24;
+
 const MAP_LAYOUT: [Constraint; 3] = [
     Constraint::Min(1),
     Constraint::Length(3),
@@ -96,7 +213,120 @@ const MAP_LAYOUT: [Constraint; 3] = [
 
 const MAX_ZOOM_FACTOR: usize = 16;
 
-const HELP_LINES: [&str; 20] = [
+/// The interval at which the session is saved to the state file, such that little is lost if Trippy is killed.
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The duration for which the outcome of exporting a report is shown in the header.
+const EXPORT_MESSAGE_DURATION: Duration = Duration::from_secs(5);
+
+/// The `xterm` control sequence which saves the terminal title on a stack.
+const SAVE_TITLE: &str = "\x1b[22;0t";
+
+/// The `xterm` control sequence which restores the terminal title saved with `SAVE_TITLE`.
+const RESTORE_TITLE: &str = "\x1b[23;0t";
+
+/// The colors used, in rotation, for each group of consecutive hops in the same autonomous system.
+const AS_GROUP_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::Green,
+    Color::Blue,
+    Color::Red,
+];
+
+/// The colors used to mark the `AS` groups in the `deuteranopia` and `protanopia` palettes.
+///
+/// These are taken from the Okabe-Ito palette, which avoids relying on red-green contrast.
+const RED_GREEN_SAFE_AS_GROUP_COLORS: [Color; 6] = [
+    Color::Rgb(0, 114, 178),
+    Color::Rgb(230, 159, 0),
+    Color::Rgb(86, 180, 233),
+    Color::Rgb(213, 94, 0),
+    Color::Rgb(204, 121, 167),
+    Color::Rgb(240, 228, 66),
+];
+
+/// The colors used to mark the `AS` groups in the `tritanopia` palette, which avoids relying on blue-yellow contrast.
+const BLUE_YELLOW_SAFE_AS_GROUP_COLORS: [Color; 6] = [
+    Color::Rgb(213, 0, 0),
+    Color::Rgb(0, 158, 115),
+    Color::Rgb(204, 121, 167),
+    Color::Rgb(153, 153, 153),
+    Color::Rgb(136, 34, 85),
+    Color::Rgb(0, 170, 170),
+];
+
+/// The colors used to signal the status of hops in the `deuteranopia` and `protanopia` palettes.
+///
+/// The severity of the loss is distinguished by lightness as well as hue, with no loss shown in blue and no responses
+/// from the target shown in vermillion.
+const RED_GREEN_SAFE_STATUS_COLORS: StatusColors = StatusColors {
+    ok: Color::Rgb(86, 180, 233),
+    loss: Color::Rgb(0, 114, 178),
+    no_response: Color::Rgb(153, 153, 153),
+    rate_limited: Color::Rgb(204, 121, 167),
+    target_loss: Color::Rgb(240, 228, 66),
+    target_down: Color::Rgb(213, 94, 0),
+};
+
+/// The colors used to signal the status of hops in the `tritanopia` palette.
+///
+/// The severity of the loss is distinguished by red-cyan contrast, with no loss shown in bluish green and no responses
+/// from the target shown in red.
+const BLUE_YELLOW_SAFE_STATUS_COLORS: StatusColors = StatusColors {
+    ok: Color::Rgb(0, 158, 115),
+    loss: Color::Rgb(0, 170, 170),
+    no_response: Color::Rgb(153, 153, 153),
+    rate_limited: Color::Rgb(136, 34, 85),
+    target_loss: Color::Rgb(255, 140, 140),
+    target_down: Color::Rgb(213, 0, 0),
+};
+
+/// The color of each status of a hop.
+struct StatusColors {
+    ok: Color,
+    loss: Color,
+    no_response: Color,
+    rate_limited: Color,
+    target_loss: Color,
+    target_down: Color,
+}
+
+impl StatusColors {
+    /// The colors of the status of hops in a `TuiPalette`, if the status is shown as colored text in the palette.
+    const fn from_palette(palette: TuiPalette) -> Option<&'static Self> {
+        match palette {
+            TuiPalette::Default => None,
+            TuiPalette::Deuteranopia | TuiPalette::Protanopia => {
+                Some(&RED_GREEN_SAFE_STATUS_COLORS)
+            }
+            TuiPalette::Tritanopia => Some(&BLUE_YELLOW_SAFE_STATUS_COLORS),
+        }
+    }
+
+    const fn color(&self, status: HopStatus) -> Color {
+        match status {
+            HopStatus::Ok => self.ok,
+            HopStatus::Loss => self.loss,
+            HopStatus::NoResponse => self.no_response,
+            HopStatus::RateLimited => self.rate_limited,
+            HopStatus::TargetLoss => self.target_loss,
+            HopStatus::TargetDown => self.target_down,
+        }
+    }
+}
+
+/// The colors used to mark the `AS` groups in a `TuiPalette`.
+const fn as_group_colors(palette: TuiPalette) -> &'static [Color] {
+    match palette {
+        TuiPalette::Default => &AS_GROUP_COLORS,
+        TuiPalette::Deuteranopia | TuiPalette::Protanopia => &RED_GREEN_SAFE_AS_GROUP_COLORS,
+        TuiPalette::Tritanopia => &BLUE_YELLOW_SAFE_AS_GROUP_COLORS,
+    }
+}
+
+const HELP_LINES: [&str; 29] = [
     "[up] & [down]    - select hop",
     "[left] & [right] - select trace",
     ", & .            - select hop address",
@@ -104,8 +334,16 @@ const HELP_LINES: [&str; 20] = [
     "d                - toggle hop details",
     "c                - toggle chart",
     "m                - toggle map",
+    "l                - toggle route changes",
+    "r                - toggle DNS lookups",
+    "x                - toggle diagnostics",
+    "p                - toggle multi-pane layout",
+    "v                - toggle comparison with another trace",
+    "o                - toggle overview of all traces by group",
+    "u                - toggle hiding hops",
     "f                - toggle freeze display",
     "Ctrl+r           - reset statistics",
+    "Ctrl+Shift+r     - reset statistics for all traces",
     "Ctrl+k           - flush DNS cache",
     "i                - show IP only",
     "n                - show hostname only",
@@ -114,6 +352,7 @@ const HELP_LINES: [&str; 20] = [
     "{ & }            - expand & collapse hosts to max and min",
     "+ & -            - zoom chart in and out",
     "z                - toggle AS information (if available)",
+    "e                - export a report of the selected trace",
     "h                - toggle help",
     "s                - toggle settings",
     "q                - quit",
@@ -136,6 +375,13 @@ pub struct Bindings {
     toggle_freeze: KeyBinding,
     toggle_chart: KeyBinding,
     toggle_map: KeyBinding,
+    toggle_route_changes: KeyBinding,
+    toggle_dns: KeyBinding,
+    toggle_diagnostics: KeyBinding,
+    toggle_panes: KeyBinding,
+    toggle_compare: KeyBinding,
+    toggle_overview: KeyBinding,
+    toggle_hide_hops: KeyBinding,
     expand_hosts: KeyBinding,
     contract_hosts: KeyBinding,
     expand_hosts_max: KeyBinding,
@@ -143,10 +389,12 @@ pub struct Bindings {
     chart_zoom_in: KeyBinding,
     chart_zoom_out: KeyBinding,
     clear_trace_data: KeyBinding,
+    clear_all_trace_data: KeyBinding,
     clear_dns_cache: KeyBinding,
     clear_selection: KeyBinding,
     toggle_as_info: KeyBinding,
     toggle_hop_details: KeyBinding,
+    export_report: KeyBinding,
     quit: KeyBinding,
 }
 
@@ -167,6 +415,13 @@ impl From<TuiBindings> for Bindings {
             toggle_freeze: KeyBinding::from(value.toggle_freeze),
             toggle_chart: KeyBinding::from(value.toggle_chart),
             toggle_map: KeyBinding::from(value.toggle_map),
+            toggle_route_changes: KeyBinding::from(value.toggle_route_changes),
+            toggle_dns: KeyBinding::from(value.toggle_dns),
+            toggle_diagnostics: KeyBinding::from(value.toggle_diagnostics),
+            toggle_panes: KeyBinding::from(value.toggle_panes),
+            toggle_compare: KeyBinding::from(value.toggle_compare),
+            toggle_overview: KeyBinding::from(value.toggle_overview),
+            toggle_hide_hops: KeyBinding::from(value.toggle_hide_hops),
             expand_hosts: KeyBinding::from(value.expand_hosts),
             contract_hosts: KeyBinding::from(value.contract_hosts),
             expand_hosts_max: KeyBinding::from(value.expand_hosts_max),
@@ -174,10 +429,12 @@ impl From<TuiBindings> for Bindings {
             chart_zoom_in: KeyBinding::from(value.chart_zoom_in),
             chart_zoom_out: KeyBinding::from(value.chart_zoom_out),
             clear_trace_data: KeyBinding::from(value.clear_trace_data),
+            clear_all_trace_data: KeyBinding::from(value.clear_all_trace_data),
             clear_dns_cache: KeyBinding::from(value.clear_dns_cache),
             clear_selection: KeyBinding::from(value.clear_selection),
             toggle_as_info: KeyBinding::from(value.toggle_as_info),
             toggle_hop_details: KeyBinding::from(value.toggle_hop_details),
+            export_report: KeyBinding::from(value.export_report),
             quit: KeyBinding::from(value.quit),
         }
     }
@@ -329,6 +586,41 @@ pub struct Theme {
     map_info_panel_text_color: Color,
 }
 
+impl Theme {
+    /// A theme without colors, which renders everything in the default colors of the terminal.
+    const fn plain() -> Self {
+        Self {
+            bg_color: Color::Reset,
+            border_color: Color::Reset,
+            text_color: Color::Reset,
+            tab_text_color: Color::Reset,
+            hops_table_header_bg_color: Color::Reset,
+            hops_table_header_text_color: Color::Reset,
+            hops_table_row_active_text_color: Color::Reset,
+            hops_table_row_inactive_text_color: Color::Reset,
+            hops_chart_selected_color: Color::Reset,
+            hops_chart_unselected_color: Color::Reset,
+            hops_chart_axis_color: Color::Reset,
+            frequency_chart_bar_color: Color::Reset,
+            frequency_chart_text_color: Color::Reset,
+            samples_chart_color: Color::Reset,
+            help_dialog_bg_color: Color::Reset,
+            help_dialog_text_color: Color::Reset,
+            settings_dialog_bg_color: Color::Reset,
+            settings_tab_text_color: Color::Reset,
+            settings_table_header_text_color: Color::Reset,
+            settings_table_header_bg_color: Color::Reset,
+            settings_table_row_text_color: Color::Reset,
+            map_world_color: Color::Reset,
+            map_radius_color: Color::Reset,
+            map_selected_color: Color::Reset,
+            map_info_panel_border_color: Color::Reset,
+            map_info_panel_bg_color: Color::Reset,
+            map_info_panel_text_color: Color::Reset,
+        }
+    }
+}
+
 impl From<TuiTheme> for Theme {
     fn from(value: TuiTheme) -> Self {
         Self {
@@ -419,6 +711,20 @@ pub struct TuiConfig {
     refresh_rate: Duration,
     /// Preserve screen on exit.
     preserve_screen: bool,
+    /// Set the terminal title to a summary of the selected trace.
+    title: bool,
+    /// Show when each address was first and last seen in the hop table.
+    seen_columns: bool,
+    /// Render using ASCII characters only.
+    ascii: bool,
+    /// Render without colors, with textual status indicators and a linear layout.
+    plain: bool,
+    /// The palette of colors used to signal the status of hops.
+    palette: TuiPalette,
+    /// The unit and precision of round-trip times.
+    rtt_format: RttFormat,
+    /// The language of the labels.
+    locale: Locale,
     /// How to render addresses.
     address_mode: AddressMode,
     /// Lookup `AS` information.
@@ -431,51 +737,137 @@ pub struct TuiConfig {
     max_addrs: Option<u8>,
     /// The maximum number of samples to record per hop.
     max_samples: usize,
+    /// The filter of hops to hide.
+    hop_filter: HopFilter,
+    /// Hide hops which match the filter on startup.
+    hide_hops: bool,
+    /// The panes to show alongside the hop table in the multi-pane layout.
+    panes: Vec<TuiPane>,
+    /// The percentage of the multi-pane layout given to the hop table.
+    pane_split: u16,
     /// The Tui color theme.
     theme: Theme,
     /// The Tui keyboard bindings.
     bindings: Bindings,
+    /// The maximum duration to run for before exiting.
+    max_duration: Option<Duration>,
+    /// The file to save the session to.
+    state_file: Option<String>,
+    /// The format of exported reports.
+    export_format: TuiExportFormat,
+    /// The directory to write exported reports to.
+    export_dir: String,
+    /// The columns of exported table and CSV reports.
+    report_columns: Vec<ReportColumn>,
+    /// Whether exported table and CSV reports show the statistics of each address of a hop.
+    report_per_address: bool,
 }
 
 impl TuiConfig {
-    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
     pub fn new(
         refresh_rate: Duration,
         preserve_screen: bool,
+        title: bool,
+        seen_columns: bool,
+        ascii: bool,
+        plain: bool,
+        palette: TuiPalette,
+        rtt_format: RttFormat,
+        locale: Locale,
         address_mode: AddressMode,
         lookup_as_info: bool,
         as_mode: AsMode,
         geoip_mode: GeoIpMode,
         max_addrs: Option<u8>,
         max_samples: usize,
+        hide_hops: Option<HopFilter>,
+        panes: Vec<TuiPane>,
+        pane_split: u16,
         tui_theme: TuiTheme,
         tui_bindings: &TuiBindings,
+        max_duration: Option<Duration>,
+        state_file: Option<String>,
+        export_format: TuiExportFormat,
+        export_dir: String,
+        report_columns: Vec<ReportColumn>,
+        report_per_address: bool,
     ) -> Self {
         Self {
             refresh_rate,
             preserve_screen,
+            title,
+            seen_columns,
+            ascii,
+            plain,
+            palette,
+            rtt_format,
+            locale,
             address_mode,
             lookup_as_info,
             as_mode,
             geoip_mode,
             max_addrs,
             max_samples,
-            theme: Theme::from(tui_theme),
+            hide_hops: hide_hops.is_some(),
+            hop_filter: hide_hops.unwrap_or_else(HopFilter::unresponsive),
+            panes,
+            pane_split,
+            theme: if plain {
+                Theme::plain()
+            } else {
+                Theme::from(tui_theme)
+            },
             bindings: Bindings::from(*tui_bindings),
+            max_duration,
+            state_file,
+            export_format,
+            export_dir,
+            report_columns,
+            report_per_address,
         }
     }
 }
 
+/// The view of the Tui, which is saved with a session and restored when it is resumed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TuiView {
+    trace_selected: usize,
+    trace_compared: Option<usize>,
+    show_hop_details: bool,
+    show_chart: bool,
+    show_map: bool,
+    show_route_changes: bool,
+    show_dns: bool,
+    show_diagnostics: bool,
+    show_panes: bool,
+    hide_hops: bool,
+    zoom_factor: usize,
+}
+
 struct TuiApp {
     selected_tracer_data: Trace,
+    /// The selected trace and generation of the current trace data snapshot.
+    snapshot_key: Option<(usize, usize)>,
     trace_info: Vec<TraceInfo>,
     tui_config: TuiConfig,
-    /// The state of the hop table.
+    /// The selected hop.
+    ///
+    /// The index of the selected hop, which differs from the selected row of the hop table when hops are hidden.
     table_state: TableState,
+    /// The state of the rows of the hop table.
+    hops_table_state: TableState,
     /// The state of the settings table.
     setting_table_state: TableState,
     /// The selected trace.
     trace_selected: usize,
+    /// The trace which is compared side by side with the selected trace, if any.
+    trace_compared: Option<usize>,
+    /// The trace data of the compared trace.
+    compared_tracer_data: Trace,
+    /// The compared trace and generation of the compared trace data snapshot.
+    compared_snapshot_key: Option<(usize, usize)>,
     /// The selected tab in the settings dialog.
     settings_tab_selected: usize,
     /// The index of the current address to show for the selected hop.
@@ -489,8 +881,19 @@ struct TuiApp {
     show_hop_details: bool,
     show_chart: bool,
     show_map: bool,
+    show_route_changes: bool,
+    show_dns: bool,
+    show_diagnostics: bool,
+    show_panes: bool,
+    /// Show the overview of all traces, by group, in place of the selected trace.
+    show_overview: bool,
+    hide_hops: bool,
     frozen_start: Option<SystemTime>,
     zoom_factor: usize,
+    /// The outcome of the latest export of a report and when it was exported.
+    export_message: Option<(Instant, String)>,
+    /// The problems which occurred at runtime.
+    problems: ProblemLog,
 }
 
 impl TuiApp {
@@ -499,14 +902,24 @@ impl TuiApp {
         resolver: DnsResolver,
         geoip_lookup: GeoIpLookup,
         trace_info: Vec<TraceInfo>,
+        view: Option<TuiView>,
+        problems: ProblemLog,
     ) -> Self {
-        Self {
-            selected_tracer_data: Trace::new(tui_config.max_samples),
+        let hide_hops = tui_config.hide_hops;
+        // the overview is shown on startup when the traces are grouped
+        let show_overview = trace_info.iter().any(|trace| trace.group.is_some());
+        let app = Self {
+            selected_tracer_data: Trace::new(tui_config.max_samples, false, false, false),
+            compared_tracer_data: Trace::new(tui_config.max_samples, false, false, false),
+            snapshot_key: None,
             trace_info,
             tui_config,
             table_state: TableState::default(),
+            hops_table_state: TableState::default(),
             setting_table_state: TableState::default(),
             trace_selected: 0,
+            trace_compared: None,
+            compared_snapshot_key: None,
             settings_tab_selected: 0,
             selected_hop_address: 0,
             resolver,
@@ -516,8 +929,73 @@ impl TuiApp {
             show_hop_details: false,
             show_chart: false,
             show_map: false,
+            show_route_changes: false,
+            show_dns: false,
+            show_diagnostics: false,
+            show_panes: false,
+            show_overview,
+            hide_hops,
             frozen_start: None,
             zoom_factor: 1,
+            export_message: None,
+            problems,
+        };
+        match view {
+            Some(view) => app.with_view(view),
+            None => app,
+        }
+    }
+
+    /// Restore the view of a previous session, ignoring the selected and compared traces if they no longer exist.
+    fn with_view(self, view: TuiView) -> Self {
+        let traces = self.trace_info.len();
+        Self {
+            trace_selected: if view.trace_selected < traces {
+                view.trace_selected
+            } else {
+                0
+            },
+            trace_compared: view
+                .trace_compared
+                .filter(|&compared| compared < traces && compared != view.trace_selected),
+            show_hop_details: view.show_hop_details,
+            show_chart: view.show_chart,
+            show_map: view.show_map,
+            show_route_changes: view.show_route_changes,
+            show_dns: view.show_dns,
+            show_diagnostics: view.show_diagnostics,
+            show_panes: view.show_panes,
+            hide_hops: view.hide_hops,
+            zoom_factor: view.zoom_factor.clamp(1, MAX_ZOOM_FACTOR),
+            ..self
+        }
+    }
+
+    /// The view of the Tui, to be saved with the session.
+    fn view(&self) -> TuiView {
+        TuiView {
+            trace_selected: self.trace_selected,
+            trace_compared: self.trace_compared,
+            show_hop_details: self.show_hop_details,
+            show_chart: self.show_chart,
+            show_map: self.show_map,
+            show_route_changes: self.show_route_changes,
+            show_dns: self.show_dns,
+            show_diagnostics: self.show_diagnostics,
+            show_panes: self.show_panes,
+            hide_hops: self.hide_hops,
+            zoom_factor: self.zoom_factor,
+        }
+    }
+
+    /// Save the traces and the view to the state file, if any.
+    ///
+    /// Failures are logged and otherwise ignored, such that they do not interrupt the Tui.
+    fn save_session(&self) {
+        if let Some(path) = &self.tui_config.state_file {
+            if let Err(err) = Session::capture(&self.trace_info, self.view()).save(path) {
+                warn!(path, %err, "failed to save session");
+            }
         }
     }
 
@@ -525,13 +1003,63 @@ impl TuiApp {
         &self.selected_tracer_data
     }
 
-    fn snapshot_trace_data(&mut self) {
-        self.selected_tracer_data = self.trace_info[self.trace_selected].data.read().clone();
+    /// Export a report of the trace data shown for the selected trace, which is frozen if the display is frozen.
+    ///
+    /// The outcome is shown in the header for a few seconds, such that a failure does not interrupt the Tui.
+    fn export_report(&mut self) {
+        let message = match report::export_report(
+            self.tracer_config(),
+            &self.selected_tracer_data,
+            &self.resolver,
+            &self.geoip_lookup,
+            self.tui_config.export_format,
+            Path::new(&self.tui_config.export_dir),
+            self.tui_config.rtt_format,
+            &self.tui_config.report_columns,
+            self.tui_config.report_per_address,
+            self.tui_config.locale,
+        ) {
+            Ok(path) => format!("exported {}", path.display()),
+            Err(err) => {
+                warn!(%err, "failed to export report");
+                format!("export failed: {err}")
+            }
+        };
+        self.export_message = Some((Instant::now(), message));
+    }
+
+    /// Snapshot the trace data of the selected trace, and of the compared trace, if it has changed since the last
+    /// snapshot.
+    ///
+    /// Returns true if a new snapshot was taken.
+    fn snapshot_trace_data(&mut self) -> bool {
+        let selected = snapshot(
+            &self.trace_info,
+            self.trace_selected,
+            &mut self.selected_tracer_data,
+            &mut self.snapshot_key,
+        );
+        let compared = self.trace_compared.is_some_and(|index| {
+            snapshot(
+                &self.trace_info,
+                index,
+                &mut self.compared_tracer_data,
+                &mut self.compared_snapshot_key,
+            )
+        });
+        selected || compared
     }
 
     fn clear_trace_data(&mut self) {
-        *self.trace_info[self.trace_selected].data.write() =
-            Trace::new(self.tui_config.max_samples);
+        self.trace_info[self.trace_selected].data.write().clear();
+        self.snapshot_key = None;
+    }
+
+    fn clear_all_trace_data(&mut self) {
+        for info in &self.trace_info {
+            info.data.write().clear();
+        }
+        self.snapshot_key = None;
     }
 
     pub fn selected_hop_or_target(&self) -> &Hop {
@@ -560,53 +1088,83 @@ impl TuiApp {
         }
     }
 
+    /// Select the next hop which is not hidden.
     fn next_hop(&mut self) {
         let hop_count = self.tracer_data().hops().len();
         if hop_count == 0 {
             return;
         }
-        let max_index = 0.max(hop_count.saturating_sub(1));
         let i = match self.table_state.selected() {
-            Some(i) => {
-                if i < max_index {
-                    i + 1
-                } else {
-                    i
-                }
-            }
-            None => 0,
+            Some(i) => (i + 1..hop_count)
+                .find(|&j| !self.is_hidden_hop(j))
+                .unwrap_or(i),
+            None => (0..hop_count)
+                .find(|&j| !self.is_hidden_hop(j))
+                .unwrap_or_default(),
         };
         self.table_state.select(Some(i));
         self.selected_hop_address = 0;
     }
 
+    /// Select the previous hop which is not hidden.
     fn previous_hop(&mut self) {
         let hop_count = self.tracer_data().hops().len();
         if hop_count == 0 {
             return;
         }
         let i = match self.table_state.selected() {
-            Some(i) => {
-                if i > 0 {
-                    i - 1
-                } else {
-                    i
-                }
-            }
-            None => 0.max(hop_count.saturating_sub(1)),
+            Some(i) => (0..i).rev().find(|&j| !self.is_hidden_hop(j)).unwrap_or(i),
+            None => (0..hop_count)
+                .rev()
+                .find(|&j| !self.is_hidden_hop(j))
+                .unwrap_or(hop_count - 1),
         };
         self.table_state.select(Some(i));
         self.selected_hop_address = 0;
     }
 
+    /// Is the hop at `index` hidden?
+    ///
+    /// Hops are only hidden if hiding is enabled and they match the hop filter.  The target hop is never hidden.
+    fn is_hidden_hop(&self, index: usize) -> bool {
+        let hop = &self.tracer_data().hops()[index];
+        self.hide_hops
+            && !self.tracer_data().is_target(hop)
+            && is_filtered_hop(hop, &self.tui_config.hop_filter, &self.resolver)
+    }
+
+    /// The rows of the hop table, in which each run of consecutive hidden hops is collapsed into a single row.
+    fn hop_rows(&self) -> Vec<HopRow> {
+        let mut rows = vec![];
+        for index in 0..self.tracer_data().hops().len() {
+            match rows.last_mut() {
+                Some(HopRow::Hidden(_, count)) if self.is_hidden_hop(index) => *count += 1,
+                _ if self.is_hidden_hop(index) => rows.push(HopRow::Hidden(index, 1)),
+                _ => rows.push(HopRow::Hop(index)),
+            }
+        }
+        rows
+    }
+
+    /// Select the next trace, or the next trace to compare with when comparing traces.
     fn next_trace(&mut self) {
-        if self.trace_selected < self.trace_info.len() - 1 {
+        if let Some(compared) = self.trace_compared {
+            self.trace_compared = (compared + 1..self.trace_info.len())
+                .find(|&index| index != self.trace_selected)
+                .or(Some(compared));
+        } else if self.trace_selected < self.trace_info.len() - 1 {
             self.trace_selected += 1;
         }
     }
 
+    /// Select the previous trace, or the previous trace to compare with when comparing traces.
     fn previous_trace(&mut self) {
-        if self.trace_selected > 0 {
+        if let Some(compared) = self.trace_compared {
+            self.trace_compared = (0..compared)
+                .rev()
+                .find(|&index| index != self.trace_selected)
+                .or(Some(compared));
+        } else if self.trace_selected > 0 {
             self.trace_selected -= 1;
         };
     }
@@ -702,11 +1260,87 @@ impl TuiApp {
     fn toggle_chart(&mut self) {
         self.show_chart = !self.show_chart;
         self.show_map = false;
+        self.show_route_changes = false;
+        self.show_dns = false;
+        self.show_diagnostics = false;
     }
 
     fn toggle_map(&mut self) {
         self.show_map = !self.show_map;
         self.show_chart = false;
+        self.show_route_changes = false;
+        self.show_dns = false;
+        self.show_diagnostics = false;
+        self.trace_compared = None;
+    }
+
+    fn toggle_route_changes(&mut self) {
+        self.show_route_changes = !self.show_route_changes;
+        self.show_chart = false;
+        self.show_map = false;
+        self.show_dns = false;
+        self.show_diagnostics = false;
+        self.trace_compared = None;
+    }
+
+    fn toggle_dns(&mut self) {
+        self.show_dns = !self.show_dns;
+        self.show_chart = false;
+        self.show_map = false;
+        self.show_route_changes = false;
+        self.show_diagnostics = false;
+        self.trace_compared = None;
+    }
+
+    fn toggle_diagnostics(&mut self) {
+        self.show_diagnostics = !self.show_diagnostics;
+        self.show_chart = false;
+        self.show_map = false;
+        self.show_route_changes = false;
+        self.show_dns = false;
+        self.trace_compared = None;
+    }
+
+    /// Toggle showing the hop table alongside the configured panes.
+    fn toggle_panes(&mut self) {
+        self.show_panes = !self.show_panes;
+        self.show_chart = false;
+        self.show_map = false;
+        self.show_route_changes = false;
+        self.show_dns = false;
+        self.show_diagnostics = false;
+        self.trace_compared = None;
+    }
+
+    /// Toggle comparing the selected trace side by side with another trace, which is initially the next trace.
+    ///
+    /// Comparing traces is only possible when there is more than one trace.
+    fn toggle_compare(&mut self) {
+        self.trace_compared = match self.trace_compared {
+            None if self.trace_info.len() > 1 => (self.trace_selected + 1..self.trace_info.len())
+                .chain(0..self.trace_selected)
+                .next(),
+            _ => None,
+        };
+        self.show_map = false;
+        self.show_route_changes = false;
+        self.show_dns = false;
+        self.show_diagnostics = false;
+    }
+
+    /// Toggle the overview of all traces, in which the trace selected when the overview is closed is shown.
+    fn toggle_overview(&mut self) {
+        self.show_overview = !self.show_overview;
+        self.trace_compared = None;
+    }
+
+    fn toggle_hide_hops(&mut self) {
+        self.hide_hops = !self.hide_hops;
+        if let Some(selected) = self.table_state.selected() {
+            if self.is_hidden_hop(selected) {
+                self.clear();
+            }
+        }
     }
 
     fn toggle_asinfo(&mut self) {
@@ -766,47 +1400,218 @@ impl TuiApp {
     }
 }
 
+/// Snapshot the trace data of the trace at `index` into `tracer_data` if it has changed since the snapshot `key`.
+///
+/// Returns true if a new snapshot was taken.
+fn snapshot(
+    trace_info: &[TraceInfo],
+    index: usize,
+    tracer_data: &mut Trace,
+    key: &mut Option<(usize, usize)>,
+) -> bool {
+    let data = trace_info[index].data.read();
+    let new_key = (index, data.generation());
+    if *key == Some(new_key) {
+        false
+    } else {
+        *tracer_data = data.clone();
+        *key = Some(new_key);
+        true
+    }
+}
+
 /// Run the frontend TUI.
 pub fn run_frontend(
     traces: Vec<TraceInfo>,
     tui_config: TuiConfig,
     resolver: DnsResolver,
     geoip_lookup: GeoIpLookup,
+    view: Option<TuiView>,
+    problems: ProblemLog,
 ) -> anyhow::Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
+    let backend = AsciiBackend::new(CrosstermBackend::new(stdout), tui_config.ascii);
     let mut terminal = Terminal::new(backend)?;
     let preserve_screen = tui_config.preserve_screen;
-    let res = run_app(&mut terminal, traces, tui_config, resolver, geoip_lookup);
+    let title = tui_config.title;
+    if title {
+        // save the terminal title such that it can be restored on exit, for those terminals which support it.
+        execute!(terminal.backend_mut().inner, Print(SAVE_TITLE))?;
+    }
+    info!(traces = traces.len(), "starting tui");
+    let res = run_app(
+        &mut terminal,
+        traces,
+        tui_config,
+        resolver,
+        geoip_lookup,
+        view,
+        problems,
+    );
     disable_raw_mode()?;
+    if title {
+        execute!(terminal.backend_mut().inner, Print(RESTORE_TITLE))?;
+    }
     if !preserve_screen {
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        execute!(terminal.backend_mut().inner, LeaveAlternateScreen)?;
     }
     terminal.show_cursor()?;
     if let Err(err) = res {
+        error!(%err, "tui failed");
         println!("{err:?}");
     }
+    info!("tui exited");
     Ok(())
 }
 
+/// A `Backend` which replaces the non-ASCII glyphs drawn by the widgets with ASCII characters, if enabled.
+///
+/// Only glyphs which occupy a single column are replaced, the wide glyphs (such as the emoji used for the hop status)
+/// are instead rendered as ASCII by the widgets themselves.
+struct AsciiBackend<B> {
+    inner: B,
+    enabled: bool,
+}
+
+impl<B: Backend> AsciiBackend<B> {
+    const fn new(inner: B, enabled: bool) -> Self {
+        Self { inner, enabled }
+    }
+}
+
+impl<B: Backend> Backend for AsciiBackend<B> {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a tui::buffer::Cell)>,
+    {
+        if self.enabled {
+            let cells = content
+                .map(|(x, y, cell)| {
+                    let mut cell = cell.clone();
+                    if !cell.symbol.is_ascii() {
+                        let symbol = cell.symbol.chars().map(ascii_char).collect::<String>();
+                        cell.set_symbol(&symbol);
+                    }
+                    (x, y, cell)
+                })
+                .collect::<Vec<_>>();
+            self.inner
+                .draw(cells.iter().map(|(x, y, cell)| (*x, *y, cell)))
+        } else {
+            self.inner.draw(content)
+        }
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor(&mut self) -> io::Result<(u16, u16)> {
+        self.inner.get_cursor()
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.inner.set_cursor(x, y)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        self.inner.size()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The ASCII character to draw in place of a character.
+///
+/// Lines are drawn with `-`, `|` and `+`, bars and chart points with `.`, `:`, `#` and `*` and any other non-ASCII
+/// character is drawn as `?`.
+const fn ascii_char(c: char) -> char {
+    match c {
+        c if c.is_ascii() => c,
+        '─' | '━' | '═' | '┄' | '┅' | '┈' | '┉' | '╌' | '╍' | '╴' | '╶' | '╸' | '╺' | '╼' | '╾' => {
+            '-'
+        }
+        '│' | '┃' | '║' | '┆' | '┇' | '┊' | '┋' | '╎' | '╏' | '╵' | '╷' | '╹' | '╻' | '╽' | '╿' => {
+            '|'
+        }
+        '\u{2500}'..='\u{257f}' => '+',
+        '\u{2581}'..='\u{2583}' => '.',
+        '\u{2584}'..='\u{2586}' => ':',
+        '\u{2580}'..='\u{259f}' => '#',
+        '\u{2800}' => ' ',
+        '\u{2801}'..='\u{28ff}' | '•' => '*',
+        _ => '?',
+    }
+}
+
+#[allow(clippy::too_many_lines)]
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     trace_info: Vec<TraceInfo>,
     tui_config: TuiConfig,
     resolver: DnsResolver,
     geoip_lookup: GeoIpLookup,
+    view: Option<TuiView>,
+    problems: ProblemLog,
 ) -> io::Result<()> {
-    let mut app = TuiApp::new(tui_config, resolver, geoip_lookup, trace_info);
+    let deadline = tui_config.max_duration.map(|max| Instant::now() + max);
+    let mut app = TuiApp::new(
+        tui_config,
+        resolver,
+        geoip_lookup,
+        trace_info,
+        view,
+        problems,
+    );
+    let mut dirty = true;
+    let mut last_drawn_secs = 0;
+    let mut last_title = None;
+    let mut last_saved = Instant::now();
     loop {
-        if app.frozen_start.is_none() {
-            app.snapshot_trace_data();
+        if matches!(deadline, Some(deadline) if Instant::now() >= deadline) {
+            debug!("max duration elapsed");
+            app.save_session();
+            return Ok(());
+        }
+        if last_saved.elapsed() >= STATE_SAVE_INTERVAL {
+            app.save_session();
+            last_saved = Instant::now();
+        }
+        if app.frozen_start.is_none() && app.snapshot_trace_data() {
             app.clamp_selected_hop();
+            dirty = true;
         };
-        terminal.draw(|f| render_app(f, &mut app))?;
+        // the header clock and frozen duration are displayed with a resolution of one second.
+        let now_secs = chrono::Local::now().timestamp();
+        if dirty || now_secs != last_drawn_secs {
+            terminal.draw(|f| render_app(f, &mut app))?;
+            dirty = false;
+            last_drawn_secs = now_secs;
+            if app.tui_config.title {
+                let title = render_title(&app);
+                if last_title.as_ref() != Some(&title) {
+                    execute!(io::stdout(), SetTitle(&title))?;
+                    last_title = Some(title);
+                }
+            }
+        }
         if event::poll(app.tui_config.refresh_rate)? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            trace!(?event, "tui event");
+            dirty |= matches!(event, Event::Key(_) | Event::Resize(_, _));
+            if let Event::Key(key) = event {
                 if key.kind == KeyEventKind::Press {
                     let bindings = &app.tui_config.bindings;
                     if app.show_help {
@@ -835,6 +1640,14 @@ fn run_app<B: Backend>(
                         app.toggle_help();
                     } else if bindings.toggle_settings.check(key) {
                         app.toggle_settings();
+                    } else if bindings.toggle_overview.check(key) {
+                        app.toggle_overview();
+                    } else if app.show_overview && bindings.next_hop.check(key) {
+                        app.next_trace();
+                        app.clear();
+                    } else if app.show_overview && bindings.previous_hop.check(key) {
+                        app.previous_trace();
+                        app.clear();
                     } else if bindings.next_hop.check(key) {
                         app.next_hop();
                     } else if bindings.previous_hop.check(key) {
@@ -861,6 +1674,18 @@ fn run_app<B: Backend>(
                         app.toggle_chart();
                     } else if bindings.toggle_map.check(key) {
                         app.toggle_map();
+                    } else if bindings.toggle_route_changes.check(key) {
+                        app.toggle_route_changes();
+                    } else if bindings.toggle_dns.check(key) {
+                        app.toggle_dns();
+                    } else if bindings.toggle_diagnostics.check(key) {
+                        app.toggle_diagnostics();
+                    } else if bindings.toggle_panes.check(key) {
+                        app.toggle_panes();
+                    } else if bindings.toggle_compare.check(key) {
+                        app.toggle_compare();
+                    } else if bindings.toggle_hide_hops.check(key) {
+                        app.toggle_hide_hops();
                     } else if bindings.contract_hosts_min.check(key) {
                         app.contract_hosts_min();
                     } else if bindings.expand_hosts_max.check(key) {
@@ -876,6 +1701,9 @@ fn run_app<B: Backend>(
                     } else if bindings.clear_trace_data.check(key) {
                         app.clear();
                         app.clear_trace_data();
+                    } else if bindings.clear_all_trace_data.check(key) {
+                        app.clear();
+                        app.clear_all_trace_data();
                     } else if bindings.clear_dns_cache.check(key) {
                         app.resolver.flush();
                     } else if bindings.clear_selection.check(key) {
@@ -884,7 +1712,10 @@ fn run_app<B: Backend>(
                         app.toggle_asinfo();
                     } else if bindings.toggle_hop_details.check(key) {
                         app.toggle_hop_details();
+                    } else if bindings.export_report.check(key) {
+                        app.export_report();
                     } else if bindings.quit.check(key) || CTRL_C.check(key) {
+                        app.save_session();
                         return Ok(());
                     }
                 }
@@ -914,7 +1745,7 @@ fn run_app<B: Backend>(
 /// |                 |                  |
 ///  ------------------------------------
 ///
-/// Header - the title, configuration, destination, clock and keyboard controls
+/// Header - the title, configuration, destination, summary, clock and keyboard controls
 /// Tab - a tab for each target being traced (only shown if > 1 target requested)
 /// Hops - a table where each row represents a single hop (time-to-live) in the trace
 /// History - a graph of historic round-trip ping samples for the target host
@@ -932,13 +1763,17 @@ fn render_app<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp) {
         .constraints(constraints.as_ref())
         .split(f.size());
     render_header(f, app, chunks[0]);
-    if app.trace_info.len() > 1 {
+    let (body, footer) = if app.trace_info.len() > 1 {
         render_tabs(f, app, chunks[1]);
-        render_body(f, chunks[2], app);
-        render_footer(f, chunks[3], app);
+        (chunks[2], chunks[3])
+    } else {
+        (chunks[1], chunks[2])
+    };
+    if app.tui_config.plain {
+        render_body(f, body.union(footer), app);
     } else {
-        render_body(f, chunks[1], app);
-        render_footer(f, chunks[2], app);
+        render_body(f, body, app);
+        render_footer(f, footer, app);
     }
     if app.show_settings {
         render_settings(f, app);
@@ -947,7 +1782,7 @@ fn render_app<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp) {
     }
 }
 
-/// Render the title, config, target, clock and keyboard controls.
+/// Render the title, config, target, summary, clock and keyboard controls.
 fn render_header<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect) {
     let header_block = Block::default()
         .title(format!(" Trippy v{} ", clap::crate_version!()))
@@ -975,6 +1810,93 @@ fn render_header<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
         .style(Style::default())
         .block(header_block.clone())
         .alignment(Alignment::Right);
+    let source = render_source(app);
+    let dest = render_destination(app);
+    let locale = app.tui_config.locale;
+    let target = match render_egress(&app.tracer_config().egress) {
+        Some(egress) => format!("{source} [{egress}] -> {dest}"),
+        None => format!("{source} -> {dest}"),
+    };
+    let left_spans = vec![
+        Spans::from(vec![
+            Span::styled(
+                format!("{}: ", translate(locale, "Target")),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(target),
+        ]),
+        Spans::from(vec![
+            Span::styled(
+                format!("{}: ", translate(locale, "Config")),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(render_config(app)),
+        ]),
+        Spans::from(vec![
+            Span::styled(
+                format!("{}: ", translate(locale, "Status")),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(render_status(app)),
+            Span::raw(render_blocking_hop(app)),
+            Span::raw(render_fragmentation_hop(app)),
+            Span::raw(format!(
+                ", {}",
+                translate_with(
+                    locale,
+                    "discovered { $count } hops",
+                    app.tracer_data().hops().len()
+                )
+            )),
+            Span::raw(match &app.export_message {
+                Some((exported, message)) if exported.elapsed() < EXPORT_MESSAGE_DURATION => {
+                    format!(", {message}")
+                }
+                _ => String::new(),
+            }),
+            Span::raw(render_problems_status(app)),
+        ]),
+        Spans::from(vec![
+            Span::styled(
+                format!("{}: ", translate(locale, "Summary")),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(render_summary(
+                app.tracer_data().summary(),
+                app.tui_config.rtt_format,
+            )),
+            Span::raw(render_probe_rate(app)),
+        ]),
+    ];
+
+    let left = Paragraph::new(left_spans)
+        .style(Style::default())
+        .block(header_block)
+        .alignment(Alignment::Left);
+    f.render_widget(right, rect);
+    f.render_widget(left, rect);
+}
+
+/// Render the terminal title, which summarizes the target, loss and average round trip time of the selected trace.
+fn render_title(app: &TuiApp) -> String {
+    let hostname = &app.tracer_config().target_hostname;
+    let data = app.tracer_data();
+    let target = data.target_hop();
+    if data.error().is_some() {
+        format!("{hostname} failed - trip")
+    } else if target.total_sent() == 0 {
+        format!("{hostname} - trip")
+    } else {
+        format!(
+            "{hostname} {:.1}% {} - trip",
+            target.loss_pct(),
+            app.tui_config.rtt_format.format_with_unit(target.avg_ms())
+        )
+    }
+}
+
+/// Render the configuration of the trace.
+fn render_config(app: &TuiApp) -> String {
     let protocol = match app.tracer_config().protocol {
         TracerProtocol::Icmp => format!("icmp({})", app.tracer_config().addr_family),
         TracerProtocol::Udp => format!(
@@ -1003,36 +1925,90 @@ fn render_header<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
         .tui_config
         .max_addrs
         .map_or_else(|| String::from("auto"), |m| m.to_string());
-    let source = render_source(app);
-    let dest = render_destination(app);
-    let target = format!("{source} -> {dest}");
-    let left_spans = vec![
-        Spans::from(vec![
-            Span::styled("Target: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::raw(target),
-        ]),
-        Spans::from(vec![
-            Span::styled("Config: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::raw(format!(
-                "protocol={protocol} as-info={as_info} details={details} max-hosts={max_hosts}"
-            )),
-        ]),
-        Spans::from(vec![
-            Span::styled("Status: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::raw(render_status(app)),
-            Span::raw(format!(
-                ", discovered {} hops",
-                app.tracer_data().hops().len()
-            )),
-        ]),
-    ];
+    format!("protocol={protocol} as-info={as_info} details={details} max-hosts={max_hosts}")
+}
 
-    let left = Paragraph::new(left_spans)
-        .style(Style::default())
-        .block(header_block)
-        .alignment(Alignment::Left);
-    f.render_widget(right, rect);
-    f.render_widget(left, rect);
+/// Render the summary of the trace as a whole.
+fn render_summary(summary: TraceSummary, rtt_format: RttFormat) -> String {
+    let round = summary.round_duration.map_or_else(
+        || String::from("n/a"),
+        |duration| rtt_format.format_with_unit(duration.as_secs_f64() * 1000_f64),
+    );
+    format!(
+        "rounds={} sent={} recv={} loss={:.1}% last-round={round} path={} hops",
+        summary.total_rounds,
+        summary.total_sent,
+        summary.total_recv,
+        summary.loss_pct,
+        summary.path_len
+    )
+}
+
+/// Format the time of the last route, AS path or target address change of a trace, if any.
+fn format_last_change(data: &Trace) -> String {
+    data.route_changes()
+        .last()
+        .map(|change| change.timestamp)
+        .max(data.as_path_changes().last().map(|change| change.timestamp))
+        .max(data.target_changes().last().map(|change| change.timestamp))
+        .map_or_else(
+            || String::from("-"),
+            |timestamp| {
+                DateTime::<Local>::from(timestamp)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            },
+        )
+}
+
+/// Render the rate at which probes are sent for the selected trace and, if there are several traces, for all traces.
+fn render_probe_rate(app: &TuiApp) -> String {
+    let Some(rate) = app.tracer_data().probe_rate() else {
+        return String::new();
+    };
+    let selected = format_probe_rate(rate, app.tracer_config().probe_size());
+    if app.trace_info.len() > 1 {
+        let (total_rate, total_bytes) = total_probe_rate(&app.trace_info);
+        format!(
+            " rate={selected} total={total_rate:.1}/s ({})",
+            format_bytes_rate(total_bytes)
+        )
+    } else {
+        format!(" rate={selected}")
+    }
+}
+
+/// The total rate at which probes, and bytes, are sent for all `traces`.
+fn total_probe_rate(traces: &[TraceInfo]) -> (f64, f64) {
+    traces
+        .iter()
+        .filter_map(|trace| {
+            let rate = trace.data.read().probe_rate()?;
+            Some((rate, rate * f64::from(trace.probe_size())))
+        })
+        .fold(
+            (0_f64, 0_f64),
+            |(rate, bytes), (trace_rate, trace_bytes)| (rate + trace_rate, bytes + trace_bytes),
+        )
+}
+
+/// Format the rate at which probes of `probe_size` bytes are sent, such as `12.0/s (1.0 KB/s)`.
+fn format_probe_rate(rate: f64, probe_size: u16) -> String {
+    format!(
+        "{rate:.1}/s ({})",
+        format_bytes_rate(rate * f64::from(probe_size))
+    )
+}
+
+/// Format a rate in bytes per second, such as `1.0 KB/s`.
+fn format_bytes_rate(bytes: f64) -> String {
+    if bytes < 1000_f64 {
+        format!("{bytes:.0} B/s")
+    } else if bytes < 1_000_000_f64 {
+        format!("{:.1} KB/s", bytes / 1000_f64)
+    } else {
+        format!("{:.1} MB/s", bytes / 1_000_000_f64)
+    }
 }
 
 /// Render the source address of the trace.
@@ -1052,10 +2028,25 @@ fn render_source(app: &mut TuiApp) -> String {
     }
 }
 
+/// Render the egress interface and first-hop gateway, if known.
+fn render_egress(egress: &Egress) -> Option<String> {
+    match (&egress.interface, egress.gateway) {
+        (Some(interface), Some(gateway)) => Some(format!("{interface} via {gateway}")),
+        (Some(interface), None) => Some(interface.clone()),
+        (None, Some(gateway)) => Some(format!("via {gateway}")),
+        (None, None) => None,
+    }
+}
+
 /// Render the destination address.
+///
+/// The address of the target is the address it was last re-resolved to and followed, if any.
 fn render_destination(app: &mut TuiApp) -> String {
     let dest_hostname = &app.tracer_config().target_hostname;
-    let dest_addr = app.tracer_config().target_addr;
+    let dest_addr = app
+        .tracer_data()
+        .target_addr()
+        .unwrap_or_else(|| app.tracer_config().target_addr);
     match app.tracer_config().port_direction {
         PortDirection::None => {
             format!("{dest_hostname} ({dest_addr})")
@@ -1071,20 +2062,69 @@ fn render_destination(app: &mut TuiApp) -> String {
 
 /// Render the headline status of the tracing.
 fn render_status(app: &TuiApp) -> String {
+    let locale = app.tui_config.locale;
     if app.selected_tracer_data.error().is_some() {
-        String::from("Failed")
+        String::from(translate(locale, "Failed"))
     } else if let Some(start) = app.frozen_start {
         format!(
-            "Frozen ({})",
+            "{} ({})",
+            translate(locale, "Frozen"),
             format_duration(Duration::from_secs(
                 start.elapsed().unwrap_or_default().as_secs()
             ))
         )
+    } else if app.selected_tracer_data.is_paused() {
+        String::from(translate(locale, "Paused"))
+    } else if let Some(backoff) = app.selected_tracer_data.backoff() {
+        format!(
+            "{} ({})",
+            translate(locale, "Backing off"),
+            format_duration(backoff)
+        )
     } else {
-        String::from("Running")
+        String::from(translate(locale, "Running"))
     }
 }
 
+/// Render the hop which blocks the probes as communication with the target is administratively prohibited, if any.
+fn render_blocking_hop(app: &TuiApp) -> String {
+    app.tracer_data()
+        .blocking_hop()
+        .map(|hop| {
+            format!(
+                ", {}",
+                translate_with(
+                    app.tui_config.locale,
+                    "blocked at hop { $hop }",
+                    usize::from(hop.ttl())
+                )
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Render the first hop which responded that a probe must be fragmented to be forwarded, if any.
+fn render_fragmentation_hop(app: &TuiApp) -> String {
+    app.tracer_data()
+        .fragmentation_hop()
+        .map(|hop| {
+            let mtu = hop
+                .icmp_response()
+                .and_then(IcmpResponse::next_hop_mtu)
+                .map(|mtu| format!(" (mtu {mtu})"))
+                .unwrap_or_default();
+            format!(
+                ", {}{mtu}",
+                translate_with(
+                    app.tui_config.locale,
+                    "fragmentation needed at hop { $hop }",
+                    usize::from(hop.ttl())
+                )
+            )
+        })
+        .unwrap_or_default()
+}
+
 /// Format the `DnsResolveMethod`.
 fn format_dns_method(resolve_method: DnsResolveMethod) -> String {
     match resolve_method {
@@ -1126,10 +2166,43 @@ fn format_geoip_mode(geoip_mode: GeoIpMode) -> String {
     }
 }
 
+/// Format the palette of the Tui.
+fn format_palette(palette: TuiPalette) -> String {
+    match palette {
+        TuiPalette::Default => "default".to_string(),
+        TuiPalette::Deuteranopia => "deuteranopia".to_string(),
+        TuiPalette::Protanopia => "protanopia".to_string(),
+        TuiPalette::Tritanopia => "tritanopia".to_string(),
+    }
+}
+
+/// Format the `TuiExportFormat`.
+fn format_export_format(export_format: TuiExportFormat) -> String {
+    match export_format {
+        TuiExportFormat::Json => "json".to_string(),
+        TuiExportFormat::Markdown => "markdown".to_string(),
+        TuiExportFormat::Csv => "csv".to_string(),
+    }
+}
+
+/// Format the panes of the multi-pane layout.
+fn format_panes(panes: &[TuiPane]) -> String {
+    panes
+        .iter()
+        .map(|pane| match pane {
+            TuiPane::Chart => "chart",
+            TuiPane::RouteChanges => "route-changes",
+            TuiPane::Map => "map",
+            TuiPane::Dns => "dns",
+            TuiPane::Diagnostics => "diagnostics",
+        })
+        .join(",")
+}
+
 /// Render the tabs, one per trace.
 fn render_tabs<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect) {
     let tabs_block = Block::default()
-        .title("Traces")
+        .title(translate(app.tui_config.locale, "Traces"))
         .title_alignment(Alignment::Left)
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
@@ -1139,12 +2212,19 @@ fn render_tabs<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect) {
                 .bg(app.tui_config.theme.bg_color)
                 .fg(app.tui_config.theme.text_color),
         );
+    let mixed_families = has_mixed_families(&app.trace_info);
     let titles: Vec<_> = app
         .trace_info
         .iter()
-        .map(|trace| {
+        .enumerate()
+        .map(|(index, trace)| {
+            let prefix = if app.trace_compared == Some(index) {
+                "vs "
+            } else {
+                ""
+            };
             Spans::from(Span::styled(
-                &trace.target_hostname,
+                format!("{prefix}{}", format_trace_name(trace, mixed_families)),
                 Style::default().fg(app.tui_config.theme.tab_text_color),
             ))
         })
@@ -1157,20 +2237,500 @@ fn render_tabs<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect) {
     f.render_widget(tabs, rect);
 }
 
-/// Render the body.
-///
-/// This is either an BSOD if there wa san error or the table of hop data or, if there is no data, the splash screen.
-fn render_body<B: Backend>(f: &mut Frame<'_, B>, rec: Rect, app: &mut TuiApp) {
-    if let Some(err) = app.selected_tracer_data.error() {
-        render_bsod(f, rec, err);
-    } else if app.tracer_data().hops().is_empty() {
-        render_splash(f, app, rec);
-    } else if app.show_chart {
-        render_chart(f, app, rec);
-    } else if app.show_map {
-        render_map(f, app, rec);
-    } else {
-        render_table(f, app, rec);
+/// Are the traces of both address families?
+///
+/// The address family is only shown to distinguish the traces of a target when tracing with both.
+fn has_mixed_families(trace_info: &[TraceInfo]) -> bool {
+    trace_info
+        .iter()
+        .map(|trace| trace.target_addr.is_ipv4())
+        .unique()
+        .count()
+        > 1
+}
+
+/// Format the name of a trace, from the target hostname and the protocol used.
+fn format_trace_name(trace: &TraceInfo, mixed_families: bool) -> String {
+    let protocol = if mixed_families {
+        format!("{}, {}", trace.protocol, trace.addr_family)
+    } else {
+        trace.protocol.to_string()
+    };
+    let protocol = match trace.ipv6_ext_header {
+        Some(header) => format!("{protocol}, {header}"),
+        None => protocol,
+    };
+    format!("{} ({protocol})", trace.target_hostname)
+}
+
+/// Render the overview of all traces, one line per trace under a line per group of traces.
+///
+/// The traces of a group are consecutive, as the targets of each group are traced after the targets which do not
+/// belong to any group.  The selected trace is highlighted and is shown in full when the overview is closed.
+fn render_overview<B: Backend>(f: &mut Frame<'_, B>, app: &TuiApp, rect: Rect) {
+    let theme = app.tui_config.theme;
+    let locale = app.tui_config.locale;
+    let header = Row::new(OVERVIEW_TABLE_HEADER.iter().map(|h| {
+        Cell::from(translate(locale, h))
+            .style(Style::default().fg(theme.hops_table_header_text_color))
+    }))
+    .style(Style::default().bg(theme.hops_table_header_bg_color));
+    let mixed_families = has_mixed_families(&app.trace_info);
+    let mut rows = vec![];
+    let mut selected_row = None;
+    for (group, traces) in &app
+        .trace_info
+        .iter()
+        .enumerate()
+        .group_by(|(_, trace)| trace.group.clone())
+    {
+        let traces = traces.collect::<Vec<_>>();
+        let indent = if let Some(group) = group {
+            let reachable = traces
+                .iter()
+                .filter(|(_, trace)| {
+                    overview_status(&trace.data.read()) == OverviewStatus::Reachable
+                })
+                .count();
+            rows.push(
+                Row::new(vec![
+                    Cell::from(group),
+                    Cell::from(format!(
+                        "{reachable}/{} {}",
+                        traces.len(),
+                        OverviewStatus::Reachable
+                    )),
+                ])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+            );
+            "  "
+        } else {
+            ""
+        };
+        for (index, trace) in traces {
+            if index == app.trace_selected {
+                selected_row = Some(rows.len());
+            }
+            let data = trace.data.read();
+            let status = overview_status(&data);
+            let summary = data.summary();
+            let last_change = format_last_change(&data);
+            let rate = data.probe_rate().map_or_else(
+                || String::from("-"),
+                |rate| format_probe_rate(rate, trace.probe_size()),
+            );
+            let (loss, path) = if status == OverviewStatus::Starting {
+                (String::from("-"), String::from("-"))
+            } else {
+                (
+                    format!("{:.1}%", summary.loss_pct),
+                    summary.path_len.to_string(),
+                )
+            };
+            rows.push(Row::new(vec![
+                Cell::from(format!(
+                    "{indent}{}",
+                    format_trace_name(trace, mixed_families)
+                )),
+                Cell::from(status.to_string()),
+                Cell::from(loss),
+                Cell::from(path),
+                Cell::from(rate),
+                Cell::from(last_change),
+            ]));
+        }
+    }
+    let mut table_state = TableState::default();
+    table_state.select(selected_row);
+    let (total_rate, total_bytes) = total_probe_rate(&app.trace_info);
+    let title = format!(
+        "{} [total {total_rate:.1}/s ({})]",
+        translate(locale, "Overview"),
+        format_bytes_rate(total_bytes)
+    );
+    let table = Table::new(rows)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_color))
+                .title(title),
+        )
+        .style(Style::default().bg(theme.bg_color).fg(theme.text_color))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&OVERVIEW_TABLE_WIDTH);
+    f.render_stateful_widget(table, rect, &mut table_state);
+}
+
+/// The status of a trace in the overview.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum OverviewStatus {
+    /// The first round has not yet completed.
+    Starting,
+    /// The target responded in the latest round.
+    Reachable,
+    /// The target did not respond in the latest round.
+    Unreachable,
+    /// Tracing failed.
+    Failed,
+}
+
+impl Display for OverviewStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Starting => write!(f, "starting"),
+            Self::Reachable => write!(f, "reachable"),
+            Self::Unreachable => write!(f, "unreachable"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// The status of a trace in the overview.
+fn overview_status(trace: &Trace) -> OverviewStatus {
+    if trace.error().is_some() {
+        OverviewStatus::Failed
+    } else if trace.round().is_none() {
+        OverviewStatus::Starting
+    } else if trace.unreachable_rounds() > 0 {
+        OverviewStatus::Unreachable
+    } else {
+        OverviewStatus::Reachable
+    }
+}
+
+/// Render the body.
+///
+/// This is either an BSOD if there wa san error or the table of hop data or, if there is no data, the splash screen.
+fn render_body<B: Backend>(f: &mut Frame<'_, B>, rec: Rect, app: &mut TuiApp) {
+    if app.show_overview {
+        render_overview(f, app, rec);
+    } else if let Some(err) = app.selected_tracer_data.error() {
+        render_bsod(f, rec, err);
+    } else if app.tracer_data().hops().is_empty() {
+        render_splash(f, app, rec);
+    } else if app.trace_compared.is_some() {
+        render_compare(f, app, rec);
+    } else if app.show_chart {
+        render_chart(f, app, rec);
+    } else if app.show_map {
+        render_map(f, app, rec);
+    } else if app.show_route_changes {
+        render_route_changes(f, app, rec);
+    } else if app.show_dns {
+        render_dns(f, app, rec);
+    } else if app.show_diagnostics {
+        render_diagnostics(f, app, rec);
+    } else if app.show_panes {
+        render_panes(f, app, rec);
+    } else {
+        render_table(f, app, rec);
+    }
+}
+
+/// Render the hop table alongside the configured panes.
+///
+/// The panes are stacked beside the hop table if the body is wide enough, otherwise they are placed side by side
+/// below it if the body is tall enough, otherwise only the hop table is shown.  In plain mode the panes are always
+/// stacked below the hop table.
+fn render_panes<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect) {
+    let (direction, panes_direction) = if app.tui_config.plain {
+        (Direction::Vertical, Direction::Vertical)
+    } else if rect.width >= PANES_MIN_WIDTH_BESIDE {
+        (Direction::Horizontal, Direction::Vertical)
+    } else if rect.height >= PANES_MIN_HEIGHT_BELOW {
+        (Direction::Vertical, Direction::Horizontal)
+    } else {
+        render_table(f, app, rect);
+        return;
+    };
+    let split = app.tui_config.pane_split;
+    let chunks = Layout::default()
+        .direction(direction)
+        .constraints(
+            [
+                Constraint::Percentage(split),
+                Constraint::Percentage(100 - split),
+            ]
+            .as_ref(),
+        )
+        .split(rect);
+    render_table(f, app, chunks[0]);
+    let panes = app.tui_config.panes.clone();
+    let count = u32::try_from(panes.len()).unwrap_or(u32::MAX);
+    let pane_chunks = Layout::default()
+        .direction(panes_direction)
+        .constraints(vec![Constraint::Ratio(1, count); panes.len()])
+        .split(chunks[1]);
+    for (pane, pane_rect) in panes.into_iter().zip(pane_chunks) {
+        match pane {
+            TuiPane::Chart => render_chart(f, app, pane_rect),
+            TuiPane::RouteChanges => render_route_changes(f, app, pane_rect),
+            TuiPane::Map => render_map(f, app, pane_rect),
+            TuiPane::Dns => render_dns(f, app, pane_rect),
+            TuiPane::Diagnostics => render_diagnostics(f, app, pane_rect),
+        }
+    }
+}
+
+/// Render the route changes observed during the trace, most recent first.
+fn render_route_changes<B: Backend>(f: &mut Frame<'_, B>, app: &TuiApp, rect: Rect) {
+    let theme = app.tui_config.theme;
+    let header = Row::new(ROUTE_CHANGES_TABLE_HEADER.iter().map(|h| {
+        Cell::from(translate(app.tui_config.locale, h))
+            .style(Style::default().fg(theme.hops_table_header_text_color))
+    }))
+    .style(Style::default().bg(theme.hops_table_header_bg_color));
+    let route_changes = app.tracer_data().route_changes();
+    let as_path_changes = app.tracer_data().as_path_changes();
+    let target_changes = app.tracer_data().target_changes();
+    let format_time = |timestamp| {
+        DateTime::<Local>::from(timestamp)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    };
+    let mut rows = route_changes
+        .iter()
+        .map(|change| {
+            let row = Row::new([
+                Cell::from(format_time(change.timestamp)),
+                Cell::from(format!("{}", change.round + 1)),
+                Cell::from(format!("{}", change.ttl)),
+                Cell::from(format_route_change_addr(
+                    change.from,
+                    &app.resolver,
+                    &app.tui_config,
+                )),
+                Cell::from(format_route_change_addr(
+                    change.to,
+                    &app.resolver,
+                    &app.tui_config,
+                )),
+            ]);
+            (change.timestamp, row)
+        })
+        .chain(as_path_changes.iter().map(|change| {
+            let row = Row::new([
+                Cell::from(format_time(change.timestamp)),
+                Cell::from(format!("{}", change.round + 1)),
+                Cell::from("AS"),
+                Cell::from(format_as_path(&change.from)),
+                Cell::from(format_as_path(&change.to)),
+            ]);
+            (change.timestamp, row)
+        }))
+        .chain(target_changes.iter().map(|change| {
+            let to = format_route_change_addr(change.to, &app.resolver, &app.tui_config);
+            let row = Row::new([
+                Cell::from(format_time(change.timestamp)),
+                Cell::from(format!("{}", change.round + 1)),
+                Cell::from("DNS"),
+                Cell::from(format_route_change_addr(
+                    change.from,
+                    &app.resolver,
+                    &app.tui_config,
+                )),
+                Cell::from(if change.followed {
+                    to
+                } else {
+                    format!("{to} [kept]")
+                }),
+            ]);
+            (change.timestamp, row)
+        }))
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|(timestamp, _)| std::cmp::Reverse(*timestamp));
+    let rows = rows.into_iter().map(|(_, row)| row);
+    let route_changes_title = translate(app.tui_config.locale, "Route Changes");
+    let mut counts = vec![route_changes.len().to_string()];
+    if !as_path_changes.is_empty() {
+        counts.push(format!("AS path {}", as_path_changes.len()));
+    }
+    if !target_changes.is_empty() {
+        counts.push(format!("target {}", target_changes.len()));
+    }
+    let title = format!("{route_changes_title} ({})", counts.join(", "));
+    let table = Table::new(rows)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_color))
+                .title(title),
+        )
+        .style(Style::default().bg(theme.bg_color).fg(theme.text_color))
+        .widths(&ROUTE_CHANGES_TABLE_WIDTH);
+    f.render_widget(table, rect);
+}
+
+/// Render the reverse DNS lookup of each address of each hop and the time taken.
+///
+/// The time taken to resolve the target is shown in the title.
+fn render_dns<B: Backend>(f: &mut Frame<'_, B>, app: &TuiApp, rect: Rect) {
+    let theme = app.tui_config.theme;
+    let header = Row::new(DNS_TABLE_HEADER.iter().map(|h| {
+        Cell::from(translate(app.tui_config.locale, h))
+            .style(Style::default().fg(theme.hops_table_header_text_color))
+    }))
+    .style(Style::default().bg(theme.hops_table_header_bg_color));
+    let rows = app.tracer_data().hops().iter().flat_map(|hop| {
+        hop.addrs().map(|addr| {
+            let duration = app
+                .resolver
+                .reverse_lookup_duration(*addr)
+                .map_or_else(|| String::from("pending"), format_lookup_duration);
+            Row::new([
+                Cell::from(format!("{}", hop.ttl())),
+                Cell::from(addr.to_string()),
+                Cell::from(app.resolver.reverse_lookup(*addr).to_string()),
+                Cell::from(duration),
+            ])
+        })
+    });
+    let table = Table::new(rows)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_color))
+                .title(format!(
+                    "DNS (target lookup {})",
+                    format_lookup_duration(app.tracer_config().target_lookup_duration)
+                )),
+        )
+        .style(Style::default().bg(theme.bg_color).fg(theme.text_color))
+        .widths(&DNS_TABLE_WIDTH);
+    f.render_widget(table, rect);
+}
+
+/// Render the number of runtime problems and the most recent problem for the header.
+///
+/// Nothing is rendered if no problems have occurred, the full list of problems is shown with the diagnostics.
+fn render_problems_status(app: &TuiApp) -> String {
+    match app.problems.latest() {
+        Some(latest) => {
+            let message = if latest.message.chars().count() > MAX_PROBLEM_MESSAGE_LEN {
+                let truncated: String = latest
+                    .message
+                    .chars()
+                    .take(MAX_PROBLEM_MESSAGE_LEN)
+                    .collect();
+                format!("{truncated}...")
+            } else {
+                latest.message
+            };
+            format!(
+                ", {} ({message})",
+                translate_with(
+                    app.tui_config.locale,
+                    "{ $count } problems",
+                    app.problems.total()
+                )
+            )
+        }
+        None => String::new(),
+    }
+}
+
+/// Render the diagnostics of the trace above the problems which occurred at runtime.
+fn render_diagnostics<B: Backend>(f: &mut Frame<'_, B>, app: &TuiApp, rect: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(DIAGNOSTICS_TABLE_HEIGHT),
+            Constraint::Min(0),
+        ])
+        .split(rect);
+    render_checksum_stats(f, app, chunks[0]);
+    render_problems(f, app, chunks[1]);
+}
+
+/// Render the problems which occurred at runtime, most recent first.
+///
+/// These are the warnings and errors, such as failures to send probes or to resolve hostnames and responses which
+/// were dropped as they could not be parsed, which are otherwise only written to the log file, if any.
+fn render_problems<B: Backend>(f: &mut Frame<'_, B>, app: &TuiApp, rect: Rect) {
+    let theme = app.tui_config.theme;
+    let header = Row::new(PROBLEMS_TABLE_HEADER.iter().map(|h| {
+        Cell::from(translate(app.tui_config.locale, h))
+            .style(Style::default().fg(theme.hops_table_header_text_color))
+    }))
+    .style(Style::default().bg(theme.hops_table_header_bg_color));
+    let rows = app.problems.problems().into_iter().map(|problem| {
+        Row::new([
+            Cell::from(problem.time.format("%H:%M:%S").to_string()),
+            Cell::from(problem.level.to_string()),
+            Cell::from(problem.message),
+        ])
+    });
+    let table = Table::new(rows)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_color))
+                .title(translate(app.tui_config.locale, "Problems")),
+        )
+        .style(Style::default().bg(theme.bg_color).fg(theme.text_color))
+        .widths(&PROBLEMS_TABLE_WIDTH);
+    f.render_widget(table, rect);
+}
+
+/// Render the checksum validation statistics of the responses received for the trace.
+///
+/// The number of responses which quoted a corrupted copy of the probe, across all hops, is also shown.
+fn render_checksum_stats<B: Backend>(f: &mut Frame<'_, B>, app: &TuiApp, rect: Rect) {
+    let theme = app.tui_config.theme;
+    let header = Row::new(DIAGNOSTICS_TABLE_HEADER.iter().map(|h| {
+        Cell::from(translate(app.tui_config.locale, h))
+            .style(Style::default().fg(theme.hops_table_header_text_color))
+    }))
+    .style(Style::default().bg(theme.hops_table_header_bg_color));
+    let stats = app.tracer_data().checksum_stats();
+    let corrupt: usize = app
+        .tracer_data()
+        .hops()
+        .iter()
+        .map(Hop::total_corrupt)
+        .sum();
+    let rows = [
+        ("Responses validated", stats.validated),
+        ("Invalid ICMP checksum", stats.invalid_icmp),
+        ("Invalid quoted ICMP checksum", stats.invalid_quoted_icmp),
+        ("Invalid quoted UDP checksum", stats.invalid_quoted_udp),
+        ("Corrupted quoted probes", corrupt),
+    ]
+    .into_iter()
+    .map(|(name, count)| Row::new([Cell::from(name), Cell::from(format!("{count}"))]));
+    let table = Table::new(rows)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_color))
+                .title(translate(app.tui_config.locale, "Diagnostics")),
+        )
+        .style(Style::default().bg(theme.bg_color).fg(theme.text_color))
+        .widths(&DIAGNOSTICS_TABLE_WIDTH);
+    f.render_widget(table, rect);
+}
+
+/// Format the duration of a DNS lookup.
+fn format_lookup_duration(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000_f64)
+}
+
+/// Format an address of a route change based on the `AddressMode`.
+fn format_route_change_addr(addr: IpAddr, dns: &DnsResolver, config: &TuiConfig) -> String {
+    match config.address_mode {
+        AddressMode::IP => addr.to_string(),
+        AddressMode::Host => dns.reverse_lookup(addr).to_string(),
+        AddressMode::Both => format!("{} ({addr})", dns.reverse_lookup(addr)),
     }
 }
 
@@ -1233,7 +2793,7 @@ fn render_map_canvas<B: Backend>(
         .background_color(app.tui_config.theme.bg_color)
         .block(
             Block::default()
-                .title("Map")
+                .title(translate(app.tui_config.locale, "Map"))
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(app.tui_config.theme.border_color))
                 .style(
@@ -1246,7 +2806,7 @@ fn render_map_canvas<B: Backend>(
             render_map_canvas_world(ctx, theme.map_world_color);
             ctx.layer();
             for entry in entries {
-                render_map_canvas_pin(ctx, entry);
+                render_map_canvas_pin(ctx, entry, app.tui_config.ascii);
                 render_map_canvas_radius(ctx, entry, theme.map_radius_color);
                 render_map_canvas_selected(
                     ctx,
@@ -1272,13 +2832,14 @@ fn render_map_canvas_world(ctx: &mut Context<'_>, color: Color) {
 }
 
 /// Render the map canvas pin.
-fn render_map_canvas_pin(ctx: &mut Context<'_>, entry: &MapEntry) {
+fn render_map_canvas_pin(ctx: &mut Context<'_>, entry: &MapEntry, ascii: bool) {
     let MapEntry {
         latitude,
         longitude,
         ..
     } = entry;
-    ctx.print(*longitude, *latitude, Span::styled("📍", Style::default()));
+    let pin = if ascii { "X" } else { "📍" };
+    ctx.print(*longitude, *latitude, Span::styled(pin, Style::default()));
 }
 
 /// Render the map canvas accuracy radius circle.
@@ -1353,7 +2914,11 @@ fn render_map_info_panel<B: Backend>(
     let info_panel = Paragraph::new(info)
         .block(
             Block::default()
-                .title(format!("Hop {}", selected_hop.ttl()))
+                .title(format!(
+                    "{} {}",
+                    translate(app.tui_config.locale, "Hop"),
+                    selected_hop.ttl()
+                ))
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .border_style(Style::default().fg(theme.map_info_panel_border_color))
@@ -1405,13 +2970,24 @@ fn build_map_entries(app: &mut TuiApp) -> Vec<MapEntry> {
 
 /// Render the ping history for all hops as a chart.
 fn render_chart<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect) {
-    let selected_hop = app.table_state.selected().map_or_else(
-        || app.tracer_data().target_hop(),
-        |s| &app.tracer_data().hops()[s],
-    );
+    let selected_ttl = app.selected_hop_or_target().ttl();
     let samples = app.tui_config.max_samples / app.zoom_factor;
-    let series_data = app
-        .selected_tracer_data
+    let series_data = chart_series(app.tracer_data(), samples);
+    let max_sample = max_chart_sample(&series_data);
+    render_trace_chart(
+        f,
+        app,
+        &series_data,
+        max_sample,
+        selected_ttl,
+        String::from("Chart"),
+        rect,
+    );
+}
+
+/// The round trip time samples, in milliseconds, of each hop of a `Trace`.
+fn chart_series(trace: &Trace, samples: usize) -> Vec<Vec<(f64, f64)>> {
+    trace
         .hops()
         .iter()
         .map(|hop| {
@@ -1422,16 +2998,35 @@ fn render_chart<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
                 .map(|(i, s)| (i as f64, (s.as_secs_f64() * 1000_f64)))
                 .collect::<Vec<_>>()
         })
-        .collect::<Vec<_>>();
-    let max_sample = series_data
+        .collect::<Vec<_>>()
+}
+
+fn max_chart_sample(series_data: &[Vec<(f64, f64)>]) -> f64 {
+    series_data
         .iter()
         .flatten()
         .map(|&(_, s)| s)
         .max_by_key(|&c| c as u64)
-        .unwrap_or_default();
+        .unwrap_or_default()
+}
+
+/// Render a chart of the samples of each hop, with the hop with the `selected_ttl` highlighted.
+///
+/// In plain mode, where the hops cannot be distinguished by color, only the hop with the `selected_ttl` is shown.
+fn render_trace_chart<B: Backend>(
+    f: &mut Frame<'_, B>,
+    app: &TuiApp,
+    series_data: &[Vec<(f64, f64)>],
+    max_sample: f64,
+    selected_ttl: u8,
+    title: String,
+    rect: Rect,
+) {
+    let samples = app.tui_config.max_samples / app.zoom_factor;
     let sets = series_data
         .iter()
         .enumerate()
+        .filter(|(i, _)| !app.tui_config.plain || i + 1 == selected_ttl as usize)
         .map(|(i, s)| {
             Dataset::default()
                 .name(format!("Hop {}", i + 1))
@@ -1440,7 +3035,7 @@ fn render_chart<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
                 .marker(Marker::Braille)
                 .style(Style::default().fg({
                     match i {
-                        i if i + 1 == selected_hop.ttl() as usize => {
+                        i if i + 1 == selected_ttl as usize => {
                             app.tui_config.theme.hops_chart_selected_color
                         }
                         _ => app.tui_config.theme.hops_chart_unselected_color,
@@ -1452,7 +3047,7 @@ fn render_chart<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
     let chart = Chart::new(sets)
         .x_axis(
             Axis::default()
-                .title("Samples")
+                .title(translate(app.tui_config.locale, "Samples"))
                 .bounds([0_f64, samples as f64])
                 .labels_alignment(Alignment::Right)
                 .labels(
@@ -1465,13 +3060,13 @@ fn render_chart<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
         )
         .y_axis(
             Axis::default()
-                .title("RTT")
+                .title(format!("RTT ({})", app.tui_config.rtt_format.unit))
                 .bounds([0_f64, max_sample])
                 .labels(
                     [
-                        String::from("0.0"),
-                        format!("{:.1}", max_sample / 2_f64),
-                        format!("{max_sample:.1}"),
+                        app.tui_config.rtt_format.format(0_f64),
+                        app.tui_config.rtt_format.format(max_sample / 2_f64),
+                        app.tui_config.rtt_format.format(max_sample),
                     ]
                     .into_iter()
                     .map(Span::from)
@@ -1490,11 +3085,125 @@ fn render_chart<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .border_style(Style::default().fg(app.tui_config.theme.border_color))
-                .title("Chart"),
+                .title(title),
         );
     f.render_widget(chart, rect);
 }
 
+/// Render the selected trace and the compared trace side by side.
+///
+/// The hops of both traces are aligned by ttl, such that the selected hop is the same hop in both, and the charts of
+/// both traces share the same scale.  Both traces are snapshotted together and so show the same point in time.
+fn render_compare<B: Backend>(f: &mut Frame<'_, B>, app: &TuiApp, rect: Rect) {
+    let Some(compared) = app.trace_compared else {
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(rect);
+    let panes = [
+        (app.trace_selected, &app.selected_tracer_data, chunks[0]),
+        (compared, &app.compared_tracer_data, chunks[1]),
+    ];
+    if app.show_chart {
+        let selected_ttl = app.selected_hop_or_target().ttl();
+        let samples = app.tui_config.max_samples / app.zoom_factor;
+        let series_data = panes.map(|(_, trace, _)| chart_series(trace, samples));
+        let max_sample = max_chart_sample(&series_data[0]).max(max_chart_sample(&series_data[1]));
+        for ((index, trace, rect), series_data) in panes.iter().zip(&series_data) {
+            let title = compare_title(&app.trace_info[*index], trace);
+            render_trace_chart(f, app, series_data, max_sample, selected_ttl, title, *rect);
+        }
+    } else {
+        let (first, last) = panes
+            .iter()
+            .flat_map(|(_, trace, _)| trace.hops().iter().map(Hop::ttl))
+            .minmax()
+            .into_option()
+            .unwrap_or_default();
+        for (index, trace, rect) in panes {
+            render_compare_table(f, app, &app.trace_info[index], trace, first..=last, rect);
+        }
+    }
+}
+
+/// Render the hop table of a compared trace with a row for each of the `ttls`, which are blank for the hops which are
+/// not in the trace.
+fn render_compare_table<B: Backend>(
+    f: &mut Frame<'_, B>,
+    app: &TuiApp,
+    info: &TraceInfo,
+    trace: &Trace,
+    ttls: RangeInclusive<u8>,
+    rect: Rect,
+) {
+    let theme = app.tui_config.theme;
+    let header_cells = COMPARE_TABLE_HEADER.iter().map(|h| {
+        Cell::from(translate(app.tui_config.locale, h))
+            .style(Style::default().fg(theme.hops_table_header_text_color))
+    });
+    let header = Row::new(header_cells)
+        .style(Style::default().bg(theme.hops_table_header_bg_color))
+        .height(1)
+        .bottom_margin(0);
+    let selected_ttl = app.selected_hop().map(Hop::ttl);
+    let mut table_state = TableState::default();
+    table_state.select(selected_ttl.and_then(|ttl| ttls.clone().position(|t| t == ttl)));
+    let rows = ttls.map(|ttl| {
+        let Some(index) = trace.hops().iter().position(|hop| hop.ttl() == ttl) else {
+            return Row::new([Cell::from(format!("  {ttl}"))]);
+        };
+        let hop = &trace.hops()[index];
+        let (hostname_cell, row_height) = render_hostname(
+            hop,
+            &app.resolver,
+            &app.geoip_lookup,
+            &info.annotations,
+            &app.tui_config,
+            info.ecn,
+            info.dscp,
+            geo_distance(&app.geoip_lookup, trace.hops(), index),
+        );
+        let row_color = if trace.is_in_round(hop) {
+            theme.hops_table_row_active_text_color
+        } else {
+            theme.hops_table_row_inactive_text_color
+        };
+        Row::new([
+            render_ttl_cell(hop, None, &app.tui_config),
+            hostname_cell,
+            render_loss_pct_cell(hop),
+            render_total_sent_cell(hop),
+            render_last_cell(hop, &app.tui_config),
+            render_avg_cell(hop, &app.tui_config),
+            render_stddev_cell(hop, &app.tui_config),
+        ])
+        .height(row_height)
+        .bottom_margin(0)
+        .style(Style::default().fg(row_color))
+    });
+    let table = Table::new(rows)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(theme.border_color))
+                .title(compare_title(info, trace)),
+        )
+        .style(Style::default().bg(theme.bg_color).fg(theme.text_color))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&COMPARE_TABLE_WIDTH);
+    f.render_stateful_widget(table, rect, &mut table_state);
+}
+
+/// The title of a compared trace, which includes the round of the trace.
+fn compare_title(info: &TraceInfo, trace: &Trace) -> String {
+    let round = trace.round().map_or(0, |round| round + 1);
+    format!("{} ({}) round {round}", info.target_hostname, info.protocol)
+}
+
 /// Render a blue screen of death.
 fn render_bsod<B: Backend>(f: &mut Frame<'_, B>, rect: Rect, error: &str) {
     let chunks = Layout::default()
@@ -1528,7 +3237,7 @@ fn render_splash<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
         .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
         .split(rect);
     let block = Block::default()
-        .title("Hops")
+        .title(translate(app.tui_config.locale, "Hops"))
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(app.tui_config.theme.border_color))
@@ -1564,19 +3273,51 @@ fn render_splash<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
 /// - The packet loss % for all probes at this hop (`Loss%`)
 /// - The number of requests sent for all probes at this hop (`Snt`)
 /// - The number of replies received for all probes at this hop (`Recv`)
+/// - The number of duplicate replies received for all probes at this hop (`Dup`)
 /// - The round-trip time of the most recent probe at this hop (`Last`)
 /// - The average round-trip time for all probes at this hop (`Avg`)
 /// - The best round-trip time for all probes at this hop (`Best`)
 /// - The worst round-trip time for all probes at this hop (`Wrst`)
 /// - The standard deviation round-trip time for all probes at this hop (`StDev`)
-/// - The status of this hop (`Sts`)
+/// - The inferred length of the return path from this hop, IPv4 only (`Rtn`)
+/// - The status of this hop (`Sts`), where loss which is likely caused by ICMP rate limiting is shown distinctly
 fn render_table<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect) {
-    let header = render_table_header(app.tui_config.theme);
+    let header = render_table_header(
+        app.tui_config.theme,
+        app.tui_config.seen_columns,
+        app.tui_config.locale,
+    );
     let selected_style = Style::default().add_modifier(Modifier::REVERSED);
-    let rows =
-        app.tracer_data().hops().iter().map(|hop| {
-            render_table_row(app, hop, &app.resolver, &app.geoip_lookup, &app.tui_config)
-        });
+    let as_groups = if app.tui_config.lookup_as_info {
+        group_by_asn(
+            app.tracer_data()
+                .hops()
+                .iter()
+                .map(|hop| hop_asn(hop, &app.resolver)),
+        )
+    } else {
+        vec![None; app.tracer_data().hops().len()]
+    };
+    let hop_rows = app.hop_rows();
+    let selected_row = app
+        .table_state
+        .selected()
+        .and_then(|selected| hop_rows.iter().position(|row| row.contains(selected)));
+    app.hops_table_state.select(selected_row);
+    let rows = hop_rows.into_iter().map(|row| match row {
+        HopRow::Hop(index) => render_table_row(
+            app,
+            &app.tracer_data().hops()[index],
+            as_groups[index],
+            geo_distance(&app.geoip_lookup, app.tracer_data().hops(), index),
+            &app.resolver,
+            &app.geoip_lookup,
+            &app.tui_config,
+        ),
+        HopRow::Hidden(index, count) => {
+            render_hidden_table_row(&app.tracer_data().hops()[index], count, &app.tui_config)
+        }
+    });
     let table = Table::new(rows)
         .header(header)
         .block(
@@ -1584,7 +3325,7 @@ fn render_table<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .border_style(Style::default().fg(app.tui_config.theme.border_color))
-                .title("Hops"),
+                .title(translate(app.tui_config.locale, "Hops")),
         )
         .style(
             Style::default()
@@ -1592,25 +3333,142 @@ fn render_table<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect: Rect)
                 .fg(app.tui_config.theme.text_color),
         )
         .highlight_style(selected_style)
-        .widths(&TABLE_WIDTH);
-    f.render_stateful_widget(table, rect, &mut app.table_state);
+        .widths(if app.tui_config.seen_columns {
+            &SEEN_TABLE_WIDTH
+        } else {
+            &TABLE_WIDTH
+        });
+    f.render_stateful_widget(table, rect, &mut app.hops_table_state);
 }
 
-/// Render the table header.
-fn render_table_header(theme: Theme) -> Row<'static> {
-    let header_cells = TABLE_HEADER
-        .iter()
-        .map(|h| Cell::from(*h).style(Style::default().fg(theme.hops_table_header_text_color)));
+/// A row of the hop table.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum HopRow {
+    /// The hop at an index.
+    Hop(usize),
+    /// A run of consecutive hidden hops, from the first index and with a count.
+    Hidden(usize, usize),
+}
+
+impl HopRow {
+    /// Does this row contain the hop at `index`?
+    fn contains(self, index: usize) -> bool {
+        match self {
+            Self::Hop(i) => i == index,
+            Self::Hidden(first, count) => (first..first + count).contains(&index),
+        }
+    }
+}
+
+/// Does a hop match the hop filter?
+///
+/// A hop which has not responded matches only if the filter includes unresponsive hops.  A hop which has responded
+/// matches if the filter includes private hops and all of its addresses are in a special purpose range, or if any of its
+/// addresses or hostnames contains a pattern of the filter.
+fn is_filtered_hop(hop: &Hop, filter: &HopFilter, dns: &DnsResolver) -> bool {
+    if hop.total_recv() == 0 {
+        return filter.unresponsive;
+    }
+    let is_private = || {
+        hop.addrs()
+            .all(|addr| SpecialPurpose::lookup(*addr).is_some())
+    };
+    let is_matched = || {
+        hop.addrs().any(|addr| {
+            let hostname = dns.reverse_lookup(*addr).to_string();
+            filter.patterns.iter().any(|pattern| {
+                addr.to_string().contains(pattern.as_str()) || hostname.contains(pattern.as_str())
+            })
+        })
+    };
+    (filter.private && is_private()) || (!filter.patterns.is_empty() && is_matched())
+}
+
+/// Render a single row in the table of hops for a run of `count` hidden hops starting at `hop`.
+fn render_hidden_table_row(hop: &Hop, count: usize, config: &TuiConfig) -> Row<'static> {
+    let hops = if count == 1 { "hop" } else { "hops" };
+    let cells = [
+        Cell::from(format!("  {}", hop.ttl())),
+        Cell::from(format!("{count} hidden {hops}")),
+    ];
+    Row::new(cells)
+        .height(1)
+        .bottom_margin(0)
+        .style(Style::default().fg(config.theme.hops_table_row_inactive_text_color))
+}
+
+/// Render the table header, with the first and last seen columns if `seen_columns` is set.
+fn render_table_header(theme: Theme, seen_columns: bool, locale: Locale) -> Row<'static> {
+    let seen_header: &[&str] = if seen_columns {
+        &SEEN_TABLE_HEADER
+    } else {
+        &[]
+    };
+    let header_cells = TABLE_HEADER.iter().chain(seen_header).map(|h| {
+        Cell::from(translate(locale, h))
+            .style(Style::default().fg(theme.hops_table_header_text_color))
+    });
     Row::new(header_cells)
         .style(Style::default().bg(theme.hops_table_header_bg_color))
         .height(1)
         .bottom_margin(0)
 }
 
+/// The autonomous system group of a hop.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct AsGroup {
+    /// The index of the group, counted from the first hop.
+    index: usize,
+    /// Whether this hop is the first in the group, i.e. the path crosses an `AS` boundary here.
+    boundary: bool,
+}
+
+/// Group consecutive hops which belong to the same autonomous system.
+///
+/// Hops without a known `AS` (i.e. which did not respond or have a private address) are not part of any group and do
+/// not end the current group, so a boundary is only shown where two known `AS` numbers differ.
+fn group_by_asn(asns: impl Iterator<Item = Option<String>>) -> Vec<Option<AsGroup>> {
+    let mut current: Option<(String, usize)> = None;
+    asns.map(|asn| {
+        let asn = asn?;
+        match &current {
+            Some((prev, index)) if *prev == asn => Some(AsGroup {
+                index: *index,
+                boundary: false,
+            }),
+            _ => {
+                let index = current.as_ref().map_or(0, |(_, index)| index + 1);
+                current = Some((asn, index));
+                Some(AsGroup {
+                    index,
+                    boundary: true,
+                })
+            }
+        }
+    })
+    .collect()
+}
+
+/// The `AS` number of the most frequently observed address of a hop, if known.
+fn hop_asn(hop: &Hop, dns: &DnsResolver) -> Option<String> {
+    let (addr, _) = hop.addrs_with_counts().max_by_key(|(_, &count)| count)?;
+    match dns.reverse_lookup_with_asinfo(*addr) {
+        DnsEntry::Resolved(Resolved::WithAsInfo(_, _, asinfo))
+        | DnsEntry::NotFound(Unresolved::WithAsInfo(_, asinfo))
+            if !asinfo.asn.is_empty() =>
+        {
+            Some(asinfo.asn)
+        }
+        _ => None,
+    }
+}
+
 /// Render a single row in the table of hops.
 fn render_table_row(
     app: &TuiApp,
     hop: &Hop,
+    as_group: Option<AsGroup>,
+    geo: Option<GeoDistance>,
     dns: &DnsResolver,
     geoip_lookup: &GeoIpLookup,
     config: &TuiConfig,
@@ -1619,36 +3477,64 @@ fn render_table_row(
         .selected_hop()
         .map(|h| h.ttl() == hop.ttl())
         .unwrap_or_default();
-    let is_target = app.tracer_data().is_target(hop);
     let is_in_round = app.tracer_data().is_in_round(hop);
-    let ttl_cell = render_ttl_cell(hop);
+    let ttl_cell = render_ttl_cell(hop, as_group, config);
     let (hostname_cell, row_height) = if is_selected_hop && app.show_hop_details {
-        render_hostname_with_details(app, hop, dns, geoip_lookup, config)
+        render_hostname_with_details(app, hop, geo, dns, geoip_lookup, config)
     } else {
-        render_hostname(hop, dns, geoip_lookup, config)
+        let trace_info = app.tracer_config();
+        render_hostname(
+            hop,
+            dns,
+            geoip_lookup,
+            &trace_info.annotations,
+            config,
+            trace_info.ecn,
+            trace_info.dscp,
+            geo,
+        )
     };
     let loss_pct_cell = render_loss_pct_cell(hop);
     let total_sent_cell = render_total_sent_cell(hop);
     let total_recv_cell = render_total_recv_cell(hop);
-    let last_cell = render_last_cell(hop);
-    let avg_cell = render_avg_cell(hop);
-    let best_cell = render_best_cell(hop);
-    let worst_cell = render_worst_cell(hop);
-    let stddev_cell = render_stddev_cell(hop);
-    let status_cell = render_status_cell(hop, is_target);
-    let cells = [
+    let total_dup_cell = render_total_dup_cell(hop);
+    let last_cell = render_last_cell(hop, config);
+    let avg_cell = render_avg_cell(hop, config);
+    let best_cell = render_best_cell(hop, config);
+    let worst_cell = render_worst_cell(hop, config);
+    let stddev_cell = render_stddev_cell(hop, config);
+    let return_path_cell = render_return_path_cell(hop);
+    let status = app.tracer_data().hop_status(hop);
+    let status_cell = render_status_cell(status, config);
+    let mut cells = vec![
         ttl_cell,
         hostname_cell,
         loss_pct_cell,
         total_sent_cell,
         total_recv_cell,
+        total_dup_cell,
         last_cell,
         avg_cell,
         best_cell,
         worst_cell,
         stddev_cell,
+        return_path_cell,
         status_cell,
     ];
+    if config.seen_columns {
+        let addrs: Vec<_> = if is_selected_hop && app.show_hop_details {
+            hop.addrs()
+                .nth(app.selected_hop_address)
+                .into_iter()
+                .collect()
+        } else {
+            displayed_addrs(hop, config)
+                .into_iter()
+                .map(|(addr, _)| addr)
+                .collect()
+        };
+        cells.extend(render_seen_cells(hop, &addrs));
+    }
     let row_color = if is_in_round {
         config.theme.hops_table_row_active_text_color
     } else {
@@ -1660,8 +3546,23 @@ fn render_table_row(
         .style(Style::default().fg(row_color))
 }
 
-fn render_ttl_cell(hop: &Hop) -> Cell<'static> {
-    Cell::from(format!("{}", hop.ttl()))
+/// Render the ttl cell, marking the `AS` group of the hop (if known) and where the path crosses an `AS` boundary.
+///
+/// The `AS` group is also distinguished by color, from the palette, unless in plain mode.
+fn render_ttl_cell(hop: &Hop, as_group: Option<AsGroup>, config: &TuiConfig) -> Cell<'static> {
+    match as_group {
+        Some(AsGroup { index, boundary }) => {
+            let marker = if boundary { '┌' } else { '│' };
+            let cell = Cell::from(format!("{marker} {}", hop.ttl()));
+            if config.plain {
+                cell
+            } else {
+                let colors = as_group_colors(config.palette);
+                cell.style(Style::default().fg(colors[index % colors.len()]))
+            }
+        }
+        None => Cell::from(format!("  {}", hop.ttl())),
+    }
 }
 
 fn render_loss_pct_cell(hop: &Hop) -> Cell<'static> {
@@ -1676,100 +3577,197 @@ fn render_total_recv_cell(hop: &Hop) -> Cell<'static> {
     Cell::from(format!("{}", hop.total_recv()))
 }
 
-fn render_avg_cell(hop: &Hop) -> Cell<'static> {
+fn render_avg_cell(hop: &Hop, config: &TuiConfig) -> Cell<'static> {
     Cell::from(if hop.total_recv() > 0 {
-        format!("{:.1}", hop.avg_ms())
+        config.rtt_format.format(hop.avg_ms())
     } else {
         String::default()
     })
 }
 
-fn render_last_cell(hop: &Hop) -> Cell<'static> {
+fn render_last_cell(hop: &Hop, config: &TuiConfig) -> Cell<'static> {
     Cell::from(
         hop.last_ms()
-            .map(|last| format!("{last:.1}"))
+            .map(|last| config.rtt_format.format(last))
             .unwrap_or_default(),
     )
 }
 
-fn render_best_cell(hop: &Hop) -> Cell<'static> {
+fn render_total_dup_cell(hop: &Hop) -> Cell<'static> {
+    Cell::from(if hop.total_dup() > 0 {
+        format!("{}", hop.total_dup())
+    } else {
+        String::default()
+    })
+}
+
+fn render_best_cell(hop: &Hop, config: &TuiConfig) -> Cell<'static> {
     Cell::from(
         hop.best_ms()
-            .map(|best| format!("{best:.1}"))
+            .map(|best| config.rtt_format.format(best))
             .unwrap_or_default(),
     )
 }
 
-fn render_worst_cell(hop: &Hop) -> Cell<'static> {
+fn render_worst_cell(hop: &Hop, config: &TuiConfig) -> Cell<'static> {
     Cell::from(
         hop.worst_ms()
-            .map(|worst| format!("{worst:.1}"))
+            .map(|worst| config.rtt_format.format(worst))
             .unwrap_or_default(),
     )
 }
 
-fn render_stddev_cell(hop: &Hop) -> Cell<'static> {
+fn render_stddev_cell(hop: &Hop, config: &TuiConfig) -> Cell<'static> {
     Cell::from(if hop.total_recv() > 1 {
-        format!("{:.1}", hop.stddev_ms())
+        config.rtt_format.format(hop.stddev_ms())
     } else {
         String::default()
     })
 }
 
-fn render_status_cell(hop: &Hop, is_target: bool) -> Cell<'static> {
-    let lost = hop.total_sent() - hop.total_recv();
-    Cell::from(match (lost, is_target) {
-        (lost, target) if target && lost == hop.total_sent() => "🔴",
-        (lost, target) if target && lost > 0 => "🟡",
-        (lost, target) if !target && lost == hop.total_sent() => "🟤",
-        (lost, target) if !target && lost > 0 => "🔵",
-        _ => "🟢",
-    })
+/// Render the first and last seen cells, with a line for each of the `addrs` of the hop as shown in the hostname cell.
+fn render_seen_cells(hop: &Hop, addrs: &[&IpAddr]) -> [Cell<'static>; 2] {
+    let format_seen = |seen_time: fn(Seen) -> SystemTime| {
+        addrs
+            .iter()
+            .map(|addr| {
+                hop.addr_seen(addr)
+                    .map(|seen| {
+                        DateTime::<Local>::from(seen_time(seen))
+                            .format("%H:%M:%S")
+                            .to_string()
+                    })
+                    .unwrap_or_default()
+            })
+            .join("\n")
+    };
+    [
+        Cell::from(format_seen(|seen| seen.first)),
+        Cell::from(format_seen(|seen| seen.last)),
+    ]
+}
+
+fn render_return_path_cell(hop: &Hop) -> Cell<'static> {
+    Cell::from(
+        hop.return_path_len()
+            .map(|len| format!("{len}"))
+            .unwrap_or_default(),
+    )
+}
+
+/// Render the status cell, as an emoji or as text where the loss at the target is upper case.
+///
+/// The status is shown as text in plain and ASCII modes and, colored to signal its severity, with palettes other than
+/// the default.
+fn render_status_cell(status: HopStatus, config: &TuiConfig) -> Cell<'static> {
+    match StatusColors::from_palette(config.palette) {
+        _ if config.plain => Cell::from(status.to_string()),
+        Some(colors) => {
+            Cell::from(status.to_string()).style(Style::default().fg(colors.color(status)))
+        }
+        None if config.ascii => Cell::from(status.to_string()),
+        None => Cell::from(status_emoji(status)),
+    }
+}
+
+/// The emoji which signals the status of a hop.
+const fn status_emoji(status: HopStatus) -> &'static str {
+    match status {
+        HopStatus::RateLimited => "🟣",
+        HopStatus::TargetDown => "🔴",
+        HopStatus::TargetLoss => "🟡",
+        HopStatus::NoResponse => "🟤",
+        HopStatus::Loss => "🔵",
+        HopStatus::Ok => "🟢",
+    }
 }
 
 /// Render hostname table cell (normal mode).
+///
+/// If the increase in round trip time from the preceding hop is inconsistent with the distance between them, the
+/// `GeoDistance` is shown on a line of its own.
+#[allow(clippy::too_many_arguments)]
 fn render_hostname(
     hop: &Hop,
     dns: &DnsResolver,
     geoip_lookup: &GeoIpLookup,
+    annotations: &Annotations,
     config: &TuiConfig,
+    ecn: Option<EcnCodepoint>,
+    dscp: Option<Dscp>,
+    geo: Option<GeoDistance>,
 ) -> (Cell<'static>, u16) {
     let (hostname, count) = if hop.total_recv() > 0 {
-        match config.max_addrs {
-            None => {
-                let hostnames = hop
-                    .addrs_with_counts()
-                    .map(|(addr, &freq)| format_address(addr, freq, hop, dns, geoip_lookup, config))
-                    .join("\n");
-                let count = hop.addr_count().clamp(1, u8::MAX as usize);
-                (hostnames, count as u16)
-            }
-            Some(max_addr) => {
-                let hostnames = hop
-                    .addrs_with_counts()
-                    .sorted_unstable_by_key(|(_, &cnt)| cnt)
-                    .rev()
-                    .take(max_addr as usize)
-                    .map(|(addr, &freq)| format_address(addr, freq, hop, dns, geoip_lookup, config))
-                    .join("\n");
-                let count = hop.addr_count().clamp(1, max_addr as usize);
-                (hostnames, count as u16)
-            }
-        }
+        let addrs = displayed_addrs(hop, config);
+        let hostnames = addrs
+            .iter()
+            .map(|&(addr, freq)| {
+                format_address(
+                    addr,
+                    freq,
+                    hop,
+                    dns,
+                    geoip_lookup,
+                    annotations,
+                    config,
+                    ecn,
+                    dscp,
+                )
+            })
+            .join("\n");
+        let count = addrs.len().clamp(1, u8::MAX as usize);
+        (hostnames, count as u16)
     } else {
         (String::from("No response"), 1)
     };
+    let recorded = hop.unseen_recorded_addrs().join(", ");
+    let (hostname, count) = if recorded.is_empty() {
+        (hostname, count)
+    } else {
+        (format!("{hostname}\nrr: {recorded}"), count + 1)
+    };
+    let (hostname, count) = match geo.and_then(|geo| Some((geo.sanity()?, geo))) {
+        Some((sanity, geo)) => (
+            format!(
+                "{hostname}\ngeo: {sanity}, {}",
+                geo.summary(config.rtt_format)
+            ),
+            count + 1,
+        ),
+        None => (hostname, count),
+    };
     (Cell::from(hostname), count)
 }
 
+/// The addresses of a hop shown in the hop table, with the number of responses from each, which are the most frequently
+/// observed if limited by `max_addrs`.
+fn displayed_addrs<'a>(hop: &'a Hop, config: &TuiConfig) -> Vec<(&'a IpAddr, usize)> {
+    let addrs = hop.addrs_with_counts().map(|(addr, &freq)| (addr, freq));
+    match config.max_addrs {
+        None => addrs.collect(),
+        Some(max_addr) => addrs
+            .sorted_unstable_by_key(|&(_, freq)| freq)
+            .rev()
+            .take(max_addr as usize)
+            .collect(),
+    }
+}
+
 /// Perform a reverse DNS lookup for an address and format the result.
+///
+/// The user supplied annotation of the address, if any, is shown after it, followed by the special purpose range the
+/// address belongs to, if any.
+#[allow(clippy::too_many_arguments)]
 fn format_address(
     addr: &IpAddr,
     freq: usize,
     hop: &Hop,
     dns: &DnsResolver,
     geoip_lookup: &GeoIpLookup,
+    annotations: &Annotations,
     config: &TuiConfig,
+    ecn: Option<EcnCodepoint>,
+    dscp: Option<Dscp>,
 ) -> String {
     let addr_fmt = match config.address_mode {
         AddressMode::IP => addr.to_string(),
@@ -1793,6 +3791,14 @@ fn format_address(
             format!("{hostname} ({addr})")
         }
     };
+    let addr_fmt = match annotations.lookup(*addr) {
+        Some(label) => format!("{addr_fmt} [{label}]"),
+        None => addr_fmt,
+    };
+    let addr_fmt = match SpecialPurpose::lookup(*addr) {
+        Some(special) => format!("{addr_fmt} [{special}]"),
+        None => addr_fmt,
+    };
     let geo_fmt = match config.geoip_mode {
         GeoIpMode::Off => None,
         GeoIpMode::Short => geoip_lookup
@@ -1808,26 +3814,46 @@ fn format_address(
             .unwrap_or_default()
             .map(|geo| geo.location()),
     };
+    let ecn_fmt = ecn
+        .and_then(|ecn| hop.ecn_status(ecn))
+        .map(|status| format!(" [ecn {status}]"))
+        .unwrap_or_default();
+    let dscp_fmt = dscp
+        .and_then(|dscp| hop.dscp_status(dscp))
+        .map(|status| format!(" [dscp {status}]"))
+        .unwrap_or_default();
+    let off_path_fmt = hop
+        .off_path(addr)
+        .map(|off_path| format!(" [off-path {off_path}]"))
+        .unwrap_or_default();
+    let icmp_fmt = hop
+        .icmp_response()
+        .and_then(IcmpResponse::flag)
+        .map(|flag| format!(" [{flag}]"))
+        .unwrap_or_default();
+    let flags_fmt = format!("{off_path_fmt}{icmp_fmt}{ecn_fmt}{dscp_fmt}");
     match geo_fmt {
         Some(geo) if hop.addr_count() > 1 => {
             format!(
-                "{} [{}] [{:.1}%]",
+                "{} [{}]{} [{:.1}%]",
                 addr_fmt,
                 geo,
+                flags_fmt,
                 (freq as f64 / hop.total_recv() as f64) * 100_f64
             )
         }
         Some(geo) => {
-            format!("{addr_fmt} [{geo}]")
+            format!("{addr_fmt} [{geo}]{flags_fmt}")
         }
         None if hop.addr_count() > 1 => {
             format!(
-                "{} [{:.1}%]",
+                "{}{} [{:.1}%]",
                 addr_fmt,
+                flags_fmt,
                 (freq as f64 / hop.total_recv() as f64) * 100_f64
             )
         }
-        None => addr_fmt,
+        None => format!("{addr_fmt}{flags_fmt}"),
     }
 }
 
@@ -1871,13 +3897,86 @@ fn format_asinfo(asinfo: &AsInfo, as_mode: AsMode) -> String {
 fn render_hostname_with_details(
     app: &TuiApp,
     hop: &Hop,
+    geo: Option<GeoDistance>,
     dns: &DnsResolver,
     geoip_lookup: &GeoIpLookup,
     config: &TuiConfig,
 ) -> (Cell<'static>, u16) {
     let (rendered, count) = if hop.total_recv() > 0 {
         let index = app.selected_hop_address;
-        format_details(hop, index, dns, geoip_lookup, config)
+        let (details, count) = format_details(hop, index, dns, geoip_lookup, config);
+        let addr = hop.addrs().nth(index);
+        let annotation = addr.and_then(|addr| app.tracer_config().annotations.lookup(*addr));
+        let (details, count) = match annotation {
+            Some(label) => (format!("{details}\nLabel: {label}"), count + 1),
+            None => (details, count),
+        };
+        let (details, count) = match addr.and_then(|addr| SpecialPurpose::lookup(*addr)) {
+            Some(special) => (format!("{details}\nRange: {special}"), count + 1),
+            None => (details, count),
+        };
+        let (details, count) = match addr
+            .filter(|_| hop.addr_count() > 1)
+            .and_then(|addr| hop.addr_stats(addr))
+        {
+            Some(stats) => (
+                format!(
+                    "{details}\nStats: {}",
+                    format_addr_stats(stats, config.rtt_format)
+                ),
+                count + 1,
+            ),
+            None => (details, count),
+        };
+        let (details, count) = match hop.icmp_response() {
+            Some(icmp) => (format!("{details}\nIcmp: {icmp}"), count + 1),
+            None => (details, count),
+        };
+        let (details, count) = match geo {
+            Some(geo) => (
+                format!(
+                    "{details}\nDistance: {}{}",
+                    geo.summary(config.rtt_format),
+                    geo.sanity()
+                        .map(|sanity| format!(" [{sanity}]"))
+                        .unwrap_or_default()
+                ),
+                count + 1,
+            ),
+            None => (details, count),
+        };
+        let (details, count) = match format_tcp_handshake(hop, config.rtt_format) {
+            Some(handshake) => (format!("{details}\nTcp: {handshake}"), count + 1),
+            None => (details, count),
+        };
+        let (details, count) = match hop.last_timestamp() {
+            Some(timestamp) => (
+                format!("{details}\nTimestamp: {}", format_timestamp(timestamp)),
+                count + 1,
+            ),
+            None => (details, count),
+        };
+        let (details, count) = if hop.total_late() > 0 {
+            let late = hop.total_late();
+            (format!("{details}\nLate: {late} responses"), count + 1)
+        } else {
+            (details, count)
+        };
+        let (details, count) = if hop.total_retries() > 0 {
+            let retries = hop.total_retries();
+            (format!("{details}\nRetries: {retries} probes"), count + 1)
+        } else {
+            (details, count)
+        };
+        if hop.total_corrupt() > 0 {
+            let corrupt = hop.total_corrupt();
+            (
+                format!("{details}\nCorrupt: {corrupt} quoted probes"),
+                count + 1,
+            )
+        } else {
+            (details, count)
+        }
     } else {
         (String::from("No response"), 1)
     };
@@ -1885,6 +3984,51 @@ fn render_hostname_with_details(
     (cell, count)
 }
 
+/// Format the statistics of the responses from a single address of a hop with several addresses.
+fn format_addr_stats(stats: &AddrStats, rtt_format: RttFormat) -> String {
+    let format_rtt = |rtt: Option<f64>| {
+        rtt.map_or_else(|| String::from("-"), |rtt| rtt_format.format_with_unit(rtt))
+    };
+    format!(
+        "loss {:.1}% ({}/{}), last {}, avg {}, best {}, worst {}, stddev {}",
+        stats.loss_pct(),
+        stats.total_recv(),
+        stats.total_sent(),
+        format_rtt(stats.last_ms()),
+        rtt_format.format_with_unit(stats.avg_ms()),
+        format_rtt(stats.best_ms()),
+        format_rtt(stats.worst_ms()),
+        rtt_format.format_with_unit(stats.stddev_ms())
+    )
+}
+
+/// Format the outcome and duration of the TCP handshakes with a hop, if any.
+/// Format the one-way delays of an ICMP timestamp reply, which have a resolution of one millisecond.
+fn format_timestamp(timestamp: Timestamp) -> String {
+    match (
+        timestamp.forward_ms(),
+        timestamp.return_ms(),
+        timestamp.asymmetry_ms(),
+    ) {
+        (Some(forward), Some(ret), Some(asymmetry)) => {
+            format!("fwd {forward}ms, rtn {ret}ms, asymmetry {asymmetry:+}ms")
+        }
+        _ => String::from("non-standard"),
+    }
+}
+
+fn format_tcp_handshake(hop: &Hop, rtt_format: RttFormat) -> Option<String> {
+    let last = hop.last_tcp_connect_ms()?;
+    let avg = hop.avg_tcp_connect_ms().unwrap_or_default();
+    Some(format!(
+        "established {}, refused {}, connect last {} avg {}",
+        hop.tcp_established(),
+        hop.tcp_refused(),
+        rtt_format.format_with_unit(last),
+        rtt_format.format_with_unit(avg)
+    ))
+}
+
 /// Format hop details.
 fn format_details(
     hop: &Hop,
@@ -2181,7 +4325,10 @@ fn render_settings_tabs<B: Backend>(f: &mut Frame<'_, B>, app: &mut TuiApp, rect
     let tabs = Tabs::new(titles)
         .block(
             Block::default()
-                .title(" Settings ")
+                .title(format!(
+                    " {} ",
+                    translate(app.tui_config.locale, "Settings")
+                ))
                 .title_alignment(Alignment::Center)
                 .borders(Borders::ALL)
                 .style(Style::default().bg(app.tui_config.theme.settings_dialog_bg_color))
@@ -2202,7 +4349,7 @@ fn render_settings_table<B: Backend>(
     items: &[SettingsItem],
 ) {
     let header_cells = SETTINGS_TABLE_HEADER.iter().map(|h| {
-        Cell::from(*h)
+        Cell::from(translate(app.tui_config.locale, h))
             .style(Style::default().fg(app.tui_config.theme.settings_table_header_text_color))
     });
     let header = Row::new(header_cells)
@@ -2251,7 +4398,7 @@ fn render_settings_info<B: Backend>(
         .style(Style::default())
         .block(
             Block::default()
-                .title(" Info ")
+                .title(format!(" {} ", translate(app.tui_config.locale, "Info")))
                 .title_alignment(Alignment::Center)
                 .borders(Borders::ALL)
                 .style(Style::default().bg(app.tui_config.theme.settings_dialog_bg_color))
@@ -2310,6 +4457,19 @@ fn format_tui_settings(app: &TuiApp) -> Vec<SettingsItem> {
             "tui-preserve-screen",
             format!("{}", app.tui_config.preserve_screen),
         ),
+        SettingsItem::new("tui-title", format!("{}", app.tui_config.title)),
+        SettingsItem::new(
+            "tui-seen-columns",
+            format!("{}", app.tui_config.seen_columns),
+        ),
+        SettingsItem::new("tui-ascii", format!("{}", app.tui_config.ascii)),
+        SettingsItem::new("plain", format!("{}", app.tui_config.plain)),
+        SettingsItem::new("tui-palette", format_palette(app.tui_config.palette)),
+        SettingsItem::new("rtt-unit", app.tui_config.rtt_format.unit.to_string()),
+        SettingsItem::new(
+            "rtt-precision",
+            app.tui_config.rtt_format.precision.to_string(),
+        ),
         SettingsItem::new(
             "tui-refresh-rate",
             format!("{}", format_duration(app.tui_config.refresh_rate)),
@@ -2329,6 +4489,21 @@ fn format_tui_settings(app: &TuiApp) -> Vec<SettingsItem> {
                 .max_addrs
                 .map_or_else(|| String::from("auto"), |m| m.to_string()),
         ),
+        SettingsItem::new(
+            "tui-hide-hops",
+            format!(
+                "{} ({})",
+                app.tui_config.hop_filter,
+                if app.hide_hops { "on" } else { "off" }
+            ),
+        ),
+        SettingsItem::new("tui-panes", format_panes(&app.tui_config.panes)),
+        SettingsItem::new("tui-pane-split", format!("{}%", app.tui_config.pane_split)),
+        SettingsItem::new(
+            "tui-export-format",
+            format_export_format(app.tui_config.export_format),
+        ),
+        SettingsItem::new("tui-export-dir", app.tui_config.export_dir.clone()),
     ]
 }
 
@@ -2349,6 +4524,12 @@ fn format_trace_settings(app: &TuiApp) -> Vec<SettingsItem> {
     vec![
         SettingsItem::new("first-ttl", format!("{}", cfg.first_ttl)),
         SettingsItem::new("max-ttl", format!("{}", cfg.max_ttl)),
+        SettingsItem::new("destination-only", format!("{}", cfg.destination_only)),
+        SettingsItem::new("adaptive-ttl", format!("{}", cfg.adaptive_ttl)),
+        SettingsItem::new(
+            "prohibited-strategy",
+            format!("{}", cfg.prohibited_strategy),
+        ),
         SettingsItem::new(
             "min-round-duration",
             format!("{}", format_duration(cfg.min_round_duration)),
@@ -2357,18 +4538,46 @@ fn format_trace_settings(app: &TuiApp) -> Vec<SettingsItem> {
             "max-round-duration",
             format!("{}", format_duration(cfg.max_round_duration)),
         ),
+        SettingsItem::new(
+            "round-jitter",
+            format!("{}", format_duration(cfg.round_jitter)),
+        ),
+        SettingsItem::new(
+            "probe-timeout",
+            cfg.probe_timeout.map_or_else(
+                || String::from("none"),
+                |probe_timeout| format!("{}", format_duration(probe_timeout)),
+            ),
+        ),
+        SettingsItem::new("retries", format!("{}", cfg.retries)),
+        SettingsItem::new("backfill-late", format!("{}", cfg.backfill_late)),
         SettingsItem::new(
             "grace-duration",
             format!("{}", format_duration(cfg.grace_duration)),
         ),
         SettingsItem::new("max-inflight", format!("{}", cfg.max_inflight)),
+        SettingsItem::new("adaptive-inflight", format!("{}", cfg.adaptive_inflight)),
+        SettingsItem::new("burst", format!("{}", cfg.burst)),
         SettingsItem::new("initial-sequence", format!("{}", cfg.initial_sequence)),
+        SettingsItem::new("sequence-strategy", format!("{}", cfg.sequence_strategy)),
         SettingsItem::new(
             "read-timeout",
             format!("{}", format_duration(cfg.read_timeout)),
         ),
         SettingsItem::new("packet-size", format!("{}", cfg.packet_size)),
         SettingsItem::new("payload-pattern", format!("{}", cfg.payload_pattern)),
+        SettingsItem::new(
+            "ecn",
+            cfg.ecn
+                .map_or_else(|| String::from("off"), |ecn| ecn.to_string()),
+        ),
+        SettingsItem::new(
+            "ipv6-ext-header",
+            cfg.ipv6_ext_header
+                .map_or_else(|| String::from("none"), |header| header.to_string()),
+        ),
+        SettingsItem::new("record-route", format!("{}", cfg.record_route)),
+        SettingsItem::new("icmp-timestamp", format!("{}", cfg.icmp_timestamp)),
         SettingsItem::new("interface", interface),
         SettingsItem::new("multipath-strategy", cfg.multipath_strategy.to_string()),
         SettingsItem::new("target-port", dst_port),
@@ -2391,9 +4600,27 @@ fn format_dns_settings(app: &TuiApp) -> Vec<SettingsItem> {
             "dns-lookup-as-info",
             format!("{}", app.tui_config.lookup_as_info),
         ),
+        SettingsItem::new(
+            "dns-max-in-flight",
+            app.resolver.config().max_in_flight.to_string(),
+        ),
+        SettingsItem::new(
+            "dns-source-address",
+            format_dns_bind_addrs(app.resolver.config()),
+        ),
     ]
 }
 
+/// Format the addresses DNS queries are sent from.
+fn format_dns_bind_addrs(config: &DnsResolverConfig) -> String {
+    match (config.bind_addr_ipv4, config.bind_addr_ipv6) {
+        (Some(ipv4), Some(ipv6)) => format!("{ipv4}, {ipv6}"),
+        (Some(ipv4), None) => ipv4.to_string(),
+        (None, Some(ipv6)) => ipv6.to_string(),
+        (None, None) => String::from("auto"),
+    }
+}
+
 /// Format `GeoIp` settings.
 fn format_geoip_settings(app: &TuiApp) -> Vec<SettingsItem> {
     vec![SettingsItem::new(
@@ -2427,6 +4654,19 @@ fn format_binding_settings(app: &TuiApp) -> Vec<SettingsItem> {
         SettingsItem::new("toggle-freeze", format!("{}", binds.toggle_freeze)),
         SettingsItem::new("toggle-chart", format!("{}", binds.toggle_chart)),
         SettingsItem::new("toggle-map", format!("{}", binds.toggle_map)),
+        SettingsItem::new(
+            "toggle-route-changes",
+            format!("{}", binds.toggle_route_changes),
+        ),
+        SettingsItem::new("toggle-dns", format!("{}", binds.toggle_dns)),
+        SettingsItem::new(
+            "toggle-diagnostics",
+            format!("{}", binds.toggle_diagnostics),
+        ),
+        SettingsItem::new("toggle-panes", format!("{}", binds.toggle_panes)),
+        SettingsItem::new("toggle-compare", format!("{}", binds.toggle_compare)),
+        SettingsItem::new("toggle-overview", format!("{}", binds.toggle_overview)),
+        SettingsItem::new("toggle-hide-hops", format!("{}", binds.toggle_hide_hops)),
         SettingsItem::new("expand-hosts", format!("{}", binds.expand_hosts)),
         SettingsItem::new("expand-hosts-max", format!("{}", binds.expand_hosts_max)),
         SettingsItem::new("contract-hosts", format!("{}", binds.contract_hosts)),
@@ -2437,6 +4677,10 @@ fn format_binding_settings(app: &TuiApp) -> Vec<SettingsItem> {
         SettingsItem::new("chart-zoom-in", format!("{}", binds.chart_zoom_in)),
         SettingsItem::new("chart-zoom-out", format!("{}", binds.chart_zoom_out)),
         SettingsItem::new("clear-trace-data", format!("{}", binds.clear_trace_data)),
+        SettingsItem::new(
+            "clear-all-trace-data",
+            format!("{}", binds.clear_all_trace_data),
+        ),
         SettingsItem::new("clear-dns-cache", format!("{}", binds.clear_dns_cache)),
         SettingsItem::new("clear-selection", format!("{}", binds.clear_selection)),
         SettingsItem::new("toggle-as-info", format!("{}", binds.toggle_as_info)),
@@ -2444,6 +4688,7 @@ fn format_binding_settings(app: &TuiApp) -> Vec<SettingsItem> {
             "toggle-hop-details",
             format!("{}", binds.toggle_hop_details),
         ),
+        SettingsItem::new("export-report", format!("{}", binds.export_report)),
         SettingsItem::new("quit", format!("{}", binds.quit)),
     ]
 }
@@ -2583,3 +4828,58 @@ fn sample_frequency(samples: &[Duration]) -> Vec<(String, u64)> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::has_translation;
+    use clap::ValueEnum;
+
+    /// The labels of the Tui other than the table headers.
+    const LABELS: [&str; 24] = [
+        "Target",
+        "Config",
+        "Status",
+        "Summary",
+        "Failed",
+        "Frozen",
+        "Paused",
+        "Backing off",
+        "Running",
+        "discovered { $count } hops",
+        "blocked at hop { $hop }",
+        "fragmentation needed at hop { $hop }",
+        "{ $count } problems",
+        "Traces",
+        "Overview",
+        "Route Changes",
+        "Problems",
+        "Diagnostics",
+        "Map",
+        "Hop",
+        "Samples",
+        "Hops",
+        "Settings",
+        "Info",
+    ];
+
+    #[test]
+    fn test_labels_have_translations() {
+        let labels = TABLE_HEADER
+            .iter()
+            .chain(&SETTINGS_TABLE_HEADER)
+            .chain(&ROUTE_CHANGES_TABLE_HEADER)
+            .chain(&DNS_TABLE_HEADER)
+            .chain(&DIAGNOSTICS_TABLE_HEADER)
+            .chain(&PROBLEMS_TABLE_HEADER)
+            .chain(&OVERVIEW_TABLE_HEADER)
+            .chain(&COMPARE_TABLE_HEADER)
+            .chain(&SEEN_TABLE_HEADER)
+            .chain(&LABELS);
+        for label in labels {
+            for locale in Locale::value_variants() {
+                assert!(has_translation(*locale, label), "{locale:?} {label}");
+            }
+        }
+    }
+}
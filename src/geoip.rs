@@ -1,14 +1,19 @@
+use crate::backend::Hop;
+use crate::config::RttFormat;
 use anyhow::Context;
 use itertools::Itertools;
 use maxminddb::geoip2::City;
 use maxminddb::Reader;
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::net::IpAddr;
 use std::path::Path;
 use std::rc::Rc;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct GeoIpCity {
     latitude: Option<f64>,
     longitude: Option<f64>,
@@ -130,12 +135,12 @@ impl From<City<'_>> for GeoIpCity {
 const LOCALE: &str = "en";
 
 /// Alias for a cache of `GeoIp` data.
-type Cache = RefCell<HashMap<IpAddr, Rc<GeoIpCity>>>;
+type Cache = Rc<RefCell<HashMap<IpAddr, Rc<GeoIpCity>>>>;
 
-/// Lookup `GeoIpCity` data form an `IpAddr`.
-#[derive(Debug)]
+/// A cheaply cloneable lookup of `GeoIpCity` data form an `IpAddr`.
+#[derive(Debug, Clone)]
 pub struct GeoIpLookup {
-    reader: Option<Reader<Vec<u8>>>,
+    reader: Option<Rc<Reader<Vec<u8>>>>,
     cache: Cache,
 }
 
@@ -145,8 +150,8 @@ impl GeoIpLookup {
         let reader = maxminddb::Reader::open_readfile(path.as_ref())
             .context(format!("{}", path.as_ref().display()))?;
         Ok(Self {
-            reader: Some(reader),
-            cache: RefCell::new(HashMap::new()),
+            reader: Some(Rc::new(reader)),
+            cache: Rc::new(RefCell::new(HashMap::new())),
         })
     }
 
@@ -154,7 +159,7 @@ impl GeoIpLookup {
     pub fn empty() -> Self {
         Self {
             reader: None,
-            cache: RefCell::new(HashMap::new()),
+            cache: Rc::new(RefCell::new(HashMap::new())),
         }
     }
 
@@ -167,13 +172,196 @@ impl GeoIpLookup {
                 return Ok(Some(geo));
             }
             let city_data = reader.lookup::<City<'_>>(addr)?;
-            let geo = self
-                .cache
-                .borrow_mut()
-                .insert(addr, Rc::new(GeoIpCity::from(city_data)));
-            Ok(geo)
+            let geo = Rc::new(GeoIpCity::from(city_data));
+            self.cache.borrow_mut().insert(addr, geo.clone());
+            Ok(Some(geo))
         } else {
             Ok(None)
         }
     }
+    /// Add previously looked up `GeoIpCity` entries to the cache.
+    ///
+    /// Entries are only added if a `MaxMind` DB file is in use.
+    pub fn seed(&self, entries: impl IntoIterator<Item = (IpAddr, GeoIpCity)>) {
+        if self.reader.is_some() {
+            self.cache
+                .borrow_mut()
+                .extend(entries.into_iter().map(|(addr, geo)| (addr, Rc::new(geo))));
+        }
+    }
+
+    /// The cached `GeoIpCity` entries.
+    pub fn entries(&self) -> Vec<(IpAddr, Rc<GeoIpCity>)> {
+        self.cache
+            .borrow()
+            .iter()
+            .map(|(addr, geo)| (*addr, geo.clone()))
+            .collect()
+    }
+}
+
+/// The mean radius of the Earth, in kilometers.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// The distance light travels in optical fiber in one millisecond, in kilometers.
+///
+/// This is the speed of light in a vacuum divided by the refractive index of fiber (approximately 1.47).
+const FIBER_KM_PER_MS: f64 = 204.0;
+
+/// The multiple of the minimum round trip time above which the increase in round trip time to a hop is a detour.
+const DETOUR_FACTOR: f64 = 3.0;
+
+/// The margin, in milliseconds, above `DETOUR_FACTOR` times the minimum round trip time before the increase in round
+/// trip time to a hop is a detour, such that ordinary queueing between nearby hops is not flagged.
+const DETOUR_MARGIN_MS: f64 = 20.0;
+
+/// The great-circle distance between the locations of a hop and of the nearest preceding hop with a location, and the
+/// increase in the best round trip time between them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoDistance {
+    /// The `ttl` of the preceding hop.
+    pub from_ttl: u8,
+    /// The great-circle distance between the two locations.
+    pub distance_km: f64,
+    /// The sum of the accuracy radius of the two locations.
+    pub uncertainty_km: f64,
+    /// The increase in the best round trip time from the preceding hop.
+    pub rtt_increase_ms: f64,
+}
+
+/// Whether the increase in round trip time between two hops is consistent with the distance between them.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GeoSanity {
+    /// The round trip time increased by less than the time for light in fiber to travel the distance, such that the
+    /// location of one of the hops is likely wrong.
+    Implausible,
+    /// The round trip time increased by far more than the time for light in fiber to travel the distance, such as
+    /// due to a detour or queueing.
+    Detour,
+}
+
+impl Display for GeoSanity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Implausible => write!(f, "implausible rtt"),
+            Self::Detour => write!(f, "detour"),
+        }
+    }
+}
+
+impl GeoDistance {
+    /// The theoretical minimum round trip time for light in fiber to travel the distance.
+    pub fn min_rtt_ms(&self) -> f64 {
+        fiber_rtt_ms(self.distance_km)
+    }
+
+    /// Whether the increase in round trip time is implausibly low, or far above the minimum, for the distance.
+    ///
+    /// The accuracy radius of the locations is allowed for, such that a hop is only flagged if the increase in round
+    /// trip time is inconsistent with every pair of points within the radius of the two locations.
+    pub fn sanity(&self) -> Option<GeoSanity> {
+        let nearest_km = (self.distance_km - self.uncertainty_km).max(0_f64);
+        let furthest_km = self.distance_km + self.uncertainty_km;
+        if nearest_km > 0_f64 && self.rtt_increase_ms < fiber_rtt_ms(nearest_km) {
+            Some(GeoSanity::Implausible)
+        } else if self.rtt_increase_ms
+            > fiber_rtt_ms(furthest_km).mul_add(DETOUR_FACTOR, DETOUR_MARGIN_MS)
+        {
+            Some(GeoSanity::Detour)
+        } else {
+            None
+        }
+    }
+
+    /// Format the distance and round trip times, such as `1234km from hop 3, rtt +14.0ms (min 12.1ms)`.
+    pub fn summary(&self, rtt_format: RttFormat) -> String {
+        let increase = rtt_format.format_with_unit(self.rtt_increase_ms);
+        format!(
+            "{:.0}km from hop {}, rtt {}{increase} (min {})",
+            self.distance_km,
+            self.from_ttl,
+            if self.rtt_increase_ms < 0_f64 {
+                ""
+            } else {
+                "+"
+            },
+            rtt_format.format_with_unit(self.min_rtt_ms())
+        )
+    }
+}
+
+/// The `GeoDistance` of `hops[index]` from the nearest preceding hop which has responded and has a location, if any.
+///
+/// The location of a hop is that of the first of its addresses with known coordinates and the best round trip time of
+/// each hop is used, as being the least affected by queueing.
+pub fn geo_distance(lookup: &GeoIpLookup, hops: &[Hop], index: usize) -> Option<GeoDistance> {
+    let located = |hop: &Hop| {
+        let best = hop.best_ms()?;
+        hop.addrs()
+            .find_map(|addr| lookup.lookup(*addr).ok().flatten()?.coordinates())
+            .map(|coordinates| (best, coordinates))
+    };
+    let (best, (lat, long, radius)) = located(hops.get(index)?)?;
+    let (from_ttl, (from_best, (from_lat, from_long, from_radius))) = hops[..index]
+        .iter()
+        .rev()
+        .find_map(|hop| located(hop).map(|located| (hop.ttl(), located)))?;
+    Some(GeoDistance {
+        from_ttl,
+        distance_km: great_circle_km((from_lat, from_long), (lat, long)),
+        uncertainty_km: f64::from(from_radius) + f64::from(radius),
+        rtt_increase_ms: best - from_best,
+    })
+}
+
+/// The great-circle distance between two coordinates, given in degrees, using the haversine formula.
+fn great_circle_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (from_lat, to_lat) = (from.0.to_radians(), to.0.to_radians());
+    let delta_lat = to_lat - from_lat;
+    let delta_long = (to.1 - from.1).to_radians();
+    let a = (from_lat.cos() * to_lat.cos()).mul_add(
+        (delta_long / 2_f64).sin().powi(2),
+        (delta_lat / 2_f64).sin().powi(2),
+    );
+    2_f64 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// The round trip time for light in fiber to travel `distance_km` and back.
+fn fiber_rtt_ms(distance_km: f64) -> f64 {
+    2_f64 * distance_km / FIBER_KM_PER_MS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case((51.5074, -0.1278), (51.5074, -0.1278), 0.0; "same location")]
+    #[test_case((51.5074, -0.1278), (40.7128, -74.0060), 5570.0; "london to new york")]
+    #[test_case((-33.8688, 151.2093), (35.6762, 139.6503), 7826.0; "sydney to tokyo")]
+    #[test_case((0.0, 0.0), (0.0, 180.0), 20015.0; "antipodes")]
+    fn test_great_circle_km(from: (f64, f64), to: (f64, f64), expected: f64) {
+        assert!((great_circle_km(from, to) - expected).abs() < 1.0);
+    }
+
+    #[test_case(5570.0, 0.0, 60.0, None; "plausible")]
+    #[test_case(5570.0, 0.0, 30.0, Some(GeoSanity::Implausible); "too fast")]
+    #[test_case(5570.0, 3000.0, 30.0, None; "too fast within uncertainty")]
+    #[test_case(5570.0, 0.0, 200.0, Some(GeoSanity::Detour); "detour")]
+    #[test_case(10.0, 0.0, 15.0, None; "queueing between nearby hops")]
+    #[test_case(0.0, 0.0, -2.0, None; "same location with lower rtt")]
+    fn test_sanity(
+        distance_km: f64,
+        uncertainty_km: f64,
+        rtt_increase_ms: f64,
+        expected: Option<GeoSanity>,
+    ) {
+        let distance = GeoDistance {
+            from_ttl: 1,
+            distance_km,
+            uncertainty_km,
+            rtt_increase_ms,
+        };
+        assert_eq!(expected, distance.sanity());
+    }
 }
@@ -0,0 +1,459 @@
+use crate::backend::{format_as_path, AsPathChange, Hop, RouteChange, Trace};
+use crate::syslog::{Severity, Syslog};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::io::Write;
+use std::net::IpAddr;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// The commands to run, and the thresholds which trigger them, for trace events.
+#[derive(Debug, Clone)]
+pub struct HookConfig {
+    /// The command to run when a route change is detected.
+    pub on_route_change: Option<String>,
+    /// The command to run when an AS path change is detected.
+    pub on_as_path_change: Option<String>,
+    /// The command to run when the target breaches a loss or latency threshold.
+    pub on_threshold_breach: Option<String>,
+    /// The command to run when the target becomes unreachable.
+    pub on_target_unreachable: Option<String>,
+    /// The loss percentage of the target above which the threshold is breached.
+    pub threshold_loss_pct: Option<f64>,
+    /// The average latency of the target above which the threshold is breached.
+    pub threshold_latency: Option<Duration>,
+    /// The number of consecutive rounds without a response after which the target is considered unreachable.
+    pub unreachable_rounds: usize,
+    /// Where to send all events, and a summary of every round, as syslog messages.
+    pub syslog: Option<Arc<Syslog>>,
+}
+
+/// Runs the configured hook commands for the events of a single trace.
+///
+/// Each command is run via the platform shell with the details of the event passed in `TRIPPY_*` environment
+/// variables and as a JSON object on stdin.  Hook commands run in the background and their output is discarded.
+///
+/// If syslog is configured then every event, and a summary of every round, is also sent to syslog with the details of
+/// the event as structured data.
+#[derive(Debug)]
+pub struct Hooks {
+    config: HookConfig,
+    target: String,
+    target_addr: IpAddr,
+    state: RefCell<HookState>,
+}
+
+/// The events seen by the `Hooks` so far.
+#[derive(Debug, Default)]
+struct HookState {
+    route_changes: usize,
+    as_path_changes: usize,
+    breached: bool,
+}
+
+impl Hooks {
+    pub fn new(config: HookConfig, target: String, target_addr: IpAddr) -> Self {
+        Self {
+            config,
+            target,
+            target_addr,
+            state: RefCell::new(HookState::default()),
+        }
+    }
+
+    /// Run the hooks for any events which occurred in the latest round of the `Trace`.
+    pub fn on_round(&self, trace: &Trace) {
+        let round = trace.round().map_or(0, |round| round + 1);
+        for details in self.events(trace) {
+            let command = match details {
+                HookDetails::RouteChange { .. } => &self.config.on_route_change,
+                HookDetails::AsPathChange { .. } => &self.config.on_as_path_change,
+                HookDetails::ThresholdBreach { .. } => &self.config.on_threshold_breach,
+                HookDetails::TargetUnreachable { .. } => &self.config.on_target_unreachable,
+                HookDetails::RoundSummary { .. } => &None,
+            };
+            let event = HookEvent {
+                event: details.name(),
+                target: &self.target,
+                target_addr: self.target_addr,
+                round,
+                details,
+            };
+            if let Some(command) = command {
+                run(command, &event);
+            }
+            if let Some(syslog) = &self.config.syslog {
+                syslog.send(
+                    event.details.severity(),
+                    event.event,
+                    &event.params(),
+                    &event.message(),
+                );
+            }
+        }
+    }
+
+    /// The events which occurred in the latest round of the `Trace`, followed by a summary of the round if syslog is
+    /// configured.
+    fn events(&self, trace: &Trace) -> Vec<HookDetails> {
+        let mut state = self.state.borrow_mut();
+        let mut events = vec![];
+        let total = trace.total_route_changes();
+        if total < state.route_changes {
+            // the trace data has been cleared
+            state.route_changes = 0;
+        }
+        let new = (total - state.route_changes).min(trace.route_changes().len());
+        let changes = &trace.route_changes()[trace.route_changes().len() - new..];
+        events.extend(changes.iter().map(HookDetails::from));
+        state.route_changes = total;
+        let total = trace.total_as_path_changes();
+        if total < state.as_path_changes {
+            state.as_path_changes = 0;
+        }
+        let new = (total - state.as_path_changes).min(trace.as_path_changes().len());
+        let changes = &trace.as_path_changes()[trace.as_path_changes().len() - new..];
+        events.extend(changes.iter().map(HookDetails::from));
+        state.as_path_changes = total;
+        let target = trace.target_hop();
+        let breached = is_breached(
+            target,
+            self.config.threshold_loss_pct,
+            self.config.threshold_latency,
+        );
+        if breached && !state.breached {
+            events.push(HookDetails::ThresholdBreach {
+                loss_pct: target.loss_pct(),
+                avg_ms: target.avg_ms(),
+            });
+        }
+        state.breached = breached;
+        if trace.unreachable_rounds() == self.config.unreachable_rounds {
+            events.push(HookDetails::TargetUnreachable {
+                unreachable_rounds: trace.unreachable_rounds(),
+            });
+        }
+        if self.config.syslog.is_some() {
+            events.push(HookDetails::RoundSummary {
+                hops: trace.hops().len(),
+                loss_pct: target.loss_pct(),
+                avg_ms: target.avg_ms(),
+                last_ms: target.last_ms(),
+            });
+        }
+        events
+    }
+}
+
+/// Run a hook command in the background.
+fn run(command: &str, event: &HookEvent<'_>) {
+    debug!(command, event = event.event, "running hook");
+    if let Err(err) = spawn(command, event) {
+        warn!(command, %err, "failed to run hook");
+    }
+}
+
+/// Is the loss or average latency of a `Hop` above the given thresholds?
+fn is_breached(hop: &Hop, loss_pct: Option<f64>, latency: Option<Duration>) -> bool {
+    let loss_breached = matches!(loss_pct, Some(threshold) if hop.loss_pct() > threshold);
+    let latency_breached = matches!(latency, Some(threshold) if hop.total_recv() > 0
+        && hop.avg_ms() > threshold.as_secs_f64() * 1000_f64);
+    loss_breached || latency_breached
+}
+
+/// Spawn the hook command, write the event to its stdin and reap it in the background.
+fn spawn(command: &str, event: &HookEvent<'_>) -> anyhow::Result<()> {
+    let json = serde_json::to_string(event)?;
+    let mut child = shell(command)
+        .envs(event.env())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // the hook may not read stdin, in which case the write fails and is ignored
+        let _ = writeln!(stdin, "{json}");
+    }
+    let command = command.to_string();
+    thread::Builder::new()
+        .name(String::from("hook"))
+        .spawn(move || match child.wait() {
+            Ok(status) if !status.success() => warn!(command, %status, "hook failed"),
+            Err(err) => warn!(command, %err, "hook failed"),
+            Ok(_) => {}
+        })?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+/// A trace event passed to a hook.
+#[derive(Debug, Serialize)]
+struct HookEvent<'a> {
+    event: &'static str,
+    target: &'a str,
+    target_addr: IpAddr,
+    round: usize,
+    #[serde(flatten)]
+    details: HookDetails,
+}
+
+/// The details of a specific trace event.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum HookDetails {
+    RouteChange {
+        ttl: u8,
+        from: IpAddr,
+        to: IpAddr,
+    },
+    AsPathChange {
+        from_as_path: Vec<String>,
+        to_as_path: Vec<String>,
+    },
+    ThresholdBreach {
+        loss_pct: f64,
+        avg_ms: f64,
+    },
+    TargetUnreachable {
+        unreachable_rounds: usize,
+    },
+    RoundSummary {
+        hops: usize,
+        loss_pct: f64,
+        avg_ms: f64,
+        last_ms: Option<f64>,
+    },
+}
+
+impl HookDetails {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::RouteChange { .. } => "route-change",
+            Self::AsPathChange { .. } => "as-path-change",
+            Self::ThresholdBreach { .. } => "threshold-breach",
+            Self::TargetUnreachable { .. } => "target-unreachable",
+            Self::RoundSummary { .. } => "round-summary",
+        }
+    }
+
+    /// The syslog severity of the event.
+    fn severity(&self) -> Severity {
+        match self {
+            Self::RouteChange { .. } | Self::AsPathChange { .. } => Severity::Notice,
+            Self::ThresholdBreach { .. } => Severity::Warning,
+            Self::TargetUnreachable { .. } => Severity::Error,
+            Self::RoundSummary { .. } => Severity::Informational,
+        }
+    }
+}
+
+impl From<&RouteChange> for HookDetails {
+    fn from(change: &RouteChange) -> Self {
+        Self::RouteChange {
+            ttl: change.ttl,
+            from: change.from,
+            to: change.to,
+        }
+    }
+}
+
+impl From<&AsPathChange> for HookDetails {
+    fn from(change: &AsPathChange) -> Self {
+        Self::AsPathChange {
+            from_as_path: change.from.clone(),
+            to_as_path: change.to.clone(),
+        }
+    }
+}
+
+impl HookEvent<'_> {
+    /// The environment variables passed to the hook command.
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("TRIPPY_EVENT", self.event.to_string()),
+            ("TRIPPY_TARGET", self.target.to_string()),
+            ("TRIPPY_TARGET_ADDR", self.target_addr.to_string()),
+            ("TRIPPY_ROUND", self.round.to_string()),
+        ];
+        match &self.details {
+            HookDetails::RouteChange { ttl, from, to } => {
+                env.push(("TRIPPY_TTL", ttl.to_string()));
+                env.push(("TRIPPY_FROM", from.to_string()));
+                env.push(("TRIPPY_TO", to.to_string()));
+            }
+            HookDetails::AsPathChange {
+                from_as_path,
+                to_as_path,
+            } => {
+                env.push(("TRIPPY_FROM_AS_PATH", from_as_path.join(" ")));
+                env.push(("TRIPPY_TO_AS_PATH", to_as_path.join(" ")));
+            }
+            HookDetails::ThresholdBreach { loss_pct, avg_ms } => {
+                env.push(("TRIPPY_LOSS_PCT", format!("{loss_pct:.1}")));
+                env.push(("TRIPPY_AVG_MS", format!("{avg_ms:.1}")));
+            }
+            HookDetails::TargetUnreachable { unreachable_rounds } => {
+                env.push(("TRIPPY_UNREACHABLE_ROUNDS", unreachable_rounds.to_string()));
+            }
+            HookDetails::RoundSummary {
+                hops,
+                loss_pct,
+                avg_ms,
+                last_ms,
+            } => {
+                env.push(("TRIPPY_HOPS", hops.to_string()));
+                env.push(("TRIPPY_LOSS_PCT", format!("{loss_pct:.1}")));
+                env.push(("TRIPPY_AVG_MS", format!("{avg_ms:.1}")));
+                if let Some(last_ms) = last_ms {
+                    env.push(("TRIPPY_LAST_MS", format!("{last_ms:.1}")));
+                }
+            }
+        }
+        env
+    }
+
+    /// The syslog structured data parameters of the event, which are named as the environment variables passed to
+    /// hook commands, without the `TRIPPY_` prefix and in lowercase.
+    fn params(&self) -> Vec<(String, String)> {
+        self.env()
+            .into_iter()
+            .filter(|(name, _)| *name != "TRIPPY_EVENT")
+            .map(|(name, value)| (name.trim_start_matches("TRIPPY_").to_lowercase(), value))
+            .collect()
+    }
+
+    /// A description of the event for syslog.
+    fn message(&self) -> String {
+        let target = self.target;
+        match &self.details {
+            HookDetails::RouteChange { ttl, from, to } => {
+                format!("route to {target} changed at hop {ttl} from {from} to {to}")
+            }
+            HookDetails::AsPathChange {
+                from_as_path,
+                to_as_path,
+            } => {
+                format!(
+                    "AS path to {target} changed from {} to {}",
+                    format_as_path(from_as_path),
+                    format_as_path(to_as_path)
+                )
+            }
+            HookDetails::ThresholdBreach { loss_pct, avg_ms } => {
+                format!("{target} breached the threshold with {loss_pct:.1}% loss and {avg_ms:.1}ms average latency")
+            }
+            HookDetails::TargetUnreachable { unreachable_rounds } => {
+                format!("{target} unreachable for {unreachable_rounds} rounds")
+            }
+            HookDetails::RoundSummary {
+                hops,
+                loss_pct,
+                avg_ms,
+                last_ms,
+            } => {
+                let last = last_ms.map_or_else(
+                    || String::from("no response"),
+                    |last_ms| format!("{last_ms:.1}ms"),
+                );
+                format!(
+                    "round {} to {target}: {hops} hops, {last}, {loss_pct:.1}% loss, {avg_ms:.1}ms average latency",
+                    self.round
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_route_change_event() {
+        let event = HookEvent {
+            event: "route-change",
+            target: "example.com",
+            target_addr: IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+            round: 12,
+            details: HookDetails::RouteChange {
+                ttl: 3,
+                from: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                to: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            },
+        };
+        assert_eq!(
+            r#"{"event":"route-change","target":"example.com","target_addr":"93.184.216.34","round":12,"ttl":3,"from":"10.0.0.1","to":"10.0.0.2"}"#,
+            serde_json::to_string(&event).unwrap()
+        );
+        assert!(event.env().contains(&("TRIPPY_TTL", String::from("3"))));
+        assert!(event
+            .env()
+            .contains(&("TRIPPY_TO", String::from("10.0.0.2"))));
+    }
+
+    #[test]
+    fn test_as_path_change_event() {
+        let event = HookEvent {
+            event: "as-path-change",
+            target: "example.com",
+            target_addr: IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+            round: 7,
+            details: HookDetails::AsPathChange {
+                from_as_path: vec![String::from("64496"), String::from("64500")],
+                to_as_path: vec![String::from("64496"), String::from("64511")],
+            },
+        };
+        assert_eq!(
+            r#"{"event":"as-path-change","target":"example.com","target_addr":"93.184.216.34","round":7,"from_as_path":["64496","64500"],"to_as_path":["64496","64511"]}"#,
+            serde_json::to_string(&event).unwrap()
+        );
+        assert!(event
+            .env()
+            .contains(&("TRIPPY_TO_AS_PATH", String::from("64496 64511"))));
+        assert_eq!(
+            "AS path to example.com changed from AS64496 AS64500 to AS64496 AS64511",
+            event.message()
+        );
+    }
+
+    #[test]
+    fn test_round_summary_syslog() {
+        let event = HookEvent {
+            event: "round-summary",
+            target: "example.com",
+            target_addr: IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+            round: 3,
+            details: HookDetails::RoundSummary {
+                hops: 9,
+                loss_pct: 12.5,
+                avg_ms: 20.25,
+                last_ms: None,
+            },
+        };
+        assert_eq!(Severity::Informational, event.details.severity());
+        assert!(event
+            .params()
+            .contains(&(String::from("loss_pct"), String::from("12.5"))));
+        assert_eq!(
+            "round 3 to example.com: 9 hops, no response, 12.5% loss, 20.2ms average latency",
+            event.message()
+        );
+    }
+}
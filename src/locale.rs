@@ -0,0 +1,251 @@
+use crate::config::Locale;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::warn;
+
+/// The resource file of the messages of each `Locale`.
+///
+/// Each message is keyed by the English text of a Tui label or report header.  A message which is filled with a
+/// value, such as a count or a hop, contains a single `{...}` placeholder and a message with a count has a variant for
+/// each plural category of the `Locale`.
+const RESOURCES: [(Locale, &str); 4] = [
+    (Locale::En, include_str!("locale/en.toml")),
+    (Locale::De, include_str!("locale/de.toml")),
+    (Locale::Es, include_str!("locale/es.toml")),
+    (Locale::Fr, include_str!("locale/fr.toml")),
+];
+
+/// The messages of a `Locale`, keyed by the English text.
+type Catalogue = HashMap<String, Message>;
+
+/// A message of a `Catalogue`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Message {
+    Text(String),
+    Plural(PluralMessage),
+}
+
+/// A message with a variant for each plural category of a `Locale`.
+///
+/// The `other` variant is required and is used for the `PluralCategory::Other` category.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PluralMessage {
+    one: Option<String>,
+    many: Option<String>,
+    other: String,
+}
+
+impl PluralMessage {
+    /// The variant of the message for a `PluralCategory`.
+    fn variant(&self, category: PluralCategory) -> Option<&str> {
+        match category {
+            PluralCategory::One => self.one.as_deref(),
+            PluralCategory::Many => self.many.as_deref(),
+            PluralCategory::Other => Some(&self.other),
+        }
+    }
+}
+
+/// The plural category of a count.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum PluralCategory {
+    One,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    /// The plural category of `count` in a `Locale`, as per the CLDR plural rules for integers.
+    fn of(locale: Locale, count: usize) -> Self {
+        match locale {
+            Locale::En | Locale::De | Locale::Es if count == 1 => Self::One,
+            Locale::Fr if count <= 1 => Self::One,
+            Locale::Es | Locale::Fr if count != 0 && count.is_multiple_of(1_000_000) => Self::Many,
+            _ => Self::Other,
+        }
+    }
+
+    /// The plural categories of a `Locale`.
+    #[cfg(test)]
+    const fn categories(locale: Locale) -> &'static [Self] {
+        match locale {
+            Locale::En | Locale::De => &[Self::One, Self::Other],
+            Locale::Es | Locale::Fr => &[Self::One, Self::Many, Self::Other],
+        }
+    }
+}
+
+/// The `Catalogue` of a `Locale`, which is read from its resource file on first use.
+///
+/// A resource file which cannot be read is logged and has no `Catalogue`.
+fn catalogue(locale: Locale) -> Option<&'static Catalogue> {
+    static CATALOGUES: OnceLock<Vec<(Locale, Catalogue)>> = OnceLock::new();
+    CATALOGUES
+        .get_or_init(|| {
+            RESOURCES
+                .iter()
+                .filter_map(|(locale, resource)| match toml::from_str(resource) {
+                    Ok(catalogue) => Some((*locale, catalogue)),
+                    Err(err) => {
+                        warn!(?locale, %err, "failed to read the locale catalogue");
+                        None
+                    }
+                })
+                .collect()
+        })
+        .iter()
+        .find(|(catalogue_locale, _)| *catalogue_locale == locale)
+        .map(|(_, catalogue)| catalogue)
+}
+
+/// Lookup the message of an English text in a `Locale`, logging a message which is missing.
+fn lookup(locale: Locale, text: &str) -> Option<&'static Message> {
+    let message = catalogue(locale).and_then(|catalogue| catalogue.get(text));
+    if message.is_none() {
+        warn!(?locale, text, "missing translation");
+    }
+    message
+}
+
+/// Translate an English Tui label or report header into a `Locale`.
+///
+/// Text which is missing from the `Locale` is logged and returned untranslated.
+pub fn translate(locale: Locale, text: &'static str) -> &'static str {
+    match lookup(locale, text) {
+        Some(Message::Text(message)) => message,
+        Some(Message::Plural(message)) => &message.other,
+        None => text,
+    }
+}
+
+/// Translate an English template which contains a single `{...}` placeholder into a `Locale` and fill it with `value`.
+///
+/// The variant of a message with a count is chosen by the plural category of `value` in the `Locale`.  A template
+/// which is missing from the `Locale`, or which has no variant for the plural category, is logged and filled
+/// untranslated.
+pub fn translate_with(locale: Locale, template: &'static str, value: usize) -> String {
+    let message = match lookup(locale, template) {
+        Some(Message::Text(message)) => Some(message.as_str()),
+        Some(Message::Plural(message)) => {
+            let category = PluralCategory::of(locale, value);
+            let variant = message.variant(category);
+            if variant.is_none() {
+                warn!(?locale, template, ?category, "missing plural variant");
+            }
+            variant
+        }
+        None => None,
+    };
+    fill(message.unwrap_or(template), value)
+}
+
+/// Replace the first `{...}` placeholder of a message with `value`.
+fn fill(message: &str, value: usize) -> String {
+    message
+        .split_once('{')
+        .and_then(|(before, rest)| rest.split_once('}').map(|(_, after)| (before, after)))
+        .map_or_else(
+            || message.to_string(),
+            |(before, after)| format!("{before}{value}{after}"),
+        )
+}
+
+/// Whether an English text has a message in a `Locale`, with a variant for each plural category of the `Locale`.
+#[cfg(test)]
+pub fn has_translation(locale: Locale, text: &str) -> bool {
+    match catalogue(locale).and_then(|catalogue| catalogue.get(text)) {
+        Some(Message::Text(_)) => true,
+        Some(Message::Plural(message)) => PluralCategory::categories(locale)
+            .iter()
+            .all(|category| message.variant(*category).is_some()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+    use std::collections::HashSet;
+    use test_case::test_case;
+
+    #[test_case(Locale::En, "Loss%", "Loss%"; "english")]
+    #[test_case(Locale::De, "Loss%", "Verl%"; "german")]
+    #[test_case(Locale::Es, "Hops", "Saltos"; "spanish")]
+    #[test_case(Locale::Fr, "Target", "Cible"; "french")]
+    #[test_case(Locale::Fr, "P95", "P95"; "same text")]
+    #[test_case(Locale::De, "Unknown", "Unknown"; "missing text is untranslated")]
+    fn test_translate(locale: Locale, text: &'static str, expected: &str) {
+        assert_eq!(expected, translate(locale, text));
+    }
+
+    #[test_case(Locale::En, 1, "discovered 1 hop"; "english one")]
+    #[test_case(Locale::En, 7, "discovered 7 hops"; "english other")]
+    #[test_case(Locale::En, 0, "discovered 0 hops"; "english zero")]
+    #[test_case(Locale::De, 1, "1 Hop entdeckt"; "german one")]
+    #[test_case(Locale::De, 7, "7 Hops entdeckt"; "german other")]
+    #[test_case(Locale::Es, 1, "1 salto descubierto"; "spanish one")]
+    #[test_case(Locale::Es, 7, "7 saltos descubiertos"; "spanish other")]
+    #[test_case(Locale::Es, 1_000_000, "1000000 de saltos descubiertos"; "spanish many")]
+    #[test_case(Locale::Fr, 0, "0 saut découvert"; "french zero")]
+    #[test_case(Locale::Fr, 1, "1 saut découvert"; "french one")]
+    #[test_case(Locale::Fr, 7, "7 sauts découverts"; "french other")]
+    fn test_translate_with_count(locale: Locale, count: usize, expected: &str) {
+        assert_eq!(
+            expected,
+            translate_with(locale, "discovered { $count } hops", count)
+        );
+    }
+
+    #[test_case(Locale::En, "blocked at hop 3"; "english")]
+    #[test_case(Locale::De, "blockiert bei Hop 3"; "german")]
+    #[test_case(Locale::Es, "bloqueado en el salto 3"; "spanish")]
+    #[test_case(Locale::Fr, "bloqué au saut 3"; "french")]
+    fn test_translate_with_value(locale: Locale, expected: &str) {
+        assert_eq!(
+            expected,
+            translate_with(locale, "blocked at hop { $hop }", 3)
+        );
+    }
+
+    #[test_case("{ $count } problems", 2, "2 problems"; "leading")]
+    #[test_case("at hop { $hop }", 2, "at hop 2"; "trailing")]
+    #[test_case("no placeholder", 2, "no placeholder"; "none")]
+    fn test_fill(message: &str, value: usize, expected: &str) {
+        assert_eq!(expected, fill(message, value));
+    }
+
+    #[test_case(Locale::En, &[(0, PluralCategory::Other), (1, PluralCategory::One), (2, PluralCategory::Other), (1_000_000, PluralCategory::Other)]; "english")]
+    #[test_case(Locale::De, &[(0, PluralCategory::Other), (1, PluralCategory::One), (2, PluralCategory::Other), (1_000_000, PluralCategory::Other)]; "german")]
+    #[test_case(Locale::Es, &[(0, PluralCategory::Other), (1, PluralCategory::One), (2, PluralCategory::Other), (1_000_000, PluralCategory::Many)]; "spanish")]
+    #[test_case(Locale::Fr, &[(0, PluralCategory::One), (1, PluralCategory::One), (2, PluralCategory::Other), (1_000_000, PluralCategory::Many)]; "french")]
+    fn test_plural_category(locale: Locale, expected: &[(usize, PluralCategory)]) {
+        for (count, category) in expected {
+            assert_eq!(*category, PluralCategory::of(locale, *count), "{count}");
+            assert!(PluralCategory::categories(locale).contains(category));
+        }
+    }
+
+    #[test]
+    fn test_catalogues_are_complete() {
+        for (locale, _) in RESOURCES {
+            assert!(catalogue(locale).is_some(), "{locale:?}");
+        }
+        let keys = |locale| {
+            catalogue(locale)
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect::<HashSet<_>>()
+        };
+        for locale in Locale::value_variants() {
+            assert_eq!(keys(Locale::En), keys(*locale), "{locale:?}");
+            for key in keys(*locale) {
+                assert!(has_translation(*locale, key), "{locale:?} {key}");
+            }
+        }
+    }
+}
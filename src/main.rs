@@ -12,63 +12,255 @@
     clippy::option_option
 )]
 #![deny(unsafe_code)]
-use crate::backend::Trace;
-use crate::caps::{drop_caps, ensure_caps};
-use crate::config::{Mode, TrippyConfig};
-use crate::dns::{DnsResolver, DnsResolverConfig};
+use crate::agent::{AgentServer, RemoteAgent, RoundPublisher};
+use crate::annotations::Annotations;
+use crate::backend::{ReResolveConfig, Trace};
+use crate::cache::LookupCache;
+use crate::caps::{drop_caps, drop_privileges, ensure_caps};
+use crate::config::{Dscp, EcnCodepoint, Mode, RttFormat, TraceTarget, TrippyConfig};
+use crate::dns::{DnsHosts, DnsResolver, DnsResolverConfig};
 use crate::frontend::TuiConfig;
 use crate::geoip::GeoIpLookup;
-use anyhow::{anyhow, Error};
+use crate::hooks::{HookConfig, Hooks};
+use crate::mqtt::MqttConfig;
+use crate::problems::ProblemLog;
+use crate::report::{ReportSchedule, TableStyle, TargetUnreachable};
+use crate::session::Session;
+use crate::syslog::Syslog;
+use crate::template::Template;
+use anyhow::{anyhow, Context, Error};
 use clap::Parser;
 use config::Args;
 use parking_lot::RwLock;
-use std::net::IpAddr;
-use std::sync::Arc;
+use std::fs::File;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use std::process;
+use std::sync::{mpsc, Arc, Barrier, Mutex};
 use std::thread;
-use std::time::Duration;
-use trippy::tracing::SourceAddr;
+use std::time::{Duration, Instant};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+use trippy::tracing::{Egress, SharedReceiver, SharedSenders, SourceAddr, PACKET_DUMP_TARGET};
 use trippy::tracing::{
-    MultipathStrategy, PortDirection, TracerAddrFamily, TracerChannelConfig, TracerConfig,
+    Ipv6ExtHeader, MultipathStrategy, PortDirection, PrivilegeMode, ProhibitedStrategy,
+    SequenceStrategy, TcpOptions, TracerAddrFamily, TracerChannelConfig, TracerConfig,
     TracerProtocol,
 };
 
+mod agent;
+mod annotations;
+mod api;
 mod backend;
+mod cache;
 mod caps;
 mod config;
 mod dns;
 mod frontend;
 mod geoip;
+mod hooks;
+mod locale;
+mod mqtt;
+mod otlp;
+mod problems;
 mod report;
+mod server;
+mod session;
+mod statsd;
+mod syslog;
+mod template;
+mod web;
 
+/// The process exit code used when tracing stopped because the target was unreachable.
+const EXIT_CODE_TARGET_UNREACHABLE: i32 = 3;
+
+#[allow(clippy::too_many_lines)]
 fn main() -> anyhow::Result<()> {
     let pid = u16::try_from(std::process::id() % u32::from(u16::MAX))?;
-    let cfg = TrippyConfig::try_from((Args::parse(), pid))?;
-    let resolver = start_dns_resolver(&cfg)?;
+    let cfg = Arc::new(TrippyConfig::try_from((Args::parse(), pid))?);
+    let problems = init_logging(&cfg)?;
+    let dns_config = make_dns_resolver_config(&cfg);
+    let dns_hosts = create_dns_hosts(&cfg)?;
+    let resolver = DnsResolver::start(dns_config, dns_hosts.clone())?;
     let geoip_lookup = create_geoip_lookup(&cfg)?;
-    ensure_caps()?;
-    let traces: Vec<_> = cfg
+    let cache = cfg.cache_file.as_ref().map(|path| {
+        LookupCache::load(
+            path,
+            cfg.cache_ttl,
+            cfg.dns_lookup_as_info,
+            &resolver,
+            &geoip_lookup,
+        )
+    });
+    let annotations = Arc::new(create_annotations(&cfg)?);
+    let privilege_mode = ensure_caps(privileged_protocol(&cfg), cfg.addr_family)?;
+    if cfg.dual_stack {
+        ensure_caps(privileged_protocol(&cfg), TracerAddrFamily::Ipv6)?;
+    }
+    let agent = match (cfg.agent, &cfg.agent_token) {
+        (Some(addr), Some(token)) => Some(AgentServer::bind(addr, token.clone())?),
+        _ => None,
+    };
+    let syslog = cfg
+        .syslog
+        .clone()
+        .map(|target| Syslog::connect(target, cfg.syslog_facility))
+        .transpose()?
+        .map(Arc::new);
+    let start = Arc::new(Barrier::new(cfg.targets.len() + 1));
+    let mut shared_sockets = SharedSockets::default();
+    let mut traces: Vec<_> = cfg
         .targets
         .iter()
         .enumerate()
-        .map(|(i, target_host)| start_tracer(&cfg, target_host, pid + i as u16, &resolver))
+        .map(|(i, target)| {
+            start_tracer(
+                &cfg,
+                target,
+                cfg.icmp_identifier.wrapping_add(i as u16),
+                &resolver,
+                &dns_hosts,
+                &annotations,
+                privilege_mode,
+                start.clone(),
+                agent.as_ref().map(|agent| agent.publisher(i)),
+                syslog.clone(),
+                &mut shared_sockets,
+            )
+        })
         .collect::<anyhow::Result<Vec<_>>>()?;
+    if let (Some(addr), Some(token)) = (&cfg.remote, &cfg.agent_token) {
+        traces.extend(start_remote(&cfg, addr, token, &annotations)?);
+    }
+    if let Some(addr) = cfg.api {
+        let starter = make_trace_starter(
+            &cfg,
+            pid,
+            &dns_hosts,
+            &annotations,
+            privilege_mode,
+            syslog,
+            shared_sockets,
+        )?;
+        api::start_api(addr, traces.clone(), starter, dns_config, dns_hosts.clone())?;
+    }
+    if let Some(endpoint) = cfg.otlp_endpoint.clone() {
+        otlp::start_otlp_exporter(endpoint, cfg.otlp_interval, traces.clone())?;
+    }
+    if let Some(addr) = cfg.statsd_addr.as_deref() {
+        statsd::start_statsd_exporter(
+            addr,
+            cfg.statsd_flavor,
+            cfg.statsd_prefix.clone(),
+            traces.clone(),
+        )?;
+    }
+    if let Some(broker) = cfg.mqtt_broker.clone() {
+        let config = MqttConfig {
+            broker,
+            topic: cfg.mqtt_topic.clone(),
+            username: cfg.mqtt_username.clone(),
+            password: cfg.mqtt_password.clone(),
+            client_id: format!("trippy-{pid}"),
+        };
+        mqtt::start_mqtt_publisher(config, traces.clone(), dns_config, dns_hosts)?;
+    }
+    drop_privileges(cfg.uid)?;
+    start.wait();
     drop_caps()?;
-    run_frontend(&cfg, resolver, geoip_lookup, traces)?;
-    Ok(())
+    let res = run_frontend(
+        &cfg,
+        resolver.clone(),
+        geoip_lookup.clone(),
+        &traces,
+        agent.as_ref(),
+        problems,
+    );
+    if let Some(cache) = cache {
+        cache.save(&resolver, &geoip_lookup)?;
+    }
+    match res {
+        Err(err) if err.is::<TargetUnreachable>() => {
+            eprintln!("{err}");
+            process::exit(EXIT_CODE_TARGET_UNREACHABLE);
+        }
+        res => res,
+    }
+}
+
+/// Initialize logging to the log file if given, or to stderr for the web dashboard, the agent and all modes other than
+/// the Tui.
+///
+/// Only events from Trippy itself are logged, events from dependencies are ignored.
+///
+/// Warning and error events are always recorded in the returned `ProblemLog`, such that they can be shown in the Tui.
+fn init_logging(cfg: &TrippyConfig) -> anyhow::Result<ProblemLog> {
+    let level = match cfg.verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    let packet_dump_level = if cfg.packet_dump {
+        LevelFilter::TRACE
+    } else {
+        LevelFilter::OFF
+    };
+    let targets = Targets::new()
+        .with_target("trippy", level)
+        .with_target("trip", level)
+        .with_target(PACKET_DUMP_TARGET, packet_dump_level);
+    let problems = ProblemLog::default();
+    let problem_targets = Targets::new()
+        .with_target("trippy", LevelFilter::WARN)
+        .with_target("trip", LevelFilter::WARN);
+    let registry =
+        tracing_subscriber::registry().with(problems.layer().with_filter(problem_targets));
+    if let Some(path) = &cfg.log_file {
+        let file =
+            File::create(path).with_context(|| format!("failed to create log file: {path}"))?;
+        let layer = tracing_subscriber::fmt::layer().with_writer(Mutex::new(file));
+        registry.with(layer.with_filter(targets)).init();
+    } else if !matches!(cfg.mode, Mode::Tui) || cfg.web.is_some() || cfg.agent.is_some() {
+        let layer = tracing_subscriber::fmt::layer().with_writer(io::stderr);
+        registry.with(layer.with_filter(targets)).init();
+    } else {
+        registry.init();
+    }
+    Ok(problems)
 }
 
 /// Start the DNS resolver.
-fn start_dns_resolver(cfg: &TrippyConfig) -> anyhow::Result<DnsResolver> {
-    Ok(match cfg.addr_family {
-        TracerAddrFamily::Ipv4 => DnsResolver::start(DnsResolverConfig::new_ipv4(
+fn make_dns_resolver_config(cfg: &TrippyConfig) -> DnsResolverConfig {
+    let config = match cfg.addr_family {
+        _ if cfg.dual_stack => DnsResolverConfig::new_dual_stack(
             cfg.dns_resolve_method,
             cfg.dns_timeout,
-        ))?,
-        TracerAddrFamily::Ipv6 => DnsResolver::start(DnsResolverConfig::new_ipv6(
+            cfg.dns_max_in_flight,
+        ),
+        TracerAddrFamily::Ipv4 => DnsResolverConfig::new_ipv4(
             cfg.dns_resolve_method,
             cfg.dns_timeout,
-        ))?,
-    })
+            cfg.dns_max_in_flight,
+        ),
+        TracerAddrFamily::Ipv6 => DnsResolverConfig::new_ipv6(
+            cfg.dns_resolve_method,
+            cfg.dns_timeout,
+            cfg.dns_max_in_flight,
+        ),
+    };
+    config.with_bind_addrs(&cfg.dns_source_addrs)
+}
+
+fn create_dns_hosts(cfg: &TrippyConfig) -> anyhow::Result<DnsHosts> {
+    if let Some(path) = cfg.dns_hosts_file.as_ref() {
+        DnsHosts::from_file(path)
+    } else {
+        Ok(DnsHosts::empty())
+    }
 }
 
 fn create_geoip_lookup(cfg: &TrippyConfig) -> anyhow::Result<GeoIpLookup> {
@@ -79,69 +271,359 @@ fn create_geoip_lookup(cfg: &TrippyConfig) -> anyhow::Result<GeoIpLookup> {
     }
 }
 
+fn create_annotations(cfg: &TrippyConfig) -> anyhow::Result<Annotations> {
+    if let Some(path) = cfg.annotations_file.as_ref() {
+        Annotations::from_file(path)
+    } else {
+        Ok(Annotations::empty())
+    }
+}
+
+/// The protocol which determines the privileges required, which is any protocol other than `icmp` used by a target.
+fn privileged_protocol(cfg: &TrippyConfig) -> TracerProtocol {
+    cfg.targets
+        .iter()
+        .map(|target| target.protocol)
+        .find(|protocol| !matches!(protocol, TracerProtocol::Icmp))
+        .unwrap_or(TracerProtocol::Icmp)
+}
+
 /// Start a tracer to a given target.
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
 fn start_tracer(
     cfg: &TrippyConfig,
-    target_host: &str,
+    target: &TraceTarget,
     trace_identifier: u16,
     resolver: &DnsResolver,
+    dns_hosts: &DnsHosts,
+    annotations: &Arc<Annotations>,
+    privilege_mode: PrivilegeMode,
+    start: Arc<Barrier>,
+    publisher: Option<RoundPublisher>,
+    syslog: Option<Arc<Syslog>>,
+    shared_sockets: &mut SharedSockets,
 ) -> Result<TraceInfo, Error> {
+    let target_host = target.hostname.as_str();
+    let lookup_start = Instant::now();
     let target_addr: IpAddr = resolver
         .lookup(target_host)
         .map_err(|e| anyhow!("failed to resolve target: {} ({})", target_host, e))?
         .into_iter()
         .find(|addr| {
             matches!(
-                (cfg.addr_family, addr),
+                (target.addr_family, addr),
                 (TracerAddrFamily::Ipv4, IpAddr::V4(_)) | (TracerAddrFamily::Ipv6, IpAddr::V6(_))
             )
         })
         .ok_or_else(|| {
             anyhow!(
                 "failed to find an {:?} address for target: {}",
-                cfg.addr_family,
+                target.addr_family,
                 target_host
             )
         })?;
+    let target_lookup_duration = lookup_start.elapsed();
     let source_addr = match cfg.source_addr {
-        None => SourceAddr::discover(target_addr, cfg.port_direction, cfg.interface.as_deref())?,
+        None => SourceAddr::discover(
+            target_addr,
+            target.port_direction,
+            cfg.interface.as_deref(),
+            cfg.ipv6_source_preference,
+        )?,
         Some(addr) => SourceAddr::validate(addr)?,
     };
-    let trace_data = Arc::new(RwLock::new(Trace::new(cfg.tui_max_samples)));
-    let channel_config = make_channel_config(cfg, source_addr, target_addr);
-    let tracer_config = make_tracer_config(cfg, target_addr, trace_identifier)?;
+    let egress = Egress::discover(source_addr, target_addr).unwrap_or_else(|err| {
+        tracing::debug!(%err, "failed to discover egress interface");
+        Egress::default()
+    });
+    let trace_data = Arc::new(RwLock::new(Trace::new(
+        cfg.tui_max_samples,
+        cfg.destination_only,
+        cfg.backfill_late,
+        cfg.report_include_samples,
+    )));
+    let channel_config = make_channel_config(
+        cfg,
+        target,
+        source_addr,
+        target_addr,
+        privilege_mode,
+        shared_sockets,
+    )?;
+    let tracer_config = make_tracer_config(cfg, target, target_addr, trace_identifier)?;
+    let reset_interval = cfg.reset_interval;
+    let unreachable_backoff = cfg.unreachable_backoff;
+    let as_lookup = cfg
+        .dns_lookup_as_info
+        .then(|| (make_dns_resolver_config(cfg), dns_hosts.clone()));
+    let re_resolve = cfg
+        .re_resolve_every
+        .filter(|_| target_host.parse::<IpAddr>().is_err())
+        .map(|every| ReResolveConfig {
+            hostname: target_host.to_string(),
+            every,
+            strategy: cfg.re_resolve_strategy,
+            resolver_config: make_dns_resolver_config(cfg),
+            hosts: dns_hosts.clone(),
+        });
+    let hooks = Hooks::new(
+        make_hook_config(cfg, syslog),
+        target_host.to_string(),
+        target_addr,
+    );
+    let (connected_tx, connected_rx) = mpsc::sync_channel(1);
     {
         let trace_data = trace_data.clone();
         thread::Builder::new()
             .name(format!("tracer-{}", tracer_config.trace_identifier.0))
             .spawn(move || {
-                backend::run_backend(&tracer_config, &channel_config, trace_data)
-                    .expect("failed to run tracer backend");
+                backend::run_backend(
+                    &tracer_config,
+                    &channel_config,
+                    trace_data,
+                    &connected_tx,
+                    &start,
+                    hooks,
+                    reset_interval,
+                    publisher,
+                    as_lookup,
+                    unreachable_backoff,
+                    re_resolve,
+                )
+                .expect("failed to run tracer backend");
             })?;
     }
+    connected_rx
+        .recv()
+        .context("tracer backend exited before connecting")??;
     Ok(make_trace_info(
         cfg,
+        target,
         trace_data,
         source_addr,
-        target_host.to_string(),
+        egress,
         target_addr,
+        target_lookup_duration,
+        annotations.clone(),
     ))
 }
 
+/// Connect to a remote agent and start receiving its traces.
+fn start_remote(
+    cfg: &TrippyConfig,
+    addr: &str,
+    token: &str,
+    annotations: &Arc<Annotations>,
+) -> anyhow::Result<Vec<TraceInfo>> {
+    let remote = RemoteAgent::connect(addr, token)?;
+    let (traces, trace_data): (Vec<_>, Vec<_>) = remote
+        .traces()
+        .iter()
+        .map(|trace| {
+            let trace_data = Arc::new(RwLock::new(Trace::new(
+                cfg.tui_max_samples,
+                trace.destination_only,
+                cfg.backfill_late,
+                cfg.report_include_samples,
+            )));
+            let target = TraceTarget {
+                hostname: format!("{} via {addr}", trace.hostname),
+                protocol: trace.protocol,
+                addr_family: if trace.target_addr.is_ipv4() {
+                    TracerAddrFamily::Ipv4
+                } else {
+                    TracerAddrFamily::Ipv6
+                },
+                port_direction: PortDirection::None,
+                ipv6_ext_header: None,
+                packet_size: cfg.packet_size,
+                group: None,
+            };
+            let info = make_trace_info(
+                cfg,
+                &target,
+                trace_data.clone(),
+                trace.source_addr,
+                Egress::default(),
+                trace.target_addr,
+                Duration::default(),
+                annotations.clone(),
+            );
+            (info, trace_data)
+        })
+        .unzip();
+    remote.start(trace_data)?;
+    Ok(traces)
+}
+
 /// Run the TUI, stream or report.
+#[allow(clippy::too_many_lines)]
 fn run_frontend(
     args: &TrippyConfig,
     resolver: DnsResolver,
     geoip_lookup: GeoIpLookup,
-    traces: Vec<TraceInfo>,
+    traces: &[TraceInfo],
+    agent: Option<&AgentServer>,
+    problems: ProblemLog,
 ) -> anyhow::Result<()> {
+    let report_file = args.report_file.as_deref().map(Path::new);
+    let schedule = args.repeat_every.map(|every| ReportSchedule {
+        every,
+        count: args.repeat_count,
+    });
+    let rtt_format = RttFormat::new(args.rtt_unit, args.rtt_precision);
+    let started = Instant::now();
     match args.mode {
-        Mode::Tui => frontend::run_frontend(traces, make_tui_config(args), resolver, geoip_lookup)?,
-        Mode::Stream => report::run_report_stream(&traces[0])?,
-        Mode::Csv => report::run_report_csv(&traces[0], args.report_cycles, &resolver)?,
-        Mode::Json => report::run_report_json(&traces[0], args.report_cycles, &resolver)?,
-        Mode::Pretty => report::run_report_table_pretty(&traces[0], args.report_cycles, &resolver)?,
-        Mode::Markdown => report::run_report_table_md(&traces[0], args.report_cycles, &resolver)?,
+        Mode::Tui => {
+            if let Some(addr) = args.web {
+                web::run_web(addr, traces, &resolver)?;
+            } else if let Some(agent) = agent {
+                agent::run_agent(agent, traces)?;
+            } else {
+                let view = match &args.state_file {
+                    Some(path) if args.resume => Some(Session::load(path)?.restore(traces)),
+                    _ => None,
+                };
+                frontend::run_frontend(
+                    traces.to_vec(),
+                    make_tui_config(args),
+                    resolver,
+                    geoip_lookup,
+                    view,
+                    problems,
+                )?;
+                if args.exit_summary {
+                    report::print_summary(traces, started.elapsed(), rtt_format);
+                }
+            }
+        }
+        Mode::Stream => {
+            let interrupted = args.exit_summary.then(report::interrupt_flag).transpose()?;
+            report::run_report_stream(
+                &traces[0],
+                args.report_timestamp,
+                &args.report_timestamp_format,
+                rtt_format,
+                args.plain,
+                interrupted.as_deref(),
+            )?;
+            if args.exit_summary {
+                report::print_summary(&traces[..1], started.elapsed(), rtt_format);
+            }
+        }
+        Mode::Csv => {
+            report::run_report_csv(
+                &traces[0],
+                args.report_cycles,
+                report_file,
+                schedule,
+                &resolver,
+                rtt_format,
+                &args.report_columns,
+                args.report_per_address,
+            )?;
+        }
+        Mode::CsvStream => {
+            report::run_report_csv_stream(&traces[0], &resolver, rtt_format, &args.report_columns)?;
+        }
+        Mode::Json => {
+            report::run_report_json(
+                &traces[0],
+                args.report_cycles,
+                report_file,
+                schedule,
+                &resolver,
+                &geoip_lookup,
+            )?;
+        }
+        Mode::Template => {
+            let path = args.template.as_deref().unwrap_or_default();
+            let template = Template::parse(&std::fs::read_to_string(path)?)?;
+            report::run_report_template(
+                &traces[0],
+                args.report_cycles,
+                report_file,
+                schedule,
+                &resolver,
+                &geoip_lookup,
+                &template,
+            )?;
+        }
+        Mode::Pretty => {
+            report::run_report_table_pretty(
+                &traces[0],
+                args.report_cycles,
+                report_file,
+                schedule,
+                &resolver,
+                &geoip_lookup,
+                rtt_format,
+                &args.report_columns,
+                args.report_per_address,
+                TableStyle::pretty(args.report_border, args.report_width, args.report_overflow),
+                args.locale,
+            )?;
+        }
+        Mode::Markdown => {
+            report::run_report_table_md(
+                &traces[0],
+                args.report_cycles,
+                report_file,
+                schedule,
+                &resolver,
+                &geoip_lookup,
+                rtt_format,
+                &args.report_columns,
+                args.report_per_address,
+                args.locale,
+            )?;
+        }
+        Mode::Firewalk => {
+            report::run_report_firewalk(
+                traces,
+                args.report_cycles,
+                report_file,
+                args.first_ttl - 1,
+                args.tui_ascii,
+            )?;
+        }
+        Mode::ExtHeader => {
+            report::run_report_ext_header(
+                traces,
+                args.report_cycles,
+                report_file,
+                &resolver,
+                args.tui_ascii,
+            )?;
+        }
+        Mode::Qos => {
+            report::run_report_qos(
+                traces,
+                args.report_cycles,
+                report_file,
+                &resolver,
+                args.tui_ascii,
+            )?;
+        }
+        Mode::SizeSweep => {
+            report::run_report_size_sweep(
+                traces,
+                args.sweep_sizes.len(),
+                args.report_cycles,
+                report_file,
+                &resolver,
+                args.tui_ascii,
+            )?;
+        }
+        Mode::Ports => {
+            report::run_report_ports(
+                traces,
+                args.report_cycles,
+                report_file,
+                &resolver,
+                args.tui_ascii,
+            )?;
+        }
     }
     Ok(())
 }
@@ -149,77 +631,263 @@ fn run_frontend(
 /// Make the tracer configuration.
 fn make_tracer_config(
     args: &TrippyConfig,
+    target: &TraceTarget,
     target_addr: IpAddr,
     trace_identifier: u16,
 ) -> anyhow::Result<TracerConfig> {
     Ok(TracerConfig::new(
         target_addr,
-        args.protocol,
+        target.protocol,
         args.max_rounds,
         trace_identifier,
         args.first_ttl,
         args.max_ttl,
         args.grace_duration,
         args.max_inflight,
+        args.adaptive_inflight,
         args.initial_sequence,
+        args.sequence_strategy,
         args.multipath_strategy,
-        args.port_direction,
+        target.port_direction,
         args.read_timeout,
         args.min_round_duration,
         args.max_round_duration,
-        args.packet_size,
+        args.round_jitter,
+        args.probe_timeout,
+        args.retries,
+        args.burst,
+        target.packet_size,
         args.payload_pattern,
+        args.destination_only,
+        args.adaptive_ttl,
+        args.prohibited_strategy,
     )?)
 }
 
 /// Make the tracer configuration.
+///
+/// Privileged channels share a single receive loop for each address family (or source address on Windows) and send
+/// on the `shared_sockets` senders, if any, of their address family.
 fn make_channel_config(
     args: &TrippyConfig,
+    target: &TraceTarget,
     source_addr: IpAddr,
     target_addr: IpAddr,
-) -> TracerChannelConfig {
-    TracerChannelConfig::new(
-        args.protocol,
-        args.addr_family,
+    privilege_mode: PrivilegeMode,
+    shared_sockets: &mut SharedSockets,
+) -> anyhow::Result<TracerChannelConfig> {
+    let channel_config = TracerChannelConfig::new(
+        target.protocol,
+        target.addr_family,
         source_addr,
         target_addr,
-        args.packet_size,
+        target.packet_size,
         args.payload_pattern,
         args.tos,
         args.read_timeout,
         args.min_round_duration,
-    )
+        TcpOptions::new(args.tcp_mss, args.tcp_window_size),
+        target.ipv6_ext_header,
+        args.record_route,
+        args.icmp_timestamp,
+        args.do_not_fragment,
+        privilege_mode,
+    );
+    Ok(match privilege_mode {
+        PrivilegeMode::Privileged => {
+            let channel_config =
+                channel_config.with_shared_receiver(shared_sockets.receiver(source_addr)?);
+            match shared_sockets.senders(target.addr_family) {
+                Some(senders) => channel_config.with_shared_senders(senders),
+                None => channel_config,
+            }
+        }
+        PrivilegeMode::Unprivileged => channel_config,
+    })
+}
+
+/// The raw sockets shared by the channels of the tracers, which must be created before privileges are dropped.
+#[derive(Default)]
+struct SharedSockets {
+    receivers: Vec<SharedReceiver>,
+    senders: Vec<SharedSenders>,
+}
+
+impl SharedSockets {
+    /// The receive loop for a channel with the `source_addr`, which is started if there is none.
+    fn receiver(&mut self, source_addr: IpAddr) -> anyhow::Result<SharedReceiver> {
+        if let Some(receiver) = self
+            .receivers
+            .iter()
+            .find(|receiver| receiver.accepts(source_addr))
+        {
+            return Ok(receiver.clone());
+        }
+        let receiver = SharedReceiver::start(source_addr)?;
+        self.receivers.push(receiver.clone());
+        Ok(receiver)
+    }
+
+    /// The senders for a channel of the `addr_family`, if created.
+    fn senders(&self, addr_family: TracerAddrFamily) -> Option<SharedSenders> {
+        self.senders
+            .iter()
+            .find(|senders| senders.addr_family() == addr_family)
+            .cloned()
+    }
+
+    /// Create the receive loop and the senders of the `addr_family`, such that channels of the address family may be
+    /// connected once privileges have been dropped.
+    fn create(&mut self, addr_family: TracerAddrFamily) -> anyhow::Result<()> {
+        let unspecified = match addr_family {
+            TracerAddrFamily::Ipv4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            TracerAddrFamily::Ipv6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
+        self.receiver(unspecified)?;
+        if self.senders(addr_family).is_none() {
+            self.senders.push(SharedSenders::new(addr_family)?);
+        }
+        Ok(())
+    }
+}
+
+/// Make the `TraceStarter` with which the API starts the traces added to it.
+///
+/// The traces added use the options, address family and packet size of the first target, and the protocol of the
+/// first target unless another is requested.  Privileges are dropped once Trippy has started, other than on Windows,
+/// and so the privileged raw sockets of these traces are created now and shared by every trace added.
+#[allow(clippy::too_many_arguments)]
+fn make_trace_starter(
+    cfg: &Arc<TrippyConfig>,
+    pid: u16,
+    dns_hosts: &DnsHosts,
+    annotations: &Arc<Annotations>,
+    privilege_mode: PrivilegeMode,
+    syslog: Option<Arc<Syslog>>,
+    mut shared_sockets: SharedSockets,
+) -> anyhow::Result<api::TraceStarter> {
+    let template = cfg.targets[0].clone();
+    if cfg!(unix) && matches!(privilege_mode, PrivilegeMode::Privileged) {
+        shared_sockets.create(template.addr_family)?;
+    }
+    let cfg = cfg.clone();
+    let dns_hosts = dns_hosts.clone();
+    let annotations = annotations.clone();
+    Ok(Box::new(move |new_trace, id, resolver| {
+        let protocol = new_trace.protocol.unwrap_or(template.protocol);
+        let target = TraceTarget {
+            hostname: new_trace.target.clone(),
+            protocol,
+            port_direction: api_port_direction(&template, protocol, pid, id),
+            group: None,
+            ..template.clone()
+        };
+        start_tracer(
+            &cfg,
+            &target,
+            cfg.icmp_identifier.wrapping_add(id as u16),
+            resolver,
+            &dns_hosts,
+            &annotations,
+            privilege_mode,
+            Arc::new(Barrier::new(1)),
+            None,
+            syslog.clone(),
+            &mut shared_sockets,
+        )
+    }))
+}
+
+/// The ports of a trace, with the given id, added via the API.
+///
+/// A `tcp` trace uses the target port of the first target, if it is also `tcp`, and a `udp` trace uses a source port
+/// unique to the trace, as for the targets given when Trippy starts.
+fn api_port_direction(
+    template: &TraceTarget,
+    protocol: TracerProtocol,
+    pid: u16,
+    id: usize,
+) -> PortDirection {
+    match (protocol, template.protocol, template.port_direction) {
+        (TracerProtocol::Icmp, _, _) => PortDirection::None,
+        (TracerProtocol::Tcp, TracerProtocol::Tcp, PortDirection::FixedDest(port)) => {
+            PortDirection::FixedDest(port)
+        }
+        (TracerProtocol::Tcp, _, _) => PortDirection::new_fixed_dest(80),
+        (TracerProtocol::Udp, _, _) => {
+            PortDirection::new_fixed_src(pid.max(1024).wrapping_add(id as u16))
+        }
+    }
+}
+
+/// Make the hook configuration.
+fn make_hook_config(args: &TrippyConfig, syslog: Option<Arc<Syslog>>) -> HookConfig {
+    HookConfig {
+        on_route_change: args.on_route_change.clone(),
+        on_as_path_change: args.on_as_path_change.clone(),
+        on_threshold_breach: args.on_threshold_breach.clone(),
+        on_target_unreachable: args.on_target_unreachable.clone(),
+        threshold_loss_pct: args.threshold_loss_pct,
+        threshold_latency: args.threshold_latency,
+        unreachable_rounds: args.max_unreachable_rounds.unwrap_or(1),
+        syslog,
+    }
 }
 
 /// Make the per-trace information.
+#[allow(clippy::too_many_arguments)]
 fn make_trace_info(
     args: &TrippyConfig,
+    target: &TraceTarget,
     trace_data: Arc<RwLock<Trace>>,
     source_addr: IpAddr,
-    target: String,
+    egress: Egress,
     target_addr: IpAddr,
+    target_lookup_duration: Duration,
+    annotations: Arc<Annotations>,
 ) -> TraceInfo {
     TraceInfo::new(
         trace_data,
         source_addr,
-        target,
+        egress,
+        target.hostname.clone(),
+        target.group.clone(),
         target_addr,
+        target_lookup_duration,
         args.multipath_strategy,
-        args.port_direction,
-        args.protocol,
-        args.addr_family,
+        target.port_direction,
+        target.protocol,
+        target.addr_family,
+        target.ipv6_ext_header,
+        args.record_route,
+        args.icmp_timestamp,
         args.first_ttl,
         args.max_ttl,
+        args.destination_only,
+        args.adaptive_ttl,
+        args.prohibited_strategy,
         args.grace_duration,
         args.min_round_duration,
         args.max_round_duration,
+        args.round_jitter,
+        args.probe_timeout,
+        args.retries,
+        args.backfill_late,
         args.max_inflight,
+        args.adaptive_inflight,
+        args.burst,
         args.initial_sequence,
+        args.sequence_strategy,
         args.read_timeout,
-        args.packet_size,
+        target.packet_size,
         args.payload_pattern,
+        args.ecn,
+        Some(Dscp::from_tos(args.tos)).filter(|dscp| dscp.0 != 0),
+        args.max_duration,
+        args.max_unreachable_rounds,
         args.interface.clone(),
         args.geoip_mmdb_file.clone(),
+        annotations,
     )
 }
 
@@ -228,88 +896,186 @@ fn make_tui_config(args: &TrippyConfig) -> TuiConfig {
     TuiConfig::new(
         args.tui_refresh_rate,
         args.tui_preserve_screen,
+        args.tui_title,
+        args.tui_seen_columns,
+        args.tui_ascii,
+        args.plain,
+        args.tui_palette,
+        RttFormat::new(args.rtt_unit, args.rtt_precision),
+        args.locale,
         args.tui_address_mode,
         args.dns_lookup_as_info,
         args.tui_as_mode,
         args.tui_geoip_mode,
         args.tui_max_addrs,
         args.tui_max_samples,
+        args.tui_hide_hops.clone(),
+        args.tui_panes.clone(),
+        args.tui_pane_split,
         args.tui_theme,
         &args.tui_bindings,
+        args.max_duration,
+        args.state_file.clone(),
+        args.tui_export_format,
+        args.tui_export_dir.clone(),
+        args.report_columns.clone(),
+        args.report_per_address,
     )
 }
 
+/// The estimated size of an `IPv4` `TCP` `SYN` probe, including the `TCP` options typically added by the OS.
+const TCP_SYN_SIZE_IPV4: u16 = 60;
+
+/// The estimated size of an `IPv6` `TCP` `SYN` probe, including the `TCP` options typically added by the OS.
+const TCP_SYN_SIZE_IPV6: u16 = 80;
+
 /// Information about a `Trace` needed for the Tui, stream and reports.
 #[derive(Debug, Clone)]
 pub struct TraceInfo {
     pub data: Arc<RwLock<Trace>>,
     pub source_addr: IpAddr,
+    pub egress: Egress,
     pub target_hostname: String,
+    pub group: Option<String>,
     pub target_addr: IpAddr,
+    pub target_lookup_duration: Duration,
     pub multipath_strategy: MultipathStrategy,
     pub port_direction: PortDirection,
     pub protocol: TracerProtocol,
     pub addr_family: TracerAddrFamily,
+    pub ipv6_ext_header: Option<Ipv6ExtHeader>,
+    pub record_route: bool,
+    pub icmp_timestamp: bool,
     pub first_ttl: u8,
     pub max_ttl: u8,
+    pub destination_only: bool,
+    pub adaptive_ttl: bool,
+    pub prohibited_strategy: ProhibitedStrategy,
     pub grace_duration: Duration,
     pub min_round_duration: Duration,
     pub max_round_duration: Duration,
+    pub round_jitter: Duration,
+    pub probe_timeout: Option<Duration>,
+    pub retries: u8,
+    pub backfill_late: bool,
     pub max_inflight: u8,
+    pub adaptive_inflight: bool,
+    pub burst: bool,
     pub initial_sequence: u16,
+    pub sequence_strategy: SequenceStrategy,
     pub read_timeout: Duration,
     pub packet_size: u16,
     pub payload_pattern: u8,
+    pub ecn: Option<EcnCodepoint>,
+    pub dscp: Option<Dscp>,
+    pub max_duration: Option<Duration>,
+    pub max_unreachable_rounds: Option<usize>,
     pub interface: Option<String>,
     pub geoip_mmdb_file: Option<String>,
+    pub annotations: Arc<Annotations>,
 }
 
 impl TraceInfo {
-    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
     #[must_use]
     pub fn new(
         data: Arc<RwLock<Trace>>,
         source_addr: IpAddr,
+        egress: Egress,
         target_hostname: String,
+        group: Option<String>,
         target_addr: IpAddr,
+        target_lookup_duration: Duration,
         multipath_strategy: MultipathStrategy,
         port_direction: PortDirection,
         protocol: TracerProtocol,
         addr_family: TracerAddrFamily,
+        ipv6_ext_header: Option<Ipv6ExtHeader>,
+        record_route: bool,
+        icmp_timestamp: bool,
         first_ttl: u8,
         max_ttl: u8,
+        destination_only: bool,
+        adaptive_ttl: bool,
+        prohibited_strategy: ProhibitedStrategy,
         grace_duration: Duration,
         min_round_duration: Duration,
         max_round_duration: Duration,
+        round_jitter: Duration,
+        probe_timeout: Option<Duration>,
+        retries: u8,
+        backfill_late: bool,
         max_inflight: u8,
+        adaptive_inflight: bool,
+        burst: bool,
         initial_sequence: u16,
+        sequence_strategy: SequenceStrategy,
         read_timeout: Duration,
         packet_size: u16,
         payload_pattern: u8,
+        ecn: Option<EcnCodepoint>,
+        dscp: Option<Dscp>,
+        max_duration: Option<Duration>,
+        max_unreachable_rounds: Option<usize>,
         interface: Option<String>,
         geoip_mmdb_file: Option<String>,
+        annotations: Arc<Annotations>,
     ) -> Self {
         Self {
             data,
             source_addr,
+            egress,
             target_hostname,
+            group,
             target_addr,
+            target_lookup_duration,
             multipath_strategy,
             port_direction,
             protocol,
             addr_family,
+            ipv6_ext_header,
+            record_route,
+            icmp_timestamp,
             first_ttl,
             max_ttl,
+            destination_only,
+            adaptive_ttl,
+            prohibited_strategy,
             grace_duration,
             min_round_duration,
             max_round_duration,
+            round_jitter,
+            probe_timeout,
+            retries,
+            backfill_late,
             max_inflight,
+            adaptive_inflight,
+            burst,
             initial_sequence,
+            sequence_strategy,
             read_timeout,
             packet_size,
             payload_pattern,
+            ecn,
+            dscp,
+            max_duration,
+            max_unreachable_rounds,
             interface,
             geoip_mmdb_file,
+            annotations,
+        }
+    }
+
+    /// The estimated size of each probe on the wire, in bytes.
+    ///
+    /// The probes of the `icmp` and `udp` protocols are exactly `packet_size` bytes, whereas `tcp` probes are `SYN`
+    /// segments, without a payload, whose size depends on the `TCP` options added by the OS and so is estimated.
+    #[must_use]
+    pub fn probe_size(&self) -> u16 {
+        match (self.protocol, self.addr_family) {
+            (TracerProtocol::Icmp | TracerProtocol::Udp, _) => self.packet_size,
+            (TracerProtocol::Tcp, TracerAddrFamily::Ipv4) => TCP_SYN_SIZE_IPV4,
+            (TracerProtocol::Tcp, TracerAddrFamily::Ipv6) => TCP_SYN_SIZE_IPV6,
         }
     }
 }
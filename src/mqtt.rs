@@ -0,0 +1,305 @@
+use crate::dns::{DnsHosts, DnsResolverConfig};
+use crate::report::{make_report, Report};
+use crate::{DnsResolver, TraceInfo};
+use anyhow::{anyhow, Context};
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// The interval at which the traces are checked for completed rounds.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The maximum time to wait to connect to, and for a response from, the broker.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The keep alive interval requested from the broker.
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+/// The default port of an MQTT broker.
+const DEFAULT_PORT: u16 = 1883;
+
+/// MQTT control packet types, as the first byte of the fixed header.
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PINGREQ: u8 = 0xc0;
+
+/// The configuration of the MQTT publisher.
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    pub broker: String,
+    pub topic: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub client_id: String,
+}
+
+/// Start publishing a summary of every round of all `traces` to an MQTT broker.
+///
+/// The summary of each round is the JSON report of the trace, with the round number, and is published with `QoS` 0 to
+/// the topic `<topic>/<target>/<protocol>`.  If the connection to the broker fails then it is reestablished for the
+/// next round.
+///
+/// The publisher performs reverse DNS lookups with its own `DnsResolver`, which is started from `dns_config` and
+/// `dns_hosts`.
+pub fn start_mqtt_publisher(
+    config: MqttConfig,
+    traces: Vec<TraceInfo>,
+    dns_config: DnsResolverConfig,
+    dns_hosts: DnsHosts,
+) -> anyhow::Result<()> {
+    thread::Builder::new()
+        .name(String::from("mqtt"))
+        .spawn(move || -> anyhow::Result<()> {
+            let resolver = DnsResolver::start(dns_config, dns_hosts)?;
+            let topics: Vec<_> = traces
+                .iter()
+                .map(|info| {
+                    format!(
+                        "{}/{}/{}",
+                        config.topic,
+                        sanitize(&info.target_hostname),
+                        format!("{:?}", info.protocol).to_lowercase()
+                    )
+                })
+                .collect();
+            let mut rounds = vec![None; traces.len()];
+            let mut client: Option<MqttClient> = None;
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                for (index, info) in traces.iter().enumerate() {
+                    let trace = info.data.read();
+                    let round = trace.round();
+                    if round.is_none() || round == rounds[index] {
+                        continue;
+                    }
+                    rounds[index] = round;
+                    let summary = RoundSummary {
+                        round: round.unwrap_or_default() + 1,
+                        report: make_report(info, &trace, &resolver, None),
+                    };
+                    drop(trace);
+                    let payload = serde_json::to_vec(&summary)?;
+                    let result = match client.as_mut() {
+                        Some(client) => client.publish(&topics[index], &payload),
+                        None => MqttClient::connect(&config).and_then(|new_client| {
+                            info!(broker = config.broker, "connected to mqtt broker");
+                            client.insert(new_client).publish(&topics[index], &payload)
+                        }),
+                    };
+                    if let Err(err) = result {
+                        warn!(broker = config.broker, %err, "failed to publish to mqtt broker");
+                        client = None;
+                    }
+                }
+                if let Some(connected) = client.as_mut() {
+                    if let Err(err) = connected.keep_alive() {
+                        debug!(broker = config.broker, %err, "failed to ping mqtt broker");
+                        client = None;
+                    }
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// The summary of a round which is published.
+#[derive(Serialize)]
+struct RoundSummary {
+    round: usize,
+    #[serde(flatten)]
+    report: Report,
+}
+
+/// A minimal MQTT 3.1.1 client which publishes with `QoS` 0.
+struct MqttClient {
+    stream: TcpStream,
+    last_sent: Instant,
+}
+
+impl MqttClient {
+    /// Connect and authenticate to the broker.
+    fn connect(config: &MqttConfig) -> anyhow::Result<Self> {
+        let addr = config
+            .broker
+            .to_socket_addrs()
+            .or_else(|_| (config.broker.trim_matches(['[', ']']), DEFAULT_PORT).to_socket_addrs())
+            .with_context(|| format!("failed to resolve mqtt broker: {}", config.broker))?
+            .next()
+            .ok_or_else(|| anyhow!("failed to resolve mqtt broker: {}", config.broker))?;
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+        stream.write_all(&connect_packet(
+            &config.client_id,
+            KEEP_ALIVE.as_secs() as u16,
+            config.username.as_deref(),
+            config.password.as_deref(),
+        ))?;
+        let mut connack = [0_u8; 4];
+        stream.read_exact(&mut connack)?;
+        match connack {
+            [CONNACK, 0x02, _, 0x00] => Ok(Self {
+                stream,
+                last_sent: Instant::now(),
+            }),
+            [CONNACK, 0x02, _, code] => {
+                Err(anyhow!("connection refused: {}", connack_reason(code)))
+            }
+            _ => Err(anyhow!("unexpected response from broker")),
+        }
+    }
+
+    /// Publish a message with `QoS` 0.
+    fn publish(&mut self, topic: &str, payload: &[u8]) -> anyhow::Result<()> {
+        self.stream.write_all(&publish_packet(topic, payload))?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// Ping the broker if nothing has been sent for half of the keep alive interval.
+    ///
+    /// The responses of the broker are not read, as when publishing with `QoS` 0 they are only ever responses to pings.
+    fn keep_alive(&mut self) -> anyhow::Result<()> {
+        if self.last_sent.elapsed() >= KEEP_ALIVE / 2 {
+            self.stream.write_all(&[PINGREQ, 0x00])?;
+            self.last_sent = Instant::now();
+        }
+        Ok(())
+    }
+}
+
+/// The reason for a refused connection, as given by the return code of a `CONNACK` packet.
+fn connack_reason(code: u8) -> &'static str {
+    match code {
+        0x01 => "unacceptable protocol version",
+        0x02 => "identifier rejected",
+        0x03 => "server unavailable",
+        0x04 => "bad username or password",
+        0x05 => "not authorized",
+        _ => "unknown reason",
+    }
+}
+
+/// Encode a `CONNECT` packet with a clean session.
+fn connect_packet(
+    client_id: &str,
+    keep_alive: u16,
+    username: Option<&str>,
+    password: Option<&str>,
+) -> Vec<u8> {
+    let mut flags = 0x02;
+    let mut payload = encode_string(client_id);
+    if let Some(username) = username {
+        flags |= 0x80;
+        payload.extend(encode_string(username));
+    }
+    if let Some(password) = password {
+        flags |= 0x40;
+        payload.extend(encode_string(password));
+    }
+    let mut body = encode_string("MQTT");
+    body.push(0x04);
+    body.push(flags);
+    body.extend(keep_alive.to_be_bytes());
+    body.extend(payload);
+    encode_packet(CONNECT, &body)
+}
+
+/// Encode a `PUBLISH` packet with `QoS` 0.
+fn publish_packet(topic: &str, payload: &[u8]) -> Vec<u8> {
+    let mut body = encode_string(topic);
+    body.extend(payload);
+    encode_packet(PUBLISH, &body)
+}
+
+/// Encode a packet from its type and its variable header and payload.
+fn encode_packet(packet_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![packet_type];
+    let mut remaining = body.len();
+    loop {
+        let mut byte = (remaining % 128) as u8;
+        remaining /= 128;
+        if remaining > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if remaining == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+/// Encode a length prefixed UTF-8 string.
+fn encode_string(value: &str) -> Vec<u8> {
+    let mut encoded = (value.len() as u16).to_be_bytes().to_vec();
+    encoded.extend(value.as_bytes());
+    encoded
+}
+
+/// Replace the characters which separate or match topic levels with `_`.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '/' | '+' | '#' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0, &[0x00]; "zero")]
+    #[test_case(127, &[0x7f]; "one byte")]
+    #[test_case(128, &[0x80, 0x01]; "two bytes")]
+    #[test_case(16_383, &[0xff, 0x7f]; "two bytes max")]
+    #[test_case(16_384, &[0x80, 0x80, 0x01]; "three bytes")]
+    fn test_encode_remaining_length(len: usize, expected: &[u8]) {
+        let packet = encode_packet(PUBLISH, &vec![0; len]);
+        assert_eq!(&packet[1..=expected.len()], expected);
+        assert_eq!(packet.len(), 1 + expected.len() + len);
+    }
+
+    #[test]
+    fn test_connect_packet() {
+        let packet = connect_packet("trippy", 60, Some("user"), Some("pass"));
+        let expected = [
+            &[CONNECT, 30][..],
+            &[0x00, 0x04],
+            b"MQTT",
+            &[0x04, 0xc2, 0x00, 0x3c],
+            &[0x00, 0x06],
+            b"trippy",
+            &[0x00, 0x04],
+            b"user",
+            &[0x00, 0x04],
+            b"pass",
+        ]
+        .concat();
+        assert_eq!(packet, expected);
+    }
+
+    #[test]
+    fn test_publish_packet() {
+        let packet = publish_packet("a/b", b"{}");
+        assert_eq!(
+            packet,
+            [&[PUBLISH, 7, 0x00, 0x03][..], b"a/b", b"{}"].concat()
+        );
+    }
+
+    #[test_case("www.example.com", "www.example.com"; "hostname")]
+    #[test_case("a/b+c#", "a_b_c_"; "separators and wildcards")]
+    fn test_sanitize(value: &str, expected: &str) {
+        assert_eq!(sanitize(value), expected);
+    }
+}
@@ -0,0 +1,410 @@
+use crate::backend::Hop;
+use crate::config::OtlpEndpoint;
+use crate::TraceInfo;
+use anyhow::anyhow;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// The maximum time to wait to connect to, and for a response from, the collector.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The OTLP aggregation temporality of sums which accumulate from a fixed start time.
+const AGGREGATION_TEMPORALITY_CUMULATIVE: u8 = 2;
+
+/// Start exporting the hop metrics of all `traces` to the OpenTelemetry collector at `endpoint` every `interval`.
+///
+/// The metrics are exported with OTLP/HTTP using the JSON encoding.  A failed export is logged and the metrics are
+/// exported again at the next interval.
+pub fn start_otlp_exporter(
+    endpoint: OtlpEndpoint,
+    interval: Duration,
+    traces: Vec<TraceInfo>,
+) -> anyhow::Result<()> {
+    thread::Builder::new()
+        .name(String::from("otlp"))
+        .spawn(move || loop {
+            thread::sleep(interval);
+            let metrics: Vec<_> = traces.iter().map(TraceMetrics::new).collect();
+            let request = make_request(&metrics, SystemTime::now());
+            match export(&endpoint, &request) {
+                Ok(()) => debug!(%endpoint, "exported metrics"),
+                Err(err) => warn!(%endpoint, %err, "failed to export metrics"),
+            }
+        })?;
+    Ok(())
+}
+
+/// The metrics of the hops of a trace.
+#[derive(Debug)]
+struct TraceMetrics {
+    target: String,
+    target_addr: IpAddr,
+    /// The time from which the probe counts accumulate, which moves forward whenever the trace is cleared.
+    start_time: SystemTime,
+    hops: Vec<HopMetrics>,
+}
+
+impl TraceMetrics {
+    fn new(info: &TraceInfo) -> Self {
+        let trace = info.data.read();
+        Self {
+            target: info.target_hostname.clone(),
+            target_addr: info.target_addr,
+            start_time: trace.reset_time(),
+            hops: trace
+                .hops()
+                .iter()
+                .filter(|hop| hop.total_sent() > 0)
+                .map(HopMetrics::new)
+                .collect(),
+        }
+    }
+}
+
+/// The metrics of a hop.
+#[derive(Debug, Clone)]
+struct HopMetrics {
+    ttl: u8,
+    last_ms: Option<f64>,
+    avg_ms: Option<f64>,
+    best_ms: Option<f64>,
+    worst_ms: Option<f64>,
+    stddev_ms: Option<f64>,
+    loss_pct: f64,
+    sent: usize,
+    recv: usize,
+}
+
+impl HopMetrics {
+    fn new(hop: &Hop) -> Self {
+        let received = hop.total_recv() > 0;
+        Self {
+            ttl: hop.ttl(),
+            last_ms: hop.last_ms(),
+            avg_ms: received.then(|| hop.avg_ms()),
+            best_ms: hop.best_ms(),
+            worst_ms: hop.worst_ms(),
+            stddev_ms: received.then(|| hop.stddev_ms()),
+            loss_pct: hop.loss_pct(),
+            sent: hop.total_sent(),
+            recv: hop.total_recv(),
+        }
+    }
+}
+
+/// A hop with the attributes and start time of its data points.
+struct HopPoints<'a> {
+    attributes: Vec<Value>,
+    start_time: &'a str,
+    metrics: &'a HopMetrics,
+}
+
+/// Post an export request to the collector.
+fn export(endpoint: &OtlpEndpoint, request: &Value) -> anyhow::Result<()> {
+    let addr = (endpoint.host.as_str(), endpoint.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("failed to resolve {}", endpoint.host))?;
+    let stream = TcpStream::connect_timeout(&addr, EXPORT_TIMEOUT)?;
+    stream.set_read_timeout(Some(EXPORT_TIMEOUT))?;
+    stream.set_write_timeout(Some(EXPORT_TIMEOUT))?;
+    let body = serde_json::to_string(request)?;
+    let mut writer = &stream;
+    write!(
+        writer,
+        "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        endpoint.path,
+        endpoint.host,
+        endpoint.port,
+        body.len()
+    )?;
+    writer.flush()?;
+    let mut status_line = String::new();
+    BufReader::new(&stream).read_line(&mut status_line)?;
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(anyhow!("unexpected response: {}", status_line.trim())),
+    }
+}
+
+/// Make an OTLP `ExportMetricsServiceRequest` for the hop metrics of all `traces`.
+fn make_request(traces: &[TraceMetrics], now: SystemTime) -> Value {
+    let now = unix_nanos(now);
+    let start_times: Vec<_> = traces
+        .iter()
+        .map(|trace| unix_nanos(trace.start_time))
+        .collect();
+    let hops: Vec<_> = traces
+        .iter()
+        .zip(&start_times)
+        .flat_map(|(trace, start_time)| {
+            trace.hops.iter().map(move |hop| HopPoints {
+                attributes: hop_attributes(trace, hop),
+                start_time,
+                metrics: hop,
+            })
+        })
+        .collect();
+    let metrics = vec![
+        gauge(
+            &hops,
+            &now,
+            "trippy.hop.rtt.last",
+            "The round trip time of the last probe",
+            "ms",
+            |hop| hop.last_ms,
+        ),
+        gauge(
+            &hops,
+            &now,
+            "trippy.hop.rtt.avg",
+            "The average round trip time of all probes",
+            "ms",
+            |hop| hop.avg_ms,
+        ),
+        gauge(
+            &hops,
+            &now,
+            "trippy.hop.rtt.best",
+            "The best round trip time of all probes",
+            "ms",
+            |hop| hop.best_ms,
+        ),
+        gauge(
+            &hops,
+            &now,
+            "trippy.hop.rtt.worst",
+            "The worst round trip time of all probes",
+            "ms",
+            |hop| hop.worst_ms,
+        ),
+        gauge(
+            &hops,
+            &now,
+            "trippy.hop.rtt.stddev",
+            "The standard deviation of the round trip time of all probes",
+            "ms",
+            |hop| hop.stddev_ms,
+        ),
+        gauge(
+            &hops,
+            &now,
+            "trippy.hop.loss",
+            "The percentage of probes which were lost",
+            "%",
+            |hop| Some(hop.loss_pct),
+        ),
+        sum(
+            &hops,
+            &now,
+            "trippy.hop.probes.sent",
+            "The number of probes sent",
+            |hop| hop.sent,
+        ),
+        sum(
+            &hops,
+            &now,
+            "trippy.hop.probes.received",
+            "The number of probe responses received",
+            |hop| hop.recv,
+        ),
+    ];
+    json!({
+        "resourceMetrics": [{
+            "resource": {
+                "attributes": [string_attribute("service.name", "trippy")],
+            },
+            "scopeMetrics": [{
+                "scope": {
+                    "name": "trippy",
+                    "version": clap::crate_version!(),
+                },
+                "metrics": metrics,
+            }],
+        }],
+    })
+}
+
+/// A gauge metric with a data point for each hop which has a `value`.
+fn gauge(
+    hops: &[HopPoints<'_>],
+    now: &str,
+    name: &str,
+    description: &str,
+    unit: &str,
+    value: fn(&HopMetrics) -> Option<f64>,
+) -> Value {
+    let points: Vec<_> = hops
+        .iter()
+        .filter_map(|hop| {
+            value(hop.metrics).map(|value| {
+                json!({
+                    "attributes": hop.attributes,
+                    "timeUnixNano": now,
+                    "asDouble": value,
+                })
+            })
+        })
+        .collect();
+    json!({
+        "name": name,
+        "description": description,
+        "unit": unit,
+        "gauge": { "dataPoints": points },
+    })
+}
+
+/// A cumulative, monotonic sum metric of probes with a data point for each hop.
+///
+/// The sums of each hop accumulate from the time its trace was created or last cleared.
+fn sum(
+    hops: &[HopPoints<'_>],
+    now: &str,
+    name: &str,
+    description: &str,
+    value: fn(&HopMetrics) -> usize,
+) -> Value {
+    let points: Vec<_> = hops
+        .iter()
+        .map(|hop| {
+            json!({
+                "attributes": hop.attributes,
+                "startTimeUnixNano": hop.start_time,
+                "timeUnixNano": now,
+                "asInt": value(hop.metrics).to_string(),
+            })
+        })
+        .collect();
+    json!({
+        "name": name,
+        "description": description,
+        "unit": "{probe}",
+        "sum": {
+            "dataPoints": points,
+            "aggregationTemporality": AGGREGATION_TEMPORALITY_CUMULATIVE,
+            "isMonotonic": true,
+        },
+    })
+}
+
+/// The attributes of a hop, which identify it by its ttl alone such that a time series is not split when the address
+/// responding at a hop changes.
+fn hop_attributes(trace: &TraceMetrics, hop: &HopMetrics) -> Vec<Value> {
+    vec![
+        string_attribute("target", &trace.target),
+        string_attribute("target.address", &trace.target_addr.to_string()),
+        json!({ "key": "hop.ttl", "value": { "intValue": hop.ttl.to_string() } }),
+    ]
+}
+
+fn string_attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// The time since the Unix epoch in nanoseconds, which is encoded as a string as per the OTLP JSON encoding.
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const HOP: HopMetrics = HopMetrics {
+        ttl: 3,
+        last_ms: Some(12.5),
+        avg_ms: Some(10.0),
+        best_ms: Some(8.0),
+        worst_ms: Some(12.5),
+        stddev_ms: Some(1.5),
+        loss_pct: 25.0,
+        sent: 4,
+        recv: 3,
+    };
+
+    fn trace(start_secs: u64, hops: Vec<HopMetrics>) -> TraceMetrics {
+        TraceMetrics {
+            target: String::from("www.example.com"),
+            target_addr: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            start_time: UNIX_EPOCH + Duration::from_secs(start_secs),
+            hops,
+        }
+    }
+
+    fn metric<'a>(request: &'a Value, name: &str) -> &'a Value {
+        request["resourceMetrics"][0]["scopeMetrics"][0]["metrics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|metric| metric["name"] == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_make_request_gauge() {
+        let request = make_request(&[trace(1, vec![HOP])], UNIX_EPOCH + Duration::from_secs(2));
+        assert_eq!(
+            metric(&request, "trippy.hop.rtt.last")["gauge"]["dataPoints"],
+            json!([{
+                "attributes": [
+                    { "key": "target", "value": { "stringValue": "www.example.com" } },
+                    { "key": "target.address", "value": { "stringValue": "192.0.2.1" } },
+                    { "key": "hop.ttl", "value": { "intValue": "3" } },
+                ],
+                "timeUnixNano": "2000000000",
+                "asDouble": 12.5,
+            }])
+        );
+        assert_eq!(
+            metric(&request, "trippy.hop.loss")["gauge"]["dataPoints"][0]["asDouble"],
+            25.0
+        );
+    }
+
+    #[test]
+    fn test_make_request_gauge_without_value() {
+        let hop = HopMetrics {
+            last_ms: None,
+            ..HOP
+        };
+        let request = make_request(&[trace(1, vec![hop])], UNIX_EPOCH);
+        assert_eq!(
+            metric(&request, "trippy.hop.rtt.last")["gauge"]["dataPoints"],
+            json!([])
+        );
+    }
+
+    #[test]
+    fn test_make_request_sum() {
+        let request = make_request(&[trace(1, vec![HOP])], UNIX_EPOCH + Duration::from_secs(2));
+        let sum = &metric(&request, "trippy.hop.probes.sent")["sum"];
+        assert_eq!(sum["aggregationTemporality"], 2);
+        assert_eq!(sum["isMonotonic"], true);
+        assert_eq!(sum["dataPoints"][0]["startTimeUnixNano"], "1000000000");
+        assert_eq!(sum["dataPoints"][0]["timeUnixNano"], "2000000000");
+        assert_eq!(sum["dataPoints"][0]["asInt"], "4");
+        assert_eq!(
+            metric(&request, "trippy.hop.probes.received")["sum"]["dataPoints"][0]["asInt"],
+            "3"
+        );
+    }
+
+    #[test]
+    fn test_make_request_start_time_per_trace() {
+        let request = make_request(
+            &[trace(1, vec![HOP]), trace(5, vec![HOP])],
+            UNIX_EPOCH + Duration::from_secs(10),
+        );
+        let points = &metric(&request, "trippy.hop.probes.sent")["sum"]["dataPoints"];
+        assert_eq!(points[0]["startTimeUnixNano"], "1000000000");
+        assert_eq!(points[1]["startTimeUnixNano"], "5000000000");
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Local};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write as _};
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// The maximum number of problems retained, the oldest problems are discarded first.
+const MAX_PROBLEMS: usize = 100;
+
+/// A problem which occurred at runtime, such as a failure to send a probe or to resolve a hostname.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Problem {
+    /// When the problem occurred.
+    pub time: DateTime<Local>,
+    /// The severity of the problem, either `WARN` or `ERROR`.
+    pub level: Level,
+    /// The message describing the problem, followed by the fields of the event.
+    pub message: String,
+}
+
+/// The problems which occurred at runtime, shared between the threads which report them and the Tui.
+///
+/// The runtime problems are the warning and error events logged by Trippy, which are otherwise not shown in the Tui.
+#[derive(Debug, Clone, Default)]
+pub struct ProblemLog {
+    inner: Arc<Mutex<ProblemLogInner>>,
+}
+
+#[derive(Debug, Default)]
+struct ProblemLogInner {
+    problems: VecDeque<Problem>,
+    total: usize,
+}
+
+impl ProblemLog {
+    /// Record a `Problem`, discarding the oldest `Problem` if the log is full.
+    pub fn push(&self, problem: Problem) {
+        let mut inner = self.inner.lock();
+        if inner.problems.len() == MAX_PROBLEMS {
+            inner.problems.pop_front();
+        }
+        inner.problems.push_back(problem);
+        inner.total += 1;
+    }
+
+    /// The retained problems, most recent first.
+    pub fn problems(&self) -> Vec<Problem> {
+        self.inner.lock().problems.iter().rev().cloned().collect()
+    }
+
+    /// The most recent `Problem`, if any.
+    pub fn latest(&self) -> Option<Problem> {
+        self.inner.lock().problems.back().cloned()
+    }
+
+    /// The total number of problems recorded, including those which have been discarded.
+    pub fn total(&self) -> usize {
+        self.inner.lock().total
+    }
+
+    /// A `Layer` which records warning and error events in this log.
+    pub fn layer(&self) -> ProblemLayer {
+        ProblemLayer { log: self.clone() }
+    }
+}
+
+/// A `Layer` which records warning and error events in a `ProblemLog`.
+#[derive(Debug)]
+pub struct ProblemLayer {
+    log: ProblemLog,
+}
+
+impl<S: Subscriber> Layer<S> for ProblemLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let level = *event.metadata().level();
+        if level > Level::WARN {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        self.log.push(Problem {
+            time: Local::now(),
+            level,
+            message: visitor.message,
+        });
+    }
+}
+
+/// Format the message of an event followed by its other fields as `name=value`.
+#[derive(Debug, Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message.insert_str(0, &format!("{value:?}"));
+        } else {
+            let _ = write!(self.message, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::{debug, error, info, warn};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_problem_layer() {
+        let log = ProblemLog::default();
+        let subscriber = tracing_subscriber::registry().with(log.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            debug!("ignored");
+            info!("ignored");
+            warn!(addr = "10.0.0.1", "reverse dns lookup failed");
+            error!(err = %"bad packet", "tracer failed");
+        });
+        let problems = log.problems();
+        assert_eq!(2, log.total());
+        assert_eq!(2, problems.len());
+        assert_eq!(Level::ERROR, problems[0].level);
+        assert_eq!("tracer failed err=bad packet", problems[0].message);
+        assert_eq!(Level::WARN, problems[1].level);
+        assert_eq!(
+            "reverse dns lookup failed addr=\"10.0.0.1\"",
+            problems[1].message
+        );
+        assert_eq!(Some(&problems[0]), log.latest().as_ref());
+    }
+
+    #[test]
+    fn test_problem_log_discards_oldest() {
+        let log = ProblemLog::default();
+        for i in 0..=MAX_PROBLEMS {
+            log.push(Problem {
+                time: Local::now(),
+                level: Level::WARN,
+                message: format!("problem {i}"),
+            });
+        }
+        let problems = log.problems();
+        assert_eq!(MAX_PROBLEMS + 1, log.total());
+        assert_eq!(MAX_PROBLEMS, problems.len());
+        assert_eq!(format!("problem {MAX_PROBLEMS}"), problems[0].message);
+        assert_eq!("problem 1", problems[MAX_PROBLEMS - 1].message);
+    }
+}
@@ -1,72 +1,377 @@
+use crate::annotations::SpecialPurpose;
+use crate::backend::{AddrStats, DscpStatus, Hop, IcmpResponse, Reachability, TraceSummary};
+use crate::config::{
+    Dscp, Locale, ReportBorder, ReportColumn, ReportOverflow, RttFormat, TimestampMode,
+    TuiExportFormat,
+};
+use crate::geoip::{geo_distance, GeoIpLookup};
+use crate::locale::translate;
+use crate::template::Template;
 use crate::{DnsResolver, Trace, TraceInfo};
-use anyhow::anyhow;
-use comfy_table::presets::{ASCII_MARKDOWN, UTF8_FULL};
-use comfy_table::{ContentArrangement, Table};
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use comfy_table::presets::{ASCII_FULL, ASCII_MARKDOWN, NOTHING, UTF8_FULL};
+use comfy_table::{ColumnConstraint, ContentArrangement, Row, Table, Width};
 use itertools::Itertools;
-use parking_lot::RwLock;
 use serde::{Serialize, Serializer};
+use signal_hook::consts::SIGINT;
+use std::fmt::Write as _;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
+use trippy::tracing::{PortDirection, TracerProtocol};
 
-/// Generate a CSV report of trace data.
+/// The minimum width of the column of the addresses of each hop in a table report, such that only this column is
+/// narrowed, by wrapping or truncating it, to fit the table in the width available.
+const MIN_ADDRS_WIDTH: u16 = 10;
+
+/// The headers of the columns of a table report which precede the statistics columns.
+const TABLE_HEADER: [&str; 2] = ["Hop", "Addrs"];
+
+/// Generate a CSV report of trace data, with a row for each address of each hop if `per_address` is set.
+#[allow(clippy::too_many_arguments)]
 pub fn run_report_csv(
     info: &TraceInfo,
     report_cycles: usize,
+    report_file: Option<&Path>,
+    schedule: Option<ReportSchedule>,
     resolver: &DnsResolver,
+    rtt_format: RttFormat,
+    columns: &[ReportColumn],
+    per_address: bool,
 ) -> anyhow::Result<()> {
-    let trace = wait_for_round(&info.data, report_cycles)?;
-    println!("Target,TargetIp,Hop,Addrs,Loss%,Snt,Recv,Last,Avg,Best,Wrst,StdDev,");
-    for hop in trace.hops().iter() {
-        let ttl = hop.ttl();
-        let hosts = hop.addrs().map(|ip| resolver.reverse_lookup(*ip)).join(":");
-        let host = if hosts.is_empty() {
-            String::from("???")
+    run_report(info, report_cycles, report_file, schedule, |trace| {
+        render_report_csv(info, trace, resolver, rtt_format, columns, per_address)
+    })
+}
+
+/// Render a CSV report of trace data.
+///
+/// If `per_address` is set then each address of a hop which responded is a row of its own, with the statistics of the
+/// responses from that address, rather than a single row for the hop as a whole.
+fn render_report_csv(
+    info: &TraceInfo,
+    trace: &Trace,
+    resolver: &DnsResolver,
+    rtt_format: RttFormat,
+    columns: &[ReportColumn],
+    per_address: bool,
+) -> String {
+    let mut report = format!("Target,TargetIp,Hop,Addrs,{}\n", csv_header(columns));
+    for hop in trace.hops() {
+        let addr_stats = hop
+            .addrs()
+            .filter_map(|addr| hop.addr_stats(addr).map(|stats| (addr, stats)))
+            .collect::<Vec<_>>();
+        if per_address && !addr_stats.is_empty() {
+            for (addr, stats) in addr_stats {
+                let host = format_host(info, resolver, *addr);
+                report.push_str(&csv_addr_row(info, hop, &host, stats, rtt_format, columns));
+                report.push('\n');
+            }
         } else {
-            hosts
-        };
-        let sent = hop.total_sent();
-        let recv = hop.total_recv();
-        let last = hop
-            .last_ms()
-            .map_or_else(|| String::from("???"), |last| format!("{last:.1}"));
-        let best = hop
-            .best_ms()
-            .map_or_else(|| String::from("???"), |best| format!("{best:.1}"));
-        let worst = hop
-            .worst_ms()
-            .map_or_else(|| String::from("???"), |worst| format!("{worst:.1}"));
-        let stddev = hop.stddev_ms();
-        let avg = hop.avg_ms();
-        let loss_pct = hop.loss_pct();
-        println!(
-            "{},{},{},{},{:.1}%,{},{},{},{:.1},{},{},{:.1}",
-            info.target_hostname,
-            info.target_addr,
-            ttl,
-            host,
-            loss_pct,
-            sent,
-            recv,
-            last,
-            avg,
-            best,
-            worst,
-            stddev
-        );
+            report.push_str(&csv_row(info, hop, resolver, rtt_format, columns));
+            report.push('\n');
+        }
+    }
+    report
+}
+
+/// Export a report of the trace data shown by the Tui to a file in `dir` which is named after the target and the
+/// current time, such as `trippy-example.com-20230501T120000.json`, without interrupting the trace.
+///
+/// Returns the path of the file written.
+#[allow(clippy::too_many_arguments)]
+pub fn export_report(
+    info: &TraceInfo,
+    trace: &Trace,
+    resolver: &DnsResolver,
+    geoip_lookup: &GeoIpLookup,
+    format: TuiExportFormat,
+    dir: &Path,
+    rtt_format: RttFormat,
+    columns: &[ReportColumn],
+    per_address: bool,
+    locale: Locale,
+) -> anyhow::Result<PathBuf> {
+    let (report, extension) = match format {
+        TuiExportFormat::Json => (
+            render_report_json(info, trace, resolver, geoip_lookup),
+            "json",
+        ),
+        TuiExportFormat::Markdown => (
+            render_report_table(
+                info,
+                trace,
+                resolver,
+                geoip_lookup,
+                rtt_format,
+                columns,
+                per_address,
+                TableStyle::MARKDOWN,
+                locale,
+            ),
+            "md",
+        ),
+        TuiExportFormat::Csv => (
+            render_report_csv(info, trace, resolver, rtt_format, columns, per_address),
+            "csv",
+        ),
+    };
+    // the target may be an IPv6 address or a remote trace, neither of which are safe to use in a file name as is
+    let target = info
+        .target_hostname
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let path = timestamped_path(
+        &dir.join(format!("trippy-{target}.{extension}")),
+        Local::now(),
+    );
+    write_report_file(&path, &report)?;
+    Ok(path)
+}
+
+/// Generate a continuous stream of CSV trace data, one row per hop for each completed round.
+pub fn run_report_csv_stream(
+    info: &TraceInfo,
+    resolver: &DnsResolver,
+    rtt_format: RttFormat,
+    columns: &[ReportColumn],
+) -> anyhow::Result<()> {
+    println!("Round,Target,TargetIp,Hop,Addrs,{}", csv_header(columns));
+    let deadline = deadline(info);
+    let mut last_round = None;
+    while !is_expired(deadline) {
+        let trace = info.data.read().clone();
+        if let Some(err) = trace.error() {
+            return Err(anyhow!("error: {}", err));
+        }
+        if let Some(round) = trace.round() {
+            if last_round != Some(round) {
+                for hop in trace.hops() {
+                    println!(
+                        "{},{}",
+                        round + 1,
+                        csv_row(info, hop, resolver, rtt_format, columns)
+                    );
+                }
+                last_round = Some(round);
+                check_unreachable(info, &trace)?;
+            }
+        }
+        sleep(Duration::from_millis(100));
     }
     Ok(())
 }
 
+/// Format the header of the `columns` of a CSV report, which is terminated by a comma.
+fn csv_header(columns: &[ReportColumn]) -> String {
+    let header = columns
+        .iter()
+        .map(|column| column_header(*column))
+        .join(",");
+    format!("{header},")
+}
+
+/// Format a single hop as a CSV row.
+fn csv_row(
+    info: &TraceInfo,
+    hop: &Hop,
+    resolver: &DnsResolver,
+    rtt_format: RttFormat,
+    columns: &[ReportColumn],
+) -> String {
+    let ttl = hop.ttl();
+    let hosts = hop
+        .addrs()
+        .map(|ip| format_host(info, resolver, *ip))
+        .join(":");
+    let host = if hosts.is_empty() {
+        String::from("???")
+    } else {
+        hosts
+    };
+    let values = columns
+        .iter()
+        .map(|column| match column {
+            ReportColumn::LossPct => format!("{}%", format_column(hop, *column, rtt_format)),
+            _ => format_column(hop, *column, rtt_format),
+        })
+        .join(",");
+    format!(
+        "{},{},{},{},{}",
+        info.target_hostname, info.target_addr, ttl, host, values
+    )
+}
+
+/// Format a single address of a hop as a CSV row.
+fn csv_addr_row(
+    info: &TraceInfo,
+    hop: &Hop,
+    host: &str,
+    stats: &AddrStats,
+    rtt_format: RttFormat,
+    columns: &[ReportColumn],
+) -> String {
+    let values = columns
+        .iter()
+        .map(|column| match column {
+            ReportColumn::LossPct => format!("{}%", format_addr_column(stats, *column, rtt_format)),
+            _ => format_addr_column(stats, *column, rtt_format),
+        })
+        .join(",");
+    format!(
+        "{},{},{},{host},{values}",
+        info.target_hostname,
+        info.target_addr,
+        hop.ttl()
+    )
+}
+
+/// The header of a column of statistics.
+fn column_header(column: ReportColumn) -> &'static str {
+    match column {
+        ReportColumn::LossPct => "Loss%",
+        ReportColumn::Sent => "Snt",
+        ReportColumn::Recv => "Recv",
+        ReportColumn::Last => "Last",
+        ReportColumn::Avg => "Avg",
+        ReportColumn::Best => "Best",
+        ReportColumn::Worst => "Wrst",
+        ReportColumn::Stddev => "StdDev",
+        ReportColumn::Jitter => "Jttr",
+        ReportColumn::JitterAvg => "Javg",
+        ReportColumn::JitterMax => "Jmax",
+        ReportColumn::P50 => "P50",
+        ReportColumn::P95 => "P95",
+        ReportColumn::P99 => "P99",
+        ReportColumn::Fwd => "Fwd",
+        ReportColumn::Rtn => "Rtn",
+    }
+}
+
+/// Format a column of statistics of a hop, where a round-trip time which is not known is shown as `???`.
+fn format_column(hop: &Hop, column: ReportColumn, rtt_format: RttFormat) -> String {
+    let format_rtt =
+        |rtt: Option<f64>| rtt.map_or_else(|| String::from("???"), |rtt| rtt_format.format(rtt));
+    match column {
+        ReportColumn::LossPct => format!("{:.1}", hop.loss_pct()),
+        ReportColumn::Sent => hop.total_sent().to_string(),
+        ReportColumn::Recv => hop.total_recv().to_string(),
+        ReportColumn::Last => format_rtt(hop.last_ms()),
+        ReportColumn::Avg => rtt_format.format(hop.avg_ms()),
+        ReportColumn::Best => format_rtt(hop.best_ms()),
+        ReportColumn::Worst => format_rtt(hop.worst_ms()),
+        ReportColumn::Stddev => rtt_format.format(hop.stddev_ms()),
+        ReportColumn::Jitter => format_rtt(hop.jitter_ms()),
+        ReportColumn::JitterAvg => format_rtt(hop.avg_jitter_ms()),
+        ReportColumn::JitterMax => format_rtt(hop.worst_jitter_ms()),
+        ReportColumn::P50 => format_rtt(hop.percentile_ms(50_f64)),
+        ReportColumn::P95 => format_rtt(hop.percentile_ms(95_f64)),
+        ReportColumn::P99 => format_rtt(hop.percentile_ms(99_f64)),
+        ReportColumn::Fwd => format_rtt(
+            hop.last_timestamp()
+                .and_then(|timestamp| timestamp.forward_ms())
+                .map(|ms| ms as f64),
+        ),
+        ReportColumn::Rtn => format_rtt(
+            hop.last_timestamp()
+                .and_then(|timestamp| timestamp.return_ms())
+                .map(|ms| ms as f64),
+        ),
+    }
+}
+
+/// Format a column of the statistics of the responses from a single address of a hop.
+///
+/// The columns which are only known for the hop as a whole, such as the jitter and the percentiles, are shown as `-`.
+fn format_addr_column(stats: &AddrStats, column: ReportColumn, rtt_format: RttFormat) -> String {
+    let format_rtt =
+        |rtt: Option<f64>| rtt.map_or_else(|| String::from("???"), |rtt| rtt_format.format(rtt));
+    match column {
+        ReportColumn::LossPct => format!("{:.1}", stats.loss_pct()),
+        ReportColumn::Sent => stats.total_sent().to_string(),
+        ReportColumn::Recv => stats.total_recv().to_string(),
+        ReportColumn::Last => format_rtt(stats.last_ms()),
+        ReportColumn::Avg => rtt_format.format(stats.avg_ms()),
+        ReportColumn::Best => format_rtt(stats.best_ms()),
+        ReportColumn::Worst => format_rtt(stats.worst_ms()),
+        ReportColumn::Stddev => rtt_format.format(stats.stddev_ms()),
+        ReportColumn::Jitter
+        | ReportColumn::JitterAvg
+        | ReportColumn::JitterMax
+        | ReportColumn::P50
+        | ReportColumn::P95
+        | ReportColumn::P99
+        | ReportColumn::Fwd
+        | ReportColumn::Rtn => String::from("-"),
+    }
+}
+
+/// Format the reverse DNS lookup of an address, followed by the user supplied annotation of the address, if any.
+fn format_host(info: &TraceInfo, resolver: &DnsResolver, addr: IpAddr) -> String {
+    let host = resolver.reverse_lookup(addr).to_string();
+    let host = match info.annotations.lookup(addr) {
+        Some(label) => format!("{host} [{label}]"),
+        None => host,
+    };
+    match SpecialPurpose::lookup(addr) {
+        Some(special) => format!("{host} [{special}]"),
+        None => host,
+    }
+}
+
 #[derive(Serialize)]
 pub struct Report {
     pub info: ReportInfo,
+    pub summary: ReportSummary,
     pub hops: Vec<ReportHop>,
+    pub route_changes: Vec<ReportRouteChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub as_path_changes: Vec<ReportAsPathChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub target_changes: Vec<ReportTargetChange>,
+}
+
+#[derive(Serialize)]
+pub struct ReportSummary {
+    rounds: usize,
+    sent: usize,
+    recv: usize,
+    #[serde(serialize_with = "fixed_width")]
+    loss_pct: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_round_ms: Option<String>,
+    path_len: u8,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    as_path: Vec<String>,
 }
 
 #[derive(Serialize)]
 pub struct ReportInfo {
     pub target: Host,
+    pub egress: ReportEgress,
+}
+
+/// The local source address, interface and first-hop gateway used by a trace.
+#[derive(Serialize)]
+pub struct ReportEgress {
+    source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interface: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gateway: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -79,6 +384,10 @@ pub struct ReportHop {
     #[serde(serialize_with = "fixed_width")]
     last: f64,
     recv: usize,
+    dup: usize,
+    corrupt: usize,
+    retries: usize,
+    late: usize,
     #[serde(serialize_with = "fixed_width")]
     avg: f64,
     #[serde(serialize_with = "fixed_width")]
@@ -87,12 +396,143 @@ pub struct ReportHop {
     worst: f64,
     #[serde(serialize_with = "fixed_width")]
     stddev: f64,
+    rate_limited: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icmp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_ttl: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    return_path_len: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ecn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dscp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tcp_handshake: Option<ReportTcpHandshake>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    recorded_addrs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<ReportTimestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geo: Option<ReportGeo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    samples: Vec<ReportSample>,
+}
+
+#[derive(Serialize)]
+pub struct ReportGeo {
+    from_ttl: u8,
+    #[serde(serialize_with = "fixed_width")]
+    distance_km: f64,
+    #[serde(serialize_with = "fixed_width")]
+    uncertainty_km: f64,
+    #[serde(serialize_with = "fixed_width")]
+    min_rtt_ms: f64,
+    #[serde(serialize_with = "fixed_width")]
+    rtt_increase_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sanity: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ReportSample {
+    sequence: u16,
+    round: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    rtt_ms: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    late: bool,
+}
+
+#[derive(Serialize)]
+pub struct ReportTimestamp {
+    originate: u32,
+    receive: u32,
+    transmit: u32,
+    received: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    forward_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    return_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    asymmetry_ms: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct ReportTcpHandshake {
+    established: usize,
+    refused: usize,
+    #[serde(serialize_with = "fixed_width")]
+    last: f64,
+    #[serde(serialize_with = "fixed_width")]
+    avg: f64,
+}
+
+#[derive(Serialize)]
+pub struct ReportRouteChange {
+    timestamp: String,
+    round: usize,
+    ttl: u8,
+    from: String,
+    to: String,
+}
+
+#[derive(Serialize)]
+pub struct ReportAsPathChange {
+    timestamp: String,
+    round: usize,
+    from: Vec<String>,
+    to: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ReportTargetChange {
+    timestamp: String,
+    round: usize,
+    from: String,
+    to: String,
+    followed: bool,
 }
 
 #[derive(Serialize)]
 pub struct Host {
     pub ip: String,
     pub hostname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub special_purpose: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub off_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ReportAddrStats>,
+}
+
+#[derive(Serialize)]
+pub struct ReportAddrStats {
+    #[serde(serialize_with = "fixed_width")]
+    loss_pct: f64,
+    sent: usize,
+    recv: usize,
+    #[serde(serialize_with = "fixed_width")]
+    last: f64,
+    #[serde(serialize_with = "fixed_width")]
+    avg: f64,
+    #[serde(serialize_with = "fixed_width")]
+    best: f64,
+    #[serde(serialize_with = "fixed_width")]
+    worst: f64,
+    #[serde(serialize_with = "fixed_width")]
+    stddev: f64,
 }
 
 #[allow(clippy::trivially_copy_pass_by_ref)]
@@ -103,22 +543,81 @@ where
     serializer.serialize_str(&format!("{val:.2}"))
 }
 
-/// Generate a CSV report of trace data.
+/// Format a `Duration` as fractional milliseconds.
+fn format_ms(duration: Duration) -> String {
+    format!("{:.2}", duration.as_secs_f64() * 1000_f64)
+}
+
+/// Format a `SystemTime` as an RFC 3339 timestamp in UTC.
+fn format_timestamp(timestamp: SystemTime) -> String {
+    DateTime::<Utc>::from(timestamp).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Generate a JSON report of trace data.
 pub fn run_report_json(
     info: &TraceInfo,
     report_cycles: usize,
+    report_file: Option<&Path>,
+    schedule: Option<ReportSchedule>,
     resolver: &DnsResolver,
+    geoip_lookup: &GeoIpLookup,
 ) -> anyhow::Result<()> {
-    let trace = wait_for_round(&info.data, report_cycles)?;
+    run_report(info, report_cycles, report_file, schedule, |trace| {
+        render_report_json(info, trace, resolver, geoip_lookup)
+    })
+}
+
+/// Render a JSON report of trace data.
+fn render_report_json(
+    info: &TraceInfo,
+    trace: &Trace,
+    resolver: &DnsResolver,
+    geoip_lookup: &GeoIpLookup,
+) -> String {
+    let report = make_report(info, trace, resolver, Some(geoip_lookup));
+    format!("{}\n", serde_json::to_string_pretty(&report).unwrap())
+}
+
+/// Generate a report of trace data rendered from a `Template` of the JSON report data.
+pub fn run_report_template(
+    info: &TraceInfo,
+    report_cycles: usize,
+    report_file: Option<&Path>,
+    schedule: Option<ReportSchedule>,
+    resolver: &DnsResolver,
+    geoip_lookup: &GeoIpLookup,
+    template: &Template,
+) -> anyhow::Result<()> {
+    run_report(info, report_cycles, report_file, schedule, |trace| {
+        let report = make_report(info, trace, resolver, Some(geoip_lookup));
+        template.render(&serde_json::to_value(&report).unwrap())
+    })
+}
+
+/// Make a `Report` of trace data.
+pub fn make_report(
+    info: &TraceInfo,
+    trace: &Trace,
+    resolver: &DnsResolver,
+    geoip_lookup: Option<&GeoIpLookup>,
+) -> Report {
     let hops: Vec<ReportHop> = trace
         .hops()
         .iter()
-        .map(|hop| {
+        .enumerate()
+        .map(|(index, hop)| {
             let hosts: Vec<_> = hop
                 .addrs()
                 .map(|ip| Host {
                     ip: ip.to_string(),
                     hostname: resolver.reverse_lookup(*ip).to_string(),
+                    dns_ms: resolver.reverse_lookup_duration(*ip).map(format_ms),
+                    annotation: info.annotations.lookup(*ip).map(ToString::to_string),
+                    special_purpose: SpecialPurpose::lookup(*ip).map(|special| special.to_string()),
+                    first_seen: hop.addr_seen(ip).map(|seen| format_timestamp(seen.first)),
+                    last_seen: hop.addr_seen(ip).map(|seen| format_timestamp(seen.last)),
+                    off_path: hop.off_path(ip).map(|off_path| off_path.to_string()),
+                    stats: hop.addr_stats(ip).map(make_addr_stats),
                 })
                 .collect();
             ReportHop {
@@ -128,97 +627,909 @@ pub fn run_report_json(
                 sent: hop.total_sent(),
                 last: hop.last_ms().unwrap_or_default(),
                 recv: hop.total_recv(),
+                dup: hop.total_dup(),
+                corrupt: hop.total_corrupt(),
+                retries: hop.total_retries(),
+                late: hop.total_late(),
                 avg: hop.avg_ms(),
                 best: hop.best_ms().unwrap_or_default(),
                 worst: hop.worst_ms().unwrap_or_default(),
                 stddev: hop.stddev_ms(),
+                rate_limited: trace.is_rate_limited(hop),
+                icmp: hop.icmp_response().map(|icmp| icmp.to_string()),
+                reply_ttl: hop.reply_ttl(),
+                return_path_len: hop.return_path_len(),
+                ecn: info
+                    .ecn
+                    .and_then(|ecn| hop.ecn_status(ecn))
+                    .map(|status| status.to_string()),
+                dscp: info
+                    .dscp
+                    .and_then(|dscp| hop.dscp_status(dscp))
+                    .map(|status| status.to_string()),
+                tcp_handshake: hop.last_tcp_connect_ms().map(|last| ReportTcpHandshake {
+                    established: hop.tcp_established(),
+                    refused: hop.tcp_refused(),
+                    last,
+                    avg: hop.avg_tcp_connect_ms().unwrap_or_default(),
+                }),
+                recorded_addrs: hop.recorded_addrs().map(ToString::to_string).collect(),
+                timestamp: hop.last_timestamp().map(|timestamp| ReportTimestamp {
+                    originate: timestamp.originate,
+                    receive: timestamp.receive,
+                    transmit: timestamp.transmit,
+                    received: timestamp.received,
+                    forward_ms: timestamp.forward_ms(),
+                    return_ms: timestamp.return_ms(),
+                    asymmetry_ms: timestamp.asymmetry_ms(),
+                }),
+                geo: geoip_lookup.and_then(|lookup| make_geo(lookup, trace.hops(), index)),
+                samples: make_samples(hop),
             }
         })
         .collect();
+    let route_changes = trace
+        .route_changes()
+        .iter()
+        .map(|change| ReportRouteChange {
+            timestamp: format_timestamp(change.timestamp),
+            round: change.round + 1,
+            ttl: change.ttl,
+            from: change.from.to_string(),
+            to: change.to.to_string(),
+        })
+        .collect();
+    let as_path_changes = trace
+        .as_path_changes()
+        .iter()
+        .map(|change| ReportAsPathChange {
+            timestamp: format_timestamp(change.timestamp),
+            round: change.round + 1,
+            from: change.from.clone(),
+            to: change.to.clone(),
+        })
+        .collect();
+    Report {
+        info: make_info(info),
+        summary: make_summary(trace.summary(), trace.as_path()),
+        hops,
+        route_changes,
+        as_path_changes,
+        target_changes: make_target_changes(trace),
+    }
+}
 
-    let report = Report {
-        info: ReportInfo {
-            target: Host {
-                ip: info.target_addr.to_string(),
-                hostname: info.target_hostname.to_string(),
-            },
+/// Make the `ReportAddrStats` of the responses from a single address of a hop.
+fn make_addr_stats(stats: &AddrStats) -> ReportAddrStats {
+    ReportAddrStats {
+        loss_pct: stats.loss_pct(),
+        sent: stats.total_sent(),
+        recv: stats.total_recv(),
+        last: stats.last_ms().unwrap_or_default(),
+        avg: stats.avg_ms(),
+        best: stats.best_ms().unwrap_or_default(),
+        worst: stats.worst_ms().unwrap_or_default(),
+        stddev: stats.stddev_ms(),
+    }
+}
+
+/// Make the `ReportTargetChange` of each change of the address of the target of a trace.
+fn make_target_changes(trace: &Trace) -> Vec<ReportTargetChange> {
+    trace
+        .target_changes()
+        .iter()
+        .map(|change| ReportTargetChange {
+            timestamp: format_timestamp(change.timestamp),
+            round: change.round + 1,
+            from: change.from.to_string(),
+            to: change.to.to_string(),
+            followed: change.followed,
+        })
+        .collect()
+}
+
+/// Make the `ReportInfo` of the target and egress of a trace.
+fn make_info(info: &TraceInfo) -> ReportInfo {
+    ReportInfo {
+        target: Host {
+            ip: info.target_addr.to_string(),
+            hostname: info.target_hostname.to_string(),
+            dns_ms: Some(format_ms(info.target_lookup_duration)),
+            annotation: info
+                .annotations
+                .lookup(info.target_addr)
+                .map(ToString::to_string),
+            special_purpose: SpecialPurpose::lookup(info.target_addr)
+                .map(|special| special.to_string()),
+            first_seen: None,
+            last_seen: None,
+            off_path: None,
+            stats: None,
         },
-        hops,
-    };
-    println!("{}", serde_json::to_string_pretty(&report).unwrap());
-    Ok(())
+        egress: ReportEgress {
+            source: info.source_addr.to_string(),
+            interface: info.egress.interface.clone(),
+            gateway: info.egress.gateway.map(|gateway| gateway.to_string()),
+        },
+    }
+}
+
+/// Make a `ReportSample` of every probe recorded for a hop, where the `rtt_ms` of probes which timed out is `null`.
+fn make_geo(geoip_lookup: &GeoIpLookup, hops: &[Hop], index: usize) -> Option<ReportGeo> {
+    geo_distance(geoip_lookup, hops, index).map(|geo| ReportGeo {
+        from_ttl: geo.from_ttl,
+        distance_km: geo.distance_km,
+        uncertainty_km: geo.uncertainty_km,
+        min_rtt_ms: geo.min_rtt_ms(),
+        rtt_increase_ms: geo.rtt_increase_ms,
+        sanity: geo.sanity().map(|sanity| sanity.to_string()),
+    })
 }
 
-/// Generate a markdown table report of trace data.
+fn make_samples(hop: &Hop) -> Vec<ReportSample> {
+    hop.probes()
+        .iter()
+        .map(|probe| ReportSample {
+            sequence: probe.sequence,
+            round: probe.round + 1,
+            timestamp: probe.sent.map(format_timestamp),
+            rtt_ms: probe.rtt.map(format_ms),
+            host: probe.host.map(|host| host.to_string()),
+            late: probe.late,
+        })
+        .collect()
+}
+
+/// Make a `ReportSummary` of the trace as a whole.
+fn make_summary(summary: TraceSummary, as_path: &[String]) -> ReportSummary {
+    ReportSummary {
+        rounds: summary.total_rounds,
+        sent: summary.total_sent,
+        recv: summary.total_recv,
+        loss_pct: summary.loss_pct,
+        last_round_ms: summary.round_duration.map(format_ms),
+        path_len: summary.path_len,
+        as_path: as_path.to_vec(),
+    }
+}
+
+/// Format the summary of the trace as a whole, as shown below a table report.
+fn format_summary(summary: TraceSummary, rtt_format: RttFormat) -> String {
+    let last_round = summary.round_duration.map_or_else(
+        || String::from("n/a"),
+        |duration| rtt_format.format_with_unit(duration.as_secs_f64() * 1000_f64),
+    );
+    format!(
+        "Rounds: {}, Sent: {}, Recv: {}, Loss: {:.1}%, Last round: {last_round}, Path: {} hops",
+        summary.total_rounds,
+        summary.total_sent,
+        summary.total_recv,
+        summary.loss_pct,
+        summary.path_len
+    )
+}
+
+/// Generate a markdown table report of trace data, with the headers in the `locale` given.
+#[allow(clippy::too_many_arguments)]
 pub fn run_report_table_md(
     info: &TraceInfo,
     report_cycles: usize,
+    report_file: Option<&Path>,
+    schedule: Option<ReportSchedule>,
     resolver: &DnsResolver,
+    geoip_lookup: &GeoIpLookup,
+    rtt_format: RttFormat,
+    columns: &[ReportColumn],
+    per_address: bool,
+    locale: Locale,
 ) -> anyhow::Result<()> {
-    run_report_table(info, report_cycles, resolver, ASCII_MARKDOWN)
+    run_report(info, report_cycles, report_file, schedule, |trace| {
+        render_report_table(
+            info,
+            trace,
+            resolver,
+            geoip_lookup,
+            rtt_format,
+            columns,
+            per_address,
+            TableStyle::MARKDOWN,
+            locale,
+        )
+    })
 }
 
-/// Generate a pretty table report of trace data.
+/// Generate a pretty table report of trace data, drawn in the `style` given and with the headers in the `locale` given.
+#[allow(clippy::too_many_arguments)]
 pub fn run_report_table_pretty(
     info: &TraceInfo,
     report_cycles: usize,
+    report_file: Option<&Path>,
+    schedule: Option<ReportSchedule>,
     resolver: &DnsResolver,
+    geoip_lookup: &GeoIpLookup,
+    rtt_format: RttFormat,
+    columns: &[ReportColumn],
+    per_address: bool,
+    style: TableStyle,
+    locale: Locale,
 ) -> anyhow::Result<()> {
-    run_report_table(info, report_cycles, resolver, UTF8_FULL)
+    run_report(info, report_cycles, report_file, schedule, |trace| {
+        render_report_table(
+            info,
+            trace,
+            resolver,
+            geoip_lookup,
+            rtt_format,
+            columns,
+            per_address,
+            style,
+            locale,
+        )
+    })
 }
 
-fn run_report_table(
+/// The style of a table report.
+#[derive(Debug, Clone, Copy)]
+pub struct TableStyle {
+    preset: &'static str,
+    width: Option<u16>,
+    overflow: ReportOverflow,
+}
+
+impl TableStyle {
+    /// The style of a markdown table report.
+    const MARKDOWN: Self = Self {
+        preset: ASCII_MARKDOWN,
+        width: None,
+        overflow: ReportOverflow::Wrap,
+    };
+
+    /// The style of a pretty table report with a `border`, at most `width` wide if given and where cells which are
+    /// wider than their column `overflow` as given.
+    pub fn pretty(border: ReportBorder, width: Option<u16>, overflow: ReportOverflow) -> Self {
+        Self {
+            preset: match border {
+                ReportBorder::Unicode => UTF8_FULL,
+                ReportBorder::Ascii => ASCII_FULL,
+                ReportBorder::None => NOTHING,
+            },
+            width,
+            overflow,
+        }
+    }
+}
+
+/// Render a table report of trace data.
+///
+/// If `per_address` is set then each hop with several addresses is followed by a row for each address, with the
+/// statistics of the responses from that address.
+#[allow(clippy::too_many_arguments)]
+fn render_report_table(
     info: &TraceInfo,
+    trace: &Trace,
+    resolver: &DnsResolver,
+    geoip_lookup: &GeoIpLookup,
+    rtt_format: RttFormat,
+    columns: &[ReportColumn],
+    per_address: bool,
+    style: TableStyle,
+    locale: Locale,
+) -> String {
+    let header = TABLE_HEADER
+        .into_iter()
+        .chain(columns.iter().map(|column| column_header(*column)))
+        .map(|header| translate(locale, header));
+    let mut table = Table::new();
+    table
+        .load_preset(style.preset)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(header);
+    if let Some(width) = style.width {
+        table.set_width(width);
+    }
+    table.set_constraints((0..=columns.len() + 1).map(|index| {
+        if index == 1 {
+            ColumnConstraint::LowerBoundary(Width::Fixed(MIN_ADDRS_WIDTH))
+        } else {
+            ColumnConstraint::ContentWidth
+        }
+    }));
+    for (index, hop) in trace.hops().iter().enumerate() {
+        let ttl = hop.ttl().to_string();
+        let icmp_flag = hop
+            .icmp_response()
+            .and_then(IcmpResponse::flag)
+            .map(|flag| format!(" [{flag}]"))
+            .unwrap_or_default();
+        let hosts = hop
+            .addrs()
+            .map(|ip| match hop.off_path(ip) {
+                Some(off_path) => format!(
+                    "{} [off-path {off_path}]{icmp_flag}",
+                    format_host(info, resolver, *ip)
+                ),
+                None => format!("{}{icmp_flag}", format_host(info, resolver, *ip)),
+            })
+            .join("\n");
+        let host = if hosts.is_empty() {
+            String::from("???")
+        } else {
+            hosts
+        };
+        let recorded = hop.unseen_recorded_addrs().join(", ");
+        let (host, height) = if recorded.is_empty() {
+            (host, hop.addr_count().max(1))
+        } else {
+            (
+                format!("{host}\nrr: {recorded}"),
+                hop.addr_count().max(1) + 1,
+            )
+        };
+        let geo = geo_distance(geoip_lookup, trace.hops(), index)
+            .and_then(|geo| Some((geo.sanity()?, geo)));
+        let (host, height) = match geo {
+            Some((sanity, geo)) => (
+                format!("{host}\ngeo: {sanity}, {}", geo.summary(rtt_format)),
+                height + 1,
+            ),
+            None => (host, height),
+        };
+        let values = columns
+            .iter()
+            .map(|column| format_column(hop, *column, rtt_format));
+        let mut row = Row::from([ttl, host].into_iter().chain(values));
+        if style.overflow == ReportOverflow::Truncate {
+            row.max_height(height);
+        }
+        table.add_row(row);
+        if per_address && hop.addr_count() > 1 {
+            for addr in hop.addrs() {
+                let Some(stats) = hop.addr_stats(addr) else {
+                    continue;
+                };
+                let values = columns
+                    .iter()
+                    .map(|column| format_addr_column(stats, *column, rtt_format));
+                let host = format!("  {}", format_host(info, resolver, *addr));
+                table.add_row(Row::from([String::new(), host].into_iter().chain(values)));
+            }
+        }
+    }
+    format!("{table}\n{}\n", format_summary(trace.summary(), rtt_format))
+}
+
+/// Generate a table report of how far the probes of each trace reached towards its target after `report_cycles`
+/// rounds, drawn with ASCII characters only if `ascii` is set.
+///
+/// Each row is a trace, such as for each port of `target-ports`, and shows whether its target was reached or else the
+/// hop which rejected, or the last hop which forwarded, the probes.
+pub fn run_report_ports(
+    traces: &[TraceInfo],
     report_cycles: usize,
+    report_file: Option<&Path>,
     resolver: &DnsResolver,
-    preset: &str,
+    ascii: bool,
 ) -> anyhow::Result<()> {
-    let trace = wait_for_round(&info.data, report_cycles)?;
-    let columns = vec![
-        "Hop", "Addrs", "Loss%", "Snt", "Recv", "Last", "Avg", "Best", "Wrst", "StdDev",
-    ];
+    let traces = wait_for_traces(traces, report_cycles)?;
+    let preset = if ascii { ASCII_FULL } else { UTF8_FULL };
+    print_or_write_report(report_file, &render_report_ports(&traces, resolver, preset))
+}
+
+/// Render a table report of how far the probes of each trace reached towards its target.
+fn render_report_ports(
+    traces: &[(&TraceInfo, Trace)],
+    resolver: &DnsResolver,
+    preset: &str,
+) -> String {
+    let columns = vec!["Target", "Protocol", "Port", "Status", "Hop", "Addrs"];
     let mut table = Table::new();
     table
         .load_preset(preset)
         .set_content_arrangement(ContentArrangement::Dynamic)
         .set_header(columns);
-    for hop in trace.hops().iter() {
-        let ttl = hop.ttl().to_string();
+    for (info, trace) in traces {
+        let target = if info.target_hostname == info.target_addr.to_string() {
+            info.target_hostname.clone()
+        } else {
+            format!("{} ({})", info.target_hostname, info.target_addr)
+        };
+        let protocol = info.protocol.to_string();
+        let port = match info.port_direction {
+            PortDirection::FixedDest(port) | PortDirection::FixedBoth(_, port) => {
+                port.0.to_string()
+            }
+            PortDirection::FixedSrc(_) | PortDirection::None => String::from("-"),
+        };
+        let reachability = trace.reachability(info.target_addr);
+        let hop = reachability
+            .ttl()
+            .and_then(|ttl| trace.hops().iter().find(|hop| hop.ttl() == ttl));
+        let ttl = hop.map_or_else(|| String::from("-"), |hop| hop.ttl().to_string());
         let hosts = hop
-            .addrs()
-            .map(|ip| resolver.reverse_lookup(*ip).to_string())
-            .join("\n");
+            .map(|hop| {
+                hop.addrs()
+                    .map(|ip| format_host(info, resolver, *ip))
+                    .join("\n")
+            })
+            .unwrap_or_default();
         let host = if hosts.is_empty() {
             String::from("???")
         } else {
             hosts
         };
-        let sent = hop.total_sent().to_string();
-        let recv = hop.total_recv().to_string();
-        let last = hop
-            .last_ms()
-            .map_or_else(|| String::from("???"), |last| format!("{last:.1}"));
-        let best = hop
-            .best_ms()
-            .map_or_else(|| String::from("???"), |best| format!("{best:.1}"));
-        let worst = hop
-            .worst_ms()
-            .map_or_else(|| String::from("???"), |worst| format!("{worst:.1}"));
-        let stddev = format!("{:.1}", hop.stddev_ms());
-        let avg = format!("{:.1}", hop.avg_ms());
-        let loss_pct = format!("{:.1}", hop.loss_pct());
+        let status = reachability.to_string();
+        table.add_row(vec![&target, &protocol, &port, &status, &ttl, &host]);
+    }
+    format!("{table}\n")
+}
+
+/// Generate a matrix report of the protocols and ports forwarded by the gateway hop after `report_cycles` rounds, drawn
+/// with ASCII characters only if `ascii` is set.
+///
+/// The traces probe the hop just past the gateway, for each target, with `icmp` and with `tcp` and `udp` to each port.
+/// Traffic is `forwarded` if any hop past the gateway (or the target) responded, `rejected` if the gateway responded
+/// that the target is unreachable and `filtered` if there was no response.
+pub fn run_report_firewalk(
+    traces: &[TraceInfo],
+    report_cycles: usize,
+    report_file: Option<&Path>,
+    gateway: u8,
+    ascii: bool,
+) -> anyhow::Result<()> {
+    let traces = wait_for_traces(traces, report_cycles)?;
+    let preset = if ascii { ASCII_FULL } else { UTF8_FULL };
+    let mut table = Table::new();
+    table
+        .load_preset(preset)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Target", "Port", "icmp", "tcp", "udp"]);
+    let rows = firewalk_rows(traces.iter().map(|(info, trace)| {
+        let port = match info.port_direction {
+            PortDirection::FixedDest(port) | PortDirection::FixedBoth(_, port) => Some(port.0),
+            PortDirection::FixedSrc(_) | PortDirection::None => None,
+        };
+        (
+            info.target_addr,
+            port,
+            info.protocol,
+            trace.reachability(info.target_addr),
+        )
+    }));
+    for (target_addr, port, cells) in rows {
+        let port = port.map_or_else(|| String::from("-"), |port| port.to_string());
         table.add_row(vec![
-            &ttl, &host, &loss_pct, &sent, &recv, &last, &avg, &best, &worst, &stddev,
+            &target_addr.to_string(),
+            &port,
+            cells[0],
+            cells[1],
+            cells[2],
         ]);
     }
-    println!("{table}");
-    Ok(())
+    let report = format!(
+        "Firewalking past hop {gateway} (ttl {})\n{table}\n",
+        gateway + 1
+    );
+    print_or_write_report(report_file, &report)
+}
+
+/// The verdict of a firewalk for a trace which reached as far as `reachability`.
+///
+/// Probes are `forwarded` past the gateway if any hop beyond it responded, `rejected` if a hop responded that the
+/// target is unreachable and `filtered` if no hop responded.
+fn firewalk_verdict(reachability: Reachability) -> &'static str {
+    match reachability {
+        Reachability::Open(_)
+        | Reachability::Closed(_)
+        | Reachability::Reached(_)
+        | Reachability::Filtered(Some(_)) => "forwarded",
+        Reachability::Rejected(_) => "rejected",
+        Reachability::Filtered(None) => "filtered",
+    }
+}
+
+/// Group the firewalk verdicts of traces by target and port, with one `icmp`, `tcp` and `udp` cell per row.
+///
+/// Rows are sorted by target and port and the cell of a protocol which was not traced is empty.
+fn firewalk_rows(
+    traces: impl IntoIterator<Item = (IpAddr, Option<u16>, TracerProtocol, Reachability)>,
+) -> Vec<(IpAddr, Option<u16>, [&'static str; 3])> {
+    traces
+        .into_iter()
+        .map(|(target_addr, port, protocol, reachability)| {
+            (
+                (target_addr, port),
+                (protocol, firewalk_verdict(reachability)),
+            )
+        })
+        .into_group_map()
+        .into_iter()
+        .sorted_by_key(|(key, _)| *key)
+        .map(|((target_addr, port), verdicts)| {
+            let mut cells = [""; 3];
+            for (protocol, verdict) in verdicts {
+                let column = match protocol {
+                    TracerProtocol::Icmp => 0,
+                    TracerProtocol::Tcp => 1,
+                    TracerProtocol::Udp => 2,
+                };
+                cells[column] = verdict;
+            }
+            (target_addr, port, cells)
+        })
+        .collect()
+}
+
+/// Generate a table report, for each trace, of the hops which forwarded probes with an `IPv6` extension header after
+/// `report_cycles` rounds, drawn with ASCII characters only if `ascii` is set.
+///
+/// Every trace is traced both without and with the extension header and so each row compares the responses of a hop to
+/// the probes without it to those with it.  Probes with the extension header are `forwarded` to a hop if it responded
+/// to them and `dropped` if it only responded to the probes without it.
+pub fn run_report_ext_header(
+    traces: &[TraceInfo],
+    report_cycles: usize,
+    report_file: Option<&Path>,
+    resolver: &DnsResolver,
+    ascii: bool,
+) -> anyhow::Result<()> {
+    let traces = wait_for_traces(traces, report_cycles)?;
+    let preset = if ascii { ASCII_FULL } else { UTF8_FULL };
+    let mut report = String::new();
+    // each trace without the extension header is followed by the same trace with it
+    for pair in traces.chunks_exact(2) {
+        let [(info, without), (with_info, with)] = pair else {
+            continue;
+        };
+        let header = with_info
+            .ipv6_ext_header
+            .map_or_else(String::new, |header| header.to_string());
+        let mut table = Table::new();
+        table
+            .load_preset(preset)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec![
+                String::from("Hop"),
+                String::from("Addrs"),
+                String::from("Without"),
+                format!("With {header}"),
+                String::from("Status"),
+            ]);
+        let max_ttl = without
+            .hops()
+            .iter()
+            .chain(with.hops())
+            .map(Hop::ttl)
+            .max()
+            .unwrap_or_default();
+        for ttl in 1..=max_ttl {
+            let hop_without = without.hops().iter().find(|hop| hop.ttl() == ttl);
+            let hop_with = with.hops().iter().find(|hop| hop.ttl() == ttl);
+            let hosts = hop_without
+                .into_iter()
+                .chain(hop_with)
+                .flat_map(Hop::addrs)
+                .unique()
+                .map(|addr| format_host(info, resolver, *addr))
+                .join("\n");
+            let host = if hosts.is_empty() {
+                String::from("???")
+            } else {
+                hosts
+            };
+            let counts =
+                |hop: Option<&Hop>| hop.map_or((0, 0), |hop| (hop.total_recv(), hop.total_sent()));
+            let ((recv_without, sent_without), (recv_with, sent_with)) =
+                (counts(hop_without), counts(hop_with));
+            let status = match (recv_without > 0, recv_with > 0) {
+                (_, true) => "forwarded",
+                (true, false) => "dropped",
+                (false, false) => "-",
+            };
+            table.add_row(vec![
+                ttl.to_string(),
+                host,
+                format!("{recv_without}/{sent_without}"),
+                format!("{recv_with}/{sent_with}"),
+                String::from(status),
+            ]);
+        }
+        let _ = writeln!(
+            report,
+            "{} ({}) {}{} with {header}: {}\n{table}",
+            info.target_hostname,
+            info.target_addr,
+            info.protocol,
+            format_port(info),
+            ext_header_verdict(without, with, info.target_addr),
+        );
+    }
+    print_or_write_report(report_file, &report)
+}
+
+/// Format the fixed destination port of a trace, if any.
+fn format_port(info: &TraceInfo) -> String {
+    match info.port_direction {
+        PortDirection::FixedDest(port) | PortDirection::FixedBoth(_, port) => {
+            format!(" port {}", port.0)
+        }
+        PortDirection::FixedSrc(_) | PortDirection::None => String::new(),
+    }
+}
+
+/// Generate a table report of the DSCP of the probes of each trace as received by each hop after `report_cycles`
+/// rounds, drawn with ASCII characters only if `ascii` is set.
+///
+/// The DSCP of the probes as received by a hop is read from the original datagram quoted in its `ICMP` response, and so
+/// is not known for hops which do not quote the probes, such as the target of an `ICMP` trace.
+pub fn run_report_qos(
+    traces: &[TraceInfo],
+    report_cycles: usize,
+    report_file: Option<&Path>,
+    resolver: &DnsResolver,
+    ascii: bool,
+) -> anyhow::Result<()> {
+    let traces = wait_for_traces(traces, report_cycles)?;
+    let preset = if ascii { ASCII_FULL } else { UTF8_FULL };
+    let mut report = String::new();
+    for (info, trace) in &traces {
+        let Some(sent) = info.dscp else {
+            continue;
+        };
+        let mut table = Table::new();
+        table
+            .load_preset(preset)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec!["Hop", "Addrs", "Received", "Status"]);
+        for hop in trace.hops() {
+            let hosts = hop
+                .addrs()
+                .map(|ip| format_host(info, resolver, *ip))
+                .join("\n");
+            let host = if hosts.is_empty() {
+                String::from("???")
+            } else {
+                hosts
+            };
+            let (received, status) = match hop.dscp_status(sent) {
+                Some(status @ DscpStatus::Preserved) => (sent.to_string(), status.to_string()),
+                Some(status @ DscpStatus::Bleached) => (Dscp(0).to_string(), status.to_string()),
+                Some(status @ DscpStatus::Remarked(dscp)) => (dscp.to_string(), status.to_string()),
+                None => (String::from("-"), String::from("-")),
+            };
+            table.add_row(vec![hop.ttl().to_string(), host, received, status]);
+        }
+        let _ = writeln!(
+            report,
+            "{} ({}) {}{} with dscp {sent}: {}\n{table}",
+            info.target_hostname,
+            info.target_addr,
+            info.protocol,
+            format_port(info),
+            qos_verdict(trace, sent),
+        );
+    }
+    print_or_write_report(report_file, &report)
+}
+
+/// Generate a table report, for each trace, of the loss and average latency of each hop for each packet size of the
+/// sweep after `report_cycles` rounds, drawn with ASCII characters only if `ascii` is set.
+///
+/// Every trace is traced once with each of the `sizes` packet sizes, smallest first, and so each row compares the
+/// responses of a hop across the packet sizes.  A hop which responds to the smaller probes but not to the larger ones
+/// reveals size-dependent handling on the path, such as an MTU which is smaller than the larger probes.
+pub fn run_report_size_sweep(
+    traces: &[TraceInfo],
+    sizes: usize,
+    report_cycles: usize,
+    report_file: Option<&Path>,
+    resolver: &DnsResolver,
+    ascii: bool,
+) -> anyhow::Result<()> {
+    let traces = wait_for_traces(traces, report_cycles)?;
+    let preset = if ascii { ASCII_FULL } else { UTF8_FULL };
+    let mut report = String::new();
+    for sweep in traces.chunks_exact(sizes.max(1)) {
+        let Some((info, _)) = sweep.first() else {
+            continue;
+        };
+        let mut table = Table::new();
+        table
+            .load_preset(preset)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(
+                ["Hop", "Addrs"]
+                    .into_iter()
+                    .map(String::from)
+                    .chain(
+                        sweep
+                            .iter()
+                            .map(|(info, _)| format!("{} bytes", info.packet_size)),
+                    )
+                    .chain(std::iter::once(String::from("Status"))),
+            );
+        let max_ttl = sweep
+            .iter()
+            .flat_map(|(_, trace)| trace.hops())
+            .map(Hop::ttl)
+            .max()
+            .unwrap_or_default();
+        for ttl in 1..=max_ttl {
+            let hops: Vec<_> = sweep
+                .iter()
+                .map(|(info, trace)| {
+                    let hop = trace.hops().iter().find(|hop| hop.ttl() == ttl);
+                    (info.packet_size, hop)
+                })
+                .collect();
+            let hosts = hops
+                .iter()
+                .filter_map(|(_, hop)| *hop)
+                .flat_map(Hop::addrs)
+                .unique()
+                .map(|addr| format_host(info, resolver, *addr))
+                .join("\n");
+            let host = if hosts.is_empty() {
+                String::from("???")
+            } else {
+                hosts
+            };
+            let cells = hops.iter().map(|(_, hop)| match hop {
+                Some(hop) if hop.total_recv() > 0 => {
+                    format!("{:.1}% {:.1}ms", hop.loss_pct(), hop.avg_ms())
+                }
+                Some(hop) if hop.total_sent() > 0 => String::from("100.0%"),
+                _ => String::from("-"),
+            });
+            table.add_row(
+                std::iter::once(ttl.to_string())
+                    .chain(std::iter::once(host))
+                    .chain(cells)
+                    .chain(std::iter::once(size_sweep_status(&hops))),
+            );
+        }
+        let _ = writeln!(
+            report,
+            "{} ({}) {}{}: {}\n{table}",
+            info.target_hostname,
+            info.target_addr,
+            info.protocol,
+            format_port(info),
+            size_sweep_verdict(sweep, info.target_addr),
+        );
+    }
+    print_or_write_report(report_file, &report)
+}
+
+/// Describe how a hop responded to the probes of each packet size of the sweep.
+fn size_sweep_status(hops: &[(u16, Option<&Hop>)]) -> String {
+    let responded: Vec<_> = hops
+        .iter()
+        .map(|(size, hop)| (*size, hop.is_some_and(|hop| hop.total_recv() > 0)))
+        .collect();
+    if responded.iter().all(|(_, responded)| *responded) {
+        String::from("all sizes")
+    } else if responded.iter().all(|(_, responded)| !*responded) {
+        String::from("-")
+    } else {
+        match responded.iter().position(|(_, responded)| !*responded) {
+            Some(first) if responded[first..].iter().all(|(_, responded)| !*responded) => {
+                format!("none from {} bytes", responded[first].0)
+            }
+            _ => String::from("intermittent"),
+        }
+    }
+}
+
+/// Describe the largest packet size of the sweep which reached the target.
+fn size_sweep_verdict(sweep: &[(&TraceInfo, Trace)], target_addr: IpAddr) -> String {
+    let reached = |trace: &Trace| {
+        matches!(
+            trace.reachability(target_addr),
+            Reachability::Open(_) | Reachability::Closed(_) | Reachability::Reached(_)
+        )
+    };
+    let reached_sizes: Vec<_> = sweep
+        .iter()
+        .filter(|(_, trace)| reached(trace))
+        .map(|(info, _)| info.packet_size)
+        .collect();
+    match (reached_sizes.len(), reached_sizes.last()) {
+        (count, _) if count == sweep.len() => String::from("every packet size reached the target"),
+        (_, Some(largest)) => format!("packet sizes up to {largest} bytes reached the target"),
+        (_, None) => String::from("no packet size reached the target"),
+    }
+}
+
+/// Describe the first hop to receive the probes with a DSCP other than `sent`, if any.
+fn qos_verdict(trace: &Trace, sent: Dscp) -> String {
+    let statuses: Vec<_> = trace
+        .hops()
+        .iter()
+        .filter_map(|hop| Some((hop.ttl(), hop.dscp_status(sent)?)))
+        .collect();
+    match statuses
+        .iter()
+        .find(|(_, status)| *status != DscpStatus::Preserved)
+    {
+        Some((ttl, status)) => format!("{status} as received by hop {ttl}"),
+        None => match statuses.last() {
+            Some((ttl, _)) => format!("preserved to hop {ttl}"),
+            None => String::from("no hop quoted the probes"),
+        },
+    }
+}
+
+/// Describe how far the probes with an `IPv6` extension header reached compared to the probes without it.
+fn ext_header_verdict(without: &Trace, with: &Trace, target_addr: IpAddr) -> String {
+    let last_responded = |trace: &Trace| {
+        trace
+            .hops()
+            .iter()
+            .filter(|hop| hop.total_recv() > 0)
+            .map(Hop::ttl)
+            .max()
+    };
+    match (
+        with.reachability(target_addr),
+        last_responded(without),
+        last_responded(with),
+    ) {
+        (Reachability::Open(_) | Reachability::Closed(_) | Reachability::Reached(_), _, _) => {
+            String::from("forwarded to the target")
+        }
+        (_, Some(last_without), Some(last_with)) if last_with < last_without => {
+            format!("dropped after hop {last_with}")
+        }
+        (_, Some(_), None) => String::from("dropped before any hop responded"),
+        _ => String::from("no different to probes without it"),
+    }
+}
+
+/// Block until trace data for `report_cycles` rounds of every trace is available, or the deadline has passed.
+fn wait_for_traces(
+    traces: &[TraceInfo],
+    report_cycles: usize,
+) -> anyhow::Result<Vec<(&TraceInfo, Trace)>> {
+    let deadline = traces.first().and_then(deadline);
+    traces
+        .iter()
+        .map(|info| {
+            let trace = wait_for_round(info, report_cycles - 1, deadline, |_| Ok(()))?;
+            Ok((info, trace))
+        })
+        .collect()
+}
+
+/// Print the report or, if a `report_file` is given, write it to the file.
+fn print_or_write_report(report_file: Option<&Path>, report: &str) -> anyhow::Result<()> {
+    if let Some(path) = report_file {
+        write_report_file(path, report)
+    } else {
+        print!("{report}");
+        Ok(())
+    }
 }
 
 /// Display a continuous stream of trace data.
-pub fn run_report_stream(info: &TraceInfo) -> anyhow::Result<()> {
-    println!("Tracing to {} ({})", info.target_hostname, info.target_addr);
-    loop {
+///
+/// The status of each hop is included if `plain` is set.
+pub fn run_report_stream(
+    info: &TraceInfo,
+    timestamp_mode: TimestampMode,
+    timestamp_format: &str,
+    rtt_format: RttFormat,
+    plain: bool,
+    interrupted: Option<&AtomicBool>,
+) -> anyhow::Result<()> {
+    println!(
+        "{}Tracing to {} ({})",
+        timestamp_prefix(timestamp_mode, timestamp_format),
+        info.target_hostname,
+        info.target_addr
+    );
+    let deadline = deadline(info);
+    let is_interrupted = || interrupted.is_some_and(|flag| flag.load(Ordering::Relaxed));
+    while !is_expired(deadline) && !is_interrupted() {
         let trace_data = &info.data.read().clone();
         if let Some(err) = trace_data.error() {
             return Err(anyhow!("error: {}", err));
@@ -230,32 +1541,275 @@ pub fn run_report_stream(info: &TraceInfo) -> anyhow::Result<()> {
             let recv = hop.total_recv();
             let last = hop
                 .last_ms()
-                .map(|last| format!("{last:.1}"))
+                .map(|last| rtt_format.format(last))
                 .unwrap_or_default();
             let best = hop
                 .best_ms()
-                .map(|best| format!("{best:.1}"))
+                .map(|best| rtt_format.format(best))
                 .unwrap_or_default();
             let worst = hop
                 .worst_ms()
-                .map(|worst| format!("{worst:.1}"))
+                .map(|worst| rtt_format.format(worst))
                 .unwrap_or_default();
-            let stddev = hop.stddev_ms();
-            let avg = hop.avg_ms();
+            let stddev = rtt_format.format(hop.stddev_ms());
+            let avg = rtt_format.format(hop.avg_ms());
             let loss_pct = hop.loss_pct();
+            let timestamp = timestamp_prefix(timestamp_mode, timestamp_format);
+            let status = if plain {
+                format!(" status={}", trace_data.hop_status(hop))
+            } else {
+                String::new()
+            };
             println!(
-                "ttl={ttl} addrs={addrs:?} loss_pct={loss_pct:.1}, sent={sent} recv={recv} last={last} best={best} worst={worst} avg={avg:.1} stddev={stddev:.1}"
+                "{timestamp}ttl={ttl} addrs={addrs:?} loss_pct={loss_pct:.1}, sent={sent} recv={recv} last={last} best={best} worst={worst} avg={avg} stddev={stddev}{status}"
             );
         }
+        check_unreachable(info, trace_data)?;
         sleep(info.min_round_duration);
     }
+    Ok(())
+}
+
+/// Catch interrupts (such as Ctrl+C), such that the returned flag is set rather than the process terminated.
+///
+/// A second interrupt, received after the flag is set, terminates the process as usual.
+pub fn interrupt_flag() -> anyhow::Result<Arc<AtomicBool>> {
+    let flag = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register_conditional_shutdown(SIGINT, 1, flag.clone())?;
+    signal_hook::flag::register(SIGINT, flag.clone())?;
+    Ok(flag)
+}
+
+/// Print a summary of each trace, which has been running for `elapsed`, to stdout.
+pub fn print_summary(traces: &[TraceInfo], elapsed: Duration, rtt_format: RttFormat) {
+    for info in traces {
+        print!(
+            "{}",
+            render_summary(info, &info.data.read(), elapsed, rtt_format)
+        );
+    }
 }
 
-/// Block until trace data for round `round` is available.
-fn wait_for_round(trace_data: &Arc<RwLock<Trace>>, report_cycles: usize) -> anyhow::Result<Trace> {
-    let mut trace = trace_data.read().clone();
-    while trace.round().is_none() || trace.round() < Some(report_cycles - 1) {
-        trace = trace_data.read().clone();
+/// Render a summary of the path, loss and round-trip times of a trace and the route changes observed.
+fn render_summary(
+    info: &TraceInfo,
+    trace: &Trace,
+    elapsed: Duration,
+    rtt_format: RttFormat,
+) -> String {
+    let summary = trace.summary();
+    let target_addr = trace.target_addr().unwrap_or(info.target_addr);
+    let elapsed = humantime::format_duration(Duration::from_secs(elapsed.as_secs()));
+    let mut out = format!(
+        "{} ({target_addr}): {} rounds in {elapsed}, loss {:.1}%, {} route changes\n",
+        info.target_hostname,
+        summary.total_rounds,
+        summary.loss_pct,
+        trace.total_route_changes()
+    );
+    for hop in trace.hops() {
+        let addrs = if hop.addr_count() == 0 {
+            String::from("*")
+        } else {
+            hop.addrs().join(" ")
+        };
+        let rtts = match (hop.best_ms(), hop.worst_ms()) {
+            (Some(best), Some(worst)) => format!(
+                " best={} avg={} worst={}",
+                rtt_format.format_with_unit(best),
+                rtt_format.format_with_unit(hop.avg_ms()),
+                rtt_format.format_with_unit(worst)
+            ),
+            _ => String::new(),
+        };
+        let _ = writeln!(
+            out,
+            "{:>3} {addrs} loss={:.1}%{rtts}",
+            hop.ttl(),
+            hop.loss_pct()
+        );
+    }
+    for change in trace.route_changes() {
+        let _ = writeln!(
+            out,
+            "    route change in round {}: ttl={} {} -> {}",
+            change.round, change.ttl, change.from, change.to
+        );
+    }
+    out
+}
+
+/// Format the current wall-clock time as a line prefix, if enabled.
+fn timestamp_prefix(timestamp_mode: TimestampMode, timestamp_format: &str) -> String {
+    match timestamp_mode {
+        TimestampMode::Off => String::new(),
+        TimestampMode::Local => format!("{} ", Local::now().format(timestamp_format)),
+        TimestampMode::Utc => format!("{} ", Utc::now().format(timestamp_format)),
+    }
+}
+
+/// The schedule of a repeated report.
+#[derive(Debug, Copy, Clone)]
+pub struct ReportSchedule {
+    /// The interval between the start of each report.
+    pub every: Duration,
+    /// The number of reports to generate, or unlimited if `None`.
+    pub count: Option<usize>,
+}
+
+/// Generate a report after `report_cycles` rounds and print it or write it to `report_file`.
+///
+/// If a `report_file` is given then it is also refreshed with the partial report after every completed round.
+///
+/// If a `schedule` is given then a report is generated repeatedly as per `run_scheduled_report`.
+fn run_report<F>(
+    info: &TraceInfo,
+    report_cycles: usize,
+    report_file: Option<&Path>,
+    schedule: Option<ReportSchedule>,
+    render: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&Trace) -> String,
+{
+    match schedule {
+        None => run_report_once(info, 0, report_cycles, report_file, deadline(info), &render),
+        Some(schedule) => run_scheduled_report(info, report_cycles, report_file, schedule, &render),
+    }
+}
+
+/// Generate a report every `schedule.every`, until `schedule.count` reports have been generated or the maximum
+/// duration has elapsed.
+///
+/// The statistics are cleared before each report and tracing is paused between reports.  Each report is printed after
+/// a timestamp heading or, if a `report_file` is given, is written to a file with the timestamp of the report appended
+/// to its name.
+fn run_scheduled_report<F>(
+    info: &TraceInfo,
+    report_cycles: usize,
+    report_file: Option<&Path>,
+    schedule: ReportSchedule,
+    render: &F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&Trace) -> String,
+{
+    let deadline = deadline(info);
+    let start = Instant::now();
+    let mut next = start;
+    for index in 1.. {
+        let start_round = {
+            let mut trace = info.data.write();
+            let start_round = trace.round().map_or(0, |round| round + 1);
+            trace.clear();
+            trace.set_paused(false);
+            start_round
+        };
+        let now = Local::now();
+        let path = report_file.map(|path| timestamped_path(path, now));
+        if path.is_none() {
+            println!("# {}", now.to_rfc3339_opts(SecondsFormat::Secs, false));
+        }
+        run_report_once(
+            info,
+            start_round,
+            report_cycles,
+            path.as_deref(),
+            deadline,
+            render,
+        )?;
+        info.data.write().set_paused(true);
+        if schedule.count.is_some_and(|count| index >= count) {
+            break;
+        }
+        next += schedule.every;
+        let wait = next.saturating_duration_since(Instant::now());
+        sleep(deadline.map_or(wait, |deadline| {
+            wait.min(deadline.saturating_duration_since(Instant::now()))
+        }));
+        if is_expired(deadline) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Append a timestamp to the name of a report file, such as `report-20230501T120000.json` for `report.json`.
+fn timestamped_path(path: &Path, timestamp: DateTime<Local>) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(timestamp.format("-%Y%m%dT%H%M%S").to_string());
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+/// Generate a report after `report_cycles` rounds, starting from round `start_round`, and print it or write it to
+/// `report_file`.
+fn run_report_once<F>(
+    info: &TraceInfo,
+    start_round: usize,
+    report_cycles: usize,
+    report_file: Option<&Path>,
+    deadline: Option<Instant>,
+    render: &F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&Trace) -> String,
+{
+    let final_round = start_round + report_cycles - 1;
+    if let Some(path) = report_file {
+        let trace = wait_for_round(info, final_round, deadline, |trace| {
+            write_report_file(path, &render(trace))
+        })?;
+        write_report_file(path, &render(&trace))?;
+        Ok(check_unreachable(info, &trace)?)
+    } else {
+        let trace = wait_for_round(info, final_round, deadline, |_| Ok(()))?;
+        print!("{}", render(&trace));
+        Ok(check_unreachable(info, &trace)?)
+    }
+}
+
+/// Atomically replace the contents of the report file.
+///
+/// The report is written to a temporary file alongside `path` which is then renamed over it such that readers never
+/// observe a partially written report.
+fn write_report_file(path: &Path, report: &str) -> anyhow::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    fs::write(&tmp_path, report)
+        .with_context(|| format!("failed to write report file: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("failed to replace report file: {}", path.display()))?;
+    Ok(())
+}
+
+/// Block until trace data for round `final_round` is available, the `deadline` has passed or the target is unreachable.
+///
+/// The `on_round` callback is invoked with the trace data each time an earlier round completes.
+fn wait_for_round<F>(
+    info: &TraceInfo,
+    final_round: usize,
+    deadline: Option<Instant>,
+    mut on_round: F,
+) -> anyhow::Result<Trace>
+where
+    F: FnMut(&Trace) -> anyhow::Result<()>,
+{
+    let mut trace = info.data.read().clone();
+    let mut last_round = None;
+    while (trace.round().is_none() || trace.round() < Some(final_round))
+        && !is_expired(deadline)
+        && check_unreachable(info, &trace).is_ok()
+    {
+        if trace.round().is_some() && trace.round() != last_round {
+            on_round(&trace)?;
+            last_round = trace.round();
+        }
+        trace = info.data.read().clone();
         if let Some(err) = trace.error() {
             return Err(anyhow!("error: {}", err));
         }
@@ -263,3 +1817,103 @@ fn wait_for_round(trace_data: &Arc<RwLock<Trace>>, report_cycles: usize) -> anyh
     }
     Ok(trace)
 }
+
+/// The instant at which to stop tracing, if a maximum duration was configured.
+fn deadline(info: &TraceInfo) -> Option<Instant> {
+    info.max_duration.map(|max| Instant::now() + max)
+}
+
+/// Has the `deadline` passed?
+fn is_expired(deadline: Option<Instant>) -> bool {
+    matches!(deadline, Some(deadline) if Instant::now() >= deadline)
+}
+
+/// The error returned when the target did not respond for `max-unreachable-rounds` consecutive rounds.
+#[derive(Debug, thiserror::Error)]
+#[error("target did not respond for {0} consecutive rounds")]
+pub struct TargetUnreachable(pub usize);
+
+/// Fail if the target did not respond for the configured maximum number of consecutive rounds.
+fn check_unreachable(info: &TraceInfo, trace: &Trace) -> Result<(), TargetUnreachable> {
+    match info.max_unreachable_rounds {
+        Some(max) if trace.unreachable_rounds() >= max => {
+            Err(TargetUnreachable(trace.unreachable_rounds()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::has_translation;
+    use clap::ValueEnum;
+    use std::net::Ipv4Addr;
+    use test_case::test_case;
+
+    #[test]
+    fn test_headers_have_translations() {
+        let headers = TABLE_HEADER.into_iter().chain(
+            ReportColumn::value_variants()
+                .iter()
+                .map(|column| column_header(*column)),
+        );
+        for header in headers {
+            for locale in Locale::value_variants() {
+                assert!(has_translation(*locale, header), "{locale:?} {header}");
+            }
+        }
+    }
+
+    #[test_case(Reachability::Open(5), "forwarded"; "open")]
+    #[test_case(Reachability::Closed(5), "forwarded"; "closed")]
+    #[test_case(Reachability::Reached(5), "forwarded"; "reached")]
+    #[test_case(Reachability::Filtered(Some(3)), "forwarded"; "filtered beyond a hop")]
+    #[test_case(Reachability::Rejected(3), "rejected"; "rejected")]
+    #[test_case(Reachability::Filtered(None), "filtered"; "filtered")]
+    fn test_firewalk_verdict(reachability: Reachability, expected: &str) {
+        assert_eq!(expected, firewalk_verdict(reachability));
+    }
+
+    #[test]
+    fn test_firewalk_rows() {
+        let addr_1 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let addr_2 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let rows = firewalk_rows([
+            (addr_2, None, TracerProtocol::Icmp, Reachability::Reached(4)),
+            (
+                addr_1,
+                Some(443),
+                TracerProtocol::Udp,
+                Reachability::Filtered(None),
+            ),
+            (
+                addr_1,
+                Some(443),
+                TracerProtocol::Tcp,
+                Reachability::Open(4),
+            ),
+            (
+                addr_1,
+                Some(80),
+                TracerProtocol::Tcp,
+                Reachability::Rejected(2),
+            ),
+            (
+                addr_1,
+                None,
+                TracerProtocol::Icmp,
+                Reachability::Filtered(Some(2)),
+            ),
+        ]);
+        assert_eq!(
+            vec![
+                (addr_1, None, ["forwarded", "", ""]),
+                (addr_1, Some(80), ["", "rejected", ""]),
+                (addr_1, Some(443), ["", "forwarded", "filtered"]),
+                (addr_2, None, ["forwarded", "", ""]),
+            ],
+            rows
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tracing::{debug, warn};
+
+/// Serve the connections accepted by the `listener` on a pool of `workers` threads.
+///
+/// Each connection is passed to the `handler` on a worker thread, such that a slow client delays no more than one
+/// worker.  Connections wait for a free worker in a queue of up to `max_queued`, beyond which further connections are
+/// closed as they are accepted, which caps the number of clients served at once.
+///
+/// The threads are named after the server `name` and run until the process ends.
+pub fn serve<H>(
+    listener: TcpListener,
+    name: &str,
+    workers: usize,
+    max_queued: usize,
+    handler: H,
+) -> anyhow::Result<()>
+where
+    H: Fn(&TcpStream) -> anyhow::Result<()> + Send + Sync + 'static,
+{
+    let (stream_tx, stream_rx) = mpsc::sync_channel::<TcpStream>(max_queued);
+    let stream_rx = Arc::new(Mutex::new(stream_rx));
+    let handler = Arc::new(handler);
+    for i in 0..workers {
+        let stream_rx = stream_rx.clone();
+        let handler = handler.clone();
+        thread::Builder::new()
+            .name(format!("{name}-{i}"))
+            .spawn(move || run_worker(&stream_rx, handler.as_ref()))?;
+    }
+    let name = name.to_string();
+    thread::Builder::new().name(name.clone()).spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    debug!(server = name, %err, "failed to accept connection");
+                    continue;
+                }
+            };
+            match stream_tx.try_send(stream) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    warn!(
+                        server = name,
+                        "closed connection as too many clients are connected"
+                    );
+                }
+                Err(TrySendError::Disconnected(_)) => return,
+            }
+        }
+    })?;
+    Ok(())
+}
+
+/// Handle each connection received from the queue in turn.
+fn run_worker<H>(stream_rx: &Mutex<Receiver<TcpStream>>, handler: &H)
+where
+    H: Fn(&TcpStream) -> anyhow::Result<()>,
+{
+    loop {
+        // The lock is only held while waiting for a connection, not while it is handled.
+        let stream = match stream_rx.lock() {
+            Ok(stream_rx) => stream_rx.recv(),
+            Err(_) => return,
+        };
+        let Ok(stream) = stream else {
+            return;
+        };
+        if let Err(err) = handler(&stream) {
+            debug!(%err, "connection closed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::sync::Barrier;
+
+    #[test]
+    fn test_serve_concurrent() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let barrier = Barrier::new(2);
+        serve(listener, "test", 2, 2, move |stream| {
+            // Both clients must be served at once for either to pass the barrier.
+            barrier.wait();
+            let mut stream = stream;
+            stream.write_all(b"ok")?;
+            Ok(())
+        })
+        .unwrap();
+        let clients: Vec<_> = (0..2)
+            .map(|_| {
+                thread::spawn(move || {
+                    let mut stream = TcpStream::connect(addr).unwrap();
+                    let mut response = String::new();
+                    stream.read_to_string(&mut response).unwrap();
+                    response
+                })
+            })
+            .collect();
+        for client in clients {
+            assert_eq!("ok", client.join().unwrap());
+        }
+    }
+}
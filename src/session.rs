@@ -0,0 +1,132 @@
+use crate::backend::TraceState;
+use crate::frontend::TuiView;
+use crate::TraceInfo;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use tracing::debug;
+use trippy::tracing::{PortDirection, TracerProtocol};
+
+/// A Tui session, which is saved to a state file such that it can be resumed.
+///
+/// A session holds the target of each trace, the statistics accumulated by each trace and the view of the Tui.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Session {
+    traces: Vec<SessionTrace>,
+    view: TuiView,
+}
+
+/// A trace of a session.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct SessionTrace {
+    /// The target, with the protocol as the scheme and the target port, if fixed.
+    target: String,
+    target_addr: IpAddr,
+    state: TraceState,
+}
+
+impl Session {
+    /// Capture the session of the `traces` and the `view` of the Tui.
+    pub fn capture(traces: &[TraceInfo], view: TuiView) -> Self {
+        Self {
+            traces: traces
+                .iter()
+                .map(|info| SessionTrace {
+                    target: target_spec(&info.target_hostname, info.protocol, info.port_direction),
+                    target_addr: info.target_addr,
+                    state: info.data.read().state(),
+                })
+                .collect(),
+            view,
+        }
+    }
+
+    /// Load the session saved to the state file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read state file: {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("invalid state file: {}", path.display()))
+    }
+
+    /// Save the session to the state file at `path`.
+    ///
+    /// The state file is replaced atomically, such that it is never left partially written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let mut tmp_path = path.as_os_str().to_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_string(self)?)
+            .and_then(|()| fs::rename(&tmp_path, path))
+            .with_context(|| format!("failed to save state file: {}", path.display()))?;
+        debug!(path = %path.display(), traces = self.traces.len(), "saved session");
+        Ok(())
+    }
+
+    /// The targets of the session, in the order they were traced and without duplicates.
+    pub fn targets(&self) -> Vec<String> {
+        let mut targets: Vec<String> = vec![];
+        for trace in &self.traces {
+            if !targets.contains(&trace.target) {
+                targets.push(trace.target.clone());
+            }
+        }
+        targets
+    }
+
+    /// Restore the statistics of each of the `traces` which has the same target, and address family, as a trace of
+    /// the session and return the view of the Tui.
+    pub fn restore(self, traces: &[TraceInfo]) -> TuiView {
+        let mut saved = self.traces;
+        for info in traces {
+            let target = target_spec(&info.target_hostname, info.protocol, info.port_direction);
+            if let Some(index) = saved.iter().position(|trace| {
+                trace.target == target && trace.target_addr.is_ipv4() == info.target_addr.is_ipv4()
+            }) {
+                let trace = saved.remove(index);
+                info.data.write().restore(trace.state);
+                debug!(target, "restored trace");
+            }
+        }
+        self.view
+    }
+}
+
+/// Format a target with the `protocol` as the scheme and the target port, if fixed, such that it can be parsed.
+fn target_spec(hostname: &str, protocol: TracerProtocol, port_direction: PortDirection) -> String {
+    let host = if hostname.contains(':') {
+        format!("[{hostname}]")
+    } else {
+        hostname.to_string()
+    };
+    match port_direction {
+        PortDirection::FixedDest(dest) | PortDirection::FixedBoth(_, dest) => {
+            format!("{protocol}://{host}:{}", dest.0)
+        }
+        PortDirection::None | PortDirection::FixedSrc(_) => format!("{protocol}://{host}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("example.com", TracerProtocol::Icmp, PortDirection::None, "icmp://example.com"; "icmp")]
+    #[test_case("example.com", TracerProtocol::Udp, PortDirection::new_fixed_src(5000), "udp://example.com"; "udp fixed src")]
+    #[test_case("example.com", TracerProtocol::Tcp, PortDirection::new_fixed_dest(443), "tcp://example.com:443"; "tcp fixed dest")]
+    #[test_case("2001:db8::1", TracerProtocol::Udp, PortDirection::new_fixed_both(5000, 53), "udp://[2001:db8::1]:53"; "ipv6 fixed both")]
+    fn test_target_spec(
+        hostname: &str,
+        protocol: TracerProtocol,
+        port_direction: PortDirection,
+        expected: &str,
+    ) {
+        assert_eq!(target_spec(hostname, protocol, port_direction), expected);
+    }
+}
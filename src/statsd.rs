@@ -0,0 +1,235 @@
+use crate::config::StatsdFlavor;
+use crate::TraceInfo;
+use anyhow::{anyhow, Context};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::thread;
+use std::time::Duration;
+use tracing::debug;
+
+/// The interval at which the traces are checked for completed rounds.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The maximum size of a datagram, which avoids fragmentation on common networks.
+const MAX_DATAGRAM_SIZE: usize = 1432;
+
+/// Start sending the hop metrics of all `traces` to the statsd server at `addr` after every round.
+///
+/// The metrics of each hop are the round trip time of the last probe, if a response was received in the round, the
+/// loss percentage and the number of probes sent and received in the round.  Failures to send are logged and
+/// otherwise ignored, as is usual for statsd.
+pub fn start_statsd_exporter(
+    addr: &str,
+    flavor: StatsdFlavor,
+    prefix: String,
+    traces: Vec<TraceInfo>,
+) -> anyhow::Result<()> {
+    let addr = addr
+        .to_socket_addrs()
+        .with_context(|| format!("failed to resolve statsd address: {addr}"))?
+        .next()
+        .ok_or_else(|| anyhow!("failed to resolve statsd address: {addr}"))?;
+    let bind_addr = match addr {
+        SocketAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+        SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+    };
+    let socket = UdpSocket::bind(bind_addr)?;
+    socket.connect(addr)?;
+    thread::Builder::new()
+        .name(String::from("statsd"))
+        .spawn(move || {
+            let mut rounds = vec![None; traces.len()];
+            let mut totals = HashMap::new();
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                for (index, info) in traces.iter().enumerate() {
+                    let trace = info.data.read();
+                    if trace.round() == rounds[index] {
+                        continue;
+                    }
+                    rounds[index] = trace.round();
+                    let lines: Vec<_> = trace
+                        .hops()
+                        .iter()
+                        .filter(|hop| trace.is_in_round(hop))
+                        .flat_map(|hop| {
+                            let (prev_sent, prev_recv) = totals
+                                .insert((index, hop.ttl()), (hop.total_sent(), hop.total_recv()))
+                                .filter(|&(sent, _)| sent <= hop.total_sent())
+                                .unwrap_or_default();
+                            let recv = hop.total_recv().saturating_sub(prev_recv);
+                            HopMetrics {
+                                target: &info.target_hostname,
+                                ttl: hop.ttl(),
+                                addr: hop
+                                    .addrs_with_counts()
+                                    .max_by_key(|(_, count)| **count)
+                                    .map(|(addr, _)| *addr),
+                                rtt_ms: hop.last_ms().filter(|_| recv > 0),
+                                loss_pct: hop.loss_pct(),
+                                sent: hop.total_sent() - prev_sent,
+                                recv,
+                            }
+                            .lines(flavor, &prefix)
+                        })
+                        .collect();
+                    drop(trace);
+                    for datagram in batch(&lines) {
+                        if let Err(err) = socket.send(datagram.as_bytes()) {
+                            debug!(%err, "failed to send statsd metrics");
+                        }
+                    }
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// The metrics of a hop for a single round.
+#[derive(Debug)]
+struct HopMetrics<'a> {
+    target: &'a str,
+    ttl: u8,
+    addr: Option<IpAddr>,
+    rtt_ms: Option<f64>,
+    loss_pct: f64,
+    sent: usize,
+    recv: usize,
+}
+
+impl HopMetrics<'_> {
+    /// The statsd lines of the metrics.
+    ///
+    /// For plain statsd the target and hop are included in the metric name, such as `trippy.example_com.hop_3.rtt`,
+    /// whereas for `DogStatsD` they are given as `target`, `hop_ttl` and `hop_address` tags.
+    fn lines(&self, flavor: StatsdFlavor, prefix: &str) -> Vec<String> {
+        let (name, tags) = match flavor {
+            StatsdFlavor::Statsd => (
+                format!("{prefix}.{}.hop_{}", sanitize(self.target), self.ttl),
+                String::new(),
+            ),
+            StatsdFlavor::Dogstatsd => {
+                let addr = self
+                    .addr
+                    .map(|addr| format!(",hop_address:{addr}"))
+                    .unwrap_or_default();
+                let tags = format!(
+                    "|#target:{},hop_ttl:{}{addr}",
+                    sanitize_tag(self.target),
+                    self.ttl
+                );
+                (format!("{prefix}.hop"), tags)
+            }
+        };
+        let mut lines = Vec::with_capacity(4);
+        if let Some(rtt_ms) = self.rtt_ms {
+            lines.push(format!("{name}.rtt:{rtt_ms:.3}|ms{tags}"));
+        }
+        lines.push(format!("{name}.loss:{:.2}|g{tags}", self.loss_pct));
+        lines.push(format!("{name}.sent:{}|c{tags}", self.sent));
+        lines.push(format!("{name}.recv:{}|c{tags}", self.recv));
+        lines
+    }
+}
+
+/// Replace the characters which have a meaning in the statsd protocol, or which separate the components of a metric
+/// name, with `_`.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '.' | ':' | '|' | '@' | '#' | ',' | ' ' | '\n' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Replace the characters which have a meaning in the `DogStatsD` protocol with `_`.
+fn sanitize_tag(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '|' | '#' | ',' | ' ' | '\n' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Join lines into datagrams of no more than `MAX_DATAGRAM_SIZE` bytes, where possible.
+fn batch(lines: &[String]) -> Vec<String> {
+    let mut datagrams = Vec::new();
+    let mut datagram = String::new();
+    for line in lines {
+        if !datagram.is_empty() && datagram.len() + 1 + line.len() > MAX_DATAGRAM_SIZE {
+            datagrams.push(std::mem::take(&mut datagram));
+        }
+        if !datagram.is_empty() {
+            datagram.push('\n');
+        }
+        datagram.push_str(line);
+    }
+    if !datagram.is_empty() {
+        datagrams.push(datagram);
+    }
+    datagrams
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const METRICS: HopMetrics<'_> = HopMetrics {
+        target: "www.example.com",
+        ttl: 3,
+        addr: Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+        rtt_ms: Some(12.3456),
+        loss_pct: 25.0,
+        sent: 1,
+        recv: 1,
+    };
+
+    #[test]
+    fn test_lines_statsd() {
+        assert_eq!(
+            METRICS.lines(StatsdFlavor::Statsd, "trippy"),
+            vec![
+                "trippy.www_example_com.hop_3.rtt:12.346|ms",
+                "trippy.www_example_com.hop_3.loss:25.00|g",
+                "trippy.www_example_com.hop_3.sent:1|c",
+                "trippy.www_example_com.hop_3.recv:1|c",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lines_dogstatsd() {
+        let metrics = HopMetrics {
+            rtt_ms: None,
+            recv: 0,
+            ..METRICS
+        };
+        let tags = "|#target:www.example.com,hop_ttl:3,hop_address:192.0.2.1";
+        assert_eq!(
+            metrics.lines(StatsdFlavor::Dogstatsd, "trippy"),
+            vec![
+                format!("trippy.hop.loss:25.00|g{tags}"),
+                format!("trippy.hop.sent:1|c{tags}"),
+                format!("trippy.hop.recv:0|c{tags}"),
+            ]
+        );
+    }
+
+    #[test_case(0, 0; "no lines")]
+    #[test_case(10, 1; "single datagram")]
+    #[test_case(100, 8; "multiple datagrams")]
+    fn test_batch(count: usize, datagrams: usize) {
+        let lines: Vec<_> = (0..count).map(|i| format!("{i:0>100}")).collect();
+        let batched = batch(&lines);
+        assert_eq!(batched.len(), datagrams);
+        assert!(batched
+            .iter()
+            .all(|datagram| datagram.len() <= MAX_DATAGRAM_SIZE));
+        assert_eq!(batched.join("\n").split('\n').count(), count.max(1));
+    }
+}
@@ -0,0 +1,259 @@
+use crate::config::{SyslogFacility, SyslogTarget};
+use anyhow::{anyhow, Context};
+use chrono::{Local, SecondsFormat};
+use parking_lot::Mutex;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// The maximum time to wait to connect to, and to write to, a remote syslog server over TCP.
+const TCP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The path of the socket of the local syslog daemon.
+#[cfg(target_os = "macos")]
+const LOCAL_SOCKET_PATH: &str = "/var/run/syslog";
+
+/// The path of the socket of the local syslog daemon.
+#[cfg(all(unix, not(target_os = "macos")))]
+const LOCAL_SOCKET_PATH: &str = "/dev/log";
+
+/// The private enterprise number used in the structured data id of messages.
+///
+/// This is the example number reserved by RFC 5612 for use in documentation, as trippy does not have its own.
+const ENTERPRISE_NUMBER: u32 = 32473;
+
+/// The severity of a syslog message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Severity {
+    Error = 3,
+    Warning = 4,
+    Notice = 5,
+    Informational = 6,
+}
+
+/// Sends RFC 5424 messages to the local syslog daemon or to a remote syslog server.
+///
+/// Failures to send are logged and otherwise ignored.  Messages sent over TCP are framed with octet counting, as
+/// per RFC 6587, and the connection is reestablished for the next message if it fails.
+#[derive(Debug)]
+pub struct Syslog {
+    target: SyslogTarget,
+    facility: SyslogFacility,
+    hostname: String,
+    transport: Mutex<Option<Transport>>,
+}
+
+#[derive(Debug)]
+enum Transport {
+    #[cfg(unix)]
+    Local(std::os::unix::net::UnixDatagram),
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+impl Syslog {
+    /// Connect to the syslog `target`.
+    ///
+    /// A remote syslog server over TCP which cannot be connected to now is connected to for the next message.
+    pub fn connect(target: SyslogTarget, facility: SyslogFacility) -> anyhow::Result<Self> {
+        let transport = match connect(&target) {
+            Ok(transport) => Some(transport),
+            Err(err) if matches!(target, SyslogTarget::Tcp { .. }) => {
+                warn!(%target, %err, "failed to connect to syslog");
+                None
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            target,
+            facility,
+            hostname: dns_lookup::get_hostname().unwrap_or_else(|_| String::from("-")),
+            transport: Mutex::new(transport),
+        })
+    }
+
+    /// Send a message with the `params` as structured data.
+    pub fn send(&self, severity: Severity, msg_id: &str, params: &[(String, String)], msg: &str) {
+        let timestamp = Local::now().to_rfc3339_opts(SecondsFormat::Millis, false);
+        let message = format_message(
+            self.facility,
+            severity,
+            &timestamp,
+            &self.hostname,
+            std::process::id(),
+            msg_id,
+            params,
+            msg,
+        );
+        let mut transport = self.transport.lock();
+        let result = match transport.take() {
+            Some(current) => Ok(current),
+            None => connect(&self.target),
+        }
+        .and_then(|current| {
+            write(&current, &message)?;
+            Ok(current)
+        });
+        match result {
+            Ok(current) => *transport = Some(current),
+            Err(err) => debug!(target = %self.target, %err, "failed to send to syslog"),
+        }
+    }
+}
+
+fn connect(target: &SyslogTarget) -> anyhow::Result<Transport> {
+    match target {
+        #[cfg(unix)]
+        SyslogTarget::Local => {
+            let socket = std::os::unix::net::UnixDatagram::unbound()?;
+            socket
+                .connect(LOCAL_SOCKET_PATH)
+                .with_context(|| format!("failed to connect to syslog: {LOCAL_SOCKET_PATH}"))?;
+            Ok(Transport::Local(socket))
+        }
+        #[cfg(not(unix))]
+        SyslogTarget::Local => Err(anyhow!("local syslog is not supported on this platform")),
+        SyslogTarget::Udp { host, port } => {
+            let socket = UdpSocket::bind(if host.contains(':') {
+                "[::]:0"
+            } else {
+                "0.0.0.0:0"
+            })?;
+            socket.connect((host.as_str(), *port))?;
+            Ok(Transport::Udp(socket))
+        }
+        SyslogTarget::Tcp { host, port } => {
+            let addr = (host.as_str(), *port)
+                .to_socket_addrs()?
+                .next()
+                .ok_or_else(|| anyhow!("failed to resolve {}", host))?;
+            let stream = TcpStream::connect_timeout(&addr, TCP_TIMEOUT)?;
+            stream.set_write_timeout(Some(TCP_TIMEOUT))?;
+            Ok(Transport::Tcp(stream))
+        }
+    }
+}
+
+fn write(transport: &Transport, message: &str) -> anyhow::Result<()> {
+    match transport {
+        #[cfg(unix)]
+        Transport::Local(socket) => {
+            socket.send(message.as_bytes())?;
+        }
+        Transport::Udp(socket) => {
+            socket.send(message.as_bytes())?;
+        }
+        Transport::Tcp(stream) => {
+            let mut writer = stream;
+            writer.write_all(format!("{} {message}", message.len()).as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Format an RFC 5424 message.
+#[allow(clippy::too_many_arguments)]
+fn format_message(
+    facility: SyslogFacility,
+    severity: Severity,
+    timestamp: &str,
+    hostname: &str,
+    proc_id: u32,
+    msg_id: &str,
+    params: &[(String, String)],
+    msg: &str,
+) -> String {
+    let priority = facility_code(facility) * 8 + severity as u8;
+    let data = if params.is_empty() {
+        String::from("-")
+    } else {
+        let mut data = format!("[trippy@{ENTERPRISE_NUMBER}");
+        for (name, value) in params {
+            let _ = write!(data, " {name}=\"{}\"", escape_param(value));
+        }
+        data.push(']');
+        data
+    };
+    format!("<{priority}>1 {timestamp} {hostname} trippy {proc_id} {msg_id} {data} {msg}")
+}
+
+/// The numerical code of a syslog facility.
+fn facility_code(facility: SyslogFacility) -> u8 {
+    match facility {
+        SyslogFacility::User => 1,
+        SyslogFacility::Daemon => 3,
+        SyslogFacility::Local0 => 16,
+        SyslogFacility::Local1 => 17,
+        SyslogFacility::Local2 => 18,
+        SyslogFacility::Local3 => 19,
+        SyslogFacility::Local4 => 20,
+        SyslogFacility::Local5 => 21,
+        SyslogFacility::Local6 => 22,
+        SyslogFacility::Local7 => 23,
+    }
+}
+
+/// Escape the characters which must be escaped in the value of a structured data parameter.
+fn escape_param(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn test_format_message() {
+        let message = format_message(
+            SyslogFacility::Local3,
+            Severity::Notice,
+            "2023-05-01T12:00:00.000+00:00",
+            "host",
+            42,
+            "route-change",
+            &[
+                (String::from("target"), String::from("example.com")),
+                (String::from("ttl"), String::from("3")),
+            ],
+            "route changed",
+        );
+        assert_eq!(
+            message,
+            r#"<157>1 2023-05-01T12:00:00.000+00:00 host trippy 42 route-change [trippy@32473 target="example.com" ttl="3"] route changed"#
+        );
+    }
+
+    #[test]
+    fn test_format_message_without_params() {
+        let message = format_message(
+            SyslogFacility::User,
+            Severity::Informational,
+            "2023-05-01T12:00:00.000+00:00",
+            "-",
+            1,
+            "round-summary",
+            &[],
+            "summary",
+        );
+        assert_eq!(
+            message,
+            "<14>1 2023-05-01T12:00:00.000+00:00 - trippy 1 round-summary - summary"
+        );
+    }
+
+    #[test_case("example.com", "example.com"; "plain")]
+    #[test_case(r#"a"b\c]d"#, r#"a\"b\\c\]d"#; "escaped")]
+    fn test_escape_param(value: &str, expected: &str) {
+        assert_eq!(escape_param(value), expected);
+    }
+}
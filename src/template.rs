@@ -0,0 +1,325 @@
+use anyhow::anyhow;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// A template which is rendered against a JSON value, written in a subset of the Mustache syntax.
+///
+/// The following tags are supported:
+///
+/// - `{{name}}` inserts the value of `name`, which may be a dotted path such as `summary.loss_pct`, or `{{.}}` for
+///   the current value.  Strings and numbers are inserted verbatim, `null` as nothing and arrays and objects as JSON.
+/// - `{{#name}}...{{/name}}` renders the enclosed block once for every element of an array, or once with the value of
+///   `name` as the current value if it is neither `false`, `null` nor an empty array.
+/// - `{{^name}}...{{/name}}` renders the enclosed block only if `name` is `false`, `null`, an empty array or missing.
+/// - `{{! comment }}` is ignored.
+///
+/// Names are resolved against the current value and then against each enclosing value in turn.  Within an array
+/// section `@index` is the (zero based) position of the current element and `@first` and `@last` are true for the
+/// first and last elements respectively, such as to separate the elements with `{{^@last}},{{/@last}}`.
+///
+/// A section or comment tag which is alone on a line is removed along with the line, such that templates may place
+/// such tags on lines of their own.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+/// A node of a parsed `Template`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Node {
+    /// Literal text.
+    Text(String),
+    /// The value of a name.
+    Variable(String),
+    /// A block rendered for the value of a name, or for its absence if inverted.
+    Section {
+        name: String,
+        inverted: bool,
+        nodes: Vec<Self>,
+    },
+}
+
+/// A tag of a `Template`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Tag<'a> {
+    Variable(&'a str),
+    Open(&'a str, bool),
+    Close(&'a str),
+    Comment,
+}
+
+impl Template {
+    /// Parse a `Template` from its source.
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let mut stack: Vec<(&str, bool, Vec<Node>)> = vec![("", false, vec![])];
+        let mut rest = source;
+        let mut line_start = true;
+        while let Some(open) = rest.find("{{") {
+            let close = rest[open..]
+                .find("}}")
+                .map(|close| open + close)
+                .ok_or_else(|| anyhow!("unclosed tag in template: {}", &rest[open..]))?;
+            let tag = parse_tag(rest[open + 2..close].trim())?;
+            let mut text = &rest[..open];
+            let mut after = &rest[close + 2..];
+            line_start = match tag {
+                Tag::Variable(_) => false,
+                _ => match standalone(text, after, line_start) {
+                    Some((before, remainder)) => {
+                        text = before;
+                        after = remainder;
+                        true
+                    }
+                    None => false,
+                },
+            };
+            let nodes = &mut stack.last_mut().expect("root").2;
+            if !text.is_empty() {
+                nodes.push(Node::Text(text.to_string()));
+            }
+            match tag {
+                Tag::Variable(name) => nodes.push(Node::Variable(name.to_string())),
+                Tag::Open(name, inverted) => stack.push((name, inverted, vec![])),
+                Tag::Close(name) => match stack.pop() {
+                    Some((open, inverted, nodes)) if open == name && !stack.is_empty() => {
+                        stack.last_mut().expect("root").2.push(Node::Section {
+                            name: name.to_string(),
+                            inverted,
+                            nodes,
+                        });
+                    }
+                    Some((open, ..)) if !stack.is_empty() => {
+                        return Err(anyhow!(
+                            "unexpected {{{{/{name}}}}} in template, expected {{{{/{open}}}}}"
+                        ));
+                    }
+                    _ => {
+                        return Err(anyhow!("unexpected {{{{/{name}}}}} in template"));
+                    }
+                },
+                Tag::Comment => {}
+            }
+            rest = after;
+        }
+        let (open, _, mut nodes) = stack.pop().expect("root");
+        if !stack.is_empty() {
+            return Err(anyhow!("unclosed {{{{#{open}}}}} in template"));
+        }
+        if !rest.is_empty() {
+            nodes.push(Node::Text(rest.to_string()));
+        }
+        Ok(Self { nodes })
+    }
+
+    /// Render the `Template` against `value`.
+    pub fn render(&self, value: &Value) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, &mut vec![Frame::new(value, None)], &mut out);
+        out
+    }
+}
+
+/// Parse the content of a tag, without the enclosing braces.
+fn parse_tag(content: &str) -> anyhow::Result<Tag<'_>> {
+    let tag = match content.chars().next() {
+        Some('!') => return Ok(Tag::Comment),
+        Some('#') => Tag::Open(content[1..].trim(), false),
+        Some('^') => Tag::Open(content[1..].trim(), true),
+        Some('/') => Tag::Close(content[1..].trim()),
+        _ => Tag::Variable(content),
+    };
+    match tag {
+        Tag::Variable(name) | Tag::Open(name, _) | Tag::Close(name) if name.is_empty() => {
+            Err(anyhow!("empty tag in template"))
+        }
+        tag => Ok(tag),
+    }
+}
+
+/// If a tag is alone on its line, the `text` which precedes it and the remainder which follows it with the line
+/// removed.
+fn standalone<'a>(text: &'a str, after: &'a str, line_start: bool) -> Option<(&'a str, &'a str)> {
+    let (before, indent) = match text.rfind('\n') {
+        Some(newline) => text.split_at(newline + 1),
+        None if line_start => ("", text),
+        None => return None,
+    };
+    if !indent.chars().all(|c| c == ' ' || c == '\t') {
+        return None;
+    }
+    let (trailing, remainder) = match after.find('\n') {
+        Some(newline) => (&after[..newline], &after[newline + 1..]),
+        None => (after, ""),
+    };
+    let trailing = trailing.strip_suffix('\r').unwrap_or(trailing);
+    if trailing.chars().all(|c| c == ' ' || c == '\t') {
+        Some((before, remainder))
+    } else {
+        None
+    }
+}
+
+/// A value in scope during rendering, with its position if it is an element of an array.
+#[derive(Debug, Clone, Copy)]
+struct Frame<'a> {
+    value: &'a Value,
+    position: Option<(usize, usize)>,
+}
+
+impl<'a> Frame<'a> {
+    const fn new(value: &'a Value, position: Option<(usize, usize)>) -> Self {
+        Self { value, position }
+    }
+}
+
+/// Render `nodes` with the values in scope given by `stack`.
+fn render_nodes<'a>(nodes: &'a [Node], stack: &mut Vec<Frame<'a>>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Variable(name) => match lookup(stack, name).as_deref() {
+                None | Some(Value::Null) => {}
+                Some(Value::String(s)) => out.push_str(s),
+                Some(value @ (Value::Bool(_) | Value::Number(_))) => {
+                    let _ = write!(out, "{value}");
+                }
+                Some(value) => out.push_str(&value.to_string()),
+            },
+            Node::Section {
+                name,
+                inverted,
+                nodes,
+            } => {
+                let value = lookup(stack, name);
+                let truthy = match value.as_deref() {
+                    None | Some(Value::Null | Value::Bool(false)) => false,
+                    Some(Value::Array(items)) => !items.is_empty(),
+                    Some(_) => true,
+                };
+                if *inverted {
+                    if !truthy {
+                        render_nodes(nodes, stack, out);
+                    }
+                } else if truthy {
+                    match value {
+                        Some(Cow::Borrowed(value)) => render_section(nodes, stack, value, out),
+                        // Values derived during rendering (such as `@first`) are not borrowed from the rendered value
+                        // and so are only used as conditions, not as the current value.
+                        _ => render_nodes(nodes, stack, out),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Render the `nodes` of a section for each element of `value` if it is an array, or otherwise once for `value`.
+fn render_section<'a>(
+    nodes: &'a [Node],
+    stack: &mut Vec<Frame<'a>>,
+    value: &'a Value,
+    out: &mut String,
+) {
+    if let Value::Array(items) = value {
+        for (index, item) in items.iter().enumerate() {
+            stack.push(Frame::new(item, Some((index, items.len()))));
+            render_nodes(nodes, stack, out);
+            stack.pop();
+        }
+    } else {
+        stack.push(Frame::new(value, None));
+        render_nodes(nodes, stack, out);
+        stack.pop();
+    }
+}
+
+/// Lookup the value of `name` in the values in scope given by `stack`.
+fn lookup<'a>(stack: &[Frame<'a>], name: &str) -> Option<Cow<'a, Value>> {
+    let top = stack.last()?;
+    if name == "." {
+        return Some(Cow::Borrowed(top.value));
+    }
+    if let Some(special) = name.strip_prefix('@') {
+        let (index, len) = stack.iter().rev().find_map(|frame| frame.position)?;
+        return match special {
+            "index" => Some(Cow::Owned(Value::from(index))),
+            "first" => Some(Cow::Owned(Value::Bool(index == 0))),
+            "last" => Some(Cow::Owned(Value::Bool(index + 1 == len))),
+            _ => None,
+        };
+    }
+    let mut segments = name.split('.');
+    let first = segments.next()?;
+    let mut value = stack
+        .iter()
+        .rev()
+        .find_map(|frame| frame.value.as_object().and_then(|obj| obj.get(first)))?;
+    for segment in segments {
+        value = match value {
+            Value::Object(obj) => obj.get(segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(Cow::Borrowed(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use test_case::test_case;
+
+    #[test_case("plain text", "plain text"; "text")]
+    #[test_case("{{target}}", "example.com"; "string")]
+    #[test_case("{{summary.loss}}%", "12.5%"; "path")]
+    #[test_case("{{summary.sent}}/{{summary.ok}}", "8/true"; "number and bool")]
+    #[test_case("[{{missing}}{{none}}]", "[]"; "missing and null")]
+    #[test_case("{{hops.1.ttl}}", "2"; "array index")]
+    #[test_case("{{#hops}}{{ttl}} {{/hops}}", "1 2 3 "; "array section")]
+    #[test_case("{{#hops}}{{#addrs}}{{.}}@{{ttl}} {{/addrs}}{{/hops}}", "a@1 b@2 c@2 "; "nested sections")]
+    #[test_case("{{#hops}}{{ttl}}{{^@last}},{{/@last}}{{/hops}}", "1,2,3"; "separator")]
+    #[test_case("{{#hops}}{{#@first}}[{{/@first}}{{@index}}{{/hops}}", "[012"; "first and index")]
+    #[test_case("{{#summary}}{{sent}}{{/summary}}", "8"; "object section")]
+    #[test_case("{{#none}}x{{/none}}{{^none}}y{{/none}}", "y"; "inverted null")]
+    #[test_case("{{#empty}}x{{/empty}}{{^empty}}y{{/empty}}", "y"; "inverted empty")]
+    #[test_case("{{^hops}}x{{/hops}}", ""; "inverted non-empty")]
+    #[test_case("a{{! a comment }}b", "ab"; "comment")]
+    #[test_case("{{ target }}", "example.com"; "whitespace")]
+    fn test_render(source: &str, expected: &str) {
+        let value = json!({
+            "target": "example.com",
+            "summary": { "loss": 12.5, "sent": 8, "ok": true },
+            "none": null,
+            "empty": [],
+            "hops": [
+                { "ttl": 1, "addrs": ["a"] },
+                { "ttl": 2, "addrs": ["b", "c"] },
+                { "ttl": 3, "addrs": [] },
+            ],
+        });
+        let template = Template::parse(source).unwrap();
+        assert_eq!(expected, template.render(&value));
+    }
+
+    #[test]
+    fn test_render_standalone() {
+        let source = "<hops>\n  {{#hops}}\n  <hop ttl=\"{{ttl}}\"/>\n  {{/hops}}\n</hops>\n";
+        let value = json!({ "hops": [{ "ttl": 1 }, { "ttl": 2 }] });
+        let template = Template::parse(source).unwrap();
+        assert_eq!(
+            "<hops>\n  <hop ttl=\"1\"/>\n  <hop ttl=\"2\"/>\n</hops>\n",
+            template.render(&value)
+        );
+    }
+
+    #[test_case("{{target", "unclosed tag in template: {{target"; "unclosed tag")]
+    #[test_case("{{#hops}}", "unclosed {{#hops}} in template"; "unclosed section")]
+    #[test_case("{{/hops}}", "unexpected {{/hops}} in template"; "unexpected close")]
+    #[test_case("{{#a}}{{/b}}", "unexpected {{/b}} in template, expected {{/a}}"; "mismatched close")]
+    #[test_case("{{}}", "empty tag in template"; "empty tag")]
+    fn test_parse_error(source: &str, expected: &str) {
+        assert_eq!(expected, Template::parse(source).unwrap_err().to_string());
+    }
+}
@@ -11,10 +11,16 @@ mod util;
 pub mod packet;
 
 pub use config::{
-    MultipathStrategy, PortDirection, TracerAddrFamily, TracerChannelConfig, TracerConfig,
-    TracerProtocol,
+    Ipv6ExtHeader, Ipv6SourcePreference, MultipathStrategy, PortDirection, PrivilegeMode,
+    ProhibitedStrategy, SequenceStrategy, TcpOptions, TracerAddrFamily, TracerChannelConfig,
+    TracerConfig, TracerProtocol,
 };
 pub use net::channel::TracerChannel;
+pub use net::dump::PACKET_DUMP_TARGET;
+pub use net::interface::{Egress, Interface};
+pub use net::shared::{SharedReceiver, SharedSenders, Subscription};
 pub use net::source::SourceAddr;
-pub use probe::{IcmpPacketType, Probe, ProbeStatus};
-pub use tracer::{Tracer, TracerRound};
+pub use net::ChecksumStats;
+pub use probe::{IcmpPacketType, IcmpTimestamps, Probe, ProbeStatus, RecordedRoute, TcpHandshake};
+pub use tracer::{CompletionReason, Tracer, TracerRound};
+pub use types::{MaxRounds, Port, Round, Sequence, TimeToLive, TraceId, TypeOfService};
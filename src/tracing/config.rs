@@ -1,15 +1,16 @@
 use crate::tracing::constants::{MAX_SEQUENCE, MAX_TTL};
 use crate::tracing::error::{TraceResult, TracerError};
+use crate::tracing::net::shared::{SharedReceiver, SharedSenders};
 use crate::tracing::types::{
-    MaxInflight, MaxRounds, PacketSize, PayloadPattern, Port, Sequence, TimeToLive, TraceId,
-    TypeOfService,
+    MaxInflight, MaxRounds, MaxSegmentSize, PacketSize, PayloadPattern, Port, Sequence, TimeToLive,
+    TraceId, TypeOfService, WindowSize,
 };
 use std::fmt::{Display, Formatter};
 use std::net::IpAddr;
 use std::time::Duration;
 
 /// The address family.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum TracerAddrFamily {
     /// Internet Protocol V4
     Ipv4,
@@ -27,7 +28,7 @@ impl Display for TracerAddrFamily {
 }
 
 /// The tracing protocol.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum TracerProtocol {
     /// Internet Control Message Protocol
     Icmp,
@@ -47,6 +48,95 @@ impl Display for TracerProtocol {
     }
 }
 
+/// The privilege mode of the tracer channel.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PrivilegeMode {
+    /// Use raw sockets, which requires elevated privileges.
+    Privileged,
+    /// Use unprivileged `ICMP` datagram sockets, which only supports the `ICMP` protocol.
+    Unprivileged,
+}
+
+impl Display for PrivilegeMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Privileged => write!(f, "privileged"),
+            Self::Unprivileged => write!(f, "unprivileged"),
+        }
+    }
+}
+
+/// The preferred kind of `IPv6` source address when an interface has several candidates.
+///
+/// See [RFC 6724](https://www.rfc-editor.org/rfc/rfc6724#section-5) rule 7.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Ipv6SourcePreference {
+    /// Prefer temporary (privacy) addresses.
+    Temporary,
+    /// Prefer stable (public) addresses.
+    Stable,
+}
+
+impl Display for Ipv6SourcePreference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Temporary => write!(f, "temporary"),
+            Self::Stable => write!(f, "stable"),
+        }
+    }
+}
+
+/// How to handle a hop which responds that communication with the target is administratively prohibited.
+///
+/// These are the `IPv4` `DestinationUnreachable` codes 9, 10 and 13 and the `IPv6` codes 1, 5 and 6, which are
+/// typically sent by a firewall or a router with a policy which rejects the probes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ProhibitedStrategy {
+    /// Treat the hop as the end of the path and stop probing higher TTLs, as for any other unreachable response.
+    Stop,
+    /// Continue probing higher TTLs beyond the hop, such as to discover whether other hops respond.
+    Continue,
+}
+
+impl ProhibitedStrategy {
+    /// Is the `DestinationUnreachable` `code` of an `IPv4` or `IPv6` response administratively prohibited?
+    #[must_use]
+    pub const fn is_prohibited(code: u8, ipv6: bool) -> bool {
+        if ipv6 {
+            matches!(code, 1 | 5 | 6)
+        } else {
+            matches!(code, 9 | 10 | 13)
+        }
+    }
+}
+
+impl Display for ProhibitedStrategy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stop => write!(f, "stop"),
+            Self::Continue => write!(f, "continue"),
+        }
+    }
+}
+
+/// How the sequence number of the first probe of every round is chosen.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SequenceStrategy {
+    /// Continue from the sequence number following the last probe of the previous round.
+    Sequential,
+    /// Start from a random sequence number, distinct from those of the previous round.
+    Random,
+}
+
+impl Display for SequenceStrategy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sequential => write!(f, "sequential"),
+            Self::Random => write!(f, "random"),
+        }
+    }
+}
+
 /// The [Equal-cost Multi-Path](https://en.wikipedia.org/wiki/Equal-cost_multi-path_routing) routing strategy.
 #[derive(Debug, Copy, Clone)]
 pub enum MultipathStrategy {
@@ -143,6 +233,53 @@ impl PortDirection {
     }
 }
 
+/// The TCP options to use for TCP probes.
+///
+/// TCP probes are sent using the OS TCP stack and so the `SYN` segment is a genuine client `SYN` which includes
+/// whichever of the `SACK` and `timestamp` options the OS is configured to send, as these cannot be set per socket on
+/// most platforms.  The `MSS` option and the receive buffer size, from which the OS derives the advertised window, may
+/// be overridden per probe socket.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TcpOptions {
+    /// The value of the `MSS` option, or the OS default if `None`.
+    pub mss: Option<MaxSegmentSize>,
+    /// A hint for the size of the receive buffer, from which the OS derives the advertised window, or the OS default
+    /// if `None`.
+    pub window_size: Option<WindowSize>,
+}
+
+impl TcpOptions {
+    #[must_use]
+    pub fn new(mss: Option<u16>, window_size: Option<u32>) -> Self {
+        Self {
+            mss: mss.map(MaxSegmentSize),
+            window_size: window_size.map(WindowSize),
+        }
+    }
+}
+
+/// An `IPv6` extension header to include in probes.
+///
+/// The extension header carries a single `PadN` option and so has no effect other than to test whether packets with
+/// such a header are forwarded, as some networks drop them.  It is added by the OS and so is not included in the
+/// packet size.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Ipv6ExtHeader {
+    /// A destination options header.
+    DestinationOptions,
+    /// A hop-by-hop options header.
+    HopByHop,
+}
+
+impl Display for Ipv6ExtHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DestinationOptions => write!(f, "dst-opts"),
+            Self::HopByHop => write!(f, "hop-by-hop"),
+        }
+    }
+}
+
 /// Tracer network channel configuration.
 #[derive(Debug, Clone)]
 pub struct TracerChannelConfig {
@@ -155,6 +292,23 @@ pub struct TracerChannelConfig {
     pub tos: TypeOfService,
     pub read_timeout: Duration,
     pub tcp_connect_timeout: Duration,
+    pub tcp_options: TcpOptions,
+    pub ipv6_ext_header: Option<Ipv6ExtHeader>,
+    pub record_route: bool,
+    pub icmp_timestamp: bool,
+    /// Whether probes must not be fragmented, or may be, or `None` to leave this to the platform.
+    ///
+    /// Raw `IPv4` probes are sent with the `Don't Fragment` bit set unless this is `Some(false)`.
+    pub dont_fragment: Option<bool>,
+    pub privilege_mode: PrivilegeMode,
+    /// The receive loop, shared with other channels, from which privileged `ICMP` packets are received.
+    ///
+    /// If `None` then the channel receives from a raw socket of its own.
+    pub shared_receiver: Option<SharedReceiver>,
+    /// The raw sockets, shared with other channels, on which privileged probes are sent.
+    ///
+    /// If `None` then the channel sends on raw sockets of its own.
+    pub shared_senders: Option<SharedSenders>,
 }
 
 impl TracerChannelConfig {
@@ -170,6 +324,12 @@ impl TracerChannelConfig {
         tos: u8,
         read_timeout: Duration,
         tcp_connect_timeout: Duration,
+        tcp_options: TcpOptions,
+        ipv6_ext_header: Option<Ipv6ExtHeader>,
+        record_route: bool,
+        icmp_timestamp: bool,
+        dont_fragment: Option<bool>,
+        privilege_mode: PrivilegeMode,
     ) -> Self {
         Self {
             protocol,
@@ -181,11 +341,40 @@ impl TracerChannelConfig {
             tos: TypeOfService(tos),
             read_timeout,
             tcp_connect_timeout,
+            tcp_options,
+            ipv6_ext_header,
+            record_route,
+            icmp_timestamp,
+            dont_fragment,
+            privilege_mode,
+            shared_receiver: None,
+            shared_senders: None,
+        }
+    }
+
+    /// Receive privileged `ICMP` packets from the `shared_receiver` rather than a raw socket of the channel.
+    #[must_use]
+    pub fn with_shared_receiver(self, shared_receiver: SharedReceiver) -> Self {
+        Self {
+            shared_receiver: Some(shared_receiver),
+            ..self
+        }
+    }
+
+    /// Send privileged probes on the `shared_senders` rather than raw sockets of the channel.
+    ///
+    /// The socket options set by the channel apply to every channel which shares the sockets.
+    #[must_use]
+    pub fn with_shared_senders(self, shared_senders: SharedSenders) -> Self {
+        Self {
+            shared_senders: Some(shared_senders),
+            ..self
         }
     }
 }
 
 /// Tracing algorithm configuration.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Copy, Clone)]
 pub struct TracerConfig {
     pub target_addr: IpAddr,
@@ -196,18 +385,31 @@ pub struct TracerConfig {
     pub max_ttl: TimeToLive,
     pub grace_duration: Duration,
     pub max_inflight: MaxInflight,
+    pub adaptive_inflight: bool,
     pub initial_sequence: Sequence,
+    pub sequence_strategy: SequenceStrategy,
     pub multipath_strategy: MultipathStrategy,
     pub port_direction: PortDirection,
     pub read_timeout: Duration,
     pub min_round_duration: Duration,
     pub max_round_duration: Duration,
+    /// The maximum random offset applied to the minimum and maximum duration of every round.
+    ///
+    /// Each round is lengthened or shortened by an offset chosen uniformly at random within `+/- round_jitter`, such
+    /// that the rounds of several tracers started together do not remain aligned.
+    pub round_jitter: Duration,
+    pub probe_timeout: Option<Duration>,
+    pub retries: u8,
+    pub burst: bool,
     pub packet_size: PacketSize,
     pub payload_pattern: PayloadPattern,
+    pub destination_only: bool,
+    pub adaptive_ttl: bool,
+    pub prohibited_strategy: ProhibitedStrategy,
 }
 
 impl TracerConfig {
-    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
     pub fn new(
         target_addr: IpAddr,
         protocol: TracerProtocol,
@@ -217,14 +419,23 @@ impl TracerConfig {
         max_ttl: u8,
         grace_duration: Duration,
         max_inflight: u8,
+        adaptive_inflight: bool,
         initial_sequence: u16,
+        sequence_strategy: SequenceStrategy,
         multipath_strategy: MultipathStrategy,
         port_direction: PortDirection,
         read_timeout: Duration,
         min_round_duration: Duration,
         max_round_duration: Duration,
+        round_jitter: Duration,
+        probe_timeout: Option<Duration>,
+        retries: u8,
+        burst: bool,
         packet_size: u16,
         payload_pattern: u8,
+        destination_only: bool,
+        adaptive_ttl: bool,
+        prohibited_strategy: ProhibitedStrategy,
     ) -> TraceResult<Self> {
         if first_ttl > MAX_TTL {
             return Err(TracerError::BadConfig(format!(
@@ -250,14 +461,23 @@ impl TracerConfig {
             max_ttl: TimeToLive(max_ttl),
             grace_duration,
             max_inflight: MaxInflight(max_inflight),
+            adaptive_inflight,
             initial_sequence: Sequence(initial_sequence),
+            sequence_strategy,
             multipath_strategy,
             port_direction,
             read_timeout,
             min_round_duration,
             max_round_duration,
+            round_jitter,
+            probe_timeout,
+            retries,
+            burst,
             packet_size: PacketSize(packet_size),
             payload_pattern: PayloadPattern(payload_pattern),
+            destination_only,
+            adaptive_ttl,
+            prohibited_strategy,
         })
     }
 }
@@ -1,4 +1,5 @@
 use crate::tracing::util::RequiredError;
+use crate::tracing::TracerProtocol;
 use std::io;
 use std::net::{IpAddr, SocketAddr};
 use thiserror::Error;
@@ -23,4 +24,6 @@ pub enum TracerError {
     AddressNotAvailable(SocketAddr),
     #[error("invalid source IP address: {0}")]
     InvalidSourceAddr(IpAddr),
+    #[error("unprivileged mode is not supported for protocol: {0}")]
+    UnprivilegedProtocol(TracerProtocol),
 }
@@ -14,19 +14,75 @@ mod platform;
 /// A network socket.
 mod socket;
 
+/// Annotated hex dumps of sent and received packets.
+pub mod dump;
+
 /// A channel for sending and receiving probes.
 pub mod channel;
 
 /// Determine the source address.
 pub mod source;
 
+/// A receive loop shared by the channels of several tracers.
+pub mod shared;
+
+/// Enumerate network interfaces.
+pub mod interface;
+
 /// An abstraction over a network interface for tracing.
 pub trait Network {
     /// Send a `Probe`.
     fn send_probe(&mut self, probe: Probe) -> TraceResult<()>;
 
+    /// Send any `Probe` which has been queued by `send_probe` but not yet sent.
+    ///
+    /// Probes may be queued such that they can be sent in a single batch.
+    fn flush(&mut self) -> TraceResult<()>;
+
     /// Receive the next Icmp packet and return a `ProbeResponse`.
     ///
     /// Returns `None` if the read times out or the packet read is not one of the types expected.
     fn recv_probe(&mut self) -> TraceResult<Option<ProbeResponse>>;
+
+    /// The checksum validation statistics of the responses received so far.
+    fn checksum_stats(&self) -> ChecksumStats;
+}
+
+impl<N: Network + ?Sized> Network for &mut N {
+    fn send_probe(&mut self, probe: Probe) -> TraceResult<()> {
+        (**self).send_probe(probe)
+    }
+
+    fn flush(&mut self) -> TraceResult<()> {
+        (**self).flush()
+    }
+
+    fn recv_probe(&mut self) -> TraceResult<Option<ProbeResponse>> {
+        (**self).recv_probe()
+    }
+
+    fn checksum_stats(&self) -> ChecksumStats {
+        (**self).checksum_stats()
+    }
+}
+
+/// Checksum validation statistics for the `ICMP` responses received by a `Network`.
+///
+/// The checksums of the `IPv4` header, the `ICMPv6` header and of `TCP` segments are validated by the OS before the
+/// packets are delivered and so are not included here.
+///
+/// Responses with invalid checksums are counted but are otherwise processed as normal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChecksumStats {
+    /// The number of responses validated.
+    pub validated: usize,
+    /// The number of responses with an invalid `ICMP` checksum (`IPv4` only).
+    pub invalid_icmp: usize,
+    /// The number of responses quoting an `ICMP` echo request with an invalid checksum.
+    pub invalid_quoted_icmp: usize,
+    /// The number of responses quoting a `UDP` datagram with an invalid checksum.
+    ///
+    /// Note that this may also count responses quoting a datagram whose source address was rewritten by a NAT
+    /// device which did not update the `UDP` checksum.
+    pub invalid_quoted_udp: usize,
 }
@@ -1,24 +1,37 @@
 use crate::tracing::error::{TraceResult, TracerError};
 use crate::tracing::net::platform::Socket;
+use crate::tracing::net::shared::Subscription;
 use crate::tracing::net::socket::TracerSocket as _;
-use crate::tracing::net::{ipv4, ipv6, platform, Network};
+use crate::tracing::net::{ipv4, ipv6, platform, ChecksumStats, Network};
 use crate::tracing::probe::ProbeResponse;
 use crate::tracing::types::{PacketSize, PayloadPattern, Sequence, TypeOfService};
-use crate::tracing::{Probe, TracerChannelConfig, TracerProtocol};
+use crate::tracing::{
+    Ipv6ExtHeader, PrivilegeMode, Probe, TcpOptions, TracerAddrFamily, TracerChannelConfig,
+    TracerProtocol,
+};
 use arrayvec::ArrayVec;
 use itertools::Itertools;
-use std::net::IpAddr;
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
 use std::time::{Duration, SystemTime};
+use tracing::{debug, trace};
 
 /// The maximum size of the IP packet we allow.
 pub const MAX_PACKET_SIZE: usize = 1024;
 
+/// The maximum number of packets to receive in a single batch.
+pub const MAX_RECV_BATCH: usize = 16;
+
+/// The maximum number of packets to send in a single batch.
+pub const MAX_SEND_BATCH: usize = 16;
+
 /// The maximum number of TCP probes we allow.
 const MAX_TCP_PROBES: usize = 256;
 
 /// A channel for sending and receiving `Probe` packets.
 pub struct TracerChannel {
     protocol: TracerProtocol,
+    privilege_mode: PrivilegeMode,
     src_addr: IpAddr,
     ipv4_length_order: platform::PlatformIpv4FieldByteOrder,
     dest_addr: IpAddr,
@@ -27,30 +40,84 @@ pub struct TracerChannel {
     tos: TypeOfService,
     read_timeout: Duration,
     tcp_connect_timeout: Duration,
+    tcp_options: TcpOptions,
+    ipv6_ext_header: Option<Ipv6ExtHeader>,
+    record_route: bool,
+    icmp_timestamp: bool,
+    dont_fragment: Option<bool>,
     icmp_send_socket: Socket,
     udp_send_socket: Socket,
-    recv_socket: Socket,
+    receiver: IcmpReceiver,
+    send_bufs: Box<[[u8; MAX_PACKET_SIZE]; MAX_SEND_BATCH]>,
+    pending_sends: ArrayVec<(usize, SocketAddr), MAX_SEND_BATCH>,
+    recv_bufs: Box<[[u8; MAX_PACKET_SIZE]; MAX_RECV_BATCH]>,
+    pending_responses: VecDeque<ProbeResponse>,
     tcp_probes: ArrayVec<TcpProbe, MAX_TCP_PROBES>,
+    checksum_stats: ChecksumStats,
 }
 
 impl TracerChannel {
     /// Create an `IcmpChannel`.
     ///
-    /// This operation requires the `CAP_NET_RAW` capability on Linux.
+    /// This operation requires the `CAP_NET_RAW` capability on Linux unless the `PrivilegeMode` is `Unprivileged`.
     pub fn connect(config: &TracerChannelConfig) -> TraceResult<Self> {
         if usize::from(config.packet_size.0) > MAX_PACKET_SIZE {
             return Err(TracerError::InvalidPacketSize(usize::from(
                 config.packet_size.0,
             )));
         }
+        if config.record_route {
+            validate_record_route(config)?;
+        }
+        if config.icmp_timestamp {
+            validate_icmp_timestamp(config)?;
+        }
         platform::startup()?;
         let ipv4_length_order =
             platform::PlatformIpv4FieldByteOrder::for_address(config.source_addr)?;
-        let icmp_send_socket = make_icmp_send_socket(config.source_addr)?;
-        let udp_send_socket = make_udp_send_socket(config.source_addr)?;
-        let recv_socket = make_recv_socket(config.source_addr)?;
+        let (icmp_send_socket, udp_send_socket, receiver) = match config.privilege_mode {
+            PrivilegeMode::Privileged => {
+                let (icmp_send_socket, udp_send_socket) = match &config.shared_senders {
+                    Some(shared) if shared.addr_family() == config.addr_family => {
+                        shared.sockets()?
+                    }
+                    _ => (
+                        make_icmp_send_socket(config.source_addr)?,
+                        make_udp_send_socket(config.source_addr)?,
+                    ),
+                };
+                let receiver = match &config.shared_receiver {
+                    Some(shared) if shared.accepts(config.source_addr) => {
+                        IcmpReceiver::Shared(shared.subscribe())
+                    }
+                    _ => IcmpReceiver::Socket(make_recv_socket(config.source_addr)?),
+                };
+                (icmp_send_socket, udp_send_socket, receiver)
+            }
+            PrivilegeMode::Unprivileged => {
+                if !matches!(config.protocol, TracerProtocol::Icmp) {
+                    return Err(TracerError::UnprivilegedProtocol(config.protocol));
+                }
+                // A single socket is used to send probes and receive responses, the UDP send socket is unused.
+                let mut socket = make_icmp_dgram_socket(config.source_addr)?;
+                socket.bind(SocketAddr::new(config.source_addr, 0))?;
+                (
+                    socket.try_clone()?,
+                    socket.try_clone()?,
+                    IcmpReceiver::Socket(socket),
+                )
+            }
+        };
+        if let (Some(header), IpAddr::V6(_)) = (config.ipv6_ext_header, config.source_addr) {
+            icmp_send_socket.set_ipv6_ext_header(header)?;
+            udp_send_socket.set_ipv6_ext_header(header)?;
+        }
+        if let Some(dont_fragment) = config.dont_fragment {
+            set_dont_fragment(config, &icmp_send_socket, &udp_send_socket, dont_fragment)?;
+        }
         Ok(Self {
             protocol: config.protocol,
+            privilege_mode: config.privilege_mode,
             src_addr: config.source_addr,
             ipv4_length_order,
             dest_addr: config.target_addr,
@@ -59,16 +126,59 @@ impl TracerChannel {
             tos: config.tos,
             read_timeout: config.read_timeout,
             tcp_connect_timeout: config.tcp_connect_timeout,
+            tcp_options: config.tcp_options,
+            ipv6_ext_header: config.ipv6_ext_header,
+            record_route: config.record_route,
+            icmp_timestamp: config.icmp_timestamp,
+            dont_fragment: config.dont_fragment,
             icmp_send_socket,
             udp_send_socket,
-            recv_socket,
+            receiver,
+            send_bufs: Box::new([[0_u8; MAX_PACKET_SIZE]; MAX_SEND_BATCH]),
+            pending_sends: ArrayVec::new(),
+            recv_bufs: Box::new([[0_u8; MAX_PACKET_SIZE]; MAX_RECV_BATCH]),
+            pending_responses: VecDeque::with_capacity(MAX_RECV_BATCH),
             tcp_probes: ArrayVec::new(),
+            checksum_stats: ChecksumStats::default(),
         })
     }
 }
 
+impl TracerChannel {
+    /// Change the target address to which subsequent probes are sent.
+    ///
+    /// The target address must be of the same address family as the source address of the channel.
+    pub fn set_target_addr(&mut self, target_addr: IpAddr) -> TraceResult<()> {
+        match (self.src_addr, target_addr) {
+            (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                self.dest_addr = target_addr;
+                Ok(())
+            }
+            _ => Err(TracerError::BadConfig(format!(
+                "target address {target_addr} is not of the same family as source address {}",
+                self.src_addr
+            ))),
+        }
+    }
+
+    /// Are unprivileged `ICMP` sockets permitted for the given address family on this platform?
+    #[must_use]
+    pub fn is_unprivileged_permitted(addr_family: TracerAddrFamily) -> bool {
+        match addr_family {
+            TracerAddrFamily::Ipv4 => Socket::new_icmp_dgram_socket_ipv4(),
+            TracerAddrFamily::Ipv6 => Socket::new_icmp_dgram_socket_ipv6(),
+        }
+        .is_ok()
+    }
+}
+
 impl Network for TracerChannel {
     fn send_probe(&mut self, probe: Probe) -> TraceResult<()> {
+        trace!(
+            sequence = probe.sequence.0,
+            ttl = probe.ttl.0,
+            "sending probe"
+        );
         match self.protocol {
             TracerProtocol::Icmp => self.dispatch_icmp_probe(probe),
             TracerProtocol::Udp => self.dispatch_udp_probe(probe),
@@ -76,14 +186,42 @@ impl Network for TracerChannel {
         }
     }
 
+    fn flush(&mut self) -> TraceResult<()> {
+        if self.pending_sends.is_empty() {
+            return Ok(());
+        }
+        let socket = match self.protocol {
+            TracerProtocol::Icmp => &self.icmp_send_socket,
+            TracerProtocol::Udp | TracerProtocol::Tcp => &self.udp_send_socket,
+        };
+        let packets = self
+            .pending_sends
+            .iter()
+            .zip(self.send_bufs.iter())
+            .map(|(&(len, addr), buf)| (&buf[..len], addr))
+            .collect::<ArrayVec<_, MAX_SEND_BATCH>>();
+        trace!(count = packets.len(), "sending probe batch");
+        let result = socket.send_batch(&packets);
+        self.pending_sends.clear();
+        Ok(result?)
+    }
+
     fn recv_probe(&mut self) -> TraceResult<Option<ProbeResponse>> {
-        match self.protocol {
+        let resp = match self.protocol {
             TracerProtocol::Icmp | TracerProtocol::Udp => self.recv_icmp_probe(),
             TracerProtocol::Tcp => match self.recv_tcp_sockets()? {
                 None => self.recv_icmp_probe(),
                 resp => Ok(resp),
             },
+        }?;
+        if let Some(resp) = &resp {
+            trace!(?resp, "received response");
         }
+        Ok(resp)
+    }
+
+    fn checksum_stats(&self) -> ChecksumStats {
+        self.checksum_stats
     }
 }
 
@@ -91,22 +229,46 @@ impl TracerChannel {
     /// Dispatch a ICMP probe.
     fn dispatch_icmp_probe(&mut self, probe: Probe) -> TraceResult<()> {
         match (self.src_addr, self.dest_addr) {
-            (IpAddr::V4(src_addr), IpAddr::V4(dest_addr)) => ipv4::dispatch_icmp_probe(
-                &mut self.icmp_send_socket,
-                probe,
-                src_addr,
-                dest_addr,
-                self.packet_size,
-                self.payload_pattern,
-                self.ipv4_length_order,
-            ),
+            (IpAddr::V4(_), IpAddr::V4(dest_addr))
+                if self.privilege_mode == PrivilegeMode::Unprivileged =>
+            {
+                ipv4::dispatch_icmp_probe_unprivileged(
+                    &self.icmp_send_socket,
+                    &mut self.send_bufs[0],
+                    probe,
+                    dest_addr,
+                    self.packet_size,
+                    self.payload_pattern,
+                    self.tos,
+                )
+            }
+            (IpAddr::V4(src_addr), IpAddr::V4(dest_addr)) => {
+                let index = self.next_send_buf()?;
+                let len = ipv4::make_icmp_probe(
+                    &mut self.send_bufs[index],
+                    probe,
+                    src_addr,
+                    dest_addr,
+                    self.packet_size,
+                    self.payload_pattern,
+                    self.tos,
+                    self.ipv4_length_order,
+                    self.record_route,
+                    self.icmp_timestamp,
+                    self.dont_fragment.unwrap_or(true),
+                )?;
+                self.queue_send(len, SocketAddr::new(IpAddr::V4(dest_addr), 0));
+                Ok(())
+            }
             (IpAddr::V6(src_addr), IpAddr::V6(dest_addr)) => ipv6::dispatch_icmp_probe(
                 &mut self.icmp_send_socket,
+                &mut self.send_bufs[0],
                 probe,
                 src_addr,
                 dest_addr,
                 self.packet_size,
                 self.payload_pattern,
+                self.tos,
             ),
             _ => unreachable!(),
         }
@@ -115,36 +277,73 @@ impl TracerChannel {
     /// Dispatch a UDP probe.
     fn dispatch_udp_probe(&mut self, probe: Probe) -> TraceResult<()> {
         match (self.src_addr, self.dest_addr) {
-            (IpAddr::V4(src_addr), IpAddr::V4(dest_addr)) => ipv4::dispatch_udp_probe(
-                &mut self.udp_send_socket,
-                probe,
-                src_addr,
-                dest_addr,
-                self.packet_size,
-                self.payload_pattern,
-                self.ipv4_length_order,
-            ),
+            (IpAddr::V4(src_addr), IpAddr::V4(dest_addr)) => {
+                let index = self.next_send_buf()?;
+                let len = ipv4::make_udp_probe(
+                    &mut self.send_bufs[index],
+                    probe,
+                    src_addr,
+                    dest_addr,
+                    self.packet_size,
+                    self.payload_pattern,
+                    self.tos,
+                    self.ipv4_length_order,
+                    self.record_route,
+                    self.dont_fragment.unwrap_or(true),
+                )?;
+                self.queue_send(
+                    len,
+                    SocketAddr::new(IpAddr::V4(dest_addr), probe.dest_port.0),
+                );
+                Ok(())
+            }
             (IpAddr::V6(src_addr), IpAddr::V6(dest_addr)) => ipv6::dispatch_udp_probe(
                 &mut self.udp_send_socket,
+                &mut self.send_bufs[0],
                 probe,
                 src_addr,
                 dest_addr,
                 self.packet_size,
                 self.payload_pattern,
+                self.tos,
             ),
             _ => unreachable!(),
         }
     }
 
+    /// The index of the send buffer in which to build the next queued probe, flushing the queue first if it is full.
+    fn next_send_buf(&mut self) -> TraceResult<usize> {
+        if self.pending_sends.is_full() {
+            self.flush()?;
+        }
+        Ok(self.pending_sends.len())
+    }
+
+    /// Queue a probe of `len` bytes, built in the buffer given by `next_send_buf`, to be sent to `remote_addr`.
+    fn queue_send(&mut self, len: usize, remote_addr: SocketAddr) {
+        self.pending_sends.push((len, remote_addr));
+    }
+
     /// Dispatch a TCP probe.
     fn dispatch_tcp_probe(&mut self, probe: Probe) -> TraceResult<()> {
         let socket = match (self.src_addr, self.dest_addr) {
-            (IpAddr::V4(src_addr), IpAddr::V4(dest_addr)) => {
-                ipv4::dispatch_tcp_probe(probe, src_addr, dest_addr, self.tos)
-            }
-            (IpAddr::V6(src_addr), IpAddr::V6(dest_addr)) => {
-                ipv6::dispatch_tcp_probe(probe, src_addr, dest_addr)
-            }
+            (IpAddr::V4(src_addr), IpAddr::V4(dest_addr)) => ipv4::dispatch_tcp_probe(
+                probe,
+                src_addr,
+                dest_addr,
+                self.tos,
+                self.tcp_options,
+                self.dont_fragment,
+            ),
+            (IpAddr::V6(src_addr), IpAddr::V6(dest_addr)) => ipv6::dispatch_tcp_probe(
+                probe,
+                src_addr,
+                dest_addr,
+                self.tos,
+                self.tcp_options,
+                self.ipv6_ext_header,
+                self.dont_fragment,
+            ),
             _ => unreachable!(),
         }?;
         self.tcp_probes
@@ -153,23 +352,86 @@ impl TracerChannel {
     }
 
     /// Generate a `ProbeResponse` for the next available ICMP packet, if any
+    ///
+    /// Incoming ICMP packets are read in batches and any responses not yet returned are held until the next call.
     fn recv_icmp_probe(&mut self) -> TraceResult<Option<ProbeResponse>> {
-        if self.recv_socket.is_readable(self.read_timeout)? {
-            match self.dest_addr {
-                IpAddr::V4(_) => ipv4::recv_icmp_probe(&mut self.recv_socket, self.protocol),
-                IpAddr::V6(_) => ipv6::recv_icmp_probe(&mut self.recv_socket, self.protocol),
+        if self.pending_responses.is_empty() {
+            match &mut self.receiver {
+                IcmpReceiver::Socket(recv_socket)
+                    if recv_socket.is_readable(self.read_timeout)? =>
+                {
+                    match (self.dest_addr, self.privilege_mode) {
+                        (IpAddr::V4(_), PrivilegeMode::Unprivileged) => {
+                            ipv4::recv_icmp_probes_unprivileged(
+                                recv_socket,
+                                self.recv_bufs.as_mut_slice(),
+                                &mut self.pending_responses,
+                            )?;
+                        }
+                        (IpAddr::V6(_), PrivilegeMode::Unprivileged) => {
+                            ipv6::recv_icmp_probes_unprivileged(
+                                recv_socket,
+                                self.recv_bufs.as_mut_slice(),
+                                &mut self.pending_responses,
+                            )?;
+                        }
+                        (IpAddr::V4(_), PrivilegeMode::Privileged) => ipv4::recv_icmp_probes(
+                            recv_socket,
+                            self.recv_bufs.as_mut_slice(),
+                            self.protocol,
+                            self.payload_pattern,
+                            &mut self.pending_responses,
+                            &mut self.checksum_stats,
+                        )?,
+                        (IpAddr::V6(_), PrivilegeMode::Privileged) => ipv6::recv_icmp_probes(
+                            recv_socket,
+                            self.recv_bufs.as_mut_slice(),
+                            self.protocol,
+                            self.payload_pattern,
+                            &mut self.pending_responses,
+                            &mut self.checksum_stats,
+                        )?,
+                    }
+                }
+                IcmpReceiver::Socket(_) => {}
+                IcmpReceiver::Shared(subscription) => {
+                    if let Some(batch) = subscription.recv_timeout(self.read_timeout)? {
+                        match self.dest_addr {
+                            IpAddr::V4(_) => ipv4::extract_probe_resps(
+                                batch.bufs(),
+                                batch.metas(),
+                                self.protocol,
+                                self.payload_pattern,
+                                &mut self.pending_responses,
+                                &mut self.checksum_stats,
+                            )?,
+                            IpAddr::V6(_) => ipv6::extract_probe_resps(
+                                batch.bufs(),
+                                batch.metas(),
+                                self.protocol,
+                                self.payload_pattern,
+                                &mut self.pending_responses,
+                                &mut self.checksum_stats,
+                            )?,
+                        }
+                    }
+                }
             }
-        } else {
-            Ok(None)
         }
+        Ok(self.pending_responses.pop_front())
     }
 
     /// Generate synthetic `ProbeResponse` if a TCP socket is connected or if the connection was refused.
     ///
     /// Any TCP socket which has not connected or failed after a timeout will be removed.
     fn recv_tcp_sockets(&mut self) -> TraceResult<Option<ProbeResponse>> {
-        self.tcp_probes
-            .retain(|probe| probe.start.elapsed().unwrap_or_default() < self.tcp_connect_timeout);
+        self.tcp_probes.retain(|probe| {
+            let retain = probe.start.elapsed().unwrap_or_default() < self.tcp_connect_timeout;
+            if !retain {
+                debug!(sequence = probe.sequence.0, "tcp connect timed out");
+            }
+            retain
+        });
         let found_index = self
             .tcp_probes
             .iter()
@@ -191,6 +453,14 @@ impl TracerChannel {
     }
 }
 
+/// The source of the `ICMP` packets received by a channel.
+enum IcmpReceiver {
+    /// A socket owned by the channel.
+    Socket(Socket),
+    /// A subscription to a `SharedReceiver`.
+    Shared(Subscription),
+}
+
 /// An entry in the TCP probes array.
 struct TcpProbe {
     socket: Socket,
@@ -208,6 +478,64 @@ impl TcpProbe {
     }
 }
 
+/// Set whether the probes sent on the sockets whose headers are built by the OS may be fragmented.
+///
+/// The `Don't Fragment` bit of the `IPv4` header of privileged `ICMP` and `UDP` probes is instead set when the probe is
+/// built, and the socket of each `TCP` probe is set when the probe is sent.
+fn set_dont_fragment(
+    config: &TracerChannelConfig,
+    icmp_send_socket: &Socket,
+    udp_send_socket: &Socket,
+    dont_fragment: bool,
+) -> TraceResult<()> {
+    match (config.source_addr, config.privilege_mode) {
+        (IpAddr::V4(_), PrivilegeMode::Privileged) => {}
+        (IpAddr::V4(_), PrivilegeMode::Unprivileged) => {
+            icmp_send_socket.set_dont_fragment(dont_fragment)?;
+        }
+        (IpAddr::V6(_), _) => {
+            icmp_send_socket.set_dont_fragment_v6(dont_fragment)?;
+            udp_send_socket.set_dont_fragment_v6(dont_fragment)?;
+        }
+    }
+    Ok(())
+}
+
+/// Validate that the `IPv4` `Record Route` option can be set on the probes of the channel.
+///
+/// The option is only set on privileged `ICMP` and `UDP` probes, for which the `IPv4` header is built rather than by
+/// the OS, and the probes must have room for the header with the option, of 60 bytes, and the `ICMP` or `UDP` header.
+fn validate_record_route(config: &TracerChannelConfig) -> TraceResult<()> {
+    const MIN_PACKET_SIZE: u16 = 68;
+    match (config.source_addr, config.protocol, config.privilege_mode) {
+        (IpAddr::V6(_), _, _) => Err(TracerError::BadConfig(String::from(
+            "record route may only be used with IPv4",
+        ))),
+        (_, TracerProtocol::Tcp, _) | (_, _, PrivilegeMode::Unprivileged) => {
+            Err(TracerError::BadConfig(String::from(
+                "record route may only be used with privileged icmp and udp probes",
+            )))
+        }
+        _ if config.packet_size.0 < MIN_PACKET_SIZE => Err(TracerError::BadConfig(format!(
+            "record route requires a packet size of at least {MIN_PACKET_SIZE}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Validate that `ICMP` `TimestampRequest` probes can be sent on the channel.
+///
+/// `TimestampRequest` probes are only defined for `IPv4` and cannot be sent on an unprivileged datagram socket, which
+/// only permits `EchoRequest` packets.
+fn validate_icmp_timestamp(config: &TracerChannelConfig) -> TraceResult<()> {
+    match (config.source_addr, config.protocol, config.privilege_mode) {
+        (IpAddr::V4(_), TracerProtocol::Icmp, PrivilegeMode::Privileged) => Ok(()),
+        _ => Err(TracerError::BadConfig(String::from(
+            "icmp timestamp may only be used with privileged IPv4 icmp probes",
+        ))),
+    }
+}
+
 /// Make a socket for sending raw `ICMP` packets.
 fn make_icmp_send_socket(addr: IpAddr) -> TraceResult<Socket> {
     Ok(match addr {
@@ -224,6 +552,14 @@ fn make_udp_send_socket(addr: IpAddr) -> TraceResult<Socket> {
     }?)
 }
 
+/// Make an unprivileged socket for sending `ICMP` probes and receiving responses.
+fn make_icmp_dgram_socket(addr: IpAddr) -> TraceResult<Socket> {
+    Ok(match addr {
+        IpAddr::V4(_) => Socket::new_icmp_dgram_socket_ipv4(),
+        IpAddr::V6(_) => Socket::new_icmp_dgram_socket_ipv6(),
+    }?)
+}
+
 /// Make a socket for receiving raw `ICMP` packets.
 fn make_recv_socket(addr: IpAddr) -> TraceResult<Socket> {
     Ok(match addr {
@@ -0,0 +1,301 @@
+use crate::tracing::packet::icmpv4::{IcmpPacket as IcmpV4Packet, IcmpType as IcmpV4Type};
+use crate::tracing::packet::icmpv6::{IcmpPacket as IcmpV6Packet, IcmpType as IcmpV6Type};
+use crate::tracing::packet::ipv4::Ipv4Packet;
+use crate::tracing::packet::ipv6::Ipv6Packet;
+use crate::tracing::packet::IpProtocol;
+use itertools::Itertools;
+use std::fmt::Write;
+use tracing::{enabled, trace, Level};
+
+/// The `tracing` target for packet dumps.
+///
+/// Packet dumps are emitted at `TRACE` level and are only generated if a subscriber is enabled for this target.
+pub const PACKET_DUMP_TARGET: &str = "trippy::packet_dump";
+
+/// The number of bytes shown on each line of a hex dump.
+const BYTES_PER_LINE: usize = 16;
+
+/// The size of the fixed `ICMP` header which precedes the original datagram quoted in error messages.
+const ICMP_HEADER_SIZE: usize = 8;
+
+/// The size of the fixed `IPv6` header.
+const IPV6_HEADER_SIZE: usize = 40;
+
+/// The direction of a dumped packet.
+#[derive(Debug, Copy, Clone)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// Dump an `IPv4` packet, and the `ICMP`, `UDP` or `TCP` packet it contains, as annotated hex.
+pub fn dump_ipv4(direction: Direction, bytes: &[u8]) {
+    if enabled!(target: PACKET_DUMP_TARGET, Level::TRACE) {
+        let mut dump = String::new();
+        write_ipv4(&mut dump, bytes, true);
+        write_hex(&mut dump, bytes);
+        emit(direction, "ipv4", bytes.len(), &dump);
+    }
+}
+
+/// Dump an `ICMP` packet (without the `IPv4` header) as annotated hex.
+pub fn dump_icmpv4(direction: Direction, bytes: &[u8]) {
+    if enabled!(target: PACKET_DUMP_TARGET, Level::TRACE) {
+        let mut dump = String::new();
+        write_icmpv4(&mut dump, bytes, true);
+        write_hex(&mut dump, bytes);
+        emit(direction, "icmp", bytes.len(), &dump);
+    }
+}
+
+/// Dump an `ICMPv6` packet (without the `IPv6` header) as annotated hex.
+pub fn dump_icmpv6(direction: Direction, bytes: &[u8]) {
+    if enabled!(target: PACKET_DUMP_TARGET, Level::TRACE) {
+        let mut dump = String::new();
+        write_icmpv6(&mut dump, bytes, true);
+        write_hex(&mut dump, bytes);
+        emit(direction, "icmpv6", bytes.len(), &dump);
+    }
+}
+
+/// Dump a `UDP` datagram (without the IP header) as annotated hex.
+pub fn dump_udp(direction: Direction, bytes: &[u8]) {
+    if enabled!(target: PACKET_DUMP_TARGET, Level::TRACE) {
+        let mut dump = String::new();
+        write_udp(&mut dump, bytes, "");
+        write_hex(&mut dump, bytes);
+        emit(direction, "udp", bytes.len(), &dump);
+    }
+}
+
+fn emit(direction: Direction, kind: &str, len: usize, dump: &str) {
+    trace!(target: PACKET_DUMP_TARGET, ?direction, kind, len, "\n{dump}");
+}
+
+/// Annotate an `IPv4` header and its payload.
+///
+/// If `outer` is true then any original datagram quoted in an `ICMP` error payload is annotated too.
+fn write_ipv4(dump: &mut String, bytes: &[u8], outer: bool) {
+    let indent = if outer { "" } else { "  " };
+    if let Some(ipv4) = Ipv4Packet::new_view(bytes) {
+        let _ = writeln!(
+            dump,
+            "{indent}ipv4: version={} ihl={} dscp={} ecn={} total_length={} identification={} flags_and_fragment_offset={:#06x} ttl={} protocol={:?} checksum={:#06x} source={} destination={}",
+            ipv4.get_version(),
+            ipv4.get_header_length(),
+            ipv4.get_dscp(),
+            ipv4.get_ecn(),
+            ipv4.get_total_length(),
+            ipv4.get_identification(),
+            ipv4.get_flags_and_fragment_offset(),
+            ipv4.get_ttl(),
+            ipv4.get_protocol(),
+            ipv4.get_checksum(),
+            ipv4.get_source(),
+            ipv4.get_destination(),
+        );
+        let payload = bytes
+            .get(usize::from(ipv4.get_header_length()) * 4..)
+            .unwrap_or_default();
+        match ipv4.get_protocol() {
+            IpProtocol::Icmp => write_icmpv4(dump, payload, outer),
+            IpProtocol::Udp => write_udp(dump, payload, indent),
+            IpProtocol::Tcp => write_tcp(dump, payload, indent),
+            _ => {}
+        }
+    }
+}
+
+/// Annotate an `IPv6` header and its payload, as quoted in an `ICMPv6` error.
+fn write_ipv6(dump: &mut String, bytes: &[u8]) {
+    if let Some(ipv6) = Ipv6Packet::new_view(bytes) {
+        let _ = writeln!(
+            dump,
+            "  ipv6: version={} traffic_class={} flow_label={} payload_length={} next_header={:?} hop_limit={} source={} destination={}",
+            ipv6.get_version(),
+            ipv6.get_traffic_class(),
+            ipv6.get_flow_label(),
+            ipv6.get_payload_length(),
+            ipv6.get_next_header(),
+            ipv6.get_hop_limit(),
+            ipv6.get_source_address(),
+            ipv6.get_destination_address(),
+        );
+        let payload = bytes.get(IPV6_HEADER_SIZE..).unwrap_or_default();
+        match ipv6.get_next_header() {
+            IpProtocol::IcmpV6 => write_icmpv6(dump, payload, false),
+            IpProtocol::Udp => write_udp(dump, payload, "  "),
+            IpProtocol::Tcp => write_tcp(dump, payload, "  "),
+            _ => {}
+        }
+    }
+}
+
+fn write_icmpv4(dump: &mut String, bytes: &[u8], outer: bool) {
+    let indent = if outer { "" } else { "  " };
+    if let Some(icmp) = IcmpV4Packet::new_view(bytes) {
+        let icmp_type = icmp.get_icmp_type();
+        let _ = write!(
+            dump,
+            "{indent}icmp: type={icmp_type:?} code={} checksum={:#06x}",
+            icmp.get_icmp_code().0,
+            icmp.get_checksum()
+        );
+        match icmp_type {
+            IcmpV4Type::EchoRequest | IcmpV4Type::EchoReply => {
+                let _ = writeln!(
+                    dump,
+                    " identifier={} sequence={}",
+                    be_u16(bytes, 4),
+                    be_u16(bytes, 6)
+                );
+            }
+            IcmpV4Type::TimeExceeded | IcmpV4Type::DestinationUnreachable if outer => {
+                let _ = writeln!(dump);
+                write_ipv4(
+                    dump,
+                    bytes.get(ICMP_HEADER_SIZE..).unwrap_or_default(),
+                    false,
+                );
+            }
+            _ => {
+                let _ = writeln!(dump);
+            }
+        }
+    }
+}
+
+fn write_icmpv6(dump: &mut String, bytes: &[u8], outer: bool) {
+    let indent = if outer { "" } else { "  " };
+    if let Some(icmp) = IcmpV6Packet::new_view(bytes) {
+        let icmp_type = icmp.get_icmp_type();
+        let _ = write!(
+            dump,
+            "{indent}icmpv6: type={icmp_type:?} code={} checksum={:#06x}",
+            icmp.get_icmp_code().0,
+            icmp.get_checksum()
+        );
+        match icmp_type {
+            IcmpV6Type::EchoRequest | IcmpV6Type::EchoReply => {
+                let _ = writeln!(
+                    dump,
+                    " identifier={} sequence={}",
+                    be_u16(bytes, 4),
+                    be_u16(bytes, 6)
+                );
+            }
+            IcmpV6Type::TimeExceeded
+            | IcmpV6Type::DestinationUnreachable
+            | IcmpV6Type::PacketTooBig
+                if outer =>
+            {
+                let _ = writeln!(dump);
+                write_ipv6(dump, bytes.get(ICMP_HEADER_SIZE..).unwrap_or_default());
+            }
+            _ => {
+                let _ = writeln!(dump);
+            }
+        }
+    }
+}
+
+/// Annotate a `UDP` header.
+///
+/// The header is read directly from the bytes as the datagram quoted in an `ICMP` error may be truncated.
+fn write_udp(dump: &mut String, bytes: &[u8], indent: &str) {
+    if bytes.len() >= 8 {
+        let _ = writeln!(
+            dump,
+            "{indent}udp: source={} destination={} length={} checksum={:#06x}",
+            be_u16(bytes, 0),
+            be_u16(bytes, 2),
+            be_u16(bytes, 4),
+            be_u16(bytes, 6)
+        );
+    }
+}
+
+/// Annotate the ports and sequence number of a `TCP` header.
+///
+/// Only the first 8 bytes of the header are read as the segment quoted in an `ICMP` error may be truncated.
+fn write_tcp(dump: &mut String, bytes: &[u8], indent: &str) {
+    if bytes.len() >= 8 {
+        let sequence = u32::from(be_u16(bytes, 4)) << 16 | u32::from(be_u16(bytes, 6));
+        let _ = writeln!(
+            dump,
+            "{indent}tcp: source={} destination={} sequence={}",
+            be_u16(bytes, 0),
+            be_u16(bytes, 2),
+            sequence
+        );
+    }
+}
+
+/// Write a hex dump of `bytes` with offsets and an ASCII column.
+fn write_hex(dump: &mut String, bytes: &[u8]) {
+    for (i, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let ascii: String = line
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() {
+                    char::from(b)
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        let _ = writeln!(
+            dump,
+            "{:04x}  {:<width$}  |{ascii}|",
+            i * BYTES_PER_LINE,
+            format!("{:02x}", line.iter().format(" ")),
+            width = BYTES_PER_LINE * 3 - 1
+        );
+    }
+}
+
+/// Read a big-endian `u16` at `offset`, or zero if out of bounds.
+fn be_u16(bytes: &[u8], offset: usize) -> u16 {
+    bytes
+        .get(offset..offset + 2)
+        .map_or(0, |b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_hex() {
+        let mut dump = String::new();
+        write_hex(&mut dump, b"0123456789abcdefXY\x00");
+        assert_eq!(
+            "0000  30 31 32 33 34 35 36 37 38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+             0010  58 59 00                                         |XY.|\n",
+            dump
+        );
+    }
+
+    #[test]
+    fn test_write_ipv4_time_exceeded() {
+        let bytes = [
+            0x45, 0x00, 0x00, 0x38, 0x00, 0x00, 0x00, 0x00, 0x3f, 0x01, 0x00, 0x00, 0x0a, 0x00,
+            0x00, 0x01, 0x0a, 0x00, 0x00, 0x02, 0x0b, 0x00, 0xf4, 0xff, 0x00, 0x00, 0x00, 0x00,
+            0x45, 0x00, 0x00, 0x54, 0x00, 0x00, 0x40, 0x00, 0x01, 0x01, 0x00, 0x00, 0x0a, 0x00,
+            0x00, 0x02, 0x01, 0x02, 0x03, 0x04, 0x08, 0x00, 0x00, 0x00, 0x12, 0x34, 0x80, 0xe8,
+        ];
+        let mut dump = String::new();
+        write_ipv4(&mut dump, &bytes, true);
+        let lines = dump.lines().collect::<Vec<_>>();
+        assert_eq!(4, lines.len());
+        assert!(lines[0].starts_with("ipv4: version=4 ihl=5"));
+        assert!(lines[0].ends_with("source=10.0.0.1 destination=10.0.0.2"));
+        assert_eq!("icmp: type=TimeExceeded code=0 checksum=0xf4ff", lines[1]);
+        assert!(lines[2].starts_with("  ipv4: version=4 ihl=5"));
+        assert!(lines[2].ends_with("source=10.0.0.2 destination=1.2.3.4"));
+        assert_eq!(
+            "  icmp: type=EchoRequest code=0 checksum=0x0000 identifier=4660 sequence=33000",
+            lines[3]
+        );
+    }
+}
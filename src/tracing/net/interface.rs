@@ -0,0 +1,181 @@
+use crate::tracing::error::{TraceResult, TracerError};
+use crate::tracing::net::platform;
+use std::net::IpAddr;
+
+/// A network interface.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Interface {
+    /// The index of the interface.
+    pub index: u32,
+    /// The name of the interface.
+    pub name: String,
+    /// The `IPv4` and `IPv6` addresses of the interface.
+    pub addrs: Vec<IpAddr>,
+}
+
+impl Interface {
+    /// List the network interfaces, ordered by index.
+    pub fn list() -> TraceResult<Vec<Self>> {
+        let mut interfaces = platform::interfaces()?;
+        interfaces.sort_by_key(|interface| interface.index);
+        Ok(interfaces)
+    }
+
+    /// Resolve an interface given by name or index to the interface name.
+    ///
+    /// Names are returned unchanged, an interface named with a number takes precedence over an interface with that
+    /// index.
+    pub fn resolve(interface: &str) -> TraceResult<String> {
+        match interface.parse::<u32>() {
+            Ok(index) => resolve_index(&Self::list()?, interface, index),
+            Err(_) => Ok(interface.to_string()),
+        }
+    }
+}
+
+/// A route in the local routing table.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Route {
+    /// The destination network of the route.
+    pub dest: IpAddr,
+    /// The prefix length of the destination network.
+    pub prefix_len: u8,
+    /// The next-hop gateway of the route, `None` if the destination is on-link.
+    pub gateway: Option<IpAddr>,
+    /// The name of the interface of the route.
+    pub interface: String,
+    /// The metric of the route, lower metrics are preferred.
+    pub metric: u32,
+}
+
+/// The local interface and first-hop gateway used to reach a target.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Egress {
+    /// The name of the interface which owns the source address, if known.
+    pub interface: Option<String>,
+    /// The first-hop gateway towards the target, `None` if unknown or if the target is on-link.
+    pub gateway: Option<IpAddr>,
+}
+
+impl Egress {
+    /// Discover the egress interface and gateway for probes sent from `source_addr` to `target_addr`.
+    ///
+    /// The gateway is only available on platforms where the routing table can be read.
+    pub fn discover(source_addr: IpAddr, target_addr: IpAddr) -> TraceResult<Self> {
+        let interface = Interface::list()?
+            .into_iter()
+            .find(|interface| interface.addrs.contains(&source_addr))
+            .map(|interface| interface.name);
+        let gateway = match &interface {
+            Some(name) => select_route(&platform::routes()?, name, target_addr)
+                .and_then(|route| route.gateway),
+            None => None,
+        };
+        Ok(Self { interface, gateway })
+    }
+}
+
+/// Select the route to `target_addr` via the given interface.
+///
+/// The longest matching prefix is selected, with the lowest metric breaking ties.
+fn select_route<'a>(
+    routes: &'a [Route],
+    interface: &str,
+    target_addr: IpAddr,
+) -> Option<&'a Route> {
+    routes
+        .iter()
+        .filter(|route| route.interface == interface && route_matches(route, target_addr))
+        .min_by_key(|route| (std::cmp::Reverse(route.prefix_len), route.metric))
+}
+
+/// Is `addr` within the destination network of the route?
+fn route_matches(route: &Route, addr: IpAddr) -> bool {
+    match (route.dest, addr) {
+        (IpAddr::V4(dest), IpAddr::V4(addr)) => {
+            let mask = u32::MAX
+                .checked_shl(32 - u32::from(route.prefix_len.min(32)))
+                .unwrap_or(0);
+            u32::from(dest) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(dest), IpAddr::V6(addr)) => {
+            let mask = u128::MAX
+                .checked_shl(128 - u32::from(route.prefix_len.min(128)))
+                .unwrap_or(0);
+            u128::from(dest) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Resolve an interface index, which may also be an interface name, to the interface name.
+fn resolve_index(interfaces: &[Interface], interface: &str, index: u32) -> TraceResult<String> {
+    interfaces
+        .iter()
+        .find(|candidate| candidate.name == interface)
+        .or_else(|| interfaces.iter().find(|candidate| candidate.index == index))
+        .map(|candidate| candidate.name.clone())
+        .ok_or_else(|| TracerError::UnknownInterface(interface.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("1", 1, Some("lo"); "by index")]
+    #[test_case("2", 2, Some("eth0"); "by second index")]
+    #[test_case("3", 3, Some("3"); "numeric name")]
+    #[test_case("9", 9, None; "unknown index")]
+    fn test_resolve_index(interface: &str, index: u32, expected: Option<&str>) {
+        let interfaces = [
+            Interface {
+                index: 1,
+                name: String::from("lo"),
+                addrs: vec![],
+            },
+            Interface {
+                index: 2,
+                name: String::from("eth0"),
+                addrs: vec![],
+            },
+            Interface {
+                index: 5,
+                name: String::from("3"),
+                addrs: vec![],
+            },
+        ];
+        let resolved = resolve_index(&interfaces, interface, index).ok();
+        assert_eq!(expected.map(String::from), resolved);
+    }
+
+    #[test_case("eth0", "192.168.1.1", Some("10.0.0.1"); "default route")]
+    #[test_case("eth0", "10.0.0.7", None; "on-link network")]
+    #[test_case("eth0", "10.8.1.1", Some("10.0.0.2"); "longest prefix")]
+    #[test_case("wg0", "192.168.1.1", Some("10.9.0.1"); "lowest metric")]
+    #[test_case("eth1", "192.168.1.1", None; "unknown interface")]
+    #[test_case("eth0", "2001:db8::1", None; "other family")]
+    fn test_select_route(interface: &str, target: &str, expected_gateway: Option<&str>) {
+        let route =
+            |dest: &str, prefix_len, gateway: Option<&str>, interface: &str, metric| Route {
+                dest: dest.parse().unwrap(),
+                prefix_len,
+                gateway: gateway.map(|gateway| gateway.parse().unwrap()),
+                interface: String::from(interface),
+                metric,
+            };
+        let routes = [
+            route("0.0.0.0", 0, Some("10.0.0.1"), "eth0", 100),
+            route("10.0.0.0", 24, None, "eth0", 0),
+            route("10.8.0.0", 16, Some("10.0.0.2"), "eth0", 0),
+            route("0.0.0.0", 0, Some("10.9.0.2"), "wg0", 50),
+            route("0.0.0.0", 0, Some("10.9.0.1"), "wg0", 10),
+        ];
+        let gateway = select_route(&routes, interface, target.parse().unwrap())
+            .and_then(|route| route.gateway);
+        assert_eq!(
+            expected_gateway.map(|gateway| gateway.parse().unwrap()),
+            gateway
+        );
+    }
+}
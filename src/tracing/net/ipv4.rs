@@ -1,123 +1,193 @@
 use crate::tracing::error::TracerError::AddressNotAvailable;
 use crate::tracing::error::{TraceResult, TracerError};
 use crate::tracing::net::channel::MAX_PACKET_SIZE;
+use crate::tracing::net::dump::{self, Direction};
 use crate::tracing::net::platform;
 use crate::tracing::net::platform::Socket;
-use crate::tracing::net::socket::TracerSocket as _;
+use crate::tracing::net::socket::{RecvMeta, TracerSocket as _};
+use crate::tracing::net::ChecksumStats;
 use crate::tracing::packet::checksum::{icmp_ipv4_checksum, udp_ipv4_checksum};
 use crate::tracing::packet::icmpv4::destination_unreachable::DestinationUnreachablePacket;
 use crate::tracing::packet::icmpv4::echo_reply::EchoReplyPacket;
 use crate::tracing::packet::icmpv4::echo_request::EchoRequestPacket;
 use crate::tracing::packet::icmpv4::time_exceeded::TimeExceededPacket;
+use crate::tracing::packet::icmpv4::timestamp::TimestampPacket;
 use crate::tracing::packet::icmpv4::{IcmpCode, IcmpPacket, IcmpType};
 use crate::tracing::packet::ipv4::Ipv4Packet;
 use crate::tracing::packet::tcp::TcpPacket;
 use crate::tracing::packet::udp::UdpPacket;
 use crate::tracing::packet::IpProtocol;
 use crate::tracing::probe::{
-    ProbeResponse, ProbeResponseData, ProbeResponseSeq, ProbeResponseSeqIcmp, ProbeResponseSeqTcp,
-    ProbeResponseSeqUdp,
+    IcmpTimestamps, ProbeResponse, ProbeResponseData, ProbeResponseSeq, ProbeResponseSeqIcmp,
+    ProbeResponseSeqTcp, ProbeResponseSeqUdp, RecordedRoute, TcpHandshake,
+};
+use crate::tracing::types::{
+    PacketSize, PayloadPattern, Sequence, TimeToLive, TraceId, TypeOfService,
 };
-use crate::tracing::types::{PacketSize, PayloadPattern, Sequence, TraceId, TypeOfService};
 use crate::tracing::util::Required;
-use crate::tracing::{Probe, TracerProtocol};
+use crate::tracing::{Probe, TcpOptions, TracerProtocol};
+use std::collections::VecDeque;
 use std::io::ErrorKind;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::time::SystemTime;
+use tracing::warn;
 
-/// The maximum size of UDP packet we allow.
-const MAX_UDP_PACKET_BUF: usize = MAX_PACKET_SIZE - Ipv4Packet::minimum_packet_size();
+/// The value for the IPv4 `flags_and_fragment_offset` field to set the `Don't fragment` bit.
+///
+/// 0100 0000 0000 0000
+const DONT_FRAGMENT: u16 = 0x4000;
 
-/// The maximum size of UDP payload we allow.
-const MAX_UDP_PAYLOAD_BUF: usize = MAX_UDP_PACKET_BUF - UdpPacket::minimum_packet_size();
+/// The `ICMP` code of a `DestinationUnreachable` response for a probe which must be fragmented to be forwarded but has
+/// the `Don't fragment` bit set.
+const FRAGMENTATION_NEEDED: u8 = 4;
 
-/// The maximum size of ICMP packet we allow.
-const MAX_ICMP_PACKET_BUF: usize = MAX_PACKET_SIZE - Ipv4Packet::minimum_packet_size();
+/// The `IPv4` option type of the `No Operation` option.
+const OPTION_NO_OPERATION: u8 = 1;
 
-/// The maximum size of ICMP payload we allow.
-const MAX_ICMP_PAYLOAD_BUF: usize = MAX_ICMP_PACKET_BUF - IcmpPacket::minimum_packet_size();
+/// The `IPv4` option type of the `End of Options List` option.
+const OPTION_END_OF_LIST: u8 = 0;
 
-/// The value for the IPv4 `flags_and_fragment_offset` field to set the `Don't fragment` bit.
+/// The `IPv4` option type of the `Record Route` option.
+const OPTION_RECORD_ROUTE: u8 = 7;
+
+/// The length of the `IPv4` header, in 32-bit words, with the `Record Route` option.
 ///
-/// 0100 0000 0000 0000
-const DONT_FRAGMENT: u16 = 0x4000;
+/// The option has room to record 9 addresses and, as with `ping -R`, is preceded by a `No Operation` option such that
+/// the addresses are aligned, which is the maximum `IPv4` header length of 60 bytes.
+const RECORD_ROUTE_HEADER_LENGTH: u8 = 15;
 
+/// Build a privileged `ICMP` probe, including the `IPv4` header, in the `send_buf` and return its length.
+///
+/// The probe is sent, possibly in a batch with other probes, to the `dest_addr` by the caller.
 #[allow(clippy::too_many_arguments)]
-pub fn dispatch_icmp_probe(
-    icmp_send_socket: &mut Socket,
+pub fn make_icmp_probe(
+    send_buf: &mut [u8],
     probe: Probe,
     src_addr: Ipv4Addr,
     dest_addr: Ipv4Addr,
     packet_size: PacketSize,
     payload_pattern: PayloadPattern,
+    tos: TypeOfService,
     ipv4_byte_order: platform::PlatformIpv4FieldByteOrder,
-) -> TraceResult<()> {
-    let mut ipv4_buf = [0_u8; MAX_PACKET_SIZE];
-    let mut icmp_buf = [0_u8; MAX_ICMP_PACKET_BUF];
+    record_route: bool,
+    icmp_timestamp: bool,
+    dont_fragment: bool,
+) -> TraceResult<usize> {
     let packet_size = usize::from(packet_size.0);
     if packet_size > MAX_PACKET_SIZE {
         return Err(TracerError::InvalidPacketSize(packet_size));
     }
-    let echo_request = make_echo_request_icmp_packet(
-        &mut icmp_buf,
-        probe.identifier,
-        probe.sequence,
-        icmp_payload_size(packet_size),
-        payload_pattern,
-    )?;
+    let ipv4_buf = if icmp_timestamp {
+        let ipv4_buf = send_buf
+            .get_mut(..header_size(record_route) + TimestampPacket::minimum_packet_size())
+            .req()?;
+        make_timestamp_request_icmp_packet(
+            ipv4_buf.get_mut(header_size(record_route)..).req()?,
+            probe.identifier,
+            probe.sequence,
+            probe.sent.unwrap_or_else(SystemTime::now),
+        )?;
+        ipv4_buf
+    } else {
+        let ipv4_buf = &mut send_buf[..packet_size];
+        make_echo_request_icmp_packet(
+            ipv4_buf.get_mut(header_size(record_route)..).req()?,
+            probe.identifier,
+            probe.sequence,
+            payload_pattern,
+        )?;
+        ipv4_buf
+    };
     let ipv4 = make_ipv4_packet(
-        &mut ipv4_buf,
+        ipv4_buf,
         ipv4_byte_order,
         IpProtocol::Icmp,
         src_addr,
         dest_addr,
         probe.ttl.0,
+        tos,
         0,
-        echo_request.packet(),
+        record_route,
+        dont_fragment,
+    )?;
+    dump::dump_ipv4(Direction::Sent, ipv4.packet());
+    Ok(ipv4.packet().len())
+}
+
+/// Dispatch an `ICMP` probe on an unprivileged datagram socket.
+///
+/// The `IPv4` header is added by the kernel, which also replaces the echo `identifier` and `checksum`.
+pub fn dispatch_icmp_probe_unprivileged(
+    icmp_send_socket: &Socket,
+    send_buf: &mut [u8],
+    probe: Probe,
+    dest_addr: Ipv4Addr,
+    packet_size: PacketSize,
+    payload_pattern: PayloadPattern,
+    tos: TypeOfService,
+) -> TraceResult<()> {
+    let packet_size = usize::from(packet_size.0);
+    if packet_size > MAX_PACKET_SIZE {
+        return Err(TracerError::InvalidPacketSize(packet_size));
+    }
+    let echo_request = make_echo_request_icmp_packet(
+        &mut send_buf[..packet_size - Ipv4Packet::minimum_packet_size()],
+        probe.identifier,
+        probe.sequence,
+        payload_pattern,
     )?;
+    icmp_send_socket.set_ttl(u32::from(probe.ttl.0))?;
+    icmp_send_socket.set_tos(u32::from(tos.0))?;
     let remote_addr = SocketAddr::new(IpAddr::V4(dest_addr), 0);
-    icmp_send_socket.send_to(ipv4.packet(), remote_addr)?;
+    dump::dump_icmpv4(Direction::Sent, echo_request.packet());
+    icmp_send_socket.send_to(echo_request.packet(), remote_addr)?;
     Ok(())
 }
 
+/// Build a privileged `UDP` probe, including the `IPv4` header, in the `send_buf` and return its length.
+///
+/// The probe is sent, possibly in a batch with other probes, to the `dest_addr` and `dest_port` of the probe by the
+/// caller.
 #[allow(clippy::too_many_arguments)]
-pub fn dispatch_udp_probe(
-    raw_send_socket: &mut Socket,
+pub fn make_udp_probe(
+    send_buf: &mut [u8],
     probe: Probe,
     src_addr: Ipv4Addr,
     dest_addr: Ipv4Addr,
     packet_size: PacketSize,
     payload_pattern: PayloadPattern,
+    tos: TypeOfService,
     ipv4_byte_order: platform::PlatformIpv4FieldByteOrder,
-) -> TraceResult<()> {
-    let mut ipv4_buf = [0_u8; MAX_PACKET_SIZE];
-    let mut udp_buf = [0_u8; MAX_UDP_PACKET_BUF];
+    record_route: bool,
+    dont_fragment: bool,
+) -> TraceResult<usize> {
     let packet_size = usize::from(packet_size.0);
     if packet_size > MAX_PACKET_SIZE {
         return Err(TracerError::InvalidPacketSize(packet_size));
     }
-    let udp = make_udp_packet(
-        &mut udp_buf,
+    let ipv4_buf = &mut send_buf[..packet_size];
+    make_udp_packet(
+        ipv4_buf.get_mut(header_size(record_route)..).req()?,
         src_addr,
         dest_addr,
         probe.src_port.0,
         probe.dest_port.0,
-        udp_payload_size(packet_size),
         payload_pattern,
     )?;
     let ipv4 = make_ipv4_packet(
-        &mut ipv4_buf,
+        ipv4_buf,
         ipv4_byte_order,
         IpProtocol::Udp,
         src_addr,
         dest_addr,
         probe.ttl.0,
+        tos,
         probe.identifier.0,
-        udp.packet(),
+        record_route,
+        dont_fragment,
     )?;
-    let remote_addr = SocketAddr::new(IpAddr::V4(dest_addr), probe.dest_port.0);
-    raw_send_socket.send_to(ipv4.packet(), remote_addr)?;
-    Ok(())
+    dump::dump_ipv4(Direction::Sent, ipv4.packet());
+    Ok(ipv4.packet().len())
 }
 
 pub fn dispatch_tcp_probe(
@@ -125,6 +195,8 @@ pub fn dispatch_tcp_probe(
     src_addr: Ipv4Addr,
     dest_addr: Ipv4Addr,
     tos: TypeOfService,
+    tcp_options: TcpOptions,
+    dont_fragment: Option<bool>,
 ) -> TraceResult<Socket> {
     fn process_result(addr: SocketAddr, res: std::io::Result<()>) -> TraceResult<()> {
         match res {
@@ -152,23 +224,162 @@ pub fn dispatch_tcp_probe(
     process_result(local_addr, socket.bind(local_addr))?;
     socket.set_ttl(u32::from(probe.ttl.0))?;
     socket.set_tos(u32::from(tos.0))?;
+    if let Some(dont_fragment) = dont_fragment {
+        socket.set_dont_fragment(dont_fragment)?;
+    }
+    set_tcp_options(&socket, tcp_options)?;
     let remote_addr = SocketAddr::new(IpAddr::V4(dest_addr), probe.dest_port.0);
     process_result(remote_addr, socket.connect(remote_addr))?;
     Ok(socket)
 }
 
-pub fn recv_icmp_probe(
+/// Apply the configured `TcpOptions` to a TCP probe socket prior to connecting.
+fn set_tcp_options(socket: &Socket, tcp_options: TcpOptions) -> TraceResult<()> {
+    if let Some(mss) = tcp_options.mss {
+        socket.set_mss(u32::from(mss.0))?;
+    }
+    if let Some(window_size) = tcp_options.window_size {
+        socket.set_recv_buffer_size(window_size.0 as usize)?;
+    }
+    Ok(())
+}
+
+pub fn recv_icmp_probes(
     recv_socket: &mut Socket,
+    recv_bufs: &mut [[u8; MAX_PACKET_SIZE]],
     protocol: TracerProtocol,
-) -> TraceResult<Option<ProbeResponse>> {
-    let mut buf = [0_u8; MAX_PACKET_SIZE];
-    match recv_socket.read(&mut buf) {
-        Ok(_bytes_read) => {
-            let ipv4 = Ipv4Packet::new_view(&buf).req()?;
-            Ok(extract_probe_resp(protocol, &ipv4)?)
+    payload_pattern: PayloadPattern,
+    responses: &mut VecDeque<ProbeResponse>,
+    checksum_stats: &mut ChecksumStats,
+) -> TraceResult<()> {
+    match recv_socket.recv_batch(recv_bufs) {
+        Ok(metas) => {
+            prepare_received(recv_bufs, &metas)?;
+            extract_probe_resps(
+                recv_bufs,
+                &metas,
+                protocol,
+                payload_pattern,
+                responses,
+                checksum_stats,
+            )
         }
         Err(err) => match err.kind() {
-            ErrorKind::WouldBlock => Ok(None),
+            ErrorKind::WouldBlock => Ok(()),
+            _ => Err(TracerError::IoError(err)),
+        },
+    }
+}
+
+/// Prepare each of a batch of received packets for extraction.
+///
+/// Any bytes left over from a previously received, longer, packet are zeroed and the `IPv4` total length is set to the
+/// number of bytes received.  This must be done exactly once for each packet received, before the packet is passed to
+/// `extract_probe_resps`.
+pub fn prepare_received(
+    recv_bufs: &mut [[u8; MAX_PACKET_SIZE]],
+    metas: &[RecvMeta],
+) -> TraceResult<()> {
+    for (buf, meta) in recv_bufs.iter_mut().zip(metas) {
+        buf[meta.len..].fill(0);
+        dump::dump_ipv4(Direction::Received, &buf[..meta.len]);
+        set_received_total_length(&mut buf[..], meta.len)?;
+    }
+    Ok(())
+}
+
+/// Extract the `ProbeResponse`, if any, from each of a batch of received, and prepared, packets.
+///
+/// Packets which are not responses to our probes are ignored and packets which cannot be parsed are dropped.
+pub fn extract_probe_resps(
+    recv_bufs: &[[u8; MAX_PACKET_SIZE]],
+    metas: &[RecvMeta],
+    protocol: TracerProtocol,
+    payload_pattern: PayloadPattern,
+    responses: &mut VecDeque<ProbeResponse>,
+    checksum_stats: &mut ChecksumStats,
+) -> TraceResult<()> {
+    for (buf, meta) in recv_bufs.iter().zip(metas) {
+        let recv = meta.recv.unwrap_or_else(SystemTime::now);
+        let ipv4 = Ipv4Packet::new_view(&buf[..]).req()?;
+        match extract_probe_resp(protocol, &ipv4, recv, payload_pattern) {
+            Ok(Some(resp)) => {
+                validate_checksums(ipv4.payload(), checksum_stats);
+                responses.push_back(resp);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                warn!(src = %ipv4.get_source(), %err, "dropped response which could not be parsed");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Receive the responses to `ICMP` probes sent on an unprivileged datagram socket.
+///
+/// `EchoReply` packets are received without the `IPv4` header, whereas `TimeExceeded` and `DestinationUnreachable`
+/// errors are received from the socket error queue as the original echo request.
+///
+/// The kernel only delivers responses to probes sent from the socket and so the `identifier`, which the kernel
+/// replaces, is reported as zero.
+pub fn recv_icmp_probes_unprivileged(
+    recv_socket: &mut Socket,
+    recv_bufs: &mut [[u8; MAX_PACKET_SIZE]],
+    responses: &mut VecDeque<ProbeResponse>,
+) -> TraceResult<()> {
+    let err_buf = recv_bufs.first_mut().req()?;
+    while let Some(meta) = recv_socket.recv_err(err_buf)? {
+        let Some(echo_request) = EchoRequestPacket::new_view(&err_buf[..meta.len]) else {
+            warn!(src = %meta.offender, "dropped error which could not be parsed");
+            continue;
+        };
+        let resp_seq =
+            ProbeResponseSeq::Icmp(ProbeResponseSeqIcmp::new(0, echo_request.get_sequence()));
+        let data = ProbeResponseData::new(SystemTime::now(), meta.offender, resp_seq)
+            .with_icmp_code(meta.icmp_code);
+        match IcmpType::from(meta.icmp_type) {
+            IcmpType::TimeExceeded => responses.push_back(ProbeResponse::TimeExceeded(data)),
+            IcmpType::DestinationUnreachable if meta.icmp_code == FRAGMENTATION_NEEDED => {
+                let mtu = u16::try_from(meta.info).unwrap_or(u16::MAX);
+                responses.push_back(ProbeResponse::DestinationUnreachable(
+                    data.with_next_hop_mtu(mtu),
+                ));
+            }
+            IcmpType::DestinationUnreachable => {
+                responses.push_back(ProbeResponse::DestinationUnreachable(data));
+            }
+            _ => {}
+        }
+    }
+    match recv_socket.recv_batch(recv_bufs) {
+        Ok(metas) => {
+            for (buf, meta) in recv_bufs.iter_mut().zip(metas) {
+                dump::dump_icmpv4(Direction::Received, &buf[..meta.len]);
+                let recv = meta.recv.unwrap_or_else(SystemTime::now);
+                let (Some(echo_reply), Some(addr)) =
+                    (EchoReplyPacket::new_view(&buf[..meta.len]), meta.addr)
+                else {
+                    warn!("dropped response which could not be parsed");
+                    continue;
+                };
+                if echo_reply.get_icmp_type() == IcmpType::EchoReply {
+                    let resp_seq = ProbeResponseSeq::Icmp(ProbeResponseSeqIcmp::new(
+                        0,
+                        echo_reply.get_sequence(),
+                    ));
+                    let data = ProbeResponseData::new(recv, addr.ip(), resp_seq)
+                        .with_icmp_code(echo_reply.get_icmp_code().0);
+                    responses.push_back(ProbeResponse::EchoReply(match meta.ttl {
+                        Some(ttl) => data.with_ttl(TimeToLive(ttl)),
+                        None => data,
+                    }));
+                }
+            }
+            Ok(())
+        }
+        Err(err) => match err.kind() {
+            ErrorKind::WouldBlock => Ok(()),
             _ => Err(TracerError::IoError(err)),
         },
     }
@@ -184,20 +395,18 @@ pub fn recv_tcp_socket(
         None => {
             let addr = tcp_socket.peer_addr()?.req()?.ip();
             tcp_socket.shutdown()?;
-            return Ok(Some(ProbeResponse::TcpReply(ProbeResponseData::new(
-                SystemTime::now(),
-                addr,
-                resp_seq,
-            ))));
+            return Ok(Some(ProbeResponse::TcpReply(
+                ProbeResponseData::new(SystemTime::now(), addr, resp_seq)
+                    .with_tcp_handshake(TcpHandshake::Established),
+            )));
         }
         Some(err) => {
             if let Some(code) = err.raw_os_error() {
                 if platform::is_conn_refused_error(code) {
-                    return Ok(Some(ProbeResponse::TcpRefused(ProbeResponseData::new(
-                        SystemTime::now(),
-                        dest_addr,
-                        resp_seq,
-                    ))));
+                    return Ok(Some(ProbeResponse::TcpRefused(
+                        ProbeResponseData::new(SystemTime::now(), dest_addr, resp_seq)
+                            .with_tcp_handshake(TcpHandshake::Refused),
+                    )));
                 }
                 if platform::is_host_unreachable_error(code) {
                     let error_addr = tcp_socket.icmp_error_info()?;
@@ -213,22 +422,53 @@ pub fn recv_tcp_socket(
     Ok(None)
 }
 
+/// Set the `total_length` of a received `IPv4` packet from the number of bytes received.
+///
+/// Some BSD derived platforms, such as macOS and older versions of FreeBSD, deliver packets read from a raw socket
+/// with the `total_length` in host byte order and excluding the header length, which would otherwise cause the payload
+/// to be truncated.
+fn set_received_total_length(buf: &mut [u8], len: usize) -> TraceResult<()> {
+    let mut ipv4 = Ipv4Packet::new(buf).req()?;
+    ipv4.set_total_length(u16::try_from(len).unwrap_or(u16::MAX));
+    Ok(())
+}
+
 /// Create an ICMP `EchoRequest` packet.
 fn make_echo_request_icmp_packet(
     icmp_buf: &mut [u8],
     identifier: TraceId,
     sequence: Sequence,
-    payload_size: usize,
     payload_pattern: PayloadPattern,
 ) -> TraceResult<EchoRequestPacket<'_>> {
-    let payload_buf = [payload_pattern.0; MAX_ICMP_PAYLOAD_BUF];
-    let packet_size = IcmpPacket::minimum_packet_size() + payload_size;
-    let mut icmp = EchoRequestPacket::new(&mut icmp_buf[..packet_size]).req()?;
+    fill_payload(icmp_buf, IcmpPacket::minimum_packet_size(), payload_pattern)?;
+    let mut icmp = EchoRequestPacket::new(icmp_buf).req()?;
     icmp.set_icmp_type(IcmpType::EchoRequest);
     icmp.set_icmp_code(IcmpCode(0));
     icmp.set_identifier(identifier.0);
-    icmp.set_payload(&payload_buf[..payload_size]);
     icmp.set_sequence(sequence.0);
+    icmp.set_checksum(0);
+    icmp.set_checksum(icmp_ipv4_checksum(icmp.packet()));
+    Ok(icmp)
+}
+
+/// Create an ICMP `TimestampRequest` packet with an `originate` timestamp of `sent`.
+///
+/// The packet has no payload, and so the `packet_size` and `payload_pattern` do not apply.
+fn make_timestamp_request_icmp_packet(
+    icmp_buf: &mut [u8],
+    identifier: TraceId,
+    sequence: Sequence,
+    sent: SystemTime,
+) -> TraceResult<TimestampPacket<'_>> {
+    let mut icmp = TimestampPacket::new(icmp_buf).req()?;
+    icmp.set_icmp_type(IcmpType::TimestampRequest);
+    icmp.set_icmp_code(IcmpCode(0));
+    icmp.set_identifier(identifier.0);
+    icmp.set_sequence(sequence.0);
+    icmp.set_originate(IcmpTimestamps::timestamp(sent));
+    icmp.set_receive(0);
+    icmp.set_transmit(0);
+    icmp.set_checksum(0);
     icmp.set_checksum(icmp_ipv4_checksum(icmp.packet()));
     Ok(icmp)
 }
@@ -240,81 +480,152 @@ fn make_udp_packet(
     dest_addr: Ipv4Addr,
     src_port: u16,
     dest_port: u16,
-    payload_size: usize,
     payload_pattern: PayloadPattern,
 ) -> TraceResult<UdpPacket<'_>> {
-    let udp_payload_buf = [payload_pattern.0; MAX_UDP_PAYLOAD_BUF];
-    let udp_packet_size = UdpPacket::minimum_packet_size() + payload_size;
-    let mut udp = UdpPacket::new(&mut udp_buf[..udp_packet_size]).req()?;
+    fill_payload(udp_buf, UdpPacket::minimum_packet_size(), payload_pattern)?;
+    let udp_packet_size = udp_buf.len();
+    let mut udp = UdpPacket::new(udp_buf).req()?;
     udp.set_source(src_port);
     udp.set_destination(dest_port);
     udp.set_length(udp_packet_size as u16);
-    udp.set_payload(&udp_payload_buf[..payload_size]);
+    udp.set_checksum(0);
     udp.set_checksum(udp_ipv4_checksum(udp.packet(), src_addr, dest_addr));
     Ok(udp)
 }
 
-/// Create an `Ipv4Packet`.
+/// Create an `Ipv4Packet` header in place around the payload already written to the end of the buffer.
 #[allow(clippy::too_many_arguments)]
-fn make_ipv4_packet<'a>(
-    ipv4_buf: &'a mut [u8],
+fn make_ipv4_packet(
+    ipv4_buf: &mut [u8],
     ipv4_byte_order: platform::PlatformIpv4FieldByteOrder,
     protocol: IpProtocol,
     src_addr: Ipv4Addr,
     dest_addr: Ipv4Addr,
     ttl: u8,
+    tos: TypeOfService,
     identification: u16,
-    payload: &[u8],
-) -> TraceResult<Ipv4Packet<'a>> {
-    let ipv4_total_length = (Ipv4Packet::minimum_packet_size() + payload.len()) as u16;
+    record_route: bool,
+    dont_fragment: bool,
+) -> TraceResult<Ipv4Packet<'_>> {
+    let ipv4_total_length = ipv4_buf.len() as u16;
     let ipv4_total_length_header = ipv4_byte_order.adjust_length(ipv4_total_length);
-    let ipv4_flags_and_fragment_offset_header = ipv4_byte_order.adjust_length(DONT_FRAGMENT);
-    let mut ipv4 = Ipv4Packet::new(&mut ipv4_buf[..ipv4_total_length as usize]).req()?;
+    let ipv4_flags_and_fragment_offset = if dont_fragment { DONT_FRAGMENT } else { 0 };
+    let ipv4_flags_and_fragment_offset_header =
+        ipv4_byte_order.adjust_length(ipv4_flags_and_fragment_offset);
+    let header_size = header_size(record_route);
+    ipv4_buf.get_mut(..header_size).req()?.fill(0);
+    if record_route {
+        // the option is followed by the (zeroed) room for the addresses to be recorded
+        let option_len = header_size - Ipv4Packet::minimum_packet_size() - 1;
+        ipv4_buf
+            .get_mut(Ipv4Packet::minimum_packet_size()..)
+            .req()?
+            .get_mut(..4)
+            .req()?
+            .copy_from_slice(&[
+                OPTION_NO_OPERATION,
+                OPTION_RECORD_ROUTE,
+                option_len as u8,
+                4,
+            ]);
+    }
+    let mut ipv4 = Ipv4Packet::new(ipv4_buf).req()?;
     ipv4.set_version(4);
-    ipv4.set_header_length(5);
+    ipv4.set_header_length(if record_route {
+        RECORD_ROUTE_HEADER_LENGTH
+    } else {
+        5
+    });
+    ipv4.set_dscp(tos.0 >> 2);
+    ipv4.set_ecn(tos.0 & 0x3);
     ipv4.set_total_length(ipv4_total_length_header);
     ipv4.set_ttl(ttl);
     ipv4.set_protocol(protocol);
     ipv4.set_source(src_addr);
     ipv4.set_destination(dest_addr);
-    ipv4.set_payload(payload);
     ipv4.set_identification(identification);
     ipv4.set_flags_and_fragment_offset(ipv4_flags_and_fragment_offset_header);
     Ok(ipv4)
 }
 
-fn icmp_payload_size(packet_size: usize) -> usize {
-    let ip_header_size = Ipv4Packet::minimum_packet_size();
-    let icmp_header_size = IcmpPacket::minimum_packet_size();
-    packet_size - icmp_header_size - ip_header_size
+/// The size of the `IPv4` header, with the `Record Route` option if `record_route` is set.
+fn header_size(record_route: bool) -> usize {
+    if record_route {
+        usize::from(RECORD_ROUTE_HEADER_LENGTH) * 4
+    } else {
+        Ipv4Packet::minimum_packet_size()
+    }
 }
 
-fn udp_payload_size(packet_size: usize) -> usize {
-    let ip_header_size = Ipv4Packet::minimum_packet_size();
-    let udp_header_size = UdpPacket::minimum_packet_size();
-    packet_size - udp_header_size - ip_header_size
+/// Fill the payload of a packet, which follows a header of `header_size` bytes, with the `payload_pattern`.
+fn fill_payload(
+    packet_buf: &mut [u8],
+    header_size: usize,
+    payload_pattern: PayloadPattern,
+) -> TraceResult<()> {
+    packet_buf
+        .get_mut(header_size..)
+        .req()?
+        .fill(payload_pattern.0);
+    Ok(())
 }
 
 fn extract_probe_resp(
     protocol: TracerProtocol,
     ipv4: &Ipv4Packet<'_>,
+    recv: SystemTime,
+    payload_pattern: PayloadPattern,
 ) -> TraceResult<Option<ProbeResponse>> {
-    let recv = SystemTime::now();
     let src = IpAddr::V4(ipv4.get_source());
+    let ttl = TimeToLive(ipv4.get_ttl());
     let icmp_v4 = IcmpPacket::new_view(ipv4.payload()).req()?;
+    let code = icmp_v4.get_icmp_code().0;
     Ok(match icmp_v4.get_icmp_type() {
         IcmpType::TimeExceeded => {
             let packet = TimeExceededPacket::new_view(icmp_v4.packet()).req()?;
+            if !is_probe_protocol(packet.payload(), protocol)? {
+                return Ok(None);
+            }
             let resp_seq = extract_time_exceeded(&packet, protocol)?;
-            Some(ProbeResponse::TimeExceeded(ProbeResponseData::new(
-                recv, src, resp_seq,
-            )))
+            let tos = extract_tos(packet.payload())?;
+            let corrupted = is_quote_corrupted(icmp_v4.packet(), payload_pattern);
+            let recorded_route = Ipv4Packet::new_view(packet.payload())
+                .map_or(RecordedRoute::EMPTY, |quoted| {
+                    extract_recorded_route(&quoted)
+                });
+            Some(ProbeResponse::TimeExceeded(
+                ProbeResponseData::new(recv, src, resp_seq)
+                    .with_tos(tos)
+                    .with_ttl(ttl)
+                    .with_icmp_code(code)
+                    .with_quote_corrupted(corrupted)
+                    .with_recorded_route(recorded_route),
+            ))
         }
         IcmpType::DestinationUnreachable => {
             let packet = DestinationUnreachablePacket::new_view(icmp_v4.packet()).req()?;
+            if !is_probe_protocol(packet.payload(), protocol)? {
+                return Ok(None);
+            }
             let resp_seq = extract_dest_unreachable(&packet, protocol)?;
+            let tos = extract_tos(packet.payload())?;
+            let corrupted = is_quote_corrupted(icmp_v4.packet(), payload_pattern);
+            let recorded_route = Ipv4Packet::new_view(packet.payload())
+                .map_or(RecordedRoute::EMPTY, |quoted| {
+                    extract_recorded_route(&quoted)
+                });
+            let data = ProbeResponseData::new(recv, src, resp_seq)
+                .with_tos(tos)
+                .with_ttl(ttl)
+                .with_icmp_code(code)
+                .with_quote_corrupted(corrupted)
+                .with_recorded_route(recorded_route);
             Some(ProbeResponse::DestinationUnreachable(
-                ProbeResponseData::new(recv, src, resp_seq),
+                if code == FRAGMENTATION_NEEDED {
+                    data.with_next_hop_mtu(packet.get_next_hop_mtu())
+                } else {
+                    data
+                },
             ))
         }
         IcmpType::EchoReply => match protocol {
@@ -323,9 +634,33 @@ fn extract_probe_resp(
                 let id = packet.get_identifier();
                 let seq = packet.get_sequence();
                 let resp_seq = ProbeResponseSeq::Icmp(ProbeResponseSeqIcmp::new(id, seq));
-                Some(ProbeResponse::EchoReply(ProbeResponseData::new(
-                    recv, src, resp_seq,
-                )))
+                Some(ProbeResponse::EchoReply(
+                    ProbeResponseData::new(recv, src, resp_seq)
+                        .with_ttl(ttl)
+                        .with_icmp_code(code)
+                        .with_recorded_route(extract_recorded_route(ipv4)),
+                ))
+            }
+            TracerProtocol::Udp | TracerProtocol::Tcp => None,
+        },
+        IcmpType::TimestampReply => match protocol {
+            TracerProtocol::Icmp => {
+                let packet = TimestampPacket::new_view(icmp_v4.packet()).req()?;
+                let id = packet.get_identifier();
+                let seq = packet.get_sequence();
+                let resp_seq = ProbeResponseSeq::Icmp(ProbeResponseSeqIcmp::new(id, seq));
+                let timestamps = IcmpTimestamps {
+                    originate: packet.get_originate(),
+                    receive: packet.get_receive(),
+                    transmit: packet.get_transmit(),
+                };
+                Some(ProbeResponse::EchoReply(
+                    ProbeResponseData::new(recv, src, resp_seq)
+                        .with_ttl(ttl)
+                        .with_icmp_code(code)
+                        .with_recorded_route(extract_recorded_route(ipv4))
+                        .with_icmp_timestamps(timestamps),
+                ))
             }
             TracerProtocol::Udp | TracerProtocol::Tcp => None,
         },
@@ -383,6 +718,161 @@ fn extract_dest_unreachable(
     })
 }
 
+/// Get the addresses recorded by the `Record Route` option of an `Ipv4Packet`, if any.
+///
+/// The option is read from the options of the header, which are skipped until the `End of Options List`, and the
+/// pointer of the option gives the number of addresses recorded.
+fn extract_recorded_route(ipv4: &Ipv4Packet<'_>) -> RecordedRoute {
+    let header_len = usize::from(ipv4.get_header_length()) * 4;
+    let mut options = ipv4
+        .packet()
+        .get(Ipv4Packet::minimum_packet_size()..header_len)
+        .unwrap_or_default();
+    while let Some(&option_type) = options.first() {
+        match option_type {
+            OPTION_END_OF_LIST => break,
+            OPTION_NO_OPERATION => options = &options[1..],
+            _ => {
+                let Some(&len) = options.get(1) else {
+                    break;
+                };
+                let len = usize::from(len).max(2);
+                let option = options.get(..len).unwrap_or(options);
+                if option_type == OPTION_RECORD_ROUTE {
+                    let pointer = option.get(2).map_or(4, |&pointer| usize::from(pointer));
+                    let recorded = option
+                        .get(3..pointer.saturating_sub(1).min(option.len()))
+                        .unwrap_or_default();
+                    return RecordedRoute::new(
+                        recorded
+                            .chunks_exact(4)
+                            .map(|addr| Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3])),
+                    );
+                }
+                options = options.get(len..).unwrap_or_default();
+            }
+        }
+    }
+    RecordedRoute::EMPTY
+}
+
+/// Is the original `Ipv4Packet` embedded in the payload of the traced protocol?
+///
+/// Responses to the probes of other traces, which may be using a different protocol, are ignored.
+fn is_probe_protocol(payload: &[u8], protocol: TracerProtocol) -> TraceResult<bool> {
+    let ip4 = Ipv4Packet::new_view(payload).req()?;
+    Ok(matches!(
+        (protocol, ip4.get_protocol()),
+        (TracerProtocol::Icmp, IpProtocol::Icmp)
+            | (TracerProtocol::Udp, IpProtocol::Udp)
+            | (TracerProtocol::Tcp, IpProtocol::Tcp)
+    ))
+}
+
+/// Get the TOS of the original `Ipv4Packet` packet embedded in the payload.
+fn extract_tos(payload: &[u8]) -> TraceResult<TypeOfService> {
+    let ip4 = Ipv4Packet::new_view(payload).req()?;
+    Ok(TypeOfService((ip4.get_dscp() << 2) | ip4.get_ecn()))
+}
+
+/// Is the original datagram quoted in an `ICMP` error corrupted?
+///
+/// The payload of a quoted `ICMP` echo request or `UDP` probe must consist entirely of the `payload_pattern` and, if
+/// the echo request is quoted in full, its checksum must be valid.  Only the bytes of the original datagram which
+/// are actually quoted are checked.  A quoted `ICMP` timestamp request carries no payload and so only its checksum is
+/// checked.
+///
+/// The checksum of a quoted `UDP` probe is not checked as it is not always updated by devices which perform NAT and a
+/// quoted `TCP` probe carries no payload and so is never considered to be corrupted.
+fn is_quote_corrupted(icmp: &[u8], payload_pattern: PayloadPattern) -> bool {
+    let Some((ipv4, nested, complete)) = split_quote(icmp) else {
+        return false;
+    };
+    let payload = nested.get(8..).unwrap_or_default();
+    let bad_payload = payload.iter().any(|&b| b != payload_pattern.0);
+    match ipv4.get_protocol() {
+        IpProtocol::Icmp if is_timestamp_request(nested) => {
+            complete
+                && match TimestampPacket::new_view(nested) {
+                    Some(timestamp) => timestamp.get_checksum() != icmp_ipv4_checksum(nested),
+                    None => true,
+                }
+        }
+        IpProtocol::Icmp if complete => match EchoRequestPacket::new_view(nested) {
+            Some(echo) => bad_payload || echo.get_checksum() != icmp_ipv4_checksum(nested),
+            None => bad_payload,
+        },
+        IpProtocol::Icmp | IpProtocol::Udp => bad_payload,
+        _ => false,
+    }
+}
+
+/// Is the quoted `ICMP` packet a `TimestampRequest`, the timestamps of which are not the `payload_pattern`?
+fn is_timestamp_request(nested: &[u8]) -> bool {
+    IcmpPacket::new_view(nested)
+        .is_some_and(|icmp| icmp.get_icmp_type() == IcmpType::TimestampRequest)
+}
+
+/// Validate the checksums of a received `ICMP` response and of the original datagram it quotes, if any.
+///
+/// The checksum of a quoted `ICMP` or `UDP` packet can only be validated if the original datagram is quoted in full.
+fn validate_checksums(icmp: &[u8], checksum_stats: &mut ChecksumStats) {
+    let Some(icmp_v4) = IcmpPacket::new_view(icmp) else {
+        return;
+    };
+    checksum_stats.validated += 1;
+    if icmp_v4.get_checksum() != icmp_ipv4_checksum(icmp) {
+        checksum_stats.invalid_icmp += 1;
+    }
+    if !matches!(
+        icmp_v4.get_icmp_type(),
+        IcmpType::TimeExceeded | IcmpType::DestinationUnreachable
+    ) {
+        return;
+    }
+    match split_quote(icmp) {
+        Some((ipv4, nested, true)) if ipv4.get_protocol() == IpProtocol::Icmp => {
+            if let Some(echo) = EchoRequestPacket::new_view(nested) {
+                if echo.get_checksum() != icmp_ipv4_checksum(nested) {
+                    checksum_stats.invalid_quoted_icmp += 1;
+                }
+            }
+        }
+        Some((ipv4, nested, true)) if ipv4.get_protocol() == IpProtocol::Udp => {
+            if let Some(udp) = UdpPacket::new_view(nested) {
+                let checksum = udp_ipv4_checksum(nested, ipv4.get_source(), ipv4.get_destination());
+                if udp.get_checksum() != 0 && udp.get_checksum() != checksum {
+                    checksum_stats.invalid_quoted_udp += 1;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Split the original datagram quoted in an `ICMP` error.
+///
+/// Returns the quoted `Ipv4Packet`, the quoted bytes of the packet it carries and whether the original datagram was
+/// quoted in full.  Any [rfc4884] extension which follows the original datagram is excluded.
+///
+/// [rfc4884]: https://datatracker.ietf.org/doc/html/rfc4884
+fn split_quote(icmp: &[u8]) -> Option<(Ipv4Packet<'_>, &[u8], bool)> {
+    let quote = icmp
+        .get(IcmpPacket::minimum_packet_size()..)
+        .unwrap_or_default();
+    let quote = match icmp.get(5) {
+        Some(&words) if words > 0 => &quote[..quote.len().min(usize::from(words) * 4)],
+        _ => quote,
+    };
+    let ipv4 = Ipv4Packet::new_view(quote)?;
+    let total_length = usize::from(ipv4.get_total_length());
+    let header_len = usize::from(ipv4.get_header_length()) * 4;
+    let nested = quote
+        .get(header_len..quote.len().min(total_length))
+        .unwrap_or_default();
+    Some((ipv4, nested, quote.len() >= total_length))
+}
+
 fn extract_echo_request(payload: &[u8]) -> TraceResult<EchoRequestPacket<'_>> {
     let ip4 = Ipv4Packet::new_view(payload).req()?;
     let header_len = usize::from(ip4.get_header_length() * 4);
@@ -429,3 +919,154 @@ fn extract_tcp_packet(payload: &[u8]) -> TraceResult<(u16, u16)> {
         Ok((tcp_packet.get_source(), tcp_packet.get_destination()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    /// An `Ipv4Packet` header with the `options`, which are padded with `End of Options List` to a multiple of 4 bytes.
+    fn ipv4_with_options(options: &[u8]) -> Vec<u8> {
+        let mut buf = vec![0_u8; Ipv4Packet::minimum_packet_size()];
+        buf.extend_from_slice(options);
+        buf.resize(buf.len().next_multiple_of(4), OPTION_END_OF_LIST);
+        buf[0] = 0x40 | (buf.len() / 4) as u8;
+        buf
+    }
+
+    /// A `Record Route` option, preceded by a `No Operation`, with room for 9 addresses and the `recorded` addresses.
+    fn record_route_option(recorded: &[[u8; 4]]) -> Vec<u8> {
+        let mut option = vec![
+            OPTION_NO_OPERATION,
+            OPTION_RECORD_ROUTE,
+            39,
+            4 + 4 * recorded.len() as u8,
+        ];
+        option.extend(recorded.iter().flatten());
+        option.resize(40, 0);
+        option
+    }
+
+    const ADDRS: [[u8; 4]; 9] = [
+        [10, 0, 0, 1],
+        [10, 0, 0, 2],
+        [10, 0, 0, 3],
+        [10, 0, 0, 4],
+        [10, 0, 0, 5],
+        [10, 0, 0, 6],
+        [10, 0, 0, 7],
+        [10, 0, 0, 8],
+        [10, 0, 0, 9],
+    ];
+
+    #[test_case(0; "nothing recorded")]
+    #[test_case(2; "partially recorded")]
+    #[test_case(9; "fully recorded")]
+    fn test_extract_recorded_route(count: usize) {
+        let buf = ipv4_with_options(&record_route_option(&ADDRS[..count]));
+        let ipv4 = Ipv4Packet::new_view(&buf).unwrap();
+        let expected = ADDRS[..count].iter().map(|&addr| Ipv4Addr::from(addr));
+        assert_eq!(RecordedRoute::new(expected), extract_recorded_route(&ipv4));
+    }
+
+    #[test]
+    fn test_extract_recorded_route_after_no_operation() {
+        let options = [
+            &[OPTION_NO_OPERATION, OPTION_NO_OPERATION][..],
+            &[OPTION_RECORD_ROUTE, 11, 8],
+            &ADDRS[0],
+            &ADDRS[1],
+        ]
+        .concat();
+        let buf = ipv4_with_options(&options);
+        let ipv4 = Ipv4Packet::new_view(&buf).unwrap();
+        assert_eq!(
+            RecordedRoute::new([Ipv4Addr::from(ADDRS[0])]),
+            extract_recorded_route(&ipv4)
+        );
+    }
+
+    #[test]
+    fn test_extract_recorded_route_after_other_option() {
+        let options = [&[0x44, 4, 5, 0, OPTION_RECORD_ROUTE, 7, 8][..], &ADDRS[0]].concat();
+        let buf = ipv4_with_options(&options);
+        let ipv4 = Ipv4Packet::new_view(&buf).unwrap();
+        assert_eq!(
+            RecordedRoute::new([Ipv4Addr::from(ADDRS[0])]),
+            extract_recorded_route(&ipv4)
+        );
+    }
+
+    #[test_case(&[]; "no options")]
+    #[test_case(&[OPTION_END_OF_LIST, OPTION_RECORD_ROUTE, 7, 8, 10, 0, 0, 1]; "after end of options list")]
+    #[test_case(&[OPTION_NO_OPERATION, OPTION_NO_OPERATION, OPTION_NO_OPERATION, OPTION_RECORD_ROUTE]; "missing length")]
+    #[test_case(&[OPTION_RECORD_ROUTE, 0, 8, 0, 10, 0, 0, 1]; "zero length")]
+    #[test_case(&[OPTION_RECORD_ROUTE, 2, 8, 0, 10, 0, 0, 1]; "length without pointer")]
+    #[test_case(&[OPTION_RECORD_ROUTE, 3, 0, 0]; "zero pointer")]
+    fn test_extract_recorded_route_empty(options: &[u8]) {
+        let buf = ipv4_with_options(options);
+        let ipv4 = Ipv4Packet::new_view(&buf).unwrap();
+        assert_eq!(RecordedRoute::EMPTY, extract_recorded_route(&ipv4));
+    }
+
+    #[test]
+    fn test_extract_recorded_route_truncated() {
+        // the option claims room for 9 addresses but the header ends after the first
+        let options = [
+            &[OPTION_NO_OPERATION, OPTION_RECORD_ROUTE, 39, 12][..],
+            &ADDRS[0],
+        ]
+        .concat();
+        let buf = ipv4_with_options(&options);
+        let ipv4 = Ipv4Packet::new_view(&buf).unwrap();
+        assert_eq!(
+            RecordedRoute::new([Ipv4Addr::from(ADDRS[0])]),
+            extract_recorded_route(&ipv4)
+        );
+    }
+
+    #[test]
+    fn test_make_ipv4_packet_record_route() {
+        let mut buf = [0xFF_u8; 68];
+        let ipv4 = make_ipv4_packet(
+            &mut buf,
+            platform::PlatformIpv4FieldByteOrder::Network,
+            IpProtocol::Icmp,
+            Ipv4Addr::new(192, 168, 1, 1),
+            Ipv4Addr::new(10, 0, 0, 1),
+            5,
+            TypeOfService(0),
+            0,
+            true,
+            false,
+        )
+        .unwrap();
+        assert_eq!(60, header_size(true));
+        assert_eq!(RECORD_ROUTE_HEADER_LENGTH, ipv4.get_header_length());
+        assert_eq!(68, ipv4.get_total_length());
+        assert_eq!(&record_route_option(&[])[..], &ipv4.packet()[20..60]);
+        assert_eq!(&[0xFF; 8], &ipv4.packet()[60..]);
+        assert_eq!(RecordedRoute::EMPTY, extract_recorded_route(&ipv4));
+    }
+
+    #[test]
+    fn test_make_ipv4_packet_without_record_route() {
+        let mut buf = [0xFF_u8; 28];
+        let ipv4 = make_ipv4_packet(
+            &mut buf,
+            platform::PlatformIpv4FieldByteOrder::Network,
+            IpProtocol::Icmp,
+            Ipv4Addr::new(192, 168, 1, 1),
+            Ipv4Addr::new(10, 0, 0, 1),
+            5,
+            TypeOfService(0),
+            0,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(20, header_size(false));
+        assert_eq!(5, ipv4.get_header_length());
+        assert_eq!(&[0xFF; 8], &ipv4.packet()[20..]);
+    }
+}
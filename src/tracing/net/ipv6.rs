@@ -1,65 +1,64 @@
 use crate::tracing::error::TracerError::AddressNotAvailable;
 use crate::tracing::error::{TraceResult, TracerError};
 use crate::tracing::net::channel::MAX_PACKET_SIZE;
+use crate::tracing::net::dump::{self, Direction};
 use crate::tracing::net::platform;
 use crate::tracing::net::platform::Socket;
-use crate::tracing::net::socket::TracerSocket as _;
+use crate::tracing::net::socket::{RecvMeta, TracerSocket as _};
+use crate::tracing::net::ChecksumStats;
 use crate::tracing::packet::checksum::{icmp_ipv6_checksum, udp_ipv6_checksum};
 use crate::tracing::packet::icmpv6::destination_unreachable::DestinationUnreachablePacket;
 use crate::tracing::packet::icmpv6::echo_reply::EchoReplyPacket;
 use crate::tracing::packet::icmpv6::echo_request::EchoRequestPacket;
+use crate::tracing::packet::icmpv6::packet_too_big::PacketTooBigPacket;
 use crate::tracing::packet::icmpv6::time_exceeded::TimeExceededPacket;
 use crate::tracing::packet::icmpv6::{IcmpCode, IcmpPacket, IcmpType};
 use crate::tracing::packet::ipv6::Ipv6Packet;
 use crate::tracing::packet::tcp::TcpPacket;
 use crate::tracing::packet::udp::UdpPacket;
+use crate::tracing::packet::IpProtocol;
 use crate::tracing::probe::{
     ProbeResponse, ProbeResponseData, ProbeResponseSeq, ProbeResponseSeqIcmp, ProbeResponseSeqTcp,
-    ProbeResponseSeqUdp,
+    ProbeResponseSeqUdp, TcpHandshake,
+};
+use crate::tracing::types::{
+    PacketSize, PayloadPattern, Sequence, TimeToLive, TraceId, TypeOfService,
 };
-use crate::tracing::types::{PacketSize, PayloadPattern, Sequence, TraceId};
 use crate::tracing::util::Required;
-use crate::tracing::{Probe, TracerProtocol};
+use crate::tracing::{Ipv6ExtHeader, Probe, TcpOptions, TracerProtocol};
+use std::collections::VecDeque;
 use std::io::ErrorKind;
 use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 use std::time::SystemTime;
+use tracing::warn;
 
-/// The maximum size of UDP packet we allow.
-const MAX_UDP_PACKET_BUF: usize = MAX_PACKET_SIZE - Ipv6Packet::minimum_packet_size();
-
-/// The maximum size of UDP payload we allow.
-const MAX_UDP_PAYLOAD_BUF: usize = MAX_UDP_PACKET_BUF - UdpPacket::minimum_packet_size();
-
-/// The maximum size of UDP packet we allow.
-const MAX_ICMP_PACKET_BUF: usize = MAX_PACKET_SIZE - Ipv6Packet::minimum_packet_size();
-
-/// The maximum size of ICMP payload we allow.
-const MAX_ICMP_PAYLOAD_BUF: usize = MAX_ICMP_PACKET_BUF - IcmpPacket::minimum_packet_size();
-
+#[allow(clippy::too_many_arguments)]
 pub fn dispatch_icmp_probe(
     icmp_send_socket: &mut Socket,
+    send_buf: &mut [u8],
     probe: Probe,
     src_addr: Ipv6Addr,
     dest_addr: Ipv6Addr,
     packet_size: PacketSize,
     payload_pattern: PayloadPattern,
+    tos: TypeOfService,
 ) -> TraceResult<()> {
-    let mut icmp_buf = [0_u8; MAX_ICMP_PACKET_BUF];
     let packet_size = usize::from(packet_size.0);
     if packet_size > MAX_PACKET_SIZE {
         return Err(TracerError::InvalidPacketSize(packet_size));
     }
     let echo_request = make_echo_request_icmp_packet(
-        &mut icmp_buf,
+        &mut send_buf[..packet_size - Ipv6Packet::minimum_packet_size()],
         src_addr,
         dest_addr,
         probe.identifier,
         probe.sequence,
-        icmp_payload_size(packet_size),
         payload_pattern,
     )?;
     icmp_send_socket.set_unicast_hops_v6(probe.ttl.0)?;
+    icmp_send_socket.set_tclass_v6(u32::from(tos.0))?;
     let remote_addr = SocketAddr::new(IpAddr::V6(dest_addr), 0);
+    dump::dump_icmpv6(Direction::Sent, echo_request.packet());
     icmp_send_socket.send_to(echo_request.packet(), remote_addr)?;
     Ok(())
 }
@@ -67,30 +66,32 @@ pub fn dispatch_icmp_probe(
 #[allow(clippy::too_many_arguments)]
 pub fn dispatch_udp_probe(
     udp_send_socket: &mut Socket,
+    send_buf: &mut [u8],
     probe: Probe,
     src_addr: Ipv6Addr,
     dest_addr: Ipv6Addr,
     packet_size: PacketSize,
     payload_pattern: PayloadPattern,
+    tos: TypeOfService,
 ) -> TraceResult<()> {
-    let mut udp_buf = [0_u8; MAX_UDP_PACKET_BUF];
     let packet_size = usize::from(packet_size.0);
     if packet_size > MAX_PACKET_SIZE {
         return Err(TracerError::InvalidPacketSize(packet_size));
     }
     let udp = make_udp_packet(
-        &mut udp_buf,
+        &mut send_buf[..packet_size - Ipv6Packet::minimum_packet_size()],
         src_addr,
         dest_addr,
         probe.src_port.0,
         probe.dest_port.0,
-        udp_payload_size(packet_size),
         payload_pattern,
     )?;
     udp_send_socket.set_unicast_hops_v6(probe.ttl.0)?;
+    udp_send_socket.set_tclass_v6(u32::from(tos.0))?;
     // Note that we set the port to be 0 in the remote `SocketAddr` as the target port is encoded in the `UDP`
     // packet.  If we (redundantly) set the target port here then the send will fail with `EINVAL`.
     let remote_addr = SocketAddr::new(IpAddr::V6(dest_addr), 0);
+    dump::dump_udp(Direction::Sent, udp.packet());
     udp_send_socket.send_to(udp.packet(), remote_addr)?;
     Ok(())
 }
@@ -99,6 +100,10 @@ pub fn dispatch_tcp_probe(
     probe: Probe,
     src_addr: Ipv6Addr,
     dest_addr: Ipv6Addr,
+    tos: TypeOfService,
+    tcp_options: TcpOptions,
+    ext_header: Option<Ipv6ExtHeader>,
+    dont_fragment: Option<bool>,
 ) -> TraceResult<Socket> {
     fn process_result(addr: SocketAddr, res: std::io::Result<()>) -> TraceResult<()> {
         match res {
@@ -125,29 +130,171 @@ pub fn dispatch_tcp_probe(
     let local_addr = SocketAddr::new(IpAddr::V6(src_addr), probe.src_port.0);
     process_result(local_addr, socket.bind(local_addr))?;
     socket.set_unicast_hops_v6(probe.ttl.0)?;
+    socket.set_tclass_v6(u32::from(tos.0))?;
+    set_tcp_options(&socket, tcp_options)?;
+    if let Some(dont_fragment) = dont_fragment {
+        socket.set_dont_fragment_v6(dont_fragment)?;
+    }
+    if let Some(header) = ext_header {
+        socket.set_ipv6_ext_header(header)?;
+    }
     let remote_addr = SocketAddr::new(IpAddr::V6(dest_addr), probe.dest_port.0);
     process_result(remote_addr, socket.connect(remote_addr))?;
     Ok(socket)
 }
 
-pub fn recv_icmp_probe(
+/// Apply the configured `TcpOptions` to a TCP probe socket prior to connecting.
+fn set_tcp_options(socket: &Socket, tcp_options: TcpOptions) -> TraceResult<()> {
+    if let Some(mss) = tcp_options.mss {
+        socket.set_mss(u32::from(mss.0))?;
+    }
+    if let Some(window_size) = tcp_options.window_size {
+        socket.set_recv_buffer_size(window_size.0 as usize)?;
+    }
+    Ok(())
+}
+
+pub fn recv_icmp_probes(
     recv_socket: &mut Socket,
+    recv_bufs: &mut [[u8; MAX_PACKET_SIZE]],
     protocol: TracerProtocol,
-) -> TraceResult<Option<ProbeResponse>> {
-    let mut buf = [0_u8; MAX_PACKET_SIZE];
-    match recv_socket.recv_from(&mut buf) {
-        Ok((_bytes_read, addr)) => {
-            let icmp_v6 = IcmpPacket::new_view(&buf).req()?;
+    payload_pattern: PayloadPattern,
+    responses: &mut VecDeque<ProbeResponse>,
+    checksum_stats: &mut ChecksumStats,
+) -> TraceResult<()> {
+    match recv_socket.recv_batch(recv_bufs) {
+        Ok(metas) => {
+            prepare_received(recv_bufs, &metas);
+            extract_probe_resps(
+                recv_bufs,
+                &metas,
+                protocol,
+                payload_pattern,
+                responses,
+                checksum_stats,
+            )
+        }
+        Err(err) => match err.kind() {
+            ErrorKind::WouldBlock => Ok(()),
+            _ => Err(TracerError::IoError(err)),
+        },
+    }
+}
 
-            let src_addr = match addr.as_ref().req()? {
-                SocketAddr::V6(addr) => addr.ip(),
-                SocketAddr::V4(_) => panic!(),
-            };
+/// Prepare each of a batch of received packets for extraction.
+///
+/// Any bytes left over from a previously received, longer, packet are zeroed.  This must be done exactly once for each packet received, before the packet is passed to
+/// `extract_probe_resps`.
+pub fn prepare_received(recv_bufs: &mut [[u8; MAX_PACKET_SIZE]], metas: &[RecvMeta]) {
+    for (buf, meta) in recv_bufs.iter_mut().zip(metas) {
+        buf[meta.len..].fill(0);
+        dump::dump_icmpv6(Direction::Received, &buf[..meta.len]);
+    }
+}
 
-            Ok(extract_probe_resp(protocol, &icmp_v6, *src_addr)?)
+/// Extract the `ProbeResponse`, if any, from each of a batch of received, and prepared, packets.
+///
+/// Packets which are not responses to our probes are ignored and packets which cannot be parsed are dropped.
+pub fn extract_probe_resps(
+    recv_bufs: &[[u8; MAX_PACKET_SIZE]],
+    metas: &[RecvMeta],
+    protocol: TracerProtocol,
+    payload_pattern: PayloadPattern,
+    responses: &mut VecDeque<ProbeResponse>,
+    checksum_stats: &mut ChecksumStats,
+) -> TraceResult<()> {
+    for (buf, meta) in recv_bufs.iter().zip(metas) {
+        let recv = meta.recv.unwrap_or_else(SystemTime::now);
+        let icmp_v6 = IcmpPacket::new_view(&buf[..]).req()?;
+        let src_addr = match meta.addr.as_ref().req()? {
+            SocketAddr::V6(addr) => addr.ip(),
+            SocketAddr::V4(_) => panic!(),
+        };
+        match extract_probe_resp(
+            protocol,
+            &icmp_v6,
+            *src_addr,
+            recv,
+            meta.ttl,
+            payload_pattern,
+        ) {
+            Ok(Some(resp)) => {
+                validate_checksums(&buf[..meta.len], checksum_stats);
+                responses.push_back(resp);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                warn!(src = %src_addr, %err, "dropped response which could not be parsed");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Receive the responses to `ICMPv6` probes sent on an unprivileged datagram socket.
+///
+/// `EchoReply` packets are received directly, whereas `TimeExceeded` and `DestinationUnreachable` errors are received
+/// from the socket error queue as the original echo request.
+///
+/// The kernel only delivers responses to probes sent from the socket and so the `identifier`, which the kernel
+/// replaces, is reported as zero.
+pub fn recv_icmp_probes_unprivileged(
+    recv_socket: &mut Socket,
+    recv_bufs: &mut [[u8; MAX_PACKET_SIZE]],
+    responses: &mut VecDeque<ProbeResponse>,
+) -> TraceResult<()> {
+    let err_buf = recv_bufs.first_mut().req()?;
+    while let Some(meta) = recv_socket.recv_err(err_buf)? {
+        let Some(echo_request) = EchoRequestPacket::new_view(&err_buf[..meta.len]) else {
+            warn!(src = %meta.offender, "dropped error which could not be parsed");
+            continue;
+        };
+        let resp_seq =
+            ProbeResponseSeq::Icmp(ProbeResponseSeqIcmp::new(0, echo_request.get_sequence()));
+        let data = ProbeResponseData::new(SystemTime::now(), meta.offender, resp_seq)
+            .with_icmp_code(meta.icmp_code);
+        match IcmpType::from(meta.icmp_type) {
+            IcmpType::TimeExceeded => responses.push_back(ProbeResponse::TimeExceeded(data)),
+            IcmpType::DestinationUnreachable => {
+                responses.push_back(ProbeResponse::DestinationUnreachable(data));
+            }
+            IcmpType::PacketTooBig => {
+                let mtu = u16::try_from(meta.info).unwrap_or(u16::MAX);
+                responses.push_back(ProbeResponse::DestinationUnreachable(
+                    data.with_next_hop_mtu(mtu),
+                ));
+            }
+            _ => {}
+        }
+    }
+    match recv_socket.recv_batch(recv_bufs) {
+        Ok(metas) => {
+            for (buf, meta) in recv_bufs.iter_mut().zip(metas) {
+                dump::dump_icmpv6(Direction::Received, &buf[..meta.len]);
+                let recv = meta.recv.unwrap_or_else(SystemTime::now);
+                let (Some(echo_reply), Some(addr)) =
+                    (EchoReplyPacket::new_view(&buf[..meta.len]), meta.addr)
+                else {
+                    warn!("dropped response which could not be parsed");
+                    continue;
+                };
+                if echo_reply.get_icmp_type() == IcmpType::EchoReply {
+                    let resp_seq = ProbeResponseSeq::Icmp(ProbeResponseSeqIcmp::new(
+                        0,
+                        echo_reply.get_sequence(),
+                    ));
+                    let data = ProbeResponseData::new(recv, addr.ip(), resp_seq)
+                        .with_icmp_code(echo_reply.get_icmp_code().0);
+                    responses.push_back(ProbeResponse::EchoReply(match meta.ttl {
+                        Some(hop_limit) => data.with_ttl(TimeToLive(hop_limit)),
+                        None => data,
+                    }));
+                }
+            }
+            Ok(())
         }
         Err(err) => match err.kind() {
-            ErrorKind::WouldBlock => Ok(None),
+            ErrorKind::WouldBlock => Ok(()),
             _ => Err(TracerError::IoError(err)),
         },
     }
@@ -163,20 +310,18 @@ pub fn recv_tcp_socket(
         None => {
             let addr = tcp_socket.peer_addr()?.req()?.ip();
             tcp_socket.shutdown()?;
-            return Ok(Some(ProbeResponse::TcpReply(ProbeResponseData::new(
-                SystemTime::now(),
-                addr,
-                resp_seq,
-            ))));
+            return Ok(Some(ProbeResponse::TcpReply(
+                ProbeResponseData::new(SystemTime::now(), addr, resp_seq)
+                    .with_tcp_handshake(TcpHandshake::Established),
+            )));
         }
         Some(err) => {
             if let Some(code) = err.raw_os_error() {
                 if platform::is_conn_refused_error(code) {
-                    return Ok(Some(ProbeResponse::TcpRefused(ProbeResponseData::new(
-                        SystemTime::now(),
-                        dest_addr,
-                        resp_seq,
-                    ))));
+                    return Ok(Some(ProbeResponse::TcpRefused(
+                        ProbeResponseData::new(SystemTime::now(), dest_addr, resp_seq)
+                            .with_tcp_handshake(TcpHandshake::Refused),
+                    )));
                 }
                 if platform::is_host_unreachable_error(code) {
                     let error_addr = tcp_socket.icmp_error_info()?;
@@ -199,16 +344,15 @@ fn make_udp_packet(
     dest_addr: Ipv6Addr,
     src_port: u16,
     dest_port: u16,
-    payload_size: usize,
     payload_pattern: PayloadPattern,
 ) -> TraceResult<UdpPacket<'_>> {
-    let udp_payload_buf = [payload_pattern.0; MAX_UDP_PAYLOAD_BUF];
-    let udp_packet_size = UdpPacket::minimum_packet_size() + payload_size;
-    let mut udp = UdpPacket::new(&mut udp_buf[..udp_packet_size]).req()?;
+    fill_payload(udp_buf, UdpPacket::minimum_packet_size(), payload_pattern)?;
+    let udp_packet_size = udp_buf.len();
+    let mut udp = UdpPacket::new(udp_buf).req()?;
     udp.set_source(src_port);
     udp.set_destination(dest_port);
     udp.set_length(udp_packet_size as u16);
-    udp.set_payload(&udp_payload_buf[..payload_size]);
+    udp.set_checksum(0);
     udp.set_checksum(udp_ipv6_checksum(udp.packet(), src_addr, dest_addr));
     Ok(udp)
 }
@@ -220,53 +364,94 @@ fn make_echo_request_icmp_packet(
     dest_addr: Ipv6Addr,
     identifier: TraceId,
     sequence: Sequence,
-    payload_size: usize,
     payload_pattern: PayloadPattern,
 ) -> TraceResult<EchoRequestPacket<'_>> {
-    let payload_buf = [payload_pattern.0; MAX_ICMP_PAYLOAD_BUF];
-    let packet_size = IcmpPacket::minimum_packet_size() + payload_size;
-    let mut icmp = EchoRequestPacket::new(&mut icmp_buf[..packet_size]).req()?;
+    fill_payload(icmp_buf, IcmpPacket::minimum_packet_size(), payload_pattern)?;
+    let mut icmp = EchoRequestPacket::new(icmp_buf).req()?;
     icmp.set_icmp_type(IcmpType::EchoRequest);
     icmp.set_icmp_code(IcmpCode(0));
     icmp.set_identifier(identifier.0);
-    icmp.set_payload(&payload_buf[..payload_size]);
     icmp.set_sequence(sequence.0);
+    icmp.set_checksum(0);
     icmp.set_checksum(icmp_ipv6_checksum(icmp.packet(), src_addr, dest_addr));
     Ok(icmp)
 }
 
-fn icmp_payload_size(packet_size: usize) -> usize {
-    let ip_header_size = Ipv6Packet::minimum_packet_size();
-    let icmp_header_size = IcmpPacket::minimum_packet_size();
-    packet_size - icmp_header_size - ip_header_size
-}
-
-fn udp_payload_size(packet_size: usize) -> usize {
-    let ip_header_size = Ipv6Packet::minimum_packet_size();
-    let udp_header_size = UdpPacket::minimum_packet_size();
-    packet_size - udp_header_size - ip_header_size
+/// Fill the payload of a packet, which follows a header of `header_size` bytes, with the `payload_pattern`.
+fn fill_payload(
+    packet_buf: &mut [u8],
+    header_size: usize,
+    payload_pattern: PayloadPattern,
+) -> TraceResult<()> {
+    packet_buf
+        .get_mut(header_size..)
+        .req()?
+        .fill(payload_pattern.0);
+    Ok(())
 }
 
 fn extract_probe_resp(
     protocol: TracerProtocol,
     icmp_v6: &IcmpPacket<'_>,
     src: Ipv6Addr,
+    recv: SystemTime,
+    hop_limit: Option<u8>,
+    payload_pattern: PayloadPattern,
 ) -> TraceResult<Option<ProbeResponse>> {
-    let recv = SystemTime::now();
-    let ip = IpAddr::V6(src);
+    let new_data = |resp_seq| {
+        let data = ProbeResponseData::new(recv, IpAddr::V6(src), resp_seq);
+        match hop_limit {
+            Some(hop_limit) => data.with_ttl(TimeToLive(hop_limit)),
+            None => data,
+        }
+    };
+    let code = icmp_v6.get_icmp_code().0;
     Ok(match icmp_v6.get_icmp_type() {
         IcmpType::TimeExceeded => {
             let packet = TimeExceededPacket::new_view(icmp_v6.packet()).req()?;
+            if !is_probe_protocol(packet.payload(), protocol)? {
+                return Ok(None);
+            }
             let resp_seq = extract_time_exceeded(&packet, protocol)?;
-            Some(ProbeResponse::TimeExceeded(ProbeResponseData::new(
-                recv, ip, resp_seq,
-            )))
+            let tos = extract_traffic_class(packet.payload())?;
+            let corrupted = is_quote_corrupted(icmp_v6.packet(), payload_pattern);
+            Some(ProbeResponse::TimeExceeded(
+                new_data(resp_seq)
+                    .with_tos(tos)
+                    .with_icmp_code(code)
+                    .with_quote_corrupted(corrupted),
+            ))
         }
         IcmpType::DestinationUnreachable => {
             let packet = DestinationUnreachablePacket::new_view(icmp_v6.packet()).req()?;
+            if !is_probe_protocol(packet.payload(), protocol)? {
+                return Ok(None);
+            }
             let resp_seq = extract_dest_unreachable(&packet, protocol)?;
+            let tos = extract_traffic_class(packet.payload())?;
+            let corrupted = is_quote_corrupted(icmp_v6.packet(), payload_pattern);
             Some(ProbeResponse::DestinationUnreachable(
-                ProbeResponseData::new(recv, ip, resp_seq),
+                new_data(resp_seq)
+                    .with_tos(tos)
+                    .with_icmp_code(code)
+                    .with_quote_corrupted(corrupted),
+            ))
+        }
+        IcmpType::PacketTooBig => {
+            let packet = PacketTooBigPacket::new_view(icmp_v6.packet()).req()?;
+            if !is_probe_protocol(packet.payload(), protocol)? {
+                return Ok(None);
+            }
+            let resp_seq = extract_packet_too_big(&packet, protocol)?;
+            let tos = extract_traffic_class(packet.payload())?;
+            let corrupted = is_quote_corrupted(icmp_v6.packet(), payload_pattern);
+            let mtu = u16::try_from(packet.get_mtu()).unwrap_or(u16::MAX);
+            Some(ProbeResponse::DestinationUnreachable(
+                new_data(resp_seq)
+                    .with_tos(tos)
+                    .with_icmp_code(code)
+                    .with_quote_corrupted(corrupted)
+                    .with_next_hop_mtu(mtu),
             ))
         }
         IcmpType::EchoReply => match protocol {
@@ -275,9 +460,9 @@ fn extract_probe_resp(
                 let id = packet.get_identifier();
                 let seq = packet.get_sequence();
                 let resp_seq = ProbeResponseSeq::Icmp(ProbeResponseSeqIcmp::new(id, seq));
-                Some(ProbeResponse::EchoReply(ProbeResponseData::new(
-                    recv, ip, resp_seq,
-                )))
+                Some(ProbeResponse::EchoReply(
+                    new_data(resp_seq).with_icmp_code(code),
+                ))
             }
             TracerProtocol::Udp | TracerProtocol::Tcp => None,
         },
@@ -325,9 +510,159 @@ fn extract_dest_unreachable(
     })
 }
 
+fn extract_packet_too_big(
+    packet: &PacketTooBigPacket<'_>,
+    protocol: TracerProtocol,
+) -> TraceResult<ProbeResponseSeq> {
+    Ok(match protocol {
+        TracerProtocol::Icmp => {
+            let (identifier, sequence) = extract_echo_request(packet.payload())?;
+            ProbeResponseSeq::Icmp(ProbeResponseSeqIcmp::new(identifier, sequence))
+        }
+        TracerProtocol::Udp => {
+            let (src_port, dest_port) = extract_udp_packet(packet.payload())?;
+            ProbeResponseSeq::Udp(ProbeResponseSeqUdp::new(0, src_port, dest_port, 0))
+        }
+        TracerProtocol::Tcp => {
+            let (src_port, dest_port) = extract_tcp_packet(packet.payload())?;
+            ProbeResponseSeq::Tcp(ProbeResponseSeqTcp::new(src_port, dest_port))
+        }
+    })
+}
+
+/// Is the original `Ipv6Packet` embedded in the payload of the traced protocol?
+///
+/// Responses to the probes of other traces, which may be using a different protocol, are ignored.
+fn is_probe_protocol(ipv6_bytes: &[u8], protocol: TracerProtocol) -> TraceResult<bool> {
+    let ipv6 = Ipv6Packet::new_view(ipv6_bytes).req()?;
+    let Some((next_header, _)) = skip_ext_headers(ipv6.get_next_header(), ipv6.payload()) else {
+        return Ok(false);
+    };
+    Ok(matches!(
+        (protocol, next_header),
+        (TracerProtocol::Icmp, IpProtocol::IcmpV6)
+            | (TracerProtocol::Udp, IpProtocol::Udp)
+            | (TracerProtocol::Tcp, IpProtocol::Tcp)
+    ))
+}
+
+/// Get the traffic class of the original `Ipv6Packet` packet embedded in the payload.
+fn extract_traffic_class(ipv6_bytes: &[u8]) -> TraceResult<TypeOfService> {
+    let ipv6 = Ipv6Packet::new_view(ipv6_bytes).req()?;
+    Ok(TypeOfService(ipv6.get_traffic_class()))
+}
+
+/// Is the original datagram quoted in an `ICMPv6` error corrupted?
+///
+/// The payload of a quoted `ICMPv6` echo request or `UDP` probe must consist entirely of the `payload_pattern` and, if
+/// the echo request is quoted in full, its checksum must be valid.  Only the bytes of the original datagram which
+/// are actually quoted are checked.
+fn is_quote_corrupted(icmp: &[u8], payload_pattern: PayloadPattern) -> bool {
+    let Some((ipv6, next_header, nested, complete)) = split_quote(icmp) else {
+        return false;
+    };
+    let payload = nested.get(8..).unwrap_or_default();
+    let bad_payload = payload.iter().any(|&b| b != payload_pattern.0);
+    match next_header {
+        IpProtocol::IcmpV6 if complete => match EchoRequestPacket::new_view(nested) {
+            Some(echo) => {
+                let checksum = icmp_ipv6_checksum(
+                    nested,
+                    ipv6.get_source_address(),
+                    ipv6.get_destination_address(),
+                );
+                bad_payload || echo.get_checksum() != checksum
+            }
+            None => bad_payload,
+        },
+        IpProtocol::IcmpV6 | IpProtocol::Udp => bad_payload,
+        _ => false,
+    }
+}
+
+/// Validate the checksums of the original datagram quoted in a received `ICMPv6` error, if any.
+///
+/// The checksum of the `ICMPv6` response itself is validated by the OS.
+fn validate_checksums(icmp: &[u8], checksum_stats: &mut ChecksumStats) {
+    let Some(icmp_v6) = IcmpPacket::new_view(icmp) else {
+        return;
+    };
+    checksum_stats.validated += 1;
+    if !matches!(
+        icmp_v6.get_icmp_type(),
+        IcmpType::TimeExceeded | IcmpType::DestinationUnreachable | IcmpType::PacketTooBig
+    ) {
+        return;
+    }
+    let Some((ipv6, next_header, nested, true)) = split_quote(icmp) else {
+        return;
+    };
+    let (src, dest) = (ipv6.get_source_address(), ipv6.get_destination_address());
+    match next_header {
+        IpProtocol::IcmpV6 => {
+            if let Some(echo) = EchoRequestPacket::new_view(nested) {
+                if echo.get_checksum() != icmp_ipv6_checksum(nested, src, dest) {
+                    checksum_stats.invalid_quoted_icmp += 1;
+                }
+            }
+        }
+        IpProtocol::Udp => {
+            if let Some(udp) = UdpPacket::new_view(nested) {
+                if udp.get_checksum() != udp_ipv6_checksum(nested, src, dest) {
+                    checksum_stats.invalid_quoted_udp += 1;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Split the original datagram quoted in an `ICMPv6` error.
+///
+/// Returns the quoted `Ipv6Packet`, the protocol and quoted bytes of the packet it carries, following any extension
+/// headers, and whether the original datagram was quoted in full.  Any [rfc4884] extension which follows the original
+/// datagram is excluded.
+///
+/// [rfc4884]: https://datatracker.ietf.org/doc/html/rfc4884
+fn split_quote(icmp: &[u8]) -> Option<(Ipv6Packet<'_>, IpProtocol, &[u8], bool)> {
+    let quote = icmp
+        .get(IcmpPacket::minimum_packet_size()..)
+        .unwrap_or_default();
+    let quote = match icmp.get(4) {
+        Some(&words) if words > 0 => &quote[..quote.len().min(usize::from(words) * 8)],
+        _ => quote,
+    };
+    let ipv6 = Ipv6Packet::new_view(quote)?;
+    let payload_length = usize::from(ipv6.get_payload_length());
+    let nested = quote
+        .get(Ipv6Packet::minimum_packet_size()..)
+        .unwrap_or_default();
+    let nested = &nested[..nested.len().min(payload_length)];
+    let complete = nested.len() == payload_length;
+    let (next_header, nested) = skip_ext_headers(ipv6.get_next_header(), nested)?;
+    Some((ipv6, next_header, nested, complete))
+}
+
+/// Skip the hop-by-hop and destination options extension headers, if any, which precede the packet carried by an
+/// `Ipv6Packet`, such as those of probes sent with an `Ipv6ExtHeader`.
+///
+/// Returns the protocol and the bytes of the packet which follows the extension headers.
+fn skip_ext_headers(next_header: IpProtocol, payload: &[u8]) -> Option<(IpProtocol, &[u8])> {
+    const HOP_BY_HOP: u8 = 0;
+    const DESTINATION_OPTIONS: u8 = 60;
+    let (mut next_header, mut payload) = (next_header, payload);
+    while matches!(next_header.id(), HOP_BY_HOP | DESTINATION_OPTIONS) {
+        let header_len = (usize::from(*payload.get(1)?) + 1) * 8;
+        next_header = IpProtocol::from(*payload.first()?);
+        payload = payload.get(header_len..)?;
+    }
+    Some((next_header, payload))
+}
+
 fn extract_echo_request(ipv6_bytes: &[u8]) -> TraceResult<(u16, u16)> {
     let ipv6 = Ipv6Packet::new_view(ipv6_bytes).req()?;
-    let echo_request_packet = EchoRequestPacket::new_view(ipv6.payload()).req()?;
+    let (_, payload) = skip_ext_headers(ipv6.get_next_header(), ipv6.payload()).req()?;
+    let echo_request_packet = EchoRequestPacket::new_view(payload).req()?;
     Ok((
         echo_request_packet.get_identifier(),
         echo_request_packet.get_sequence(),
@@ -336,7 +671,8 @@ fn extract_echo_request(ipv6_bytes: &[u8]) -> TraceResult<(u16, u16)> {
 
 fn extract_udp_packet(ipv6_bytes: &[u8]) -> TraceResult<(u16, u16)> {
     let ipv6 = Ipv6Packet::new_view(ipv6_bytes).req()?;
-    let udp_packet = UdpPacket::new_view(ipv6.payload()).req()?;
+    let (_, payload) = skip_ext_headers(ipv6.get_next_header(), ipv6.payload()).req()?;
+    let udp_packet = UdpPacket::new_view(payload).req()?;
     Ok((udp_packet.get_source(), udp_packet.get_destination()))
 }
 
@@ -361,6 +697,7 @@ fn extract_udp_packet(ipv6_bytes: &[u8]) -> TraceResult<(u16, u16)> {
 /// [rfc2460]: https://datatracker.ietf.org/doc/html/rfc2460#section-5
 fn extract_tcp_packet(ipv6_bytes: &[u8]) -> TraceResult<(u16, u16)> {
     let ipv6 = Ipv6Packet::new_view(ipv6_bytes).req()?;
-    let tcp_packet = TcpPacket::new_view(ipv6.payload()).req()?;
+    let (_, payload) = skip_ext_headers(ipv6.get_next_header(), ipv6.payload()).req()?;
+    let tcp_packet = TcpPacket::new_view(payload).req()?;
     Ok((tcp_packet.get_source(), tcp_packet.get_destination()))
 }
@@ -15,7 +15,7 @@ use std::net::IpAddr;
 /// byte order.  Linux will accept either byte order."
 #[derive(Debug, Copy, Clone)]
 pub enum PlatformIpv4FieldByteOrder {
-    #[cfg(all(unix, not(target_os = "linux"), not(target_os = "windows")))]
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
     Host,
     Network,
 }
@@ -53,7 +53,7 @@ impl PlatformIpv4FieldByteOrder {
     #[must_use]
     pub fn adjust_length(self, ipv4_total_length: u16) -> u16 {
         match self {
-            #[cfg(all(unix, not(target_os = "linux"), not(target_os = "windows")))]
+            #[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
             Self::Host => ipv4_total_length.swap_bytes(),
             Self::Network => ipv4_total_length,
         }
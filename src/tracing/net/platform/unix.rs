@@ -1,35 +1,72 @@
 use super::byte_order::PlatformIpv4FieldByteOrder;
 use crate::tracing::error::{TraceResult, TracerError};
-use crate::tracing::net::socket::TracerSocket;
+use crate::tracing::net::interface::{Interface, Route};
+use crate::tracing::net::socket::{RecvErrMeta, RecvMeta, TracerSocket};
+use crate::tracing::net::source::InterfaceAddrIpv6;
 use crate::tracing::util::Required;
+use crate::tracing::Ipv6ExtHeader;
 use nix::{
+    libc::{iovec, msghdr, sockaddr_storage},
     sys::select::FdSet,
-    sys::socket::{AddressFamily, SockaddrLike},
+    sys::socket::{
+        recvmsg, setsockopt, sockopt, AddressFamily, ControlMessageOwned, MsgFlags, SockaddrLike,
+        SockaddrStorage,
+    },
     sys::time::{TimeVal, TimeValLike},
 };
 use socket2::{Domain, Protocol, SockAddr, Type};
 use std::io;
-use std::io::Read;
+use std::mem::{self, MaybeUninit};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::net::{Shutdown, SocketAddr};
 use std::os::unix::io::AsRawFd;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "netbsd"
+))]
+use {
+    crate::tracing::net::channel::{MAX_PACKET_SIZE, MAX_RECV_BATCH, MAX_SEND_BATCH},
+    arrayvec::ArrayVec,
+    nix::libc::mmsghdr,
+};
+
+/// The `IPV6_RECVHOPLIMIT` socket option.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const IPV6_RECVHOPLIMIT: nix::libc::c_int = nix::libc::IPV6_RECVHOPLIMIT;
+
+/// The `IPV6_RECVHOPLIMIT` socket option, as defined by RFC 3542, which libc does not define for this platform.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const IPV6_RECVHOPLIMIT: nix::libc::c_int = 37;
+
+/// A buffer for the control messages of a received packet, aligned for a `cmsghdr`.
+///
+/// There is room for both a `SO_TIMESTAMP` timestamp and an `IP_TTL` ttl or `IPV6_HOPLIMIT` hop limit.
+type CmsgBuf = [u64; 8];
+
+/// An `IPv6` options extension header of 8 bytes with a single `PadN` option of 4 bytes.
+///
+/// The next header (the first byte) is set by the OS.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const IPV6_EXT_HEADER: [u8; 8] = [0, 0, 1, 4, 0, 0, 0, 0];
 
 /// The size of the test packet to use for discovering the `total_length` byte order.
-#[cfg(not(target_os = "linux"))]
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
 const TEST_PACKET_LENGTH: u16 = 256;
 
 /// Discover the required byte ordering for the IPv4 header fields `total_length`, `flags` and `fragment_offset`.
 ///
-/// Linux accepts either network byte order or host byte order for the `total_length` field and so we skip the
-/// check and return network byte order unconditionally.
-#[cfg(target_os = "linux")]
+/// Linux, including Android, accepts either network byte order or host byte order for the `total_length` field and so
+/// we skip the check and return network byte order unconditionally.
+#[cfg(any(target_os = "linux", target_os = "android"))]
 #[allow(clippy::unnecessary_wraps)]
 pub fn for_address(_src_addr: IpAddr) -> TraceResult<PlatformIpv4FieldByteOrder> {
     Ok(PlatformIpv4FieldByteOrder::Network)
 }
 
-#[cfg(not(target_os = "linux"))]
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
 pub fn for_address(addr: IpAddr) -> TraceResult<PlatformIpv4FieldByteOrder> {
     let addr = match addr {
         IpAddr::V4(addr) => addr,
@@ -51,7 +88,7 @@ pub fn for_address(addr: IpAddr) -> TraceResult<PlatformIpv4FieldByteOrder> {
 ///
 /// The packet is actually of length `256` bytes but we set the `total_length` based on the input provided so as to
 /// test if the OS rejects the attempt.
-#[cfg(not(target_os = "linux"))]
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
 fn test_send_local_ip4_packet(src_addr: Ipv4Addr, total_length: u16) -> TraceResult<()> {
     let mut buf = [0_u8; TEST_PACKET_LENGTH as usize];
     let mut ipv4 = crate::tracing::packet::ipv4::Ipv4Packet::new(&mut buf).req()?;
@@ -87,18 +124,159 @@ pub fn lookup_interface_addr_ipv4(name: &str) -> TraceResult<IpAddr> {
         .ok_or_else(|| TracerError::UnknownInterface(name.to_string()))
 }
 
-pub fn lookup_interface_addr_ipv6(name: &str) -> TraceResult<IpAddr> {
-    nix::ifaddrs::getifaddrs()
-        .map_err(|_| TracerError::UnknownInterface(name.to_string()))?
-        .find_map(|ia| {
-            ia.address.and_then(|addr| match addr.family() {
-                Some(AddressFamily::Inet6) if ia.interface_name == name => addr
-                    .as_sockaddr_in6()
-                    .map(|sock_addr| IpAddr::V6(sock_addr.ip())),
+/// Lookup the candidate `IPv6` source addresses of the named interface, or of all interfaces if `name` is `None`.
+pub fn lookup_interface_addrs_ipv6(name: Option<&str>) -> TraceResult<Vec<InterfaceAddrIpv6>> {
+    let addrs = interface_addrs_ipv6()?
+        .into_iter()
+        .filter(|addr| match name {
+            Some(name) => addr.interface == name,
+            None => true,
+        })
+        .collect::<Vec<_>>();
+    match name {
+        Some(name) if addrs.is_empty() => Err(TracerError::UnknownInterface(name.to_string())),
+        _ => Ok(addrs),
+    }
+}
+
+/// Read the `IPv6` addresses, and their flags, of all interfaces from `/proc/net/if_inet6`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn interface_addrs_ipv6() -> io::Result<Vec<InterfaceAddrIpv6>> {
+    const IFA_F_TEMPORARY: u8 = 0x01;
+    const IFA_F_DADFAILED: u8 = 0x08;
+    const IFA_F_DEPRECATED: u8 = 0x20;
+    const IFA_F_TENTATIVE: u8 = 0x40;
+    let if_inet6 = std::fs::read_to_string("/proc/net/if_inet6")?;
+    Ok(if_inet6
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields[..] {
+                [addr, _index, _prefix_len, _scope, flags, interface] => {
+                    let addr = Ipv6Addr::from(u128::from_str_radix(addr, 16).ok()?);
+                    let flags = u8::from_str_radix(flags, 16).ok()?;
+                    (flags & (IFA_F_TENTATIVE | IFA_F_DADFAILED) == 0).then(|| InterfaceAddrIpv6 {
+                        interface: interface.to_string(),
+                        addr,
+                        temporary: flags & IFA_F_TEMPORARY != 0,
+                        deprecated: flags & IFA_F_DEPRECATED != 0,
+                    })
+                }
                 _ => None,
-            })
+            }
         })
-        .ok_or_else(|| TracerError::UnknownInterface(name.to_string()))
+        .collect())
+}
+
+/// Lookup the `IPv6` addresses of all interfaces.
+///
+/// The temporary and deprecated flags of addresses are not available on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn interface_addrs_ipv6() -> io::Result<Vec<InterfaceAddrIpv6>> {
+    Ok(nix::ifaddrs::getifaddrs()
+        .map_err(io::Error::from)?
+        .filter_map(|ia| {
+            let addr = ia.address?;
+            match addr.family() {
+                Some(AddressFamily::Inet6) => Some(InterfaceAddrIpv6 {
+                    interface: ia.interface_name,
+                    addr: addr.as_sockaddr_in6()?.ip(),
+                    temporary: false,
+                    deprecated: false,
+                }),
+                _ => None,
+            }
+        })
+        .collect())
+}
+
+/// Lookup all network interfaces and their addresses.
+pub fn interfaces() -> TraceResult<Vec<Interface>> {
+    let mut interfaces: Vec<Interface> = vec![];
+    for ia in nix::ifaddrs::getifaddrs().map_err(io::Error::from)? {
+        let addr = ia.address.and_then(|addr| match addr.family() {
+            Some(AddressFamily::Inet) => addr
+                .as_sockaddr_in()
+                .map(|sock_addr| IpAddr::V4(Ipv4Addr::from(sock_addr.ip()))),
+            Some(AddressFamily::Inet6) => addr
+                .as_sockaddr_in6()
+                .map(|sock_addr| IpAddr::V6(sock_addr.ip())),
+            _ => None,
+        });
+        match interfaces
+            .iter_mut()
+            .find(|interface| interface.name == ia.interface_name)
+        {
+            Some(interface) => interface.addrs.extend(addr),
+            None => interfaces.push(Interface {
+                index: nix::net::if_::if_nametoindex(ia.interface_name.as_str())
+                    .map_err(io::Error::from)?,
+                name: ia.interface_name,
+                addrs: addr.into_iter().collect(),
+            }),
+        }
+    }
+    Ok(interfaces)
+}
+
+/// Read the `IPv4` and `IPv6` routing tables from `/proc/net/route` and `/proc/net/ipv6_route`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn routes() -> TraceResult<Vec<Route>> {
+    const RTF_UP: u32 = 0x0001;
+    const RTF_REJECT: u32 = 0x0200;
+    let is_usable = |flags: u32| flags & RTF_UP != 0 && flags & RTF_REJECT == 0;
+    let parse_ipv4 = |field: &str| {
+        u32::from_str_radix(field, 16)
+            .ok()
+            .map(|addr| Ipv4Addr::from(addr.to_ne_bytes()))
+    };
+    let parse_ipv6 = |field: &str| u128::from_str_radix(field, 16).ok().map(Ipv6Addr::from);
+    let route = std::fs::read_to_string("/proc/net/route")?;
+    let ipv4 = route.lines().skip(1).filter_map(|line| {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        match fields[..] {
+            [interface, dest, gateway, flags, _refcnt, _use, metric, mask, ..] => {
+                let gateway = parse_ipv4(gateway)?;
+                is_usable(u32::from_str_radix(flags, 16).ok()?).then_some(())?;
+                Some(Route {
+                    dest: IpAddr::V4(parse_ipv4(dest)?),
+                    prefix_len: u8::try_from(parse_ipv4(mask).map(u32::from)?.count_ones()).ok()?,
+                    gateway: (!gateway.is_unspecified()).then_some(IpAddr::V4(gateway)),
+                    interface: interface.to_string(),
+                    metric: metric.parse().ok()?,
+                })
+            }
+            _ => None,
+        }
+    });
+    // The IPv6 routing table is absent if IPv6 is disabled.
+    let ipv6_route = std::fs::read_to_string("/proc/net/ipv6_route").unwrap_or_default();
+    let ipv6 = ipv6_route.lines().filter_map(|line| {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        match fields[..] {
+            [dest, prefix_len, _src, _src_prefix_len, gateway, metric, _refcnt, _use, flags, interface] =>
+            {
+                let gateway = parse_ipv6(gateway)?;
+                is_usable(u32::from_str_radix(flags, 16).ok()?).then_some(())?;
+                Some(Route {
+                    dest: IpAddr::V6(parse_ipv6(dest)?),
+                    prefix_len: u8::from_str_radix(prefix_len, 16).ok()?,
+                    gateway: (!gateway.is_unspecified()).then_some(IpAddr::V6(gateway)),
+                    interface: interface.to_string(),
+                    metric: u32::from_str_radix(metric, 16).ok()?,
+                })
+            }
+            _ => None,
+        }
+    });
+    Ok(ipv4.chain(ipv6).collect())
+}
+
+/// Reading the routing table is not supported on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+#[allow(clippy::unnecessary_wraps)]
+pub fn routes() -> TraceResult<Vec<Route>> {
+    Ok(vec![])
 }
 
 #[allow(clippy::unnecessary_wraps)]
@@ -132,27 +310,38 @@ pub fn discover_local_addr(target_addr: IpAddr, port: u16) -> TraceResult<IpAddr
 }
 
 /// A network socket.
+#[derive(Debug)]
 pub struct Socket {
     inner: socket2::Socket,
 }
 
 impl Socket {
+    const fn from_inner(inner: socket2::Socket) -> Self {
+        Self { inner }
+    }
+
     fn new(domain: Domain, ty: Type, protocol: Protocol) -> io::Result<Self> {
-        Ok(Self {
-            inner: socket2::Socket::new(domain, ty, Some(protocol))?,
-        })
+        Ok(Self::from_inner(socket2::Socket::new(
+            domain,
+            ty,
+            Some(protocol),
+        )?))
     }
 
     fn new_raw_ipv4(protocol: Protocol) -> io::Result<Self> {
-        Ok(Self {
-            inner: socket2::Socket::new(Domain::IPV4, Type::RAW, Some(protocol))?,
-        })
+        Ok(Self::from_inner(socket2::Socket::new(
+            Domain::IPV4,
+            Type::RAW,
+            Some(protocol),
+        )?))
     }
 
     fn new_raw_ipv6(protocol: Protocol) -> io::Result<Self> {
-        Ok(Self {
-            inner: socket2::Socket::new(Domain::IPV6, Type::RAW, Some(protocol))?,
-        })
+        Ok(Self::from_inner(socket2::Socket::new(
+            Domain::IPV6,
+            Type::RAW,
+            Some(protocol),
+        )?))
     }
 
     fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
@@ -162,6 +351,95 @@ impl Socket {
     fn local_addr(&self) -> io::Result<Option<SocketAddr>> {
         Ok(self.inner.local_addr()?.as_socket())
     }
+
+    /// Request that the kernel queues `ICMP` errors for sent packets (`IP_RECVERR` / `IPV6_RECVERR`).
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn set_recv_err(&self, domain: Domain) -> io::Result<()> {
+        if domain == Domain::IPV4 {
+            setsockopt(self.inner.as_raw_fd(), sockopt::Ipv4RecvErr, &true)?;
+        } else {
+            setsockopt(self.inner.as_raw_fd(), sockopt::Ipv6RecvErr, &true)?;
+        }
+        Ok(())
+    }
+
+    /// Set an integer socket option.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn set_int_option(
+        &self,
+        level: nix::libc::c_int,
+        optname: nix::libc::c_int,
+        optval: nix::libc::c_int,
+    ) -> io::Result<()> {
+        #[allow(unsafe_code)]
+        let res = unsafe {
+            nix::libc::setsockopt(
+                self.inner.as_raw_fd(),
+                level,
+                optname,
+                std::ptr::addr_of!(optval).cast(),
+                std::mem::size_of::<nix::libc::c_int>() as nix::libc::socklen_t,
+            )
+        };
+        if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Create an unprivileged `ICMP` datagram socket.
+    ///
+    /// Only Linux and Android, where such "ping" sockets are permitted for the groups in the `net.ipv4.ping_group_range`
+    /// sysctl, are supported.  Android permits them for all groups by default.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn new_icmp_dgram(domain: Domain, protocol: Protocol) -> io::Result<Self> {
+        let socket = Self::new(domain, Type::DGRAM, protocol)?;
+        socket.set_nonblocking(true)?;
+        socket.set_recv_timestamp(true)?;
+        socket.set_recv_err(domain)?;
+        if domain == Domain::IPV4 {
+            socket.set_int_option(nix::libc::IPPROTO_IP, nix::libc::IP_RECVTTL, 1)?;
+        } else {
+            socket.set_recv_hop_limit()?;
+        }
+        Ok(socket)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    #[allow(clippy::unnecessary_wraps)]
+    fn new_icmp_dgram(_domain: Domain, _protocol: Protocol) -> io::Result<Self> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// Request that the kernel timestamps received packets (`SO_TIMESTAMP`).
+    fn set_recv_timestamp(&self, enabled: bool) -> io::Result<()> {
+        Ok(setsockopt(
+            self.inner.as_raw_fd(),
+            sockopt::ReceiveTimestamp,
+            &enabled,
+        )?)
+    }
+
+    /// Request that the kernel delivers the hop limit of received `IPv6` packets (`IPV6_RECVHOPLIMIT`).
+    fn set_recv_hop_limit(&self) -> io::Result<()> {
+        let optval: nix::libc::c_int = 1;
+        #[allow(unsafe_code)]
+        let res = unsafe {
+            nix::libc::setsockopt(
+                self.inner.as_raw_fd(),
+                nix::libc::IPPROTO_IPV6,
+                IPV6_RECVHOPLIMIT,
+                std::ptr::addr_of!(optval).cast(),
+                mem::size_of::<nix::libc::c_int>() as nix::libc::socklen_t,
+            )
+        };
+        if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
 }
 
 impl TracerSocket for Socket {
@@ -191,13 +469,22 @@ impl TracerSocket for Socket {
         let socket = Self::new_raw_ipv4(Protocol::ICMPV4)?;
         socket.set_nonblocking(true)?;
         socket.set_header_included(true)?;
+        socket.set_recv_timestamp(true)?;
         Ok(socket)
     }
     fn new_recv_socket_ipv6(_addr: Ipv6Addr) -> io::Result<Self> {
         let socket = Self::new_raw_ipv6(Protocol::ICMPV6)?;
         socket.set_nonblocking(true)?;
+        socket.set_recv_timestamp(true)?;
+        socket.set_recv_hop_limit()?;
         Ok(socket)
     }
+    fn new_icmp_dgram_socket_ipv4() -> io::Result<Self> {
+        Self::new_icmp_dgram(Domain::IPV4, Protocol::ICMPV4)
+    }
+    fn new_icmp_dgram_socket_ipv6() -> io::Result<Self> {
+        Self::new_icmp_dgram(Domain::IPV6, Protocol::ICMPV6)
+    }
     fn new_stream_socket_ipv4() -> io::Result<Self> {
         let socket = Self::new(Domain::IPV4, Type::STREAM, Protocol::TCP)?;
         socket.set_nonblocking(true)?;
@@ -234,6 +521,98 @@ impl TracerSocket for Socket {
     fn set_unicast_hops_v6(&self, hops: u8) -> io::Result<()> {
         self.inner.set_unicast_hops_v6(u32::from(hops))
     }
+    fn set_tclass_v6(&self, tclass: u32) -> io::Result<()> {
+        self.inner.set_tclass_v6(tclass)
+    }
+    /// Set the `Don't Fragment` bit regardless of the path MTU known to the kernel (`IP_PMTUDISC_PROBE`), such that
+    /// probes larger than the path MTU are still sent, or never set it (`IP_PMTUDISC_DONT`).
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn set_dont_fragment(&self, dont_fragment: bool) -> io::Result<()> {
+        let mode = if dont_fragment {
+            nix::libc::IP_PMTUDISC_PROBE
+        } else {
+            nix::libc::IP_PMTUDISC_DONT
+        };
+        self.set_int_option(nix::libc::IPPROTO_IP, nix::libc::IP_MTU_DISCOVER, mode)
+    }
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    fn set_dont_fragment(&self, dont_fragment: bool) -> io::Result<()> {
+        Ok(setsockopt(
+            self.inner.as_raw_fd(),
+            sockopt::IpDontFrag,
+            &dont_fragment,
+        )?)
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios"
+    )))]
+    fn set_dont_fragment(&self, _dont_fragment: bool) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+    /// Prohibit fragmentation by the sender regardless of the path MTU known to the kernel (`IPV6_PMTUDISC_PROBE`), or
+    /// permit it (`IPV6_PMTUDISC_DONT`).
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn set_dont_fragment_v6(&self, dont_fragment: bool) -> io::Result<()> {
+        let mode = if dont_fragment {
+            nix::libc::IPV6_PMTUDISC_PROBE
+        } else {
+            nix::libc::IPV6_PMTUDISC_DONT
+        };
+        self.set_int_option(nix::libc::IPPROTO_IPV6, nix::libc::IPV6_MTU_DISCOVER, mode)
+    }
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    fn set_dont_fragment_v6(&self, dont_fragment: bool) -> io::Result<()> {
+        Ok(setsockopt(
+            self.inner.as_raw_fd(),
+            sockopt::Ipv6DontFrag,
+            &dont_fragment,
+        )?)
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios"
+    )))]
+    fn set_dont_fragment_v6(&self, _dont_fragment: bool) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+    /// Set a sticky `IPv6` extension header (`IPV6_DSTOPTS` / `IPV6_HOPOPTS`), which requires `CAP_NET_RAW`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn set_ipv6_ext_header(&self, header: Ipv6ExtHeader) -> io::Result<()> {
+        let optname = match header {
+            Ipv6ExtHeader::DestinationOptions => nix::libc::IPV6_DSTOPTS,
+            Ipv6ExtHeader::HopByHop => nix::libc::IPV6_HOPOPTS,
+        };
+        #[allow(unsafe_code)]
+        let res = unsafe {
+            nix::libc::setsockopt(
+                self.inner.as_raw_fd(),
+                nix::libc::IPPROTO_IPV6,
+                optname,
+                IPV6_EXT_HEADER.as_ptr().cast(),
+                IPV6_EXT_HEADER.len() as nix::libc::socklen_t,
+            )
+        };
+        if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn set_ipv6_ext_header(&self, _header: Ipv6ExtHeader) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+    fn set_mss(&self, mss: u32) -> io::Result<()> {
+        self.inner.set_mss(mss)
+    }
+    fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.inner.set_recv_buffer_size(size)
+    }
     fn connect(&self, address: SocketAddr) -> io::Result<()> {
         self.inner.connect(&SockAddr::from(address))
     }
@@ -241,6 +620,63 @@ impl TracerSocket for Socket {
         self.inner.send_to(buf, &SockAddr::from(addr))?;
         Ok(())
     }
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd"
+    ))]
+    fn send_batch(&self, packets: &[(&[u8], SocketAddr)]) -> io::Result<()> {
+        for chunk in packets.chunks(MAX_SEND_BATCH) {
+            let addrs: ArrayVec<SockAddr, MAX_SEND_BATCH> = chunk
+                .iter()
+                .map(|&(_, addr)| SockAddr::from(addr))
+                .collect();
+            let mut iovs: ArrayVec<iovec, MAX_SEND_BATCH> = chunk
+                .iter()
+                .map(|&(buf, _)| iovec {
+                    iov_base: buf.as_ptr().cast_mut().cast(),
+                    iov_len: buf.len(),
+                })
+                .collect();
+            let mut msgs: ArrayVec<mmsghdr, MAX_SEND_BATCH> = iovs
+                .iter_mut()
+                .zip(&addrs)
+                .map(|(iov, addr)| {
+                    #[allow(unsafe_code)]
+                    // SAFETY: a zeroed `msghdr` is valid, and the fields which may differ by platform are left zeroed
+                    let mut mhdr: msghdr = unsafe { mem::zeroed() };
+                    mhdr.msg_name = addr.as_ptr().cast_mut().cast();
+                    mhdr.msg_namelen = addr.len();
+                    mhdr.msg_iov = iov;
+                    mhdr.msg_iovlen = 1;
+                    mmsghdr {
+                        msg_hdr: mhdr,
+                        msg_len: 0,
+                    }
+                })
+                .collect();
+            // the kernel may send fewer packets than requested, in which case the remainder are sent again
+            let mut sent = 0;
+            while sent < msgs.len() {
+                #[allow(unsafe_code)]
+                let count = unsafe {
+                    nix::libc::sendmmsg(
+                        self.inner.as_raw_fd(),
+                        msgs[sent..].as_mut_ptr(),
+                        (msgs.len() - sent) as _,
+                        0,
+                    )
+                };
+                match usize::try_from(count) {
+                    Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+                    Ok(count) => sent += count,
+                    Err(_) => return Err(io::Error::last_os_error()),
+                }
+            }
+        }
+        Ok(())
+    }
     fn is_readable(&self, timeout: Duration) -> io::Result<bool> {
         let mut read = FdSet::new();
         read.insert(self.inner.as_raw_fd());
@@ -265,11 +701,129 @@ impl TracerSocket for Socket {
         )?;
         Ok(writable == 1)
     }
-    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, Option<SocketAddr>)> {
-        self.inner.recv_from_into_buf(buf)
+    fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<RecvMeta> {
+        let mut iov = iovec {
+            iov_base: buf.as_mut_ptr().cast(),
+            iov_len: buf.len(),
+        };
+        let mut addr = MaybeUninit::zeroed();
+        let mut cmsg_buf = CmsgBuf::default();
+        let mut mhdr = recv_msghdr(&mut iov, &mut addr, &mut cmsg_buf);
+        #[allow(unsafe_code)]
+        let len =
+            unsafe { nix::libc::recvmsg(self.inner.as_raw_fd(), std::ptr::addr_of_mut!(mhdr), 0) };
+        let len = usize::try_from(len).map_err(|_| io::Error::last_os_error())?;
+        #[allow(unsafe_code)]
+        Ok(unsafe { recv_meta(&mhdr, len) })
+    }
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd"
+    ))]
+    fn recv_batch(
+        &mut self,
+        bufs: &mut [[u8; MAX_PACKET_SIZE]],
+    ) -> io::Result<ArrayVec<RecvMeta, MAX_RECV_BATCH>> {
+        let mut iovs: ArrayVec<iovec, MAX_RECV_BATCH> = bufs
+            .iter_mut()
+            .take(MAX_RECV_BATCH)
+            .map(|buf| iovec {
+                iov_base: buf.as_mut_ptr().cast(),
+                iov_len: buf.len(),
+            })
+            .collect();
+        let mut addrs = [MaybeUninit::zeroed(); MAX_RECV_BATCH];
+        let mut cmsg_bufs = [CmsgBuf::default(); MAX_RECV_BATCH];
+        let mut msgs: ArrayVec<mmsghdr, MAX_RECV_BATCH> = iovs
+            .iter_mut()
+            .zip(&mut addrs)
+            .zip(&mut cmsg_bufs)
+            .map(|((iov, addr), cmsg_buf)| mmsghdr {
+                msg_hdr: recv_msghdr(iov, addr, cmsg_buf),
+                msg_len: 0,
+            })
+            .collect();
+        #[allow(unsafe_code)]
+        let count = unsafe {
+            nix::libc::recvmmsg(
+                self.inner.as_raw_fd(),
+                msgs.as_mut_ptr(),
+                msgs.len() as _,
+                0,
+                std::ptr::null_mut::<nix::libc::timespec>(),
+            )
+        };
+        let count = usize::try_from(count).map_err(|_| io::Error::last_os_error())?;
+        Ok(msgs
+            .iter()
+            .take(count)
+            .map(|msg| {
+                #[allow(unsafe_code)]
+                unsafe {
+                    recv_meta(&msg.msg_hdr, msg.msg_len as usize)
+                }
+            })
+            .collect())
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn recv_err(&mut self, buf: &mut [u8]) -> io::Result<Option<RecvErrMeta>> {
+        loop {
+            let mut iov = [io::IoSliceMut::new(buf)];
+            // the error may be preceded by the ttl or hop limit of the `ICMP` error
+            let mut cmsg_buf = nix::cmsg_space!(
+                nix::libc::c_int,
+                nix::libc::sock_extended_err,
+                nix::libc::sockaddr_in6
+            );
+            let msg = match recvmsg::<SockaddrStorage>(
+                self.inner.as_raw_fd(),
+                &mut iov,
+                Some(&mut cmsg_buf),
+                MsgFlags::MSG_ERRQUEUE,
+            ) {
+                Ok(msg) => msg,
+                Err(nix::Error::EAGAIN) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            let meta = msg.cmsgs().find_map(|cmsg| match cmsg {
+                ControlMessageOwned::Ipv4RecvErr(err, Some(addr))
+                    if err.ee_origin == nix::libc::SO_EE_ORIGIN_ICMP =>
+                {
+                    Some(RecvErrMeta {
+                        len: msg.bytes,
+                        offender: IpAddr::V4(Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr))),
+                        icmp_type: err.ee_type,
+                        icmp_code: err.ee_code,
+                        info: err.ee_info,
+                    })
+                }
+                ControlMessageOwned::Ipv6RecvErr(err, Some(addr))
+                    if err.ee_origin == nix::libc::SO_EE_ORIGIN_ICMP6 =>
+                {
+                    Some(RecvErrMeta {
+                        len: msg.bytes,
+                        offender: IpAddr::V6(Ipv6Addr::from(addr.sin6_addr.s6_addr)),
+                        icmp_type: err.ee_type,
+                        icmp_code: err.ee_code,
+                        info: err.ee_info,
+                    })
+                }
+                _ => None,
+            });
+            if meta.is_some() {
+                return Ok(meta);
+            }
+        }
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    #[allow(clippy::unused_self, clippy::unnecessary_wraps)]
+    fn recv_err(&mut self, _buf: &mut [u8]) -> io::Result<Option<RecvErrMeta>> {
+        Ok(None)
     }
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.inner.read(buf)
+    fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self::from_inner(self.inner.try_clone()?))
     }
     fn shutdown(&self) -> io::Result<()> {
         self.inner.shutdown(Shutdown::Both)
@@ -290,28 +844,80 @@ impl TracerSocket for Socket {
     }
 }
 
-impl io::Read for Socket {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.inner.read(buf)
+/// Convert a `SockaddrStorage` to a `SocketAddr`, if it holds an IPv4 or IPv6 address.
+fn sockaddr_to_socket_addr(addr: &SockaddrStorage) -> Option<SocketAddr> {
+    if let Some(addr) = addr.as_sockaddr_in() {
+        Some(SocketAddr::V4((*addr).into()))
+    } else {
+        addr.as_sockaddr_in6()
+            .map(|addr| SocketAddr::V6((*addr).into()))
     }
 }
 
-/// An extension trait to allow `recv_from` method which writes to a `&mut [u8]`.
+/// A `msghdr` to receive a packet into the buffer of the `iov`, along with its source `addr` and control messages.
+fn recv_msghdr(
+    iov: &mut iovec,
+    addr: &mut MaybeUninit<sockaddr_storage>,
+    cmsg_buf: &mut CmsgBuf,
+) -> msghdr {
+    #[allow(unsafe_code)]
+    // SAFETY: a zeroed `msghdr` is valid, and the fields which may differ by platform are left zeroed
+    let mut mhdr: msghdr = unsafe { mem::zeroed() };
+    mhdr.msg_name = addr.as_mut_ptr().cast();
+    mhdr.msg_namelen = mem::size_of::<sockaddr_storage>() as nix::libc::socklen_t;
+    mhdr.msg_iov = iov;
+    mhdr.msg_iovlen = 1;
+    mhdr.msg_control = cmsg_buf.as_mut_ptr().cast();
+    mhdr.msg_controllen = mem::size_of::<CmsgBuf>() as _;
+    mhdr
+}
+
+/// The metadata of a packet of `len` bytes received with the `mhdr`.
 ///
-/// This is required for `socket2::Socket` which [does not currently provide] this method.
+/// The time the packet was received by the kernel (`SO_TIMESTAMP`) and the ttl of an `IPv4` packet (`IP_TTL`) or hop
+/// limit of an `IPv6` packet (`IPV6_HOPLIMIT`) are read from the control messages, if present.
 ///
-/// [does not currently provide]: https://github.com/rust-lang/socket2/issues/223
-trait RecvFrom {
-    fn recv_from_into_buf(&self, buf: &mut [u8]) -> io::Result<(usize, Option<SocketAddr>)>;
+/// # Safety
+///
+/// The `mhdr` must have been created by `recv_msghdr` and filled by a successful `recvmsg` or `recvmmsg`.
+#[allow(unsafe_code)]
+unsafe fn recv_meta(mhdr: &msghdr, len: usize) -> RecvMeta {
+    let addr = unsafe { SockaddrStorage::from_raw(mhdr.msg_name.cast(), Some(mhdr.msg_namelen)) };
+    let mut recv = None;
+    let mut ttl = None;
+    let mut cmsg = unsafe { nix::libc::CMSG_FIRSTHDR(mhdr) };
+    while let Some(header) = unsafe { cmsg.as_ref() } {
+        let data = unsafe { nix::libc::CMSG_DATA(cmsg) };
+        match (header.cmsg_level, header.cmsg_type) {
+            (nix::libc::SOL_SOCKET, nix::libc::SCM_TIMESTAMP) => {
+                let tv = unsafe { data.cast::<nix::libc::timeval>().read_unaligned() };
+                recv = Some(timeval_to_system_time(TimeVal::from(tv)));
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (nix::libc::IPPROTO_IP, nix::libc::IP_TTL)
+            | (nix::libc::IPPROTO_IPV6, nix::libc::IPV6_HOPLIMIT) => {
+                let hops = unsafe { data.cast::<nix::libc::c_int>().read_unaligned() };
+                ttl = u8::try_from(hops).ok();
+            }
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            (nix::libc::IPPROTO_IPV6, nix::libc::IPV6_HOPLIMIT) => {
+                let hops = unsafe { data.cast::<nix::libc::c_int>().read_unaligned() };
+                ttl = u8::try_from(hops).ok();
+            }
+            _ => {}
+        }
+        cmsg = unsafe { nix::libc::CMSG_NXTHDR(mhdr, cmsg) };
+    }
+    RecvMeta {
+        len,
+        addr: addr.as_ref().and_then(sockaddr_to_socket_addr),
+        recv,
+        ttl,
+    }
 }
 
-impl RecvFrom for socket2::Socket {
-    // Safety: the `recv` implementation promises not to write uninitialised
-    // bytes to the `buf`fer, so this casting is safe.
-    #![allow(unsafe_code)]
-    fn recv_from_into_buf(&self, buf: &mut [u8]) -> io::Result<(usize, Option<SocketAddr>)> {
-        let buf = unsafe { &mut *(buf as *mut [u8] as *mut [std::mem::MaybeUninit<u8>]) };
-        self.recv_from(buf)
-            .map(|(size, addr)| (size, addr.as_socket()))
-    }
+/// Convert a kernel `TimeVal` timestamp to a `SystemTime`.
+fn timeval_to_system_time(tv: TimeVal) -> SystemTime {
+    SystemTime::UNIX_EPOCH
+        + Duration::from_micros(u64::try_from(tv.num_microseconds()).unwrap_or_default())
 }
@@ -1,8 +1,11 @@
 use super::byte_order::PlatformIpv4FieldByteOrder;
 use crate::tracing::error::{TraceResult, TracerError};
 use crate::tracing::net::channel::MAX_PACKET_SIZE;
+use crate::tracing::net::interface::{Interface, Route};
 use crate::tracing::net::platform::windows::adapter::Adapters;
-use crate::tracing::net::socket::TracerSocket;
+use crate::tracing::net::socket::{RecvErrMeta, RecvMeta, TracerSocket};
+use crate::tracing::net::source::InterfaceAddrIpv6;
+use crate::tracing::Ipv6ExtHeader;
 use socket2::{Domain, Protocol, SockAddr, Type};
 use std::ffi::c_void;
 use std::io::{Error, ErrorKind, Result};
@@ -14,10 +17,11 @@ use std::time::Duration;
 use windows_sys::Win32::Foundation::{WAIT_FAILED, WAIT_TIMEOUT};
 use windows_sys::Win32::Networking::WinSock::{
     AF_INET, AF_INET6, FD_CONNECT, FD_WRITE, ICMP_ERROR_INFO, IN6_ADDR, IN6_ADDR_0, IN_ADDR,
-    IN_ADDR_0, IPPROTO_RAW, IPPROTO_TCP, SIO_ROUTING_INTERFACE_QUERY, SOCKADDR_IN, SOCKADDR_IN6,
-    SOCKADDR_IN6_0, SOCKADDR_STORAGE, SOCKET_ERROR, SOL_SOCKET, SO_ERROR, SO_PORT_SCALABILITY,
-    SO_REUSE_UNICASTPORT, TCP_FAIL_CONNECT_ON_ICMP_ERROR, TCP_ICMP_ERROR_INFO, WSABUF, WSADATA,
-    WSAEADDRNOTAVAIL, WSAECONNREFUSED, WSAEHOSTUNREACH, WSAEINPROGRESS, WSA_IO_INCOMPLETE,
+    IN_ADDR_0, IPPROTO_IP, IPPROTO_IPV6, IPPROTO_RAW, IPPROTO_TCP, IPV6_DONTFRAG, IPV6_TCLASS,
+    IP_DONTFRAGMENT, SIO_ROUTING_INTERFACE_QUERY, SOCKADDR_IN, SOCKADDR_IN6, SOCKADDR_IN6_0,
+    SOCKADDR_STORAGE, SOCKET_ERROR, SOL_SOCKET, SO_ERROR, SO_PORT_SCALABILITY,
+    SO_REUSE_UNICASTPORT, TCP_FAIL_CONNECT_ON_ICMP_ERROR, TCP_ICMP_ERROR_INFO, TCP_MAXSEG, WSABUF,
+    WSADATA, WSAEADDRNOTAVAIL, WSAECONNREFUSED, WSAEHOSTUNREACH, WSAEINPROGRESS, WSA_IO_INCOMPLETE,
     WSA_IO_PENDING,
 };
 use windows_sys::Win32::System::IO::OVERLAPPED;
@@ -67,8 +71,57 @@ pub fn lookup_interface_addr_ipv4(name: &str) -> TraceResult<IpAddr> {
     lookup_interface_addr(&Adapters::ipv4()?, name)
 }
 
-pub fn lookup_interface_addr_ipv6(name: &str) -> TraceResult<IpAddr> {
-    lookup_interface_addr(&Adapters::ipv6()?, name)
+/// Lookup the candidate `IPv6` source addresses of the named adapter, or of all adapters if `name` is `None`.
+///
+/// The temporary and deprecated flags of addresses are not retrieved on this platform.
+pub fn lookup_interface_addrs_ipv6(name: Option<&str>) -> TraceResult<Vec<InterfaceAddrIpv6>> {
+    let addrs = Adapters::ipv6()?
+        .iter()
+        .filter(|adapter| match name {
+            Some(name) => adapter.name.eq_ignore_ascii_case(name),
+            None => true,
+        })
+        .filter_map(|adapter| match adapter.addr {
+            IpAddr::V6(addr) => Some(InterfaceAddrIpv6 {
+                interface: adapter.name,
+                addr,
+                temporary: false,
+                deprecated: false,
+            }),
+            IpAddr::V4(_) => None,
+        })
+        .collect::<Vec<_>>();
+    match name {
+        Some(name) if addrs.is_empty() => Err(TracerError::UnknownInterface(name.to_string())),
+        _ => Ok(addrs),
+    }
+}
+
+/// Lookup all network adapters and their addresses.
+pub fn interfaces() -> TraceResult<Vec<Interface>> {
+    let ipv4 = Adapters::ipv4()?;
+    let ipv6 = Adapters::ipv6()?;
+    let mut interfaces: Vec<Interface> = vec![];
+    for adapter in ipv4.iter().chain(ipv6.iter()) {
+        match interfaces
+            .iter_mut()
+            .find(|interface| interface.name == adapter.name)
+        {
+            Some(interface) => interface.addrs.push(adapter.addr),
+            None => interfaces.push(Interface {
+                index: adapter.index,
+                name: adapter.name,
+                addrs: vec![adapter.addr],
+            }),
+        }
+    }
+    Ok(interfaces)
+}
+
+/// Reading the routing table is not supported on this platform.
+#[allow(clippy::unnecessary_wraps)]
+pub fn routes() -> TraceResult<Vec<Route>> {
+    Ok(vec![])
 }
 
 pub fn discover_local_addr(target: IpAddr, _port: u16) -> TraceResult<IpAddr> {
@@ -191,6 +244,16 @@ impl Socket {
         self.inner.set_nonblocking(is_non_blocking)
     }
 
+    // TODO
+    // we always copy and claim to have returned MAX_PACKET_SIZE bytes, regardless of how many bytes we actually
+    // received.  The callers currently ignore this and just try to parse a packet from the buffer which isn't ideal.
+    // Really we should record the actual number of bytes read in the `get_overlapped_result` call and return that here.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        buf.copy_from_slice(self.buf.as_slice());
+        self.post_recv_from()?;
+        Ok(MAX_PACKET_SIZE)
+    }
+
     // TODO handle case where `WSARecvFrom` succeeded immediately.
     fn post_recv_from(&mut self) -> Result<()> {
         fn is_err(res: i32) -> bool {
@@ -329,6 +392,14 @@ impl TracerSocket for Socket {
         Ok(sock)
     }
 
+    fn new_icmp_dgram_socket_ipv4() -> Result<Self> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+
+    fn new_icmp_dgram_socket_ipv6() -> Result<Self> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+
     fn new_udp_dgram_socket_ipv4() -> Result<Self> {
         Self::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
     }
@@ -374,6 +445,30 @@ impl TracerSocket for Socket {
         self.inner.set_unicast_hops_v6(max_hops.into())
     }
 
+    fn set_tclass_v6(&self, tclass: u32) -> Result<()> {
+        self.setsockopt_u32(IPPROTO_IPV6, IPV6_TCLASS as _, tclass)
+    }
+
+    fn set_dont_fragment(&self, dont_fragment: bool) -> Result<()> {
+        self.setsockopt_u32(IPPROTO_IP, IP_DONTFRAGMENT as _, u32::from(dont_fragment))
+    }
+
+    fn set_dont_fragment_v6(&self, dont_fragment: bool) -> Result<()> {
+        self.setsockopt_u32(IPPROTO_IPV6, IPV6_DONTFRAG as _, u32::from(dont_fragment))
+    }
+
+    fn set_ipv6_ext_header(&self, _header: Ipv6ExtHeader) -> Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+
+    fn set_mss(&self, mss: u32) -> Result<()> {
+        self.setsockopt_u32(IPPROTO_TCP, TCP_MAXSEG as _, mss)
+    }
+
+    fn set_recv_buffer_size(&self, size: usize) -> Result<()> {
+        self.inner.set_recv_buffer_size(size)
+    }
+
     fn connect(&self, dest_socketaddr: SocketAddr) -> Result<()> {
         self.set_fail_connect_on_icmp_error(true)?;
         syscall!(
@@ -424,20 +519,16 @@ impl TracerSocket for Socket {
         Ok(true)
     }
 
-    fn recv_from(&mut self, buf: &mut [u8]) -> Result<(usize, Option<SocketAddr>)> {
+    // Kernel receive timestamps and ttls are not currently supported on Windows.
+    fn recv_from(&mut self, buf: &mut [u8]) -> Result<RecvMeta> {
         let addr = sockaddrptr_to_ipaddr(addr_of_mut!(*self.from))?;
         let len = self.read(buf)?;
-        Ok((len, Some(SocketAddr::new(addr, 0))))
-    }
-
-    // TODO
-    // we always copy and claim to have returned MAX_PACKET_SIZE bytes, regardless of how many bytes we actually
-    // received.  The callers currently ignore this and just try to parse a packet from the buffer which isn't ideal.
-    // Really we should record the actual number of bytes read in the `get_overlapped_result` call and return that here.
-    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        buf.copy_from_slice(self.buf.as_slice());
-        self.post_recv_from()?;
-        Ok(MAX_PACKET_SIZE)
+        Ok(RecvMeta {
+            len,
+            addr: Some(SocketAddr::new(addr, 0)),
+            recv: None,
+            ttl: None,
+        })
     }
 
     fn shutdown(&self) -> Result<()> {
@@ -456,6 +547,16 @@ impl TracerSocket for Socket {
         }
     }
 
+    #[allow(clippy::unused_self)]
+    fn recv_err(&mut self, _buf: &mut [u8]) -> Result<Option<RecvErrMeta>> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+
+    #[allow(clippy::unused_self)]
+    fn try_clone(&self) -> Result<Self> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+
     #[allow(unsafe_code)]
     fn icmp_error_info(&self) -> Result<IpAddr> {
         let icmp_error_info = self.getsockopt::<ICMP_ERROR_INFO>(
@@ -706,6 +807,8 @@ mod adapter {
     pub struct AdapterAddress {
         /// The adapter friendly name.
         pub name: String,
+        /// The adapter interface index.
+        pub index: u32,
         /// The adapter IpAddress.
         pub addr: IpAddr,
     }
@@ -747,9 +850,14 @@ mod adapter {
                         let sockaddr = socket_address.lpSockaddr;
                         sockaddrptr_to_ipaddr(sockaddr.cast()).ok()?
                     };
+                    let index = match (*self.next).Anonymous1.Anonymous.IfIndex {
+                        0 => (*self.next).Ipv6IfIndex,
+                        index => index,
+                    };
                     self.next = (*self.next).Next;
                     Some(AdapterAddress {
                         name: friendly_name,
+                        index,
                         addr,
                     })
                 }
@@ -0,0 +1,417 @@
+use crate::tracing::error::{TraceResult, TracerError};
+use crate::tracing::net::channel::{MAX_PACKET_SIZE, MAX_RECV_BATCH};
+use crate::tracing::net::platform::Socket;
+use crate::tracing::net::socket::{RecvMeta, TracerSocket as _};
+use crate::tracing::net::{ipv4, ipv6};
+use crate::tracing::TracerAddrFamily;
+use arrayvec::ArrayVec;
+use parking_lot::Mutex;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// The maximum number of received batches queued for a subscriber before further batches are dropped.
+const MAX_QUEUED_BATCHES: usize = 16;
+
+/// The maximum number of batch buffers held by the receive loop.
+///
+/// A buffer is reused once every subscriber has processed it.  If every buffer is still held by a subscriber then the
+/// packets received are dropped for every subscriber.
+const MAX_POOLED_BATCHES: usize = MAX_QUEUED_BATCHES * 2;
+
+/// How long the receive loop waits for a packet before checking whether it is still required.
+const POLL_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A batch of packets received, and prepared for extraction, by a `SharedReceiver`.
+pub struct RecvBatch {
+    bufs: Box<[[u8; MAX_PACKET_SIZE]; MAX_RECV_BATCH]>,
+    metas: ArrayVec<RecvMeta, MAX_RECV_BATCH>,
+}
+
+impl RecvBatch {
+    fn new() -> Self {
+        Self {
+            bufs: Box::new([[0_u8; MAX_PACKET_SIZE]; MAX_RECV_BATCH]),
+            metas: ArrayVec::new(),
+        }
+    }
+
+    /// The buffers holding the packets of the batch.
+    pub fn bufs(&self) -> &[[u8; MAX_PACKET_SIZE]] {
+        self.bufs.as_slice()
+    }
+
+    /// The metadata of each packet in the batch.
+    pub fn metas(&self) -> &[RecvMeta] {
+        &self.metas
+    }
+
+    /// Receive a batch of packets from the `socket` and prepare them for extraction.
+    fn recv(&mut self, socket: &mut Socket, source_addr: IpAddr) -> TraceResult<()> {
+        self.metas = socket.recv_batch(self.bufs.as_mut_slice())?;
+        match source_addr {
+            IpAddr::V4(_) => ipv4::prepare_received(self.bufs.as_mut_slice(), &self.metas)?,
+            IpAddr::V6(_) => ipv6::prepare_received(self.bufs.as_mut_slice(), &self.metas),
+        }
+        Ok(())
+    }
+}
+
+/// A single receive loop, on a single raw socket, shared by the channels of several tracers.
+///
+/// A raw socket receives every `ICMP` packet and so, without sharing, every packet is copied to, and polled for by,
+/// the channel of every tracer.  Instead, each channel which is configured with a `SharedReceiver` subscribes to the
+/// batches of packets received by the loop and each packet is read from the socket, and prepared, once.  Batches are
+/// received into a pool of buffers which are shared with, rather than copied to, each subscriber.
+///
+/// The receive loop runs until every handle to the `SharedReceiver` and every subscription has been dropped.
+#[derive(Debug, Clone)]
+pub struct SharedReceiver {
+    source_addr: IpAddr,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl SharedReceiver {
+    /// Start a receive loop on a new raw socket for the `source_addr`.
+    ///
+    /// This operation requires the `CAP_NET_RAW` capability on Linux.
+    pub fn start(source_addr: IpAddr) -> TraceResult<Self> {
+        let subscribers = Arc::new(Mutex::new(Vec::new()));
+        let (ready_tx, ready_rx) = mpsc::sync_channel(1);
+        {
+            let subscribers = subscribers.clone();
+            thread::Builder::new()
+                .name(format!("receiver-{source_addr}"))
+                .spawn(move || {
+                    let socket = match source_addr {
+                        IpAddr::V4(addr) => Socket::new_recv_socket_ipv4(addr),
+                        IpAddr::V6(addr) => Socket::new_recv_socket_ipv6(addr),
+                    };
+                    match socket {
+                        Ok(socket) => {
+                            // The receiver is only dropped if the starting thread has gone away.
+                            if ready_tx.send(Ok(())).is_ok() {
+                                run_receiver(socket, source_addr, &subscribers);
+                            }
+                        }
+                        Err(err) => drop(ready_tx.send(Err(err))),
+                    }
+                })?;
+        }
+        match ready_rx.recv() {
+            Ok(result) => result?,
+            Err(_) => return Err(receiver_stopped()),
+        }
+        Ok(Self {
+            source_addr,
+            subscribers,
+        })
+    }
+
+    /// The source address of the raw socket.
+    #[must_use]
+    pub const fn source_addr(&self) -> IpAddr {
+        self.source_addr
+    }
+
+    /// May a channel with the `source_addr` receive from this receive loop?
+    ///
+    /// On Windows the raw socket is bound to the source address, whereas elsewhere it receives the packets of every
+    /// address of the address family.
+    #[must_use]
+    pub fn accepts(&self, source_addr: IpAddr) -> bool {
+        if cfg!(windows) {
+            self.source_addr == source_addr
+        } else {
+            self.source_addr.is_ipv4() == source_addr.is_ipv4()
+        }
+    }
+
+    /// Subscribe to the batches of packets received.
+    ///
+    /// Batches are dropped, rather than blocking the receive loop, if the subscriber falls behind.
+    #[must_use]
+    pub fn subscribe(&self) -> Subscription {
+        let (tx, rx) = mpsc::sync_channel(MAX_QUEUED_BATCHES);
+        let dropped = Arc::new(AtomicUsize::new(0));
+        self.subscribers.lock().push(Subscriber {
+            tx,
+            dropped: dropped.clone(),
+        });
+        Subscription {
+            rx,
+            dropped,
+            reported: 0,
+        }
+    }
+}
+
+/// A subscription to the batches of packets received by a `SharedReceiver`.
+#[derive(Debug)]
+pub struct Subscription {
+    rx: Receiver<Arc<RecvBatch>>,
+    dropped: Arc<AtomicUsize>,
+    reported: usize,
+}
+
+impl Subscription {
+    /// Wait up to `timeout` for the next batch of packets.
+    ///
+    /// Any packets dropped for this subscription since the last call are reported.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> TraceResult<Option<Arc<RecvBatch>>> {
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        if dropped > self.reported {
+            warn!(
+                count = dropped - self.reported,
+                total = dropped,
+                "dropped received packets as the tracer fell behind the shared receiver"
+            );
+            self.reported = dropped;
+        }
+        match self.rx.recv_timeout(timeout) {
+            Ok(batch) => Ok(Some(batch)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(receiver_stopped()),
+        }
+    }
+
+    /// The total number of packets dropped for this subscription.
+    #[must_use]
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// The receive loop side of a `Subscription`.
+#[derive(Debug)]
+struct Subscriber {
+    tx: SyncSender<Arc<RecvBatch>>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl Subscriber {
+    /// Record that `count` packets were dropped for this subscriber.
+    fn record_dropped(&self, count: usize) {
+        self.dropped.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+/// The raw sockets on which the channels of several tracers send privileged probes.
+///
+/// The sockets are created once, such that a channel may be connected after the privileges required to create raw
+/// sockets have been dropped, and each channel sends on duplicates of them.  On Windows, where privileges are not
+/// dropped and sockets cannot be duplicated, each channel instead creates sockets of its own.
+#[derive(Debug, Clone)]
+pub struct SharedSenders {
+    addr_family: TracerAddrFamily,
+    #[cfg(unix)]
+    icmp: Arc<Socket>,
+    #[cfg(unix)]
+    udp: Arc<Socket>,
+}
+
+impl SharedSenders {
+    /// Create the raw sockets for sending the `ICMP` and `UDP` probes of the `addr_family`.
+    ///
+    /// This operation requires the `CAP_NET_RAW` capability on Linux.
+    pub fn new(addr_family: TracerAddrFamily) -> TraceResult<Self> {
+        #[cfg(unix)]
+        {
+            let (icmp, udp) = make_send_sockets(addr_family)?;
+            Ok(Self {
+                addr_family,
+                icmp: Arc::new(icmp),
+                udp: Arc::new(udp),
+            })
+        }
+        #[cfg(windows)]
+        Ok(Self { addr_family })
+    }
+
+    /// The address family of the sockets.
+    #[must_use]
+    pub const fn addr_family(&self) -> TracerAddrFamily {
+        self.addr_family
+    }
+
+    /// The `ICMP` and `UDP` sockets on which a channel sends.
+    pub(super) fn sockets(&self) -> TraceResult<(Socket, Socket)> {
+        #[cfg(unix)]
+        {
+            Ok((self.icmp.try_clone()?, self.udp.try_clone()?))
+        }
+        #[cfg(windows)]
+        make_send_sockets(self.addr_family)
+    }
+}
+
+/// Make the raw sockets for sending the `ICMP` and `UDP` probes of the `addr_family`.
+fn make_send_sockets(addr_family: TracerAddrFamily) -> TraceResult<(Socket, Socket)> {
+    Ok(match addr_family {
+        TracerAddrFamily::Ipv4 => (
+            Socket::new_icmp_send_socket_ipv4()?,
+            Socket::new_udp_send_socket_ipv4()?,
+        ),
+        TracerAddrFamily::Ipv6 => (
+            Socket::new_icmp_send_socket_ipv6()?,
+            Socket::new_udp_send_socket_ipv6()?,
+        ),
+    })
+}
+
+/// The error returned when the receive loop of a `SharedReceiver` has stopped.
+pub fn receiver_stopped() -> TracerError {
+    TracerError::IoError(std::io::Error::new(
+        ErrorKind::BrokenPipe,
+        "shared receiver stopped",
+    ))
+}
+
+/// Read batches of packets from the `socket` and deliver each batch to every subscriber.
+///
+/// The loop ends if the socket cannot be read, which disconnects every subscriber, or once no handle to the
+/// `SharedReceiver` and no subscriber remains.
+fn run_receiver(
+    mut socket: Socket,
+    source_addr: IpAddr,
+    subscribers: &Arc<Mutex<Vec<Subscriber>>>,
+) {
+    let mut pool: Vec<Arc<RecvBatch>> = Vec::new();
+    let mut overflow = RecvBatch::new();
+    loop {
+        if Arc::strong_count(subscribers) == 1 && subscribers.lock().is_empty() {
+            debug!("shared receiver no longer required");
+            return;
+        }
+        match socket.is_readable(POLL_TIMEOUT) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            // Switching to an unprivileged user interrupts the wait on every thread.
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => {
+                warn!(%err, "shared receiver failed");
+                subscribers.lock().clear();
+                return;
+            }
+        }
+        let free = match pool
+            .iter_mut()
+            .position(|batch| Arc::get_mut(batch).is_some())
+        {
+            Some(index) => Some(index),
+            None if pool.len() < MAX_POOLED_BATCHES => {
+                pool.push(Arc::new(RecvBatch::new()));
+                Some(pool.len() - 1)
+            }
+            None => None,
+        };
+        let batch = match free {
+            Some(index) => Arc::get_mut(&mut pool[index]).expect("batch buffer in use"),
+            None => &mut overflow,
+        };
+        match batch.recv(&mut socket, source_addr) {
+            Ok(()) => {}
+            Err(TracerError::IoError(err))
+                if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) =>
+            {
+                continue
+            }
+            Err(err) => {
+                warn!(%err, "shared receiver failed");
+                subscribers.lock().clear();
+                return;
+            }
+        }
+        match free {
+            Some(index) => deliver(subscribers, &pool[index]),
+            None => {
+                for subscriber in subscribers.lock().iter() {
+                    subscriber.record_dropped(overflow.metas.len());
+                }
+            }
+        }
+    }
+}
+
+/// Deliver a `batch` to every subscriber, dropping it for any subscriber whose queue is full.
+///
+/// Subscribers which have been dropped are removed.
+fn deliver(subscribers: &Mutex<Vec<Subscriber>>, batch: &Arc<RecvBatch>) {
+    subscribers
+        .lock()
+        .retain(|subscriber| match subscriber.tx.try_send(batch.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                subscriber.record_dropped(batch.metas.len());
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_receiver() -> SharedReceiver {
+        SharedReceiver {
+            source_addr: IpAddr::from([127, 0, 0, 1]),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn make_batch(packets: usize) -> Arc<RecvBatch> {
+        let mut batch = RecvBatch::new();
+        for _ in 0..packets {
+            batch.metas.push(RecvMeta {
+                len: 0,
+                addr: None,
+                recv: None,
+                ttl: None,
+            });
+        }
+        Arc::new(batch)
+    }
+
+    #[test]
+    fn test_deliver_shares_batch() {
+        let receiver = make_receiver();
+        let mut first = receiver.subscribe();
+        let mut second = receiver.subscribe();
+        let batch = make_batch(2);
+        deliver(&receiver.subscribers, &batch);
+        let first_batch = first.recv_timeout(Duration::ZERO).unwrap().unwrap();
+        let second_batch = second.recv_timeout(Duration::ZERO).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&batch, &first_batch));
+        assert!(Arc::ptr_eq(&batch, &second_batch));
+        drop((first_batch, second_batch));
+        let mut batch = batch;
+        assert!(Arc::get_mut(&mut batch).is_some());
+    }
+
+    #[test]
+    fn test_deliver_counts_dropped() {
+        let receiver = make_receiver();
+        let mut subscription = receiver.subscribe();
+        let batch = make_batch(3);
+        for _ in 0..=MAX_QUEUED_BATCHES {
+            deliver(&receiver.subscribers, &batch);
+        }
+        assert_eq!(3, subscription.dropped());
+        assert!(subscription.recv_timeout(Duration::ZERO).unwrap().is_some());
+        assert_eq!(3, subscription.reported);
+    }
+
+    #[test]
+    fn test_deliver_removes_dropped_subscriber() {
+        let receiver = make_receiver();
+        drop(receiver.subscribe());
+        deliver(&receiver.subscribers, &make_batch(1));
+        assert!(receiver.subscribers.lock().is_empty());
+    }
+}
@@ -1,6 +1,41 @@
+use crate::tracing::net::channel::{MAX_PACKET_SIZE, MAX_RECV_BATCH};
+use crate::tracing::Ipv6ExtHeader;
+use arrayvec::ArrayVec;
 use std::io::{Error, Result};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
+
+/// The metadata of a received packet.
+#[derive(Debug, Copy, Clone)]
+pub struct RecvMeta {
+    /// The number of bytes received.
+    pub len: usize,
+    /// The address the packet was received from, if known.
+    pub addr: Option<SocketAddr>,
+    /// The time the packet was received by the kernel, if available.
+    pub recv: Option<SystemTime>,
+    /// The ttl (`IPv4`) or hop limit (`IPv6`) of the packet received, if available.
+    ///
+    /// This is only set for sockets which do not receive the IP header, such as raw `IPv6` sockets and datagram
+    /// sockets, for which it is received as ancillary data.
+    pub ttl: Option<u8>,
+}
+
+/// The metadata of an `ICMP` error received for a previously sent packet.
+#[derive(Debug, Copy, Clone)]
+pub struct RecvErrMeta {
+    /// The number of bytes of the original packet received.
+    pub len: usize,
+    /// The address of the host which sent the `ICMP` error.
+    pub offender: IpAddr,
+    /// The `ICMP` type of the error.
+    pub icmp_type: u8,
+    /// The `ICMP` code of the error.
+    pub icmp_code: u8,
+    /// The additional information of the error, which is the next hop MTU of a `Fragmentation Needed` (`IPv4`) or
+    /// `Packet Too Big` (`IPv6`) error.
+    pub info: u32,
+}
 
 pub trait TracerSocket
 where
@@ -18,6 +53,10 @@ where
     fn new_recv_socket_ipv4(addr: Ipv4Addr) -> Result<Self>;
     /// Create an IPv6 socket for receiving UDP probe responses.
     fn new_recv_socket_ipv6(addr: Ipv6Addr) -> Result<Self>;
+    /// Create an unprivileged IPv4 datagram socket for sending ICMP probes and receiving responses.
+    fn new_icmp_dgram_socket_ipv4() -> Result<Self>;
+    /// Create an unprivileged IPv6 datagram socket for sending ICMP probes and receiving responses.
+    fn new_icmp_dgram_socket_ipv6() -> Result<Self>;
     /// Create a IPv4/TCP socket for sending TCP probes.
     fn new_stream_socket_ipv4() -> Result<Self>;
     /// Create a IPv6/TCP socket for sending TCP probes.
@@ -32,14 +71,52 @@ where
     fn set_reuse_port(&self, reuse: bool) -> Result<()>;
     fn set_header_included(&self, included: bool) -> Result<()>;
     fn set_unicast_hops_v6(&self, hops: u8) -> Result<()>;
+    /// Set the IPv6 traffic class (i.e. DSCP+ECN).
+    fn set_tclass_v6(&self, tclass: u32) -> Result<()>;
+    /// Set whether the IPv4 packets sent have the `Don't Fragment` bit set, or may be fragmented.
+    fn set_dont_fragment(&self, dont_fragment: bool) -> Result<()>;
+    /// Set whether the IPv6 packets sent must not be fragmented by the sender, or may be fragmented.
+    fn set_dont_fragment_v6(&self, dont_fragment: bool) -> Result<()>;
+    /// Set an `IPv6` extension header, such as a destination options header, to include in every packet sent.
+    fn set_ipv6_ext_header(&self, header: Ipv6ExtHeader) -> Result<()>;
+    /// Set the TCP maximum segment size (`MSS`) option.
+    fn set_mss(&self, mss: u32) -> Result<()>;
+    /// Set the size of the receive buffer, which determines the advertised TCP window.
+    fn set_recv_buffer_size(&self, size: usize) -> Result<()>;
     fn connect(&self, address: SocketAddr) -> Result<()>;
     fn send_to(&self, buf: &[u8], addr: SocketAddr) -> Result<()>;
+    /// Send a batch of packets, each to its address.
+    ///
+    /// The default implementation sends the packets one at a time.
+    fn send_batch(&self, packets: &[(&[u8], SocketAddr)]) -> Result<()> {
+        for &(buf, addr) in packets {
+            self.send_to(buf, addr)?;
+        }
+        Ok(())
+    }
     /// Returns true if the socket becomes readable before the timeout, false otherwise.
     fn is_readable(&self, timeout: Duration) -> Result<bool>;
     /// Returns true if the socket is currently writeable, false otherwise.
     fn is_writable(&self) -> Result<bool>;
-    fn recv_from(&mut self, buf: &mut [u8]) -> Result<(usize, Option<SocketAddr>)>;
-    fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+    /// Receive a packet along with its metadata, such as the time it was received by the kernel, if available.
+    fn recv_from(&mut self, buf: &mut [u8]) -> Result<RecvMeta>;
+    /// Receive a batch of packets, one per buffer, returning the metadata of each packet received.
+    ///
+    /// The default implementation receives a single packet.
+    fn recv_batch(
+        &mut self,
+        bufs: &mut [[u8; MAX_PACKET_SIZE]],
+    ) -> Result<ArrayVec<RecvMeta, MAX_RECV_BATCH>> {
+        let mut metas = ArrayVec::new();
+        if let Some(buf) = bufs.first_mut() {
+            metas.push(self.recv_from(buf)?);
+        }
+        Ok(metas)
+    }
+    /// Receive the original packet of the next queued `ICMP` error, if any.
+    fn recv_err(&mut self, buf: &mut [u8]) -> Result<Option<RecvErrMeta>>;
+    /// Create a new handle to the same underlying socket.
+    fn try_clone(&self) -> Result<Self>;
     fn shutdown(&self) -> Result<()>;
     fn peer_addr(&self) -> Result<Option<SocketAddr>>;
     fn take_error(&self) -> Result<Option<Error>>;
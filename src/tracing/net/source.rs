@@ -1,11 +1,12 @@
 use crate::tracing::error::TraceResult;
-use crate::tracing::error::TracerError::InvalidSourceAddr;
+use crate::tracing::error::TracerError::{InvalidSourceAddr, UnknownInterface};
 use crate::tracing::net::platform;
 use crate::tracing::net::platform::Socket;
 use crate::tracing::net::socket::TracerSocket as _;
 use crate::tracing::types::Port;
-use crate::tracing::PortDirection;
-use std::net::{IpAddr, SocketAddr};
+use crate::tracing::{Ipv6SourcePreference, PortDirection};
+use std::cmp::Ordering;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 
 /// The port used for local address discovery if not dest port is available.
 const DISCOVERY_PORT: Port = Port(80);
@@ -15,15 +16,47 @@ pub struct SourceAddr;
 
 impl SourceAddr {
     /// Discover the source `IpAddr`.
+    ///
+    /// If an `interface` is given then the source address is chosen from the addresses of that interface, otherwise
+    /// the OS chooses the source address.  `IPv6` source addresses are chosen from those of the interface following
+    /// the rules of [RFC 6724](https://www.rfc-editor.org/rfc/rfc6724#section-5), with `ipv6_preference` controlling
+    /// whether temporary or stable addresses are preferred.  If an `ipv6_preference` is given without an `interface`
+    /// then the address chosen by the OS is replaced by the preferred address of the same interface.
     pub fn discover(
         target_addr: IpAddr,
         port_direction: PortDirection,
         interface: Option<&str>,
+        ipv6_preference: Option<Ipv6SourcePreference>,
     ) -> TraceResult<IpAddr> {
         let port = port_direction.dest().unwrap_or(DISCOVERY_PORT).0;
-        match interface.as_ref() {
-            Some(interface) => lookup_interface_addr(target_addr, interface),
-            None => platform::discover_local_addr(target_addr, port),
+        match (target_addr, interface) {
+            (IpAddr::V4(_), Some(interface)) => platform::lookup_interface_addr_ipv4(interface),
+            (IpAddr::V6(dest), Some(interface)) => {
+                let candidates = platform::lookup_interface_addrs_ipv6(Some(interface))?;
+                select_ipv6_source(dest, &candidates, ipv6_preference)
+                    .map(IpAddr::V6)
+                    .ok_or_else(|| UnknownInterface(interface.to_string()))
+            }
+            (IpAddr::V6(dest), None) if ipv6_preference.is_some() => {
+                let local_addr = platform::discover_local_addr(target_addr, port)?;
+                let candidates = platform::lookup_interface_addrs_ipv6(None)?;
+                let interface_candidates = candidates
+                    .iter()
+                    .find(|candidate| IpAddr::V6(candidate.addr) == local_addr)
+                    .map(|local| {
+                        candidates
+                            .iter()
+                            .filter(|candidate| candidate.interface == local.interface)
+                            .cloned()
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                Ok(
+                    select_ipv6_source(dest, &interface_candidates, ipv6_preference)
+                        .map_or(local_addr, IpAddr::V6),
+                )
+            }
+            _ => platform::discover_local_addr(target_addr, port),
         }
     }
 
@@ -49,10 +82,149 @@ pub fn udp_socket_for_addr_family(addr: IpAddr) -> TraceResult<Socket> {
     }?)
 }
 
-/// Lookup the address for a named interface.
-fn lookup_interface_addr(addr: IpAddr, name: &str) -> TraceResult<IpAddr> {
-    match addr {
-        IpAddr::V4(_) => platform::lookup_interface_addr_ipv4(name),
-        IpAddr::V6(_) => platform::lookup_interface_addr_ipv6(name),
+/// A candidate `IPv6` source address of a network interface.
+///
+/// Tentative addresses and addresses which failed duplicate address detection are never candidates.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InterfaceAddrIpv6 {
+    /// The name of the interface.
+    pub interface: String,
+    /// The address.
+    pub addr: Ipv6Addr,
+    /// Whether this is a temporary (privacy) address.
+    pub temporary: bool,
+    /// Whether the preferred lifetime of this address has expired.
+    pub deprecated: bool,
+}
+
+/// Select the best source address for `dest` from `candidates`.
+///
+/// This implements the source address selection rules of
+/// [RFC 6724](https://www.rfc-editor.org/rfc/rfc6724#section-5) which apply to a single interface, using the default
+/// policy table.  Temporary addresses are preferred unless `preference` is `Ipv6SourcePreference::Stable`.
+fn select_ipv6_source(
+    dest: Ipv6Addr,
+    candidates: &[InterfaceAddrIpv6],
+    preference: Option<Ipv6SourcePreference>,
+) -> Option<Ipv6Addr> {
+    let prefer_temporary = preference != Some(Ipv6SourcePreference::Stable);
+    candidates
+        .iter()
+        .min_by(|a, b| compare_ipv6_source(dest, a, b, prefer_temporary))
+        .map(|candidate| candidate.addr)
+}
+
+/// Compare two candidate source addresses for `dest`, the preferred address is ordered first.
+fn compare_ipv6_source(
+    dest: Ipv6Addr,
+    a: &InterfaceAddrIpv6,
+    b: &InterfaceAddrIpv6,
+    prefer_temporary: bool,
+) -> Ordering {
+    // Rule 1: prefer the same address.
+    (b.addr == dest)
+        .cmp(&(a.addr == dest))
+        // Rule 2: prefer the appropriate scope.
+        .then_with(|| compare_scope(scope(a.addr), scope(b.addr), scope(dest)))
+        // Rule 3: avoid deprecated addresses.
+        .then_with(|| a.deprecated.cmp(&b.deprecated))
+        // Rule 6: prefer a matching label.
+        .then_with(|| (label(b.addr) == label(dest)).cmp(&(label(a.addr) == label(dest))))
+        // Rule 7: prefer temporary, or stable, addresses.
+        .then_with(|| {
+            if prefer_temporary {
+                b.temporary.cmp(&a.temporary)
+            } else {
+                a.temporary.cmp(&b.temporary)
+            }
+        })
+        // Rule 8: use the longest matching prefix.
+        .then_with(|| common_prefix_len(b.addr, dest).cmp(&common_prefix_len(a.addr, dest)))
+}
+
+/// Prefer the smallest scope which is not smaller than the scope of the destination.
+fn compare_scope(a: u8, b: u8, dest: u8) -> Ordering {
+    match a.cmp(&b) {
+        Ordering::Less if a < dest => Ordering::Greater,
+        Ordering::Greater if b < dest => Ordering::Less,
+        ordering => ordering,
+    }
+}
+
+/// The scope of an `IPv6` address as defined in RFC 4291.
+fn scope(addr: Ipv6Addr) -> u8 {
+    let first = addr.segments()[0];
+    if addr.is_multicast() {
+        addr.octets()[1] & 0x0f
+    } else if addr.is_loopback() || first & 0xffc0 == 0xfe80 {
+        0x2
+    } else if first & 0xffc0 == 0xfec0 {
+        0x5
+    } else {
+        0xe
+    }
+}
+
+/// The label of an `IPv6` address in the default policy table of RFC 6724.
+fn label(addr: Ipv6Addr) -> u8 {
+    match addr.segments() {
+        _ if addr.is_loopback() => 0,
+        [0, 0, 0, 0, 0, 0xffff, _, _] => 4,
+        [0, 0, 0, 0, 0, 0, _, _] => 3,
+        [0x2001, 0, ..] => 5,
+        [0x2002, ..] => 2,
+        [0x3ffe, ..] => 12,
+        [first, ..] if first & 0xffc0 == 0xfec0 => 11,
+        [first, ..] if first & 0xfe00 == 0xfc00 => 13,
+        _ => 1,
+    }
+}
+
+/// The number of leading bits two addresses have in common.
+fn common_prefix_len(a: Ipv6Addr, b: Ipv6Addr) -> u32 {
+    (u128::from(a) ^ u128::from(b)).leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn candidate(addr: &str, temporary: bool, deprecated: bool) -> InterfaceAddrIpv6 {
+        InterfaceAddrIpv6 {
+            interface: String::from("eth0"),
+            addr: Ipv6Addr::from_str(addr).unwrap(),
+            temporary,
+            deprecated,
+        }
+    }
+
+    #[test_case("2001:db8::1", &[], None, None; "no candidates")]
+    #[test_case("2001:db8::1", &[("2001:db8::10", false, false), ("2001:db8::1", false, true)], None, Some("2001:db8::1"); "same address")]
+    #[test_case("2001:db8::1", &[("fe80::10", false, false), ("2001:db8::10", false, false)], None, Some("2001:db8::10"); "global scope")]
+    #[test_case("fe80::1", &[("2001:db8::10", false, false), ("fe80::10", false, false)], None, Some("fe80::10"); "link local scope")]
+    #[test_case("2001:db8::1", &[("2001:db8::10", false, true), ("2001:db9::10", false, false)], None, Some("2001:db9::10"); "avoid deprecated")]
+    #[test_case("fd00::1", &[("2001:db8::10", false, false), ("fd01::10", false, false)], None, Some("fd01::10"); "matching label")]
+    #[test_case("2001:db8::1", &[("2001:db8::10", false, false), ("2001:db9::10", true, false)], None, Some("2001:db9::10"); "default temporary")]
+    #[test_case("2001:db8::1", &[("2001:db8::10", false, false), ("2001:db9::10", true, false)], Some(Ipv6SourcePreference::Temporary), Some("2001:db9::10"); "prefer temporary")]
+    #[test_case("2001:db8::1", &[("2001:db9::10", true, false), ("2001:db8::10", false, false)], Some(Ipv6SourcePreference::Stable), Some("2001:db8::10"); "prefer stable")]
+    #[test_case("2001:db8:1::1", &[("2001:db8:2::10", false, false), ("2001:db8:1::10", false, false)], None, Some("2001:db8:1::10"); "longest prefix")]
+    fn test_select_ipv6_source(
+        dest: &str,
+        candidates: &[(&str, bool, bool)],
+        preference: Option<Ipv6SourcePreference>,
+        expected: Option<&str>,
+    ) {
+        let candidates = candidates
+            .iter()
+            .map(|&(addr, temporary, deprecated)| candidate(addr, temporary, deprecated))
+            .collect::<Vec<_>>();
+        let selected =
+            select_ipv6_source(Ipv6Addr::from_str(dest).unwrap(), &candidates, preference);
+        assert_eq!(
+            expected.map(|addr| Ipv6Addr::from_str(addr).unwrap()),
+            selected
+        );
     }
 }
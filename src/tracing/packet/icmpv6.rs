@@ -7,6 +7,7 @@ pub enum IcmpType {
     EchoRequest,
     EchoReply,
     DestinationUnreachable,
+    PacketTooBig,
     TimeExceeded,
     Other(u8),
 }
@@ -18,6 +19,7 @@ impl IcmpType {
             Self::EchoRequest => 128,
             Self::EchoReply => 129,
             Self::DestinationUnreachable => 1,
+            Self::PacketTooBig => 2,
             Self::TimeExceeded => 3,
             Self::Other(id) => *id,
         }
@@ -30,6 +32,7 @@ impl From<u8> for IcmpType {
             128 => Self::EchoRequest,
             129 => Self::EchoReply,
             1 => Self::DestinationUnreachable,
+            2 => Self::PacketTooBig,
             3 => Self::TimeExceeded,
             id => Self::Other(id),
         }
@@ -145,6 +148,9 @@ mod tests {
         packet.set_icmp_type(IcmpType::DestinationUnreachable);
         assert_eq!(IcmpType::DestinationUnreachable, packet.get_icmp_type());
         assert_eq!([0x01], packet.packet()[0..1]);
+        packet.set_icmp_type(IcmpType::PacketTooBig);
+        assert_eq!(IcmpType::PacketTooBig, packet.get_icmp_type());
+        assert_eq!([0x02], packet.packet()[0..1]);
         packet.set_icmp_type(IcmpType::TimeExceeded);
         assert_eq!(IcmpType::TimeExceeded, packet.get_icmp_type());
         assert_eq!([0x03], packet.packet()[0..1]);
@@ -981,3 +987,170 @@ pub mod destination_unreachable {
         }
     }
 }
+
+pub mod packet_too_big {
+    use crate::tracing::packet::buffer::Buffer;
+    use crate::tracing::packet::fmt_payload;
+    use crate::tracing::packet::icmpv6::{IcmpCode, IcmpType};
+    use std::fmt::{Debug, Formatter};
+
+    const TYPE_OFFSET: usize = 0;
+    const CODE_OFFSET: usize = 1;
+    const CHECKSUM_OFFSET: usize = 2;
+    const MTU_OFFSET: usize = 4;
+
+    /// Represents an ICMP `PacketTooBig` packet.
+    ///
+    /// The internal representation is held in network byte order (big-endian) and all accessor methods take and return
+    /// data in host byte order, converting as necessary for the given architecture.
+    pub struct PacketTooBigPacket<'a> {
+        buf: Buffer<'a>,
+    }
+
+    impl<'a> PacketTooBigPacket<'a> {
+        pub fn new(packet: &'a mut [u8]) -> Option<PacketTooBigPacket<'_>> {
+            if packet.len() >= Self::minimum_packet_size() {
+                Some(Self {
+                    buf: Buffer::Mutable(packet),
+                })
+            } else {
+                None
+            }
+        }
+
+        #[must_use]
+        pub fn new_view(packet: &'a [u8]) -> Option<PacketTooBigPacket<'_>> {
+            if packet.len() >= Self::minimum_packet_size() {
+                Some(Self {
+                    buf: Buffer::Immutable(packet),
+                })
+            } else {
+                None
+            }
+        }
+
+        #[must_use]
+        pub const fn minimum_packet_size() -> usize {
+            8
+        }
+
+        #[must_use]
+        pub fn get_icmp_type(&self) -> IcmpType {
+            IcmpType::from(self.buf.read(TYPE_OFFSET))
+        }
+
+        #[must_use]
+        pub fn get_icmp_code(&self) -> IcmpCode {
+            IcmpCode::from(self.buf.read(CODE_OFFSET))
+        }
+
+        #[must_use]
+        pub fn get_checksum(&self) -> u16 {
+            u16::from_be_bytes(self.buf.get_bytes(CHECKSUM_OFFSET))
+        }
+
+        #[must_use]
+        pub fn get_mtu(&self) -> u32 {
+            u32::from_be_bytes(self.buf.get_bytes(MTU_OFFSET))
+        }
+
+        pub fn set_icmp_type(&mut self, val: IcmpType) {
+            *self.buf.write(TYPE_OFFSET) = val.id();
+        }
+
+        pub fn set_icmp_code(&mut self, val: IcmpCode) {
+            *self.buf.write(CODE_OFFSET) = val.0;
+        }
+
+        pub fn set_checksum(&mut self, val: u16) {
+            self.buf.set_bytes(CHECKSUM_OFFSET, val.to_be_bytes());
+        }
+
+        pub fn set_mtu(&mut self, val: u32) {
+            self.buf.set_bytes(MTU_OFFSET, val.to_be_bytes());
+        }
+
+        pub fn set_payload(&mut self, vals: &[u8]) {
+            let current_offset = Self::minimum_packet_size();
+            self.buf.as_slice_mut()[current_offset..current_offset + vals.len()]
+                .copy_from_slice(vals);
+        }
+
+        #[must_use]
+        pub fn packet(&self) -> &[u8] {
+            self.buf.as_slice()
+        }
+
+        #[must_use]
+        pub fn payload(&self) -> &[u8] {
+            &self.buf.as_slice()[Self::minimum_packet_size()..]
+        }
+    }
+
+    impl Debug for PacketTooBigPacket<'_> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("PacketTooBigPacket")
+                .field("icmp_type", &self.get_icmp_type())
+                .field("icmp_code", &self.get_icmp_code())
+                .field("checksum", &self.get_checksum())
+                .field("mtu", &self.get_mtu())
+                .field("payload", &fmt_payload(self.payload()))
+                .finish()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_icmp_type() {
+            let mut buf = [0_u8; PacketTooBigPacket::minimum_packet_size()];
+            let mut packet = PacketTooBigPacket::new(&mut buf).unwrap();
+            packet.set_icmp_type(IcmpType::PacketTooBig);
+            assert_eq!(IcmpType::PacketTooBig, packet.get_icmp_type());
+            assert_eq!([0x02], packet.packet()[0..1]);
+            packet.set_icmp_type(IcmpType::Other(255));
+            assert_eq!(IcmpType::Other(255), packet.get_icmp_type());
+            assert_eq!([0xFF], packet.packet()[0..1]);
+        }
+
+        #[test]
+        fn test_checksum() {
+            let mut buf = [0_u8; PacketTooBigPacket::minimum_packet_size()];
+            let mut packet = PacketTooBigPacket::new(&mut buf).unwrap();
+            packet.set_checksum(0);
+            assert_eq!(0, packet.get_checksum());
+            assert_eq!([0x00, 0x00], packet.packet()[2..=3]);
+            packet.set_checksum(u16::MAX);
+            assert_eq!(u16::MAX, packet.get_checksum());
+            assert_eq!([0xFF, 0xFF], packet.packet()[2..=3]);
+        }
+
+        #[test]
+        fn test_mtu() {
+            let mut buf = [0_u8; PacketTooBigPacket::minimum_packet_size()];
+            let mut packet = PacketTooBigPacket::new(&mut buf).unwrap();
+            packet.set_mtu(0);
+            assert_eq!(0, packet.get_mtu());
+            assert_eq!([0x00, 0x00, 0x00, 0x00], packet.packet()[4..=7]);
+            packet.set_mtu(1280);
+            assert_eq!(1280, packet.get_mtu());
+            assert_eq!([0x00, 0x00, 0x05, 0x00], packet.packet()[4..=7]);
+            packet.set_mtu(u32::MAX);
+            assert_eq!(u32::MAX, packet.get_mtu());
+            assert_eq!([0xFF, 0xFF, 0xFF, 0xFF], packet.packet()[4..=7]);
+        }
+
+        #[test]
+        fn test_view() {
+            let buf = [0x02, 0x00, 0xdf, 0xdc, 0x00, 0x00, 0x05, 0x00];
+            let packet = PacketTooBigPacket::new_view(&buf).unwrap();
+            assert_eq!(IcmpType::PacketTooBig, packet.get_icmp_type());
+            assert_eq!(IcmpCode(0), packet.get_icmp_code());
+            assert_eq!(57308, packet.get_checksum());
+            assert_eq!(1280, packet.get_mtu());
+            assert!(packet.payload().is_empty());
+        }
+    }
+}
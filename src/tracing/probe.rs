@@ -1,5 +1,5 @@
-use crate::tracing::types::{Port, Round, Sequence, TimeToLive, TraceId};
-use std::net::IpAddr;
+use crate::tracing::types::{Port, Round, Sequence, TimeToLive, TraceId, TypeOfService};
+use std::net::{IpAddr, Ipv4Addr};
 use std::time::{Duration, SystemTime};
 
 /// The state of an ICMP echo request/response
@@ -27,6 +27,34 @@ pub struct Probe {
     pub received: Option<SystemTime>,
     /// The type of ICMP response packet received for the probe.
     pub icmp_packet_type: Option<IcmpPacketType>,
+    /// The TOS (i.e. DSCP+ECN) of the probe as it was received by the responding host.
+    ///
+    /// This is read from the original datagram quoted in `TimeExceeded` and `DestinationUnreachable` responses and
+    /// is therefore not available for other responses.
+    pub received_tos: Option<TypeOfService>,
+    /// The time-to-live (IPv4 only) of the response to the probe as received by this host.
+    pub reply_ttl: Option<TimeToLive>,
+    /// The code of the ICMP response packet received for the probe.
+    ///
+    /// The meaning of the code depends on the `icmp_packet_type` and the address family of the `host`.
+    pub icmp_code: Option<u8>,
+    /// The outcome of the TCP handshake with the target (TCP only).
+    pub tcp_handshake: Option<TcpHandshake>,
+    /// Whether the original datagram quoted in the response differs from the probe sent.
+    pub quote_corrupted: bool,
+    /// The addresses recorded by the `IPv4` `Record Route` option of the probe, if it was set.
+    ///
+    /// This is read from the original datagram quoted in `TimeExceeded` and `DestinationUnreachable` responses and from
+    /// the `EchoReply` itself, which echoes the option.
+    pub recorded_route: RecordedRoute,
+    /// The timestamps of the `TimestampReply` received for the probe, if it was an `ICMP` `TimestampRequest`.
+    pub icmp_timestamps: Option<IcmpTimestamps>,
+    /// The next hop MTU reported by a `Fragmentation Needed` (`IPv4`) or `Packet Too Big` (`IPv6`) response.
+    pub next_hop_mtu: Option<u16>,
+    /// The number of duplicate responses received for the probe.
+    pub duplicates: u16,
+    /// The number of times the probe for this TTL has been retransmitted in the round, zero for the original probe.
+    pub retry: u8,
 }
 
 impl Probe {
@@ -52,6 +80,16 @@ impl Probe {
             host: None,
             received: None,
             icmp_packet_type: None,
+            received_tos: None,
+            reply_ttl: None,
+            icmp_code: None,
+            tcp_handshake: None,
+            quote_corrupted: false,
+            recorded_route: RecordedRoute::EMPTY,
+            icmp_timestamps: None,
+            next_hop_mtu: None,
+            duplicates: 0,
+            retry: 0,
         }
     }
 
@@ -93,6 +131,34 @@ impl Probe {
             ..self
         }
     }
+
+    #[must_use]
+    pub const fn with_retry(self, retry: u8) -> Self {
+        Self { retry, ..self }
+    }
+
+    #[must_use]
+    pub const fn with_duplicate(self) -> Self {
+        Self {
+            duplicates: self.duplicates.saturating_add(1),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub const fn with_response_details(self, details: ProbeResponseDetails) -> Self {
+        Self {
+            received_tos: details.tos,
+            reply_ttl: details.ttl,
+            icmp_code: details.icmp_code,
+            tcp_handshake: details.tcp_handshake,
+            quote_corrupted: details.quote_corrupted,
+            recorded_route: details.recorded_route,
+            icmp_timestamps: details.icmp_timestamps,
+            next_hop_mtu: details.next_hop_mtu,
+            ..self
+        }
+    }
 }
 
 /// The status of a `Echo` for a single TTL.
@@ -126,6 +192,18 @@ pub enum IcmpPacketType {
     NotApplicable,
 }
 
+/// The outcome of the TCP handshake with the target.
+///
+/// The handshake of a probe which reaches the target is either completed (and then immediately closed) by the
+/// operating system if the target responds with `SYN+ACK`, or aborted if the target responds with `RST`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpHandshake {
+    /// The target responded with `SYN+ACK` and the connection was established.
+    Established,
+    /// The target responded with `RST` and the connection was refused.
+    Refused,
+}
+
 /// The response to a probe.
 #[derive(Debug, Copy, Clone)]
 pub enum ProbeResponse {
@@ -145,6 +223,8 @@ pub struct ProbeResponseData {
     pub addr: IpAddr,
     /// Information about the sequence number of the probe response.
     pub resp_seq: ProbeResponseSeq,
+    /// Additional details of the probe response.
+    pub details: ProbeResponseDetails,
 }
 
 impl ProbeResponseData {
@@ -153,7 +233,148 @@ impl ProbeResponseData {
             recv,
             addr,
             resp_seq,
+            details: ProbeResponseDetails::default(),
+        }
+    }
+
+    pub fn with_tos(mut self, tos: TypeOfService) -> Self {
+        self.details.tos = Some(tos);
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: TimeToLive) -> Self {
+        self.details.ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_icmp_code(mut self, icmp_code: u8) -> Self {
+        self.details.icmp_code = Some(icmp_code);
+        self
+    }
+
+    pub fn with_tcp_handshake(mut self, tcp_handshake: TcpHandshake) -> Self {
+        self.details.tcp_handshake = Some(tcp_handshake);
+        self
+    }
+
+    pub fn with_quote_corrupted(mut self, quote_corrupted: bool) -> Self {
+        self.details.quote_corrupted = quote_corrupted;
+        self
+    }
+
+    pub fn with_recorded_route(mut self, recorded_route: RecordedRoute) -> Self {
+        self.details.recorded_route = recorded_route;
+        self
+    }
+
+    pub fn with_icmp_timestamps(mut self, icmp_timestamps: IcmpTimestamps) -> Self {
+        self.details.icmp_timestamps = Some(icmp_timestamps);
+        self
+    }
+
+    pub fn with_next_hop_mtu(mut self, next_hop_mtu: u16) -> Self {
+        self.details.next_hop_mtu = Some(next_hop_mtu);
+        self
+    }
+}
+
+/// Details of a probe response which are recorded against the `Probe`, where available.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ProbeResponseDetails {
+    /// The TOS of the original probe datagram quoted in the response.
+    pub tos: Option<TypeOfService>,
+    /// The time-to-live of the probe response.
+    pub ttl: Option<TimeToLive>,
+    /// The ICMP code of the probe response.
+    pub icmp_code: Option<u8>,
+    /// The outcome of the TCP handshake of the probe response.
+    pub tcp_handshake: Option<TcpHandshake>,
+    /// Whether the original probe datagram quoted in the response is corrupted.
+    pub quote_corrupted: bool,
+    /// The addresses recorded by the `IPv4` `Record Route` option of the probe.
+    pub recorded_route: RecordedRoute,
+    /// The timestamps of an `ICMP` `TimestampReply` probe response.
+    pub icmp_timestamps: Option<IcmpTimestamps>,
+    /// The next hop MTU of a `Fragmentation Needed` (`IPv4`) or `Packet Too Big` (`IPv6`) probe response.
+    pub next_hop_mtu: Option<u16>,
+}
+
+/// The addresses recorded by the `IPv4` `Record Route` option, in the order they were recorded.
+///
+/// The option has room for at most 9 addresses, each of which is the address of the interface a hop forwarded the
+/// packet from, and so reveals addresses of the first 9 hops which may not otherwise be seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedRoute {
+    addrs: [Ipv4Addr; Self::MAX_ADDRS],
+    len: u8,
+}
+
+impl RecordedRoute {
+    /// The maximum number of addresses the `Record Route` option can record.
+    pub const MAX_ADDRS: usize = 9;
+
+    /// A recorded route without any addresses.
+    pub const EMPTY: Self = Self {
+        addrs: [Ipv4Addr::UNSPECIFIED; Self::MAX_ADDRS],
+        len: 0,
+    };
+
+    /// Create a recorded route from the first `MAX_ADDRS` of the `addrs`.
+    #[must_use]
+    pub fn new(addrs: impl IntoIterator<Item = Ipv4Addr>) -> Self {
+        let mut route = Self::EMPTY;
+        for (slot, addr) in route.addrs.iter_mut().zip(addrs) {
+            *slot = addr;
+            route.len += 1;
         }
+        route
+    }
+
+    /// The recorded addresses.
+    #[must_use]
+    pub fn addrs(&self) -> &[Ipv4Addr] {
+        &self.addrs[..usize::from(self.len)]
+    }
+}
+
+impl Default for RecordedRoute {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// The timestamps of an `ICMP` `TimestampReply`, each the number of milliseconds since midnight UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IcmpTimestamps {
+    /// When the `TimestampRequest` was sent, as set by this host.
+    pub originate: u32,
+    /// When the `TimestampRequest` was received by the responding host.
+    pub receive: u32,
+    /// When the `TimestampReply` was sent by the responding host.
+    pub transmit: u32,
+}
+
+impl IcmpTimestamps {
+    /// The number of milliseconds in a day, after which timestamps wrap to zero.
+    pub const MILLIS_PER_DAY: u32 = 86_400_000;
+
+    /// The timestamp of `time`, as the number of milliseconds since midnight UTC.
+    #[must_use]
+    pub fn timestamp(time: SystemTime) -> u32 {
+        let millis = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        (millis % u128::from(Self::MILLIS_PER_DAY)) as u32
+    }
+
+    /// Whether the `receive` and `transmit` timestamps are standard timestamps.
+    ///
+    /// A host which cannot provide a timestamp in milliseconds since midnight UTC sets the high-order bit to indicate
+    /// a non-standard value.
+    #[must_use]
+    pub fn is_standard(&self) -> bool {
+        self.receive < Self::MILLIS_PER_DAY && self.transmit < Self::MILLIS_PER_DAY
     }
 }
 
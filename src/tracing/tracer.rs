@@ -1,34 +1,49 @@
 use self::state::TracerState;
 use crate::tracing::error::{TraceResult, TracerError};
-use crate::tracing::net::Network;
+use crate::tracing::net::{ChecksumStats, Network};
 use crate::tracing::probe::{
     ProbeResponse, ProbeResponseData, ProbeResponseSeq, ProbeResponseSeqIcmp, ProbeResponseSeqTcp,
     ProbeResponseSeqUdp,
 };
 use crate::tracing::types::{Sequence, TimeToLive, TraceId};
-use crate::tracing::{MultipathStrategy, PortDirection, TracerProtocol};
+use crate::tracing::{IcmpPacketType, MultipathStrategy, PortDirection, TracerProtocol};
 use crate::tracing::{Probe, TracerConfig};
 use std::net::IpAddr;
-use std::time::{Duration, SystemTime};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, trace, warn};
 
 /// The output from a round of tracing.
 #[derive(Debug, Clone)]
 pub struct TracerRound<'a> {
     /// The state of all `Probe` that were sent in the round.
     pub probes: &'a [Probe],
+    /// The `Probe` of the previous round for which a response was received after that round had completed.
+    pub late_probes: &'a [Probe],
     /// The largest time-to-live (ttl) for which we received a reply in the round.
     pub largest_ttl: TimeToLive,
     /// Indicates what triggered the completion of the tracing round.
     pub reason: CompletionReason,
+    /// The checksum validation statistics of all responses received so far.
+    pub checksum_stats: ChecksumStats,
 }
 
 impl<'a> TracerRound<'a> {
     #[must_use]
-    pub fn new(probes: &'a [Probe], largest_ttl: TimeToLive, reason: CompletionReason) -> Self {
+    pub fn new(
+        probes: &'a [Probe],
+        late_probes: &'a [Probe],
+        largest_ttl: TimeToLive,
+        reason: CompletionReason,
+        checksum_stats: ChecksumStats,
+    ) -> Self {
         Self {
             probes,
+            late_probes,
             largest_ttl,
             reason,
+            checksum_stats,
         }
     }
 }
@@ -47,6 +62,7 @@ pub enum CompletionReason {
 pub struct Tracer<F> {
     config: TracerConfig,
     publish: F,
+    stop: Option<Arc<AtomicBool>>,
 }
 
 impl<F: Fn(&TracerRound<'_>)> Tracer<F> {
@@ -54,63 +70,135 @@ impl<F: Fn(&TracerRound<'_>)> Tracer<F> {
         Self {
             config: *config,
             publish,
+            stop: None,
+        }
+    }
+
+    /// Stop tracing once the `stop` flag is set, rather than only after the maximum number of rounds.
+    #[must_use]
+    pub fn with_stop(self, stop: Arc<AtomicBool>) -> Self {
+        Self {
+            stop: Some(stop),
+            ..self
         }
     }
 
     /// Run a continuous trace and publish results.
     pub fn trace<N: Network>(self, mut network: N) -> TraceResult<()> {
+        debug!(
+            target_addr = %self.config.target_addr,
+            protocol = ?self.config.protocol,
+            "starting trace"
+        );
         let mut state = TracerState::new(self.config);
-        while !state.finished(self.config.max_rounds) {
+        while !state.finished(self.config.max_rounds) && !self.is_stopped() {
+            self.send_retry(&mut network, &mut state)?;
             self.send_request(&mut network, &mut state)?;
+            network.flush()?;
             self.recv_response(&mut network, &mut state)?;
-            self.update_round(&mut state);
+            self.update_round(&mut state, network.checksum_stats());
         }
         Ok(())
     }
 
+    /// Has the `stop` flag, if any, been set?
+    fn is_stopped(&self) -> bool {
+        self.stop
+            .as_ref()
+            .is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
     /// Send the next probe if required.
     ///
-    /// Send a `Probe` for the next time-to-live (ttl) if all of the following are true:
+    /// In burst mode every `Probe` which may be sent is sent back-to-back, rather than one at a time interleaved with
+    /// reading responses, such as to minimize the time skew between the probes of the hops within a round.
+    fn send_request<N: Network>(&self, network: &mut N, st: &mut TracerState) -> TraceResult<()> {
+        while self.can_send_probe(st) {
+            self.send_probe(network, st)?;
+            if !self.config.burst {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Can a `Probe` be sent for the next time-to-live (ttl)?
+    ///
+    /// A `Probe` may be sent if all of the following are true:
     ///
     /// 1 - the target host has not been found
-    /// 2 - the next ttl is not greater than the maximum allowed ttl
+    /// 2 - the next ttl is not greater than the maximum allowed ttl, or the adaptive ttl ceiling if lower
     /// 3 - if the target ttl of the target is known:
     ///       - the next ttl is not greater than the ttl of the target host observed from the prior round
     ///     otherwise:
-    ///       - the number of unknown-in-flight probes is lower than the maximum allowed
-    fn send_request<N: Network>(&self, network: &mut N, st: &mut TracerState) -> TraceResult<()> {
+    ///       - the number of unknown-in-flight probes is lower than the in-flight window, which is the maximum
+    ///         allowed unless adaptive in-flight is enabled
+    fn can_send_probe(&self, st: &TracerState) -> bool {
         let can_send_ttl = if let Some(target_ttl) = st.target_ttl() {
             st.ttl() <= target_ttl
         } else {
             st.ttl() - st.max_received_ttl().unwrap_or_default()
-                < TimeToLive(self.config.max_inflight.0)
+                < TimeToLive(st.inflight_window().0)
         };
-        if !st.target_found() && st.ttl() <= self.config.max_ttl && can_send_ttl {
-            match self.config.protocol {
-                TracerProtocol::Icmp => {
-                    network.send_probe(st.next_probe())?;
-                }
-                TracerProtocol::Udp => network.send_probe(st.next_probe())?,
-                TracerProtocol::Tcp => {
-                    let mut probe = if st.round_has_capacity() {
-                        st.next_probe()
-                    } else {
-                        return Err(TracerError::InsufficientCapacity);
-                    };
-                    while let Err(err) = network.send_probe(probe) {
-                        match err {
-                            TracerError::AddressNotAvailable(_) => {
-                                if st.round_has_capacity() {
-                                    probe = st.reissue_probe();
-                                } else {
-                                    return Err(TracerError::InsufficientCapacity);
-                                }
+        let max_ttl = st.ttl_ceiling().map_or(self.config.max_ttl, |ceiling| {
+            ceiling.min(self.config.max_ttl)
+        });
+        !st.target_found() && st.ttl() <= max_ttl && can_send_ttl
+    }
+
+    /// Send a `Probe` for the next time-to-live (ttl).
+    fn send_probe<N: Network>(&self, network: &mut N, st: &mut TracerState) -> TraceResult<()> {
+        match self.config.protocol {
+            TracerProtocol::Icmp => {
+                network.send_probe(st.next_probe())?;
+            }
+            TracerProtocol::Udp => network.send_probe(st.next_probe())?,
+            TracerProtocol::Tcp => {
+                let mut probe = if st.round_has_capacity() {
+                    st.next_probe()
+                } else {
+                    return Err(TracerError::InsufficientCapacity);
+                };
+                while let Err(err) = network.send_probe(probe) {
+                    match err {
+                        TracerError::AddressNotAvailable(_) => {
+                            warn!(
+                                sequence = probe.sequence.0,
+                                "address not available, reissuing probe"
+                            );
+                            if st.round_has_capacity() {
+                                probe = st.reissue_probe();
+                            } else {
+                                return Err(TracerError::InsufficientCapacity);
                             }
-                            other => return Err(other),
                         }
+                        other => return Err(other),
                     }
                 }
-            };
+            }
+        };
+        Ok(())
+    }
+
+    /// Resend a `Probe` for a time-to-live (ttl) which has not been responded to within the probe timeout, if required.
+    ///
+    /// See `TracerState::next_retry` for which probes are retried.  A retry which cannot be sent as no address is
+    /// available (TCP only) is left awaited such that it may be retried again.
+    fn send_retry<N: Network>(&self, network: &mut N, st: &mut TracerState) -> TraceResult<()> {
+        if self.config.retries == 0 || !st.round_has_capacity() {
+            return Ok(());
+        }
+        if let Some(probe) = st.next_retry(SystemTime::now()) {
+            let probe = st.retry_probe(probe);
+            match network.send_probe(probe) {
+                Err(TracerError::AddressNotAvailable(_)) => {
+                    warn!(
+                        sequence = probe.sequence.0,
+                        "address not available for retry"
+                    );
+                }
+                result => result?,
+            }
         }
         Ok(())
     }
@@ -131,32 +219,81 @@ impl<F: Fn(&TracerRound<'_>)> Tracer<F> {
     /// When we process an `EchoReply` from the target host we extract the time-to-live from the corresponding
     /// original `EchoRequest`.  Note that this may not be the greatest time-to-live that was sent in the round as
     /// the algorithm will send `EchoRequest` with larger time-to-live values before the `EchoReply` is received.
+    ///
+    /// A response which is received for a `Probe` of the previous round, after that round has completed, is recorded
+    /// as a late response to be published with the current round.
     fn recv_response<N: Network>(&self, network: &mut N, st: &mut TracerState) -> TraceResult<()> {
         let next = network.recv_probe()?;
         match next {
             Some(ProbeResponse::TimeExceeded(data)) => {
                 let (trace_id, sequence, received, host) = self.extract(&data);
                 let is_target = host == self.config.target_addr;
-                if self.check_trace_id(trace_id) && st.in_round(sequence) {
-                    st.complete_probe_time_exceeded(sequence, host, received, is_target);
+                if self.check_trace_id(trace_id) && self.check_ports(&data) {
+                    if st.in_round(sequence) {
+                        st.complete_probe_time_exceeded(
+                            sequence,
+                            host,
+                            received,
+                            data.details,
+                            is_target,
+                        );
+                    } else {
+                        st.complete_late_probe(
+                            sequence,
+                            IcmpPacketType::TimeExceeded,
+                            host,
+                            received,
+                            data.details,
+                        );
+                    }
                 }
             }
             Some(ProbeResponse::DestinationUnreachable(data)) => {
                 let (trace_id, sequence, received, host) = self.extract(&data);
-                if self.check_trace_id(trace_id) && st.in_round(sequence) {
-                    st.complete_probe_unreachable(sequence, host, received);
+                if self.check_trace_id(trace_id) && self.check_ports(&data) {
+                    if st.in_round(sequence) {
+                        st.complete_probe_unreachable(sequence, host, received, data.details);
+                    } else {
+                        st.complete_late_probe(
+                            sequence,
+                            IcmpPacketType::Unreachable,
+                            host,
+                            received,
+                            data.details,
+                        );
+                    }
                 }
             }
             Some(ProbeResponse::EchoReply(data)) => {
                 let (trace_id, sequence, received, host) = self.extract(&data);
-                if self.check_trace_id(trace_id) && st.in_round(sequence) {
-                    st.complete_probe_echo_reply(sequence, host, received);
+                if self.check_trace_id(trace_id) && self.check_ports(&data) {
+                    if st.in_round(sequence) {
+                        st.complete_probe_echo_reply(sequence, host, received, data.details);
+                    } else {
+                        st.complete_late_probe(
+                            sequence,
+                            IcmpPacketType::EchoReply,
+                            host,
+                            received,
+                            data.details,
+                        );
+                    }
                 }
             }
             Some(ProbeResponse::TcpReply(data) | ProbeResponse::TcpRefused(data)) => {
                 let (trace_id, sequence, received, host) = self.extract(&data);
-                if self.check_trace_id(trace_id) && st.in_round(sequence) {
-                    st.complete_probe_other(sequence, host, received);
+                if self.check_trace_id(trace_id) && self.check_ports(&data) {
+                    if st.in_round(sequence) {
+                        st.complete_probe_other(sequence, host, received, data.details);
+                    } else {
+                        st.complete_late_probe(
+                            sequence,
+                            IcmpPacketType::NotApplicable,
+                            host,
+                            received,
+                            data.details,
+                        );
+                    }
                 }
             }
             None => {}
@@ -173,16 +310,27 @@ impl<F: Fn(&TracerRound<'_>)> Tracer<F> {
     /// 3 - either:
     ///     A - the target has been found OR
     ///     B - the target has not been found and the round has exceeded the maximum round duration
-    fn update_round(&self, st: &mut TracerState) {
+    fn update_round(&self, st: &mut TracerState, checksum_stats: ChecksumStats) {
         let now = SystemTime::now();
-        let round_duration = now.duration_since(st.round_start()).unwrap_or_default();
-        let round_min = round_duration > self.config.min_round_duration;
+        let round_duration = Instant::now().saturating_duration_since(st.round_start());
+        let round_min = round_duration > st.min_round_duration();
         let grace_exceeded = exceeds(st.received_time(), now, self.config.grace_duration);
-        let round_max = round_duration > self.config.max_round_duration;
+        let round_max = round_duration > st.max_round_duration();
         let target_found = st.target_found();
         if round_min && grace_exceeded && target_found || round_max {
-            self.publish_trace(st);
-            st.advance_round(self.config.first_ttl);
+            self.publish_trace(st, checksum_stats);
+            st.advance_round(self.next_round_first_ttl(st));
+        }
+    }
+
+    /// The `ttl` of the first `Probe` of the next round.
+    ///
+    /// When tracing the destination only, the rounds which follow the discovery of the target start from (and so only
+    /// probe) the `ttl` of the target.
+    fn next_round_first_ttl(&self, st: &TracerState) -> TimeToLive {
+        match st.target_ttl() {
+            Some(target_ttl) if self.config.destination_only => target_ttl,
+            _ => self.config.first_ttl,
         }
     }
 
@@ -190,7 +338,7 @@ impl<F: Fn(&TracerRound<'_>)> Tracer<F> {
     ///
     /// If the round completed without receiving an `EchoReply` from the target host then we also publish the next
     /// `Probe` which is assumed to represent the TTL of the target host.
-    fn publish_trace(&self, state: &TracerState) {
+    fn publish_trace(&self, state: &TracerState, checksum_stats: ChecksumStats) {
         let max_received_ttl = if let Some(target_ttl) = state.target_ttl() {
             target_ttl
         } else {
@@ -208,14 +356,57 @@ impl<F: Fn(&TracerRound<'_>)> Tracer<F> {
         } else {
             CompletionReason::RoundTimeLimitExceeded
         };
-        (self.publish)(&TracerRound::new(probes, largest_ttl, reason));
+        debug!(largest_ttl = largest_ttl.0, ?reason, "round complete");
+        (self.publish)(&TracerRound::new(
+            probes,
+            state.late_probes(),
+            largest_ttl,
+            reason,
+            checksum_stats,
+        ));
     }
 
     /// Check if the `TraceId` matches the expected value for this tracer.
     ///
     /// A special value of `0` is accepted for `udp` and `tcp` which do not have an identifier.
     fn check_trace_id(&self, trace_id: TraceId) -> bool {
-        self.config.trace_identifier == trace_id || trace_id == TraceId(0)
+        let valid = self.config.trace_identifier == trace_id || trace_id == TraceId(0);
+        if !valid {
+            trace!(
+                trace_id = trace_id.0,
+                "ignoring response for another tracer"
+            );
+        }
+        valid
+    }
+
+    /// Check if the fixed ports of a `udp` or `tcp` response match the expected values for this tracer.
+    ///
+    /// Tracers to different targets may be in flight with the same sequence numbers at the same time and so the fixed
+    /// ports are used to distinguish the responses to the probes of this tracer.
+    fn check_ports(&self, resp: &ProbeResponseData) -> bool {
+        let (src_port, dest_port) = match resp.resp_seq {
+            ProbeResponseSeq::Icmp(_) => return true,
+            ProbeResponseSeq::Udp(ProbeResponseSeqUdp {
+                src_port,
+                dest_port,
+                ..
+            })
+            | ProbeResponseSeq::Tcp(ProbeResponseSeqTcp {
+                src_port,
+                dest_port,
+            }) => (src_port, dest_port),
+        };
+        let valid = match self.config.port_direction {
+            PortDirection::None => true,
+            PortDirection::FixedSrc(src) => src.0 == src_port,
+            PortDirection::FixedDest(dest) => dest.0 == dest_port,
+            PortDirection::FixedBoth(src, dest) => src.0 == src_port && dest.0 == dest_port,
+        };
+        if !valid {
+            trace!(src_port, dest_port, "ignoring response for another tracer");
+        }
+        valid
     }
 
     /// Extract the `TraceId`, `Sequence`, `SystemTime` and `IpAddr` from the `ProbeResponseData` in a protocol specific way.
@@ -264,13 +455,18 @@ impl<F: Fn(&TracerRound<'_>)> Tracer<F> {
 /// `TracerState` struct.
 mod state {
     use crate::tracing::constants::MAX_SEQUENCE_PER_ROUND;
-    use crate::tracing::types::{MaxRounds, Port, Round, Sequence, TimeToLive, TraceId};
+    use crate::tracing::probe::ProbeResponseDetails;
+    use crate::tracing::types::{
+        MaxInflight, MaxRounds, Port, Round, Sequence, TimeToLive, TraceId,
+    };
     use crate::tracing::{
-        IcmpPacketType, MultipathStrategy, PortDirection, Probe, ProbeStatus, TracerConfig,
-        TracerProtocol,
+        IcmpPacketType, MultipathStrategy, PortDirection, Probe, ProbeStatus, ProhibitedStrategy,
+        SequenceStrategy, TracerConfig, TracerProtocol,
     };
+    use rand::Rng;
     use std::net::IpAddr;
-    use std::time::SystemTime;
+    use std::time::{Duration, Instant, SystemTime};
+    use tracing::warn;
 
     /// The maximum number of `Probe` entries in the buffer.
     ///
@@ -296,13 +492,31 @@ mod state {
     /// send up to a max `ttl` of 255 (a `ttl` of 0 is never sent).
     const MAX_SEQUENCE: Sequence = Sequence(u16::MAX - BUFFER_SIZE);
 
+    /// The number of ttl beyond the ttl of the target which are probed when the adaptive ttl ceiling is in effect.
+    const ADAPTIVE_TTL_MARGIN: TimeToLive = TimeToLive(2);
+
+    /// The number of consecutive rounds which must fail to find the target before the adaptive ttl ceiling is lifted.
+    const ADAPTIVE_TTL_ROUNDS: usize = 3;
+
+    /// The smallest in-flight window when adaptive in-flight is enabled, unless the maximum in-flight is lower.
+    ///
+    /// The window bounds the number of consecutive hops which do not respond that can be traced beyond and so it is
+    /// not allowed to shrink too far.
+    const MIN_ADAPTIVE_INFLIGHT: u8 = 8;
+
+    /// The factor by which the average round-trip time of a round must exceed the smoothed round-trip time for the
+    /// adaptive in-flight window to be shrunk.
+    const ADAPTIVE_INFLIGHT_LATENCY_FACTOR: u32 = 2;
+
     /// Mutable state needed for the tracing algorithm.
     #[derive(Debug)]
     pub struct TracerState {
         /// Tracer configuration.
         config: TracerConfig,
         /// The state of all `Probe` requests and responses.
-        buffer: [Probe; BUFFER_SIZE as usize],
+        ///
+        /// This always holds `BUFFER_SIZE` probes and is allocated on the heap as it is too large for the stack.
+        buffer: Vec<Probe>,
         /// An increasing sequence number for every `EchoRequest`.
         sequence: Sequence,
         /// The starting sequence number of the current round.
@@ -311,8 +525,15 @@ mod state {
         ttl: TimeToLive,
         /// The current round.
         round: Round,
-        /// The timestamp of when the current round started.
-        round_start: SystemTime,
+        /// The scheduled start of the current round.
+        ///
+        /// This is a monotonic deadline which may be slightly earlier than the time the round actually started, see
+        /// `next_round_start`.
+        round_start: Instant,
+        /// The minimum duration of the current round, after applying a random `round_jitter` offset.
+        min_round_duration: Duration,
+        /// The maximum duration of the current round, after applying the same offset as `min_round_duration`.
+        max_round_duration: Duration,
         /// Did we receive an `EchoReply` from the target host in this round?
         target_found: bool,
         /// The maximum time-to-live echo response packet we have received.
@@ -322,24 +543,54 @@ mod state {
         /// Note that this is _not_ reset each round and that it can also _change_ over time, including going _down_ as
         /// responses can be are received out-of-order.
         target_ttl: Option<TimeToLive>,
+        /// The maximum time-to-live to probe, if adaptive ttl is enabled and the target has been discovered.
+        ///
+        /// Unlike `target_ttl` this is _not_ reset when a response is received from beyond the target.  It is only
+        /// lifted, allowing the full `max_ttl` to be probed again, once the target has not been found for
+        /// `ADAPTIVE_TTL_ROUNDS` consecutive rounds, such that a path which lengthens will be discovered.
+        ttl_ceiling: Option<TimeToLive>,
+        /// The number of consecutive rounds which have completed without finding the target.
+        rounds_without_target: usize,
+        /// The maximum number of unknown-in-flight probes.
+        ///
+        /// This is always the configured `max_inflight` unless adaptive in-flight is enabled, in which case it is
+        /// grown by one after each round without rising loss or latency and halved otherwise.
+        inflight_window: MaxInflight,
+        /// The number of probes lost in the previous round.
+        prev_lost: Option<usize>,
+        /// The smoothed average round-trip time of all rounds.
+        smoothed_rtt: Option<Duration>,
         /// The timestamp of the echo response packet.
         received_time: Option<SystemTime>,
+        /// The `Probe` of the previous round which were awaiting a response when that round completed.
+        prev_probes: Vec<Probe>,
+        /// The `Probe` of the previous round for which a response has been received in the current round.
+        late_probes: Vec<Probe>,
     }
 
     impl TracerState {
         pub fn new(config: TracerConfig) -> Self {
             Self {
                 config,
-                buffer: [Probe::default(); BUFFER_SIZE as usize],
+                buffer: vec![Probe::default(); BUFFER_SIZE as usize],
                 sequence: config.initial_sequence,
                 round_sequence: config.initial_sequence,
                 ttl: config.first_ttl,
                 round: Round(0),
-                round_start: SystemTime::now(),
+                round_start: Instant::now(),
+                min_round_duration: config.min_round_duration,
+                max_round_duration: config.max_round_duration,
                 target_found: false,
                 max_received_ttl: None,
                 target_ttl: None,
+                ttl_ceiling: None,
+                rounds_without_target: 0,
+                inflight_window: config.max_inflight,
+                prev_lost: None,
+                smoothed_rtt: None,
                 received_time: None,
+                prev_probes: Vec::new(),
+                late_probes: Vec::new(),
             }
         }
 
@@ -358,10 +609,20 @@ mod state {
             self.ttl
         }
 
-        pub const fn round_start(&self) -> SystemTime {
+        pub const fn round_start(&self) -> Instant {
             self.round_start
         }
 
+        /// The minimum duration of the current round.
+        pub const fn min_round_duration(&self) -> Duration {
+            self.min_round_duration
+        }
+
+        /// The maximum duration of the current round.
+        pub const fn max_round_duration(&self) -> Duration {
+            self.max_round_duration
+        }
+
         pub const fn target_found(&self) -> bool {
             self.target_found
         }
@@ -374,10 +635,22 @@ mod state {
             self.target_ttl
         }
 
+        pub const fn ttl_ceiling(&self) -> Option<TimeToLive> {
+            self.ttl_ceiling
+        }
+
+        pub const fn inflight_window(&self) -> MaxInflight {
+            self.inflight_window
+        }
+
         pub const fn received_time(&self) -> Option<SystemTime> {
             self.received_time
         }
 
+        pub fn late_probes(&self) -> &[Probe] {
+            &self.late_probes
+        }
+
         /// Is `sequence` in the current round?
         pub fn in_round(&self, sequence: Sequence) -> bool {
             sequence >= self.round_sequence && sequence.0 - self.round_sequence.0 < BUFFER_SIZE
@@ -420,6 +693,59 @@ mod state {
             probe
         }
 
+        /// The awaited `Probe` to be retried, if any.
+        ///
+        /// A `Probe` is retried if it is the latest `Probe` for its ttl in the round, it has not been responded to
+        /// within the probe timeout and it has been retried fewer than the configured number of times.  Only probes
+        /// for a ttl below that of the furthest response received, or no greater than the ttl of the target if known,
+        /// are retried such that only hops which may be rate limiting responses are retried.
+        pub fn next_retry(&self, now: SystemTime) -> Option<Probe> {
+            let probe_timeout = self.config.probe_timeout?;
+            let max_retry_ttl = match self.target_ttl {
+                Some(target_ttl) => target_ttl,
+                None => self.max_received_ttl? - TimeToLive(1),
+            };
+            let mut seen = [false; 256];
+            for probe in self.probes().iter().rev() {
+                if probe.status == ProbeStatus::NotSent || seen[usize::from(probe.ttl.0)] {
+                    continue;
+                }
+                seen[usize::from(probe.ttl.0)] = true;
+                let timed_out = probe.sent.is_some_and(|sent| {
+                    now.duration_since(sent).unwrap_or_default() > probe_timeout
+                });
+                if probe.status == ProbeStatus::Awaited
+                    && probe.retry < self.config.retries
+                    && probe.ttl <= max_retry_ttl
+                    && timed_out
+                {
+                    return Some(*probe);
+                }
+            }
+            None
+        }
+
+        /// Create and return a retry of `probe` at the current `sequence`.
+        ///
+        /// Unlike `next_probe` the `ttl` is not incremented.
+        pub fn retry_probe(&mut self, probe: Probe) -> Probe {
+            let (src_port, dest_port, identifier) = self.probe_data();
+            let probe = Probe::new(
+                self.sequence,
+                identifier,
+                src_port,
+                dest_port,
+                probe.ttl,
+                self.round,
+                SystemTime::now(),
+            )
+            .with_retry(probe.retry + 1);
+            self.buffer[usize::from(self.sequence - self.round_sequence)] = probe;
+            debug_assert!(self.sequence < Sequence(u16::MAX));
+            self.sequence += Sequence(1);
+            probe
+        }
+
         /// Re-issue the `Probe` with the next sequence number.
         ///
         /// This will mark the `Probe` at the previous `sequence` as skipped and re-create it with the previous `ttl`
@@ -515,6 +841,7 @@ mod state {
             sequence: Sequence,
             host: IpAddr,
             received: SystemTime,
+            details: ProbeResponseDetails,
             is_target: bool,
         ) {
             self.complete_probe(
@@ -522,18 +849,37 @@ mod state {
                 IcmpPacketType::TimeExceeded,
                 host,
                 received,
+                details,
                 is_target,
             );
         }
 
         /// Mark the `Probe` at `sequence` completed as `Unreachable` and update the round state.
+        ///
+        /// An unreachable response ends the path, and so is treated as being from the target, unless it is
+        /// administratively prohibited and the `ProhibitedStrategy` is to continue probing beyond the hop which sent
+        /// it.
         pub fn complete_probe_unreachable(
             &mut self,
             sequence: Sequence,
             host: IpAddr,
             received: SystemTime,
+            details: ProbeResponseDetails,
         ) {
-            self.complete_probe(sequence, IcmpPacketType::Unreachable, host, received, true);
+            let is_prohibited = details
+                .icmp_code
+                .is_some_and(|code| ProhibitedStrategy::is_prohibited(code, host.is_ipv6()));
+            let is_target = host == self.config.target_addr
+                || !is_prohibited
+                || self.config.prohibited_strategy == ProhibitedStrategy::Stop;
+            self.complete_probe(
+                sequence,
+                IcmpPacketType::Unreachable,
+                host,
+                received,
+                details,
+                is_target,
+            );
         }
 
         /// Mark the `Probe` at `sequence` completed as `EchoReply` and update the round state.
@@ -542,8 +888,16 @@ mod state {
             sequence: Sequence,
             host: IpAddr,
             received: SystemTime,
+            details: ProbeResponseDetails,
         ) {
-            self.complete_probe(sequence, IcmpPacketType::EchoReply, host, received, true);
+            self.complete_probe(
+                sequence,
+                IcmpPacketType::EchoReply,
+                host,
+                received,
+                details,
+                true,
+            );
         }
 
         /// Mark the `Probe` at `sequence` completed as `NotApplicable` and update the round state.
@@ -552,12 +906,14 @@ mod state {
             sequence: Sequence,
             host: IpAddr,
             received: SystemTime,
+            details: ProbeResponseDetails,
         ) {
             self.complete_probe(
                 sequence,
                 IcmpPacketType::NotApplicable,
                 host,
                 received,
+                details,
                 true,
             );
         }
@@ -574,21 +930,42 @@ mod state {
         /// The ICMP replies may arrive out-of-order and so we must be careful here to avoid overwriting the state with
         /// stale values.  We may also receive multiple replies from the target host with differing time-to-live values
         /// and so must ensure we use the time-to-live with the lowest sequence number.
+        ///
+        /// If the `Probe` has already been completed then the response is a duplicate, which is counted but otherwise
+        /// ignored such that it cannot overwrite the state of the original response.
         fn complete_probe(
             &mut self,
             sequence: Sequence,
             icmp_packet_type: IcmpPacketType,
             host: IpAddr,
             received: SystemTime,
+            details: ProbeResponseDetails,
             is_target: bool,
         ) {
             // Retrieve and update the `Probe` at `sequence`.
-            let probe = self
-                .probe_at(sequence)
+            let probe = self.probe_at(sequence);
+            if probe.status == ProbeStatus::Complete {
+                self.buffer[usize::from(sequence - self.round_sequence)] = probe.with_duplicate();
+                return;
+            }
+
+            // A response which is received after the probe timeout is ignored such that the `Probe` is considered
+            // lost, regardless of when the round ends.
+            if self.is_timed_out(probe, received) {
+                return;
+            }
+            if probe.sent.is_some_and(|sent| received < sent) {
+                warn!(
+                    sequence = sequence.0,
+                    "response received before the probe was sent, the system clock may have changed"
+                );
+            }
+            let probe = probe
                 .with_status(ProbeStatus::Complete)
                 .with_icmp_packet_type(icmp_packet_type)
                 .with_host(host)
-                .with_received(received);
+                .with_received(received)
+                .with_response_details(details);
             self.buffer[usize::from(sequence - self.round_sequence)] = probe;
 
             // If this `Probe` found the target then we set the `target_tll` if not already set, being careful to
@@ -620,34 +997,220 @@ mod state {
             self.target_found |= is_target;
         }
 
+        /// Update the state of a `Probe` of the previous round for which a response was received late.
+        ///
+        /// Responses for any other `Probe`, or which are received after the probe timeout, are ignored.
+        pub fn complete_late_probe(
+            &mut self,
+            sequence: Sequence,
+            icmp_packet_type: IcmpPacketType,
+            host: IpAddr,
+            received: SystemTime,
+            details: ProbeResponseDetails,
+        ) {
+            let Some(index) = self
+                .prev_probes
+                .iter()
+                .position(|probe| probe.sequence == sequence)
+            else {
+                return;
+            };
+            let probe = self.prev_probes.swap_remove(index);
+            if self.is_timed_out(probe, received) {
+                return;
+            }
+            let probe = probe
+                .with_status(ProbeStatus::Complete)
+                .with_icmp_packet_type(icmp_packet_type)
+                .with_host(host)
+                .with_received(received)
+                .with_response_details(details);
+            self.late_probes.push(probe);
+        }
+
+        /// Was `received` beyond the probe timeout, if any, of `probe`?
+        fn is_timed_out(&self, probe: Probe, received: SystemTime) -> bool {
+            match (self.config.probe_timeout, probe.sent) {
+                (Some(probe_timeout), Some(sent)) => {
+                    received.duration_since(sent).unwrap_or_default() > probe_timeout
+                }
+                _ => false,
+            }
+        }
+
         /// Advance to the next round.
         ///
         /// If, during the rond which just completed, we went above the max sequence number then we reset it here.
         /// We do this here to avoid having to deal with the sequence number wrapping during a round, which is more
         /// problematic.
         pub fn advance_round(&mut self, first_ttl: TimeToLive) {
+            self.prev_probes = self
+                .probes()
+                .iter()
+                .filter(|probe| probe.status == ProbeStatus::Awaited)
+                .copied()
+                .collect();
+            self.late_probes.clear();
+            if self.config.adaptive_ttl {
+                self.update_ttl_ceiling();
+            }
+            if self.config.adaptive_inflight {
+                self.update_inflight_window();
+            }
             if self.sequence >= MAX_SEQUENCE {
                 self.sequence = self.config.initial_sequence;
             }
+            if self.config.sequence_strategy == SequenceStrategy::Random {
+                self.sequence = self.random_round_sequence();
+            }
             self.target_found = false;
             self.round_sequence = self.sequence;
             self.received_time = None;
-            self.round_start = SystemTime::now();
+            self.round_start = self.next_round_start(Instant::now());
+            self.update_round_durations();
             self.max_received_ttl = None;
             self.round += Round(1);
             self.ttl = first_ttl;
         }
+
+        /// Update the adaptive ttl ceiling from the round which just completed.
+        ///
+        /// If the target was found then the ceiling is set just beyond the ttl of the target, otherwise the ceiling
+        /// is lifted once the target has not been found for `ADAPTIVE_TTL_ROUNDS` consecutive rounds.
+        fn update_ttl_ceiling(&mut self) {
+            if self.target_found {
+                if let Some(target_ttl) = self.target_ttl {
+                    self.ttl_ceiling = Some(target_ttl + ADAPTIVE_TTL_MARGIN);
+                }
+                self.rounds_without_target = 0;
+            } else if self.ttl_ceiling.is_some() {
+                self.rounds_without_target += 1;
+                if self.rounds_without_target >= ADAPTIVE_TTL_ROUNDS {
+                    self.ttl_ceiling = None;
+                    self.rounds_without_target = 0;
+                }
+            }
+        }
+
+        /// Update the adaptive in-flight window from the round which just completed.
+        ///
+        /// The window is halved, down to `MIN_ADAPTIVE_INFLIGHT`, if more probes were lost than in the previous round
+        /// or if the average round-trip time of the round exceeds `ADAPTIVE_INFLIGHT_LATENCY_FACTOR` times the
+        /// smoothed round-trip time, otherwise it is grown by one, up to `max_inflight`.
+        ///
+        /// A probe is considered lost if no response was received for it and a response was received for a probe
+        /// with a higher ttl.
+        fn update_inflight_window(&mut self) {
+            let max_received_ttl = self.max_received_ttl.unwrap_or_default();
+            let lost = self
+                .probes()
+                .iter()
+                .filter(|probe| {
+                    probe.status == ProbeStatus::Awaited && probe.ttl < max_received_ttl
+                })
+                .count();
+            let (total_rtt, count) = self
+                .probes()
+                .iter()
+                .filter_map(|probe| probe.received?.duration_since(probe.sent?).ok())
+                .fold((Duration::ZERO, 0_u32), |(total, count), rtt| {
+                    (total + rtt, count + 1)
+                });
+            let avg_rtt = (count > 0).then(|| total_rtt / count);
+            let loss_rising = self.prev_lost.is_some_and(|prev_lost| lost > prev_lost);
+            let latency_rising = match (avg_rtt, self.smoothed_rtt) {
+                (Some(avg_rtt), Some(smoothed_rtt)) => {
+                    avg_rtt > smoothed_rtt * ADAPTIVE_INFLIGHT_LATENCY_FACTOR
+                }
+                _ => false,
+            };
+            let max_inflight = self.config.max_inflight.0;
+            self.inflight_window = if loss_rising || latency_rising {
+                MaxInflight(
+                    (self.inflight_window.0 / 2).max(MIN_ADAPTIVE_INFLIGHT.min(max_inflight)),
+                )
+            } else {
+                MaxInflight(self.inflight_window.0.saturating_add(1).min(max_inflight))
+            };
+            self.prev_lost = Some(lost);
+            if let Some(avg_rtt) = avg_rtt {
+                self.smoothed_rtt = Some(
+                    self.smoothed_rtt
+                        .map_or(avg_rtt, |smoothed_rtt| (smoothed_rtt * 7 + avg_rtt) / 8),
+                );
+            }
+        }
+
+        /// A random sequence number from which to start the next round.
+        ///
+        /// The sequence is chosen such that none of the sequence numbers which may be used by the next round were used
+        /// by the round which just completed, so that a late response to a `Probe` of that round cannot be mistaken
+        /// for a response to a `Probe` of the next round.
+        fn random_round_sequence(&self) -> Sequence {
+            let low = self.config.initial_sequence.0;
+            let high = MAX_SEQUENCE.0.max(low);
+            let excluded_low = self
+                .round_sequence
+                .0
+                .saturating_sub(BUFFER_SIZE)
+                .clamp(low, high);
+            let excluded_high = self.sequence.0.clamp(excluded_low, high);
+            let available = (high - low) - (excluded_high - excluded_low);
+            if available == 0 {
+                return self.config.initial_sequence;
+            }
+            let sequence = rand::thread_rng().gen_range(low..low + available);
+            if sequence >= excluded_low {
+                Sequence(sequence + (excluded_high - excluded_low))
+            } else {
+                Sequence(sequence)
+            }
+        }
+
+        /// Choose the minimum and maximum duration of the next round.
+        ///
+        /// Both are offset from the configured durations by the same random amount within `+/- round_jitter`.
+        fn update_round_durations(&mut self) {
+            let jitter = self.config.round_jitter;
+            if jitter.is_zero() {
+                return;
+            }
+            let offset = rand::thread_rng().gen_range(Duration::ZERO..=jitter * 2);
+            self.min_round_duration =
+                (self.config.min_round_duration + offset).saturating_sub(jitter);
+            self.max_round_duration =
+                (self.config.max_round_duration + offset).saturating_sub(jitter);
+        }
+
+        /// The scheduled start of the next round, given the current time.
+        ///
+        /// Rounds are scheduled at fixed intervals of the minimum round duration from the start of the previous
+        /// round, rather than relative to when the previous round completed, such that the cadence of rounds does not
+        /// drift over time.  Any intervals which were missed because the previous round overran are skipped.
+        ///
+        /// The interval after the current round is its own, possibly jittered, minimum duration, whereas any skipped
+        /// intervals are of the configured minimum round duration.
+        fn next_round_start(&self, now: Instant) -> Instant {
+            let interval = self.config.min_round_duration.as_nanos();
+            if interval == 0 {
+                return now;
+            }
+            let scheduled = self.round_start + self.min_round_duration;
+            let late = now.saturating_duration_since(scheduled).as_nanos();
+            scheduled + Duration::from_nanos((late - late % interval) as u64)
+        }
     }
 
     #[cfg(test)]
     mod tests {
         use super::*;
-        use crate::tracing::probe::IcmpPacketType;
+        use crate::tracing::probe::{IcmpPacketType, ProbeResponseDetails};
         use crate::tracing::types::{MaxInflight, PacketSize, PayloadPattern};
         use crate::tracing::ProbeStatus;
         use rand::Rng;
         use std::net::{IpAddr, Ipv4Addr};
         use std::time::Duration;
+        use test_case::test_case;
 
         #[allow(
             clippy::cognitive_complexity,
@@ -693,7 +1256,13 @@ mod state {
             // Update the state of the probe 1 after receiving a TimeExceeded
             let received_1 = SystemTime::now();
             let host = IpAddr::V4(Ipv4Addr::LOCALHOST);
-            state.complete_probe_time_exceeded(Sequence(33000), host, received_1, false);
+            state.complete_probe_time_exceeded(
+                Sequence(33000),
+                host,
+                received_1,
+                ProbeResponseDetails::default(),
+                false,
+            );
 
             // Validate the state of the probe 1 after the update
             let probe_1_fetch = state.probe_at(Sequence(33000));
@@ -765,7 +1334,13 @@ mod state {
             // Update the state of probe 2 after receiving a TimeExceeded
             let received_2 = SystemTime::now();
             let host = IpAddr::V4(Ipv4Addr::LOCALHOST);
-            state.complete_probe_time_exceeded(Sequence(33001), host, received_2, false);
+            state.complete_probe_time_exceeded(
+                Sequence(33001),
+                host,
+                received_2,
+                ProbeResponseDetails::default(),
+                false,
+            );
             let probe_2_recv = state.probe_at(Sequence(33001));
 
             // Validate the TracerState after the update to probe 2
@@ -790,7 +1365,12 @@ mod state {
             // Update the state of probe 3 after receiving a EchoReply
             let received_3 = SystemTime::now();
             let host = IpAddr::V4(Ipv4Addr::LOCALHOST);
-            state.complete_probe_echo_reply(Sequence(33002), host, received_3);
+            state.complete_probe_echo_reply(
+                Sequence(33002),
+                host,
+                received_3,
+                ProbeResponseDetails::default(),
+            );
             let probe_3_recv = state.probe_at(Sequence(33002));
 
             // Validate the TracerState after the update to probe 3
@@ -869,6 +1449,295 @@ mod state {
             assert_eq!(state.sequence, Sequence(33000));
         }
 
+        #[test]
+        fn test_adaptive_ttl_ceiling() {
+            let mut state = TracerState::new(cfg(Sequence(33000)));
+            let host = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+            // The target is found at ttl 3 and so the ceiling is set just beyond it
+            for _ in 0..3 {
+                state.next_probe();
+            }
+            state.complete_probe_echo_reply(
+                Sequence(33002),
+                host,
+                SystemTime::now(),
+                ProbeResponseDetails::default(),
+            );
+            assert_eq!(state.ttl_ceiling(), None);
+            state.advance_round(TimeToLive(1));
+            assert_eq!(state.ttl_ceiling(), Some(TimeToLive(5)));
+
+            // The path lengthens, the target ttl is reset but the ceiling is retained
+            for _ in 0..3 {
+                state.next_probe();
+            }
+            state.complete_probe_time_exceeded(
+                Sequence(33005),
+                host,
+                SystemTime::now(),
+                ProbeResponseDetails::default(),
+                false,
+            );
+            assert_eq!(state.target_ttl(), None);
+            state.advance_round(TimeToLive(1));
+            assert_eq!(state.ttl_ceiling(), Some(TimeToLive(5)));
+            state.advance_round(TimeToLive(1));
+            assert_eq!(state.ttl_ceiling(), Some(TimeToLive(5)));
+
+            // The target has not been found for 3 rounds and so the ceiling is lifted
+            state.advance_round(TimeToLive(1));
+            assert_eq!(state.ttl_ceiling(), None);
+        }
+
+        #[test]
+        fn test_adaptive_ttl_disabled() {
+            let config = TracerConfig {
+                adaptive_ttl: false,
+                ..cfg(Sequence(33000))
+            };
+            let mut state = TracerState::new(config);
+            state.next_probe();
+            state.complete_probe_echo_reply(
+                Sequence(33000),
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                SystemTime::now(),
+                ProbeResponseDetails::default(),
+            );
+            state.advance_round(TimeToLive(1));
+            assert_eq!(state.ttl_ceiling(), None);
+        }
+
+        #[test_case(ProhibitedStrategy::Stop, 13, true; "stop on prohibited")]
+        #[test_case(ProhibitedStrategy::Continue, 13, false; "continue on prohibited")]
+        #[test_case(ProhibitedStrategy::Continue, 1, true; "stop on host unreachable")]
+        fn test_prohibited_strategy(strategy: ProhibitedStrategy, code: u8, expected: bool) {
+            let config = TracerConfig {
+                target_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                prohibited_strategy: strategy,
+                ..cfg(Sequence(33000))
+            };
+            let mut state = TracerState::new(config);
+            state.next_probe();
+            state.complete_probe_unreachable(
+                Sequence(33000),
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                SystemTime::now(),
+                ProbeResponseDetails {
+                    icmp_code: Some(code),
+                    ..ProbeResponseDetails::default()
+                },
+            );
+            assert_eq!(expected, state.target_found());
+        }
+
+        #[test]
+        fn test_adaptive_inflight_window() {
+            let config = TracerConfig {
+                max_inflight: MaxInflight(24),
+                adaptive_inflight: true,
+                ..cfg(Sequence(33000))
+            };
+            let mut state = TracerState::new(config);
+            assert_eq!(state.inflight_window(), MaxInflight(24));
+
+            // Round 0: no responses from ttl 1 & 2, no prior loss and so the window remains at the maximum
+            round(&mut state, &[3], Duration::from_millis(10));
+            assert_eq!(state.inflight_window(), MaxInflight(24));
+
+            // Round 1: loss falls
+            round(&mut state, &[1, 2, 3], Duration::from_millis(10));
+            assert_eq!(state.inflight_window(), MaxInflight(24));
+
+            // Round 2: loss rises and so the window is halved
+            round(&mut state, &[3], Duration::from_millis(10));
+            assert_eq!(state.inflight_window(), MaxInflight(12));
+
+            // Round 3: loss does not rise and so the window is grown
+            round(&mut state, &[3], Duration::from_millis(10));
+            assert_eq!(state.inflight_window(), MaxInflight(13));
+
+            // Round 4: latency rises and so the window is halved, but not below the minimum
+            round(&mut state, &[3], Duration::from_millis(100));
+            assert_eq!(state.inflight_window(), MaxInflight(8));
+        }
+
+        /// Send probes for ttl 1 to 3 and complete those for `responding_ttls` after `rtt`, then advance the round.
+        fn round(state: &mut TracerState, responding_ttls: &[u8], rtt: Duration) {
+            let probes = [state.next_probe(), state.next_probe(), state.next_probe()];
+            for probe in probes
+                .iter()
+                .filter(|probe| responding_ttls.contains(&probe.ttl.0))
+            {
+                state.complete_probe_time_exceeded(
+                    probe.sequence,
+                    IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    probe.sent.unwrap() + rtt,
+                    ProbeResponseDetails::default(),
+                    false,
+                );
+            }
+            state.advance_round(TimeToLive(1));
+        }
+
+        #[test]
+        fn test_probe_timeout() {
+            let config = TracerConfig {
+                probe_timeout: Some(Duration::from_millis(500)),
+                ..cfg(Sequence(33000))
+            };
+            let mut state = TracerState::new(config);
+            let host = IpAddr::V4(Ipv4Addr::LOCALHOST);
+            let probe_1 = state.next_probe();
+            let probe_2 = state.next_probe();
+
+            // The response to probe 1 is received within the timeout
+            state.complete_probe_time_exceeded(
+                probe_1.sequence,
+                host,
+                probe_1.sent.unwrap() + Duration::from_millis(400),
+                ProbeResponseDetails::default(),
+                false,
+            );
+            assert_eq!(
+                state.probe_at(probe_1.sequence).status,
+                ProbeStatus::Complete
+            );
+
+            // The response to probe 2 is received beyond the timeout and so is ignored
+            state.complete_probe_echo_reply(
+                probe_2.sequence,
+                host,
+                probe_2.sent.unwrap() + Duration::from_millis(600),
+                ProbeResponseDetails::default(),
+            );
+            assert_eq!(
+                state.probe_at(probe_2.sequence).status,
+                ProbeStatus::Awaited
+            );
+            assert!(!state.target_found());
+            assert_eq!(state.max_received_ttl(), Some(TimeToLive(1)));
+        }
+
+        #[test]
+        fn test_retry() {
+            let config = TracerConfig {
+                probe_timeout: Some(Duration::from_millis(500)),
+                retries: 1,
+                ..cfg(Sequence(33000))
+            };
+            let mut state = TracerState::new(config);
+            let probe_1 = state.next_probe();
+            let probe_2 = state.next_probe();
+            let probe_3 = state.next_probe();
+            let now = probe_3.sent.unwrap() + Duration::from_millis(600);
+
+            // No response has been received and so there is nothing to retry
+            assert_eq!(state.next_retry(now), None);
+
+            // A response from ttl 2 means probe 1 may be rate limited and so is retried, but not probe 3
+            state.complete_probe_time_exceeded(
+                probe_2.sequence,
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                probe_2.sent.unwrap() + Duration::from_millis(10),
+                ProbeResponseDetails::default(),
+                false,
+            );
+            assert_eq!(state.next_retry(probe_1.sent.unwrap()), None);
+            assert_eq!(state.next_retry(now), Some(probe_1));
+            let retry = state.retry_probe(probe_1);
+            assert_eq!(retry.sequence, Sequence(33003));
+            assert_eq!(retry.ttl, TimeToLive(1));
+            assert_eq!(retry.retry, 1);
+            assert_eq!(state.ttl(), TimeToLive(4));
+
+            // The retry supersedes probe 1 and may not itself be retried
+            let later = retry.sent.unwrap() + Duration::from_millis(600);
+            assert_eq!(state.next_retry(later), None);
+        }
+
+        #[test]
+        fn test_adaptive_inflight_sequence_wrap() {
+            let config = TracerConfig {
+                adaptive_inflight: true,
+                max_inflight: MaxInflight(24),
+                ..cfg(Sequence(33000))
+            };
+            let max_inflight = config.max_inflight.0;
+            let mut state = TracerState::new(config);
+            let mut wrapped = false;
+            for round in 0..2000 {
+                let probes = (0..20).map(|_| state.next_probe()).collect::<Vec<_>>();
+                if round % 2 == 0 {
+                    let last = probes.last().unwrap();
+                    state.complete_probe_time_exceeded(
+                        last.sequence,
+                        IpAddr::V4(Ipv4Addr::LOCALHOST),
+                        SystemTime::now(),
+                        ProbeResponseDetails::default(),
+                        false,
+                    );
+                }
+                let prev_round_sequence = state.round_sequence;
+                state.advance_round(TimeToLive(1));
+                if state.round_sequence < prev_round_sequence {
+                    assert_eq!(Sequence(33000), state.round_sequence);
+                    wrapped = true;
+                }
+                let window = state.inflight_window().0;
+                assert!((MIN_ADAPTIVE_INFLIGHT..=max_inflight).contains(&window));
+            }
+            assert!(wrapped);
+        }
+
+        #[test]
+        fn test_late_probe() {
+            let mut state = TracerState::new(cfg(Sequence(33000)));
+            let host = IpAddr::V4(Ipv4Addr::LOCALHOST);
+            let probe_1 = state.next_probe();
+            let probe_2 = state.next_probe();
+            state.complete_probe_time_exceeded(
+                probe_1.sequence,
+                host,
+                SystemTime::now(),
+                ProbeResponseDetails::default(),
+                false,
+            );
+            state.advance_round(TimeToLive(1));
+            assert!(!state.in_round(probe_2.sequence));
+
+            // The response to probe 2 is received in the next round
+            let received = probe_2.sent.unwrap() + Duration::from_secs(2);
+            state.complete_late_probe(
+                probe_2.sequence,
+                IcmpPacketType::EchoReply,
+                host,
+                received,
+                ProbeResponseDetails::default(),
+            );
+            assert_eq!(state.late_probes().len(), 1);
+            let late = state.late_probes()[0];
+            assert_eq!(late.sequence, probe_2.sequence);
+            assert_eq!(late.status, ProbeStatus::Complete);
+            assert_eq!(late.received, Some(received));
+
+            // Duplicate responses and responses for probes which were already complete are ignored
+            for sequence in [probe_2.sequence, probe_1.sequence] {
+                state.complete_late_probe(
+                    sequence,
+                    IcmpPacketType::EchoReply,
+                    host,
+                    received,
+                    ProbeResponseDetails::default(),
+                );
+            }
+            assert_eq!(state.late_probes().len(), 1);
+
+            // Late probes are published with a single round only
+            state.advance_round(TimeToLive(1));
+            assert!(state.late_probes().is_empty());
+        }
+
         #[test]
         fn test_sequence_wrap3() {
             let total_rounds = 2000;
@@ -919,6 +1788,103 @@ mod state {
             assert!(!state.in_round(Sequence(64491)));
         }
 
+        #[test]
+        fn test_duplicate_response() {
+            let mut state = TracerState::new(cfg(Sequence(33000)));
+            _ = state.next_probe();
+            let host = IpAddr::V4(Ipv4Addr::LOCALHOST);
+            let received_1 = SystemTime::now();
+            let received_2 = received_1 + Duration::from_millis(5);
+            state.complete_probe_echo_reply(
+                Sequence(33000),
+                host,
+                received_1,
+                ProbeResponseDetails::default(),
+            );
+            state.complete_probe_echo_reply(
+                Sequence(33000),
+                host,
+                received_2,
+                ProbeResponseDetails::default(),
+            );
+            let probe = state.probe_at(Sequence(33000));
+            assert_eq!(probe.status, ProbeStatus::Complete);
+            assert_eq!(probe.received, Some(received_1));
+            assert_eq!(probe.duplicates, 1);
+            assert_eq!(state.received_time, Some(received_1));
+        }
+
+        #[test]
+        fn test_next_round_start() {
+            let config = TracerConfig {
+                min_round_duration: Duration::from_secs(1),
+                ..cfg(Sequence(33000))
+            };
+            let state = TracerState::new(config);
+            let start = state.round_start;
+            assert_eq!(
+                start + Duration::from_secs(1),
+                state.next_round_start(start + Duration::from_millis(1010))
+            );
+            assert_eq!(
+                start + Duration::from_secs(2),
+                state.next_round_start(start + Duration::from_millis(2500))
+            );
+        }
+
+        #[test]
+        fn test_random_sequence_strategy() {
+            let config = TracerConfig {
+                sequence_strategy: SequenceStrategy::Random,
+                ..cfg(Sequence(33000))
+            };
+            let mut state = TracerState::new(config);
+            for _ in 0..100 {
+                let prev_round_sequence = state.round_sequence;
+                for _ in 0..10 {
+                    state.next_probe();
+                }
+                let prev_sequence = state.sequence;
+                state.advance_round(TimeToLive(1));
+                assert!(state.round_sequence >= Sequence(33000));
+                assert!(state.round_sequence < MAX_SEQUENCE);
+                assert!(
+                    state.round_sequence.0 + BUFFER_SIZE <= prev_round_sequence.0
+                        || state.round_sequence >= prev_sequence
+                );
+            }
+        }
+
+        #[test]
+        fn test_round_jitter() {
+            let config = TracerConfig {
+                min_round_duration: Duration::from_secs(1),
+                max_round_duration: Duration::from_secs(2),
+                round_jitter: Duration::from_millis(200),
+                ..cfg(Sequence(33000))
+            };
+            let mut state = TracerState::new(config);
+            assert_eq!(Duration::from_secs(1), state.min_round_duration());
+            assert_eq!(Duration::from_secs(2), state.max_round_duration());
+            for _ in 0..100 {
+                state.advance_round(TimeToLive(1));
+                let min_round_duration = state.min_round_duration();
+                assert!(min_round_duration >= Duration::from_millis(800));
+                assert!(min_round_duration <= Duration::from_millis(1200));
+                assert_eq!(
+                    min_round_duration + Duration::from_secs(1),
+                    state.max_round_duration()
+                );
+            }
+        }
+
+        #[test]
+        fn test_next_round_start_no_interval() {
+            let state = TracerState::new(cfg(Sequence(33000)));
+            let now = state.round_start + Duration::from_millis(10);
+            assert_eq!(now, state.next_round_start(now));
+        }
+
         fn cfg(initial_sequence: Sequence) -> TracerConfig {
             TracerConfig {
                 target_addr: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
@@ -930,13 +1896,22 @@ mod state {
                 grace_duration: Duration::default(),
                 max_inflight: MaxInflight::default(),
                 initial_sequence,
+                sequence_strategy: SequenceStrategy::Sequential,
                 multipath_strategy: MultipathStrategy::Classic,
                 port_direction: PortDirection::None,
                 read_timeout: Duration::default(),
                 min_round_duration: Duration::default(),
                 max_round_duration: Duration::default(),
+                round_jitter: Duration::default(),
+                probe_timeout: None,
+                retries: 0,
+                burst: false,
                 packet_size: PacketSize::default(),
                 payload_pattern: PayloadPattern::default(),
+                destination_only: false,
+                adaptive_ttl: true,
+                adaptive_inflight: false,
+                prohibited_strategy: ProhibitedStrategy::Stop,
             }
         }
     }
@@ -36,6 +36,14 @@ pub struct PayloadPattern(pub u8);
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Ord, PartialOrd)]
 pub struct TypeOfService(pub u8);
 
+/// `MaxSegmentSize` (TCP `MSS` option) newtype.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Ord, PartialOrd)]
+pub struct MaxSegmentSize(pub u16);
+
+/// `WindowSize` (TCP receive buffer size hint) newtype.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Ord, PartialOrd)]
+pub struct WindowSize(pub u32);
+
 /// Port newtype.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Ord, PartialOrd)]
 pub struct Port(pub u16);
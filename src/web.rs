@@ -0,0 +1,147 @@
+use crate::report::make_report;
+use crate::server;
+use crate::{DnsResolver, TraceInfo};
+use anyhow::Context;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender, SyncSender};
+use std::time::Duration;
+use tracing::info;
+
+/// The dashboard page, which polls the trace data from the `/api/traces` endpoint.
+const INDEX_HTML: &str = include_str!("web/index.html");
+
+/// The maximum time to wait to read a request from a client.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The maximum number of request headers to read.
+const MAX_HEADERS: usize = 100;
+
+/// The number of threads serving client connections.
+const WORKERS: usize = 4;
+
+/// The maximum number of client connections waiting for a free worker, beyond which connections are closed.
+const MAX_QUEUED: usize = 4;
+
+/// Run the web dashboard, serving the trace data of all `traces` on `addr`.
+///
+/// Connections are served on a small pool of threads, such that a slow client does not delay any other client.  The
+/// reports of the trace data are made on the calling thread, which owns the `resolver`, for each request in turn.
+pub fn run_web(
+    addr: SocketAddr,
+    traces: &[TraceInfo],
+    resolver: &DnsResolver,
+) -> anyhow::Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("failed to bind web dashboard: {addr}"))?;
+    info!(%addr, "serving web dashboard");
+    let (requests_tx, requests_rx) = mpsc::channel::<ReportsRequest>();
+    server::serve(listener, "web", WORKERS, MAX_QUEUED, move |stream| {
+        handle_connection(stream, &requests_tx)
+    })?;
+    for response_tx in requests_rx {
+        // The client may have disconnected while the request was queued.
+        drop(response_tx.send(make_reports(traces, resolver)));
+    }
+    Ok(())
+}
+
+/// A request for the reports of all traces, with the sender on which they are returned to the connection.
+type ReportsRequest = SyncSender<anyhow::Result<String>>;
+
+/// The JSON reports of all traces.
+///
+/// Each report is made from a snapshot of the trace data, such that the trace is not locked while any reverse DNS
+/// lookups of the report are performed.
+fn make_reports(traces: &[TraceInfo], resolver: &DnsResolver) -> anyhow::Result<String> {
+    let reports: Vec<_> = traces
+        .iter()
+        .map(|info| {
+            let trace = info.data.read().clone();
+            make_report(info, &trace, resolver, None)
+        })
+        .collect();
+    Ok(serde_json::to_string(&reports)?)
+}
+
+/// Handle a single HTTP request and close the connection.
+fn handle_connection(stream: &TcpStream, requests: &Sender<ReportsRequest>) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    for _ in 0..MAX_HEADERS {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+    let (status, content_type, body) = match Route::from(request_line.as_str()) {
+        Route::Index => ("200 OK", "text/html; charset=utf-8", INDEX_HTML.to_string()),
+        Route::Traces => {
+            let (response_tx, response_rx) = mpsc::sync_channel(1);
+            requests.send(response_tx)?;
+            ("200 OK", "application/json", response_rx.recv()??)
+        }
+        Route::NotFound => ("404 Not Found", "text/plain", String::from("not found")),
+        Route::MethodNotAllowed => (
+            "405 Method Not Allowed",
+            "text/plain",
+            String::from("method not allowed"),
+        ),
+    };
+    let mut writer = stream;
+    write!(
+        writer,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The resource requested by a client.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Route {
+    /// The dashboard page.
+    Index,
+    /// The trace data of all traces.
+    Traces,
+    /// An unknown resource.
+    NotFound,
+    /// A request which is not a `GET`.
+    MethodNotAllowed,
+}
+
+impl From<&str> for Route {
+    /// Route an HTTP request line, such as `GET /api/traces HTTP/1.1`.
+    fn from(request_line: &str) -> Self {
+        let mut parts = request_line.split_whitespace();
+        let (Some("GET"), Some(target)) = (parts.next(), parts.next()) else {
+            return Self::MethodNotAllowed;
+        };
+        let path = target.split_once('?').map_or(target, |(path, _)| path);
+        match path {
+            "/" | "/index.html" => Self::Index,
+            "/api/traces" => Self::Traces,
+            _ => Self::NotFound,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("GET / HTTP/1.1", Route::Index; "index")]
+    #[test_case("GET /index.html HTTP/1.1", Route::Index; "index html")]
+    #[test_case("GET /api/traces HTTP/1.1", Route::Traces; "traces")]
+    #[test_case("GET /api/traces?t=1 HTTP/1.1", Route::Traces; "traces with query")]
+    #[test_case("GET /favicon.ico HTTP/1.1", Route::NotFound; "unknown path")]
+    #[test_case("POST /api/traces HTTP/1.1", Route::MethodNotAllowed; "post")]
+    #[test_case("", Route::MethodNotAllowed; "empty request")]
+    fn test_route(request_line: &str, expected: Route) {
+        assert_eq!(expected, Route::from(request_line));
+    }
+}